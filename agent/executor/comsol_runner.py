@@ -7,6 +7,7 @@ from pathlib import Path
 from typing import TYPE_CHECKING, Any, Optional
 
 from agent.utils.config import get_project_root, get_settings
+from agent.utils.filenames import sanitize_filename
 from agent.utils.java_runtime import ensure_bundled_java
 from agent.utils.logger import get_logger
 from agent.schemas.geometry import GeometryPlan, GeometryShape
@@ -100,14 +101,46 @@ class COMSOLRunner:
     """COMSOL Java API 运行器"""
 
     _jvm_started = False
+    # 桌面端请求带 comsol_server 时记录的目标 (host, port)；为空则在本进程以独立模式加载 COMSOL
+    _server: Optional[tuple] = None
+    # 当前已连接的 server；独立模式下恒为 None
+    _connected: Optional[tuple] = None
+    _standalone = False
 
     def __init__(self):
         self._ensure_jvm_started()
         self.settings = get_settings()
 
+    @classmethod
+    def use_server(cls, host: str, port: int) -> None:
+        """改为连接 COMSOL server（mphserver）。JVM 未启动时仅记录目标，首次使用 COMSOL 时连接；
+        已连接其他 server 时在下次使用前切换。请求未带 comsol_server 时保持当前连接不变。"""
+        cls._server = (host, int(port))
+
+    @classmethod
+    def _connect_server(cls) -> None:
+        if cls._standalone:
+            host, port = cls._server
+            raise RuntimeError(
+                f"COMSOL 已在 bridge 进程内以独立模式加载，无法再连接 {host}:{port}，请重启 bridge 后重试"
+            )
+        ModelUtil = _jpype().JClass("com.comsol.model.util.ModelUtil")
+        if cls._connected is not None:
+            ModelUtil.disconnect()
+            cls._connected = None
+        host, port = cls._server
+        try:
+            ModelUtil.connect(host, port)
+        except Exception as e:
+            raise RuntimeError(f"无法连接 COMSOL server {host}:{port}: {e}") from e
+        cls._connected = cls._server
+        logger.info("已连接 COMSOL server %s:%s", host, port)
+
     @classmethod
     def _ensure_jvm_started(cls):
         if cls._jvm_started:
+            if cls._server is not None and cls._server != cls._connected:
+                cls._connect_server()
             return
 
         logger.info("启动 JVM...")
@@ -145,7 +178,9 @@ class COMSOLRunner:
             jpype.startJVM(jvm_path, *jvm_args)
             # 使用 JClass 加载，避免 "No module named 'com'"（com 为 Java 包，非 Python 模块）
             ModelUtil = jpype.JClass("com.comsol.model.util.ModelUtil")
-            ModelUtil.initStandalone(False)
+            if cls._server is None:
+                ModelUtil.initStandalone(False)
+                cls._standalone = True
             logger.info("JVM 启动成功，COMSOL API 已加载")
             cls._jvm_started = True
         except RuntimeError:
@@ -153,6 +188,8 @@ class COMSOLRunner:
         except Exception as e:
             logger.error(f"加载 COMSOL API 失败: {e}")
             raise RuntimeError(f"无法加载 COMSOL API: {e}") from e
+        if cls._server is not None:
+            cls._connect_server()
 
     def create_model(self, model_name: str):
         jpype = _jpype()
@@ -373,7 +410,7 @@ class COMSOLRunner:
         output_filename: Optional[str] = None,
         output_dir: Optional[Path] = None,
     ) -> Path:
-        safe_name = (plan.model_name or "model").replace(" ", "_").strip() or "model"
+        safe_name = sanitize_filename((plan.model_name or "model").replace(" ", "_"), max_bytes=200)
         dimension = plan.dimension
         logger.info(f"根据计划创建 {dimension}D 模型: {safe_name}")
 
@@ -388,8 +425,11 @@ class COMSOLRunner:
 
         self.build_geometry(model, "geom1")
 
-        if output_filename is None:
+        if not output_filename:
             output_filename = f"{safe_name}.mph"
+        else:
+            name_path = Path(output_filename)
+            output_filename = str(name_path.with_name(sanitize_filename(name_path.name)))
 
         if output_dir is not None:
             output_path = Path(output_dir).resolve() / output_filename
@@ -402,6 +442,14 @@ class COMSOLRunner:
     @classmethod
     def shutdown_jvm(cls):
         if cls._jvm_started:
-            _jpype().shutdownJVM()
+            jpype = _jpype()
+            if cls._connected is not None:
+                try:
+                    jpype.JClass("com.comsol.model.util.ModelUtil").disconnect()
+                except Exception as e:
+                    logger.warning("断开 COMSOL server 失败: %s", e)
+                cls._connected = None
+            jpype.shutdownJVM()
             cls._jvm_started = False
+            cls._standalone = False
             logger.info("JVM 已关闭")
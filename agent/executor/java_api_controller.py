@@ -2479,6 +2479,99 @@ class JavaAPIController:
             logger.exception("export_model_preview 失败")
             return {"status": "error", "message": str(e), "image_base64": None}
 
+    # ===== 导出脚本（供桌面端 export_job_script 留档）=====
+
+    # 脚本格式 → COMSOL 按扩展名选择的保存类型
+    _SCRIPT_SUFFIXES = {"java": ".java", "matlab": ".m"}
+
+    def export_model_script(self, model_path: str, fmt: str = "java") -> Dict[str, Any]:
+        """加载 .mph 模型，按其建模历史另存为 COMSOL Java（.java）或 MATLAB（.m）脚本，返回脚本文本。"""
+        suffix = self._SCRIPT_SUFFIXES.get(fmt)
+        if suffix is None:
+            return {"status": "error", "message": f"不支持的脚本格式: {fmt}", "code": None}
+        path = Path(model_path)
+        if not path.exists():
+            return {"status": "error", "message": "模型文件不存在", "code": None}
+        COMSOLRunner._ensure_jvm_started()
+        ModelUtil = _jpype().JClass("com.comsol.model.util.ModelUtil")
+        tag = f"mph_agent_export_{uuid4().hex[:8]}"
+        out_dir = Path(tempfile.mkdtemp(prefix="comsol_script_"))
+        try:
+            model = ModelUtil.load(tag, str(path.resolve()))
+            # 文件名即 Java 类名，须为合法标识符
+            name = re.sub(r"[^A-Za-z0-9_]", "_", path.stem)
+            if not name or name[0].isdigit():
+                name = f"model_{name}"
+            out_path = out_dir / f"{name}{suffix}"
+            model.save(str(out_path))
+            code = out_path.read_text(encoding="utf-8", errors="replace")
+            return {"status": "success", "message": "脚本已导出", "code": code}
+        except Exception as e:
+            logger.exception("export_model_script 失败")
+            return {"status": "error", "message": f"导出脚本失败: {e}", "code": None}
+        finally:
+            try:
+                ModelUtil.remove(tag)
+            except Exception:
+                pass
+            shutil.rmtree(out_dir, ignore_errors=True)
+
+    # ===== 材料库（供桌面端 materials_search 缓存）=====
+
+    # 材料库 .mph 在各版本安装目录中的常见位置（相对 COMSOL 安装根目录）
+    _MATERIAL_LIBRARY_DIRS = ("data/mph/materials", "data/materials", "materials")
+
+    def _material_library_files(self) -> List[Path]:
+        jar_path = Path(self.settings.comsol_jar_path or "")
+        if not self.settings.comsol_jar_path or not jar_path.exists():
+            return []
+        root = jar_path.parent if jar_path.is_dir() else jar_path.parent.parent
+        files: List[Path] = []
+        for rel in self._MATERIAL_LIBRARY_DIRS:
+            lib_dir = root / rel
+            if lib_dir.is_dir():
+                files.extend(sorted(lib_dir.glob("*.mph")))
+        return files
+
+    def list_library_materials(self) -> Dict[str, Any]:
+        """逐个加载安装目录下的材料库 .mph，列出其中材料的名称、所属库与基本属性（def 属性组）。"""
+        files = self._material_library_files()
+        if not files:
+            return {"status": "error", "message": "未找到 COMSOL 材料库，请检查 COMSOL_JAR_PATH", "materials": []}
+        COMSOLRunner._ensure_jvm_started()
+        ModelUtil = _jpype().JClass("com.comsol.model.util.ModelUtil")
+        materials: List[Dict[str, Any]] = []
+        for path in files:
+            tag = f"mph_agent_matlib_{uuid4().hex[:8]}"
+            try:
+                model = ModelUtil.load(tag, str(path))
+                mat_seq = self._materials_api(model)
+                for mat_tag in self._tags_or_names(mat_seq):
+                    mat = mat_seq.get(mat_tag) if hasattr(mat_seq, "get") else self._material_feature(model, mat_tag)
+                    properties: Dict[str, str] = {}
+                    try:
+                        group = mat.propertyGroup("def")
+                        for prop in group.properties():
+                            properties[str(prop)] = str(group.getString(prop))
+                    except Exception:
+                        pass
+                    materials.append(
+                        {
+                            "name": str(mat.label()) if hasattr(mat, "label") else mat_tag,
+                            "library": path.stem,
+                            "source": str(path),
+                            "properties": properties,
+                        }
+                    )
+            except Exception as e:
+                logger.warning("读取材料库 %s 失败: %s", path.name, e)
+            finally:
+                try:
+                    ModelUtil.remove(tag)
+                except Exception:
+                    pass
+        return {"status": "success", "message": f"共 {len(materials)} 种材料", "materials": materials}
+
     def _geom_for_export(self, model):
         """获取用于导出的几何对象。"""
         try:
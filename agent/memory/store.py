@@ -8,13 +8,13 @@ import sqlite3
 from pathlib import Path
 from typing import Any, Dict, List, Optional
 
-from agent.utils.config import get_install_dir
+from agent.utils.config import get_data_dir
 from agent.utils.logger import get_logger
 
 logger = get_logger(__name__)
 
 # 默认存储根目录
-_DEFAULT_BASE = get_install_dir() / ".context"
+_DEFAULT_BASE = get_data_dir() / ".context"
 
 # SQLite 表结构
 _SCHEMA = """
@@ -17,6 +17,8 @@ from agent.planner.material_agent import MaterialAgent
 from agent.planner.physics_agent import PhysicsAgent
 from agent.planner.study_agent import StudyAgent
 from agent.utils.config import get_settings
+from agent.utils.filenames import sanitize_filename
+from agent.utils.host import ACTIONS as HOST_ACTIONS, request_host
 from agent.utils.logger import get_logger
 from agent.schemas.task import ExecutionStep, GlobalDefinitionPlan, ReActTaskPlan
 
@@ -48,6 +50,9 @@ ACTION_STAGE_SUFFIX = {
     "call_official_api": "java_api",
 }
 
+# 宿主操作的显示名
+HOST_ACTION_LABELS = {"open_url": "打开网址", "read_file": "读取文件", "run_shell": "运行命令"}
+
 
 class ActionExecutor:
     """行动执行器 - 执行具体的建模操作"""
@@ -108,6 +113,8 @@ class ActionExecutor:
                     return parent, base_name
             return parent, stem
         base_name = (getattr(plan, "model_name", None) or "model").replace(".mph", "").strip()
+        # 预留阶段后缀与扩展名（如 _geometry.mph）的长度
+        base_name = sanitize_filename(base_name, max_bytes=200)
         out = getattr(plan, "output_dir", None)
         if isinstance(out, (str, Path)):
             parent = Path(out).resolve()
@@ -183,6 +190,9 @@ class ActionExecutor:
             "create_selection": self.execute_create_selection,
             "export_results": self.execute_export_results,
             "call_official_api": self.execute_call_official_api,
+            "open_url": self.execute_host_action,
+            "read_file": self.execute_host_action,
+            "run_shell": self.execute_host_action,
             "retry": self.execute_retry,
             "skip": self.execute_skip,
         }
@@ -982,6 +992,29 @@ class ActionExecutor:
             logger.error("call_official_api 失败: %s", e)
             return {"status": "error", "message": f"call_official_api 失败: {e}"}
 
+    # ===== Host actions =====
+
+    def execute_host_action(
+        self, plan: ReActTaskPlan, step: ExecutionStep, thought: Dict[str, Any]
+    ) -> Dict[str, Any]:
+        """请桌面端执行 open_url / read_file / run_shell；桌面端询问用户或按记住的决定授权。"""
+        params = thought.get("parameters", {}) or step.parameters or {}
+        required = HOST_ACTIONS[step.action]
+        if not params.get(required):
+            return {"status": "error", "message": f"{step.action} 需要提供 {required} 参数"}
+        args = {
+            k: params[k] for k in ("url", "path", "command", "cwd", "reason") if params.get(k)
+        }
+        label = HOST_ACTION_LABELS[step.action]
+        self._emit_step_start(label, f"等待桌面端授权: {params[required]}")
+        reply = request_host(step.action, **args)
+        if not reply.get("ok"):
+            message = reply.get("message") or f"{label}失败"
+            self._emit_step_end(label, message, denied=bool(reply.get("denied")))
+            return {"status": "error", "message": message, "denied": bool(reply.get("denied"))}
+        self._emit_step_end(label, f"{label}完成")
+        return {"status": "success", "message": f"{label}完成", "result": reply.get("result")}
+
     # ===== Retry / Skip =====
 
     def execute_retry(
@@ -609,6 +609,9 @@ class ReActAgent:
             "create_selection": "selection",
             "export_results": "postprocess",
             "call_official_api": "java_api",
+            "open_url": "host",
+            "read_file": "host",
+            "run_shell": "host",
         }
 
         step_type = step_type_map.get(action, "geometry")
@@ -577,6 +577,9 @@ class ReasoningEngine:
             "create_selection": "selection",
             "export_results": "postprocess",
             "call_official_api": "java_api",
+            "open_url": "host",
+            "read_file": "host",
+            "run_shell": "host",
         }
 
         # 每步只带该步需要的具体参数
@@ -620,6 +623,14 @@ class ReasoningEngine:
                 "args": params.get("args"),
                 "target_path": params.get("target_path"),
             },
+            # 宿主操作：由桌面端询问用户后执行
+            "open_url": {"url": params.get("url"), "reason": params.get("reason")},
+            "read_file": {"path": params.get("path"), "reason": params.get("reason")},
+            "run_shell": {
+                "command": params.get("command"),
+                "cwd": params.get("cwd"),
+                "reason": params.get("reason"),
+            },
         }
 
         total_steps = len(required_steps)
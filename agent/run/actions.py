@@ -284,6 +284,7 @@ def do_run(
     user_input: str,
     output: Optional[str] = None,
     workspace_dir: Optional[str] = None,
+    job_dir: Optional[str] = None,
     use_react: bool = True,
     no_context: bool = False,
     conversation_id: Optional[str] = None,
@@ -303,6 +304,8 @@ def do_run(
 ) -> Tuple[bool, str, bool]:
     """执行默认模式：自然语言 -> 创建模型。given_plan 非空时（Plan 模式进入）直接使用该计划，跳过编排。
 
+    ``job_dir`` 为桌面端分配的任务独立目录，非空时导出与模型文件写在其中，优先于 ``workspace_dir``。
+
     ``use_worktree=True`` 时会通过 ``WorktreeRuntime`` 在仓库旁创建一个 git worktree
     隔离本次建模执行（保留产物，不污染主分支）。worktree 内的 ``.port_sessions/``
     与 ``.context/`` 都独立。
@@ -321,6 +324,7 @@ def do_run(
             "skip_check": skip_check,
             "no_context": no_context,
             "workspace_dir": workspace_dir or "",
+            "job_dir": job_dir or "",
             "backend": backend or "",
             "model": model or "",
             "output": output or "",
@@ -406,8 +410,8 @@ def do_run(
 
     try:
         if use_react:
-            if workspace_dir:
-                output_dir = Path(workspace_dir).expanduser().resolve()
+            if job_dir or workspace_dir:
+                output_dir = Path(job_dir or workspace_dir).expanduser().resolve()
                 output_dir.mkdir(parents=True, exist_ok=True)
             else:
                 output_dir = context_manager.context_dir if conversation_id else None
@@ -1729,13 +1733,12 @@ def do_context_clear(conversation_id: Optional[str] = None) -> Tuple[bool, str]:
 
 
 def do_config_save(env_updates: Optional[dict] = None) -> Tuple[bool, str]:
-    """将配置写入项目根目录的 .env 文件并重载配置，供桌面端保存后同步。"""
+    """将配置写入 .env 文件（默认项目根目录，配置档案下为档案自己的 .env）并重载配置，供桌面端保存后同步。"""
     if not env_updates:
         return False, "无配置项"
-    from agent.utils.config import get_project_root, reload_settings
+    from agent.utils.config import get_env_file, reload_settings
 
-    root = get_project_root()
-    env_path = root / ".env"
+    env_path = get_env_file()
     env_keys = [
         "LLM_BACKEND",
         "DEEPSEEK_API_KEY",
@@ -0,0 +1,273 @@
+"""远程 bridge 服务：在装有 COMSOL 的机器上监听 TCP 或 WebSocket，为每个连接启动一个 tui-bridge 子进程并原样转发字节流。
+
+桌面端在设置中配置 `remote` 后经由此服务收发请求，协议（就绪信号、hello 协商、分帧）与本机子进程完全相同。
+TCP 连接的第一行为 `{"token": "..."}`，服务回复 `{"ok": true}` 后开始转发；
+WebSocket 连接以 `Authorization: Bearer <token>`（或查询参数 `token`）认证，二进制消息承载字节流。
+指定 `--certfile`/`--keyfile` 时以 TLS 监听（WebSocket 为 wss），桌面端在 `remote` 中设置 `tls: true`；
+桌面端只在 TLS 连接上向非本机地址发送令牌。连接断开时结束对应的 bridge 进程。
+"""
+import argparse
+import asyncio
+import hmac
+import ipaddress
+import json
+import os
+import ssl
+import sys
+from pathlib import Path
+from typing import List, Optional, Sequence
+
+TOKEN_ENV = "MPH_AGENT_REMOTE_TOKEN"
+DEFAULT_PORT = 8765
+DEFAULT_WS_PATH = "/bridge"
+AUTH_TIMEOUT = 10.0
+# 连接断开后等待 bridge 自行退出的时间，超时强制结束
+STOP_GRACE = 5.0
+CHUNK = 64 * 1024
+
+
+def _project_root() -> Path:
+    return Path(__file__).resolve().parents[2]
+
+
+def bridge_command() -> List[str]:
+    """每个连接启动的 bridge：与桌面端开发模式相同的 `cli.py tui-bridge`。"""
+    return [sys.executable, str(_project_root() / "cli.py"), "tui-bridge"]
+
+
+def check_token(expected: Optional[str], given: object) -> bool:
+    """未配置令牌时放行；否则按常量时间比较。"""
+    if not expected:
+        return True
+    if not isinstance(given, str):
+        return False
+    return hmac.compare_digest(expected.encode("utf-8"), given.encode("utf-8"))
+
+
+def is_loopback(host: str) -> bool:
+    if host == "localhost":
+        return True
+    try:
+        return ipaddress.ip_address(host).is_loopback
+    except ValueError:
+        return False
+
+
+def tls_context(certfile: Optional[str], keyfile: Optional[str]) -> Optional[ssl.SSLContext]:
+    """按证书与私钥创建服务端 TLS 上下文；都未指定时为 None（明文）。"""
+    if not certfile and not keyfile:
+        return None
+    if not certfile:
+        raise ValueError("指定 --keyfile 时必须同时指定 --certfile")
+    context = ssl.create_default_context(ssl.Purpose.CLIENT_AUTH)
+    context.load_cert_chain(certfile, keyfile)
+    return context
+
+
+async def _spawn(command: Sequence[str]) -> asyncio.subprocess.Process:
+    env = dict(os.environ)
+    env.setdefault("PYTHONIOENCODING", "utf-8")
+    env.setdefault("PYTHONUNBUFFERED", "1")
+    return await asyncio.create_subprocess_exec(
+        *command,
+        stdin=asyncio.subprocess.PIPE,
+        stdout=asyncio.subprocess.PIPE,
+        cwd=str(_project_root()),
+        env=env,
+    )
+
+
+async def _stop(proc: asyncio.subprocess.Process) -> None:
+    """关闭 stdin 让 bridge 自行退出（关闭 COMSOL 会话），超时后强制结束。"""
+    if proc.returncode is not None:
+        return
+    if proc.stdin is not None and not proc.stdin.is_closing():
+        proc.stdin.close()
+    try:
+        await asyncio.wait_for(proc.wait(), STOP_GRACE)
+    except asyncio.TimeoutError:
+        proc.kill()
+        await proc.wait()
+
+
+async def _pump_stdout(proc: asyncio.subprocess.Process, send) -> None:
+    assert proc.stdout is not None
+    while True:
+        chunk = await proc.stdout.read(CHUNK)
+        if not chunk:
+            return
+        await send(chunk)
+
+
+async def _until_first(*coros) -> None:
+    """任一方向结束即结束整个连接。"""
+    tasks = [asyncio.ensure_future(c) for c in coros]
+    try:
+        await asyncio.wait(tasks, return_when=asyncio.FIRST_COMPLETED)
+    finally:
+        for t in tasks:
+            t.cancel()
+        await asyncio.gather(*tasks, return_exceptions=True)
+
+
+async def _reject_tcp(writer: asyncio.StreamWriter, message: str) -> None:
+    writer.write((json.dumps({"ok": False, "message": message}, ensure_ascii=False) + "\n").encode("utf-8"))
+    try:
+        await writer.drain()
+    finally:
+        writer.close()
+
+
+async def serve_tcp(
+    host: str,
+    port: int,
+    token: Optional[str],
+    command: Optional[Sequence[str]] = None,
+    tls: Optional[ssl.SSLContext] = None,
+) -> asyncio.AbstractServer:
+    """启动 TCP 服务（`tls` 非空时为 TLS）并返回 server；调用方负责 `serve_forever` 或关闭。"""
+    command = list(command or bridge_command())
+
+    async def handle(reader: asyncio.StreamReader, writer: asyncio.StreamWriter) -> None:
+        peer = writer.get_extra_info("peername")
+        try:
+            line = await asyncio.wait_for(reader.readline(), AUTH_TIMEOUT)
+            hello = json.loads(line.decode("utf-8") or "{}")
+        except (asyncio.TimeoutError, ValueError):
+            await _reject_tcp(writer, "未收到认证信息")
+            return
+        if not isinstance(hello, dict) or not check_token(token, hello.get("token")):
+            sys.stderr.write(f"bridge-server: 拒绝 {peer}：令牌无效\n")
+            await _reject_tcp(writer, "令牌无效")
+            return
+
+        proc = await _spawn(command)
+        sys.stderr.write(f"bridge-server: {peer} 已连接，bridge pid={proc.pid}\n")
+        writer.write(b'{"ok":true}\n')
+        await writer.drain()
+
+        async def upstream() -> None:
+            assert proc.stdin is not None
+            while True:
+                chunk = await reader.read(CHUNK)
+                if not chunk:
+                    return
+                proc.stdin.write(chunk)
+                await proc.stdin.drain()
+
+        async def send(chunk: bytes) -> None:
+            writer.write(chunk)
+            await writer.drain()
+
+        try:
+            await _until_first(upstream(), _pump_stdout(proc, send))
+        except (ConnectionError, BrokenPipeError):
+            pass
+        finally:
+            await _stop(proc)
+            writer.close()
+            sys.stderr.write(f"bridge-server: {peer} 已断开，bridge 退出码 {proc.returncode}\n")
+
+    return await asyncio.start_server(handle, host, port, ssl=tls)
+
+
+def websocket_app(
+    token: Optional[str],
+    path: str = DEFAULT_WS_PATH,
+    command: Optional[Sequence[str]] = None,
+):
+    """WebSocket 服务的 ASGI 应用（FastAPI），由 uvicorn 运行。"""
+    from fastapi import FastAPI, WebSocket, WebSocketDisconnect
+
+    command = list(command or bridge_command())
+    app = FastAPI()
+
+    @app.websocket(path)
+    async def bridge(websocket: WebSocket) -> None:
+        auth = websocket.headers.get("authorization", "")
+        given = auth[len("Bearer "):].strip() if auth.startswith("Bearer ") else None
+        if given is None:
+            given = websocket.query_params.get("token")
+        if not check_token(token, given):
+            # 策略违规（1008）：令牌无效
+            await websocket.close(code=1008)
+            return
+        await websocket.accept()
+        proc = await _spawn(command)
+
+        async def upstream() -> None:
+            assert proc.stdin is not None
+            while True:
+                msg = await websocket.receive()
+                if msg["type"] == "websocket.disconnect":
+                    return
+                data = msg.get("bytes")
+                if data is None:
+                    data = (msg.get("text") or "").encode("utf-8")
+                proc.stdin.write(data)
+                await proc.stdin.drain()
+
+        try:
+            await _until_first(upstream(), _pump_stdout(proc, websocket.send_bytes))
+        except (WebSocketDisconnect, ConnectionError, BrokenPipeError):
+            pass
+        finally:
+            await _stop(proc)
+            try:
+                await websocket.close()
+            except RuntimeError:
+                pass
+
+    return app
+
+
+def main(argv: Optional[Sequence[str]] = None) -> int:
+    parser = argparse.ArgumentParser(prog="cli.py bridge-server", description="远程 bridge 服务")
+    parser.add_argument("--host", default="127.0.0.1")
+    parser.add_argument("--port", type=int, default=DEFAULT_PORT)
+    parser.add_argument("--transport", choices=["tcp", "websocket"], default="tcp")
+    parser.add_argument("--path", default=DEFAULT_WS_PATH, help="WebSocket 路径")
+    parser.add_argument("--token", default=os.environ.get(TOKEN_ENV), help=f"认证令牌，默认取环境变量 {TOKEN_ENV}")
+    parser.add_argument("--certfile", help="TLS 证书（PEM，可含证书链）")
+    parser.add_argument("--keyfile", help="TLS 私钥（PEM），证书文件已含私钥时可省略")
+    args = parser.parse_args(argv)
+
+    if not args.token and not is_loopback(args.host):
+        sys.stderr.write(f"bridge-server: 监听非本机地址时必须设置令牌（--token 或 {TOKEN_ENV}）\n")
+        return 2
+    try:
+        tls = tls_context(args.certfile, args.keyfile)
+    except (ValueError, OSError, ssl.SSLError) as e:
+        sys.stderr.write(f"bridge-server: 加载 TLS 证书失败: {e}\n")
+        return 2
+    if tls is None and not is_loopback(args.host):
+        sys.stderr.write("bridge-server: 警告：未启用 TLS，桌面端不会向非本机地址发送令牌，请指定 --certfile\n")
+
+    if args.transport == "websocket":
+        import uvicorn
+
+        uvicorn.run(
+            websocket_app(args.token, args.path),
+            host=args.host,
+            port=args.port,
+            ssl_certfile=args.certfile,
+            ssl_keyfile=args.keyfile,
+        )
+        return 0
+
+    async def run() -> None:
+        server = await serve_tcp(args.host, args.port, args.token, tls=tls)
+        scheme = "tls" if tls else "tcp"
+        sys.stderr.write(f"bridge-server: 监听 {scheme}://{args.host}:{args.port}\n")
+        async with server:
+            await server.serve_forever()
+
+    try:
+        asyncio.run(run())
+    except KeyboardInterrupt:
+        pass
+    return 0
+
+
+if __name__ == "__main__":
+    sys.exit(main())
@@ -1,7 +1,15 @@
 """TUI 桥接：从 stdin 读 JSON 行，调用 agent.run.actions，向 stdout 写 JSON 行。供 Bun OpenTUI 前端通过子进程调用。"""
+import _thread
+import contextlib
+import itertools
 import json
 import os
+import queue
+import signal
+import struct
 import sys
+import threading
+import time
 import traceback
 from pathlib import Path
 from typing import Any, Optional, TextIO
@@ -56,6 +64,7 @@ try:
     from agent.core.events import EventBus, Event, EventType
     from agent.executor.java_api_controller import JavaAPIController
     from agent.utils.context_manager import get_all_models_from_context, get_context_manager
+    from agent.utils.host import set_host_handler
 except Exception as e:
     _early_log("Import failed:\n" + "".join(traceback.format_exception(type(e), e, e.__traceback__)))
     raise
@@ -93,11 +102,258 @@ def _debug_log(msg: str) -> None:
         pass
 
 
+# 当前请求的关联 ID（桌面端注入的 `_id`），回显到每条响应与事件，供其按请求分发
+_current_request_id: Optional[str] = None
+# 查询线程各自记录所处理请求的 `_id`，不覆盖主线程的当前请求
+_local = threading.local()
+# 读取线程会在任务执行中回复取消消息，写 stdout 需整行互斥
+_stdout_lock = threading.Lock()
+# 取消只作用于正在执行的请求：检查当前 ID 与中断主线程须在同一锁内
+_cancel_lock = threading.Lock()
+# 主线程的当前请求已开始写最终回复（或已结束）：之后送达的取消中断直接丢弃
+_request_closed = True
+# 主线程正在写 stdout 的嵌套层数；期间送达的取消推迟到写完后再抛出，不写出半条消息
+_shield_depth = 0
+_interrupt_pending = False
+# 读取线程转交给主线程处理的请求行；None 表示 stdin 已关闭
+_requests: "queue.Queue[Optional[str]]" = queue.Queue()
+# 读取线程转交给查询线程的只读请求，主线程执行长任务时也能立即处理
+_queries: "queue.Queue[Optional[dict]]" = queue.Queue()
+# 当前请求的心跳线程（桌面端在流式请求中注入 `_heartbeat_secs` 时启动）
+_heartbeat: Optional["_Heartbeat"] = None
+# 等待桌面端答复的宿主操作：host_id → 答复队列，由读取线程按 host_reply 的 target 投递
+_host_waiters: "dict[str, queue.Queue[dict]]" = {}
+_host_lock = threading.Lock()
+_host_seq = itertools.count(1)
+# 各请求的事件序号（从 1 开始递增），桌面端据此丢弃重连/重放造成的重复事件并报告缺口
+_event_seqs: "dict[str, itertools.count]" = {}
+
+
+# 与桌面端协商的协议版本：hello 回复 protocol 与 min_protocol（要求桌面端至少支持的版本）
+PROTOCOL_VERSION = 2
+MIN_HOST_PROTOCOL = 2
+# hello 回复中声明的命令；桌面端会拒绝发送列表之外的命令，新增命令时需同步
+_COMMANDS = (
+    "ping",
+    "echo",
+    "selftest",
+    "run",
+    "plan",
+    "discuss",
+    "case",
+    "case_library_list",
+    "case_library_sync",
+    "case_library_sync_status",
+    "doc_kb_import",
+    "doc_kb_status",
+    "doc_kb_search",
+    "skills_list_local",
+    "skills_create_local",
+    "skills_import_local",
+    "skills_list_online",
+    "ops_catalog",
+    "exec",
+    "demo",
+    "doctor",
+    "context_show",
+    "context_get_summary",
+    "context_prompt_context",
+    "context_set_summary",
+    "ollama_ping",
+    "context_history",
+    "context_stats",
+    "context_clear",
+    "config_save",
+    "model_preview",
+    "export_script",
+    "materials_list",
+    "models_list",
+    "list_apis",
+    "conversation_delete",
+    "conversation_title_suggest",
+    "hello",
+    "cancel",
+    "host_reply",
+    "set_log_level",
+    "shutdown",
+)
+# 可在查询线程中与主线程的任务并行处理的只读命令：不启动 JVM、不修改上下文。
+# hello 回复中声明后，桌面端在流式任务执行期间也立即发送这些命令而不排队
+_CONCURRENT_COMMANDS = (
+    "ping",
+    "echo",
+    "case_library_list",
+    "case_library_sync_status",
+    "doc_kb_status",
+    "skills_list_local",
+    "context_show",
+    "context_get_summary",
+    "context_history",
+    "context_stats",
+    "models_list",
+)
+
+
+# 协议输出流：切换到长度前缀分帧后 sys.stdout 改指向 stderr，第三方库的 print 不会破坏分帧
+_stdout: TextIO = sys.stdout
+# 握手后与桌面端约定的分帧方式："lines" 或 "length_prefixed"（4 字节大端长度 + 消息体）
+_framing = "lines"
+_FRAMINGS = ("length_prefixed", "lines")
+# 输出编码："json" 或 "msgpack"（仅长度前缀分帧下可用）；请求始终为 JSON
+_codec = "json"
+
+try:
+    import msgpack as _msgpack
+except ImportError:
+    _msgpack = None
+
+
+def _encode(payload: dict) -> bytes:
+    if _codec == "msgpack":
+        try:
+            return _msgpack.packb(payload, use_bin_type=True)
+        except (TypeError, ValueError, OverflowError):
+            # 无法用 MessagePack 表示的值退回 JSON，桌面端按首字节 `{` 区分
+            pass
+    return json.dumps(payload, ensure_ascii=False).encode("utf-8")
+
+
+def _routing_first(payload: dict) -> dict:
+    """`_id` 与 `_event` 放在最前：消息超过桌面端的大小上限被整条跳过时，桌面端只凭开头找回其归属。"""
+    keys = [k for k in ("_id", "_event") if k in payload]
+    if not keys or list(payload)[: len(keys)] == keys:
+        return payload
+    ordered = {k: payload[k] for k in keys}
+    ordered.update(payload)
+    return ordered
+
+
+def _write_raw(payload: dict) -> None:
+    data = _encode(_routing_first(payload))
+    if _framing == "length_prefixed":
+        _stdout.buffer.write(struct.pack(">I", len(data)) + data)
+        _stdout.buffer.flush()
+    else:
+        _stdout.write(data.decode("utf-8") + "\n")
+        _stdout.flush()
+
+
+def _on_interrupt(signum: int, frame: Any) -> None:
+    """SIGINT 与取消消息（`_thread.interrupt_main`）的处理：请求已结束时丢弃，
+    主线程正在写 stdout 时推迟，其余情况抛出 KeyboardInterrupt 取消当前请求。"""
+    global _interrupt_pending
+    if _request_closed:
+        return
+    if _shield_depth:
+        _interrupt_pending = True
+        return
+    raise KeyboardInterrupt
+
+
+@contextlib.contextmanager
+def _shielded():
+    """主线程内不被取消打断的区段；期间送达的取消在区段结束后抛出（请求仍在执行时）。"""
+    global _shield_depth, _interrupt_pending
+    if threading.current_thread() is not threading.main_thread():
+        yield
+        return
+    _shield_depth += 1
+    try:
+        yield
+    finally:
+        _shield_depth -= 1
+    if not _shield_depth and _interrupt_pending:
+        _interrupt_pending = False
+        if not _request_closed:
+            raise KeyboardInterrupt
+
+
+def _write_message(payload: dict) -> None:
+    with _shielded(), _stdout_lock:
+        _write_raw(payload)
+
+
+class _Heartbeat:
+    """长任务执行期间定期发送 heartbeat 事件，桌面端据此区分「计算慢」与「卡死」。
+    最终回复写出前停止，保证不会在回复之后再出现该请求的心跳。"""
+
+    def __init__(self, request_id: Optional[str], interval: float) -> None:
+        self._request_id = request_id
+        self._interval = interval
+        self._stop = threading.Event()
+        self._started = time.monotonic()
+        self._thread = threading.Thread(target=self._run, name="bridge-heartbeat", daemon=True)
+        self._thread.start()
+
+    def _run(self) -> None:
+        while not self._stop.wait(self._interval):
+            payload: dict = {
+                "_event": True,
+                "type": "heartbeat",
+                "data": {"elapsed_secs": round(time.monotonic() - self._started, 1)},
+                "iteration": None,
+            }
+            if self._request_id is not None:
+                payload["_id"] = self._request_id
+            with _stdout_lock:
+                if self._stop.is_set():
+                    return
+                _write_raw(payload)
+
+    def stop(self) -> None:
+        # 在 stdout 锁内置位：之后心跳线程即使已醒来也不会再写
+        with _stdout_lock:
+            self._stop.set()
+
+
+def _start_heartbeat(req: Any) -> None:
+    global _heartbeat
+    interval = req.get("_heartbeat_secs") if isinstance(req, dict) else None
+    if isinstance(interval, (int, float)) and not isinstance(interval, bool) and interval > 0:
+        _heartbeat = _Heartbeat(_current_request_id, float(interval))
+
+
+def _stop_heartbeat() -> None:
+    global _heartbeat
+    if _heartbeat is not None:
+        _heartbeat.stop()
+        _heartbeat = None
+
+
+def _request_id() -> Optional[str]:
+    """当前线程所处理请求的 `_id`：查询线程取自己的，主线程取当前任务的。"""
+    return getattr(_local, "request_id", _current_request_id)
+
+
 def _reply(ok: bool, message: str, **extra: Any) -> None:
+    # 心跳与取消属于主线程的任务，查询线程回复时不能动它们
+    if threading.current_thread() is threading.main_thread():
+        _stop_heartbeat()
+        # 先结束当前请求再写回复：与之竞争的取消不再中断主线程，不会打断回复或重复回复
+        request_id = _close_request()
+    else:
+        request_id = _request_id()
     payload: dict = {"ok": ok, "message": message, **extra}
-    line = json.dumps(_json_safe(payload), ensure_ascii=False) + "\n"
-    sys.stdout.write(line)
-    sys.stdout.flush()
+    if request_id is not None:
+        payload["_id"] = request_id
+        _event_seqs.pop(request_id, None)
+    _write_message(_json_safe(payload))
+
+
+def _user_input(req: dict[str, Any]) -> str:
+    """用户输入；桌面端入库的附件（`attachments`，已是稳定路径）以清单形式附在末尾。"""
+    text = (req.get("input") or "").strip()
+    attachments = [a for a in (req.get("attachments") or []) if isinstance(a, dict) and a.get("path")]
+    if not attachments:
+        return text
+    lines = ["", "参考附件（本地文件，可按路径读取）："]
+    for a in attachments:
+        meta = a.get("meta") or {}
+        detail = ", ".join(f"{k}={v}" for k, v in meta.items() if v is not None and k != "columns")
+        if meta.get("columns"):
+            detail = ", ".join(filter(None, [detail, "columns=" + "/".join(map(str, meta["columns"]))]))
+        lines.append(f"- {a.get('name') or a['path']} [{a.get('kind', 'file')}] {a['path']}" + (f" ({detail})" if detail else ""))
+    return text + "\n".join(lines)
 
 
 def _json_safe(obj: Any) -> Any:
@@ -115,7 +371,8 @@ def _json_safe(obj: Any) -> Any:
         except Exception:
             pass
     if isinstance(obj, dict):
-        return {k: _json_safe(v) for k, v in obj.items()}
+        # MessagePack 允许非字符串键，桌面端只接受字符串键，与 JSON 保持一致
+        return {(k if isinstance(k, str) else str(k)): _json_safe(v) for k, v in obj.items()}
     if isinstance(obj, (list, tuple)):
         return [_json_safe(v) for v in obj]
     if hasattr(obj, "isoformat"):
@@ -124,16 +381,20 @@ def _json_safe(obj: Any) -> Any:
 
 
 def _emit_event(event: Event) -> None:
-    """将事件序列化为 JSON 行写入 stdout。"""
+    """将事件序列化为 JSON 行写入 stdout，带所属请求内递增的 `seq`。"""
     payload = {
         "_event": True,
         "type": event.type.value,
         "data": _json_safe(event.data),
         "iteration": event.iteration,
     }
-    line = json.dumps(payload, ensure_ascii=False) + "\n"
-    sys.stdout.write(line)
-    sys.stdout.flush()
+    request_id = _request_id()
+    # 在 stdout 锁内取号，保证写出顺序与序号一致
+    with _shielded(), _stdout_lock:
+        if request_id is not None:
+            payload["_id"] = request_id
+            payload["seq"] = next(_event_seqs.setdefault(request_id, itertools.count(1)))
+        _write_raw(payload)
 
 
 def _handle(req: dict[str, Any]) -> None:
@@ -143,6 +404,38 @@ def _handle(req: dict[str, Any]) -> None:
         return
 
     try:
+        # 桌面端 attach_endpoint 注入的 mphserver / 已保存连接：之后的 COMSOL 操作改为连接该 server
+        server = req.get("comsol_server")
+        if isinstance(server, dict) and server.get("host") and server.get("port"):
+            from agent.executor.comsol_runner import COMSOLRunner
+
+            COMSOLRunner.use_server(str(server["host"]), int(server["port"]))
+
+        # 供桌面端 bridge_benchmark 测量往返延迟与吞吐量
+        if cmd == "ping":
+            _reply(True, "pong")
+            return
+
+        if cmd == "echo":
+            _reply(True, "", data=req.get("data"))
+            return
+
+        if cmd == "selftest":
+            # 启动自检：新建并立即移除一个空模型，验证 JVM 与 COMSOL API 可用
+            from agent.executor.comsol_runner import COMSOLRunner, _jpype
+
+            COMSOLRunner._ensure_jvm_started()
+            ModelUtil = _jpype().JClass("com.comsol.model.util.ModelUtil")
+            tag = "mph_agent_selftest"
+            ModelUtil.create(tag)
+            ModelUtil.remove(tag)
+            _reply(
+                True,
+                "COMSOL 自检通过",
+                detail={"java_home": os.environ.get("JAVA_HOME")},
+            )
+            return
+
         if cmd == "run":
             event_bus = EventBus()
             event_bus.subscribe_all(_emit_event)
@@ -150,9 +443,10 @@ def _handle(req: dict[str, Any]) -> None:
             try:
                 try:
                     ok, msg, plan_needs_clarification = do_run(
-                        user_input=(req.get("input") or "").strip(),
+                        user_input=_user_input(req),
                         output=req.get("output") or None,
                         workspace_dir=req.get("workspace_dir") or None,
+                        job_dir=req.get("job_dir") or None,
                         use_react=req.get("use_react", True),
                         no_context=req.get("no_context", False),
                         conversation_id=req.get("conversation_id") or None,
@@ -188,7 +482,7 @@ def _handle(req: dict[str, Any]) -> None:
                 event_bus = EventBus()
                 event_bus.subscribe_all(_emit_event)
             ok, msg, plan_dict, plan_confirmed, clarifying_questions = do_plan_mode(
-                user_input=(req.get("input") or "").strip(),
+                user_input=_user_input(req),
                 conversation_id=req.get("conversation_id") or None,
                 backend=req.get("backend") or None,
                 api_key=req.get("api_key") or None,
@@ -215,7 +509,7 @@ def _handle(req: dict[str, Any]) -> None:
                 event_bus = EventBus()
                 event_bus.subscribe_all(_emit_event)
             ok, msg, card = do_discuss(
-                user_input=(req.get("input") or "").strip(),
+                user_input=_user_input(req),
                 conversation_id=req.get("conversation_id") or None,
                 verbose=req.get("verbose", False),
                 backend=req.get("backend") or None,
@@ -513,6 +807,29 @@ def _handle(req: dict[str, Any]) -> None:
                 _reply(False, str(e), image_base64=None)
             return
 
+        if cmd == "export_script":
+            # 桌面端 export_job_script：把任务产出的模型另存为 Java / MATLAB 脚本
+            model_path = (req.get("model_path") or "").strip()
+            if not model_path:
+                _reply(False, "任务没有可导出的模型文件", code=None)
+                return
+            result = JavaAPIController().export_model_script(
+                model_path, fmt=(req.get("format") or "java").strip().lower()
+            )
+            _reply(result.get("status") == "success", result.get("message", ""), code=result.get("code"))
+            return
+
+        if cmd == "materials_list":
+            # 桌面端材料补全缓存：列出 COMSOL 安装自带的材料库，版本号原样回传供缓存判定失效
+            result = JavaAPIController().list_library_materials()
+            _reply(
+                result.get("status") == "success",
+                result.get("message", ""),
+                materials=result.get("materials", []),
+                comsol_version=req.get("comsol_version"),
+            )
+            return
+
         if cmd == "models_list":
             limit = int(req.get("limit") or 50)
             try:
@@ -583,6 +900,285 @@ def _handle(req: dict[str, Any]) -> None:
         _reply(False, str(e))
 
 
+def _shutdown() -> None:
+    """桌面端退出前的清理：关闭已启动的 JVM（随之断开 COMSOL 会话）。"""
+    runner_mod = sys.modules.get("agent.executor.comsol_runner")
+    if runner_mod is None:
+        return
+    try:
+        runner_mod.COMSOLRunner.shutdown_jvm()
+    except Exception as e:
+        sys.stderr.write(f"tui-bridge: 关闭 JVM 失败: {e}\n")
+
+
+def _open_request(request_id: Optional[str]) -> None:
+    """主线程开始处理一条请求；此后针对它的取消会中断主线程。"""
+    global _current_request_id, _request_closed, _interrupt_pending
+    with _cancel_lock:
+        _current_request_id = request_id
+        _request_closed = False
+        _interrupt_pending = False
+
+
+def _close_request() -> Optional[str]:
+    """结束主线程的当前请求并返回其 `_id`；已送达但尚未抛出的取消随之作废。"""
+    global _current_request_id, _request_closed
+    with _cancel_lock:
+        request_id, _current_request_id = _current_request_id, None
+        _request_closed = True
+    return request_id
+
+
+def _handle_cancel(req: dict[str, Any]) -> None:
+    """取消控制消息 `{"cmd": "cancel", "target": <_id>}`：目标正在执行时向主线程抛出
+    KeyboardInterrupt（与 SIGINT 取消同一路径），并立即回复是否已开始取消。"""
+    target = req.get("target")
+    target = str(target) if target is not None else None
+    with _cancel_lock:
+        cancelling = target is not None and target == _current_request_id
+        if cancelling:
+            _thread.interrupt_main()
+    payload: dict = {
+        "ok": True,
+        "message": "正在取消" if cancelling else "目标请求未在执行",
+        "cancelling": cancelling,
+    }
+    if req.get("_id") is not None:
+        payload["_id"] = str(req["_id"])
+    _write_message(payload)
+
+
+# 桌面端的级别名 → loguru 级别名
+_LOG_LEVELS = {
+    "error": "ERROR",
+    "warn": "WARNING",
+    "info": "INFO",
+    "debug": "DEBUG",
+    "trace": "TRACE",
+}
+_BASE_BRIDGE_DEBUG = os.environ.get("MPH_AGENT_BRIDGE_DEBUG")
+
+
+def _handle_set_log_level(req: dict[str, Any]) -> None:
+    """日志级别控制消息 `{"cmd": "set_log_level", "level": "debug"}`：即时调整日志级别，
+    debug / trace 时同时打开请求/响应调试日志，其余级别恢复启动时的设置。"""
+    from agent.utils.logger import set_level_override
+
+    level = _LOG_LEVELS.get(str(req.get("level") or "").strip().lower())
+    if level is not None:
+        set_level_override(level)
+        if level in ("DEBUG", "TRACE"):
+            os.environ["MPH_AGENT_BRIDGE_DEBUG"] = "1"
+        elif _BASE_BRIDGE_DEBUG is None:
+            os.environ.pop("MPH_AGENT_BRIDGE_DEBUG", None)
+        else:
+            os.environ["MPH_AGENT_BRIDGE_DEBUG"] = _BASE_BRIDGE_DEBUG
+    payload: dict = {
+        "ok": level is not None,
+        "message": f"日志级别已设为 {level}" if level else f"未知日志级别: {req.get('level')}",
+        "level": level,
+    }
+    if req.get("_id") is not None:
+        payload["_id"] = str(req["_id"])
+    _write_message(payload)
+
+
+def host_request(action: str, timeout: float = 300.0, **args: Any) -> dict[str, Any]:
+    """请桌面端执行宿主操作（open_url / read_file / run_shell），由用户授权后执行。
+    只能在流式请求执行期间调用；返回 `{"ok": bool, "result": ..., "message": ..., "denied": bool}`，
+    超时或未授权时 ok 为 False。"""
+    host_id = f"host-{next(_host_seq)}"
+    waiter: "queue.Queue[dict]" = queue.Queue(maxsize=1)
+    with _host_lock:
+        _host_waiters[host_id] = waiter
+    try:
+        payload: dict = {
+            "_event": True,
+            "type": "host_request",
+            "data": _json_safe({"host_id": host_id, "action": action, **args}),
+        }
+        if _current_request_id is not None:
+            payload["_id"] = _current_request_id
+        _write_message(payload)
+        try:
+            return waiter.get(timeout=timeout)
+        except queue.Empty:
+            return {"ok": False, "message": f"等待桌面端答复超时（{int(timeout)} 秒）"}
+    finally:
+        with _host_lock:
+            _host_waiters.pop(host_id, None)
+
+
+def _handle_host_reply(req: dict[str, Any]) -> None:
+    """宿主操作的答复 `{"cmd": "host_reply", "target": <host_id>, ...}`；不回复。"""
+    target = req.get("target")
+    with _host_lock:
+        waiter = _host_waiters.get(str(target)) if target is not None else None
+    if waiter is None:
+        return
+    reply = {k: v for k, v in req.items() if k not in ("cmd", "target")}
+    try:
+        waiter.put_nowait(reply)
+    except queue.Full:
+        pass
+
+
+def _handle_hello(req: dict[str, Any]) -> None:
+    """握手 `{"cmd": "hello", "protocol": N, "framing": [...], "codecs": [...]}`：回报协议版本与
+    命令列表，从桌面端提供的分帧方式与编码中各选定一种（MessagePack 需已安装 msgpack）。回复仍按行写出，之后双方切换到选定的分帧。
+    在读取线程中处理，保证读取下一条消息前已完成切换。"""
+    global _framing, _codec
+    offered = req.get("framing") or ["lines"]
+    chosen = next((f for f in offered if f in _FRAMINGS), "lines")
+    codecs = req.get("codecs") or ["json"]
+    codec = "msgpack" if "msgpack" in codecs and chosen == "length_prefixed" and _msgpack else "json"
+    host = req.get("protocol")
+    payload = {
+        "ok": True,
+        "message": f"bridge 协议 {PROTOCOL_VERSION}（桌面端 {host}）",
+        "protocol": PROTOCOL_VERSION,
+        "min_protocol": MIN_HOST_PROTOCOL,
+        "framing": chosen,
+        "codec": codec,
+        "capabilities": {"cmds": list(_COMMANDS), "concurrent": list(_CONCURRENT_COMMANDS)},
+    }
+    with _stdout_lock:
+        _write_raw(payload)
+        _framing = chosen
+        _codec = codec
+        if chosen == "length_prefixed":
+            sys.stdout = sys.stderr
+
+
+class _StdinReader:
+    """直接按文件描述符读取 stdin：同一缓冲区既能按行也能按长度读取，
+    且阻塞时不持有 io 模块的锁，进程退出时守护线程不会卡住解释器。"""
+
+    def __init__(self, fd: int) -> None:
+        self._fd = fd
+        self._buf = bytearray()
+
+    def _fill(self) -> bool:
+        chunk = os.read(self._fd, 65536)
+        self._buf.extend(chunk)
+        return bool(chunk)
+
+    def readline(self) -> bytes:
+        while b"\n" not in self._buf and self._fill():
+            pass
+        end = self._buf.find(b"\n") + 1 or len(self._buf)
+        line = bytes(self._buf[:end])
+        del self._buf[:end]
+        return line
+
+    def read(self, n: int) -> bytes:
+        while len(self._buf) < n and self._fill():
+            pass
+        data = bytes(self._buf[:n])
+        del self._buf[:n]
+        return data
+
+
+def _read_message(stream: _StdinReader) -> Optional[bytes]:
+    """按当前分帧读取一条消息；stdin 关闭时返回 None。"""
+    if _framing == "length_prefixed":
+        header = stream.read(4)
+        if len(header) < 4:
+            return None
+        (length,) = struct.unpack(">I", header)
+        data = stream.read(length)
+        return data if len(data) == length else None
+    line = stream.readline()
+    return line or None
+
+
+def _read_stdin() -> None:
+    """读取线程：握手与取消消息当场处理，只读查询交给查询线程，其余请求按顺序交给主线程。"""
+    stream = _StdinReader(sys.stdin.fileno())
+    while True:
+        raw = _read_message(stream)
+        if raw is None:
+            break
+        stripped = raw.decode("utf-8", errors="replace").strip()
+        if not stripped:
+            continue
+        try:
+            req = json.loads(stripped)
+        except json.JSONDecodeError:
+            req = None
+        cmd = (req.get("cmd") or "").strip() if isinstance(req, dict) else ""
+        if cmd == "hello":
+            _handle_hello(req)
+            continue
+        if cmd == "cancel":
+            _handle_cancel(req)
+            continue
+        if cmd == "host_reply":
+            _handle_host_reply(req)
+            continue
+        if cmd == "set_log_level":
+            _handle_set_log_level(req)
+            continue
+        if cmd in _CONCURRENT_COMMANDS:
+            _queries.put(req)
+            continue
+        _requests.put(stripped)
+    _queries.put(None)
+    _requests.put(None)
+
+
+def _serve_queries() -> None:
+    """查询线程：逐条处理只读请求，回复带各自的 `_id`，不影响主线程的任务、心跳与取消。"""
+    while True:
+        req = _queries.get()
+        if req is None:
+            return
+        request_id = req.get("_id")
+        _local.request_id = str(request_id) if request_id is not None else None
+        if _bridge_debug():
+            _debug_log(f"[bridge] 并行处理查询: {req.get('cmd')}\n")
+        try:
+            _handle(req)
+        except Exception as e:
+            _reply(False, str(e))
+
+
+def _serve(line: str) -> bool:
+    """处理一条请求行；收到 shutdown 时返回 False。"""
+    if _bridge_debug():
+        _debug_log(f"[bridge] 收到请求: {line[:200]}{'...' if len(line) > 200 else ''}\n")
+    _close_request()
+    try:
+        req = json.loads(line)
+    except json.JSONDecodeError as e:
+        if _bridge_debug():
+            _debug_log("".join(traceback.format_exception(type(e), e, e.__traceback__)))
+        _reply(False, f"JSON 解析错误: {e}")
+        return True
+    request_id = req.get("_id") if isinstance(req, dict) else None
+    _open_request(str(request_id) if request_id is not None else None)
+    if isinstance(req, dict) and (req.get("cmd") or "").strip() == "shutdown":
+        # 先回复再清理：桌面端收到回复后只需等待进程退出
+        _reply(True, "bridge 正在退出")
+        _shutdown()
+        return False
+    _start_heartbeat(req)
+    try:
+        _handle(req)
+    except KeyboardInterrupt:
+        # SIGINT 或取消消息：只取消当前请求，进程继续服务
+        _reply(False, "任务已取消", cancelled=True)
+    except BaseException as e:
+        if _bridge_debug():
+            _debug_log("".join(traceback.format_exception(type(e), e, e.__traceback__)))
+        _reply(False, str(e))
+        raise
+    finally:
+        _stop_heartbeat()
+        _close_request()
+    return True
+
+
 def main() -> None:
     """从 stdin 按行读 JSON，处理并写一行 JSON 到 stdout。"""
     if sys.stdin.isatty():
@@ -599,26 +1195,18 @@ def main() -> None:
 
         sys.excepthook = _excepthook
 
-    for line in sys.stdin:
-        line = line.strip()
-        if not line:
-            continue
-        if _bridge_debug():
-            _debug_log(f"[bridge] 收到请求: {line[:200]}{'...' if len(line) > 200 else ''}\n")
+    signal.signal(signal.SIGINT, _on_interrupt)
+    set_host_handler(host_request)
+    threading.Thread(target=_serve_queries, name="bridge-query", daemon=True).start()
+    threading.Thread(target=_read_stdin, name="stdin-reader", daemon=True).start()
+    while True:
         try:
-            req = json.loads(line)
-        except json.JSONDecodeError as e:
-            if _bridge_debug():
-                _debug_log("".join(traceback.format_exception(type(e), e, e.__traceback__)))
-            _reply(False, f"JSON 解析错误: {e}")
+            line = _requests.get()
+            if line is None or not _serve(line):
+                return
+        except KeyboardInterrupt:
+            # 取消在请求已回复后才送达，没有可取消的任务
             continue
-        try:
-            _handle(req)
-        except BaseException as e:
-            if _bridge_debug():
-                _debug_log("".join(traceback.format_exception(type(e), e, e.__traceback__)))
-            _reply(False, str(e))
-            raise
 
 
 if __name__ == "__main__":
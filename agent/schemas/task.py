@@ -40,6 +40,7 @@ class ExecutionStep(BaseModel):
         "geometry_io",
         "postprocess",
         "java_api",
+        "host",
     ] = Field(..., description="Step type")
     action: str = Field(..., description="Action name")
     parameters: Dict[str, Any] = Field(default_factory=dict, description="Action parameters")
@@ -1,4 +1,5 @@
 """配置管理"""
+import os
 import sys
 from pathlib import Path
 from typing import Optional, Dict
@@ -8,8 +9,12 @@ from dotenv import load_dotenv
 
 from agent.utils import secrets as secrets_utils
 
+# 桌面端按配置档案启动 bridge 时传入的数据目录与 .env 路径；未设置时沿用安装目录与项目根 .env
+DATA_DIR_ENV = "MPH_AGENT_DATA_DIR"
+ENV_FILE_ENV = "MPH_AGENT_ENV_FILE"
+
 # 加载 .env 文件
-load_dotenv()
+load_dotenv(os.environ.get(ENV_FILE_ENV) or None)
 
 
 def get_install_dir() -> Path:
@@ -28,6 +33,18 @@ def get_install_dir() -> Path:
     return Path(__file__).parent.parent.parent
 
 
+def get_data_dir() -> Path:
+    """获取运行数据目录（.context 等），配置档案可经 MPH_AGENT_DATA_DIR 隔离"""
+    override = os.environ.get(DATA_DIR_ENV, "").strip()
+    return Path(override) if override else get_install_dir()
+
+
+def get_env_file() -> Path:
+    """获取读写的 .env 路径，配置档案可经 MPH_AGENT_ENV_FILE 隔离"""
+    override = os.environ.get(ENV_FILE_ENV, "").strip()
+    return Path(override) if override else get_project_root() / ".env"
+
+
 def get_project_root() -> Path:
     """
     获取当前项目根目录（含 pyproject.toml 的目录），用于保存到「项目下的 models」而非虚拟环境内。
@@ -164,7 +181,8 @@ def get_settings() -> Settings:
     """获取配置实例（单例）"""
     global _settings
     if _settings is None:
-        _settings = Settings()
+        override = os.environ.get(ENV_FILE_ENV, "").strip()
+        _settings = Settings(_env_file=override) if override else Settings()
         # 确保输出目录存在
         Path(_settings.model_output_dir).mkdir(parents=True, exist_ok=True)
     return _settings
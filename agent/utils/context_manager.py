@@ -11,7 +11,7 @@ from pathlib import Path
 from typing import Any, Dict, List, Optional
 from uuid import uuid4
 
-from agent.utils.config import get_install_dir
+from agent.utils.config import get_data_dir
 from agent.utils.logger import get_logger
 
 logger = get_logger(__name__)
@@ -50,7 +50,7 @@ class ContextManager:
         if context_dir is not None:
             self.context_dir = Path(context_dir)
         else:
-            base = get_install_dir() / ".context"
+            base = get_data_dir() / ".context"
             self.context_dir = base / (conversation_id or "default")
 
         self.context_dir.mkdir(parents=True, exist_ok=True)
@@ -985,7 +985,7 @@ class ContextManager:
 def get_all_models_from_context(limit: int = 50) -> List[Dict[str, Any]]:
     """Aggregate models from all conversation folders."""
 
-    base = get_install_dir() / ".context"
+    base = get_data_dir() / ".context"
     if not base.exists():
         return []
     collected: List[Dict[str, Any]] = []
@@ -0,0 +1,46 @@
+"""文件名清理：得到各平台都能创建的文件名（与桌面端 filenames.rs 规则一致）"""
+
+import unicodedata
+
+# 文件名字节数上限（ext4 等文件系统的单级名称上限）
+MAX_NAME_BYTES = 255
+# 超过该长度的“扩展名”视为名称的一部分
+MAX_EXT_CHARS = 16
+# Windows 上不能作为文件名（不论扩展名）的设备名
+RESERVED_NAMES = frozenset(
+    ["CON", "PRN", "AUX", "NUL"]
+    + [f"COM{i}" for i in "123456789¹²³"]
+    + [f"LPT{i}" for i in "123456789¹²³"]
+)
+_UNSAFE = set('<>:"/\\|?*')
+
+
+def _truncate_bytes(s: str, max_bytes: int) -> str:
+    """按 UTF-8 字节截断，不拆开多字节字符"""
+    return s.encode("utf-8")[:max_bytes].decode("utf-8", errors="ignore")
+
+
+def sanitize_filename(name: str, fallback: str = "model", max_bytes: int = MAX_NAME_BYTES) -> str:
+    """替换非法字符与控制字符，去掉首尾空格与结尾的点，避开 Windows 设备名（CON、NUL 等），
+    超长时截断名称并保留扩展名；结果为空时用 fallback。"""
+    out = "".join(
+        "_" if c in _UNSAFE or unicodedata.category(c) == "Cc" else c for c in name or ""
+    )
+    out = out.lstrip().rstrip(". ")
+
+    stem, dot, ext = out.rpartition(".")
+    if stem and ext and len(ext) <= MAX_EXT_CHARS:
+        ext = dot + ext
+    else:
+        stem, ext = out, ""
+
+    if stem.split(".", 1)[0].rstrip().upper() in RESERVED_NAMES:
+        stem = "_" + stem
+
+    ext_bytes = len(ext.encode("utf-8"))
+    if len(stem.encode("utf-8")) + ext_bytes > max_bytes:
+        stem = _truncate_bytes(stem, max(max_bytes - ext_bytes, 0)).rstrip(". ")
+
+    if not stem.strip("."):
+        stem = fallback
+    return stem + ext
@@ -0,0 +1,25 @@
+"""宿主操作：请运行本 agent 的桌面端打开网址、读取工作区外的文件或运行命令，
+由用户授权（可按项目记住）后执行。命令行等没有宿主的环境中这些操作不可用。"""
+
+from typing import Any, Callable, Dict, Optional
+
+# 宿主支持的操作及各自的必填参数
+ACTIONS = {"open_url": "url", "read_file": "path", "run_shell": "command"}
+
+HostHandler = Callable[..., Dict[str, Any]]
+_handler: Optional[HostHandler] = None
+
+
+def set_host_handler(handler: Optional[HostHandler]) -> None:
+    """登记执行宿主操作的函数（桌面端 bridge 启动时登记 `host_request`）。"""
+    global _handler
+    _handler = handler
+
+
+def request_host(action: str, **args: Any) -> Dict[str, Any]:
+    """请求宿主执行操作，返回 `{"ok": bool, "result": ..., "message": ..., "denied": bool}`。"""
+    if action not in ACTIONS:
+        return {"ok": False, "message": f"未知的宿主操作: {action}"}
+    if _handler is None:
+        return {"ok": False, "message": "当前运行环境没有桌面端，无法执行宿主操作"}
+    return _handler(action, **args)
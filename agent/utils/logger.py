@@ -19,15 +19,30 @@ def get_logger(name: str):
     return loguru_logger.bind(name=name)
 
 
+_level_override: Optional[str] = None
+
+
+def set_level_override(level: Optional[str]) -> None:
+    """
+    运行时覆盖日志级别（桌面端 bridge_set_log_level），之后的 setup_logging 也沿用
+
+    Args:
+        level: loguru 级别名，None 表示取消覆盖
+    """
+    global _level_override
+    _level_override = level
+    setup_logging()
+
+
 def setup_logging(log_level: Optional[str] = None):
     """
     配置日志系统
     
     Args:
-        log_level: 日志级别，如果为 None 则从配置读取
+        log_level: 日志级别，如果为 None 则从配置读取；运行时覆盖的级别优先
     """
     settings = get_settings()
-    level = log_level or settings.log_level
+    level = _level_override or log_level or settings.log_level
     
     # 移除默认处理器
     loguru_logger.remove()
@@ -102,6 +102,7 @@ def _print_help() -> None:
         "  uv run python cli.py                       启动桌面（有 release exe 时优先用它）\n"
         "  uv run python cli.py dev                   强制 Tauri+Vite 开发模式（新前端）\n"
         "  uv run python cli.py tui-bridge            内部子进程入口（Tauri 调用）\n"
+        "  uv run python cli.py bridge-server [...]   远程 bridge 服务（TCP/WebSocket，--help 查看参数）\n"
         "  uv run python cli.py parity                打印 clawcode parity 审计结果\n"
         "  uv run python cli.py workflow [name]       列出/查看 .claw-workflows.json 工作流\n"
         "  uv run python cli.py sub-agents-sync       将 planner 子 Agent 写入 .claude/agents/*.md\n"
@@ -187,6 +188,11 @@ def main() -> None:
         bridge_main()
         return
 
+    if args[0] == "bridge-server":
+        from agent.run.bridge_server import main as server_main
+
+        sys.exit(server_main(args[1:]))
+
     if args[0] == "parity":
         sys.exit(_run_parity_command())
 
@@ -44,6 +44,22 @@ try {
     New-Item -ItemType Directory -Path $BinariesDir -Force | Out-Null
     Copy-Item -Path $DistExe -Destination $DestExe -Force
     Write-Host "Bridge built: $DestExe"
+
+    # 完整性清单：覆盖 binaries 下本目标三元组的全部 bridge 文件（只有这些会装进安装包），
+    # 键为安装后的文件名（Tauri 会去掉三元组后缀）。随安装包放在 bridge 旁，应用启动 bridge 前逐个校验，缺少清单时拒绝启动
+    $ManifestPath = Join-Path $BinariesDir "bridge-manifest.json"
+    $files = [ordered]@{}
+    Get-ChildItem -Path $BinariesDir -File -Recurse |
+        Where-Object { $_.Name -like "*-$TargetTriple*" } |
+        Sort-Object FullName |
+        ForEach-Object {
+            $rel = $_.FullName.Substring($BinariesDir.Length + 1).Replace("\", "/").Replace("-$TargetTriple", "")
+            $files[$rel] = (Get-FileHash -Algorithm SHA256 -Path $_.FullName).Hash.ToLower()
+        }
+    # 不带 BOM 写出，serde_json 不接受 BOM
+    $json = @{ files = $files } | ConvertTo-Json -Depth 3
+    [System.IO.File]::WriteAllText($ManifestPath, $json, (New-Object System.Text.UTF8Encoding $false))
+    Write-Host "Bridge manifest written: $ManifestPath ($($files.Count) files)"
 } finally {
     Pop-Location
 }
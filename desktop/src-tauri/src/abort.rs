@@ -0,0 +1,410 @@
+use crate::bridge::{install_handles, restart_bridge, stop_child, BridgeState};
+use crate::bridge_error::BridgeError;
+use crate::debug_console::{self, Direction};
+use crate::dispatcher::{recv, Dispatcher};
+use crate::jobs::JobRegistry;
+use crate::panics::guarded;
+use crate::process::{process_tree, ChildHandle};
+use crate::sessions::state_of_stream;
+use crate::standby;
+use crate::store::{load_json, save_json};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+
+const DEFAULT_GRACE_SECS: u64 = 10;
+const MAX_GRACE_SECS: u64 = 600;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AbortMode {
+    /// 先请 bridge 自行取消（给 COMSOL 保存/收尾的机会），超时后再强制结束
+    #[default]
+    Cooperative,
+    /// 立即结束 bridge 进程，尽快释放 CPU
+    Kill,
+}
+
+fn default_grace_secs() -> u64 {
+    DEFAULT_GRACE_SECS
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AbortStrategy {
+    #[serde(default)]
+    pub mode: AbortMode,
+    /// 协作取消的等待时间，超过后强制结束
+    #[serde(default = "default_grace_secs")]
+    pub grace_secs: u64,
+}
+
+impl Default for AbortStrategy {
+    fn default() -> Self {
+        Self {
+            mode: AbortMode::default(),
+            grace_secs: DEFAULT_GRACE_SECS,
+        }
+    }
+}
+
+/// bridge_abort 的全局策略，任务可单独覆盖
+#[derive(Default)]
+pub struct AbortSettings {
+    path: Option<PathBuf>,
+    strategy: Mutex<AbortStrategy>,
+}
+
+impl AbortSettings {
+    pub fn load(path: PathBuf) -> Self {
+        let strategy: AbortStrategy = load_json(&path);
+        Self {
+            path: Some(path),
+            strategy: Mutex::new(strategy),
+        }
+    }
+
+    pub fn get(&self) -> AbortStrategy {
+        *self.strategy.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn set(&self, strategy: AbortStrategy) -> Result<(), String> {
+        *self.strategy.lock().unwrap_or_else(|e| e.into_inner()) = strategy;
+        match self.path {
+            Some(ref p) => save_json(p, &strategy),
+            None => Ok(()),
+        }
+    }
+}
+
+/// 一次中止或取消实际做了什么，同时以 `bridge-aborted` 事件发给前端
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct AbortReport {
+    /// bridge_cancel_stream 取消的流式请求
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stream_id: Option<String>,
+    pub mode: AbortMode,
+    /// 中止时是否有流式任务在执行
+    pub stream_active: bool,
+    /// 是否向 bridge 发出了协作取消
+    pub cancel_requested: bool,
+    /// 协作取消在宽限期内完成，bridge 进程未重启
+    pub cancelled: bool,
+    /// 被强制结束的 bridge 进程及其派生进程
+    pub killed_pids: Vec<u32>,
+    pub restarted: bool,
+    /// 由预热的备用进程接替，没有冷启动
+    pub standby: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub restart_ms: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub new_pid: Option<u32>,
+    /// 重启失败时的初始化错误
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<BridgeError>,
+}
+
+impl AbortReport {
+    /// 发送事件；重启失败时以错误返回，报告仍随事件送达
+    fn finish(self, app: &AppHandle) -> Result<Self, BridgeError> {
+        let _ = app.emit("bridge-aborted", &self);
+        match self.error {
+            Some(ref e) => Err(e.clone()),
+            None => Ok(self),
+        }
+    }
+}
+
+fn validate(mut strategy: AbortStrategy) -> AbortStrategy {
+    strategy.grace_secs = strategy.grace_secs.min(MAX_GRACE_SECS);
+    strategy
+}
+
+/// 向 bridge 发送 SIGINT：Python 端把 KeyboardInterrupt 当作取消当前请求并回复。
+/// 只能按 pid 发信号，发送前确认进程未被回收且启动时间一致。
+/// Windows 无法向无控制台的子进程单独发送 Ctrl+C，只能强制结束
+fn request_cancel(child: &ChildHandle) -> bool {
+    #[cfg(unix)]
+    {
+        let Some(pid) = child.verified_pid() else {
+            return false;
+        };
+        std::process::Command::new("kill")
+            .args(["-INT", &pid.to_string()])
+            .status()
+            .map(|s| s.success())
+            .unwrap_or(false)
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = child;
+        false
+    }
+}
+
+/// 等待流式请求自行结束；超时返回 false
+async fn wait_stream_end(state: &BridgeState, grace: Duration) -> bool {
+    let deadline = tokio::time::Instant::now() + grace;
+    loop {
+        if state.lock().await.active_streams == 0 {
+            return true;
+        }
+        if tokio::time::Instant::now() >= deadline {
+            return false;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+}
+
+/// 结束当前 bridge 进程并重新启动，把结束的进程、重启耗时与新进程写入报告。
+/// 默认会话有预热的备用进程时直接换上，再在后台预热下一个
+async fn restart_now(app: &AppHandle, state: &BridgeState, report: &mut AbortReport) {
+    let started = std::time::Instant::now();
+    let is_default = Arc::ptr_eq(state, app.state::<BridgeState>().inner());
+    let java_home = state.lock().await.bundled_java_home.clone();
+    let spare = if is_default {
+        standby::take(app, &java_home).await
+    } else {
+        None
+    };
+    {
+        let mut guard = state.lock().await;
+        report.killed_pids = guard
+            .child
+            .as_ref()
+            .and_then(|c| c.verified_pid())
+            .map(process_tree)
+            .unwrap_or_default();
+        stop_child(&mut guard).await;
+        if let Some(handles) = spare {
+            install_handles(&mut guard, handles);
+            report.standby = true;
+        }
+    }
+    if !report.standby {
+        restart_bridge(state).await;
+    }
+    if is_default {
+        standby::refill(app);
+    }
+    let guard = state.lock().await;
+    report.restarted = true;
+    report.restart_ms = Some(started.elapsed().as_millis() as u64);
+    report.new_pid = guard.child.as_ref().map(|c| c.pid);
+    report.error = guard.init_error.clone();
+}
+
+/// 经 stdin 发送取消控制消息 `{"cmd":"cancel","target":<_id>}`；
+/// bridge 在 `wait` 内确认正在取消该请求返回 true
+async fn send_cancel(dispatcher: &Dispatcher, target: &str, wait: Duration) -> bool {
+    let (id, mut rx) = dispatcher.register();
+    let line = serde_json::json!({ "cmd": "cancel", "target": target, "_id": id }).to_string();
+    debug_console::record(Direction::Out, &line);
+    if dispatcher.write_line(&line).await.is_err() {
+        dispatcher.cancel(&id);
+        return false;
+    }
+    match tokio::time::timeout(wait, recv(&mut rx)).await {
+        Ok(Ok(Ok(ack))) => ack.get("cancelling").and_then(Value::as_bool) == Some(true),
+        Ok(_) => false,
+        Err(_) => {
+            // 旧版 bridge 在任务结束前不读 stdin，收不到确认
+            dispatcher.cancel(&id);
+            false
+        }
+    }
+}
+
+/// 等待指定流式请求结束；超过 `deadline` 返回 false
+async fn wait_stream_gone(
+    state: &BridgeState,
+    stream_id: &str,
+    deadline: tokio::time::Instant,
+) -> bool {
+    loop {
+        if !state.lock().await.stream_ids.contains_key(stream_id) {
+            return true;
+        }
+        if tokio::time::Instant::now() >= deadline {
+            return false;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+}
+
+/// 按当前任务的覆盖策略（否则全局策略）中止正在执行的命令
+pub async fn abort_running(app: &AppHandle) -> Result<AbortReport, BridgeError> {
+    let state = app.state::<BridgeState>();
+    let strategy = app
+        .state::<JobRegistry>()
+        .running()
+        .and_then(|j| j.abort_strategy)
+        .unwrap_or_else(|| app.state::<AbortSettings>().get());
+
+    let (stream_active, child) = {
+        let guard = state.lock().await;
+        (guard.active_streams > 0, guard.child.clone())
+    };
+    let mut report = AbortReport {
+        mode: strategy.mode,
+        stream_active,
+        ..AbortReport::default()
+    };
+
+    // 只有流式任务进行中才发取消信号；空闲时 SIGINT 会直接结束 bridge
+    if strategy.mode == AbortMode::Cooperative && stream_active {
+        if let Some(child) = child {
+            report.cancel_requested = request_cancel(&child);
+            if report.cancel_requested
+                && wait_stream_end(state.inner(), Duration::from_secs(strategy.grace_secs)).await
+            {
+                report.cancelled = true;
+                return report.finish(app);
+            }
+            eprintln!("Warning: bridge 未在宽限期内响应取消，强制结束");
+        }
+    }
+
+    restart_now(app, state.inner(), &mut report).await;
+    report.finish(app)
+}
+
+/// 经协议内的取消消息中止全部流式请求，用于无法按 pid 发信号的远程 bridge。
+/// 宽限期内未全部确认并结束、或策略为立即结束时，断开连接后重新连接（服务端随之结束该 bridge）
+pub async fn abort_in_band(
+    app: &AppHandle,
+    state: &BridgeState,
+) -> Result<AbortReport, BridgeError> {
+    let strategy = app
+        .state::<JobRegistry>()
+        .running()
+        .and_then(|j| j.abort_strategy)
+        .unwrap_or_else(|| app.state::<AbortSettings>().get());
+    let (dispatcher, streams) = {
+        let guard = state.lock().await;
+        let streams: Vec<(String, String)> = guard
+            .stream_ids
+            .iter()
+            .map(|(k, v)| (k.clone(), v.clone()))
+            .collect();
+        (guard.dispatcher.clone(), streams)
+    };
+    let mut report = AbortReport {
+        mode: strategy.mode,
+        stream_active: !streams.is_empty(),
+        ..AbortReport::default()
+    };
+
+    if let (AbortMode::Cooperative, Some(dispatcher), false) =
+        (strategy.mode, dispatcher, streams.is_empty())
+    {
+        report.cancel_requested = true;
+        let grace = Duration::from_secs(strategy.grace_secs);
+        let deadline = tokio::time::Instant::now() + grace;
+        let mut done = true;
+        for (_, target) in &streams {
+            done &= send_cancel(&dispatcher, target, grace).await;
+        }
+        for (stream_id, _) in &streams {
+            done = done && wait_stream_gone(state, stream_id, deadline).await;
+        }
+        if done {
+            report.cancelled = true;
+            return report.finish(app);
+        }
+        eprintln!("Warning: 远程 bridge 未在宽限期内完成取消，断开重连");
+    }
+
+    restart_now(app, state, &mut report).await;
+    report.finish(app)
+}
+
+/// 只取消一条流式请求，bridge 进程与其中的 COMSOL 会话保留。
+/// 取消消息在 `grace_secs`（默认取中止策略的宽限期）内未获确认、或确认后流仍未结束，
+/// 才结束进程并重启
+#[tauri::command]
+pub async fn bridge_cancel_stream(
+    app: AppHandle,
+    stream_id: String,
+    grace_secs: Option<u64>,
+) -> Result<AbortReport, BridgeError> {
+    guarded("bridge_cancel_stream", async move {
+        let state = state_of_stream(&app, &stream_id)
+            .await
+            .ok_or_else(|| format!("没有进行中的流式请求: {}", stream_id))?;
+        let grace = grace_secs
+            .unwrap_or_else(|| app.state::<AbortSettings>().get().grace_secs)
+            .min(MAX_GRACE_SECS);
+        let deadline = tokio::time::Instant::now() + Duration::from_secs(grace);
+        let (dispatcher, target): (Arc<Dispatcher>, String) = {
+            let guard = state.lock().await;
+            let target = guard
+                .stream_ids
+                .get(&stream_id)
+                .cloned()
+                .ok_or_else(|| format!("没有进行中的流式请求: {}", stream_id))?;
+            let dispatcher = guard
+                .dispatcher
+                .clone()
+                .ok_or_else(BridgeError::not_initialized)?;
+            (dispatcher, target)
+        };
+
+        let mut report = AbortReport {
+            mode: AbortMode::Cooperative,
+            stream_active: true,
+            cancel_requested: true,
+            ..AbortReport::default()
+        };
+        let acked = send_cancel(&dispatcher, &target, Duration::from_secs(grace)).await;
+        if acked && wait_stream_gone(&state, &stream_id, deadline).await {
+            report.cancelled = true;
+        } else {
+            eprintln!(
+                "Warning: bridge 未在 {} 秒内完成取消 {}，强制结束",
+                grace, stream_id
+            );
+            restart_now(&app, &state, &mut report).await;
+        }
+        report.stream_id = Some(stream_id);
+        report.finish(&app)
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn abort_strategy_get(
+    settings: tauri::State<'_, AbortSettings>,
+) -> Result<AbortStrategy, String> {
+    guarded("abort_strategy_get", async move { Ok(settings.get()) }).await
+}
+
+#[tauri::command]
+pub async fn abort_strategy_set(
+    settings: tauri::State<'_, AbortSettings>,
+    strategy: AbortStrategy,
+) -> Result<(), String> {
+    guarded("abort_strategy_set", async move {
+        settings.set(validate(strategy))
+    })
+    .await
+}
+
+/// 为单个任务覆盖中止策略；传 None 恢复使用全局设置
+#[tauri::command]
+pub async fn job_set_abort_strategy(
+    jobs: tauri::State<'_, JobRegistry>,
+    job_id: String,
+    strategy: Option<AbortStrategy>,
+) -> Result<(), String> {
+    guarded("job_set_abort_strategy", async move {
+        if jobs.get(&job_id).is_none() {
+            return Err(format!("未找到任务: {}", job_id));
+        }
+        jobs.set_abort_strategy(&job_id, strategy.map(validate));
+        Ok(())
+    })
+    .await
+}
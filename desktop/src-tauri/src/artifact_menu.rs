@@ -0,0 +1,293 @@
+use crate::artifacts::{is_locked, ArtifactRegistry};
+use crate::bridge::{open_in_folder, open_path};
+use crate::comsol::{detect_installs, open_in_comsol};
+use crate::dialogs::{show, DialogDirStore, DialogFilter, DialogOptions};
+use crate::panics::guarded;
+use crate::policy::CommandPolicy;
+use std::path::Path;
+use tauri::menu::{IsMenuItem, Menu, MenuEvent, MenuItem, PredefinedMenuItem, Submenu};
+use tauri::{AppHandle, Emitter, LogicalPosition, Manager, WebviewWindow, Wry};
+
+/// 产物右键菜单项的 id 前缀；与托盘菜单共用全局菜单事件，据此区分
+const ID_PREFIX: &str = "artifact:";
+/// 导出对话框记住目录所用的用途名
+const EXPORT_PURPOSE: &str = "artifact_export";
+
+fn item_id(action: &str, path: &str) -> String {
+    format!("{}{}:{}", ID_PREFIX, action, path)
+}
+
+fn is_model(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| e.eq_ignore_ascii_case("mph"))
+}
+
+/// 按产物登记与文件实际状态构建菜单：不存在的文件只能复制路径，
+/// 被占用或只读模式下不可删除，只有 .mph 且检测到 COMSOL 安装时才可选版本打开
+fn build_menu(app: &AppHandle, path: &str, versions: &[String]) -> tauri::Result<Menu<Wry>> {
+    let file = Path::new(path);
+    let exists = file.is_file();
+    let tracked = app
+        .state::<ArtifactRegistry>()
+        .list()
+        .into_iter()
+        .find(|a| a.path == path);
+    let writable = app.state::<CommandPolicy>().ensure_writable("删除").is_ok();
+    let deletable = exists && writable && !is_locked(file);
+
+    let mut items: Vec<Box<dyn IsMenuItem<Wry>>> = Vec::new();
+    if tracked.as_ref().is_some_and(|a| a.externally_modified) {
+        items.push(Box::new(MenuItem::with_id(
+            app,
+            item_id("noop", path),
+            "已被外部修改",
+            false,
+            None::<&str>,
+        )?));
+        items.push(Box::new(PredefinedMenuItem::separator(app)?));
+    }
+    items.push(Box::new(MenuItem::with_id(
+        app,
+        item_id("open", path),
+        "打开",
+        exists,
+        None::<&str>,
+    )?));
+    items.push(Box::new(MenuItem::with_id(
+        app,
+        item_id("reveal", path),
+        "在文件夹中显示",
+        exists,
+        None::<&str>,
+    )?));
+    if is_model(file) {
+        let version_items = versions
+            .iter()
+            .map(|v| {
+                MenuItem::with_id(
+                    app,
+                    format!("{}comsol:{}:{}", ID_PREFIX, v, path),
+                    format!("COMSOL {}", v),
+                    exists,
+                    None::<&str>,
+                )
+            })
+            .collect::<tauri::Result<Vec<_>>>()?;
+        let refs: Vec<&dyn IsMenuItem<Wry>> = version_items
+            .iter()
+            .map(|i| i as &dyn IsMenuItem<Wry>)
+            .collect();
+        items.push(Box::new(Submenu::with_items(
+            app,
+            "用 COMSOL 版本打开",
+            exists && !refs.is_empty(),
+            &refs,
+        )?));
+    }
+    items.push(Box::new(MenuItem::with_id(
+        app,
+        item_id("copy-path", path),
+        "复制路径",
+        true,
+        None::<&str>,
+    )?));
+    items.push(Box::new(PredefinedMenuItem::separator(app)?));
+    items.push(Box::new(MenuItem::with_id(
+        app,
+        item_id("export", path),
+        "导出副本…",
+        exists,
+        None::<&str>,
+    )?));
+    items.push(Box::new(MenuItem::with_id(
+        app,
+        item_id("trash", path),
+        "移到回收站",
+        deletable,
+        None::<&str>,
+    )?));
+    let refs: Vec<&dyn IsMenuItem<Wry>> = items.iter().map(|i| i.as_ref()).collect();
+    Menu::with_items(app, &refs)
+}
+
+/// 移到系统回收站（而非直接删除），误删可从回收站恢复
+fn move_to_trash(path: &str) -> Result<(), String> {
+    #[cfg(target_os = "windows")]
+    let output = std::process::Command::new("powershell")
+        .args([
+            "-NoProfile",
+            "-NonInteractive",
+            "-Command",
+            "Add-Type -AssemblyName Microsoft.VisualBasic; \
+             [Microsoft.VisualBasic.FileIO.FileSystem]::DeleteFile(\
+             $env:MPH_AGENT_TRASH_PATH, 'OnlyErrorDialogs', 'SendToRecycleBin')",
+        ])
+        .env("MPH_AGENT_TRASH_PATH", path)
+        .output();
+    #[cfg(target_os = "macos")]
+    let output = std::process::Command::new("osascript")
+        .args([
+            "-e",
+            "on run argv",
+            "-e",
+            "tell application \"Finder\" to delete POSIX file (item 1 of argv)",
+            "-e",
+            "end run",
+            path,
+        ])
+        .output();
+    #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+    let output = std::process::Command::new("gio")
+        .args(["trash", path])
+        .output();
+    let output = output.map_err(|e| format!("调用回收站失败: {}", e))?;
+    if !output.status.success() {
+        return Err(format!(
+            "移到回收站失败: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(())
+}
+
+async fn trash_artifact(app: &AppHandle, path: &str) -> Result<(), String> {
+    app.state::<CommandPolicy>().ensure_writable("删除")?;
+    if is_locked(Path::new(path)) {
+        return Err(format!("文件正被其他程序占用: {}", path));
+    }
+    let target = path.to_string();
+    tauri::async_runtime::spawn_blocking(move || move_to_trash(&target))
+        .await
+        .map_err(|e| e.to_string())??;
+    app.state::<ArtifactRegistry>().relocate(path, None);
+    Ok(())
+}
+
+/// 选择目标位置后复制一份；项目取自产物登记，用于记住导出目录
+async fn export_artifact(app: &AppHandle, path: &str) -> Result<(), String> {
+    let file = Path::new(path);
+    let project = app
+        .state::<ArtifactRegistry>()
+        .list()
+        .into_iter()
+        .find(|a| a.path == path)
+        .and_then(|a| a.project);
+    let filters = file
+        .extension()
+        .map(|e| e.to_string_lossy().into_owned())
+        .map(|ext| DialogFilter {
+            name: ext.to_uppercase(),
+            extensions: vec![ext],
+        })
+        .into_iter()
+        .collect();
+    let store = app.state::<DialogDirStore>();
+    let target = show(
+        app.clone(),
+        store.inner(),
+        EXPORT_PURPOSE.to_string(),
+        project,
+        DialogOptions {
+            title: None,
+            default_name: file.file_name().map(|n| n.to_string_lossy().into_owned()),
+            filters,
+            save: true,
+            locale: None,
+        },
+    )
+    .await?;
+    let Some(target) = target else {
+        return Ok(());
+    };
+    if Path::new(&target) == file {
+        return Ok(());
+    }
+    std::fs::copy(file, &target).map_err(|e| format!("导出失败: {}", e))?;
+    Ok(())
+}
+
+/// 全局菜单事件中处理产物右键菜单项；其他菜单的事件直接忽略
+pub fn handle_menu_event(app: &AppHandle, event: MenuEvent) {
+    let Some(rest) = event.id().as_ref().strip_prefix(ID_PREFIX) else {
+        return;
+    };
+    let Some((action, path)) = rest.split_once(':') else {
+        return;
+    };
+    let (action, path) = (action.to_string(), path.to_string());
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let result = match action.as_str() {
+            "open" => open_path(path.clone()).await,
+            "reveal" => open_in_folder(path.clone()).await,
+            "comsol" => match path.split_once(':') {
+                Some((version, model)) => {
+                    open_in_comsol(
+                        app.state::<CommandPolicy>(),
+                        model.to_string(),
+                        Some(version.to_string()),
+                    )
+                    .await
+                }
+                None => Ok(()),
+            },
+            // 剪贴板由前端写入
+            "copy-path" => app
+                .emit(
+                    "artifact-menu-action",
+                    serde_json::json!({ "action": "copy-path", "path": path }),
+                )
+                .map_err(|e| e.to_string()),
+            "export" => export_artifact(&app, &path).await,
+            "trash" => trash_artifact(&app, &path).await.map(|_| {
+                let _ = app.emit(
+                    "artifact-menu-action",
+                    serde_json::json!({ "action": "trash", "path": path }),
+                );
+            }),
+            _ => Ok(()),
+        };
+        if let Err(e) = result {
+            eprintln!("Warning: 产物菜单操作 {} 失败: {}", action, e);
+            let _ = app.emit(
+                "artifact-menu-action",
+                serde_json::json!({ "action": action, "path": path, "error": e }),
+            );
+        }
+    });
+}
+
+/// 在调用方窗口弹出产物右键菜单；x/y 为窗口内的逻辑坐标，缺省时在鼠标位置弹出
+#[tauri::command]
+pub async fn artifact_context_menu(
+    app: AppHandle,
+    window: WebviewWindow,
+    path: String,
+    x: Option<f64>,
+    y: Option<f64>,
+) -> Result<(), String> {
+    guarded("artifact_context_menu", async move {
+        let path = path.trim().to_string();
+        if path.is_empty() {
+            return Err("路径为空".to_string());
+        }
+        let versions: Vec<String> = if is_model(Path::new(&path)) {
+            tauri::async_runtime::spawn_blocking(detect_installs)
+                .await
+                .map_err(|e| e.to_string())?
+                .into_iter()
+                .map(|i| i.version)
+                .collect()
+        } else {
+            Vec::new()
+        };
+        let menu = build_menu(&app, &path, &versions).map_err(|e| e.to_string())?;
+        match x.zip(y) {
+            Some((x, y)) => window.popup_menu_at(&menu, LogicalPosition::new(x, y)),
+            None => window.popup_menu(&menu),
+        }
+        .map_err(|e| e.to_string())
+    })
+    .await
+}
@@ -0,0 +1,711 @@
+use crate::jobs::JobRegistry;
+use crate::notifications::NotificationCenter;
+use crate::panics::guarded;
+use crate::postprocess;
+use crate::remap::{PathRemap, RemapChange};
+use crate::store::{load_json, now_ms, save_json};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
+use std::io::Read;
+#[cfg(target_os = "windows")]
+use std::os::windows::process::CommandExt;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+/// 轮询已登记产物的间隔
+const SCAN_INTERVAL: Duration = Duration::from_secs(3);
+/// 文件被占用后轮询释放的间隔与最长等待
+const UNLOCK_POLL: Duration = Duration::from_secs(2);
+const UNLOCK_WAIT_MAX: Duration = Duration::from_secs(3600);
+/// 完整性巡检时每个文件之间稍作停顿，避免占满磁盘
+const INTEGRITY_PAUSE: Duration = Duration::from_millis(200);
+
+/// 正在等待释放的文件，避免重复启动轮询
+static WATCHING_LOCKS: Mutex<Option<HashSet<String>>> = Mutex::new(None);
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TrackedArtifact {
+    pub path: String,
+    pub sha256: String,
+    pub size: u64,
+    pub modified_ms: u64,
+    #[serde(default)]
+    pub request_id: Option<String>,
+    #[serde(default)]
+    pub project: Option<String>,
+    pub registered_at: u64,
+    /// 检测到外部修改、尚未确认覆盖
+    #[serde(default)]
+    pub externally_modified: bool,
+    #[serde(default)]
+    pub current_sha256: Option<String>,
+    #[serde(default)]
+    pub detected_at: Option<u64>,
+    /// 完整性巡检时文件不存在
+    #[serde(default)]
+    pub missing: bool,
+    /// 大小与修改时间未变但内容哈希不符，多为磁盘或同步软件损坏
+    #[serde(default)]
+    pub corrupted: bool,
+    /// 最近一次完整性巡检的时间
+    #[serde(default)]
+    pub verified_at: Option<u64>,
+}
+
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct IntegritySummary {
+    pub checked: usize,
+    pub ok: usize,
+    pub missing: Vec<String>,
+    pub corrupted: Vec<String>,
+    /// 本轮新发现缺失或损坏的条目数
+    pub newly_flagged: usize,
+    /// 之前缺失、本轮又找到的条目
+    pub recovered: Vec<String>,
+    pub started_at: u64,
+    pub finished_at: u64,
+}
+
+enum Verdict {
+    Ok,
+    Missing,
+    Corrupted(String),
+    /// 文件已被改动，交给外部修改检测处理
+    Changed,
+}
+
+struct FileStamp {
+    size: u64,
+    modified_ms: u64,
+}
+
+fn stamp(path: &Path) -> Option<FileStamp> {
+    let meta = std::fs::metadata(path).ok()?;
+    let modified_ms = meta
+        .modified()
+        .ok()
+        .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0);
+    Some(FileStamp {
+        size: meta.len(),
+        modified_ms,
+    })
+}
+
+pub fn sha256_file(path: &Path) -> Result<String, String> {
+    let mut file =
+        std::fs::File::open(path).map_err(|e| format!("打开 {} 失败: {}", path.display(), e))?;
+    let mut hasher = Sha256::new();
+    let mut buf = vec![0u8; 64 * 1024];
+    loop {
+        let n = file
+            .read(&mut buf)
+            .map_err(|e| format!("读取 {} 失败: {}", path.display(), e))?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+    }
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// 产物登记表：记录 agent 生成的 .mph 文件及其哈希，用于发现用户在 COMSOL 中的外部修改
+#[derive(Default)]
+pub struct ArtifactRegistry {
+    path: Option<PathBuf>,
+    items: Mutex<Vec<TrackedArtifact>>,
+}
+
+impl ArtifactRegistry {
+    pub fn load(path: PathBuf) -> Self {
+        let items: Vec<TrackedArtifact> = load_json(&path);
+        Self {
+            path: Some(path),
+            items: Mutex::new(items),
+        }
+    }
+
+    fn update<T>(&self, f: impl FnOnce(&mut Vec<TrackedArtifact>) -> T) -> T {
+        let mut items = self.items.lock().unwrap_or_else(|e| e.into_inner());
+        let out = f(&mut items);
+        if let Some(ref p) = self.path {
+            if let Err(e) = save_json(p, &*items) {
+                eprintln!("Warning: 保存产物登记失败: {}", e);
+            }
+        }
+        out
+    }
+
+    /// 登记（或重新登记）产物，以当前文件内容为基准
+    pub fn register(
+        &self,
+        path: &str,
+        request_id: Option<&str>,
+        project: Option<&str>,
+    ) -> Result<TrackedArtifact, String> {
+        let p = Path::new(path);
+        let st = stamp(p).ok_or_else(|| format!("文件不存在: {}", path))?;
+        let artifact = TrackedArtifact {
+            path: path.to_string(),
+            sha256: sha256_file(p)?,
+            size: st.size,
+            modified_ms: st.modified_ms,
+            request_id: request_id.map(String::from),
+            project: project.map(String::from),
+            registered_at: now_ms(),
+            externally_modified: false,
+            current_sha256: None,
+            detected_at: None,
+            missing: false,
+            corrupted: false,
+            verified_at: Some(now_ms()),
+        };
+        self.update(|items| {
+            items.retain(|a| a.path != path);
+            items.push(artifact.clone());
+        });
+        Ok(artifact)
+    }
+
+    pub fn get(&self, path: &str) -> Option<TrackedArtifact> {
+        self.items
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .iter()
+            .find(|a| a.path == path)
+            .cloned()
+    }
+
+    pub fn list(&self) -> Vec<TrackedArtifact> {
+        self.items.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    /// 检查所有产物，返回本轮新发现被外部修改的条目。
+    /// 先比较大小与修改时间，变化时才计算哈希；内容未变（如仅 touch）只刷新时间戳。
+    pub fn scan(&self) -> Vec<TrackedArtifact> {
+        let snapshot = self.list();
+        let mut changed = Vec::new();
+        for a in snapshot.iter().filter(|a| !a.externally_modified) {
+            let Some(st) = stamp(Path::new(&a.path)) else {
+                continue;
+            };
+            if st.size == a.size && st.modified_ms == a.modified_ms {
+                continue;
+            }
+            let Ok(hash) = sha256_file(Path::new(&a.path)) else {
+                continue;
+            };
+            changed.push((a.path.clone(), st, hash));
+        }
+        if changed.is_empty() {
+            return Vec::new();
+        }
+        self.update(|items| {
+            let mut out = Vec::new();
+            for (path, st, hash) in changed {
+                let Some(a) = items.iter_mut().find(|a| a.path == path) else {
+                    continue;
+                };
+                a.size = st.size;
+                a.modified_ms = st.modified_ms;
+                if hash != a.sha256 {
+                    a.externally_modified = true;
+                    a.current_sha256 = Some(hash);
+                    a.detected_at = Some(now_ms());
+                    out.push(a.clone());
+                }
+            }
+            out
+        })
+    }
+
+    /// 用户确认可以覆盖：以当前内容为新基准并清除修改标记
+    pub fn confirm_overwrite(&self, path: &str) -> Result<(), String> {
+        let prev = self
+            .list()
+            .into_iter()
+            .find(|a| a.path == path)
+            .ok_or_else(|| format!("未登记的产物: {}", path))?;
+        self.register(path, prev.request_id.as_deref(), prev.project.as_deref())
+            .map(|_| ())
+    }
+
+    /// 请求可能覆盖的已有文件：`output` 为绝对路径时直接取该文件，
+    /// 仅为文件名时按文件名匹配已登记的产物
+    pub fn overwrite_targets(&self, payload: &Value) -> Vec<String> {
+        let Some(output) = payload.get("output").and_then(|v| v.as_str()) else {
+            return Vec::new();
+        };
+        let output = output.trim();
+        if output.is_empty() {
+            return Vec::new();
+        }
+        let target = Path::new(output);
+        if target.is_absolute() {
+            return if target.is_file() {
+                vec![output.to_string()]
+            } else {
+                Vec::new()
+            };
+        }
+        self.list()
+            .into_iter()
+            .filter(|a| Path::new(&a.path).file_name() == target.file_name())
+            .filter(|a| Path::new(&a.path).is_file())
+            .map(|a| a.path)
+            .collect()
+    }
+
+    /// 请求会写入已被外部修改、且未确认覆盖的产物时拒绝。
+    /// payload 带 `confirm_overwrite: true` 视为已确认。
+    pub fn check_overwrite(&self, payload: &Value) -> Result<(), String> {
+        let targets = self.overwrite_targets(payload);
+        if targets.is_empty() {
+            return Ok(());
+        }
+        let conflicts: Vec<String> = self
+            .list()
+            .into_iter()
+            .filter(|a| a.externally_modified && targets.contains(&a.path))
+            .map(|a| a.path)
+            .collect();
+        if conflicts.is_empty() {
+            return Ok(());
+        }
+        if payload.get("confirm_overwrite").and_then(|v| v.as_bool()) == Some(true) {
+            for p in &conflicts {
+                self.confirm_overwrite(p)?;
+            }
+            return Ok(());
+        }
+        Err(format!(
+            "模型文件已在外部被修改，需确认后才能覆盖: {}",
+            conflicts.join(", ")
+        ))
+    }
+
+    /// 文件被移动（to 为 Some）或删除后更新登记
+    pub fn relocate(&self, from: &str, to: Option<&str>) {
+        self.update(|items| match to {
+            Some(to) => {
+                if let Some(a) = items.iter_mut().find(|a| a.path == from) {
+                    a.path = to.to_string();
+                }
+            }
+            None => items.retain(|a| a.path != from),
+        });
+    }
+
+    /// 网络盘重新挂载等原因根目录变化后，批量改写登记的路径
+    pub fn remap(&self, remap: &PathRemap, apply: bool) -> Vec<RemapChange> {
+        let rewrite = |items: &mut Vec<TrackedArtifact>| {
+            let mut changes = Vec::new();
+            for a in items.iter_mut() {
+                let entry = a.path.clone();
+                remap.rewrite("artifacts", &entry, "path", &mut a.path, &mut changes);
+                if let Some(ref mut project) = a.project {
+                    remap.rewrite("artifacts", &entry, "project", project, &mut changes);
+                }
+            }
+            changes
+        };
+        if apply {
+            self.update(rewrite)
+        } else {
+            rewrite(&mut self.list())
+        }
+    }
+
+    /// 逐个重新计算产物哈希，标记缺失与损坏的条目。耗时较长，应在后台线程调用
+    pub fn verify_all(&self) -> IntegritySummary {
+        let mut summary = IntegritySummary {
+            started_at: now_ms(),
+            ..IntegritySummary::default()
+        };
+        let mut verdicts = Vec::new();
+        for a in self.list().iter().filter(|a| !a.externally_modified) {
+            let path = Path::new(&a.path);
+            let verdict = match stamp(path) {
+                None => Verdict::Missing,
+                Some(st) if st.size != a.size || st.modified_ms != a.modified_ms => {
+                    Verdict::Changed
+                }
+                Some(_) => match sha256_file(path) {
+                    Ok(hash) if hash == a.sha256 => Verdict::Ok,
+                    Ok(hash) => Verdict::Corrupted(hash),
+                    // 打开失败（如被独占）不算损坏，下轮再查
+                    Err(_) => Verdict::Changed,
+                },
+            };
+            verdicts.push((a.path.clone(), verdict));
+            std::thread::sleep(INTEGRITY_PAUSE);
+        }
+        self.update(|items| {
+            let now = now_ms();
+            for (path, verdict) in verdicts {
+                let Some(a) = items.iter_mut().find(|a| a.path == path) else {
+                    continue;
+                };
+                summary.checked += 1;
+                match verdict {
+                    Verdict::Ok => {
+                        if a.missing {
+                            summary.recovered.push(path);
+                        }
+                        a.missing = false;
+                        a.corrupted = false;
+                        a.verified_at = Some(now);
+                        summary.ok += 1;
+                    }
+                    Verdict::Missing => {
+                        if !a.missing {
+                            summary.newly_flagged += 1;
+                            a.missing = true;
+                            a.detected_at = Some(now);
+                        }
+                        summary.missing.push(path);
+                    }
+                    Verdict::Corrupted(hash) => {
+                        if !a.corrupted {
+                            summary.newly_flagged += 1;
+                            a.corrupted = true;
+                            a.detected_at = Some(now);
+                        }
+                        a.missing = false;
+                        a.current_sha256 = Some(hash);
+                        summary.corrupted.push(path);
+                    }
+                    Verdict::Changed => {
+                        if a.missing {
+                            summary.recovered.push(path);
+                        }
+                        a.missing = false;
+                    }
+                }
+            }
+        });
+        summary.finished_at = now_ms();
+        summary
+    }
+
+    pub fn is_tracked(&self, path: &str) -> bool {
+        self.items
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .iter()
+            .any(|a| a.path == path)
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct LockHolder {
+    pub pid: u32,
+    pub name: String,
+}
+
+/// 以写方式打开文件检测共享冲突；COMSOL 打开模型时会独占 .mph。
+/// 只有 Windows 会强制共享模式，其他平台总是返回 false
+pub(crate) fn is_locked(path: &Path) -> bool {
+    #[cfg(target_os = "windows")]
+    {
+        const ERROR_SHARING_VIOLATION: i32 = 32;
+        const ERROR_LOCK_VIOLATION: i32 = 33;
+        match std::fs::OpenOptions::new().write(true).open(path) {
+            Ok(_) => false,
+            Err(e) => matches!(
+                e.raw_os_error(),
+                Some(ERROR_SHARING_VIOLATION | ERROR_LOCK_VIOLATION)
+            ),
+        }
+    }
+    #[cfg(not(target_os = "windows"))]
+    {
+        let _ = path;
+        false
+    }
+}
+
+/// 通过 Restart Manager 查询占用文件的进程；PowerShell 内联 P/Invoke，避免引入 Windows 绑定依赖
+#[cfg(target_os = "windows")]
+const RESTART_MANAGER_PS: &str = r#"
+Add-Type -TypeDefinition @'
+using System;
+using System.Text;
+using System.Runtime.InteropServices;
+public static class MphLock {
+  [StructLayout(LayoutKind.Sequential)]
+  struct UniqueProcess { public int Pid; public System.Runtime.InteropServices.ComTypes.FILETIME Start; }
+  [StructLayout(LayoutKind.Sequential, CharSet = CharSet.Unicode)]
+  struct ProcessInfo {
+    public UniqueProcess Process;
+    [MarshalAs(UnmanagedType.ByValTStr, SizeConst = 256)] public string AppName;
+    [MarshalAs(UnmanagedType.ByValTStr, SizeConst = 64)] public string ServiceName;
+    public int AppType; public uint AppStatus; public uint SessionId;
+    [MarshalAs(UnmanagedType.Bool)] public bool Restartable;
+  }
+  [DllImport("rstrtmgr.dll", CharSet = CharSet.Unicode)]
+  static extern int RmStartSession(out uint handle, int flags, StringBuilder key);
+  [DllImport("rstrtmgr.dll")]
+  static extern int RmEndSession(uint handle);
+  [DllImport("rstrtmgr.dll", CharSet = CharSet.Unicode)]
+  static extern int RmRegisterResources(uint handle, uint nFiles, string[] files, uint nApps, UniqueProcess[] apps, uint nServices, string[] services);
+  [DllImport("rstrtmgr.dll")]
+  static extern int RmGetList(uint handle, out uint needed, ref uint count, [In, Out] ProcessInfo[] info, ref uint reasons);
+  public static string Holders(string path) {
+    uint handle;
+    if (RmStartSession(out handle, 0, new StringBuilder(64)) != 0) return "";
+    try {
+      if (RmRegisterResources(handle, 1, new[] { path }, 0, null, 0, null) != 0) return "";
+      uint needed = 0, count = 0, reasons = 0;
+      if (RmGetList(handle, out needed, ref count, null, ref reasons) != 234 || needed == 0) return "";
+      var info = new ProcessInfo[needed];
+      count = needed;
+      if (RmGetList(handle, out needed, ref count, info, ref reasons) != 0) return "";
+      var sb = new StringBuilder();
+      for (int i = 0; i < count; i++) sb.AppendLine(info[i].Process.Pid + "\t" + info[i].AppName);
+      return sb.ToString();
+    } finally { RmEndSession(handle); }
+  }
+}
+'@
+[MphLock]::Holders($env:MPH_AGENT_LOCK_PATH)
+"#;
+
+/// 占用文件的进程；查询失败或非 Windows 时为空
+fn lock_holders(path: &Path) -> Vec<LockHolder> {
+    #[cfg(target_os = "windows")]
+    {
+        const CREATE_NO_WINDOW: u32 = 0x08000000;
+        let Ok(out) = std::process::Command::new("powershell")
+            .args([
+                "-NoProfile",
+                "-NonInteractive",
+                "-Command",
+                RESTART_MANAGER_PS,
+            ])
+            .env("MPH_AGENT_LOCK_PATH", path)
+            .creation_flags(CREATE_NO_WINDOW)
+            .output()
+        else {
+            return Vec::new();
+        };
+        String::from_utf8_lossy(&out.stdout)
+            .lines()
+            .filter_map(|line| {
+                let (pid, name) = line.trim().split_once('\t')?;
+                Some(LockHolder {
+                    pid: pid.parse().ok()?,
+                    name: name.trim().to_string(),
+                })
+            })
+            .collect()
+    }
+    #[cfg(not(target_os = "windows"))]
+    {
+        let _ = path;
+        Vec::new()
+    }
+}
+
+/// 文件释放后发送 `file-unlocked`，前端据此提示用户重试
+fn spawn_unlock_watcher(app: &AppHandle, path: String) {
+    {
+        let mut watching = WATCHING_LOCKS.lock().unwrap_or_else(|e| e.into_inner());
+        if !watching
+            .get_or_insert_with(HashSet::new)
+            .insert(path.clone())
+        {
+            return;
+        }
+    }
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let deadline = tokio::time::Instant::now() + UNLOCK_WAIT_MAX;
+        loop {
+            tokio::time::sleep(UNLOCK_POLL).await;
+            let p = path.clone();
+            let locked = tauri::async_runtime::spawn_blocking(move || is_locked(Path::new(&p)))
+                .await
+                .unwrap_or(false);
+            if !locked {
+                app.state::<NotificationCenter>().deliver(
+                    &app,
+                    "file-unlocked",
+                    serde_json::json!({ "path": path }),
+                );
+                break;
+            }
+            if tokio::time::Instant::now() >= deadline {
+                break;
+            }
+        }
+        if let Some(w) = WATCHING_LOCKS
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .as_mut()
+        {
+            w.remove(&path);
+        }
+    });
+}
+
+/// 请求要覆盖的文件正被其他程序（通常是打开着模型的 COMSOL）占用时拒绝，
+/// 发送 `file-locked` 事件并在释放后发送 `file-unlocked`
+pub async fn check_not_locked(app: &AppHandle, payload: &Value) -> Result<(), String> {
+    let targets = app.state::<ArtifactRegistry>().overwrite_targets(payload);
+    for path in targets {
+        let p = path.clone();
+        let holders = tauri::async_runtime::spawn_blocking(move || {
+            let p = Path::new(&p);
+            is_locked(p).then(|| lock_holders(p))
+        })
+        .await
+        .map_err(|e| e.to_string())?;
+        let Some(holders) = holders else {
+            continue;
+        };
+        let by = if holders.is_empty() {
+            "其他程序".to_string()
+        } else {
+            holders
+                .iter()
+                .map(|h| format!("{} (PID {})", h.name, h.pid))
+                .collect::<Vec<_>>()
+                .join("、")
+        };
+        app.state::<NotificationCenter>().deliver(
+            app,
+            "file-locked",
+            serde_json::json!({
+                "path": path,
+                "holders": holders,
+                "retry_event": "file-unlocked",
+            }),
+        );
+        spawn_unlock_watcher(app, path.clone());
+        return Err(format!(
+            "FileLocked: {} 正被 {} 占用，请在 COMSOL 中关闭该模型后重试",
+            path, by
+        ));
+    }
+    Ok(())
+}
+
+/// 后台轮询产物文件；任务运行期间 agent 自己会写文件，跳过检测
+pub fn spawn_artifact_watcher(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            tokio::time::sleep(SCAN_INTERVAL).await;
+            if app.state::<JobRegistry>().running().is_some() {
+                continue;
+            }
+            let scan_app = app.clone();
+            let changed = tauri::async_runtime::spawn_blocking(move || {
+                scan_app.state::<ArtifactRegistry>().scan()
+            })
+            .await
+            .unwrap_or_default();
+            let notifications = app.state::<NotificationCenter>();
+            for a in changed {
+                notifications.deliver(
+                    &app,
+                    "artifact-externally-modified",
+                    serde_json::json!({
+                        "path": a.path,
+                        "request_id": a.request_id,
+                        "previous_sha256": a.sha256,
+                        "current_sha256": a.current_sha256,
+                        "hash_changed": true,
+                        "size": a.size,
+                        "modified_ms": a.modified_ms,
+                    }),
+                );
+            }
+        }
+    });
+}
+
+async fn run_integrity_scan(app: &AppHandle) -> IntegritySummary {
+    let scan_app = app.clone();
+    tauri::async_runtime::spawn_blocking(move || scan_app.state::<ArtifactRegistry>().verify_all())
+        .await
+        .unwrap_or_default()
+}
+
+/// 后台巡检产物完整性，由维护调度在空闲时低频调用；
+/// 有新发现的缺失/损坏或找回的文件时，把汇总发到通知中心
+pub(crate) async fn background_integrity_scan(app: &AppHandle) -> IntegritySummary {
+    let summary = run_integrity_scan(app).await;
+    if summary.newly_flagged > 0 || !summary.recovered.is_empty() {
+        app.state::<NotificationCenter>().deliver(
+            app,
+            "artifact-integrity-scan",
+            serde_json::to_value(&summary).unwrap_or_default(),
+        );
+    }
+    summary
+}
+
+#[tauri::command]
+pub async fn artifacts_list(
+    artifacts: tauri::State<'_, ArtifactRegistry>,
+) -> Result<Vec<TrackedArtifact>, String> {
+    guarded("artifacts_list", async move { Ok(artifacts.list()) }).await
+}
+
+#[tauri::command]
+pub async fn artifact_register(
+    app: AppHandle,
+    artifacts: tauri::State<'_, ArtifactRegistry>,
+    path: String,
+    project: Option<String>,
+) -> Result<TrackedArtifact, String> {
+    guarded("artifact_register", async move {
+        let artifact = artifacts.register(path.trim(), None, project.as_deref())?;
+        postprocess::submit(&app, &artifact.path);
+        Ok(artifact)
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn artifact_confirm_overwrite(
+    artifacts: tauri::State<'_, ArtifactRegistry>,
+    path: String,
+) -> Result<(), String> {
+    guarded("artifact_confirm_overwrite", async move {
+        artifacts.confirm_overwrite(path.trim())
+    })
+    .await
+}
+
+/// 立即运行一次完整性巡检并返回汇总
+#[tauri::command]
+pub async fn artifacts_verify(app: AppHandle) -> Result<IntegritySummary, String> {
+    guarded("artifacts_verify", async move {
+        Ok(run_integrity_scan(&app).await)
+    })
+    .await
+}
+
+/// 查询文件是否被占用及占用它的进程
+#[tauri::command]
+pub async fn artifact_lock_status(path: String) -> Result<Value, String> {
+    guarded("artifact_lock_status", async move {
+        let path = path.trim().to_string();
+        tauri::async_runtime::spawn_blocking(move || {
+            let p = Path::new(&path);
+            let locked = is_locked(p);
+            let holders = if locked { lock_holders(p) } else { Vec::new() };
+            serde_json::json!({ "path": path, "locked": locked, "holders": holders })
+        })
+        .await
+        .map_err(|e| e.to_string())
+    })
+    .await
+}
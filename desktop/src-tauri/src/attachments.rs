@@ -0,0 +1,471 @@
+use crate::artifacts::sha256_file;
+use crate::filenames::sanitize_file_name;
+use crate::panics::guarded;
+use crate::sandbox::check_path;
+use crate::store::{load_json, now_ms, save_json};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::cmp::Reverse;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager};
+
+/// 请求中的附件字段：提交时为用户选择的路径或已入库附件的 id，发送前替换为入库后的记录
+const ATTACHMENTS_KEY: &str = "attachments";
+/// 未指定项目的附件放在该目录
+const DEFAULT_PROJECT_DIR: &str = "_default";
+/// 提取元数据时最多读取的字节数
+const SNIFF_BYTES: usize = 64 * 1024;
+/// CSV 预览的列名个数上限
+const MAX_COLUMNS: usize = 64;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AttachmentKind {
+    Pdf,
+    Csv,
+    Image,
+    Text,
+}
+
+impl AttachmentKind {
+    fn from_ext(ext: &str) -> Option<Self> {
+        Some(match ext {
+            "pdf" => Self::Pdf,
+            "csv" | "tsv" => Self::Csv,
+            "png" | "jpg" | "jpeg" | "gif" | "bmp" | "webp" => Self::Image,
+            "txt" | "md" | "json" | "dat" => Self::Text,
+            _ => return None,
+        })
+    }
+
+    /// 各类附件的大小上限
+    fn max_bytes(self) -> u64 {
+        const MB: u64 = 1024 * 1024;
+        match self {
+            Self::Pdf => 50 * MB,
+            Self::Csv => 200 * MB,
+            Self::Image => 20 * MB,
+            Self::Text => 10 * MB,
+        }
+    }
+}
+
+/// 入库后的附件；`path` 在附件删除前保持不变，可放心交给 bridge
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Attachment {
+    pub id: String,
+    /// 原始文件名
+    pub name: String,
+    pub kind: AttachmentKind,
+    pub path: String,
+    /// 用户选择时的原路径，仅供展示
+    pub source: String,
+    pub size: u64,
+    pub sha256: String,
+    #[serde(default)]
+    pub project: Option<String>,
+    /// 轻量元数据：PDF 页数、CSV 列名与行数、图片尺寸等
+    #[serde(default)]
+    pub meta: Value,
+    pub added_at: u64,
+}
+
+impl Attachment {
+    /// 发送给 bridge 的形式
+    fn for_bridge(&self) -> Value {
+        serde_json::json!({
+            "id": self.id,
+            "name": self.name,
+            "kind": self.kind,
+            "path": self.path,
+            "meta": self.meta,
+        })
+    }
+}
+
+fn read_head(path: &Path) -> Result<Vec<u8>, String> {
+    let mut file = std::fs::File::open(path).map_err(|e| format!("读取附件失败: {}", e))?;
+    let mut buf = Vec::with_capacity(SNIFF_BYTES);
+    file.by_ref()
+        .take(SNIFF_BYTES as u64)
+        .read_to_end(&mut buf)
+        .map_err(|e| format!("读取附件失败: {}", e))?;
+    Ok(buf)
+}
+
+/// 按扩展名定类型，再用文件头校验，拒绝改了扩展名的文件
+fn classify(path: &Path, head: &[u8]) -> Result<AttachmentKind, String> {
+    let ext = path
+        .extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    let kind = AttachmentKind::from_ext(&ext)
+        .ok_or_else(|| format!("不支持的附件类型: {}", path.display()))?;
+    let valid = match kind {
+        AttachmentKind::Pdf => head.starts_with(b"%PDF-"),
+        AttachmentKind::Image => image_size(head).is_some() || ext == "bmp" || ext == "webp",
+        AttachmentKind::Csv | AttachmentKind::Text => !head.contains(&0),
+    };
+    if !valid {
+        return Err(format!(
+            "附件内容与扩展名 .{} 不符: {}",
+            ext,
+            path.display()
+        ));
+    }
+    Ok(kind)
+}
+
+/// PNG / JPEG / GIF 的像素尺寸
+fn image_size(head: &[u8]) -> Option<(u32, u32)> {
+    if head.starts_with(b"\x89PNG\r\n\x1a\n") && head.len() >= 24 {
+        let w = u32::from_be_bytes(head[16..20].try_into().ok()?);
+        let h = u32::from_be_bytes(head[20..24].try_into().ok()?);
+        return Some((w, h));
+    }
+    if (head.starts_with(b"GIF87a") || head.starts_with(b"GIF89a")) && head.len() >= 10 {
+        let w = u16::from_le_bytes([head[6], head[7]]) as u32;
+        let h = u16::from_le_bytes([head[8], head[9]]) as u32;
+        return Some((w, h));
+    }
+    if head.starts_with(&[0xFF, 0xD8]) {
+        // 逐段查找 SOF 标记
+        let mut i = 2;
+        while i + 9 < head.len() {
+            if head[i] != 0xFF {
+                return None;
+            }
+            let marker = head[i + 1];
+            let len = u16::from_be_bytes([head[i + 2], head[i + 3]]) as usize;
+            if matches!(marker, 0xC0..=0xC3 | 0xC5..=0xC7 | 0xC9..=0xCB | 0xCD..=0xCF) {
+                let h = u16::from_be_bytes([head[i + 5], head[i + 6]]) as u32;
+                let w = u16::from_be_bytes([head[i + 7], head[i + 8]]) as u32;
+                return Some((w, h));
+            }
+            i += 2 + len;
+        }
+    }
+    None
+}
+
+fn csv_meta(path: &Path, head: &[u8]) -> Value {
+    let text = String::from_utf8_lossy(head);
+    let first = text.lines().next().unwrap_or_default();
+    let delimiter = [',', '\t', ';']
+        .into_iter()
+        .max_by_key(|d| first.matches(*d).count())
+        .unwrap_or(',');
+    let columns: Vec<&str> = first
+        .split(delimiter)
+        .map(|c| c.trim().trim_matches('"'))
+        .take(MAX_COLUMNS)
+        .collect();
+    // 行数需要扫完整个文件，只数换行符
+    let rows = std::fs::File::open(path).ok().map(|f| {
+        let mut reader = std::io::BufReader::new(f);
+        let mut buf = [0u8; 64 * 1024];
+        let mut n = 0usize;
+        while let Ok(read) = reader.read(&mut buf) {
+            if read == 0 {
+                break;
+            }
+            n += buf[..read].iter().filter(|b| **b == b'\n').count();
+        }
+        n
+    });
+    serde_json::json!({
+        "delimiter": delimiter.to_string(),
+        "columns": columns,
+        "rows": rows,
+    })
+}
+
+/// PDF 页数按 `/Type /Page` 对象计数（不含 `/Pages`），不做完整解析；
+/// 页面对象位于压缩对象流中时数不到，返回 null
+fn pdf_meta(path: &Path) -> Value {
+    let pages = std::fs::read(path).ok().and_then(|data| {
+        let mut n = 0usize;
+        let mut rest = data.as_slice();
+        while let Some(pos) = find(rest, b"/Type") {
+            rest = &rest[pos + 5..];
+            let skip = rest.iter().take_while(|b| b.is_ascii_whitespace()).count();
+            let after = &rest[skip..];
+            if after.starts_with(b"/Page") && after.get(5) != Some(&b's') {
+                n += 1;
+            }
+        }
+        (n > 0).then_some(n)
+    });
+    serde_json::json!({ "pages": pages })
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|w| w == needle)
+}
+
+fn extract_meta(kind: AttachmentKind, path: &Path, head: &[u8]) -> Value {
+    match kind {
+        AttachmentKind::Pdf => pdf_meta(path),
+        AttachmentKind::Csv => csv_meta(path, head),
+        AttachmentKind::Image => match image_size(head) {
+            Some((w, h)) => serde_json::json!({ "width": w, "height": h }),
+            None => Value::Null,
+        },
+        AttachmentKind::Text => serde_json::json!({
+            "lines": String::from_utf8_lossy(head).lines().count(),
+            "truncated": head.len() >= SNIFF_BYTES,
+        }),
+    }
+}
+
+/// 入库前的校验：沙箱、类型与大小；返回类型、大小与文件头
+fn inspect(src: &Path) -> Result<(AttachmentKind, u64, Vec<u8>), String> {
+    check_path(src)?;
+    let size = std::fs::metadata(src)
+        .ok()
+        .filter(|m| m.is_file())
+        .ok_or_else(|| format!("文件不存在: {}", src.display()))?
+        .len();
+    let head = read_head(src)?;
+    let kind = classify(src, &head)?;
+    if size > kind.max_bytes() {
+        return Err(format!(
+            "附件过大（{} MB，上限 {} MB）: {}",
+            size / (1024 * 1024),
+            kind.max_bytes() / (1024 * 1024),
+            src.display()
+        ));
+    }
+    Ok((kind, size, head))
+}
+
+/// 项目名可能是任意字符串或目录路径，取哈希作为目录名
+fn project_dir(project: Option<&str>) -> String {
+    match project.map(str::trim).filter(|p| !p.is_empty()) {
+        None => DEFAULT_PROJECT_DIR.to_string(),
+        Some(p) => {
+            let digest = Sha256::digest(p.as_bytes());
+            digest[..6].iter().map(|b| format!("{:02x}", b)).collect()
+        }
+    }
+}
+
+/// 去掉原名中的路径分隔符、保留字符与设备名
+fn safe_name(name: &str) -> String {
+    sanitize_file_name(name, "attachment").name
+}
+
+/// 提示词附件库：用户附上的数据手册、边界数据与图片复制到 app data 下按项目存放，
+/// 请求中只传入库后的稳定路径，原文件之后被移动或删除也不影响任务与复现
+#[derive(Default)]
+pub struct AttachmentStore {
+    dir: Option<PathBuf>,
+    items: Mutex<Vec<Attachment>>,
+}
+
+impl AttachmentStore {
+    pub fn load(dir: PathBuf) -> Self {
+        let items: Vec<Attachment> = load_json(&dir.join("index.json"));
+        Self {
+            dir: Some(dir),
+            items: Mutex::new(items),
+        }
+    }
+
+    fn update(&self, f: impl FnOnce(&mut Vec<Attachment>)) {
+        let mut items = self.items.lock().unwrap_or_else(|e| e.into_inner());
+        f(&mut items);
+        if let Some(ref dir) = self.dir {
+            if let Err(e) = save_json(&dir.join("index.json"), &*items) {
+                eprintln!("Warning: 保存附件索引失败: {}", e);
+            }
+        }
+    }
+
+    pub fn get(&self, id: &str) -> Option<Attachment> {
+        self.items
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .iter()
+            .find(|a| a.id == id)
+            .cloned()
+    }
+
+    pub fn list(&self, project: Option<&str>) -> Vec<Attachment> {
+        let items = self.items.lock().unwrap_or_else(|e| e.into_inner());
+        let mut out: Vec<Attachment> = items
+            .iter()
+            .filter(|a| project.is_none() || a.project.as_deref() == project)
+            .cloned()
+            .collect();
+        out.sort_by_key(|a| Reverse(a.added_at));
+        out
+    }
+
+    /// 校验并复制一个文件；同一项目内内容相同的文件只保存一份
+    pub fn ingest(&self, source: &str, project: Option<&str>) -> Result<Attachment, String> {
+        let dir = self.dir.as_ref().ok_or("附件目录不可用")?;
+        let src = Path::new(source.trim());
+        let (kind, size, head) = inspect(src)?;
+        let sha256 = sha256_file(src)?;
+        let project = project.map(str::trim).filter(|p| !p.is_empty());
+        let bucket = project_dir(project);
+        // 同一内容在不同项目中各存一份，id 由项目与内容共同决定
+        let id: String = Sha256::digest(format!("{}:{}", bucket, sha256).as_bytes())[..8]
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect();
+        if let Some(existing) = self.get(&id) {
+            if Path::new(&existing.path).is_file() {
+                return Ok(existing);
+            }
+        }
+
+        let name = src
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "attachment".to_string());
+        let target_dir = dir.join(bucket);
+        std::fs::create_dir_all(&target_dir).map_err(|e| format!("创建附件目录失败: {}", e))?;
+        let dest = target_dir.join(format!("{}-{}", id, safe_name(&name)));
+        std::fs::copy(src, &dest).map_err(|e| format!("复制附件失败: {}", e))?;
+
+        let attachment = Attachment {
+            id,
+            name,
+            kind,
+            path: dest.to_string_lossy().into_owned(),
+            source: src.to_string_lossy().into_owned(),
+            size,
+            sha256,
+            project: project.map(String::from),
+            meta: extract_meta(kind, &dest, &head),
+            added_at: now_ms(),
+        };
+        self.update(|items| {
+            items.retain(|a| a.id != attachment.id);
+            items.push(attachment.clone());
+        });
+        Ok(attachment)
+    }
+
+    fn remove(&self, id: &str) -> Result<Attachment, String> {
+        let removed = self.get(id).ok_or_else(|| format!("未找到附件: {}", id))?;
+        let _ = std::fs::remove_file(&removed.path);
+        self.update(|items| items.retain(|a| a.id != id));
+        Ok(removed)
+    }
+}
+
+/// 请求中 `attachments` 的各项：用户选择的路径或已入库附件的 id
+fn attachment_refs(payload: &Value) -> Result<Option<Vec<String>>, String> {
+    let Some(list) = payload.get(ATTACHMENTS_KEY).and_then(|v| v.as_array()) else {
+        return Ok(None);
+    };
+    list.iter()
+        .map(|v| match v {
+            Value::String(s) => Ok(s.clone()),
+            Value::Object(o) => o
+                .get("id")
+                .or_else(|| o.get("path"))
+                .and_then(|v| v.as_str())
+                .map(String::from)
+                .ok_or_else(|| "附件需要 id 或 path".to_string()),
+            _ => Err("附件需要 id 或 path".to_string()),
+        })
+        .collect::<Result<_, _>>()
+        .map(Some)
+}
+
+/// 只校验不入库：已入库的 id 换成入库记录，路径换成待入库的预览（含元数据）
+pub fn preview_payload(app: &AppHandle, payload: &mut Value) -> Result<(), String> {
+    let Some(refs) = attachment_refs(payload)? else {
+        return Ok(());
+    };
+    let store = app.state::<AttachmentStore>();
+    let mut out = Vec::with_capacity(refs.len());
+    for r in &refs {
+        if let Some(a) = store.get(r) {
+            out.push(a.for_bridge());
+            continue;
+        }
+        let src = Path::new(r.trim());
+        let (kind, size, head) = inspect(src)?;
+        out.push(serde_json::json!({
+            "name": src.file_name().map(|n| n.to_string_lossy().into_owned()),
+            "kind": kind,
+            "source": r.trim(),
+            "size": size,
+            "meta": extract_meta(kind, src, &head),
+            "pending": true,
+        }));
+    }
+    payload[ATTACHMENTS_KEY] = Value::Array(out);
+    Ok(())
+}
+
+/// 发送前处理请求中的 `attachments`：路径入库、id 换成入库记录，
+/// 替换为 `[{id, name, kind, path, meta}]` 交给 bridge
+pub async fn ingest_payload(app: &AppHandle, payload: &mut Value) -> Result<(), String> {
+    let Some(refs) = attachment_refs(payload)? else {
+        return Ok(());
+    };
+    let project = crate::jobs::project_of(payload);
+    let app = app.clone();
+    let stored = tauri::async_runtime::spawn_blocking(move || {
+        let store = app.state::<AttachmentStore>();
+        refs.iter()
+            .map(|r| match store.get(r) {
+                Some(a) => Ok(a),
+                None => store.ingest(r, project.as_deref()),
+            })
+            .collect::<Result<Vec<_>, String>>()
+    })
+    .await
+    .map_err(|e| e.to_string())??;
+    payload[ATTACHMENTS_KEY] = Value::Array(stored.iter().map(Attachment::for_bridge).collect());
+    Ok(())
+}
+
+/// 校验并复制附件到项目的附件库，返回入库记录（含元数据与稳定路径）
+#[tauri::command]
+pub async fn attachments_ingest(
+    app: AppHandle,
+    paths: Vec<String>,
+    project: Option<String>,
+) -> Result<Vec<Attachment>, String> {
+    guarded("attachments_ingest", async move {
+        tauri::async_runtime::spawn_blocking(move || {
+            let store = app.state::<AttachmentStore>();
+            paths
+                .iter()
+                .map(|p| store.ingest(p, project.as_deref()))
+                .collect()
+        })
+        .await
+        .map_err(|e| e.to_string())?
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn attachments_list(
+    store: tauri::State<'_, AttachmentStore>,
+    project: Option<String>,
+) -> Result<Vec<Attachment>, String> {
+    guarded("attachments_list", async move {
+        Ok(store.list(project.as_deref()))
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn attachment_remove(
+    store: tauri::State<'_, AttachmentStore>,
+    id: String,
+) -> Result<Attachment, String> {
+    guarded("attachment_remove", async move { store.remove(id.trim()) }).await
+}
@@ -0,0 +1,270 @@
+use crate::abort::AbortReport;
+use crate::bridge::StdioBridge;
+use crate::bridge_error::BridgeError;
+use crate::panics::guarded;
+use crate::remote::{self, RemoteBridge};
+use crate::sessions;
+use serde::Serialize;
+use serde_json::Value;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::Duration;
+use tauri::AppHandle;
+
+pub type BridgeFuture<'a, T> = Pin<Box<dyn Future<Output = Result<T, BridgeError>> + Send + 'a>>;
+
+/// bridge 当前所处的阶段
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Liveness {
+    /// 尚未启动或已按关闭流程退出，下一次请求会按需启动
+    Stopped,
+    Starting,
+    Ready,
+    /// 请求超时等原因判定为不可靠，下一次请求前重启
+    Unhealthy,
+    /// 进程意外退出
+    Exited,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct BridgeStatus {
+    pub liveness: Liveness,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+    /// 在途的请求数（含流式请求）
+    pub in_flight: usize,
+    pub active_streams: usize,
+}
+
+/// 命令层与 bridge 之间的接口：bridge_send / bridge_send_stream / bridge_abort 只经由它收发，
+/// 不关心 bridge 是本机子进程还是其他传输方式。实现有 stdio 子进程（`StdioBridge`）
+/// 与经 TCP/WebSocket 或 ssh 连接的远程 bridge（`RemoteBridge`）
+pub trait Bridge: Send + Sync {
+    /// 发送一条非流式请求，在 `timeout` 内等待响应；payload 已经过命令层的检查与改写
+    fn send(&self, cmd: &str, payload: Value, timeout: Duration) -> BridgeFuture<'_, Value>;
+
+    /// 以流式请求执行一个任务：登记任务、转发事件，结束后返回最终响应
+    fn send_stream(
+        &self,
+        cmd: String,
+        payload: Value,
+        request_id: Option<String>,
+    ) -> BridgeFuture<'_, Value>;
+
+    /// 中止正在执行的命令
+    fn abort(&self) -> BridgeFuture<'_, AbortReport>;
+
+    /// 当前状态，不发送请求
+    fn health(&self) -> BridgeFuture<'_, BridgeStatus>;
+}
+
+/// 按会话取 bridge；未指定会话时为默认会话。设置了 `remote` 时为远程 bridge
+pub fn resolve(app: &AppHandle, session: Option<&str>) -> Result<Arc<dyn Bridge>, String> {
+    let state = sessions::resolve(app, session)?;
+    if remote::is_active() || sessions::is_remote(app, session) {
+        return Ok(Arc::new(RemoteBridge::new(app.clone(), state, session)));
+    }
+    Ok(Arc::new(StdioBridge::new(app.clone(), state, session)))
+}
+
+/// 正在启动（含冷启动 venv 与重启）时立即返回 `BridgeStarting:` 错误，
+/// 前端可据此显示「正在启动」并重试，而不是让请求阻塞到握手超时
+pub async fn ensure_not_starting(bridge: &dyn Bridge) -> Result<(), BridgeError> {
+    if bridge.health().await?.liveness == Liveness::Starting {
+        return Err(BridgeError::starting());
+    }
+    Ok(())
+}
+
+/// bridge 当前状态（启动中、就绪、异常、已退出）与在途请求数，不发送请求
+#[tauri::command]
+pub async fn bridge_health(
+    app: AppHandle,
+    session: Option<String>,
+) -> Result<BridgeStatus, BridgeError> {
+    guarded("bridge_health", async move {
+        let bridge = resolve(&app, session.as_deref())?;
+        bridge.health().await
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    fn block_on<F: std::future::Future>(future: F) -> F::Output {
+        tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap()
+            .block_on(future)
+    }
+
+    /// 按预设结果应答的 bridge，记录收到的命令
+    struct ScriptedBridge {
+        liveness: Liveness,
+        send: Result<Value, BridgeError>,
+        abort: Result<AbortReport, BridgeError>,
+        sent: Mutex<Vec<String>>,
+    }
+
+    impl ScriptedBridge {
+        fn new(liveness: Liveness) -> Self {
+            Self {
+                liveness,
+                send: Ok(serde_json::json!({ "ok": true })),
+                abort: Ok(AbortReport::default()),
+                sent: Mutex::new(Vec::new()),
+            }
+        }
+
+        fn failing(error: BridgeError) -> Self {
+            Self {
+                send: Err(error.clone()),
+                abort: Err(error),
+                ..Self::new(Liveness::Ready)
+            }
+        }
+    }
+
+    impl Bridge for ScriptedBridge {
+        fn send(&self, cmd: &str, _payload: Value, _timeout: Duration) -> BridgeFuture<'_, Value> {
+            if cmd == "panic" {
+                panic!("scripted");
+            }
+            self.sent
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .push(cmd.to_string());
+            let result = self.send.clone();
+            Box::pin(async move { result })
+        }
+
+        fn send_stream(
+            &self,
+            cmd: String,
+            payload: Value,
+            _request_id: Option<String>,
+        ) -> BridgeFuture<'_, Value> {
+            self.send(&cmd, payload, Duration::ZERO)
+        }
+
+        fn abort(&self) -> BridgeFuture<'_, AbortReport> {
+            let result = self.abort.clone();
+            Box::pin(async move { result })
+        }
+
+        fn health(&self) -> BridgeFuture<'_, BridgeStatus> {
+            let status = BridgeStatus {
+                liveness: self.liveness,
+                detail: None,
+                in_flight: 0,
+                active_streams: 0,
+            };
+            Box::pin(async move { Ok(status) })
+        }
+    }
+
+    /// 与 bridge_send 相同的流程：启动中先拒绝，再经 `guarded` 发送
+    fn send(bridge: &ScriptedBridge, cmd: &str) -> Result<Value, BridgeError> {
+        block_on(guarded("bridge_send", async {
+            ensure_not_starting(bridge).await?;
+            bridge.send(cmd, Value::Null, Duration::from_secs(1)).await
+        }))
+    }
+
+    fn kind(error: &BridgeError) -> Value {
+        serde_json::to_value(error).unwrap()["kind"].clone()
+    }
+
+    #[test]
+    fn starting_bridge_rejects_send_with_retry_hint() {
+        let bridge = ScriptedBridge::new(Liveness::Starting);
+        let err = send(&bridge, "status").unwrap_err();
+        let json = serde_json::to_value(&err).unwrap();
+        assert_eq!(json["kind"], "starting");
+        assert_eq!(json["retry_after_ms"], 1000);
+        assert!(bridge.sent.lock().unwrap().is_empty());
+
+        for liveness in [
+            Liveness::Stopped,
+            Liveness::Ready,
+            Liveness::Unhealthy,
+            Liveness::Exited,
+        ] {
+            let bridge = ScriptedBridge::new(liveness);
+            assert_eq!(send(&bridge, "status").unwrap()["ok"], true);
+            assert_eq!(*bridge.sent.lock().unwrap(), ["status"]);
+        }
+    }
+
+    #[test]
+    fn send_and_abort_errors_keep_their_kind() {
+        let cases = [
+            (BridgeError::not_initialized(), "not_initialized"),
+            (
+                BridgeError::Timeout {
+                    message: "BridgeTimeout".to_string(),
+                },
+                "timeout",
+            ),
+            (
+                BridgeError::ProtocolError {
+                    message: "ProtocolError".to_string(),
+                },
+                "protocol_error",
+            ),
+            (
+                BridgeError::ChildExited {
+                    code: Some(1),
+                    message: "BridgeDead".to_string(),
+                },
+                "child_exited",
+            ),
+            (
+                BridgeError::Aborted {
+                    message: "已中止".to_string(),
+                },
+                "aborted",
+            ),
+            (BridgeError::from("命令被策略拦截"), "rejected"),
+        ];
+        for (error, expected) in cases {
+            let bridge = ScriptedBridge::failing(error.clone());
+            let sent = send(&bridge, "status").unwrap_err();
+            assert_eq!(kind(&sent), expected);
+            assert_eq!(sent.message(), error.message());
+
+            let aborted = block_on(guarded("bridge_abort", bridge.abort())).unwrap_err();
+            assert_eq!(kind(&aborted), expected);
+            assert_eq!(sent.message(), aborted.message());
+        }
+    }
+
+    #[test]
+    fn abort_report_carries_restart_error() {
+        let mut bridge = ScriptedBridge::new(Liveness::Ready);
+        bridge.abort = Ok(AbortReport {
+            restarted: false,
+            error: Some(BridgeError::spawn_failed("握手失败".to_string())),
+            ..Default::default()
+        });
+        let report = block_on(guarded("bridge_abort", bridge.abort())).unwrap();
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["error"]["kind"], "spawn_failed");
+        assert_eq!(json["error"]["message"], "握手失败");
+    }
+
+    #[test]
+    fn panicking_bridge_is_rejected() {
+        let bridge = ScriptedBridge::new(Liveness::Ready);
+        let err = send(&bridge, "panic").unwrap_err();
+        assert_eq!(kind(&err), "rejected");
+        assert!(err.message().contains("bridge_send"));
+        assert!(err.message().contains("scripted"));
+    }
+}
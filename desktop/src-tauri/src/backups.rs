@@ -0,0 +1,367 @@
+use crate::artifacts::ArtifactRegistry;
+use crate::panics::guarded;
+use crate::policy::CommandPolicy;
+use crate::sandbox::check_path;
+use crate::store::{load_json, now_ms, save_json};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::cmp::Reverse;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager};
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BackupPolicy {
+    /// 每个模型最多保留的备份份数
+    pub max_count: usize,
+    /// 每个模型备份的总大小上限（MB）；最新一份始终保留
+    pub max_total_mb: u64,
+}
+
+impl Default for BackupPolicy {
+    fn default() -> Self {
+        Self {
+            max_count: 5,
+            max_total_mb: 2048,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BackupEntry {
+    /// 被备份的原文件
+    pub source: String,
+    /// 备份文件
+    pub path: String,
+    pub size: u64,
+    pub created_at: u64,
+    #[serde(default)]
+    pub request_id: Option<String>,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+struct BackupIndex {
+    #[serde(default)]
+    policy: BackupPolicy,
+    #[serde(default)]
+    entries: Vec<BackupEntry>,
+}
+
+/// 覆盖前自动备份：备份文件放在 app data 的 backups 目录，按模型轮转
+#[derive(Default)]
+pub struct BackupManager {
+    dir: Option<PathBuf>,
+    index: Mutex<BackupIndex>,
+}
+
+impl BackupManager {
+    pub fn load(dir: PathBuf) -> Self {
+        let index: BackupIndex = load_json(&dir.join("index.json"));
+        Self {
+            dir: Some(dir),
+            index: Mutex::new(index),
+        }
+    }
+
+    fn save(&self, index: &BackupIndex) {
+        if let Some(ref dir) = self.dir {
+            if let Err(e) = save_json(&dir.join("index.json"), index) {
+                eprintln!("Warning: 保存备份索引失败: {}", e);
+            }
+        }
+    }
+
+    pub fn policy(&self) -> BackupPolicy {
+        self.index
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .policy
+            .clone()
+    }
+
+    pub fn set_policy(&self, policy: BackupPolicy) {
+        let mut index = self.index.lock().unwrap_or_else(|e| e.into_inner());
+        index.policy = policy;
+        let mut sources: Vec<String> = index.entries.iter().map(|e| e.source.clone()).collect();
+        sources.sort();
+        sources.dedup();
+        for s in sources {
+            rotate(&mut index, &s);
+        }
+        self.save(&index);
+    }
+
+    /// 把 `source` 复制一份到备份目录并按策略轮转
+    pub fn backup(&self, source: &str, request_id: Option<&str>) -> Result<BackupEntry, String> {
+        let dir = self.dir.as_ref().ok_or("备份目录不可用")?.join("files");
+        std::fs::create_dir_all(&dir).map_err(|e| format!("创建备份目录失败: {}", e))?;
+        let src = Path::new(source);
+        let stem = src
+            .file_stem()
+            .map(|s| s.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "model".to_string());
+        let ext = src
+            .extension()
+            .map(|s| format!(".{}", s.to_string_lossy()))
+            .unwrap_or_default();
+        let created_at = now_ms();
+        // 同一毫秒内的多次备份（如恢复紧接在覆盖之后）加序号区分，不能互相覆盖
+        let mut dest = dir.join(format!("{}-{}{}", stem, created_at, ext));
+        let mut n = 1;
+        while dest.exists() {
+            dest = dir.join(format!("{}-{}-{}{}", stem, created_at, n, ext));
+            n += 1;
+        }
+        let size = std::fs::copy(src, &dest).map_err(|e| format!("备份 {} 失败: {}", source, e))?;
+
+        let entry = BackupEntry {
+            source: source.to_string(),
+            path: dest.to_string_lossy().into_owned(),
+            size,
+            created_at,
+            request_id: request_id.map(String::from),
+        };
+        let mut index = self.index.lock().unwrap_or_else(|e| e.into_inner());
+        index.entries.push(entry.clone());
+        rotate(&mut index, source);
+        self.save(&index);
+        Ok(entry)
+    }
+
+    /// 某模型的备份，最新的在前（下标 0）；`source` 为空时列出全部
+    pub fn list(&self, source: Option<&str>) -> Vec<BackupEntry> {
+        let index = self.index.lock().unwrap_or_else(|e| e.into_inner());
+        // 先逆序再稳定排序：同一毫秒的备份以后加入的为新
+        let mut out: Vec<BackupEntry> = index
+            .entries
+            .iter()
+            .rev()
+            .filter(|e| source.is_none() || Some(e.source.as_str()) == source)
+            .cloned()
+            .collect();
+        out.sort_by_key(|e| Reverse(e.created_at));
+        out
+    }
+
+    /// 用第 `index` 份备份恢复模型；恢复前先备份当前文件，避免误操作丢失。
+    /// 备份当前文件会触发轮转，可能删掉要恢复的这份，所以先把它复制到模型旁的临时文件
+    pub fn restore(&self, source: &str, index: usize) -> Result<BackupEntry, String> {
+        let entry = self
+            .list(Some(source))
+            .into_iter()
+            .nth(index)
+            .ok_or_else(|| format!("{} 没有第 {} 份备份", source, index))?;
+        let target = Path::new(source);
+        let mut name = target.file_name().unwrap_or_default().to_os_string();
+        name.push(".restore");
+        let staged = target.with_file_name(name);
+        std::fs::copy(&entry.path, &staged).map_err(|e| format!("恢复备份失败: {}", e))?;
+        let current = if target.is_file() {
+            self.backup(source, None).map(|_| ())
+        } else {
+            Ok(())
+        };
+        let restored = current.and_then(|_| {
+            std::fs::rename(&staged, target).map_err(|e| format!("恢复备份失败: {}", e))
+        });
+        if restored.is_err() {
+            let _ = std::fs::remove_file(&staged);
+        }
+        restored.map(|_| entry)
+    }
+}
+
+/// 超出份数或总大小的旧备份连同文件一起删除
+fn rotate(index: &mut BackupIndex, source: &str) {
+    let max_count = index.policy.max_count.max(1);
+    let max_bytes = index.policy.max_total_mb.saturating_mul(1024 * 1024);
+    let mut own: Vec<&BackupEntry> = index
+        .entries
+        .iter()
+        .rev()
+        .filter(|e| e.source == source)
+        .collect();
+    own.sort_by_key(|e| Reverse(e.created_at));
+
+    let mut total = 0u64;
+    let mut expired = Vec::new();
+    for (i, e) in own.iter().enumerate() {
+        total += e.size;
+        if i > 0 && (i >= max_count || total > max_bytes) {
+            expired.push(e.path.clone());
+        }
+    }
+    for p in &expired {
+        if let Err(e) = std::fs::remove_file(p) {
+            eprintln!("Warning: 删除旧备份 {} 失败: {}", p, e);
+        }
+    }
+    index.entries.retain(|e| !expired.contains(&e.path));
+}
+
+/// 请求将覆盖已有模型时先做备份；备份失败则拒绝执行，避免覆盖后无法找回
+pub async fn backup_before_write(
+    app: &AppHandle,
+    payload: &Value,
+    request_id: Option<&str>,
+) -> Result<(), String> {
+    let targets = app.state::<ArtifactRegistry>().overwrite_targets(payload);
+    if targets.is_empty() {
+        return Ok(());
+    }
+    let app = app.clone();
+    let request_id = request_id.map(String::from);
+    tauri::async_runtime::spawn_blocking(move || {
+        let backups = app.state::<BackupManager>();
+        for t in targets {
+            backups.backup(&t, request_id.as_deref())?;
+        }
+        Ok(())
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+pub async fn backups_list(
+    backups: tauri::State<'_, BackupManager>,
+    path: Option<String>,
+) -> Result<Vec<BackupEntry>, String> {
+    guarded(
+        "backups_list",
+        async move { Ok(backups.list(path.as_deref())) },
+    )
+    .await
+}
+
+#[tauri::command]
+pub async fn restore_backup(
+    backups: tauri::State<'_, BackupManager>,
+    artifacts: tauri::State<'_, ArtifactRegistry>,
+    policy: tauri::State<'_, CommandPolicy>,
+    path: String,
+    index: usize,
+) -> Result<BackupEntry, String> {
+    guarded("restore_backup", async move {
+        policy.ensure_writable("恢复备份")?;
+        let path = path.trim();
+        check_path(Path::new(path))?;
+        let entry = backups.restore(path, index)?;
+        // 恢复的是用户认可的版本，以它为新的比对基准
+        if artifacts.is_tracked(path) {
+            artifacts.confirm_overwrite(path)?;
+        }
+        Ok(entry)
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn backup_get_policy(
+    backups: tauri::State<'_, BackupManager>,
+) -> Result<BackupPolicy, String> {
+    guarded("backup_get_policy", async move { Ok(backups.policy()) }).await
+}
+
+#[tauri::command]
+pub async fn backup_set_policy(
+    backups: tauri::State<'_, BackupManager>,
+    max_count: usize,
+    max_total_mb: u64,
+) -> Result<(), String> {
+    guarded("backup_set_policy", async move {
+        if max_count == 0 {
+            return Err("备份份数至少为 1".to_string());
+        }
+        backups.set_policy(BackupPolicy {
+            max_count,
+            max_total_mb,
+        });
+        Ok(())
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "mph-backups-{}-{}-{}",
+            name,
+            std::process::id(),
+            now_ms()
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn entry(path: &str, size: u64, created_at: u64) -> BackupEntry {
+        BackupEntry {
+            source: "model.mph".into(),
+            path: path.into(),
+            size,
+            created_at,
+            request_id: None,
+        }
+    }
+
+    #[test]
+    fn rotate_keeps_newest_by_count_and_size() {
+        let mut index = BackupIndex {
+            policy: BackupPolicy {
+                max_count: 2,
+                max_total_mb: 1,
+            },
+            entries: vec![entry("a", 100, 1), entry("b", 100, 2), entry("c", 100, 3)],
+        };
+        rotate(&mut index, "model.mph");
+        let kept: Vec<&str> = index.entries.iter().map(|e| e.path.as_str()).collect();
+        assert_eq!(kept, vec!["b", "c"]);
+
+        // 最新一份即使超过总大小上限也保留
+        index.entries.push(entry("d", 2 << 20, 4));
+        rotate(&mut index, "model.mph");
+        let kept: Vec<&str> = index.entries.iter().map(|e| e.path.as_str()).collect();
+        assert_eq!(kept, vec!["d"]);
+    }
+
+    #[test]
+    fn rotate_prefers_later_entry_on_same_timestamp() {
+        let mut index = BackupIndex {
+            policy: BackupPolicy {
+                max_count: 1,
+                max_total_mb: 1,
+            },
+            entries: vec![entry("old", 1, 5), entry("new", 1, 5)],
+        };
+        rotate(&mut index, "model.mph");
+        assert_eq!(index.entries.len(), 1);
+        assert_eq!(index.entries[0].path, "new");
+    }
+
+    #[test]
+    fn restore_survives_rotation_of_restored_backup() {
+        let dir = temp_dir("restore");
+        let backups = BackupManager::load(dir.join("backups"));
+        backups.set_policy(BackupPolicy {
+            max_count: 1,
+            max_total_mb: 2048,
+        });
+        let model = dir.join("model.mph");
+        let source = model.to_string_lossy().into_owned();
+        std::fs::write(&model, "v1").unwrap();
+        backups.backup(&source, None).unwrap();
+        std::fs::write(&model, "v2").unwrap();
+
+        backups.restore(&source, 0).unwrap();
+        assert_eq!(std::fs::read_to_string(&model).unwrap(), "v1");
+        // 唯一保留的备份是恢复前的当前版本
+        let list = backups.list(Some(&source));
+        assert_eq!(list.len(), 1);
+        assert_eq!(std::fs::read_to_string(&list[0].path).unwrap(), "v2");
+        assert!(!dir.join("model.mph.restore").exists());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,173 @@
+use crate::bridge::{send_request, BridgeState};
+use crate::bridge_error::BridgeError;
+use crate::jobs::JobRegistry;
+use crate::panics::guarded;
+use crate::store::now_ms;
+use serde::Serialize;
+use serde_json::json;
+use std::time::Instant;
+use tauri::{AppHandle, Emitter};
+
+const DEFAULT_ITERATIONS: u32 = 50;
+const MAX_ITERATIONS: u32 = 1000;
+/// 吞吐量测试的默认载荷大小（字节）；传入 payload_size 时追加该档
+const DEFAULT_SIZES: &[usize] = &[1024, 64 * 1024, 1024 * 1024];
+const MAX_PAYLOAD: usize = 16 * 1024 * 1024;
+/// 大载荷档位的往返次数上限，避免测速本身占用太久
+const MAX_THROUGHPUT_ROUNDS: u32 = 20;
+const EVENT_NAME: &str = "bridge-benchmark";
+const EVENT_COUNT: u32 = 2000;
+
+#[derive(Clone, Debug, Serialize)]
+pub struct LatencyStats {
+    pub samples: u32,
+    pub min_ms: f64,
+    pub mean_ms: f64,
+    pub p50_ms: f64,
+    pub p95_ms: f64,
+    pub max_ms: f64,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ThroughputResult {
+    pub payload_bytes: usize,
+    pub rounds: u32,
+    pub latency: LatencyStats,
+    /// 请求与响应字节合计 / 总耗时
+    pub mb_per_sec: f64,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct EventRate {
+    pub events: u32,
+    pub elapsed_ms: f64,
+    pub events_per_sec: f64,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct BenchmarkReport {
+    /// 当前传输方式；不同传输在同一台机器上的报告可直接对比
+    pub transport: &'static str,
+    pub iterations: u32,
+    pub ping: LatencyStats,
+    pub throughput: Vec<ThroughputResult>,
+    pub events: EventRate,
+    pub started_at: u64,
+    pub elapsed_ms: f64,
+}
+
+fn stats(mut samples: Vec<f64>) -> LatencyStats {
+    samples.sort_by(|a, b| a.total_cmp(b));
+    let n = samples.len();
+    let at = |q: f64| samples[((n as f64 - 1.0) * q).round() as usize];
+    LatencyStats {
+        samples: n as u32,
+        min_ms: samples[0],
+        mean_ms: samples.iter().sum::<f64>() / n as f64,
+        p50_ms: at(0.5),
+        p95_ms: at(0.95),
+        max_ms: samples[n - 1],
+    }
+}
+
+fn ms_since(t: Instant) -> f64 {
+    t.elapsed().as_secs_f64() * 1000.0
+}
+
+/// 单次往返；bridge 返回 ok=false 也视为完成了一次往返，但 echo 内容不符时报错
+async fn roundtrip(state: &BridgeState, cmd: &str, data: &str) -> Result<(f64, usize), String> {
+    let start = Instant::now();
+    let resp = send_request(state, cmd, json!({ "data": data })).await?;
+    let elapsed = ms_since(start);
+    if cmd == "echo" && resp["data"].as_str().map(str::len) != Some(data.len()) {
+        return Err(format!(
+            "bridge echo 返回内容不一致：{}",
+            resp["message"].as_str().unwrap_or("缺少 data 字段")
+        ));
+    }
+    let resp_bytes = serde_json::to_string(&resp).map(|s| s.len()).unwrap_or(0);
+    Ok((elapsed, data.len() + resp_bytes))
+}
+
+/// 测量 bridge 往返延迟、不同载荷的吞吐量与前端事件推送速率
+#[tauri::command]
+pub async fn bridge_benchmark(
+    app: AppHandle,
+    state: tauri::State<'_, BridgeState>,
+    jobs: tauri::State<'_, JobRegistry>,
+    iterations: Option<u32>,
+    payload_size: Option<usize>,
+) -> Result<BenchmarkReport, BridgeError> {
+    guarded("bridge_benchmark", async move {
+        if let Some(job) = jobs.running() {
+            return Err(format!("任务 {} 正在运行，请稍后再测速", job.cmd).into());
+        }
+        let iterations = iterations
+            .unwrap_or(DEFAULT_ITERATIONS)
+            .clamp(1, MAX_ITERATIONS);
+        let mut sizes = DEFAULT_SIZES.to_vec();
+        if let Some(size) = payload_size {
+            if size > MAX_PAYLOAD {
+                return Err(format!("载荷不能超过 {} MB", MAX_PAYLOAD / 1024 / 1024).into());
+            }
+            if !sizes.contains(&size) {
+                sizes.push(size);
+                sizes.sort_unstable();
+            }
+        }
+        let state = state.inner();
+        let started_at = now_ms();
+        let total = Instant::now();
+
+        // 预热一次，排除懒初始化带来的首包延迟
+        roundtrip(state, "ping", "").await?;
+        let mut ping = Vec::with_capacity(iterations as usize);
+        for _ in 0..iterations {
+            ping.push(roundtrip(state, "ping", "").await?.0);
+        }
+
+        let mut throughput = Vec::new();
+        for size in sizes {
+            let data = "x".repeat(size);
+            let rounds = iterations.min(MAX_THROUGHPUT_ROUNDS);
+            let mut samples = Vec::with_capacity(rounds as usize);
+            let mut bytes = 0usize;
+            let start = Instant::now();
+            for _ in 0..rounds {
+                let (ms, b) = roundtrip(state, "echo", &data).await?;
+                samples.push(ms);
+                bytes += b;
+            }
+            let secs = start.elapsed().as_secs_f64().max(f64::EPSILON);
+            throughput.push(ThroughputResult {
+                payload_bytes: size,
+                rounds,
+                latency: stats(samples),
+                mb_per_sec: bytes as f64 / secs / (1024.0 * 1024.0),
+            });
+        }
+
+        let start = Instant::now();
+        for seq in 0..EVENT_COUNT {
+            app.emit(EVENT_NAME, json!({ "seq": seq, "of": EVENT_COUNT }))
+                .map_err(|e| e.to_string())?;
+        }
+        let elapsed_ms = ms_since(start);
+        let events = EventRate {
+            events: EVENT_COUNT,
+            elapsed_ms,
+            events_per_sec: EVENT_COUNT as f64 / (elapsed_ms / 1000.0).max(f64::EPSILON),
+        };
+
+        Ok(BenchmarkReport {
+            transport: "stdio",
+            iterations,
+            ping: stats(ping),
+            throughput,
+            events,
+            started_at,
+            elapsed_ms: ms_since(total),
+        })
+    })
+    .await
+}
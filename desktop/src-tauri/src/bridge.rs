@@ -1,65 +1,546 @@
+use crate::abort::{abort_running, AbortReport};
+use crate::artifacts::ArtifactRegistry;
+use crate::backend::{self, Bridge, BridgeFuture, BridgeStatus, Liveness};
+use crate::bridge_error::BridgeError;
+use crate::changes::{register_model, ChangeTracker};
+use crate::debug_console::{self, Direction};
+use crate::dispatcher::{
+    excerpt, is_final, read_bounded_line, recv, Dispatcher, LineRead, MAX_LINE_BYTES,
+};
+use crate::eta::baseline;
+use crate::event_routing::{self, emit_job_event, EventRouter};
+use crate::exit_log::ExitLog;
+use crate::integrity::verify_before_launch;
+use crate::jdk::managed_java_home;
+use crate::jobs::{job_dir, project_of, JobRegistry, JobStatus, JOB_DIR_KEY};
+use crate::launcher;
+use crate::license::LicenseGuard;
+use crate::limits::{self, AppliedLimits, RESOURCE_LIMIT};
+use crate::log_level;
+use crate::notifications::NotificationCenter;
+use crate::notifier::notify_job_finished;
+use crate::panics::guarded;
+use crate::permissions::handle_host_request;
+use crate::policy::CommandPolicy;
+use crate::postprocess;
+use crate::process::{contain, isolate_group, reap, ChildHandle, TreeGuard};
+use crate::profiles;
+use crate::progress::ProgressAggregator;
+use crate::protocol::negotiate;
+use crate::proxy;
+use crate::recent::RecentModels;
+use crate::request_queue::{Priority, RequestQueue};
+use crate::results::extract_key_results;
+use crate::sandbox::{wrap_command, LabelLease};
+use crate::sessions;
+use crate::size_limits;
+use crate::standby;
+use crate::stats::RequestCounters;
+use crate::stderr_log::{new_buf, record_line, StderrBuf};
+use crate::storage::{check_quota, handle_failed_outputs};
+use crate::store::now_ms;
+use crate::stream::{next_request_id, StreamRegistry};
+use crate::timeouts::{RequestTimeouts, StreamBudget, DEFAULT_TIMEOUT_SECS, HEARTBEAT_KEY};
+use crate::validate::pre_send_checks;
+use serde::Serialize;
 use serde_json::Value;
-use std::path::PathBuf;
-use std::sync::Arc;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::future::Future;
 #[cfg(target_os = "windows")]
 #[allow(unused_imports)]
 use std::os::windows::process::CommandExt;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::task::Poll;
+use std::time::Duration;
 use tauri::{AppHandle, Emitter, Manager};
-use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
-use tokio::process::{Child, ChildStdin, ChildStdout, Command};
-use tokio::sync::Mutex;
+use tokio::io::{AsyncRead, BufReader};
+use tokio::process::{Child, Command};
+use tokio::sync::{watch, Mutex};
 
 pub struct BridgeStateInner {
-    pub stdin: Option<ChildStdin>,
-    pub reader: Option<BufReader<ChildStdout>>,
-    pub child: Option<Child>,
-    pub child_pid: Option<u32>,
-    pub stream_active: bool,
+    /// 管道的多路复用器；请求可并发在途，按 `_id` 配对响应
+    pub dispatcher: Option<Arc<Dispatcher>>,
+    /// 子进程退出状态；由 wait 任务持有 Child，进程一退出即写入
+    pub exit: Option<ExitWatch>,
+    pub child: Option<ChildHandle>,
+    /// 在途的流式请求数
+    pub active_streams: usize,
+    /// 在途流式请求的前端请求 ID → 注入请求的 `_id`，供协作取消定位
+    pub stream_ids: HashMap<String, String>,
+    /// 发送队列：bridge 忙于流式任务时其他请求在此排队
+    pub queue: Arc<RequestQueue>,
     pub init_in_progress: bool,
     pub bundled_java_home: Option<PathBuf>,
-    pub init_error: Option<String>,
-    pub stderr_buf: Arc<std::sync::Mutex<String>>,
+    pub init_error: Option<BridgeError>,
+    pub stderr_buf: StderrBuf,
+    pub capabilities: Option<BridgeCapabilities>,
+    /// 请求超时等原因判定 bridge 已不可靠；下一次确保就绪时先结束再重启
+    pub unhealthy: Option<String>,
+    /// 已按关闭流程退出；监护任务不再自动重启，下一次请求仍会按需启动
+    pub shut_down: bool,
+    /// 请求计数，跨重启累计，供 bridge_stats 查询
+    pub counters: Arc<RequestCounters>,
+    /// 最近一次意外退出，跨重启保留
+    pub exits: Arc<ExitLog>,
+    /// 该会话固定使用的启动实现（如经 ssh 的远程 bridge）；未设置时用全局实现
+    pub launcher: Option<Arc<dyn launcher::BridgeLauncher>>,
 }
 
-pub type BridgeState = Arc<Mutex<BridgeStateInner>>;
+impl BridgeStateInner {
+    /// 尚未启动的 bridge 状态；`bundled_java_home` 为启动时使用的 JAVA_HOME
+    pub fn new(bundled_java_home: Option<PathBuf>) -> Self {
+        Self {
+            dispatcher: None,
+            exit: None,
+            child: None,
+            active_streams: 0,
+            stream_ids: Default::default(),
+            queue: Default::default(),
+            init_in_progress: false,
+            bundled_java_home,
+            init_error: None,
+            stderr_buf: new_buf(),
+            capabilities: None,
+            unhealthy: None,
+            shut_down: false,
+            counters: Default::default(),
+            exits: Default::default(),
+            launcher: None,
+        }
+    }
+}
 
-const HANDSHAKE_TIMEOUT_SECS: u64 = 30;
+/// 握手时 bridge 声明的可用命令及其 schema 版本；旧版 bridge 不声明时为 None，不做校验
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct BridgeCapabilities {
+    pub cmds: BTreeMap<String, u32>,
+    /// 对象形式声明时附带的说明与参数 schema：`{"run": {"version": 2, "description": ..., "params": {...}}}`
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub specs: BTreeMap<String, CmdSpec>,
+    /// 可与正在执行的任务并行处理的只读查询（`"concurrent": [...]`）；
+    /// 这些命令不进发送队列，流式任务执行期间也立即发送
+    #[serde(skip_serializing_if = "BTreeSet::is_empty")]
+    pub concurrent: BTreeSet<String>,
+}
 
-fn bridge_ready(inner: &BridgeStateInner) -> bool {
-    inner.stdin.is_some() && inner.reader.is_some()
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct CmdSpec {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// JSON Schema（或 bridge 自定义的参数描述），原样转交前端
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub params: Option<Value>,
 }
 
-fn kill_pid(pid: u32) {
-    #[cfg(unix)]
-    {
-        let _ = std::process::Command::new("kill")
-            .args(["-9", &pid.to_string()])
-            .status();
+impl BridgeCapabilities {
+    /// 支持以下几种声明形式：
+    /// `"cmds": ["run", ...]`、`"cmds": {"run": 2}`、`"cmds": {"run": {"version": 2}}`
+    pub(crate) fn from_ready(ready: &Value) -> Option<Self> {
+        let cmds = ready
+            .get("capabilities")
+            .and_then(|c| c.get("cmds"))
+            .or_else(|| ready.get("cmds"))?;
+        let mut out = BTreeMap::new();
+        let mut specs = BTreeMap::new();
+        match cmds {
+            Value::Array(items) => {
+                for name in items.iter().filter_map(|v| v.as_str()) {
+                    out.insert(name.to_string(), 1);
+                }
+            }
+            Value::Object(map) => {
+                for (name, spec) in map {
+                    let version = spec
+                        .as_u64()
+                        .or_else(|| spec.get("version").and_then(|v| v.as_u64()))
+                        .or_else(|| spec.get("schema_version").and_then(|v| v.as_u64()))
+                        .unwrap_or(1);
+                    out.insert(name.clone(), u32::try_from(version).unwrap_or(u32::MAX));
+                    let spec = CmdSpec {
+                        description: spec
+                            .get("description")
+                            .and_then(|v| v.as_str())
+                            .map(String::from),
+                        params: spec.get("params").or_else(|| spec.get("schema")).cloned(),
+                    };
+                    if spec.description.is_some() || spec.params.is_some() {
+                        specs.insert(name.clone(), spec);
+                    }
+                }
+            }
+            _ => return None,
+        }
+        let concurrent = ready
+            .get("capabilities")
+            .and_then(|c| c.get("concurrent"))
+            .and_then(|v| v.as_array())
+            .map(|items| {
+                items
+                    .iter()
+                    .filter_map(|v| v.as_str())
+                    .filter(|name| out.contains_key(*name))
+                    .map(String::from)
+                    .collect()
+            })
+            .unwrap_or_default();
+        Some(Self {
+            cmds: out,
+            specs,
+            concurrent,
+        })
     }
-    #[cfg(windows)]
-    {
-        let _ = std::process::Command::new("taskkill")
-            .args(["/F", "/PID"])
-            .arg(pid.to_string())
-            .status();
+}
+
+/// bridge 子进程退出信息（BridgeDead）
+#[derive(Clone, Debug, Serialize)]
+pub struct BridgeExit {
+    pub pid: u32,
+    pub exit_code: Option<i32>,
+    /// 被信号终止时的信号编号（Unix）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub signal: Option<i32>,
+    /// 退出原因的说明，如“被信号 9（SIGKILL）终止”
+    pub reason: String,
+    pub exited_at: u64,
+    pub stderr_tail: String,
+    /// 因超出资源上限被结束时的说明
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<String>,
+}
+
+pub type ExitWatch = watch::Receiver<Option<BridgeExit>>;
+
+pub(crate) fn check_cmd_supported(inner: &BridgeStateInner, cmd: &str) -> Result<(), String> {
+    match inner.capabilities {
+        Some(ref caps) if !caps.cmds.contains_key(cmd) => Err(format!(
+            "当前 bridge 不支持命令 `{}`（支持: {}）",
+            cmd,
+            caps.cmds.keys().cloned().collect::<Vec<_>>().join(", ")
+        )),
+        _ => Ok(()),
     }
 }
 
-fn read_stderr_snapshot(buf: &Arc<std::sync::Mutex<String>>) -> String {
-    buf.lock().unwrap_or_else(|e| e.into_inner()).clone()
+pub type BridgeState = Arc<Mutex<BridgeStateInner>>;
+
+pub(crate) const HANDSHAKE_TIMEOUT_SECS: u64 = 30;
+
+fn bridge_ready(inner: &BridgeStateInner) -> bool {
+    inner.dispatcher.is_some() && !has_exited(inner) && inner.unhealthy.is_none()
+}
+
+/// 命令可否绕过发送队列、与正在执行的任务并行：需 bridge 已就绪且在握手时声明过
+fn runs_concurrently(inner: &BridgeStateInner, cmd: &str) -> bool {
+    bridge_ready(inner)
+        && inner
+            .capabilities
+            .as_ref()
+            .is_some_and(|c| c.concurrent.contains(cmd))
+}
+
+fn has_exited(inner: &BridgeStateInner) -> bool {
+    inner.exit.as_ref().is_some_and(|e| e.borrow().is_some())
 }
 
-fn make_error_with_stderr(base_msg: &str, stderr_buf: &Arc<std::sync::Mutex<String>>) -> String {
-    let stderr = read_stderr_snapshot(stderr_buf);
-    if stderr.trim().is_empty() {
+fn stderr_tail(stderr_buf: &StderrBuf) -> String {
+    stderr_buf
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .tail_text(30)
+}
+
+fn with_stderr_tail(base_msg: &str, tail: &str) -> String {
+    if tail.trim().is_empty() {
         base_msg.to_string()
     } else {
-        let tail: String = stderr.lines().rev().take(30).collect::<Vec<_>>().into_iter().rev().collect::<Vec<_>>().join("\n");
         format!("{}\n\n--- Python stderr ---\n{}", base_msg, tail)
     }
 }
 
-fn find_bundled_bridge_exe() -> Option<PathBuf> {
+fn make_error_with_stderr(base_msg: &str, stderr_buf: &StderrBuf) -> String {
+    with_stderr_tail(base_msg, &stderr_tail(stderr_buf))
+}
+
+/// PATH 摘要保留的条目数
+const PATH_EXCERPT: usize = 12;
+
+static LAST_LAUNCH: std::sync::Mutex<Option<LaunchSnapshot>> = std::sync::Mutex::new(None);
+
+/// 最近一次启动 bridge 时的进程环境；启动失败时远程排查的关键信息
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct LaunchSnapshot {
+    /// source（源码 + Python）/ bundled（安装包可执行文件）/ none（都找不到）
+    pub mode: &'static str,
+    pub program: Option<String>,
+    pub args: Vec<String>,
+    pub cwd: Option<String>,
+    pub java_home: Option<String>,
+    pub path: Vec<String>,
+    pub path_entries: usize,
+    pub exit_code: Option<i32>,
+    pub stderr_tail: String,
+    pub captured_at: u64,
+}
+
+impl LaunchSnapshot {
+    pub(crate) fn new(bundled_java_home: &Option<PathBuf>) -> Self {
+        let paths: Vec<String> = std::env::var_os("PATH")
+            .map(|p| {
+                std::env::split_paths(&p)
+                    .map(|d| d.to_string_lossy().into_owned())
+                    .collect()
+            })
+            .unwrap_or_default();
+        Self {
+            mode: "none",
+            java_home: bundled_java_home
+                .as_ref()
+                .map(|p| p.to_string_lossy().into_owned())
+                .or_else(|| std::env::var("JAVA_HOME").ok()),
+            path_entries: paths.len(),
+            path: paths.into_iter().take(PATH_EXCERPT).collect(),
+            captured_at: now_ms(),
+            ..Self::default()
+        }
+    }
+
+    pub(crate) fn record_command(
+        &mut self,
+        mode: &'static str,
+        program: &str,
+        args: &[String],
+        cwd: Option<&Path>,
+    ) {
+        self.mode = mode;
+        self.program = Some(program.to_string());
+        self.args = args.to_vec();
+        self.cwd = cwd.map(|d| d.to_string_lossy().into_owned());
+    }
+
+    /// 供错误信息使用的纯文本形式；stderr 已由 with_stderr_tail 附上，这里不重复
+    fn describe(&self) -> String {
+        let none = || "（无）".to_string();
+        let mut path = self.path.join(if cfg!(windows) { ";" } else { ":" });
+        if self.path_entries > self.path.len() {
+            path.push_str(&format!(" …（共 {} 项）", self.path_entries));
+        }
+        [
+            format!("mode: {}", self.mode),
+            format!("program: {}", self.program.clone().unwrap_or_else(none)),
+            format!("args: {}", self.args.join(" ")),
+            format!("cwd: {}", self.cwd.clone().unwrap_or_else(none)),
+            format!("JAVA_HOME: {}", self.java_home.clone().unwrap_or_else(none)),
+            format!("PATH: {}", path),
+            format!(
+                "exit code: {}",
+                self.exit_code.map(|c| c.to_string()).unwrap_or_else(none)
+            ),
+        ]
+        .join("\n")
+    }
+}
+
+pub fn last_launch() -> Option<LaunchSnapshot> {
+    LAST_LAUNCH
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone()
+}
+
+fn remember_launch(snapshot: &LaunchSnapshot) {
+    *LAST_LAUNCH.lock().unwrap_or_else(|e| e.into_inner()) = Some(snapshot.clone());
+}
+
+/// 启动失败：记录环境快照、写日志，并把快照附在返回的错误后面
+fn launch_failed(
+    err: BridgeError,
+    mut snapshot: LaunchSnapshot,
+    stderr_buf: Option<&StderrBuf>,
+) -> BridgeError {
+    if let Some(buf) = stderr_buf {
+        snapshot.stderr_tail = stderr_tail(buf);
+    }
+    remember_launch(&snapshot);
+    let err = err.map_message(|msg| {
+        format!(
+            "{}\n\n--- 启动环境 ---\n{}",
+            with_stderr_tail(msg, &snapshot.stderr_tail),
+            snapshot.describe()
+        )
+    });
+    eprintln!("[bridge-init] 启动失败: {}", err);
+    err
+}
+
+/// 握手失败时结束 bridge 及其已派生的 JVM
+async fn kill_child_tree(child: &mut Child, tree: &TreeGuard) {
+    if let Some(pid) = child.id() {
+        tree.kill(pid);
+    }
+    let _ = child.kill().await;
+}
+
+/// 握手失败时进程多半已退出，稍等片刻取退出码
+async fn exit_code_soon(child: &mut Child) -> Option<i32> {
+    tokio::time::timeout(std::time::Duration::from_millis(500), child.wait())
+        .await
+        .ok()
+        .and_then(|r| r.ok())
+        .and_then(|s| s.code())
+}
+
+fn signal_name(signal: i32) -> Option<&'static str> {
+    Some(match signal {
+        2 => "SIGINT",
+        6 => "SIGABRT",
+        9 => "SIGKILL",
+        11 => "SIGSEGV",
+        15 => "SIGTERM",
+        _ => return None,
+    })
+}
+
+/// 按退出码与信号说明退出原因；Windows 上崩溃表现为 NTSTATUS 形式的退出码
+fn exit_reason(code: Option<i32>, signal: Option<i32>) -> String {
+    if let Some(sig) = signal {
+        return match signal_name(sig) {
+            Some(name) => format!("被信号 {}（{}）终止", sig, name),
+            None => format!("被信号 {} 终止", sig),
+        };
+    }
+    match code.map(|c| c as u32) {
+        None => "退出状态未知".to_string(),
+        Some(0) => "进程自行退出".to_string(),
+        Some(0xC000_0005) => "访问冲突（0xC0000005），多为 JVM 或本地库崩溃".to_string(),
+        Some(0xC000_00FD) => "栈溢出（0xC00000FD）".to_string(),
+        Some(0xC000_0409) => "栈缓冲区溢出（0xC0000409）".to_string(),
+        Some(0xC000_013A) => "被 Ctrl+C 或控制台关闭终止（0xC000013A）".to_string(),
+        Some(c) => format!("以非零状态 {} 退出", c as i32),
+    }
+}
+
+pub(crate) fn bridge_dead_error(exit: &BridgeExit) -> String {
+    let code = exit
+        .exit_code
+        .map(|c| c.to_string())
+        .unwrap_or_else(|| "无".to_string());
+    let msg = match exit.limit {
+        Some(ref limit) => format!("{} {}（exit code: {}）", RESOURCE_LIMIT, limit, code),
+        None => format!(
+            "BridgeDead: bridge 子进程已退出（exit code: {}，{}）",
+            code, exit.reason
+        ),
+    };
+    with_stderr_tail(&msg, &exit.stderr_tail)
+}
+
+/// Child 交给回收任务持续等待退出，结束进程统一经由返回的句柄；
+/// 资源限制随之保留到进程退出，用来判断是否因超限被结束
+fn spawn_exit_watcher(
+    child: Child,
+    tree: TreeGuard,
+    stderr_buf: StderrBuf,
+    limits: AppliedLimits,
+    labels: LabelLease,
+) -> (ChildHandle, ExitWatch) {
+    let pid = child.id().unwrap_or_default();
+    let (handle, exited) = reap(child, tree);
+    let (tx, rx) = watch::channel(None);
+    tokio::spawn(async move {
+        let status = exited.await.ok().and_then(|r| r.ok());
+        // 进程已退出（含被结束），恢复沙箱目录的完整性标签
+        drop(labels);
+        // 留一点时间让 stderr 读取任务收完最后几行
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+        let tail = stderr_tail(&stderr_buf);
+        let limit = {
+            let tail = tail.clone();
+            tokio::task::spawn_blocking(move || limits.violation(&tail))
+                .await
+                .ok()
+                .flatten()
+        };
+        #[cfg(unix)]
+        let signal = status.and_then(|s| std::os::unix::process::ExitStatusExt::signal(&s));
+        #[cfg(not(unix))]
+        let signal = None;
+        let exit_code = status.and_then(|s| s.code());
+        let exit = BridgeExit {
+            pid,
+            exit_code,
+            signal,
+            reason: exit_reason(exit_code, signal),
+            exited_at: now_ms(),
+            stderr_tail: tail,
+            limit,
+        };
+        eprintln!(
+            "Warning: bridge 子进程已退出 (exit code: {:?})",
+            exit.exit_code
+        );
+        let _ = tx.send(Some(exit));
+    });
+    (handle, rx)
+}
+
+pub(crate) async fn wait_exit(mut exit: ExitWatch) -> BridgeExit {
+    loop {
+        let current = exit.borrow_and_update().clone();
+        if let Some(e) = current {
+            return e;
+        }
+        if exit.changed().await.is_err() {
+            // wait 任务异常结束而未写入，视为状态未知，不打断读写
+            std::future::pending::<()>().await;
+        }
+    }
+}
+
+/// 管道读写失败后等待退出状态的时间；回收任务收集 stderr 末尾约需 100 毫秒
+const EXIT_REPORT_WAIT: Duration = Duration::from_secs(2);
+
+/// 一次管道读写与子进程退出竞争：进程先退出时立刻以 BridgeDead 失败，不再等可能挂起的管道
+async fn pipe_io<T>(
+    exit: &Option<ExitWatch>,
+    stderr_buf: &StderrBuf,
+    what: &str,
+    io: impl Future<Output = std::io::Result<T>>,
+) -> Result<T, BridgeError> {
+    let result = match exit.clone() {
+        None => Ok(io.await),
+        Some(exit) => {
+            let mut io = std::pin::pin!(io);
+            let mut dead = std::pin::pin!(wait_exit(exit));
+            std::future::poll_fn(|cx| {
+                if let Poll::Ready(e) = dead.as_mut().poll(cx) {
+                    return Poll::Ready(Err(e));
+                }
+                io.as_mut().poll(cx).map(Ok)
+            })
+            .await
+        }
+    };
+    match result {
+        Ok(Ok(v)) => Ok(v),
+        Ok(Err(e)) => {
+            // stdout EOF 往往先于退出状态到达：稍等回收任务，报出退出码与原因而不是笼统的管道错误
+            if let Some(exit) = exit.clone() {
+                if let Ok(dead) = tokio::time::timeout(EXIT_REPORT_WAIT, wait_exit(exit)).await {
+                    return Err(BridgeError::exited(&dead));
+                }
+            }
+            // 进程仍在或无从得知其状态：管道已断，请求随之失败，调用方会重启 bridge
+            Err(BridgeError::ChildExited {
+                code: None,
+                message: make_error_with_stderr(&format!("{} 失败: {}", what, e), stderr_buf),
+            })
+        }
+        Err(dead) => Err(BridgeError::exited(&dead)),
+    }
+}
+
+pub(crate) fn find_bundled_bridge_exe() -> Option<PathBuf> {
     let exe = std::env::current_exe().ok()?;
     let dir = exe.parent()?;
     let entries = std::fs::read_dir(dir).ok()?;
@@ -78,7 +559,7 @@ fn find_bundled_bridge_exe() -> Option<PathBuf> {
     None
 }
 
-fn find_project_root() -> Option<PathBuf> {
+pub(crate) fn find_project_root() -> Option<PathBuf> {
     // 显式环境变量优先（便于从任意目录启动时指定项目根）
     if let Ok(val) = std::env::var("MPH_AGENT_ROOT") {
         let path = PathBuf::from(val);
@@ -111,7 +592,7 @@ fn find_project_root() -> Option<PathBuf> {
     None
 }
 
-fn find_python_cmd(root: &PathBuf) -> (String, Vec<String>) {
+pub(crate) fn find_python_cmd(root: &Path) -> (String, Vec<String>) {
     let cli_path = root.join("cli.py");
     let cli_str = cli_path.to_string_lossy().to_string();
 
@@ -144,6 +625,15 @@ fn find_python_cmd(root: &PathBuf) -> (String, Vec<String>) {
 }
 
 pub fn bundled_java_home_from_app(app: &tauri::App) -> Option<PathBuf> {
+    // 应用内更新过的 JDK 优先于安装包自带的
+    if let Some(home) = app
+        .path()
+        .app_data_dir()
+        .ok()
+        .and_then(|d| managed_java_home(&d))
+    {
+        return Some(home);
+    }
     let res_dir = app.path().resource_dir().ok()?;
     let java_home = res_dir.join("runtime").join("java");
     #[cfg(target_os = "windows")]
@@ -158,49 +648,56 @@ pub fn bundled_java_home_from_app(app: &tauri::App) -> Option<PathBuf> {
 }
 
 pub struct BridgeHandles {
-    pub stdin: ChildStdin,
-    pub reader: BufReader<ChildStdout>,
-    pub child: Child,
-    pub pid: u32,
-    pub stderr_buf: Arc<std::sync::Mutex<String>>,
+    pub dispatcher: Arc<Dispatcher>,
+    pub exit: ExitWatch,
+    pub child: ChildHandle,
+    pub stderr_buf: StderrBuf,
+    pub capabilities: Option<BridgeCapabilities>,
 }
 
-fn spawn_stderr_reader(
-    stderr: tokio::process::ChildStderr,
-    buf: Arc<std::sync::Mutex<String>>,
-) {
+fn spawn_stderr_reader(stderr: tokio::process::ChildStderr, buf: StderrBuf) {
     tokio::spawn(async move {
         let mut reader = BufReader::new(stderr);
-        let mut line = String::new();
         loop {
-            line.clear();
-            match reader.read_line(&mut line).await {
-                Ok(0) => break,
-                Ok(_) => {
-                    eprint!("[bridge-stderr] {}", line);
-                    if let Ok(mut b) = buf.lock() {
-                        b.push_str(&line);
-                        const MAX_STDERR: usize = 64 * 1024;
-                        if b.len() > MAX_STDERR {
-                            let cut = b.len() - MAX_STDERR / 2;
-                            *b = b[cut..].to_string();
-                        }
-                    }
-                }
-                Err(_) => break,
-            }
+            // 宽松解码并限制行长：非法字节或超长输出都不能让读取中断，否则管道写满会卡住子进程
+            let line = match read_bounded_line(&mut reader, MAX_LINE_BYTES).await {
+                Ok(LineRead::Line(line)) => line,
+                Ok(LineRead::TooLong(n)) => format!("<{} 字节的超长输出已丢弃>\n", n),
+                Ok(LineRead::Eof) | Err(_) => break,
+            };
+            eprint!("[bridge-stderr] {}", line);
+            debug_console::record(Direction::Err, &line);
+            record_line(&buf, &line);
         }
     });
 }
 
-pub async fn init_bridge(bundled_java_home: Option<PathBuf>) -> Result<BridgeHandles, String> {
-    let stderr_buf: Arc<std::sync::Mutex<String>> = Arc::new(std::sync::Mutex::new(String::new()));
+pub async fn init_bridge(bundled_java_home: Option<PathBuf>) -> Result<BridgeHandles, BridgeError> {
+    let mut snapshot = LaunchSnapshot::new(&bundled_java_home);
 
-    let mut child = spawn_bridge_child(&bundled_java_home).await?;
+    let (child, limits, labels) = match spawn_bridge_child(&bundled_java_home, &mut snapshot).await
+    {
+        Ok(c) => c,
+        Err(e) => return Err(launch_failed(BridgeError::spawn_failed(e), snapshot, None)),
+    };
+    attach_bridge_child(child, limits, labels, snapshot).await
+}
 
-    let pid = child.id().unwrap_or(0);
-    let stdin = child.stdin.take().ok_or("无法获取子进程 stdin")?;
-    let stdout = child.stdout.take().ok_or("无法获取子进程 stdout")?;
+/// 等待已启动的 bridge 进程发出就绪信号并协商协议，成功后交出句柄；
+/// 失败时结束进程，错误信息附上启动环境与 stderr。`labels` 保留到进程退出
+pub(crate) async fn attach_bridge_child(
+    mut child: Child,
+    mut limits: AppliedLimits,
+    labels: LabelLease,
+    mut snapshot: LaunchSnapshot,
+) -> Result<BridgeHandles, BridgeError> {
+    // 先建立结束范围，之后派生的 JVM 等进程都归入其中
+    let tree = contain(&child);
+    limits.attach(&tree);
+    let stderr_buf = new_buf();
+    let pipe = |name: &str| BridgeError::spawn_failed(format!("无法获取子进程 {}", name));
+    let mut stdin = child.stdin.take().ok_or_else(|| pipe("stdin"))?;
+    let stdout = child.stdout.take().ok_or_else(|| pipe("stdout"))?;
     let stderr = child.stderr.take();
 
     if let Some(se) = stderr {
@@ -209,72 +706,139 @@ pub async fn init_bridge(bundled_java_home: Option<PathBuf>) -> Result<BridgeHan
 
     let mut reader = BufReader::new(stdout);
 
-    match tokio::time::timeout(
+    let capabilities = match tokio::time::timeout(
         std::time::Duration::from_secs(HANDSHAKE_TIMEOUT_SECS),
         wait_for_handshake(&mut reader),
     )
     .await
     {
-        Ok(Ok(())) => {}
+        Ok(Ok(caps)) => caps,
         Ok(Err(e)) => {
-            let _ = child.kill().await;
-            return Err(make_error_with_stderr(
-                &format!("Bridge 握手失败: {}", e),
-                &stderr_buf,
+            snapshot.exit_code = exit_code_soon(&mut child).await;
+            kill_child_tree(&mut child, &tree).await;
+            return Err(launch_failed(
+                BridgeError::spawn_failed(format!("Bridge 握手失败: {}", e)),
+                snapshot,
+                Some(&stderr_buf),
             ));
         }
         Err(_) => {
-            let _ = child.kill().await;
-            return Err(make_error_with_stderr(
-                &format!(
+            kill_child_tree(&mut child, &tree).await;
+            return Err(launch_failed(
+                BridgeError::spawn_failed(format!(
                     "Bridge 握手超时 ({}s)：Python 进程未在规定时间内发送就绪信号",
                     HANDSHAKE_TIMEOUT_SECS
-                ),
-                &stderr_buf,
+                )),
+                snapshot,
+                Some(&stderr_buf),
             ));
         }
-    }
+    };
+
+    // 就绪后先协商协议版本，不兼容时直接报出双方版本，而不是等到请求时解析失败
+    let negotiated = match tokio::time::timeout(
+        std::time::Duration::from_secs(HANDSHAKE_TIMEOUT_SECS),
+        negotiate(&mut stdin, &mut reader),
+    )
+    .await
+    {
+        Ok(Ok(n)) => n,
+        Ok(Err(e)) => {
+            snapshot.exit_code = exit_code_soon(&mut child).await;
+            kill_child_tree(&mut child, &tree).await;
+            let err = match e {
+                BridgeError::Incompatible { .. } => e,
+                e => e.map_message(|m| format!("Bridge 协议协商失败: {}", m)),
+            };
+            return Err(launch_failed(err, snapshot, Some(&stderr_buf)));
+        }
+        Err(_) => {
+            kill_child_tree(&mut child, &tree).await;
+            return Err(launch_failed(
+                BridgeError::spawn_failed(format!(
+                    "Bridge 协议协商超时 ({}s)：Python 进程未回复 hello",
+                    HANDSHAKE_TIMEOUT_SECS
+                )),
+                snapshot,
+                Some(&stderr_buf),
+            ));
+        }
+    };
+    remember_launch(&snapshot);
 
+    let (child, exit) = spawn_exit_watcher(child, tree, stderr_buf.clone(), limits, labels);
     Ok(BridgeHandles {
-        stdin,
-        reader,
+        dispatcher: Dispatcher::start(stdin, reader, negotiated.framing, negotiated.codec),
+        exit,
         child,
-        pid,
         stderr_buf,
+        capabilities: negotiated.capabilities.or(capabilities),
     })
 }
 
-async fn wait_for_handshake(reader: &mut BufReader<ChildStdout>) -> Result<(), String> {
-    let mut line = String::new();
-    let bytes = reader
-        .read_line(&mut line)
+pub(crate) async fn wait_for_handshake<R: AsyncRead + Unpin>(
+    reader: &mut BufReader<R>,
+) -> Result<Option<BridgeCapabilities>, String> {
+    let line = match read_bounded_line(reader, MAX_LINE_BYTES)
         .await
-        .map_err(|e| format!("读取握手信号失败: {}", e))?;
-
-    if bytes == 0 {
-        return Err("Python 进程在发送握手信号前退出（stdout EOF）".to_string());
-    }
+        .map_err(|e| format!("读取握手信号失败: {}", e))?
+    {
+        LineRead::Line(line) => line,
+        LineRead::TooLong(n) => return Err(format!("握手信号过长（{} 字节）", n)),
+        LineRead::Eof => return Err("Python 进程在发送握手信号前退出（stdout EOF）".to_string()),
+    };
+    debug_console::record(Direction::In, &line);
 
     let trimmed = line.trim();
-    let parsed: Value =
-        serde_json::from_str(trimmed).map_err(|e| format!("握手信号 JSON 解析失败: {} (内容: {})", e, trimmed))?;
+    let parsed: Value = serde_json::from_str(trimmed)
+        .map_err(|e| format!("握手信号 JSON 解析失败: {} (内容: {})", e, excerpt(trimmed)))?;
 
     if parsed.get("_ready").and_then(|v| v.as_bool()) == Some(true) {
-        Ok(())
+        Ok(BridgeCapabilities::from_ready(&parsed))
     } else {
-        Err(format!("收到非握手信号: {}", trimmed))
+        Err(format!("收到非握手信号: {}", excerpt(trimmed)))
     }
 }
 
-async fn spawn_bridge_child(bundled_java_home: &Option<PathBuf>) -> Result<Child, String> {
+/// 开发模式下 bridge 实际加载的源码：入口 cli.py 与 agent 包内全部 .py
+fn bridge_sources(root: &Path) -> Vec<PathBuf> {
+    let mut out = vec![root.join("cli.py")];
+    let mut stack = vec![root.join("agent")];
+    while let Some(dir) = stack.pop() {
+        let Ok(rd) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in rd.flatten() {
+            let path = entry.path();
+            if path.is_dir() {
+                if entry.file_name() != "__pycache__" {
+                    stack.push(path);
+                }
+            } else if path.extension().is_some_and(|e| e == "py") {
+                out.push(path);
+            }
+        }
+    }
+    out
+}
+
+async fn spawn_bridge_child(
+    bundled_java_home: &Option<PathBuf>,
+    snapshot: &mut LaunchSnapshot,
+) -> Result<(Child, AppliedLimits, LabelLease), String> {
     // 开发模式：找到 pyproject.toml 时优先用 Python 脚本
     if let Some(root) = find_project_root() {
+        verify_before_launch(&root, &bridge_sources(&root), false)?;
         let (cmd, args) = find_python_cmd(&root);
         let root_str = root.to_string_lossy().to_string();
+        let (program, full_args, labels) =
+            wrap_command(cmd.clone(), args.clone(), std::slice::from_ref(&root))?;
+        let (program, full_args, limits) = limits::wrap(program, full_args);
+        snapshot.record_command("source", &program, &full_args, Some(&root));
 
-        let mut builder = Command::new(&cmd);
+        let mut builder = Command::new(&program);
         builder
-            .args(&args)
+            .args(&full_args)
             .stdin(std::process::Stdio::piped())
             .stdout(std::process::Stdio::piped())
             .stderr(std::process::Stdio::piped())
@@ -282,6 +846,12 @@ async fn spawn_bridge_child(bundled_java_home: &Option<PathBuf>) -> Result<Child
             .env("PYTHONIOENCODING", "utf-8")
             .env("PYTHONUNBUFFERED", "1")
             .env("PYTHONPATH", &root_str);
+        isolate_group(&mut builder);
+        if let Some(level) = log_level::python_level() {
+            builder.env("LOG_LEVEL", level);
+        }
+        builder.envs(proxy::env_vars());
+        builder.envs(profiles::env_vars());
 
         if let Some(ref jh) = bundled_java_home {
             builder.env("JAVA_HOME", jh);
@@ -303,19 +873,41 @@ async fn spawn_bridge_child(bundled_java_home: &Option<PathBuf>) -> Result<Child
             )
         })?;
 
-        return Ok(child);
+        return Ok((child, limits, labels));
     }
 
     // 打包模式：使用安装包内的 bridge 可执行文件
     if let Some(bridge_exe) = find_bundled_bridge_exe() {
-        let mut builder = Command::new(&bridge_exe);
+        if let Some(dir) = bridge_exe.parent() {
+            verify_before_launch(dir, std::slice::from_ref(&bridge_exe), true)?;
+        }
+        let writable: Vec<PathBuf> = bridge_exe
+            .parent()
+            .map(Path::to_path_buf)
+            .into_iter()
+            .collect();
+        let (program, full_args, labels) = wrap_command(
+            bridge_exe.to_string_lossy().into_owned(),
+            Vec::new(),
+            &writable,
+        )?;
+        let (program, full_args, limits) = limits::wrap(program, full_args);
+        snapshot.record_command("bundled", &program, &full_args, bridge_exe.parent());
+        let mut builder = Command::new(&program);
         builder
+            .args(&full_args)
             .stdin(std::process::Stdio::piped())
             .stdout(std::process::Stdio::piped())
             .stderr(std::process::Stdio::piped());
         if let Some(ref d) = bridge_exe.parent() {
             builder.current_dir(d);
         }
+        isolate_group(&mut builder);
+        if let Some(level) = log_level::python_level() {
+            builder.env("LOG_LEVEL", level);
+        }
+        builder.envs(proxy::env_vars());
+        builder.envs(profiles::env_vars());
         if let Some(ref jh) = bundled_java_home {
             builder.env("JAVA_HOME", jh);
             builder.env("MPH_AGENT_USE_BUNDLED_JAVA", "1");
@@ -325,14 +917,10 @@ async fn spawn_bridge_child(bundled_java_home: &Option<PathBuf>) -> Result<Child
             const CREATE_NO_WINDOW: u32 = 0x08000000;
             builder.creation_flags(CREATE_NO_WINDOW);
         }
-        let child = builder.spawn().map_err(|e| {
-            format!(
-                "启动打包 bridge 失败 ({}): {}",
-                bridge_exe.display(),
-                e
-            )
-        })?;
-        return Ok(child);
+        let child = builder
+            .spawn()
+            .map_err(|e| format!("启动打包 bridge 失败 ({}): {}", bridge_exe.display(), e))?;
+        return Ok((child, limits, labels));
     }
 
     Err(
@@ -341,34 +929,159 @@ async fn spawn_bridge_child(bundled_java_home: &Option<PathBuf>) -> Result<Child
     )
 }
 
-async fn restart_bridge(state: &BridgeState) {
+/// 健康检查间隔；重启失败后按倍数退避，最长 SUPERVISE_MAX_BACKOFF
+const SUPERVISE_INTERVAL: Duration = Duration::from_secs(10);
+const SUPERVISE_MAX_BACKOFF: Duration = Duration::from_secs(300);
+const PING_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum BridgeHealth {
+    Connected,
+    Reconnecting,
+    Failed,
+}
+
+enum Probe {
+    /// 正在初始化或尚未启动过，不干预
+    Skip,
+    Healthy,
+    Down(String),
+}
+
+/// 检查一次 bridge：进程已退出或被标记异常即判定为断开；空闲时再发 ping 确认仍有响应。
+/// bridge 串行处理请求，有请求在途时不 ping，以免误判长任务为无响应
+async fn probe(state: &BridgeState, started: bool) -> Probe {
+    {
+        let guard = state.lock().await;
+        if guard.init_in_progress || guard.shut_down {
+            return Probe::Skip;
+        }
+        if let Some(exit) = guard.exit.as_ref().and_then(|e| e.borrow().clone()) {
+            return Probe::Down(bridge_dead_error(&exit));
+        }
+        if let Some(ref reason) = guard.unhealthy {
+            return Probe::Down(reason.clone());
+        }
+        let Some(ref d) = guard.dispatcher else {
+            return if started {
+                Probe::Down("bridge 未运行".to_string())
+            } else {
+                Probe::Skip
+            };
+        };
+        let ping_supported = guard
+            .capabilities
+            .as_ref()
+            .is_none_or(|c| c.cmds.contains_key("ping"));
+        if !ping_supported || d.in_flight() > 0 || guard.active_streams > 0 {
+            return Probe::Healthy;
+        }
+    }
+    match send_request_with_timeout(state, "ping", serde_json::json!({}), PING_TIMEOUT).await {
+        Ok(_) => Probe::Healthy,
+        Err(e) => Probe::Down(e.into()),
+    }
+}
+
+/// 启动时的初始化结果：成功发 `bridge-ready`，失败发 `bridge-failed`（带结构化错误）
+pub fn emit_init_result(app: &AppHandle, result: Result<(), &BridgeError>, elapsed: Duration) {
+    let elapsed_ms = elapsed.as_millis() as u64;
+    let _ = match result {
+        Ok(()) => app.emit(
+            "bridge-ready",
+            serde_json::json!({ "elapsed_ms": elapsed_ms }),
+        ),
+        Err(e) => app.emit(
+            "bridge-failed",
+            serde_json::json!({ "error": e, "elapsed_ms": elapsed_ms }),
+        ),
+    };
+}
+
+fn emit_bridge_status(app: &AppHandle, status: BridgeHealth, detail: Option<&str>) {
+    let _ = app.emit(
+        "bridge-status",
+        serde_json::json!({ "status": status, "detail": detail }),
+    );
+}
+
+/// 后台监护任务：定期健康检查，发现崩溃或无响应时以保存的 JAVA_HOME 重启，
+/// 并通过 `bridge-status` 事件（connected / reconnecting / failed）通知前端
+pub fn spawn_supervisor(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let state = app.state::<BridgeState>().inner().clone();
+        let mut last: Option<BridgeHealth> = None;
+        let mut started = false;
+        let mut wait = SUPERVISE_INTERVAL;
+        loop {
+            tokio::time::sleep(wait).await;
+            let reason = match probe(&state, started).await {
+                Probe::Skip => continue,
+                Probe::Healthy => {
+                    started = true;
+                    wait = SUPERVISE_INTERVAL;
+                    if last != Some(BridgeHealth::Connected) {
+                        emit_bridge_status(&app, BridgeHealth::Connected, None);
+                        last = Some(BridgeHealth::Connected);
+                    }
+                    continue;
+                }
+                Probe::Down(reason) => reason,
+            };
+            eprintln!("Warning: bridge 健康检查失败，正在重启: {}", reason);
+            emit_bridge_status(&app, BridgeHealth::Reconnecting, Some(&reason));
+            {
+                // ping 超时时 send_request 可能已自行重启，只结束仍处于故障状态的进程
+                let mut guard = state.lock().await;
+                if has_exited(&guard) || guard.unhealthy.is_some() {
+                    stop_child(&mut guard).await;
+                }
+            }
+            match ensure_bridge_ready(&state).await {
+                Ok(()) => {
+                    wait = SUPERVISE_INTERVAL;
+                    emit_bridge_status(&app, BridgeHealth::Connected, None);
+                    last = Some(BridgeHealth::Connected);
+                }
+                Err(e) => {
+                    wait = (wait * 2).min(SUPERVISE_MAX_BACKOFF);
+                    emit_bridge_status(&app, BridgeHealth::Failed, Some(e.message()));
+                    last = Some(BridgeHealth::Failed);
+                }
+            }
+        }
+    });
+}
+
+pub(crate) async fn restart_bridge(state: &BridgeState) {
     let _ = ensure_bridge_ready(state).await;
 }
 
-async fn ensure_bridge_ready(state: &BridgeState) -> Result<(), String> {
+pub(crate) async fn ensure_bridge_ready(state: &BridgeState) -> Result<(), BridgeError> {
     let deadline =
         tokio::time::Instant::now() + std::time::Duration::from_secs(HANDSHAKE_TIMEOUT_SECS + 5);
 
     loop {
-        let maybe_java_home = {
+        let (maybe_java_home, session_launcher) = {
             let mut guard = state.lock().await;
 
             if bridge_ready(&guard) {
                 return Ok(());
             }
+            if guard.unhealthy.is_some() {
+                stop_child(&mut guard).await;
+            }
 
             if !guard.init_in_progress {
                 guard.init_in_progress = true;
                 guard.init_error = None;
-                guard.bundled_java_home.clone()
+                (guard.bundled_java_home.clone(), guard.launcher.clone())
             } else {
                 if tokio::time::Instant::now() >= deadline {
-                    return Err(
-                        guard
-                            .init_error
-                            .clone()
-                            .unwrap_or_else(|| "Bridge 初始化超时".to_string()),
-                    );
+                    return Err(guard.init_error.clone().unwrap_or_else(|| {
+                        BridgeError::spawn_failed("Bridge 初始化超时".to_string())
+                    }));
                 }
                 drop(guard);
                 tokio::time::sleep(std::time::Duration::from_millis(120)).await;
@@ -376,24 +1089,21 @@ async fn ensure_bridge_ready(state: &BridgeState) -> Result<(), String> {
             }
         };
 
-        match init_bridge(maybe_java_home).await {
+        let launched = match session_launcher {
+            Some(l) => l.launch(maybe_java_home).await,
+            None => launcher::launch(maybe_java_home).await,
+        };
+        match launched {
             Ok(handles) => {
-                let mut guard = state.lock().await;
-                guard.stdin = Some(handles.stdin);
-                guard.reader = Some(handles.reader);
-                guard.child = Some(handles.child);
-                guard.child_pid = Some(handles.pid);
-                guard.stderr_buf = handles.stderr_buf;
-                guard.init_error = None;
-                guard.init_in_progress = false;
+                install_handles(&mut *state.lock().await, handles);
                 return Ok(());
             }
             Err(e) => {
                 let mut guard = state.lock().await;
-                guard.stdin = None;
-                guard.reader = None;
+                guard.dispatcher = None;
+                guard.exit = None;
                 guard.child = None;
-                guard.child_pid = None;
+                guard.capabilities = None;
                 guard.init_error = Some(e.clone());
                 guard.init_in_progress = false;
                 return Err(e);
@@ -402,306 +1112,947 @@ async fn ensure_bridge_ready(state: &BridgeState) -> Result<(), String> {
     }
 }
 
+/// 把已完成握手的 bridge 进程设为当前进程；调用方需持有状态锁
+pub(crate) fn install_handles(guard: &mut BridgeStateInner, handles: BridgeHandles) {
+    guard.exits.watch(handles.exit.clone());
+    guard.dispatcher = Some(handles.dispatcher);
+    guard.exit = Some(handles.exit);
+    guard.child = Some(handles.child);
+    guard.stderr_buf = handles.stderr_buf;
+    guard.capabilities = handles.capabilities;
+    guard.init_error = None;
+    guard.init_in_progress = false;
+    guard.shut_down = false;
+}
+
+/// `session` 指定具名 bridge 会话，未指定时发往默认会话
 #[tauri::command]
 pub async fn bridge_send(
-    state: tauri::State<'_, BridgeState>,
+    app: AppHandle,
     cmd: String,
+    mut payload: Value,
+    timeout_secs: Option<u64>,
+    session: Option<String>,
+) -> Result<Value, BridgeError> {
+    guarded("bridge_send", async move {
+        app.state::<CommandPolicy>().check(&cmd)?;
+        let bridge = backend::resolve(&app, session.as_deref())?;
+        backend::ensure_not_starting(bridge.as_ref()).await?;
+        let timeout = app
+            .state::<RequestTimeouts>()
+            .resolve(&cmd, timeout_secs, &mut payload);
+        let state = sessions::resolve(&app, session.as_deref())?;
+        pre_send_checks(&app, &state, &cmd, &mut payload, None, false)
+            .await
+            .map_err(|v| BridgeError::from(v.message))?;
+        bridge.send(&cmd, payload, timeout).await
+    })
+    .await
+}
+
+/// 经由 stdin/stdout 与本机 bridge 子进程通信的实现
+pub struct StdioBridge {
+    app: AppHandle,
+    state: BridgeState,
+    session: Option<String>,
+}
+
+impl StdioBridge {
+    pub fn new(app: AppHandle, state: BridgeState, session: Option<&str>) -> Self {
+        Self {
+            app,
+            state,
+            session: session.map(String::from),
+        }
+    }
+}
+
+impl Bridge for StdioBridge {
+    fn send(&self, cmd: &str, payload: Value, timeout: Duration) -> BridgeFuture<'_, Value> {
+        let cmd = cmd.to_string();
+        Box::pin(
+            async move { send_request_with_timeout(&self.state, &cmd, payload, timeout).await },
+        )
+    }
+
+    fn send_stream(
+        &self,
+        cmd: String,
+        payload: Value,
+        request_id: Option<String>,
+    ) -> BridgeFuture<'_, Value> {
+        Box::pin(submit_stream_job(
+            &self.app,
+            cmd,
+            payload,
+            request_id,
+            None,
+            self.session.as_deref(),
+        ))
+    }
+
+    /// 中止只针对默认会话；具名会话的流式请求按请求 ID 取消
+    fn abort(&self) -> BridgeFuture<'_, AbortReport> {
+        Box::pin(async move {
+            match self.session.as_deref().map(str::trim) {
+                None | Some("") | Some(sessions::DEFAULT_SESSION) => abort_running(&self.app).await,
+                Some(id) => Err(BridgeError::from(format!(
+                    "具名会话 {} 不支持中止，请按请求 ID 取消",
+                    id
+                ))),
+            }
+        })
+    }
+
+    fn health(&self) -> BridgeFuture<'_, BridgeStatus> {
+        Box::pin(async move {
+            let guard = self.state.lock().await;
+            let exit = guard.exit.as_ref().and_then(|e| e.borrow().clone());
+            let (liveness, detail) = if guard.init_in_progress && !bridge_ready(&guard) {
+                (Liveness::Starting, None)
+            } else if let Some(ref exit) = exit {
+                (Liveness::Exited, Some(bridge_dead_error(exit)))
+            } else if let Some(ref reason) = guard.unhealthy {
+                (Liveness::Unhealthy, Some(reason.clone()))
+            } else if bridge_ready(&guard) {
+                (Liveness::Ready, None)
+            } else {
+                (
+                    Liveness::Stopped,
+                    guard.init_error.as_ref().map(ToString::to_string),
+                )
+            };
+            Ok(BridgeStatus {
+                liveness,
+                detail,
+                in_flight: guard.dispatcher.as_ref().map_or(0, |d| d.in_flight()),
+                active_streams: guard.active_streams,
+            })
+        })
+    }
+}
+
+/// 发送一条非流式请求并等待其响应，供后端内部复用；按默认时间预算等待
+pub async fn send_request(
+    state: &BridgeState,
+    cmd: &str,
     payload: Value,
-) -> Result<Value, String> {
-    ensure_bridge_ready(state.inner()).await?;
-
-    let (mut stdin, mut reader, stderr_buf) = {
-        let mut guard = state.inner().lock().await;
-        let s = guard.stdin.take().ok_or("Bridge 未初始化")?;
-        let r = guard.reader.take().ok_or("Bridge 未初始化")?;
-        let se = guard.stderr_buf.clone();
-        (s, r, se)
+) -> Result<Value, BridgeError> {
+    let timeout = Duration::from_secs(DEFAULT_TIMEOUT_SECS);
+    send_request_with_timeout(state, cmd, payload, timeout).await
+}
+
+/// 发送一条非流式请求并在 `timeout` 内等待响应。默认以高优先级排队，
+/// `timeout` 从轮到本请求发送时开始计算；bridge 声明可并行的查询不排队，直接发送。
+/// 超时视为 bridge 挂起：放弃本次等待并把 bridge 标记为异常，由重启恢复
+pub async fn send_request_with_timeout(
+    state: &BridgeState,
+    cmd: &str,
+    mut payload: Value,
+    timeout: Duration,
+) -> Result<Value, BridgeError> {
+    let priority = Priority::take(&mut payload, Priority::High);
+    let (queue, concurrent) = {
+        let guard = state.lock().await;
+        (guard.queue.clone(), runs_concurrently(&guard, cmd))
+    };
+    let _slot = if concurrent {
+        None
+    } else {
+        Some(queue.acquire(cmd, priority, None).await?)
+    };
+    ensure_bridge_ready(state).await?;
+
+    let (dispatcher, stderr_buf, exit, counters) = {
+        let guard = state.lock().await;
+        check_cmd_supported(&guard, cmd)?;
+        let d = guard
+            .dispatcher
+            .clone()
+            .ok_or_else(BridgeError::not_initialized)?;
+        guard.counters.started(false);
+        (
+            d,
+            guard.stderr_buf.clone(),
+            guard.exit.clone(),
+            guard.counters.clone(),
+        )
     };
 
     let mut req = match payload.as_object() {
         Some(obj) => obj.clone(),
         None => serde_json::Map::new(),
     };
-    req.insert("cmd".into(), Value::String(cmd));
+    req.insert("cmd".into(), Value::String(cmd.to_string()));
+    let (id, mut rx) = dispatcher.register();
+    req.insert("_id".into(), Value::String(id.clone()));
 
     let line = serde_json::to_string(&Value::Object(req)).map_err(|e| e.to_string())?;
-    let line_with_newline = format!("{}\n", line);
-
-    if let Err(e) = stdin.write_all(line_with_newline.as_bytes()).await {
-        let err = make_error_with_stderr(&format!("写入 bridge stdin 失败: {}", e), &stderr_buf);
-        restart_bridge(state.inner()).await;
-        return Err(err);
+    // 超大的请求在发送前拒绝，bridge 不受影响
+    if let Err(e) = size_limits::current().check_request(&line) {
+        dispatcher.cancel(&id);
+        let result = Err(BridgeError::from(e));
+        counters.finished(&result);
+        return result;
     }
-    if let Err(e) = stdin.flush().await {
-        let err = make_error_with_stderr(&format!("flush bridge stdin 失败: {}", e), &stderr_buf);
-        restart_bridge(state.inner()).await;
-        return Err(err);
+    debug_console::record(Direction::Out, &line);
+    let write = dispatcher.write_line(&line);
+    if let Err(err) = pipe_io(&exit, &stderr_buf, "写入 bridge stdin", write).await {
+        dispatcher.cancel(&id);
+        reset_bridge(state, &dispatcher).await;
+        let result = Err(err);
+        counters.finished(&result);
+        return result;
     }
 
-    let mut resp_line = String::new();
-    let bytes = match reader.read_line(&mut resp_line).await {
-        Ok(b) => b,
-        Err(e) => {
-            let err =
-                make_error_with_stderr(&format!("读取 bridge stdout 失败: {}", e), &stderr_buf);
-            restart_bridge(state.inner()).await;
-            return Err(err);
+    let response = async {
+        loop {
+            match pipe_io(&exit, &stderr_buf, "读取 bridge stdout", recv(&mut rx)).await {
+                Ok(Ok(v)) if is_final(&v) => return Ok(v),
+                // 非流式请求不关心中间事件
+                Ok(Ok(_)) => continue,
+                // 分发器只以错误送回无法解析或过长的响应
+                Ok(Err(message)) => return Err(BridgeError::ProtocolError { message }),
+                Err(err) => {
+                    dispatcher.cancel(&id);
+                    reset_bridge(state, &dispatcher).await;
+                    return Err(err);
+                }
+            }
         }
     };
+    let result = match tokio::time::timeout(timeout, response).await {
+        Ok(result) => result,
+        Err(_) => {
+            dispatcher.cancel(&id);
+            let reason = format!("命令 `{}` 在 {} 秒内未响应", cmd, timeout.as_secs());
+            mark_unhealthy(state, &dispatcher, &reason).await;
+            Err(BridgeError::Timeout {
+                message: make_error_with_stderr(
+                    &format!("BridgeTimeout: {}，bridge 已标记为异常并将重启", reason),
+                    &stderr_buf,
+                ),
+            })
+        }
+    };
+    counters.finished(&result);
+    result
+}
 
-    if bytes == 0 {
-        let err = make_error_with_stderr("Bridge 子进程已退出（EOF）", &stderr_buf);
-        restart_bridge(state.inner()).await;
-        return Err(err);
+/// 把仍是当前进程的 bridge 标记为异常。没有其他请求在途时立即在后台重启，
+/// 否则留给下一次 ensure_bridge_ready 处理，不打断仍在进行的请求
+async fn mark_unhealthy(state: &BridgeState, dispatcher: &Arc<Dispatcher>, reason: &str) {
+    let mut guard = state.lock().await;
+    if !guard
+        .dispatcher
+        .as_ref()
+        .is_some_and(|d| Arc::ptr_eq(d, dispatcher))
+    {
+        return;
     }
-
-    let trimmed = resp_line.trim();
-    if trimmed.is_empty() {
-        let err = make_error_with_stderr("Bridge 返回为空", &stderr_buf);
-        restart_bridge(state.inner()).await;
-        return Err(err);
+    eprintln!("Warning: bridge 标记为异常: {}", reason);
+    guard.unhealthy = Some(reason.to_string());
+    if dispatcher.in_flight() == 0 && guard.active_streams == 0 {
+        stop_child(&mut guard).await;
+        let state = state.clone();
+        tokio::spawn(async move { restart_bridge(&state).await });
     }
+}
 
+/// 管道读写失败后结束该 bridge 进程并重启；若已被其他请求换成新进程则只等待就绪。
+/// 旧进程上的其他在途请求随其 stdout 关闭一并失败
+async fn reset_bridge(state: &BridgeState, dispatcher: &Arc<Dispatcher>) {
     {
-        let mut guard = state.inner().lock().await;
-        guard.stdin = Some(stdin);
-        guard.reader = Some(reader);
+        let mut guard = state.lock().await;
+        if guard
+            .dispatcher
+            .as_ref()
+            .is_some_and(|d| Arc::ptr_eq(d, dispatcher))
+        {
+            stop_child(&mut guard).await;
+        }
     }
-
-    serde_json::from_str(trimmed).map_err(|e| format!("JSON 解析失败: {} (内容: {})", e, trimmed))
+    restart_bridge(state).await;
 }
 
+/// `session` 指定具名 bridge 会话，未指定时发往默认会话
 #[tauri::command]
 pub async fn bridge_send_stream(
     app: AppHandle,
-    state: tauri::State<'_, BridgeState>,
     cmd: String,
     payload: Value,
-) -> Result<Value, String> {
-    ensure_bridge_ready(state.inner()).await?;
-
-    let (mut stdin, mut reader, stderr_buf, pid) = {
-        let mut guard = state.inner().lock().await;
-        let s = guard.stdin.take().ok_or("Bridge 未初始化")?;
-        let r = guard.reader.take().ok_or("Bridge 未初始化")?;
-        let se = guard.stderr_buf.clone();
-        let p = guard.child_pid;
-        guard.stream_active = true;
-        (s, r, se, p)
+    request_id: Option<String>,
+    session: Option<String>,
+) -> Result<Value, BridgeError> {
+    guarded("bridge_send_stream", async move {
+        backend::resolve(&app, session.as_deref())?
+            .send_stream(cmd, payload, request_id)
+            .await
+    })
+    .await
+}
+
+/// 登记任务并以流式请求执行，结束后更新任务状态并发送 job-finished 通知
+pub(crate) async fn submit_stream_job(
+    app: &AppHandle,
+    cmd: String,
+    mut payload: Value,
+    request_id: Option<String>,
+    rerun_of: Option<&str>,
+    session: Option<&str>,
+) -> Result<Value, BridgeError> {
+    let state = sessions::resolve(app, session)?;
+    let notifications = app.state::<NotificationCenter>();
+    let progress = app.state::<ProgressAggregator>();
+    let jobs = app.state::<JobRegistry>();
+    app.state::<CommandPolicy>().check(&cmd)?;
+    let request_id = request_id.unwrap_or_else(next_request_id);
+    // 每个任务写到自己的目录，并发任务的导出不会互相覆盖，清理时整目录删除
+    if let (Some(dir), Some(obj)) = (job_dir(&payload, &request_id), payload.as_object_mut()) {
+        obj.insert(
+            JOB_DIR_KEY.into(),
+            Value::String(dir.to_string_lossy().into_owned()),
+        );
+    }
+    jobs.create(&request_id, &cmd, &payload, rerun_of);
+    jobs.wait_until_resumed().await;
+    let _seat = app
+        .state::<LicenseGuard>()
+        .acquire(app, &request_id, &payload)
+        .await;
+    jobs.mark_running(&request_id);
+    let baseline = baseline(&jobs, &cmd, &payload, project_of(&payload).as_deref());
+    let router = app.state::<EventRouter>();
+    router.track_job(&request_id, project_of(&payload));
+    let started = progress.start(&request_id, &cmd, baseline);
+    emit_job_event(app, "job-progress", &request_id, started, false);
+
+    let result = stream_request(app, &state, &request_id, &cmd, payload).await;
+
+    let (ok, message) = match &result {
+        Ok(v) => (v["ok"].as_bool().unwrap_or(false), v["message"].clone()),
+        Err(e) => (false, Value::String(e.to_string())),
+    };
+    let status = if ok {
+        JobStatus::Succeeded
+    } else if matches!(&result, Ok(v) if v["cancelled"].as_bool() == Some(true)) {
+        JobStatus::Aborted
+    } else {
+        JobStatus::Failed
     };
+    jobs.finish(&request_id, status, message.as_str().map(String::from));
+    let changes = app.state::<ChangeTracker>().finish(&request_id, status);
+    jobs.set_changes(&request_id, changes);
+    extract_key_results(app, &request_id).await;
+    if status != JobStatus::Succeeded {
+        handle_failed_outputs(app, &request_id);
+    }
+    notify_job_finished(app, &request_id);
+    if let Some(p) = progress.finish(&request_id, ok, message.as_str().map(String::from)) {
+        jobs.set_stage_offsets(&request_id, p.stage_offsets.clone());
+        emit_job_event(app, "job-progress", &request_id, p, false);
+    }
+    event_routing::flush_job(app, &request_id);
+    router.untrack_job(&request_id);
+    notifications.deliver(
+        app,
+        "job-finished",
+        serde_json::json!({
+            "request_id": request_id,
+            "cmd": cmd,
+            "ok": ok,
+            "message": message,
+        }),
+    );
 
-    if let Some(p) = pid {
-        let mut guard = state.inner().lock().await;
-        guard.child_pid = Some(p);
+    result
+}
+
+/// 登记 run_end 报告的模型文件：任务产物、最近列表、外部修改基准与存储配额
+fn record_artifact(
+    app: &AppHandle,
+    request_id: &str,
+    path: &str,
+    project: Option<&str>,
+    success: &Value,
+) {
+    app.state::<JobRegistry>().add_artifact(request_id, path);
+    postprocess::submit(app, path);
+    app.state::<RecentModels>()
+        .touch(path, project.map(String::from));
+    if let Err(e) = register_model(
+        &app.state::<ChangeTracker>(),
+        &app.state::<ArtifactRegistry>(),
+        request_id,
+        path,
+        project,
+    ) {
+        eprintln!("Warning: 登记产物失败: {}", e);
     }
+    app.state::<NotificationCenter>().deliver(
+        app,
+        "artifact-created",
+        serde_json::json!({
+            "request_id": request_id,
+            "path": path,
+            "success": success,
+        }),
+    );
+    check_quota(app, project);
+}
+
+/// 发送一条流式请求并转发中间事件，直到收到最终响应
+async fn stream_request(
+    app: &AppHandle,
+    state: &BridgeState,
+    request_id: &str,
+    cmd: &str,
+    mut payload: Value,
+) -> Result<Value, BridgeError> {
+    let budget = app.state::<RequestTimeouts>().resolve_stream(&mut payload);
+    let priority = Priority::take(&mut payload, Priority::Normal);
+    let queue = state.lock().await.queue.clone();
+    let _slot = queue.acquire(cmd, priority, Some(request_id)).await?;
+    ensure_bridge_ready(state).await?;
+    // 与 validate_request 的 dry run 走同一套检查；bridge 已就绪，参数按其声明的 schema 校验
+    pre_send_checks(app, state, cmd, &mut payload, Some(request_id), false)
+        .await
+        .map_err(|v| BridgeError::from(v.message))?;
+
+    let (dispatcher, stderr_buf, exit, counters) = {
+        let mut guard = state.lock().await;
+        let d = guard
+            .dispatcher
+            .clone()
+            .ok_or_else(BridgeError::not_initialized)?;
+        guard.active_streams += 1;
+        guard.counters.started(true);
+        (
+            d,
+            guard.stderr_buf.clone(),
+            guard.exit.clone(),
+            guard.counters.clone(),
+        )
+    };
 
+    let project = project_of(&payload);
+    let workspace = payload
+        .get("workspace_dir")
+        .and_then(|v| v.as_str())
+        .map(String::from);
     let mut req = match payload.as_object() {
         Some(obj) => obj.clone(),
         None => serde_json::Map::new(),
     };
-    req.insert("cmd".into(), Value::String(cmd));
+    req.insert("cmd".into(), Value::String(cmd.to_string()));
+    req.insert(HEARTBEAT_KEY.into(), Value::from(budget.heartbeat_secs()));
+    let (id, mut rx) = dispatcher.register();
+    req.insert("_id".into(), Value::String(id.clone()));
+    state
+        .lock()
+        .await
+        .stream_ids
+        .insert(request_id.to_string(), id.clone());
 
     let line = serde_json::to_string(&Value::Object(req)).map_err(|e| e.to_string())?;
-    let line_with_newline = format!("{}\n", line);
-
-    if let Err(e) = stdin.write_all(line_with_newline.as_bytes()).await {
-        let err = make_error_with_stderr(
-            &format!("写入 bridge stdin 失败: {}", e),
-            &stderr_buf,
-        );
-        let mut guard = state.inner().lock().await;
-        guard.stream_active = false;
-        drop(guard);
-        restart_bridge(state.inner()).await;
-        return Err(err);
+    if let Err(e) = size_limits::current().check_request(&line) {
+        dispatcher.cancel(&id);
+        end_stream(state, request_id).await;
+        let result = Err(BridgeError::from(e));
+        counters.finished(&result);
+        return result;
     }
-    if let Err(e) = stdin.flush().await {
-        let err = make_error_with_stderr(
-            &format!("flush bridge stdin 失败: {}", e),
-            &stderr_buf,
-        );
-        let mut guard = state.inner().lock().await;
-        guard.stream_active = false;
-        drop(guard);
-        restart_bridge(state.inner()).await;
-        return Err(err);
+    debug_console::record(Direction::Out, &line);
+    let write = dispatcher.write_line(&line);
+    if let Err(err) = pipe_io(&exit, &stderr_buf, "写入 bridge stdin", write).await {
+        dispatcher.cancel(&id);
+        end_stream(state, request_id).await;
+        reset_bridge(state, &dispatcher).await;
+        let result = Err(err);
+        counters.finished(&result);
+        return result;
     }
 
+    let streams = app.state::<StreamRegistry>();
+    let progress = app.state::<ProgressAggregator>();
+    let mut emitter = streams.start(app.clone(), request_id.to_string());
+
+    // 管道错误需要重启 bridge；响应行本身无法解析只让本请求失败
+    let mut pipe_broken = false;
+    let mut timed_out = None;
+    let started = tokio::time::Instant::now();
+    let mut last_output = started;
+    let mut stalled = false;
     let result = loop {
-        let mut resp_line = String::new();
-        let bytes = match reader.read_line(&mut resp_line).await {
-            Ok(b) => b,
-            Err(e) => {
-                break Err(make_error_with_stderr(
-                    &format!("读取 bridge stdout 失败: {}", e),
-                    &stderr_buf,
-                ));
+        let read = pipe_io(&exit, &stderr_buf, "读取 bridge stdout", recv(&mut rx));
+        let mut read = std::pin::pin!(read);
+        let mut report_at = last_output + budget.stall;
+        let waited = loop {
+            let next = next_stream_line(read.as_mut(), started, last_output, report_at, budget);
+            match next.await {
+                Ok(StreamWait::Stalled(silence)) => {
+                    stalled = true;
+                    emit_stalled(app, request_id, silence, budget.stall, true);
+                    report_at += budget.stall;
+                    if !child_alive(state).await {
+                        break Ok(Err(BridgeError::ChildExited {
+                            code: None,
+                            message: format!(
+                                "BridgeDead: bridge 进程在静默 {} 秒后被发现已退出",
+                                silence.as_secs()
+                            ),
+                        }));
+                    }
+                }
+                Ok(StreamWait::Line(line)) => break Ok(line),
+                Err(reason) => break Err(reason),
             }
         };
-
-        if bytes == 0 {
-            break Err(make_error_with_stderr(
-                "Bridge 子进程已退出，未收到完整响应",
-                &stderr_buf,
-            ));
-        }
-
-        let trimmed = resp_line.trim();
-        if trimmed.is_empty() {
-            continue;
+        if waited.is_ok() {
+            last_output = tokio::time::Instant::now();
+            if std::mem::take(&mut stalled) {
+                emit_stalled(app, request_id, Duration::ZERO, budget.stall, false);
+            }
         }
-
-        let parsed: Value = match serde_json::from_str(trimmed) {
-            Ok(v) => v,
-            Err(e) => {
-                break Err(format!("JSON 解析失败: {} (内容: {})", e, trimmed));
+        let parsed = match waited {
+            Ok(Ok(Ok(v))) => v,
+            Ok(Ok(Err(message))) => break Err(BridgeError::ProtocolError { message }),
+            Ok(Err(err)) => {
+                pipe_broken = true;
+                break Err(err);
+            }
+            Err(reason) => {
+                let msg = reason.to_string();
+                timed_out = Some(msg.clone());
+                break Err(BridgeError::Timeout {
+                    message: make_error_with_stderr(&msg, &stderr_buf),
+                });
             }
         };
 
         if parsed.get("_event").and_then(|v| v.as_bool()) == Some(true) {
-            let _ = app.emit("bridge-event", &parsed);
+            // 心跳只用于重置静默计时，不转发给前端
+            if parsed.get("type").and_then(|v| v.as_str()) == Some("heartbeat") {
+                continue;
+            }
+            // bridge 请求宿主执行操作：授权与执行在后台进行，bridge 等待 host_reply
+            if parsed.get("type").and_then(|v| v.as_str()) == Some("host_request") {
+                handle_host_request(
+                    app,
+                    dispatcher.clone(),
+                    request_id,
+                    project.clone(),
+                    workspace.clone(),
+                    parsed["data"].clone(),
+                );
+                continue;
+            }
+            if parsed.get("type").and_then(|v| v.as_str()) == Some("run_end") {
+                if let Some(path) = parsed["data"]["model_path"].as_str() {
+                    record_artifact(
+                        app,
+                        request_id,
+                        path,
+                        project.as_deref(),
+                        &parsed["data"]["success"],
+                    );
+                }
+                if let Some(path) = parsed["data"]["key_results"].as_str() {
+                    app.state::<JobRegistry>().add_artifact(request_id, path);
+                    app.state::<ChangeTracker>().record_export(request_id, path);
+                    postprocess::submit(app, path);
+                }
+            }
+            app.state::<ChangeTracker>().observe(request_id, &parsed);
+            if let Some(p) = progress.observe(request_id, &parsed) {
+                emit_job_event(app, "job-progress", request_id, p, false);
+            }
+            emitter.push(parsed);
         } else {
             break Ok(parsed);
         }
     };
 
-    {
-        let mut guard = state.inner().lock().await;
-        guard.stream_active = false;
-        if result.is_ok() {
-            guard.stdin = Some(stdin);
-            guard.reader = Some(reader);
-        } else {
-            guard.stdin.take();
-            guard.reader.take();
-            guard.child.take();
-            guard.child_pid.take();
-            drop(guard);
-            restart_bridge(state.inner()).await;
-        }
+    emitter.finish(streams.inner()).await;
+
+    end_stream(state, request_id).await;
+    if pipe_broken {
+        dispatcher.cancel(&id);
+        reset_bridge(state, &dispatcher).await;
+    } else if let Some(reason) = timed_out {
+        // bridge 串行处理请求，仍在执行超时的任务，只能重启才能继续服务
+        dispatcher.cancel(&id);
+        mark_unhealthy(state, &dispatcher, &reason).await;
     }
 
+    counters.finished(&result);
     result
 }
 
-#[tauri::command]
-pub async fn bridge_abort(state: tauri::State<'_, BridgeState>) -> Result<(), String> {
-    let pid = {
-        let mut guard = state.inner().lock().await;
-        let p = guard.child_pid.take();
-        guard.stdin.take();
-        guard.reader.take();
-        if let Some(mut child) = guard.child.take() {
-            let _ = child.kill().await;
-        }
-        guard.stream_active = false;
-        p
+/// 流式任务超时的两种原因，错误信息分别以 StreamTimeout / StreamIdleTimeout 开头
+enum StreamTimeout {
+    Total(Duration),
+    Idle(Duration),
+}
+
+impl std::fmt::Display for StreamTimeout {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Total(d) => write!(
+                f,
+                "StreamTimeout: 任务运行超过总时长上限 {} 秒",
+                d.as_secs()
+            ),
+            Self::Idle(d) => write!(
+                f,
+                "StreamIdleTimeout: bridge 已 {} 秒没有任何事件",
+                d.as_secs()
+            ),
+        }
+    }
+}
+
+/// 等待下一行输出的结果：读到内容，或已静默到需要提示「卡住」
+enum StreamWait<T> {
+    Line(T),
+    Stalled(Duration),
+}
+
+/// 等待下一行输出，同时受总时长与静默时长两个时钟约束；
+/// 到达 `report_at` 仍无输出时返回 Stalled，调用方可继续以同一个读取等待
+async fn next_stream_line<T>(
+    read: std::pin::Pin<&mut impl Future<Output = T>>,
+    started: tokio::time::Instant,
+    last_output: tokio::time::Instant,
+    report_at: tokio::time::Instant,
+    budget: StreamBudget,
+) -> Result<StreamWait<T>, StreamTimeout> {
+    let total_at = started + budget.total;
+    let idle_at = last_output + budget.idle;
+    let deadline = total_at.min(idle_at).min(report_at);
+    match tokio::time::timeout_at(deadline, read).await {
+        Ok(v) => Ok(StreamWait::Line(v)),
+        Err(_) if deadline == total_at => Err(StreamTimeout::Total(budget.total)),
+        Err(_) if deadline == idle_at => Err(StreamTimeout::Idle(budget.idle)),
+        Err(_) => Ok(StreamWait::Stalled(last_output.elapsed())),
+    }
+}
+
+/// 流式任务静默超过卡住判定时长时通知前端；恢复输出后再发一次 stalled=false
+fn emit_stalled(
+    app: &AppHandle,
+    request_id: &str,
+    silence: Duration,
+    stall: Duration,
+    stalled: bool,
+) {
+    let _ = app.emit(
+        "bridge-stalled",
+        serde_json::json!({
+            "request_id": request_id,
+            "stalled": stalled,
+            "seconds_since_output": silence.as_secs(),
+            "stall_secs": stall.as_secs(),
+        }),
+    );
+}
+
+/// 子进程是否仍在运行；静默期间用来区分「计算慢」与「进程已死但管道未断」
+async fn child_alive(state: &BridgeState) -> bool {
+    state
+        .lock()
+        .await
+        .child
+        .as_ref()
+        .is_some_and(|c| c.is_alive())
+}
+
+async fn end_stream(state: &BridgeState, request_id: &str) {
+    let mut guard = state.lock().await;
+    guard.active_streams = guard.active_streams.saturating_sub(1);
+    guard.stream_ids.remove(request_id);
+}
+
+/// 断开并结束当前 bridge 进程；调用方需持有状态锁
+pub(crate) async fn stop_child(guard: &mut BridgeStateInner) {
+    guard.exits.release();
+    let child = guard.child.take();
+    guard.dispatcher.take();
+    guard.exit.take();
+    guard.active_streams = 0;
+    guard.stream_ids.clear();
+    guard.unhealthy = None;
+    // 经由回收任务结束；进程已退出时句柄失效，不会误杀复用了 pid 的进程
+    if let Some(c) = child {
+        c.kill();
+    }
+}
+
+/// 关闭流程中等待 bridge 自行退出的时间，超时后强制结束
+pub const SHUTDOWN_GRACE: Duration = Duration::from_secs(5);
+/// 强制结束后等待回收的时间
+const KILL_WAIT: Duration = Duration::from_secs(1);
+
+/// 关闭 bridge：发送 `{"cmd":"shutdown"}` 让其关闭 COMSOL 会话、清理临时文件后退出，
+/// 在 `grace` 内未退出则强制结束。返回是否为自行退出
+pub async fn shutdown_bridge(state: &BridgeState, grace: Duration) -> bool {
+    let (dispatcher, exit, child) = {
+        let mut guard = state.lock().await;
+        guard.exits.release();
+        guard.shut_down = true;
+        guard.active_streams = 0;
+        guard.stream_ids.clear();
+        guard.unhealthy = None;
+        (
+            guard.dispatcher.take(),
+            guard.exit.take(),
+            guard.child.take(),
+        )
+    };
+    let (Some(dispatcher), Some(exit)) = (dispatcher, exit) else {
+        if let Some(c) = child {
+            c.kill();
+        }
+        return true;
     };
-    if let Some(p) = pid {
-        kill_pid(p);
+
+    let (id, _rx) = dispatcher.register();
+    let line = serde_json::json!({ "cmd": "shutdown", "_id": id }).to_string();
+    debug_console::record(Direction::Out, &line);
+    if let Err(e) = dispatcher.write_line(&line).await {
+        eprintln!("Warning: 发送 shutdown 失败: {}", e);
     }
-    restart_bridge(state.inner()).await;
-    let guard = state.inner().lock().await;
-    if let Some(ref e) = guard.init_error {
-        Err(e.clone())
-    } else {
-        Ok(())
+    if tokio::time::timeout(grace, wait_exit(exit.clone()))
+        .await
+        .is_ok()
+    {
+        return true;
     }
+
+    eprintln!(
+        "Warning: bridge 未在 {} 秒内退出，强制结束",
+        grace.as_secs()
+    );
+    if let Some(c) = child {
+        c.kill();
+        if tokio::time::timeout(KILL_WAIT, wait_exit(exit))
+            .await
+            .is_err()
+        {
+            c.kill_now();
+        }
+    }
+    false
+}
+
+/// 手动关闭 bridge；之后的请求会重新启动它
+#[tauri::command]
+pub async fn bridge_shutdown(
+    state: tauri::State<'_, BridgeState>,
+    grace_secs: Option<u64>,
+) -> Result<serde_json::Value, BridgeError> {
+    guarded("bridge_shutdown", async move {
+        let grace = grace_secs
+            .map(Duration::from_secs)
+            .unwrap_or(SHUTDOWN_GRACE);
+        let clean = shutdown_bridge(state.inner(), grace).await;
+        Ok(serde_json::json!({ "clean": clean }))
+    })
+    .await
+}
+
+/// 中止当前命令，返回实际执行的操作（协作取消是否成功、结束的进程、重启耗时与新进程）
+#[tauri::command]
+pub async fn bridge_abort(app: AppHandle) -> Result<AbortReport, BridgeError> {
+    guarded("bridge_abort", async move {
+        backend::resolve(&app, None)?.abort().await
+    })
+    .await
 }
 
 #[tauri::command]
 pub async fn bridge_init_status(
     state: tauri::State<'_, BridgeState>,
-) -> Result<serde_json::Value, String> {
-    let guard = state.inner().lock().await;
-    let ready = bridge_ready(&guard);
-    let error = guard.init_error.clone();
-    let initializing = guard.init_in_progress;
-    drop(guard);
-    Ok(serde_json::json!({ "ready": ready, "error": error, "initializing": initializing }))
+) -> Result<serde_json::Value, BridgeError> {
+    guarded("bridge_init_status", async move {
+        let guard = state.inner().lock().await;
+        let ready = bridge_ready(&guard);
+        let error = guard.init_error.clone();
+        let initializing = guard.init_in_progress;
+        let unhealthy = guard.unhealthy.clone();
+        drop(guard);
+        Ok(serde_json::json!({
+            "ready": ready,
+            "error": error,
+            "initializing": initializing,
+            "unhealthy": unhealthy,
+            "launch": last_launch(),
+        }))
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn bridge_capabilities(
+    state: tauri::State<'_, BridgeState>,
+) -> Result<Option<BridgeCapabilities>, BridgeError> {
+    guarded("bridge_capabilities", async move {
+        let guard = state.inner().lock().await;
+        Ok(guard.capabilities.clone())
+    })
+    .await
+}
+
+/// 显式启动 bridge（延迟启动模式下浏览历史结果时不必拉起）；已在运行时直接返回
+#[tauri::command]
+pub async fn bridge_start(
+    app: AppHandle,
+    state: tauri::State<'_, BridgeState>,
+) -> Result<serde_json::Value, BridgeError> {
+    guarded("bridge_start", async move {
+        if bridge_ready(&*state.inner().lock().await) {
+            return Ok(serde_json::json!({ "ready": true, "started": false }));
+        }
+        let started = std::time::Instant::now();
+        let result = ensure_bridge_ready(state.inner()).await;
+        match result {
+            Ok(()) => {
+                emit_init_result(&app, Ok(()), started.elapsed());
+                standby::refill(&app);
+            }
+            Err(e) => {
+                emit_init_result(&app, Err(&e), started.elapsed());
+                return Err(e);
+            }
+        }
+        Ok(serde_json::json!({ "ready": true, "started": true }))
+    })
+    .await
+}
+
+/// 停止 bridge 进程，释放 Python 与 JVM 占用的内存；之后的建模请求会重新拉起。
+/// 有任务运行时需 `force` 才会停止
+#[tauri::command]
+pub async fn bridge_stop(
+    app: AppHandle,
+    state: tauri::State<'_, BridgeState>,
+    force: Option<bool>,
+    grace_secs: Option<u64>,
+) -> Result<serde_json::Value, BridgeError> {
+    guarded("bridge_stop", async move {
+        {
+            let guard = state.inner().lock().await;
+            let busy = guard.active_streams > 0
+                || guard.dispatcher.as_ref().is_some_and(|d| d.in_flight() > 0);
+            if busy && !force.unwrap_or(false) {
+                return Err(BridgeError::from("bridge 正在执行任务；确认停止请传 force"));
+            }
+            if guard.child.is_none() && !guard.init_in_progress {
+                return Ok(serde_json::json!({ "stopped": false, "clean": true }));
+            }
+        }
+        standby::discard(&app).await;
+        let grace = grace_secs
+            .map(Duration::from_secs)
+            .unwrap_or(SHUTDOWN_GRACE);
+        let clean = shutdown_bridge(state.inner(), grace).await;
+        Ok(serde_json::json!({ "stopped": true, "clean": clean }))
+    })
+    .await
 }
 
 #[tauri::command]
 pub async fn bridge_ensure_ready(
     state: tauri::State<'_, BridgeState>,
-) -> Result<serde_json::Value, String> {
-    match ensure_bridge_ready(state.inner()).await {
-        Ok(()) => Ok(serde_json::json!({ "ready": true, "error": null, "initializing": false })),
-        Err(e) => Ok(serde_json::json!({ "ready": false, "error": e, "initializing": false })),
-    }
+) -> Result<serde_json::Value, BridgeError> {
+    guarded("bridge_ensure_ready", async move {
+        match ensure_bridge_ready(state.inner()).await {
+            Ok(()) => {
+                Ok(serde_json::json!({ "ready": true, "error": null, "initializing": false }))
+            }
+            Err(e) => Ok(serde_json::json!({ "ready": false, "error": e, "initializing": false })),
+        }
+    })
+    .await
 }
 
 #[tauri::command]
 pub async fn open_path(path: String) -> Result<(), String> {
-    let path = path.trim();
-    if path.is_empty() {
-        return Err("路径为空".to_string());
-    }
-    #[cfg(target_os = "windows")]
-    {
-        std::process::Command::new("cmd")
-            .args(["/C", "start", "", path])
-            .spawn()
-            .map_err(|e| e.to_string())?;
-    }
-    #[cfg(target_os = "macos")]
-    {
-        std::process::Command::new("open")
-            .arg(path)
-            .spawn()
-            .map_err(|e| e.to_string())?;
-    }
-    #[cfg(not(any(target_os = "windows", target_os = "macos")))]
-    {
-        std::process::Command::new("xdg-open")
-            .arg(path)
-            .spawn()
-            .map_err(|e| e.to_string())?;
-    }
-    Ok(())
+    guarded("open_path", async move {
+        let path = path.trim();
+        if path.is_empty() {
+            return Err("路径为空".to_string());
+        }
+        #[cfg(target_os = "windows")]
+        {
+            std::process::Command::new("cmd")
+                .args(["/C", "start", "", path])
+                .spawn()
+                .map_err(|e| e.to_string())?;
+        }
+        #[cfg(target_os = "macos")]
+        {
+            std::process::Command::new("open")
+                .arg(path)
+                .spawn()
+                .map_err(|e| e.to_string())?;
+        }
+        #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+        {
+            std::process::Command::new("xdg-open")
+                .arg(path)
+                .spawn()
+                .map_err(|e| e.to_string())?;
+        }
+        Ok(())
+    })
+    .await
 }
 
 #[tauri::command]
 pub async fn open_in_folder(path: String) -> Result<(), String> {
-    let path = path.trim();
-    if path.is_empty() {
-        return Err("路径为空".to_string());
-    }
-    let path_buf = std::path::PathBuf::from(path);
-    if !path_buf.exists() {
-        return Err("文件或目录不存在".to_string());
-    }
-    let dir = if path_buf.is_dir() {
-        path_buf
-    } else {
-        path_buf
-            .parent()
-            .map(|p| p.to_path_buf())
-            .unwrap_or(path_buf)
-    };
-    let abs = dir.canonicalize().map_err(|e| e.to_string())?;
-    let dir_str = abs.to_string_lossy().to_string();
+    guarded("open_in_folder", async move {
+        let path = path.trim();
+        if path.is_empty() {
+            return Err("路径为空".to_string());
+        }
+        let path_buf = std::path::PathBuf::from(path);
+        if !path_buf.exists() {
+            return Err("文件或目录不存在".to_string());
+        }
+        let dir = if path_buf.is_dir() {
+            path_buf
+        } else {
+            path_buf
+                .parent()
+                .map(|p| p.to_path_buf())
+                .unwrap_or(path_buf)
+        };
+        let abs = dir.canonicalize().map_err(|e| e.to_string())?;
+        let dir_str = abs.to_string_lossy().to_string();
 
-    #[cfg(target_os = "windows")]
-    {
-        std::process::Command::new("explorer")
-            .arg(&dir_str)
-            .spawn()
-            .map_err(|e| e.to_string())?;
-    }
-    #[cfg(target_os = "macos")]
-    {
-        std::process::Command::new("open")
-            .arg(&dir_str)
-            .spawn()
-            .map_err(|e| e.to_string())?;
-    }
-    #[cfg(not(any(target_os = "windows", target_os = "macos")))]
-    {
-        std::process::Command::new("xdg-open")
-            .arg(&dir_str)
-            .spawn()
-            .map_err(|e| e.to_string())?;
-    }
-    Ok(())
+        #[cfg(target_os = "windows")]
+        {
+            std::process::Command::new("explorer")
+                .arg(&dir_str)
+                .spawn()
+                .map_err(|e| e.to_string())?;
+        }
+        #[cfg(target_os = "macos")]
+        {
+            std::process::Command::new("open")
+                .arg(&dir_str)
+                .spawn()
+                .map_err(|e| e.to_string())?;
+        }
+        #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+        {
+            std::process::Command::new("xdg-open")
+                .arg(&dir_str)
+                .spawn()
+                .map_err(|e| e.to_string())?;
+        }
+        Ok(())
+    })
+    .await
 }
@@ -0,0 +1,146 @@
+use crate::bridge::{bridge_dead_error, BridgeExit};
+use crate::protocol::PROTOCOL_VERSION;
+use serde::Serialize;
+
+/// bridge 命令失败的类别，序列化为 `{"kind": "timeout", "message": ...}`，
+/// 与 panic 时的拒绝结构一致，前端按 `kind` 区分处理
+#[derive(Clone, Debug, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum BridgeError {
+    /// bridge 尚未启动或已被关闭
+    NotInitialized { message: String },
+    /// bridge 仍在启动，稍后重试即可；`retry_after_ms` 为建议的重试间隔
+    Starting {
+        retry_after_ms: u64,
+        message: String,
+    },
+    /// bridge 的协议版本或命令集与桌面端不兼容；bridge 未回报版本时为 None
+    Incompatible {
+        host_protocol: u32,
+        bridge_protocol: Option<u32>,
+        message: String,
+    },
+    /// 启动子进程或握手失败
+    SpawnFailed { message: String },
+    /// 请求或流式任务超时
+    Timeout { message: String },
+    /// bridge 输出无法解析
+    ProtocolError { message: String },
+    /// 子进程在请求进行中退出，或管道断开而退出状态未知；被信号终止或状态未知时 code 为 None
+    ChildExited { code: Option<i32>, message: String },
+    /// 子进程超出设置中的内存上限被结束
+    ResourceLimit { code: Option<i32>, message: String },
+    /// 请求被中止或在排队时取消
+    Aborted { message: String },
+    /// 发送前即被拒绝：策略拦截、参数校验、命令不受支持等
+    Rejected { message: String },
+}
+
+/// 启动中被拒绝的请求建议的重试间隔
+const STARTING_RETRY_MS: u64 = 1000;
+
+impl BridgeError {
+    pub fn message(&self) -> &str {
+        match self {
+            Self::NotInitialized { message }
+            | Self::Starting { message, .. }
+            | Self::Incompatible { message, .. }
+            | Self::SpawnFailed { message }
+            | Self::Timeout { message }
+            | Self::ProtocolError { message }
+            | Self::ChildExited { message, .. }
+            | Self::ResourceLimit { message, .. }
+            | Self::Aborted { message }
+            | Self::Rejected { message } => message,
+        }
+    }
+
+    fn message_mut(&mut self) -> &mut String {
+        match self {
+            Self::NotInitialized { message }
+            | Self::Starting { message, .. }
+            | Self::Incompatible { message, .. }
+            | Self::SpawnFailed { message }
+            | Self::Timeout { message }
+            | Self::ProtocolError { message }
+            | Self::ChildExited { message, .. }
+            | Self::ResourceLimit { message, .. }
+            | Self::Aborted { message }
+            | Self::Rejected { message } => message,
+        }
+    }
+
+    /// 保留类别，改写说明（附加上下文、stderr 末尾或启动环境）
+    pub fn map_message(mut self, f: impl FnOnce(&str) -> String) -> Self {
+        let message = f(self.message());
+        *self.message_mut() = message;
+        self
+    }
+
+    pub fn not_initialized() -> Self {
+        Self::NotInitialized {
+            message: "Bridge 未初始化".to_string(),
+        }
+    }
+
+    pub fn starting() -> Self {
+        Self::Starting {
+            retry_after_ms: STARTING_RETRY_MS,
+            message: "BridgeStarting: Python bridge 仍在启动，请稍后重试".to_string(),
+        }
+    }
+
+    pub fn incompatible(bridge_protocol: Option<u32>, message: String) -> Self {
+        Self::Incompatible {
+            host_protocol: PROTOCOL_VERSION,
+            bridge_protocol,
+            message,
+        }
+    }
+
+    pub fn spawn_failed(message: String) -> Self {
+        Self::SpawnFailed { message }
+    }
+
+    /// 子进程已退出：超出内存上限被结束时为 ResourceLimit，其余为 ChildExited
+    pub fn exited(exit: &BridgeExit) -> Self {
+        let message = bridge_dead_error(exit);
+        match exit.limit {
+            Some(_) => Self::ResourceLimit {
+                code: exit.exit_code,
+                message,
+            },
+            None => Self::ChildExited {
+                code: exit.exit_code,
+                message,
+            },
+        }
+    }
+}
+
+/// 命令层的参数校验、策略拦截等以字符串报错，统一视为发送前被拒绝；
+/// 其余类别由产生错误的地方直接构造
+impl From<String> for BridgeError {
+    fn from(message: String) -> Self {
+        Self::Rejected { message }
+    }
+}
+
+impl From<&str> for BridgeError {
+    fn from(message: &str) -> Self {
+        message.to_string().into()
+    }
+}
+
+/// 仍以字符串报错的内部调用方只取说明
+impl From<BridgeError> for String {
+    fn from(mut e: BridgeError) -> Self {
+        std::mem::take(e.message_mut())
+    }
+}
+
+impl std::fmt::Display for BridgeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.message())
+    }
+}
@@ -0,0 +1,284 @@
+use crate::artifacts::{ArtifactRegistry, TrackedArtifact};
+use crate::jobs::{JobRegistry, JobStatus};
+use crate::panics::guarded;
+use crate::store::now_ms;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+/// 单个任务最多记录的操作数，超出后只计数
+const MAX_ACTIONS: usize = 200;
+/// 计入变更摘要的 bridge 事件：实际执行的步骤、材料与物理场设置
+const ACTION_EVENTS: &[&str] = &["step_end", "material_end", "coupling_added", "action_end"];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FileChangeKind {
+    /// 任务前未登记的模型文件
+    Created,
+    /// 已登记的模型文件内容发生变化
+    Modified,
+    /// 重新保存但内容与登记时一致
+    Unchanged,
+    /// 导出的结果表等附属文件
+    Exported,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct FileChange {
+    pub path: String,
+    pub kind: FileChangeKind,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub previous_sha256: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sha256: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub previous_size: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub size: Option<u64>,
+}
+
+/// bridge 报告的一次操作
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ActionRecord {
+    /// 事件类型，如 step_end / material_end
+    pub event: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub step_type: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub action: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<String>,
+    pub at: u64,
+}
+
+/// 一次任务做了什么：文件变化与 bridge 报告的操作，结束时写入任务记录
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct JobChanges {
+    /// 一句话概括，如「新建 1 个模型，修改 1 个模型；执行 6 项操作」
+    pub headline: String,
+    pub files: Vec<FileChange>,
+    pub actions: Vec<ActionRecord>,
+    /// 超出记录上限而未保留的操作数
+    #[serde(default)]
+    pub actions_dropped: usize,
+    /// 任务仍在运行时为 false
+    #[serde(default)]
+    pub complete: bool,
+    pub generated_at: u64,
+}
+
+impl JobChanges {
+    fn count(&self, kind: FileChangeKind) -> usize {
+        self.files.iter().filter(|f| f.kind == kind).count()
+    }
+
+    fn summarize(&mut self, status: Option<JobStatus>) {
+        let mut parts = Vec::new();
+        for (kind, label) in [
+            (FileChangeKind::Created, "新建"),
+            (FileChangeKind::Modified, "修改"),
+            (FileChangeKind::Exported, "导出"),
+        ] {
+            let n = self.count(kind);
+            if n > 0 {
+                let noun = if kind == FileChangeKind::Exported {
+                    "个结果文件"
+                } else {
+                    "个模型"
+                };
+                parts.push(format!("{} {} {}", label, n, noun));
+            }
+        }
+        let mut headline = if parts.is_empty() {
+            "未改动文件".to_string()
+        } else {
+            parts.join("，")
+        };
+        let actions = self.actions.len() + self.actions_dropped;
+        if actions > 0 {
+            headline.push_str(&format!("；执行 {} 项操作", actions));
+        }
+        match status {
+            Some(JobStatus::Failed) => headline.push_str("（任务失败）"),
+            Some(JobStatus::Aborted) => headline.push_str("（任务已中止）"),
+            _ => {}
+        }
+        self.headline = headline;
+        self.generated_at = now_ms();
+    }
+}
+
+fn text(data: &Value, key: &str) -> Option<String> {
+    data.get(key)
+        .and_then(|v| v.as_str())
+        .filter(|s| !s.is_empty())
+        .map(String::from)
+}
+
+/// 运行中任务的变更收集：流式事件与产物登记时写入，任务结束时汇总进任务记录
+#[derive(Default)]
+pub struct ChangeTracker {
+    jobs: Mutex<HashMap<String, JobChanges>>,
+}
+
+impl ChangeTracker {
+    fn with(&self, request_id: &str, f: impl FnOnce(&mut JobChanges)) {
+        let mut jobs = self.jobs.lock().unwrap_or_else(|e| e.into_inner());
+        f(jobs.entry(request_id.to_string()).or_default());
+    }
+
+    /// 记录一条 bridge 事件；只保留实际执行的操作（规划阶段的 step_end 忽略）
+    pub fn observe(&self, request_id: &str, event: &Value) {
+        let Some(kind) = event.get("type").and_then(|v| v.as_str()) else {
+            return;
+        };
+        if !ACTION_EVENTS.contains(&kind) {
+            return;
+        }
+        let data = &event["data"];
+        let status = text(data, "status");
+        if status.as_deref() == Some("planned") {
+            return;
+        }
+        let record = ActionRecord {
+            event: kind.to_string(),
+            step_type: text(data, "step_type").or_else(|| text(data, "type")),
+            action: text(data, "action"),
+            message: text(data, "message"),
+            status,
+            at: now_ms(),
+        };
+        self.with(request_id, |c| {
+            if c.actions.len() < MAX_ACTIONS {
+                c.actions.push(record);
+            } else {
+                c.actions_dropped += 1;
+            }
+        });
+    }
+
+    /// 模型文件重新登记后调用；`previous` 为登记前的记录
+    pub fn record_model(
+        &self,
+        request_id: &str,
+        previous: Option<&TrackedArtifact>,
+        current: &TrackedArtifact,
+    ) {
+        let kind = match previous {
+            None => FileChangeKind::Created,
+            Some(p) if p.sha256 == current.sha256 => FileChangeKind::Unchanged,
+            Some(_) => FileChangeKind::Modified,
+        };
+        let change = FileChange {
+            path: current.path.clone(),
+            kind,
+            previous_sha256: previous.map(|p| p.sha256.clone()),
+            sha256: Some(current.sha256.clone()),
+            previous_size: previous.map(|p| p.size),
+            size: Some(current.size),
+        };
+        self.push_file(request_id, change);
+    }
+
+    pub fn record_export(&self, request_id: &str, path: &str) {
+        let size = std::fs::metadata(path).ok().map(|m| m.len());
+        self.push_file(
+            request_id,
+            FileChange {
+                path: path.to_string(),
+                kind: FileChangeKind::Exported,
+                previous_sha256: None,
+                sha256: None,
+                previous_size: None,
+                size,
+            },
+        );
+    }
+
+    /// 同一文件多次保存时合并：保留最早的基准与最新的结果
+    fn push_file(&self, request_id: &str, change: FileChange) {
+        self.with(request_id, |c| {
+            match c.files.iter_mut().find(|f| f.path == change.path) {
+                Some(f) => {
+                    if f.kind != FileChangeKind::Created && change.kind != FileChangeKind::Exported
+                    {
+                        f.kind = if f.previous_sha256 == change.sha256 {
+                            FileChangeKind::Unchanged
+                        } else {
+                            FileChangeKind::Modified
+                        };
+                    }
+                    f.sha256 = change.sha256;
+                    f.size = change.size;
+                }
+                None => c.files.push(change),
+            }
+        });
+    }
+
+    /// 运行中任务当前已收集到的变更
+    fn snapshot(&self, request_id: &str) -> Option<JobChanges> {
+        let jobs = self.jobs.lock().unwrap_or_else(|e| e.into_inner());
+        let mut changes = jobs.get(request_id)?.clone();
+        changes.summarize(None);
+        Some(changes)
+    }
+
+    /// 任务结束：汇总并移出收集表
+    pub fn finish(&self, request_id: &str, status: JobStatus) -> JobChanges {
+        let mut changes = self
+            .jobs
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(request_id)
+            .unwrap_or_default();
+        changes.complete = true;
+        changes.summarize(Some(status));
+        changes
+    }
+}
+
+/// 模型文件登记前后对比，记入任务的变更摘要
+pub fn register_model(
+    tracker: &ChangeTracker,
+    artifacts: &ArtifactRegistry,
+    request_id: &str,
+    path: &str,
+    project: Option<&str>,
+) -> Result<(), String> {
+    let previous = artifacts.get(path);
+    let current = artifacts.register(path, Some(request_id), project)?;
+    tracker.record_model(request_id, previous.as_ref(), &current);
+    Ok(())
+}
+
+/// 任务做了什么：新建/修改的模型、导出的结果文件与 bridge 报告的操作。
+/// 运行中的任务返回目前为止的变更
+#[tauri::command]
+pub async fn job_changes(
+    jobs: tauri::State<'_, JobRegistry>,
+    tracker: tauri::State<'_, ChangeTracker>,
+    job_id: String,
+) -> Result<JobChanges, String> {
+    guarded("job_changes", async move {
+        let job = jobs
+            .get(&job_id)
+            .ok_or_else(|| format!("未找到任务: {}", job_id))?;
+        if let Some(changes) = job.changes {
+            return Ok(changes);
+        }
+        if !job.status.is_finished() {
+            return Ok(tracker.snapshot(&job_id).unwrap_or_else(|| {
+                let mut empty = JobChanges::default();
+                empty.summarize(None);
+                empty
+            }));
+        }
+        Err(format!("任务 {} 没有变更记录（早于变更摘要功能）", job_id))
+    })
+    .await
+}
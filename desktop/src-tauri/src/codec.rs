@@ -0,0 +1,96 @@
+use crate::dispatcher::{classify, Incoming, PROTOCOL_ERROR};
+use crate::framing::Framing;
+use serde_json::Value;
+
+/// bridge 输出的编码。请求体量小，始终以 JSON 发送；协商为 MessagePack 后，
+/// bridge 的响应与事件以 MessagePack 编码，结果数组、几何数据等大段数值不再经过文本转换
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Codec {
+    Json,
+    /// 仅在长度前缀分帧下可用
+    MsgPack,
+}
+
+impl Codec {
+    /// hello 中按偏好顺序提供的编码；按行分帧时只能用 JSON
+    pub fn offered(framings: &[&str]) -> Vec<&'static str> {
+        if framings.contains(&Framing::LengthPrefixed.name()) {
+            vec!["msgpack", "json"]
+        } else {
+            vec!["json"]
+        }
+    }
+
+    /// hello 回复选定的编码；bridge 不回报（缺少 msgpack 模块或旧版本）时为 JSON
+    pub fn from_reply(reply: &Value, framing: Framing) -> Result<Self, String> {
+        match reply.get("codec").and_then(|v| v.as_str()) {
+            None | Some("json") => Ok(Self::Json),
+            Some("msgpack") if framing == Framing::LengthPrefixed => Ok(Self::MsgPack),
+            Some("msgpack") => Err("MessagePack 编码需要长度前缀分帧".to_string()),
+            Some(other) => Err(format!("bridge 选择了未提供的编码 `{}`", other)),
+        }
+    }
+
+    /// 解码一条消息。以 `{` 开头的消息按 JSON 解析：bridge 遇到无法用 MessagePack
+    /// 表示的值时会退回 JSON，顶层为整数 123 的 MessagePack 不是合法消息，不会混淆
+    pub fn decode(self, data: &[u8]) -> Option<Incoming> {
+        if self == Self::Json || data.first() == Some(&b'{') {
+            return classify(&String::from_utf8_lossy(data));
+        }
+        Some(match rmp_serde::from_slice::<Value>(data) {
+            Ok(v) if v.is_object() => Ok(v),
+            Ok(_) => Err(format!("{}: MessagePack 消息不是对象", PROTOCOL_ERROR)),
+            Err(e) => Err(format!(
+                "{}: MessagePack 解码失败: {}（{} 字节）",
+                PROTOCOL_ERROR,
+                e,
+                data.len()
+            )),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    proptest! {
+        /// 任意字节都能解码或给出 ProtocolError；MessagePack 模式下每条消息都有结果
+        #[test]
+        fn decode_is_total(
+            bytes in prop::collection::vec(any::<u8>(), 0..512),
+            msgpack in any::<bool>(),
+        ) {
+            let codec = if msgpack { Codec::MsgPack } else { Codec::Json };
+            match codec.decode(&bytes) {
+                Some(Ok(v)) => prop_assert!(v.is_object()),
+                Some(Err(e)) => prop_assert!(e.starts_with(PROTOCOL_ERROR)),
+                None => {
+                    let json_like = bytes.first() == Some(&b'{');
+                    prop_assert!(!msgpack || json_like);
+                }
+            }
+        }
+
+        /// bridge 以 MessagePack 编码的对象原样解回
+        #[test]
+        fn msgpack_objects_round_trip(
+            bytes in prop::collection::vec(any::<u8>(), 0..256),
+            iteration in 0u32..1000,
+        ) {
+            let v = serde_json::json!({
+                "_id": format!("r{}", iteration),
+                "_event": true,
+                "data": String::from_utf8_lossy(&bytes),
+                "raw": bytes,
+                "iteration": iteration,
+            });
+            let data = rmp_serde::to_vec_named(&v).unwrap();
+            match Codec::MsgPack.decode(&data) {
+                Some(Ok(decoded)) => prop_assert_eq!(decoded, v),
+                other => prop_assert!(false, "解码失败: {:?}", other),
+            }
+        }
+    }
+}
@@ -0,0 +1,303 @@
+use crate::bridge::BridgeState;
+use crate::panics::guarded;
+use crate::policy::CommandPolicy;
+use serde::Serialize;
+use serde_json::{json, Map, Value};
+
+/// 参数名与前端 invoke 时一致（Tauri 默认把 snake_case 参数转成 camelCase）
+struct Param {
+    name: &'static str,
+    /// JSON Schema 基本类型：string / integer / number / boolean / object / array
+    ty: &'static str,
+    required: bool,
+}
+
+const fn req(name: &'static str, ty: &'static str) -> Param {
+    Param {
+        name,
+        ty,
+        required: true,
+    }
+}
+
+const fn opt(name: &'static str, ty: &'static str) -> Param {
+    Param {
+        name,
+        ty,
+        required: false,
+    }
+}
+
+struct TauriCommand {
+    name: &'static str,
+    category: &'static str,
+    description: &'static str,
+    params: &'static [Param],
+}
+
+const fn cmd(
+    name: &'static str,
+    category: &'static str,
+    description: &'static str,
+    params: &'static [Param],
+) -> TauriCommand {
+    TauriCommand {
+        name,
+        category,
+        description,
+        params,
+    }
+}
+
+/// 与 lib.rs 中 `generate_handler!` 注册的命令保持一致；新增命令时在此登记
+#[rustfmt::skip]
+const TAURI_COMMANDS: &[TauriCommand] = &[
+    cmd("list_commands", "通用", "列出全部可用命令及参数说明", &[]),
+    cmd("get_app_paths", "通用", "查看应用数据、配置、日志、资源与输出目录", &[]),
+    cmd("bridge_send", "Bridge", "向 bridge 发送一条命令并等待结果", &[req("cmd", "string"), req("payload", "object"), opt("timeoutSecs", "integer"), opt("session", "string")]),
+    cmd("bridge_send_stream", "Bridge", "发送命令并以事件流接收进度与结果", &[req("cmd", "string"), req("payload", "object"), opt("requestId", "string"), opt("session", "string")]),
+    cmd("bridge_abort", "Bridge", "中止当前正在执行的 bridge 命令", &[]),
+    cmd("bridge_cancel_stream", "Bridge", "协作取消一条流式请求，未确认时才重启 bridge", &[req("streamId", "string"), opt("graceSecs", "integer")]),
+    cmd("bridge_queue_status", "Bridge", "查看正在执行与排队中的 bridge 请求", &[]),
+    cmd("bridge_queue_cancel", "Bridge", "取消一条尚未发送的排队请求", &[req("ticket", "string")]),
+    cmd("bridge_shutdown", "Bridge", "通知 bridge 清理并退出，超时后强制结束", &[opt("graceSecs", "integer")]),
+    cmd("abort_strategy_get", "Bridge", "查看中止策略（协作取消或立即结束）", &[]),
+    cmd("abort_strategy_set", "Bridge", "设置中止策略与协作取消的等待秒数", &[req("strategy", "object")]),
+    cmd("job_set_abort_strategy", "任务", "为单个任务覆盖中止策略", &[req("jobId", "string"), opt("strategy", "object")]),
+    cmd("bridge_init_status", "Bridge", "查询 bridge 初始化状态", &[]),
+    cmd("bridge_health", "Bridge", "查看 bridge 当前状态与在途请求数，不发送请求", &[opt("session", "string")]),
+    cmd("bridge_capabilities", "Bridge", "查询 bridge 握手时声明的命令", &[]),
+    cmd("bridge_ensure_ready", "Bridge", "确保 bridge 已启动，必要时重新初始化", &[]),
+    cmd("bridge_start", "Bridge", "启动 bridge（延迟启动模式下按需调用）", &[]),
+    cmd("validate_request", "Bridge", "只执行发送前检查，返回规范化请求或具体违规项", &[req("cmd", "string"), req("payload", "object"), opt("stream", "boolean")]),
+    cmd("bridge_stop", "Bridge", "停止 bridge 释放内存，下次建模请求时重新启动", &[opt("force", "boolean"), opt("graceSecs", "integer")]),
+    cmd("bridge_standby_status", "Bridge", "查看预热备用 bridge 进程的状态（中止后直接接替）", &[]),
+    cmd("bridge_stats", "Bridge", "查看 bridge 进程（含 JVM）的 CPU、内存、运行时长与请求计数", &[opt("session", "string")]),
+    cmd("bridge_session_create", "Bridge", "新建具名 bridge 会话（独立进程），首个请求时启动", &[opt("id", "string"), opt("label", "string"), opt("project", "string")]),
+    cmd("bridge_session_list", "Bridge", "列出默认会话与具名会话的运行状态", &[]),
+    cmd("remote_connect_ssh", "Bridge", "经 ssh 在远程主机上启动 bridge 并登记为具名会话", &[req("host", "string"), opt("user", "string"), opt("port", "integer"), opt("identityFile", "string"), req("remoteDir", "string"), opt("python", "string"), opt("id", "string"), opt("label", "string"), opt("project", "string")]),
+    cmd("bridge_session_close", "Bridge", "关闭具名 bridge 会话并结束其进程", &[req("id", "string"), opt("force", "boolean"), opt("graceSecs", "integer")]),
+    cmd("bridge_integrity_status", "Bridge", "查询 bridge 文件完整性校验状态", &[]),
+    cmd("bridge_integrity_override", "Bridge", "开发时跳过完整性校验", &[req("enabled", "boolean")]),
+    cmd("bridge_benchmark", "Bridge", "测量 bridge 往返延迟、吞吐量与事件推送速率", &[opt("iterations", "integer"), opt("payloadSize", "integer")]),
+    cmd("bridge_set_session_options", "Bridge", "设置本次会话的临时请求覆盖（详细日志、dry-run、LLM 后端/模型），不持久化", &[opt("opts", "object")]),
+    cmd("bridge_get_session_options", "Bridge", "查看本次会话的临时请求覆盖", &[]),
+    cmd("bridge_timeouts_get", "Bridge", "查看 bridge_send 的等待时间与流式任务的总时长、静默上限", &[]),
+    cmd("bridge_timeouts_set", "Bridge", "设置 bridge_send 的等待秒数与流式任务的总时长、静默上限", &[req("config", "object")]),
+    cmd("bridge_get_stderr", "Bridge", "查看 bridge 进程 stderr 的最近若干行", &[opt("lines", "integer")]),
+    cmd("bridge_set_log_level", "Bridge", "运行时切换桌面端与 bridge 的日志详细程度（error/warn/info/debug/trace），不需重启", &[req("level", "string")]),
+    cmd("bridge_last_exit", "Bridge", "查看 bridge 最近一次意外退出的退出码、原因与 stderr", &[opt("session", "string")]),
+    cmd("selftest_get", "Bridge", "查看启动自检设置与最近结果", &[]),
+    cmd("selftest_set", "Bridge", "开启或关闭启动自检", &[req("enabled", "boolean")]),
+    cmd("selftest_run", "Bridge", "手动运行 COMSOL 自检", &[]),
+    cmd("stream_set_rate", "Bridge", "限制流式请求的事件推送频率", &[req("requestId", "string"), opt("maxEventsPerSec", "number")]),
+    cmd("debug_console_open", "调试", "打开 bridge 原始流量调试控制台", &[]),
+    cmd("debug_console_close", "调试", "关闭调试控制台", &[]),
+    cmd("debug_console_set_paused", "调试", "暂停或恢复调试控制台实时推送", &[req("paused", "boolean")]),
+    cmd("debug_console_history", "调试", "读取调试控制台的历史流量", &[opt("sinceSeq", "integer"), opt("direction", "string"), opt("filter", "string")]),
+    cmd("diagnostics_panics", "调试", "查看本次运行捕获的 panic 次数与最近的回溯", &[]),
+    cmd("bridge_recording_start", "调试", "开始把 bridge 请求与响应（含时间与耗时）录制为 NDJSON 文件", &[]),
+    cmd("bridge_recording_stop", "调试", "停止录制 bridge 请求与响应", &[]),
+    cmd("bridge_recording_export", "调试", "导出录制文件，便于附在问题报告中", &[opt("id", "string"), req("path", "string")]),
+    cmd("bridge_replay_start", "调试", "用录制的 NDJSON 回放代替 Python bridge 驱动界面，无需安装 COMSOL", &[req("path", "string"), opt("speed", "number")]),
+    cmd("bridge_replay_stop", "调试", "退出回放模式并重新启动真实的 bridge", &[]),
+    cmd("capture_repro_state", "调试", "导出包含 bridge 流量、任务请求、环境与设置的复现包", &[opt("jobId", "string"), opt("path", "string")]),
+    cmd("jobs_list", "任务", "列出任务历史", &[opt("project", "string"), opt("limit", "integer")]),
+    cmd("job_get", "任务", "查看单个任务详情", &[req("jobId", "string")]),
+    cmd("job_changes", "任务", "查看任务的变更摘要：新建/修改的模型、导出的结果文件与执行的操作", &[req("jobId", "string")]),
+    cmd("job_rerun", "任务", "以历史任务的请求为基础、合并修改后重新提交", &[req("jobId", "string"), opt("overrides", "object"), opt("requestId", "string")]),
+    cmd("pipeline_submit", "任务", "提交带依赖关系的多步任务流水线", &[req("steps", "array"), opt("name", "string"), opt("project", "string")]),
+    cmd("pipelines_list", "任务", "列出流水线历史", &[opt("limit", "integer")]),
+    cmd("pipeline_get", "任务", "查看单条流水线及各步骤状态", &[req("pipelineId", "string")]),
+    cmd("pipeline_retry", "任务", "重试流水线中失败的步骤及其下游", &[req("pipelineId", "string"), opt("step", "string")]),
+    cmd("pipeline_cancel", "任务", "取消流水线，中止正在执行的步骤", &[req("pipelineId", "string")]),
+    cmd("compare_jobs", "任务", "并排比较两个任务的参数、结果标量与产物", &[req("idA", "string"), req("idB", "string")]),
+    cmd("compare_images", "任务", "比较两个任务导出的结果图：SSIM 评分与标出差异的 PNG", &[req("a", "string"), req("b", "string"), opt("out", "string"), opt("gpu", "boolean")]),
+    cmd("search_everything", "工具", "在模型、任务、产物与对话会话中全文搜索，按相关度排序", &[req("query", "string"), opt("limit", "number"), opt("kinds", "string")]),
+    cmd("search_reindex", "工具", "立即更新搜索索引中有变化的文档", &[]),
+    cmd("results_trend", "任务", "查看某个关键结果在历史任务中的变化趋势", &[req("metric", "string"), opt("project", "string"), opt("limit", "integer")]),
+    cmd("queue_set_paused", "任务", "暂停或恢复任务队列", &[req("paused", "boolean")]),
+    cmd("job_progress_current", "任务", "查询当前任务的聚合进度", &[]),
+    cmd("job_eta", "任务", "根据相似任务的历史耗时估算剩余时间", &[req("jobId", "string")]),
+    cmd("export_job_script", "任务", "把任务导出为可复现的脚本", &[req("jobId", "string"), req("format", "string"), req("path", "string")]),
+    cmd("event_ack", "通知", "确认已收到一条通知", &[req("deliveryId", "integer")]),
+    cmd("events_unacked", "通知", "列出尚未确认的通知", &[]),
+    cmd("notifications_list", "通知", "列出通知历史", &[]),
+    cmd("notifier_get", "通知", "查看任务结束的邮件/webhook 通知配置", &[]),
+    cmd("notifier_set", "通知", "设置任务结束的邮件/webhook 通知渠道", &[req("config", "object")]),
+    cmd("notifier_test", "通知", "向通知渠道发送测试消息", &[opt("sink", "string")]),
+    cmd("migration_report", "通知", "查看启动时的数据迁移报告", &[]),
+    cmd("artifacts_list", "产物", "列出已登记的输出文件", &[]),
+    cmd("artifact_register", "产物", "登记一个输出文件", &[req("path", "string"), opt("project", "string")]),
+    cmd("postprocessors_list", "产物", "列出产物后处理步骤及其配置", &[]),
+    cmd("postprocessor_set", "产物", "启用/停用某个后处理步骤或修改其适用扩展名与参数", &[req("id", "string"), req("settings", "object")]),
+    cmd("postprocess_status", "产物", "查看某个产物的后处理结果", &[req("path", "string")]),
+    cmd("postprocess_run", "产物", "对文件重新运行适用的后处理步骤", &[req("path", "string")]),
+    cmd("artifact_confirm_overwrite", "产物", "确认覆盖已被外部修改的输出文件", &[req("path", "string")]),
+    cmd("artifacts_verify", "产物", "立即校验所有已登记产物是否存在、哈希是否一致", &[]),
+    cmd("artifact_lock_status", "产物", "检查文件是否被其他程序占用及占用进程", &[req("path", "string")]),
+    cmd("artifact_context_menu", "产物", "在当前窗口弹出产物右键菜单（打开、显示、按版本用 COMSOL 打开、复制路径、导出、移到回收站）", &[req("path", "string"), opt("x", "number"), opt("y", "number")]),
+    cmd("backups_list", "产物", "列出文件的写前备份", &[opt("path", "string")]),
+    cmd("attachments_ingest", "文件", "校验并复制提示词附件到项目附件库", &[req("paths", "array"), opt("project", "string")]),
+    cmd("attachments_list", "文件", "列出项目附件库中的附件", &[opt("project", "string")]),
+    cmd("attachment_remove", "文件", "从附件库删除附件", &[req("id", "string")]),
+    cmd("sanitize_filename", "文件", "把文件名清理为各平台都能创建的形式（非法字符、保留名、结尾的点与空格、超长）", &[req("name", "string"), opt("fallback", "string")]),
+    cmd("restore_backup", "产物", "从备份恢复文件", &[req("path", "string"), req("index", "integer")]),
+    cmd("backup_get_policy", "产物", "查看备份保留策略", &[]),
+    cmd("backup_set_policy", "产物", "设置备份保留策略", &[req("maxCount", "integer"), req("maxTotalMb", "integer")]),
+    cmd("storage_usage", "存储", "统计各项目的磁盘占用", &[]),
+    cmd("logs_usage", "存储", "统计各类日志的磁盘占用", &[]),
+    cmd("logs_get_retention", "存储", "查看日志轮转与保留策略", &[]),
+    cmd("logs_set_retention", "存储", "设置日志切段大小、压缩与保留天数、总量上限", &[req("policy", "object")]),
+    cmd("logs_rotate", "存储", "立即按策略轮转并清理日志", &[]),
+    cmd("maintenance_status", "存储", "查看后台维护（缓存清理、完整性巡检、索引压缩、日志轮转）的运行记录与下次到期时间", &[]),
+    cmd("maintenance_run", "存储", "立即运行后台维护；有任务运行时等空闲后运行", &[opt("task", "string"), opt("force", "boolean")]),
+    cmd("maintenance_defer", "存储", "推迟后台维护若干分钟，0 为取消推迟", &[opt("task", "string"), req("minutes", "integer")]),
+    cmd("storage_get_config", "存储", "查看存储配额设置", &[]),
+    cmd("storage_set_quota", "存储", "设置项目存储配额", &[opt("project", "string"), opt("quotaMb", "integer")]),
+    cmd("storage_set_failed_outputs", "存储", "设置失败任务半成品输出的处理方式（保留/隔离/删除）", &[req("mode", "string")]),
+    cmd("cleanup_suggestions", "存储", "给出可清理的大文件建议", &[opt("project", "string"), opt("limit", "integer")]),
+    cmd("job_remove_work_dir", "存储", "删除已结束任务的独立工作目录及其中的产物", &[req("request_id", "string")]),
+    cmd("comsol_installs", "COMSOL", "检测本机 COMSOL 安装", &[]),
+    cmd("refresh_environment_cache", "COMSOL", "丢弃并重新探测 COMSOL 安装、Python 解释器与 JDK 的缓存", &[]),
+    cmd("open_in_comsol", "COMSOL", "用 COMSOL 打开模型", &[req("path", "string"), opt("version", "string")]),
+    cmd("mphserver_status", "COMSOL", "查看托管 mphserver 状态", &[]),
+    cmd("mphserver_start", "COMSOL", "启动托管 mphserver", &[opt("version", "string"), opt("port", "integer")]),
+    cmd("mphserver_stop", "COMSOL", "停止托管 mphserver", &[]),
+    cmd("license_status", "COMSOL", "查看许可证席位配置与当前占用、等待席位的任务", &[]),
+    cmd("license_set_seats", "COMSOL", "设置可同时使用的许可证席位数（null 取消限制）", &[opt("seats", "integer")]),
+    cmd("get_service_endpoints", "COMSOL", "列出本地服务的实际地址与端口段，供外部工具发现", &[]),
+    cmd("service_port_range_set", "COMSOL", "配置本地服务自动选端口的端口段", &[req("service", "string"), opt("range", "object")]),
+    cmd("connect_comsol_server", "COMSOL", "连接远程 COMSOL 服务器", &[req("host", "string"), req("port", "integer"), opt("project", "string")]),
+    cmd("disconnect_comsol_server", "COMSOL", "断开远程 COMSOL 服务器", &[opt("project", "string")]),
+    cmd("comsol_server_profiles", "COMSOL", "列出已保存的服务器连接", &[]),
+    cmd("jdk_check_update", "COMSOL", "检查内置 JDK 更新", &[]),
+    cmd("jdk_update", "COMSOL", "下载并切换到新版 JDK", &[]),
+    cmd("geometry_prepare", "结果", "准备 STL/OBJ 几何预览", &[req("path", "string"), opt("unit", "string"), opt("maxTriangles", "integer")]),
+    cmd("table_open", "结果", "打开 CSV/COMSOL 表格", &[req("path", "string")]),
+    cmd("table_rows", "结果", "分页读取表格行，可排序与过滤", &[req("handle", "integer"), req("offset", "integer"), req("count", "integer"), opt("sort", "object"), opt("filter", "object")]),
+    cmd("table_close", "结果", "关闭表格句柄", &[req("handle", "integer")]),
+    cmd("convert_value", "工具", "单位换算", &[req("value", "number"), req("from", "string"), req("to", "string")]),
+    cmd("parse_quantity", "工具", "解析带单位的数值", &[req("text", "string")]),
+    cmd("materials_search", "工具", "模糊搜索材料库", &[req("query", "string"), opt("limit", "integer")]),
+    cmd("materials_refresh", "工具", "重新拉取材料库", &[]),
+    cmd("recent_models_list", "文件", "列出最近打开的模型", &[]),
+    cmd("recent_models_add", "文件", "添加最近模型记录", &[req("path", "string"), opt("project", "string")]),
+    cmd("recent_models_clear", "文件", "清空最近模型列表", &[]),
+    cmd("remap_root", "文件", "把产物登记、最近模型与任务记录中旧根目录下的路径改到新根目录（可只预览）", &[req("oldPrefix", "string"), req("newPrefix", "string"), opt("dryRun", "boolean")]),
+    cmd("open_path", "文件", "用系统默认程序打开文件", &[req("path", "string")]),
+    cmd("open_in_folder", "文件", "在文件管理器中显示文件", &[req("path", "string")]),
+    cmd("dialog_open_file", "文件", "按用途记住起始目录的打开文件对话框", &[req("purpose", "string"), opt("project", "string"), opt("title", "string"), opt("filters", "array"), opt("locale", "string")]),
+    cmd("dialog_save_file", "文件", "按用途记住起始目录的保存文件对话框", &[req("purpose", "string"), opt("project", "string"), opt("title", "string"), opt("defaultName", "string"), opt("filters", "array"), opt("locale", "string")]),
+    cmd("dialog_dirs_get", "文件", "查看各用途记住的对话框目录", &[]),
+    cmd("dialog_dir_set", "文件", "指定或清除某用途的起始目录", &[req("purpose", "string"), opt("project", "string"), opt("dir", "string")]),
+    cmd("viewers_get", "文件", "查看按扩展名配置的外部查看器", &[]),
+    cmd("viewers_set", "文件", "设置扩展名对应的外部查看器", &[req("ext", "string"), opt("app", "string")]),
+    cmd("project_notes_get", "文件", "读取项目笔记（Markdown），可取历史版本", &[req("project", "string"), opt("version", "integer")]),
+    cmd("project_notes_set", "文件", "保存项目笔记，内容变化时自动存档旧版本", &[req("project", "string"), req("content", "string"), opt("checkpoint", "boolean")]),
+    cmd("open_in_viewer", "文件", "用外部查看器打开任务产物或项目输出目录下的文件", &[req("path", "string"), opt("viewer", "string"), opt("project", "string")]),
+    cmd("settings_get", "设置", "查看设置文件的生效取值、待重启改动与解析问题", &[]),
+    cmd("settings_reload", "设置", "立即重新读取设置文件", &[]),
+    cmd("settings_effective", "设置", "查看本机设置与项目设置合并后的取值及各项来源", &[opt("project", "string")]),
+    cmd("policy_get", "设置", "查看命令策略与只读模式", &[]),
+    cmd("policy_set", "设置", "设置命令白名单与只读模式，未传的项保持不变", &[opt("allowedCmds", "array"), opt("clearAllowedCmds", "boolean"), opt("readOnly", "boolean")]),
+    cmd("sandbox_get", "设置", "查看文件沙箱设置", &[]),
+    cmd("sandbox_set", "设置", "设置文件沙箱", &[req("enabled", "boolean"), req("roots", "array")]),
+    cmd("permission_respond", "设置", "答复 bridge 请求的宿主操作（打开网址、读文件、运行命令），可按项目记住", &[req("promptId", "string"), req("allow", "boolean"), opt("remember", "boolean")]),
+    cmd("permissions_list", "设置", "查看按项目记住的宿主操作授权", &[]),
+    cmd("permission_forget", "设置", "清除记住的宿主操作授权", &[opt("project", "string"), opt("rule", "string")]),
+    cmd("profiles_list", "设置", "列出用户档案", &[]),
+    cmd("switch_profile", "设置", "切换用户档案并重启", &[req("name", "string")]),
+    cmd("profile_delete", "设置", "删除用户档案", &[req("name", "string")]),
+    cmd("progress_window_open", "窗口", "打开迷你进度窗口", &[opt("corner", "string"), opt("clickThrough", "boolean")]),
+    cmd("progress_window_close", "窗口", "关闭迷你进度窗口", &[]),
+    cmd("progress_window_snap", "窗口", "把进度窗口吸附到屏幕角落", &[req("corner", "string")]),
+    cmd("progress_window_set_click_through", "窗口", "设置进度窗口鼠标穿透", &[req("enabled", "boolean")]),
+    cmd("apply_window_icon", "窗口", "为当前窗口设置应用图标", &[]),
+    cmd("window_subscribe", "窗口", "当前窗口只接收指定项目/任务的任务事件", &[opt("projects", "array"), opt("jobs", "array")]),
+    cmd("window_unsubscribe", "窗口", "取消订阅，当前窗口恢复接收全部任务事件", &[]),
+    cmd("window_subscriptions", "窗口", "列出各窗口的任务事件订阅", &[]),
+    cmd("window_zoom_get", "窗口", "当前显示器 DPI 与生效的界面缩放", &[]),
+    cmd("window_zoom_set", "窗口", "设置并保存当前显示器 DPI 的界面缩放", &[req("zoom", "number"), opt("allDpi", "boolean")]),
+    cmd("window_zoom_reset", "窗口", "清除当前显示器 DPI 的界面缩放", &[opt("allDpi", "boolean")]),
+];
+
+#[derive(Clone, Debug, Serialize)]
+pub struct CommandInfo {
+    pub name: String,
+    /// `tauri`：前端直接 invoke；`bridge`：通过 bridge_send 发送
+    pub source: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub category: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// 参数的 JSON Schema；bridge 未声明时为 None
+    pub params: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version: Option<u32>,
+    /// 当前策略（白名单、只读模式）是否允许执行
+    pub allowed: bool,
+}
+
+fn params_schema(params: &[Param]) -> Value {
+    let mut properties = Map::new();
+    for p in params {
+        properties.insert(p.name.to_string(), json!({ "type": p.ty }));
+    }
+    let required: Vec<&str> = params
+        .iter()
+        .filter(|p| p.required)
+        .map(|p| p.name)
+        .collect();
+    json!({
+        "type": "object",
+        "properties": properties,
+        "required": required,
+        "additionalProperties": false,
+    })
+}
+
+/// 命令面板元数据：Tauri 命令与 bridge 握手声明的命令（含参数 schema 与说明）
+#[tauri::command]
+pub async fn list_commands(
+    state: tauri::State<'_, BridgeState>,
+    policy: tauri::State<'_, CommandPolicy>,
+) -> Result<Value, String> {
+    guarded("list_commands", async move {
+        let mut out: Vec<CommandInfo> = TAURI_COMMANDS
+            .iter()
+            .map(|c| CommandInfo {
+                name: c.name.to_string(),
+                source: "tauri",
+                category: Some(c.category.to_string()),
+                description: Some(c.description.to_string()),
+                params: Some(params_schema(c.params)),
+                version: None,
+                allowed: true,
+            })
+            .collect();
+
+        let capabilities = state.inner().lock().await.capabilities.clone();
+        let negotiated = capabilities.is_some();
+        if let Some(caps) = capabilities {
+            for (name, version) in caps.cmds {
+                let spec = caps.specs.get(&name).cloned().unwrap_or_default();
+                out.push(CommandInfo {
+                    allowed: policy.check(&name).is_ok(),
+                    name,
+                    source: "bridge",
+                    category: None,
+                    description: spec.description,
+                    params: spec.params,
+                    version: Some(version),
+                });
+            }
+        }
+        Ok(json!({
+            "commands": out,
+            // 旧版 bridge 握手不声明命令时为 false，前端应退回自由输入
+            "bridge_negotiated": negotiated,
+        }))
+    })
+    .await
+}
@@ -0,0 +1,243 @@
+use crate::artifacts::sha256_file;
+use crate::jobs::{JobRecord, JobRegistry};
+use crate::panics::guarded;
+use crate::tables::last_row_scalars;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+
+/// 视为结果导出表的扩展名
+const EXPORT_EXTS: &[&str] = &["csv", "txt", "dat"];
+/// 导出文件的修改时间允许晚于任务结束的余量
+const EXPORT_SLACK_MS: u64 = 5_000;
+/// 参数展开的最大深度，更深的值整体比较
+const MAX_PARAM_DEPTH: usize = 4;
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ParamDiff {
+    pub key: String,
+    pub a: Option<Value>,
+    pub b: Option<Value>,
+    pub same: bool,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ScalarDiff {
+    /// `<导出文件名>/<列名>`
+    pub key: String,
+    pub a: Option<f64>,
+    pub b: Option<f64>,
+    pub diff: Option<f64>,
+    /// 相对差 (b - a) / |a|
+    pub rel_diff: Option<f64>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ArtifactDiff {
+    pub name: String,
+    pub a: Option<String>,
+    pub b: Option<String>,
+    /// 两边都存在时内容是否一致
+    pub identical: Option<bool>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct JobComparison {
+    pub a: JobRecord,
+    pub b: JobRecord,
+    pub parameters: Vec<ParamDiff>,
+    pub scalars: Vec<ScalarDiff>,
+    pub artifacts: Vec<ArtifactDiff>,
+}
+
+/// 把请求参数展开为 `a.b.c` 形式的扁平键，便于逐项对齐
+fn flatten(prefix: &str, value: &Value, depth: usize, out: &mut BTreeMap<String, Value>) {
+    match value {
+        Value::Object(map) if depth < MAX_PARAM_DEPTH && !map.is_empty() => {
+            for (k, v) in map {
+                let key = if prefix.is_empty() {
+                    k.clone()
+                } else {
+                    format!("{}.{}", prefix, k)
+                };
+                flatten(&key, v, depth + 1, out);
+            }
+        }
+        _ => {
+            out.insert(prefix.to_string(), value.clone());
+        }
+    }
+}
+
+fn align_params(a: &Value, b: &Value) -> Vec<ParamDiff> {
+    let (mut fa, mut fb) = (BTreeMap::new(), BTreeMap::new());
+    flatten("", a, 0, &mut fa);
+    flatten("", b, 0, &mut fb);
+    let keys: BTreeSet<String> = fa.keys().chain(fb.keys()).cloned().collect();
+    keys.into_iter()
+        .map(|key| {
+            let (va, vb) = (fa.remove(&key), fb.remove(&key));
+            ParamDiff {
+                same: va == vb,
+                key,
+                a: va,
+                b: vb,
+            }
+        })
+        .collect()
+}
+
+fn is_export(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| EXPORT_EXTS.contains(&e.to_ascii_lowercase().as_str()))
+}
+
+fn modified_ms(path: &Path) -> Option<u64> {
+    std::fs::metadata(path)
+        .ok()?
+        .modified()
+        .ok()?
+        .duration_since(std::time::UNIX_EPOCH)
+        .ok()
+        .map(|d| d.as_millis() as u64)
+}
+
+/// 任务的结果导出表：登记的产物中的表格文件，以及产物所在目录里在任务运行期间写入的表格
+fn job_exports(job: &JobRecord) -> Vec<PathBuf> {
+    let mut out: BTreeSet<PathBuf> = job
+        .artifacts
+        .iter()
+        .map(PathBuf::from)
+        .filter(|p| is_export(p) && p.is_file())
+        .collect();
+    if let (Some(start), Some(end)) = (job.started_at, job.finished_at) {
+        let dirs: BTreeSet<PathBuf> = job
+            .artifacts
+            .iter()
+            .filter_map(|a| Path::new(a).parent().map(Path::to_path_buf))
+            .collect();
+        for dir in dirs {
+            let Ok(entries) = std::fs::read_dir(&dir) else {
+                continue;
+            };
+            out.extend(
+                entries
+                    .flatten()
+                    .map(|e| e.path())
+                    .filter(|p| is_export(p) && p.is_file())
+                    .filter(|p| {
+                        modified_ms(p).is_some_and(|m| m >= start && m <= end + EXPORT_SLACK_MS)
+                    }),
+            );
+        }
+    }
+    out.into_iter().collect()
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_else(|| path.to_string_lossy().into_owned())
+}
+
+fn job_scalars(job: &JobRecord) -> BTreeMap<String, f64> {
+    let mut out = BTreeMap::new();
+    for path in job_exports(job) {
+        match last_row_scalars(&path) {
+            Ok(values) => {
+                let file = file_name(&path);
+                out.extend(
+                    values
+                        .into_iter()
+                        .map(|(col, v)| (format!("{}/{}", file, col), v)),
+                );
+            }
+            Err(e) => eprintln!("Warning: 解析导出表 {} 失败: {}", path.display(), e),
+        }
+    }
+    out
+}
+
+fn align_scalars(a: &JobRecord, b: &JobRecord) -> Vec<ScalarDiff> {
+    let (mut sa, mut sb) = (job_scalars(a), job_scalars(b));
+    let keys: BTreeSet<String> = sa.keys().chain(sb.keys()).cloned().collect();
+    keys.into_iter()
+        .map(|key| {
+            let (va, vb) = (sa.remove(&key), sb.remove(&key));
+            let diff = va.zip(vb).map(|(x, y)| y - x);
+            let rel_diff = va
+                .zip(diff)
+                .filter(|(x, _)| *x != 0.0)
+                .map(|(x, d)| d / x.abs());
+            ScalarDiff {
+                key,
+                a: va,
+                b: vb,
+                diff,
+                rel_diff,
+            }
+        })
+        .collect()
+}
+
+/// 按文件名对齐两个任务的产物；同名文件比较内容哈希
+fn align_artifacts(a: &JobRecord, b: &JobRecord) -> Vec<ArtifactDiff> {
+    let by_name = |job: &JobRecord| -> BTreeMap<String, String> {
+        let exports = job_exports(job)
+            .into_iter()
+            .map(|p| p.to_string_lossy().into_owned());
+        job.artifacts
+            .iter()
+            .cloned()
+            .chain(exports)
+            .map(|p| (file_name(Path::new(&p)), p))
+            .collect()
+    };
+    let (mut ma, mut mb) = (by_name(a), by_name(b));
+    let names: BTreeSet<String> = ma.keys().chain(mb.keys()).cloned().collect();
+    names
+        .into_iter()
+        .map(|name| {
+            let (pa, pb) = (ma.remove(&name), mb.remove(&name));
+            let identical = match (&pa, &pb) {
+                (Some(x), Some(y)) if x == y => Some(true),
+                (Some(x), Some(y)) => sha256_file(Path::new(x))
+                    .ok()
+                    .zip(sha256_file(Path::new(y)).ok())
+                    .map(|(hx, hy)| hx == hy),
+                _ => None,
+            };
+            ArtifactDiff {
+                name,
+                a: pa,
+                b: pb,
+                identical,
+            }
+        })
+        .collect()
+}
+
+/// 并排比较两个任务：对齐请求参数、导出表中的关键标量与产物文件
+#[tauri::command]
+pub async fn compare_jobs(
+    jobs: tauri::State<'_, JobRegistry>,
+    id_a: String,
+    id_b: String,
+) -> Result<JobComparison, String> {
+    guarded("compare_jobs", async move {
+        let get = |id: &str| jobs.get(id).ok_or_else(|| format!("未找到任务: {}", id));
+        let (a, b) = (get(&id_a)?, get(&id_b)?);
+        tauri::async_runtime::spawn_blocking(move || JobComparison {
+            parameters: align_params(&a.payload, &b.payload),
+            scalars: align_scalars(&a, &b),
+            artifacts: align_artifacts(&a, &b),
+            a,
+            b,
+        })
+        .await
+        .map_err(|e| e.to_string())
+    })
+    .await
+}
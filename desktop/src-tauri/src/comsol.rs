@@ -0,0 +1,168 @@
+use crate::env_cache;
+use crate::panics::guarded;
+use crate::policy::CommandPolicy;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ComsolInstall {
+    /// 形如 "6.3"
+    pub version: String,
+    /// 安装根目录（含 Multiphysics 子目录的那一级）
+    pub root: String,
+    pub executable: String,
+    pub server_executable: Option<String>,
+}
+
+#[cfg(target_os = "windows")]
+const BIN_DIR: &[&str] = &["bin", "win64"];
+#[cfg(not(target_os = "windows"))]
+const BIN_DIR: &[&str] = &["bin"];
+
+#[cfg(target_os = "windows")]
+const EXE_SUFFIX: &str = ".exe";
+#[cfg(not(target_os = "windows"))]
+const EXE_SUFFIX: &str = "";
+
+/// 各平台 COMSOL 默认安装位置的上级目录
+pub(crate) fn search_roots() -> Vec<PathBuf> {
+    let mut roots = Vec::new();
+    #[cfg(target_os = "windows")]
+    {
+        for var in ["ProgramFiles", "ProgramW6432"] {
+            if let Ok(p) = std::env::var(var) {
+                roots.push(PathBuf::from(p).join("COMSOL"));
+            }
+        }
+        roots.push(PathBuf::from(r"C:\Program Files\COMSOL"));
+    }
+    #[cfg(target_os = "macos")]
+    {
+        roots.push(PathBuf::from("/Applications"));
+    }
+    #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+    {
+        roots.push(PathBuf::from("/usr/local"));
+        roots.push(PathBuf::from("/opt"));
+    }
+    roots
+}
+
+/// 目录名 COMSOL63 / comsol62 → "6.3" / "6.2"
+fn version_from_dir(name: &str) -> Option<String> {
+    let lower = name.to_lowercase();
+    let digits = lower.strip_prefix("comsol")?;
+    if digits.len() < 2 || !digits.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    Some(format!("{}.{}", &digits[..1], &digits[1..]))
+}
+
+fn version_key(version: &str) -> Vec<u32> {
+    version.split('.').filter_map(|p| p.parse().ok()).collect()
+}
+
+fn install_at(root: &Path, version: String) -> Option<ComsolInstall> {
+    let multiphysics = ["Multiphysics", "multiphysics"]
+        .iter()
+        .map(|d| root.join(d))
+        .find(|p| p.is_dir())?;
+    let bin = BIN_DIR.iter().fold(multiphysics, |p, d| p.join(d));
+    let exe = bin.join(format!("comsol{}", EXE_SUFFIX));
+    if !exe.is_file() {
+        return None;
+    }
+    let server = bin.join(format!("comsolmphserver{}", EXE_SUFFIX));
+    Some(ComsolInstall {
+        version,
+        root: root.to_string_lossy().into_owned(),
+        executable: exe.to_string_lossy().into_owned(),
+        server_executable: server
+            .is_file()
+            .then(|| server.to_string_lossy().into_owned()),
+    })
+}
+
+/// 扫描默认安装位置及 COMSOL_JAR_PATH 所指的安装，按版本从新到旧排序
+pub fn detect_installs() -> Vec<ComsolInstall> {
+    let mut out: Vec<ComsolInstall> = Vec::new();
+    for base in search_roots() {
+        let Ok(rd) = std::fs::read_dir(&base) else {
+            continue;
+        };
+        for entry in rd.flatten() {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            if let Some(v) = version_from_dir(&name) {
+                if let Some(i) = install_at(&entry.path(), v) {
+                    out.push(i);
+                }
+            }
+        }
+    }
+    // COMSOL_JAR_PATH 一般指向 <root>/Multiphysics/plugins
+    if let Ok(jar) = std::env::var("COMSOL_JAR_PATH") {
+        let root = Path::new(&jar).ancestors().nth(2).map(Path::to_path_buf);
+        if let Some(root) = root {
+            let name = root.file_name().unwrap_or_default().to_string_lossy();
+            let version = version_from_dir(&name).unwrap_or_else(|| "unknown".to_string());
+            if let Some(i) = install_at(&root, version) {
+                out.push(i);
+            }
+        }
+    }
+    out.sort_by_key(|i| std::cmp::Reverse(version_key(&i.version)));
+    out.dedup_by(|a, b| a.executable == b.executable);
+    out
+}
+
+/// 按版本选择安装；`version` 可写 "6.3" 或 "63"，为空时取最新版
+pub fn find_install(version: Option<&str>) -> Result<ComsolInstall, String> {
+    let installs = env_cache::comsol_installs();
+    let wanted = version.map(|v| v.trim().replace('.', ""));
+    match wanted.filter(|v| !v.is_empty()) {
+        Some(v) => installs
+            .into_iter()
+            .find(|i| i.version.replace('.', "") == v)
+            .ok_or_else(|| format!("未找到 COMSOL {} 安装", version.unwrap_or_default())),
+        None => installs
+            .into_iter()
+            .next()
+            .ok_or_else(|| "未检测到 COMSOL 安装".to_string()),
+    }
+}
+
+#[tauri::command]
+pub async fn comsol_installs() -> Result<Vec<ComsolInstall>, String> {
+    guarded("comsol_installs", async move {
+        tauri::async_runtime::spawn_blocking(env_cache::comsol_installs)
+            .await
+            .map_err(|e| e.to_string())
+    })
+    .await
+}
+
+/// 用指定版本的 COMSOL 打开模型，避免系统默认关联到其他版本
+#[tauri::command]
+pub async fn open_in_comsol(
+    policy: tauri::State<'_, CommandPolicy>,
+    path: String,
+    version: Option<String>,
+) -> Result<(), String> {
+    guarded("open_in_comsol", async move {
+        policy.ensure_writable("启动 COMSOL")?;
+        let path = path.trim();
+        if path.is_empty() {
+            return Err("路径为空".to_string());
+        }
+        if !Path::new(path).is_file() {
+            return Err("文件不存在".to_string());
+        }
+        let install = find_install(version.as_deref())?;
+        std::process::Command::new(&install.executable)
+            .args(["-open", path])
+            .spawn()
+            .map_err(|e| format!("启动 COMSOL {} 失败: {}", install.version, e))?;
+        Ok(())
+    })
+    .await
+}
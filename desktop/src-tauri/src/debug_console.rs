@@ -0,0 +1,194 @@
+use crate::event_routing;
+use crate::log_level;
+use crate::panics::guarded;
+use crate::profiles::webview_data_dir;
+use crate::repro;
+use crate::store::now_ms;
+use crate::transcript;
+use crate::zoom;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter, Manager, WebviewUrl, WebviewWindowBuilder, WindowEvent};
+
+pub const DEBUG_CONSOLE_LABEL: &str = "debug-console";
+/// 只发往调试窗口，主窗口不会收到
+const TRAFFIC_EVENT: &str = "bridge-traffic";
+const HISTORY_LIMIT: usize = 2000;
+/// 单行超过该长度时截断，避免大结果把 webview 拖垮
+const MAX_LINE_BYTES: usize = 64 * 1024;
+
+/// 控制台窗口打开期间才记录；关闭后只剩复现包用的小环形缓冲
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static PAUSED: AtomicBool = AtomicBool::new(false);
+static SEQ: AtomicU64 = AtomicU64::new(0);
+static SINK: Mutex<Option<AppHandle>> = Mutex::new(None);
+static HISTORY: Mutex<VecDeque<TrafficLine>> = Mutex::new(VecDeque::new());
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Direction {
+    /// 写入 bridge stdin
+    Out,
+    /// 从 bridge stdout 读到
+    In,
+    /// bridge stderr
+    Err,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct TrafficLine {
+    pub seq: u64,
+    pub ts: u64,
+    pub direction: Direction,
+    pub line: String,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub truncated: bool,
+}
+
+fn truncate(line: &str) -> (String, bool) {
+    let line = line.trim_end_matches(['\r', '\n']);
+    if line.len() <= MAX_LINE_BYTES {
+        return (line.to_string(), false);
+    }
+    let mut end = MAX_LINE_BYTES;
+    while !line.is_char_boundary(end) {
+        end -= 1;
+    }
+    (line[..end].to_string(), true)
+}
+
+/// bridge 读写任务中的钩子：记录一行原始流量并推送到调试窗口
+pub fn record(direction: Direction, line: &str) {
+    repro::remember(direction, line);
+    transcript::record(direction, line);
+    // stderr 已由读取任务原样转发，这里只补上 stdin/stdout 流量
+    if direction != Direction::Err && log_level::enabled("debug") {
+        let arrow = match direction {
+            Direction::Out => "->",
+            _ => "<-",
+        };
+        eprintln!("[bridge-io] {} {}", arrow, truncate(line).0);
+    }
+    if !ENABLED.load(Ordering::Relaxed) {
+        return;
+    }
+    let (line, truncated) = truncate(line);
+    let entry = TrafficLine {
+        seq: SEQ.fetch_add(1, Ordering::Relaxed) + 1,
+        ts: now_ms(),
+        direction,
+        line,
+        truncated,
+    };
+    {
+        let mut history = HISTORY.lock().unwrap_or_else(|e| e.into_inner());
+        if history.len() >= HISTORY_LIMIT {
+            history.pop_front();
+        }
+        history.push_back(entry.clone());
+    }
+    // 暂停时仍写入历史，恢复后前端按 seq 补齐
+    if PAUSED.load(Ordering::Relaxed) {
+        return;
+    }
+    let sink = SINK.lock().unwrap_or_else(|e| e.into_inner()).clone();
+    if let Some(app) = sink {
+        let _ = app.emit_to(DEBUG_CONSOLE_LABEL, TRAFFIC_EVENT, &entry);
+    }
+}
+
+fn disable() {
+    ENABLED.store(false, Ordering::Relaxed);
+    PAUSED.store(false, Ordering::Relaxed);
+    *SINK.lock().unwrap_or_else(|e| e.into_inner()) = None;
+    HISTORY.lock().unwrap_or_else(|e| e.into_inner()).clear();
+}
+
+/// 打开调试控制台窗口并开始记录 bridge 原始流量
+#[tauri::command]
+pub async fn debug_console_open(app: AppHandle) -> Result<(), String> {
+    guarded("debug_console_open", async move {
+        if let Some(w) = app.get_webview_window(DEBUG_CONSOLE_LABEL) {
+            let _ = w.unminimize();
+            w.show().map_err(|e| e.to_string())?;
+            return w.set_focus().map_err(|e| e.to_string());
+        }
+        let mut builder = WebviewWindowBuilder::new(
+            &app,
+            DEBUG_CONSOLE_LABEL,
+            WebviewUrl::App("index.html#/debug-console".into()),
+        )
+        .title("Bridge 调试控制台")
+        .inner_size(960.0, 600.0);
+        if let Some(dir) = webview_data_dir(&app) {
+            builder = builder.data_directory(dir);
+        }
+        let window = builder
+            .build()
+            .map_err(|e| format!("创建调试控制台失败: {}", e))?;
+        zoom::attach(&app, &window);
+        event_routing::attach(&app, &window);
+        window.on_window_event(|event| {
+            if let WindowEvent::Destroyed = event {
+                disable();
+            }
+        });
+        *SINK.lock().unwrap_or_else(|e| e.into_inner()) = Some(app.clone());
+        PAUSED.store(false, Ordering::Relaxed);
+        ENABLED.store(true, Ordering::Relaxed);
+        Ok(())
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn debug_console_close(app: AppHandle) -> Result<(), String> {
+    guarded("debug_console_close", async move {
+        disable();
+        if let Some(w) = app.get_webview_window(DEBUG_CONSOLE_LABEL) {
+            w.close().map_err(|e| e.to_string())?;
+        }
+        Ok(())
+    })
+    .await
+}
+
+/// 暂停/恢复实时推送；暂停期间的流量仍保留在历史中
+#[tauri::command]
+pub async fn debug_console_set_paused(paused: bool) -> Result<(), String> {
+    guarded("debug_console_set_paused", async move {
+        PAUSED.store(paused, Ordering::Relaxed);
+        Ok(())
+    })
+    .await
+}
+
+/// 读取历史流量：`since_seq` 之后的行，可按方向与关键字（不区分大小写）过滤
+#[tauri::command]
+pub async fn debug_console_history(
+    since_seq: Option<u64>,
+    direction: Option<Direction>,
+    filter: Option<String>,
+) -> Result<Vec<TrafficLine>, String> {
+    guarded("debug_console_history", async move {
+        let since = since_seq.unwrap_or(0);
+        let filter = filter
+            .map(|f| f.trim().to_lowercase())
+            .filter(|f| !f.is_empty());
+        let history = HISTORY.lock().unwrap_or_else(|e| e.into_inner());
+        Ok(history
+            .iter()
+            .filter(|l| l.seq > since)
+            .filter(|l| direction.is_none_or(|d| l.direction == d))
+            .filter(|l| {
+                filter
+                    .as_ref()
+                    .is_none_or(|f| l.line.to_lowercase().contains(f))
+            })
+            .cloned()
+            .collect())
+    })
+    .await
+}
@@ -0,0 +1,376 @@
+use crate::bridge::find_project_root;
+use crate::messages::{dialog_text, Locale};
+use crate::panics::guarded;
+use crate::paths::output_dir;
+use crate::store::{load_json, save_json};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tauri::AppHandle;
+use tauri_plugin_dialog::DialogExt;
+
+/// 用途 → 目录
+type DirMap = BTreeMap<String, String>;
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct DialogDirs {
+    /// 各用途最近一次使用的目录
+    #[serde(default)]
+    pub last: DirMap,
+    /// 按项目覆盖，优先于 `last`
+    #[serde(default)]
+    pub projects: BTreeMap<String, DirMap>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct DialogFilter {
+    pub name: String,
+    pub extensions: Vec<String>,
+}
+
+/// 文件对话框的起始目录记忆：打开模型、导出图片、保存报告等用途各记各的
+#[derive(Default)]
+pub struct DialogDirStore {
+    path: Option<PathBuf>,
+    dirs: Mutex<DialogDirs>,
+}
+
+/// 用途名只允许小写字母、数字和 `_`，如 open_model / export_image / save_report
+fn validate_purpose(purpose: &str) -> Result<String, String> {
+    let purpose = purpose.trim();
+    if purpose.is_empty()
+        || !purpose
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+    {
+        return Err(format!("无效的对话框用途: {}", purpose));
+    }
+    Ok(purpose.to_string())
+}
+
+impl DialogDirStore {
+    pub fn load(path: PathBuf) -> Self {
+        let dirs: DialogDirs = load_json(&path);
+        Self {
+            path: Some(path),
+            dirs: Mutex::new(dirs),
+        }
+    }
+
+    fn persist(&self, dirs: &DialogDirs) {
+        if let Some(ref p) = self.path {
+            if let Err(e) = save_json(p, dirs) {
+                eprintln!("Warning: 保存对话框目录失败: {}", e);
+            }
+        }
+    }
+
+    /// 起始目录：项目覆盖 > 最近使用 > 模型输出目录；已被删除的目录跳过
+    fn resolve(&self, purpose: &str, project: Option<&str>) -> Option<PathBuf> {
+        let dirs = self.dirs.lock().unwrap_or_else(|e| e.into_inner());
+        project
+            .and_then(|p| dirs.projects.get(p))
+            .and_then(|m| m.get(purpose))
+            .into_iter()
+            .chain(dirs.last.get(purpose))
+            .map(PathBuf::from)
+            .find(|d| d.is_dir())
+            .or_else(|| output_dir(&find_project_root()).filter(|d| d.is_dir()))
+    }
+
+    fn remember(&self, purpose: &str, project: Option<&str>, dir: &Path) {
+        let dir = dir.to_string_lossy().into_owned();
+        let mut dirs = self.dirs.lock().unwrap_or_else(|e| e.into_inner());
+        dirs.last.insert(purpose.to_string(), dir.clone());
+        if let Some(p) = project {
+            dirs.projects
+                .entry(p.to_string())
+                .or_default()
+                .insert(purpose.to_string(), dir);
+        }
+        self.persist(&dirs);
+    }
+
+    fn set(&self, purpose: &str, project: Option<&str>, dir: Option<String>) {
+        let mut dirs = self.dirs.lock().unwrap_or_else(|e| e.into_inner());
+        let map = match project {
+            Some(p) => dirs.projects.entry(p.to_string()).or_default(),
+            None => &mut dirs.last,
+        };
+        match dir {
+            Some(d) => map.insert(purpose.to_string(), d),
+            None => map.remove(purpose),
+        };
+        dirs.projects.retain(|_, m| !m.is_empty());
+        self.persist(&dirs);
+    }
+}
+
+fn project_key(project: Option<String>) -> Option<String> {
+    project
+        .map(|p| p.trim().to_string())
+        .filter(|p| !p.is_empty())
+}
+
+pub(crate) struct DialogOptions {
+    pub title: Option<String>,
+    pub default_name: Option<String>,
+    pub filters: Vec<DialogFilter>,
+    pub save: bool,
+    /// 文案语言，缺省取系统语言
+    pub locale: Option<String>,
+}
+
+/// 未指定标题/文件类型时按用途从文案目录补全
+fn localize(
+    purpose: &str,
+    locale: Option<&str>,
+    title: &mut Option<String>,
+    filters: &mut Vec<DialogFilter>,
+) {
+    let Some(text) = dialog_text(Locale::resolve(locale), purpose) else {
+        return;
+    };
+    if title.is_none() {
+        *title = Some(text.title.to_string());
+    }
+    if filters.is_empty() {
+        *filters = text
+            .filters
+            .iter()
+            .map(|f| DialogFilter {
+                name: f.name.to_string(),
+                extensions: f.extensions.iter().map(|e| e.to_string()).collect(),
+            })
+            .collect();
+    }
+}
+
+fn allowed_extensions(filters: &[DialogFilter]) -> Vec<String> {
+    filters
+        .iter()
+        .flat_map(|f| f.extensions.iter())
+        .map(|e| e.trim_start_matches('.').to_ascii_lowercase())
+        .collect()
+}
+
+/// 校验扩展名：保存时缺扩展名补上第一个允许的扩展名，其余不符一律拒绝
+fn enforce_extension(
+    mut path: PathBuf,
+    filters: &[DialogFilter],
+    save: bool,
+) -> Result<PathBuf, String> {
+    let allowed = allowed_extensions(filters);
+    if allowed.is_empty() || allowed.iter().any(|e| e == "*") {
+        return Ok(path);
+    }
+    let ext = path
+        .extension()
+        .map(|e| e.to_string_lossy().to_ascii_lowercase());
+    match ext {
+        Some(ref e) if allowed.contains(e) => Ok(path),
+        None if save => {
+            let mut name = path.file_name().unwrap_or_default().to_os_string();
+            name.push(".");
+            name.push(&allowed[0]);
+            path.set_file_name(name);
+            Ok(path)
+        }
+        _ => Err(format!(
+            "不支持的文件类型: {}（允许: {}）",
+            path.display(),
+            allowed.join(", ")
+        )),
+    }
+}
+
+/// Windows 上 canonicalize 得到 `\\?\` 前缀的路径，COMSOL 与 Python 端不一定认，去掉
+fn strip_verbatim(path: PathBuf) -> PathBuf {
+    #[cfg(target_os = "windows")]
+    {
+        let s = path.to_string_lossy();
+        if let Some(rest) = s.strip_prefix(r"\\?\UNC\") {
+            return PathBuf::from(format!(r"\\{}", rest));
+        }
+        if let Some(rest) = s.strip_prefix(r"\\?\") {
+            return PathBuf::from(rest);
+        }
+    }
+    path
+}
+
+/// 规范化选择结果：绝对路径、解析符号链接与 `..`（保存时文件可能还不存在，只规范化所在目录），
+/// 且必须能无损表示为 UTF-8
+fn normalize(path: PathBuf, save: bool) -> Result<String, String> {
+    let path = if path.is_absolute() {
+        path
+    } else {
+        std::env::current_dir()
+            .map_err(|e| e.to_string())?
+            .join(path)
+    };
+    let canonical = if save {
+        match (path.parent(), path.file_name()) {
+            (Some(dir), Some(name)) => dir
+                .canonicalize()
+                .map(|d| d.join(name))
+                .map_err(|e| format!("目录不可用 {}: {}", dir.display(), e))?,
+            _ => return Err(format!("无效的保存路径: {}", path.display())),
+        }
+    } else {
+        path.canonicalize()
+            .map_err(|e| format!("无法访问 {}: {}", path.display(), e))?
+    };
+    strip_verbatim(canonical)
+        .into_os_string()
+        .into_string()
+        .map_err(|p| format!("路径含无法以 UTF-8 表示的字符: {}", p.to_string_lossy()))
+}
+
+/// 打开或保存文件对话框；从记住的目录开始，选择后记录所在目录。
+/// 返回的路径已校验扩展名并规范化
+pub(crate) async fn show(
+    app: AppHandle,
+    store: &DialogDirStore,
+    purpose: String,
+    project: Option<String>,
+    options: DialogOptions,
+) -> Result<Option<String>, String> {
+    let DialogOptions {
+        mut title,
+        default_name,
+        mut filters,
+        save,
+        locale,
+    } = options;
+    let purpose = validate_purpose(&purpose)?;
+    localize(&purpose, locale.as_deref(), &mut title, &mut filters);
+    let project = project_key(project);
+    let start = store.resolve(&purpose, project.as_deref());
+    let dialog_filters = filters.clone();
+    let picked = tauri::async_runtime::spawn_blocking(move || {
+        let mut builder = app.dialog().file();
+        if let Some(dir) = start {
+            builder = builder.set_directory(dir);
+        }
+        if let Some(t) = title {
+            builder = builder.set_title(t);
+        }
+        if let Some(name) = default_name {
+            builder = builder.set_file_name(name);
+        }
+        for f in &dialog_filters {
+            let exts: Vec<&str> = f.extensions.iter().map(String::as_str).collect();
+            builder = builder.add_filter(f.name.clone(), &exts);
+        }
+        if save {
+            builder.blocking_save_file()
+        } else {
+            builder.blocking_pick_file()
+        }
+    })
+    .await
+    .map_err(|e| e.to_string())?;
+    let Some(picked) = picked else {
+        return Ok(None);
+    };
+    let path = picked.into_path().map_err(|e| e.to_string())?;
+    let path = normalize(enforce_extension(path, &filters, save)?, save)?;
+    if let Some(dir) = Path::new(&path).parent() {
+        store.remember(&purpose, project.as_deref(), dir);
+    }
+    Ok(Some(path))
+}
+
+#[tauri::command]
+pub async fn dialog_open_file(
+    app: AppHandle,
+    store: tauri::State<'_, DialogDirStore>,
+    purpose: String,
+    project: Option<String>,
+    title: Option<String>,
+    filters: Option<Vec<DialogFilter>>,
+    locale: Option<String>,
+) -> Result<Option<String>, String> {
+    guarded("dialog_open_file", async move {
+        show(
+            app,
+            store.inner(),
+            purpose,
+            project,
+            DialogOptions {
+                title,
+                default_name: None,
+                filters: filters.unwrap_or_default(),
+                save: false,
+                locale,
+            },
+        )
+        .await
+    })
+    .await
+}
+
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn dialog_save_file(
+    app: AppHandle,
+    store: tauri::State<'_, DialogDirStore>,
+    purpose: String,
+    project: Option<String>,
+    title: Option<String>,
+    default_name: Option<String>,
+    filters: Option<Vec<DialogFilter>>,
+    locale: Option<String>,
+) -> Result<Option<String>, String> {
+    guarded("dialog_save_file", async move {
+        show(
+            app,
+            store.inner(),
+            purpose,
+            project,
+            DialogOptions {
+                title,
+                default_name,
+                filters: filters.unwrap_or_default(),
+                save: true,
+                locale,
+            },
+        )
+        .await
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn dialog_dirs_get(
+    store: tauri::State<'_, DialogDirStore>,
+) -> Result<DialogDirs, String> {
+    guarded("dialog_dirs_get", async move {
+        Ok(store.dirs.lock().unwrap_or_else(|e| e.into_inner()).clone())
+    })
+    .await
+}
+
+/// 手动指定（或传 None 清除）某用途的起始目录；带 project 时只作用于该项目
+#[tauri::command]
+pub async fn dialog_dir_set(
+    store: tauri::State<'_, DialogDirStore>,
+    purpose: String,
+    project: Option<String>,
+    dir: Option<String>,
+) -> Result<(), String> {
+    guarded("dialog_dir_set", async move {
+        let purpose = validate_purpose(&purpose)?;
+        let dir = dir.map(|d| d.trim().to_string()).filter(|d| !d.is_empty());
+        if let Some(ref d) = dir {
+            if !Path::new(d).is_dir() {
+                return Err(format!("目录不存在: {}", d));
+            }
+        }
+        store.set(&purpose, project_key(project).as_deref(), dir);
+        Ok(())
+    })
+    .await
+}
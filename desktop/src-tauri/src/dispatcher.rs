@@ -0,0 +1,609 @@
+use crate::codec::Codec;
+use crate::debug_console::{self, Direction};
+use crate::framing::{read_message, write_frame, Frame, Framing};
+use crate::size_limits;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::Arc;
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncRead, AsyncWrite, BufReader};
+use tokio::sync::{mpsc, Mutex};
+
+/// 送回等待方的一行输出：解析后的 JSON，或无法解析时的错误
+pub type Incoming = Result<Value, String>;
+
+/// 一条请求的输出通道；记录尚未取走的条数，供读取任务限制积压的事件
+pub struct ResponseRx {
+    rx: mpsc::UnboundedReceiver<Incoming>,
+    queued: Arc<AtomicUsize>,
+}
+
+impl ResponseRx {
+    pub async fn recv(&mut self) -> Option<Incoming> {
+        let item = self.rx.recv().await;
+        if item.is_some() {
+            self.queued.fetch_sub(1, Ordering::SeqCst);
+        }
+        item
+    }
+}
+
+/// 握手等按行读取时单行输出的长度上限；超出的行整行丢弃。
+/// 握手之后的消息按设置中的 `size_limits` 限制
+pub const MAX_LINE_BYTES: usize = 64 * 1024 * 1024;
+/// 超长消息保留的开头字节数，用来找回其 `_id` 与是否为事件
+const HEAD_BYTES: usize = 4096;
+/// 协议层错误的前缀，与 BridgeDead / BridgeTimeout 一样供前端识别
+pub const PROTOCOL_ERROR: &str = "ProtocolError";
+
+/// 从管道读到的一行
+pub enum LineRead {
+    Eof,
+    /// 按 UTF-8 宽松解码（非法字节替换为 U+FFFD），含结尾换行
+    Line(String),
+    /// 超过长度上限的行，只保留其字节数
+    TooLong(usize),
+}
+
+/// 读取一行，不因非法 UTF-8 或超长输出而出错或无限占用内存：
+/// 读取过程中始终消费到换行为止，保证下一次读取从新的一行开始
+pub async fn read_bounded_line<R: AsyncBufRead + Unpin>(
+    reader: &mut R,
+    max: usize,
+) -> std::io::Result<LineRead> {
+    Ok(read_line_keeping_head(reader, max, 0).await?.0)
+}
+
+/// 同 `read_bounded_line`；行超长时另外返回其开头至多 `head` 个字节
+pub async fn read_line_keeping_head<R: AsyncBufRead + Unpin>(
+    reader: &mut R,
+    max: usize,
+    head: usize,
+) -> std::io::Result<(LineRead, Vec<u8>)> {
+    let mut buf = Vec::new();
+    let mut total = 0usize;
+    let mut overflow = false;
+    loop {
+        let chunk = reader.fill_buf().await?;
+        if chunk.is_empty() {
+            break;
+        }
+        let (take, done) = match chunk.iter().position(|&b| b == b'\n') {
+            Some(i) => (i + 1, true),
+            None => (chunk.len(), false),
+        };
+        total = total.saturating_add(take);
+        if !overflow {
+            if buf.len() + take > max {
+                overflow = true;
+                let keep = head.saturating_sub(buf.len()).min(take);
+                buf.extend_from_slice(&chunk[..keep]);
+                buf.truncate(head);
+            } else {
+                buf.extend_from_slice(&chunk[..take]);
+            }
+        }
+        reader.consume(take);
+        if done {
+            break;
+        }
+    }
+    Ok(match (total, overflow) {
+        (0, _) => (LineRead::Eof, Vec::new()),
+        (n, true) => (LineRead::TooLong(n), buf),
+        _ => (
+            LineRead::Line(String::from_utf8_lossy(&buf).into_owned()),
+            Vec::new(),
+        ),
+    })
+}
+
+/// 截取前若干字符用于错误信息，避免把整段异常输出塞进报错
+pub fn excerpt(text: &str) -> String {
+    const MAX_CHARS: usize = 200;
+    match text.char_indices().nth(MAX_CHARS) {
+        Some((end, _)) => format!("{}…", &text[..end]),
+        None => text.to_string(),
+    }
+}
+
+/// 把一行输出归类：JSON 对象交给等待方；以 `{` 开头却解析失败的行视为损坏的响应，
+/// 以结构化错误结束对应请求；其余行（第三方库直接 print 到 stdout 等）不是协议内容，返回 None
+pub fn classify(line: &str) -> Option<Incoming> {
+    let trimmed = line.trim();
+    if trimmed.is_empty() {
+        return None;
+    }
+    match serde_json::from_str::<Value>(trimmed) {
+        Ok(v) if v.is_object() => Some(Ok(v)),
+        Ok(_) => None,
+        Err(_) if !trimmed.starts_with('{') => None,
+        Err(e) => Some(Err(format!(
+            "{}: JSON 解析失败: {} (内容: {})",
+            PROTOCOL_ERROR,
+            e,
+            excerpt(trimmed)
+        ))),
+    }
+}
+
+/// 回显的 `_id`；兼容 bridge 把它写成数字的情况
+fn echoed_id(v: &Value) -> Option<String> {
+    match v.get("_id")? {
+        Value::String(s) => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        _ => None,
+    }
+}
+
+/// 从被跳过的超长消息开头找回 `_id` 与是否为事件（bridge 把这两个键写在最前）。JSON 按文本查找；
+/// MessagePack 中键与短字符串值以 fixstr 编码，按字节查找
+fn salvage(codec: Codec, head: &[u8]) -> (Option<String>, bool) {
+    // MessagePack 模式下无法编码的消息退回 JSON，以 `{` 开头
+    if codec == Codec::Json || head.first() == Some(&b'{') {
+        let text = String::from_utf8_lossy(head);
+        let id = text.find("\"_id\"").and_then(|i| {
+            let rest = text[i + 5..].trim_start().strip_prefix(':')?.trim_start();
+            let rest = rest.strip_prefix('"').unwrap_or(rest);
+            let end = rest.find(|c: char| !c.is_ascii_alphanumeric())?;
+            Some(rest[..end].to_string()).filter(|s| !s.is_empty())
+        });
+        let compact: String = text.chars().filter(|c| !c.is_whitespace()).collect();
+        (id, compact.contains("\"_event\":true"))
+    } else {
+        let find = |needle: &[u8]| head.windows(needle.len()).position(|w| w == needle);
+        let id = find(b"\xa3_id").and_then(|i| {
+            let tag = *head.get(i + 4)?;
+            let len = (tag & 0x1f) as usize;
+            let bytes = head
+                .get(i + 5..i + 5 + len)
+                .filter(|_| tag & 0xe0 == 0xa0)?;
+            Some(String::from_utf8_lossy(bytes).into_owned())
+        });
+        (id, find(b"\xa6_event\xc3").is_some())
+    }
+}
+
+/// 超过单个事件上限的事件换成的截断标记，任务继续进行
+fn truncated_event(
+    id: Option<&str>,
+    original: Option<&Value>,
+    bytes: usize,
+    limit: usize,
+) -> Value {
+    let mut marker = serde_json::json!({
+        "_event": true,
+        "type": "event_truncated",
+        "data": {
+            "original_type": original.and_then(|v| v.get("type")).cloned(),
+            "bytes": bytes,
+            "limit": limit,
+            "message": format!("事件过大（{} 字节，上限 {}），内容已丢弃", bytes, limit),
+        },
+        "iteration": original.and_then(|v| v.get("iteration")).cloned(),
+    });
+    if let Some(id) = id {
+        marker["_id"] = Value::String(id.to_string());
+    }
+    marker
+}
+
+struct Waiter {
+    tx: mpsc::UnboundedSender<Incoming>,
+    queued: Arc<AtomicUsize>,
+    /// 因积压超限而丢弃、尚未告知等待方的事件数
+    dropped: usize,
+}
+
+impl Waiter {
+    fn send(&self, item: Incoming) {
+        self.queued.fetch_add(1, Ordering::SeqCst);
+        if self.tx.send(item).is_err() {
+            self.queued.fetch_sub(1, Ordering::SeqCst);
+        }
+    }
+}
+
+#[derive(Default)]
+struct Pending {
+    waiters: HashMap<String, Waiter>,
+}
+
+/// bridge 管道的多路复用：每条请求由 Rust 注入 `_id`，读取任务按 bridge 回显的 `_id`
+/// 把流式事件与最终响应送回对应的等待方，多个请求可同时在途而不会错配
+pub struct Dispatcher {
+    stdin: Mutex<Box<dyn AsyncWrite + Send + Unpin>>,
+    /// 握手协商出的分帧方式，读写两个方向一致
+    framing: Framing,
+    /// bridge 输出的编码；请求始终为 JSON
+    codec: Codec,
+    pending: std::sync::Mutex<Pending>,
+    next_id: AtomicU64,
+}
+
+fn is_event(v: &Value) -> bool {
+    v.get("_event").and_then(|e| e.as_bool()) == Some(true)
+}
+
+impl Dispatcher {
+    /// 接管 bridge 的 stdin/stdout（或回放时的内存管道）并启动读取任务；
+    /// 进程退出（stdout EOF）后所有等待方的通道关闭
+    pub fn start<W, R>(stdin: W, reader: BufReader<R>, framing: Framing, codec: Codec) -> Arc<Self>
+    where
+        W: AsyncWrite + Send + Unpin + 'static,
+        R: AsyncRead + Send + Unpin + 'static,
+    {
+        let dispatcher = Arc::new(Self {
+            stdin: Mutex::new(Box::new(stdin)),
+            framing,
+            codec,
+            pending: std::sync::Mutex::new(Pending::default()),
+            next_id: AtomicU64::new(1),
+        });
+        tokio::spawn(read_loop(dispatcher.clone(), reader));
+        dispatcher
+    }
+
+    /// 登记一条请求，返回要注入请求的 `_id` 与接收其输出的通道
+    pub fn register(&self) -> (String, ResponseRx) {
+        let id = format!("r{}", self.next_id.fetch_add(1, Ordering::SeqCst));
+        let (tx, rx) = mpsc::unbounded_channel();
+        let queued = Arc::new(AtomicUsize::new(0));
+        let mut pending = self.pending.lock().unwrap_or_else(|e| e.into_inner());
+        pending.waiters.insert(
+            id.clone(),
+            Waiter {
+                tx,
+                queued: queued.clone(),
+                dropped: 0,
+            },
+        );
+        (id, ResponseRx { rx, queued })
+    }
+
+    /// 放弃等待（写入失败、调用方提前返回）
+    pub fn cancel(&self, id: &str) {
+        let mut pending = self.pending.lock().unwrap_or_else(|e| e.into_inner());
+        pending.waiters.remove(id);
+    }
+
+    pub fn in_flight(&self) -> usize {
+        self.pending
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .waiters
+            .len()
+    }
+
+    /// 写入一条请求；整条在锁内写完，并发请求不会交错。
+    /// 超过请求大小上限时不写入，返回 InvalidInput（调用方应先用 `SizeLimits::check_request` 检查）
+    pub async fn write_line(&self, line: &str) -> std::io::Result<()> {
+        if let Err(e) = size_limits::current().check_request(line) {
+            return Err(std::io::Error::new(std::io::ErrorKind::InvalidInput, e));
+        }
+        let mut stdin = self.stdin.lock().await;
+        write_frame(&mut *stdin, self.framing, line).await
+    }
+
+    fn route(&self, item: Incoming) {
+        let echoed = item.as_ref().ok().and_then(echoed_id);
+        self.route_to(echoed, item);
+    }
+
+    /// 送给指定请求。`echoed` 为 None（不回显 `_id` 的旧版 bridge、bridge 的解析错误回复、
+    /// 找不回 `_id` 的超长消息）时只在恰有一个请求在途时交给它，否则无从判断归属，记录后丢弃，
+    /// 不按发送顺序猜测，以免结束无关的请求。
+    /// 事件在等待方积压超过上限时丢弃，之后的第一条输出前补一条 `events_dropped` 事件
+    fn route_to(&self, echoed: Option<String>, item: Incoming) {
+        let mut pending = self.pending.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(ref id) = echoed {
+            if !pending.waiters.contains_key(id) {
+                eprintln!("Warning: 丢弃未知请求 {} 的 bridge 输出", id);
+                return;
+            }
+        }
+        let id = match echoed {
+            Some(id) => id,
+            None if pending.waiters.len() == 1 => match pending.waiters.keys().next() {
+                Some(id) => id.clone(),
+                None => return,
+            },
+            None if pending.waiters.is_empty() => {
+                eprintln!("Warning: 丢弃无请求等待的 bridge 输出: {:?}", item);
+                return;
+            }
+            None => {
+                eprintln!(
+                    "Warning: {}: 丢弃未带 _id 的 bridge 输出（{} 个请求在途，无法判断归属）: {:?}",
+                    PROTOCOL_ERROR,
+                    pending.waiters.len(),
+                    item
+                );
+                return;
+            }
+        };
+        let item = item.map(|mut v| {
+            if let Some(obj) = v.as_object_mut() {
+                obj.remove("_id");
+            }
+            v
+        });
+        let last = !matches!(&item, Ok(v) if is_event(v));
+        if let Some(waiter) = pending.waiters.get_mut(&id) {
+            let limit = size_limits::current().max_buffered_events;
+            if !last && waiter.queued.load(Ordering::SeqCst) >= limit {
+                waiter.dropped += 1;
+                if waiter.dropped == 1 {
+                    eprintln!("Warning: 请求 {} 积压的事件超过 {} 条，开始丢弃", id, limit);
+                }
+                return;
+            }
+            if waiter.dropped > 0 {
+                waiter.send(Ok(serde_json::json!({
+                    "_event": true,
+                    "type": "events_dropped",
+                    "data": {
+                        "count": waiter.dropped,
+                        "limit": limit,
+                        "message": format!("事件积压超过 {} 条，丢弃了 {} 条", limit, waiter.dropped),
+                    },
+                    "iteration": null,
+                })));
+                waiter.dropped = 0;
+            }
+            waiter.send(item);
+        }
+        if last {
+            pending.waiters.remove(&id);
+        }
+    }
+
+    fn close(&self) {
+        let mut pending = self.pending.lock().unwrap_or_else(|e| e.into_inner());
+        pending.waiters.clear();
+    }
+}
+
+async fn read_loop<R: AsyncRead + Unpin>(dispatcher: Arc<Dispatcher>, mut reader: BufReader<R>) {
+    loop {
+        let limits = size_limits::current();
+        let max = limits.max_response_bytes;
+        let data = match read_message(&mut reader, dispatcher.framing, max, HEAD_BYTES).await {
+            Ok(Frame::Eof) => break,
+            Ok(Frame::Data(data)) => data,
+            Ok(Frame::TooLong { len, head }) => {
+                // 整条已跳过，只凭开头找回归属：事件换成截断标记，响应以协议错误结束请求
+                let (id, event) = salvage(dispatcher.codec, &head);
+                eprintln!(
+                    "Warning: bridge 输出过长（{} 字节，上限 {}），已丢弃",
+                    len, max
+                );
+                let item = if event {
+                    Ok(truncated_event(id.as_deref(), None, len, max))
+                } else {
+                    Err(format!(
+                        "{}: 响应过长（{} 字节，上限 {}），已丢弃",
+                        PROTOCOL_ERROR, len, max
+                    ))
+                };
+                dispatcher.route_to(id, item);
+                continue;
+            }
+            Err(e) => {
+                eprintln!("Warning: 读取 bridge stdout 失败: {}", e);
+                break;
+            }
+        };
+        let item = match dispatcher.codec.decode(&data) {
+            Some(Ok(v)) if is_event(&v) && data.len() > limits.max_event_bytes => {
+                let id = echoed_id(&v);
+                Some(Ok(truncated_event(
+                    id.as_deref(),
+                    Some(&v),
+                    data.len(),
+                    limits.max_event_bytes,
+                )))
+            }
+            item => item,
+        };
+        // 调试控制台始终显示 JSON 文本，MessagePack 消息记录解码后的内容
+        let text = match item {
+            Some(Ok(ref v)) if dispatcher.codec == Codec::MsgPack => v.to_string(),
+            _ => String::from_utf8_lossy(&data).into_owned(),
+        };
+        debug_console::record(Direction::In, &text);
+        match item {
+            Some(item) => dispatcher.route(item),
+            None if !text.trim().is_empty() => {
+                eprintln!("Warning: 忽略非协议输出: {}", excerpt(text.trim()));
+            }
+            None => {}
+        }
+    }
+    dispatcher.close();
+}
+
+/// 等待下一行输出；通道关闭（bridge stdout 已结束）视为管道错误
+pub async fn recv(rx: &mut ResponseRx) -> std::io::Result<Incoming> {
+    rx.recv().await.ok_or_else(|| {
+        std::io::Error::new(
+            std::io::ErrorKind::UnexpectedEof,
+            "bridge stdout 已关闭，未收到完整响应",
+        )
+    })
+}
+
+/// 事件行（`_event: true`）之外的第一行即最终响应
+pub fn is_final(v: &Value) -> bool {
+    !is_event(v)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+    use tokio::io::AsyncWriteExt;
+
+    fn block_on<F: std::future::Future>(future: F) -> F::Output {
+        tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap()
+            .block_on(future)
+    }
+
+    /// 按换行切分（含换行），最后一段没有换行时也是一行
+    fn segments(bytes: &[u8]) -> Vec<&[u8]> {
+        bytes.split_inclusive(|&b| b == b'\n').collect()
+    }
+
+    proptest! {
+        /// 任意字节、任意上限与缓冲区大小：逐行读完不出错，每行的结果与按换行切分一致
+        #[test]
+        fn line_reader_matches_split_on_any_bytes(
+            bytes in prop::collection::vec(any::<u8>(), 0..2048),
+            max in 1usize..128,
+            head in 0usize..64,
+            capacity in 1usize..64,
+        ) {
+            let reads = block_on(async {
+                let mut reader = BufReader::with_capacity(capacity, &bytes[..]);
+                let mut reads = Vec::new();
+                loop {
+                    match read_line_keeping_head(&mut reader, max, head).await.unwrap() {
+                        (LineRead::Eof, _) => break,
+                        read => reads.push(read),
+                    }
+                }
+                reads
+            });
+            let expected = segments(&bytes);
+            prop_assert_eq!(reads.len(), expected.len());
+            for ((read, kept), seg) in reads.into_iter().zip(expected) {
+                match read {
+                    LineRead::Line(line) => {
+                        prop_assert!(seg.len() <= max);
+                        prop_assert_eq!(line, String::from_utf8_lossy(seg).into_owned());
+                    }
+                    LineRead::TooLong(len) => {
+                        prop_assert!(len > max);
+                        prop_assert_eq!(len, seg.len());
+                        // 至少保留到发现超长为止读到的部分
+                        prop_assert!(seg.starts_with(&kept));
+                        prop_assert!(kept.len() <= head);
+                        prop_assert!(kept.len() >= head.min(max + 1));
+                    }
+                    LineRead::Eof => unreachable!(),
+                }
+            }
+        }
+
+        /// 任意文本都能归类：对象交出，以 `{` 开头的坏行是 ProtocolError，其余忽略
+        #[test]
+        fn classify_is_total(bytes in prop::collection::vec(any::<u8>(), 0..512)) {
+            let line = String::from_utf8_lossy(&bytes);
+            let braced = line.trim().starts_with('{');
+            match classify(&line) {
+                Some(Ok(v)) => prop_assert!(v.is_object()),
+                Some(Err(e)) => {
+                    prop_assert!(braced);
+                    prop_assert!(e.starts_with(PROTOCOL_ERROR));
+                }
+                None => prop_assert!(!braced),
+            }
+        }
+
+        /// 超长消息的开头可能是任意字节，找回 `_id` 时不应越界
+        #[test]
+        fn salvage_never_panics(
+            bytes in prop::collection::vec(any::<u8>(), 0..512),
+            msgpack in any::<bool>(),
+        ) {
+            let codec = if msgpack { Codec::MsgPack } else { Codec::Json };
+            let (id, _) = salvage(codec, &bytes);
+            if let Some(id) = id {
+                prop_assert!(!id.is_empty() || msgpack);
+            }
+        }
+
+        /// 收到一行任意垃圾输出后分发仍然正常：多个请求在途时坏行无法判断归属，不送给任何请求，
+        /// 之后带 `_id` 的响应照常送到
+        #[test]
+        fn dispatcher_survives_garbage(garbage in prop::collection::vec(any::<u8>(), 0..1024)) {
+            let garbage: Vec<u8> = garbage.into_iter().filter(|&b| b != b'\n').collect();
+            let (first, second) = block_on(async {
+                let (host, mut bridge) = tokio::io::duplex(64 * 1024);
+                let (read_half, write_half) = tokio::io::split(host);
+                let dispatcher =
+                    Dispatcher::start(write_half, BufReader::new(read_half), Framing::Lines, Codec::Json);
+                let (_, mut first) = dispatcher.register();
+                let (id, mut second) = dispatcher.register();
+                bridge.write_all(&garbage).await.unwrap();
+                bridge.write_all(b"\n").await.unwrap();
+                let reply = serde_json::json!({"_id": id, "ok": true});
+                bridge.write_all(format!("{}\n", reply).as_bytes()).await.unwrap();
+                let second = recv(&mut second).await.unwrap();
+                // 第二个请求的响应已送达，若坏行被送给第一个请求也已在通道中
+                let mut items = Vec::new();
+                while let Ok(item) = first.rx.try_recv() {
+                    items.push(item);
+                }
+                (items, second)
+            });
+            prop_assert!(first.is_empty());
+            prop_assert_eq!(second.unwrap()["ok"].as_bool(), Some(true));
+        }
+    }
+
+    fn start_pair() -> (Arc<Dispatcher>, tokio::io::DuplexStream) {
+        let (host, bridge) = tokio::io::duplex(64 * 1024);
+        let (read_half, write_half) = tokio::io::split(host);
+        let dispatcher = Dispatcher::start(
+            write_half,
+            BufReader::new(read_half),
+            Framing::Lines,
+            Codec::Json,
+        );
+        (dispatcher, bridge)
+    }
+
+    #[test]
+    fn reply_without_id_goes_to_the_only_pending_request() {
+        block_on(async {
+            let (dispatcher, mut bridge) = start_pair();
+            let (_, mut only) = dispatcher.register();
+            bridge.write_all(b"{\"ok\": true}\n").await.unwrap();
+            let reply = recv(&mut only).await.unwrap().unwrap();
+            assert_eq!(reply["ok"], true);
+            assert_eq!(dispatcher.in_flight(), 0);
+        });
+    }
+
+    #[test]
+    fn reply_without_id_is_dropped_while_several_are_pending() {
+        block_on(async {
+            let (dispatcher, mut bridge) = start_pair();
+            let (first_id, mut first) = dispatcher.register();
+            let (second_id, mut second) = dispatcher.register();
+            // bridge 对无法解析的请求回复的错误不带 `_id`
+            bridge
+                .write_all(b"{\"ok\": false, \"message\": \"bad json\"}\n{\n")
+                .await
+                .unwrap();
+            let reply = serde_json::json!({ "_id": second_id, "ok": true });
+            bridge
+                .write_all(format!("{}\n", reply).as_bytes())
+                .await
+                .unwrap();
+            assert_eq!(recv(&mut second).await.unwrap().unwrap()["ok"], true);
+            assert!(first.rx.try_recv().is_err());
+            assert_eq!(dispatcher.in_flight(), 1);
+            let reply = serde_json::json!({ "_id": first_id, "ok": true });
+            bridge
+                .write_all(format!("{}\n", reply).as_bytes())
+                .await
+                .unwrap();
+            assert_eq!(recv(&mut first).await.unwrap().unwrap()["ok"], true);
+        });
+    }
+}
@@ -0,0 +1,298 @@
+use crate::bridge::{find_bundled_bridge_exe, find_project_root, find_python_cmd, BridgeState};
+use crate::comsol::{detect_installs, search_roots, ComsolInstall};
+use crate::panics::guarded;
+use crate::settings::LiveSettings;
+use crate::store::{load_json, now_ms, save_json};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::UNIX_EPOCH;
+use tauri::{AppHandle, Manager};
+
+/// 超过该时长的探测结果即使校验通过也重新探测（PATH 上的解释器等无法按文件校验）
+const MAX_AGE_MS: u64 = 7 * 24 * 3600 * 1000;
+
+/// 探测结果依赖的文件：路径、修改时间与大小；文件不存在时 modified_ms 为 None
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+struct Stamp {
+    path: String,
+    modified_ms: Option<u64>,
+    len: u64,
+}
+
+fn stamp(path: &Path) -> Stamp {
+    let meta = std::fs::metadata(path).ok();
+    Stamp {
+        path: path.to_string_lossy().into_owned(),
+        modified_ms: meta
+            .as_ref()
+            .and_then(|m| m.modified().ok())
+            .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+            .map(|d| d.as_millis() as u64),
+        len: meta.map_or(0, |m| m.len()),
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Probed<T> {
+    pub value: T,
+    pub probed_at: u64,
+    stamps: Vec<Stamp>,
+}
+
+impl<T> Probed<T> {
+    fn new(value: T, stamps: Vec<Stamp>) -> Self {
+        Self {
+            value,
+            probed_at: now_ms(),
+            stamps,
+        }
+    }
+
+    /// 依赖的文件都未变化且未过期
+    fn valid(&self, current: &[Stamp]) -> bool {
+        now_ms().saturating_sub(self.probed_at) < MAX_AGE_MS && self.stamps == current
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PythonInfo {
+    /// 启动 bridge 的命令（打包的 bridge 可执行文件或 Python 解释器）
+    pub command: String,
+    pub bundled: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct JdkInfo {
+    pub home: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+}
+
+/// 运行环境探测结果：COMSOL 安装、Python 解释器与可用的 JDK
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct EnvSnapshot {
+    #[serde(default)]
+    pub comsol: Option<Probed<Vec<ComsolInstall>>>,
+    #[serde(default)]
+    pub python: Option<Probed<PythonInfo>>,
+    #[serde(default)]
+    pub jdks: Option<Probed<Vec<JdkInfo>>>,
+}
+
+struct Cache {
+    path: Option<PathBuf>,
+    data: EnvSnapshot,
+}
+
+/// 探测缓存：发现 COMSOL、JDK 等可能要扫描网络驱动器，结果存在 app data 下，
+/// 使用前按依赖文件校验，失效的项才重新探测
+static CACHE: Mutex<Cache> = Mutex::new(Cache {
+    path: None,
+    data: EnvSnapshot {
+        comsol: None,
+        python: None,
+        jdks: None,
+    },
+});
+
+fn lock() -> std::sync::MutexGuard<'static, Cache> {
+    CACHE.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+pub fn load(path: PathBuf) {
+    let data: EnvSnapshot = load_json(&path);
+    *lock() = Cache {
+        path: Some(path),
+        data,
+    };
+}
+
+fn update(f: impl FnOnce(&mut EnvSnapshot)) {
+    let mut cache = lock();
+    f(&mut cache.data);
+    if let Some(ref p) = cache.path {
+        if let Err(e) = save_json(p, &cache.data) {
+            eprintln!("Warning: 保存环境探测缓存失败: {}", e);
+        }
+    }
+}
+
+/// 安装位置的上级目录（新增或删除安装会改变其修改时间）、各安装的可执行文件与 COMSOL_JAR_PATH
+fn comsol_stamps(installs: &[ComsolInstall]) -> Vec<Stamp> {
+    let mut stamps: Vec<Stamp> = search_roots().iter().map(|p| stamp(p)).collect();
+    stamps.extend(installs.iter().map(|i| stamp(Path::new(&i.executable))));
+    if let Ok(jar) = std::env::var("COMSOL_JAR_PATH") {
+        stamps.push(stamp(Path::new(&jar)));
+    }
+    stamps
+}
+
+/// 已安装的 COMSOL；缓存仍有效时不再扫描
+pub fn comsol_installs() -> Vec<ComsolInstall> {
+    let cached = lock().data.comsol.clone();
+    if let Some(c) = cached.filter(|c| c.valid(&comsol_stamps(&c.value))) {
+        return c.value;
+    }
+    let installs = detect_installs();
+    let probed = Probed::new(installs.clone(), comsol_stamps(&installs));
+    update(|d| d.comsol = Some(probed));
+    installs
+}
+
+fn python_stamps(info: &PythonInfo) -> Vec<Stamp> {
+    let mut stamps = vec![stamp(Path::new(&info.command))];
+    if let Some(root) = find_project_root() {
+        stamps.push(stamp(&root.join("pyproject.toml")));
+    }
+    stamps
+}
+
+fn probe_python() -> Option<PythonInfo> {
+    if let Some(exe) = find_bundled_bridge_exe() {
+        return Some(PythonInfo {
+            command: exe.to_string_lossy().into_owned(),
+            bundled: true,
+            version: None,
+        });
+    }
+    let root = find_project_root()?;
+    let (command, args) = find_python_cmd(&root);
+    // 只保留解释器选项（如 py 启动器的 -3），去掉脚本与子命令
+    let options = args.iter().filter(|a| a.starts_with('-'));
+    let version = std::process::Command::new(&command)
+        .args(options)
+        .arg("--version")
+        .output()
+        .ok()
+        .filter(|o| o.status.success())
+        .map(|o| {
+            // Python 2 把版本写到 stderr
+            let text = [o.stdout, o.stderr].concat();
+            String::from_utf8_lossy(&text).trim().to_string()
+        })
+        .filter(|v| !v.is_empty());
+    Some(PythonInfo {
+        command,
+        bundled: false,
+        version,
+    })
+}
+
+fn python_info() -> Option<PythonInfo> {
+    let cached = lock().data.python.clone();
+    if let Some(p) = cached.filter(|p| p.valid(&python_stamps(&p.value))) {
+        return Some(p.value);
+    }
+    let info = probe_python()?;
+    let probed = Probed::new(info.clone(), python_stamps(&info));
+    update(|d| d.python = Some(probed));
+    Some(info)
+}
+
+fn java_exe(home: &Path) -> PathBuf {
+    #[cfg(target_os = "windows")]
+    return home.join("bin").join("java.exe");
+    #[cfg(not(target_os = "windows"))]
+    return home.join("bin").join("java");
+}
+
+fn jdk_stamps(homes: &[PathBuf]) -> Vec<Stamp> {
+    homes
+        .iter()
+        .flat_map(|h| [stamp(&java_exe(h)), stamp(&h.join("release"))])
+        .collect()
+}
+
+/// JDK 版本：优先读 release 文件，没有时运行 `java -version`
+fn jdk_version(home: &Path) -> Option<String> {
+    if let Ok(text) = std::fs::read_to_string(home.join("release")) {
+        if let Some(v) = text.lines().find_map(|l| l.strip_prefix("JAVA_VERSION=")) {
+            return Some(v.trim().trim_matches('"').to_string());
+        }
+    }
+    let output = std::process::Command::new(java_exe(home))
+        .arg("-version")
+        .output()
+        .ok()?;
+    let text = String::from_utf8_lossy(&output.stderr);
+    let line = text.lines().next()?;
+    line.split('"').nth(1).map(String::from)
+}
+
+fn jdks(homes: &[PathBuf]) -> Vec<JdkInfo> {
+    let cached = lock().data.jdks.clone();
+    let stamps = jdk_stamps(homes);
+    if let Some(j) = cached.filter(|j| j.valid(&stamps)) {
+        return j.value;
+    }
+    let infos: Vec<JdkInfo> = homes
+        .iter()
+        .filter(|h| java_exe(h).is_file())
+        .map(|h| JdkInfo {
+            home: h.to_string_lossy().into_owned(),
+            version: jdk_version(h),
+        })
+        .collect();
+    let probed = Probed::new(infos.clone(), stamps);
+    update(|d| d.jdks = Some(probed));
+    infos
+}
+
+/// 候选 JDK：bridge 当前使用的、设置文件指定的与 JAVA_HOME 环境变量
+async fn java_homes(app: &AppHandle) -> Vec<PathBuf> {
+    let mut homes = Vec::new();
+    if let Some(h) = app
+        .state::<BridgeState>()
+        .lock()
+        .await
+        .bundled_java_home
+        .clone()
+    {
+        homes.push(h);
+    }
+    if let Some(h) = app.try_state::<LiveSettings>().and_then(|s| s.java_home()) {
+        homes.push(h);
+    }
+    if let Some(h) = std::env::var_os("JAVA_HOME").map(PathBuf::from) {
+        homes.push(h);
+    }
+    homes.dedup();
+    homes
+}
+
+/// 校验并补齐全部探测项后返回当前结果
+fn probe_all(homes: &[PathBuf]) -> EnvSnapshot {
+    comsol_installs();
+    python_info();
+    jdks(homes);
+    lock().data.clone()
+}
+
+/// 当前缓存的探测结果（不做校验，用于诊断信息）
+pub fn snapshot() -> EnvSnapshot {
+    lock().data.clone()
+}
+
+/// 启动后在后台校验缓存，失效的项重新探测，之后的调用直接命中缓存
+pub fn spawn_environment_probe(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let homes = java_homes(&app).await;
+        let _ = tauri::async_runtime::spawn_blocking(move || probe_all(&homes)).await;
+    });
+}
+
+/// 丢弃缓存并重新探测 COMSOL 安装、Python 解释器与 JDK
+#[tauri::command]
+pub async fn refresh_environment_cache(app: AppHandle) -> Result<EnvSnapshot, String> {
+    guarded("refresh_environment_cache", async move {
+        let homes = java_homes(&app).await;
+        update(|d| *d = EnvSnapshot::default());
+        tauri::async_runtime::spawn_blocking(move || probe_all(&homes))
+            .await
+            .map_err(|e| e.to_string())
+    })
+    .await
+}
@@ -0,0 +1,156 @@
+use crate::jobs::{JobRecord, JobRegistry, JobStatus};
+use crate::panics::guarded;
+use crate::progress::{JobProgress, ProgressAggregator};
+use crate::store::now_ms;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+/// 参与估算的历史任务数上限（取最近的）
+const MAX_SAMPLES: usize = 20;
+/// 历史不足时改用进度外推，进度太低时外推误差过大
+const MIN_PERCENT_FOR_EXTRAPOLATION: f64 = 10.0;
+
+/// 请求中用于判断「相似任务」的元数据键
+const SIGNATURE_KEYS: &[&str] = &["physics", "study", "mesh_size", "mesh"];
+
+/// 相似历史任务的耗时基线：总耗时与各阶段开始时刻（均取中位数）
+#[derive(Clone, Debug, Default)]
+pub struct Baseline {
+    pub samples: usize,
+    pub total_ms: u64,
+    pub stage_offsets: BTreeMap<String, u64>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct JobEta {
+    pub request_id: String,
+    pub remaining_ms: Option<u64>,
+    pub eta_at: Option<u64>,
+    /// history（相似任务历史）/ progress（按当前进度外推）/ none
+    pub basis: &'static str,
+    pub samples: usize,
+    pub percent: f64,
+    pub stage: String,
+}
+
+fn signature(cmd: &str, payload: &Value) -> Vec<Value> {
+    std::iter::once(Value::String(cmd.to_string()))
+        .chain(
+            SIGNATURE_KEYS
+                .iter()
+                .map(|k| payload.get(*k).cloned().unwrap_or(Value::Null)),
+        )
+        .collect()
+}
+
+fn median(mut values: Vec<u64>) -> Option<u64> {
+    if values.is_empty() {
+        return None;
+    }
+    values.sort_unstable();
+    Some(values[values.len() / 2])
+}
+
+/// 由同一命令、相同物理场/网格元数据的成功任务计算基线；
+/// 元数据都没填时退化为同一命令、同一项目
+pub fn baseline(
+    jobs: &JobRegistry,
+    cmd: &str,
+    payload: &Value,
+    project: Option<&str>,
+) -> Option<Baseline> {
+    let sig = signature(cmd, payload);
+    let has_metadata = sig.iter().skip(1).any(|v| !v.is_null());
+    let samples: Vec<JobRecord> = jobs
+        .list(None, usize::MAX)
+        .into_iter()
+        .filter(|j| j.status == JobStatus::Succeeded && j.cmd == cmd)
+        .filter(|j| j.started_at.is_some() && j.finished_at.is_some())
+        .filter(|j| {
+            if has_metadata {
+                signature(&j.cmd, &j.payload) == sig
+            } else {
+                j.project.as_deref() == project
+            }
+        })
+        .take(MAX_SAMPLES)
+        .collect();
+    let total_ms = median(
+        samples
+            .iter()
+            .filter_map(|j| Some(j.finished_at?.saturating_sub(j.started_at?)))
+            .collect(),
+    )?;
+    let mut per_stage: BTreeMap<String, Vec<u64>> = BTreeMap::new();
+    for j in &samples {
+        for (stage, offset) in &j.stage_offsets {
+            per_stage.entry(stage.clone()).or_default().push(*offset);
+        }
+    }
+    Some(Baseline {
+        samples: samples.len(),
+        total_ms,
+        stage_offsets: per_stage
+            .into_iter()
+            .filter_map(|(stage, v)| Some((stage, median(v)?)))
+            .collect(),
+    })
+}
+
+/// 剩余时间：有历史基线时以「当前阶段在历史中的开始时刻」对齐，
+/// 否则按当前进度外推
+pub fn estimate(
+    progress: &JobProgress,
+    baseline: Option<&Baseline>,
+    now: u64,
+) -> (Option<u64>, &'static str) {
+    if progress.finished {
+        return (Some(0), progress.eta_basis.unwrap_or("none"));
+    }
+    let elapsed = now.saturating_sub(progress.started_at);
+    if let Some(b) = baseline {
+        let remaining = match (
+            b.stage_offsets.get(&progress.stage),
+            progress.stage_offsets.get(&progress.stage),
+        ) {
+            (Some(hist), Some(cur)) => {
+                let in_stage = elapsed.saturating_sub(*cur);
+                b.total_ms.saturating_sub(*hist).saturating_sub(in_stage)
+            }
+            _ => b.total_ms.saturating_sub(elapsed),
+        };
+        // 已超过历史耗时时基线失效，交给进度外推
+        if remaining > 0 {
+            return (Some(remaining), "history");
+        }
+    }
+    if progress.percent >= MIN_PERCENT_FOR_EXTRAPOLATION && progress.percent < 100.0 {
+        let total = elapsed as f64 * 100.0 / progress.percent;
+        return (Some((total - elapsed as f64).max(0.0) as u64), "progress");
+    }
+    (None, "none")
+}
+
+/// 查询任务的预计剩余时间（按当前时刻重新计算）
+#[tauri::command]
+pub async fn job_eta(
+    progress: tauri::State<'_, ProgressAggregator>,
+    job_id: String,
+) -> Result<JobEta, String> {
+    guarded("job_eta", async move {
+        let p = progress
+            .refresh_eta(&job_id)
+            .ok_or_else(|| format!("没有任务 {} 的进度信息", job_id))?;
+        Ok(JobEta {
+            request_id: p.request_id,
+            remaining_ms: p.eta_ms,
+            eta_at: p.eta_ms.map(|ms| now_ms() + ms),
+            basis: p.eta_basis.unwrap_or("none"),
+            samples: p.eta_samples,
+            percent: p.percent,
+            stage: p.stage,
+        })
+    })
+    .await
+}
@@ -0,0 +1,362 @@
+use crate::panics::guarded;
+use crate::stream::is_mergeable_chunk;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeSet, HashMap};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, EventTarget, Manager, WebviewWindow, WindowEvent};
+
+/// 窗口最小化状态的缓存时长；逐条 token 查询窗口状态代价太高
+const MINIMIZED_TTL: Duration = Duration::from_millis(500);
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct WindowSubscription {
+    #[serde(default)]
+    pub projects: BTreeSet<String>,
+    #[serde(default)]
+    pub jobs: BTreeSet<String>,
+}
+
+/// 最小化期间暂缓发送的一条事件
+#[derive(Clone, Debug, PartialEq)]
+struct HeldEvent {
+    event: String,
+    request_id: String,
+    payload: Value,
+}
+
+/// 各窗口暂缓发送的高频事件；相邻的同阶段 llm_stream_chunk 合并为一条，
+/// 积压只随输出文本增长，不随事件条数增长
+#[derive(Default)]
+struct HeldEvents {
+    by_window: HashMap<String, Vec<HeldEvent>>,
+}
+
+impl HeldEvents {
+    fn hold(&mut self, label: &str, event: &str, request_id: &str, payload: Value) {
+        let held = self.by_window.entry(label.to_string()).or_default();
+        if let Some(last) = held.last_mut() {
+            if last.event == event
+                && last.request_id == request_id
+                && is_mergeable_chunk(&last.payload, &payload)
+            {
+                let extra = payload["data"]["chunk"].as_str().unwrap_or_default();
+                if let Some(Value::String(chunk)) = last
+                    .payload
+                    .get_mut("data")
+                    .and_then(|d| d.get_mut("chunk"))
+                {
+                    chunk.push_str(extra);
+                    return;
+                }
+            }
+        }
+        held.push(HeldEvent {
+            event: event.to_string(),
+            request_id: request_id.to_string(),
+            payload,
+        });
+    }
+
+    /// 取出某个窗口暂缓的全部事件
+    fn take_window(&mut self, label: &str) -> Vec<HeldEvent> {
+        self.by_window.remove(label).unwrap_or_default()
+    }
+
+    /// 取出各窗口中属于某个任务的事件，其余任务的事件保持原顺序
+    fn take_job(&mut self, request_id: &str) -> Vec<(String, HeldEvent)> {
+        let mut out = Vec::new();
+        for (label, held) in self.by_window.iter_mut() {
+            let (job, rest): (Vec<_>, Vec<_>) =
+                held.drain(..).partition(|e| e.request_id == request_id);
+            *held = rest;
+            out.extend(job.into_iter().map(|e| (label.clone(), e)));
+        }
+        self.by_window.retain(|_, held| !held.is_empty());
+        out
+    }
+}
+
+/// 任务事件的按窗口路由：登记了订阅的窗口只收到所订阅项目/任务的事件，
+/// 未登记的窗口（如旧版主窗口）照旧收到全部。最小化的窗口暂缓接收高频事件：
+/// 合并后暂存，窗口恢复、收到下一条普通事件或任务结束时补发，输出不会丢失
+#[derive(Default)]
+pub struct EventRouter {
+    subscriptions: Mutex<HashMap<String, WindowSubscription>>,
+    /// request_id -> 所属项目，任务开始时登记，避免每条事件都查任务表
+    job_projects: Mutex<HashMap<String, Option<String>>>,
+    minimized: Mutex<HashMap<String, (bool, Instant)>>,
+    held: Mutex<HeldEvents>,
+}
+
+impl EventRouter {
+    pub fn track_job(&self, request_id: &str, project: Option<String>) {
+        self.job_projects
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(request_id.to_string(), project);
+    }
+
+    pub fn untrack_job(&self, request_id: &str) {
+        self.job_projects
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(request_id);
+    }
+
+    fn wants(&self, label: &str, request_id: &str, project: Option<&str>) -> bool {
+        let subs = self.subscriptions.lock().unwrap_or_else(|e| e.into_inner());
+        match subs.get(label) {
+            None => true,
+            Some(s) => {
+                s.jobs.contains(request_id) || project.is_some_and(|p| s.projects.contains(p))
+            }
+        }
+    }
+
+    fn is_minimized(&self, label: &str, window: &WebviewWindow) -> bool {
+        let mut cache = self.minimized.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some((minimized, at)) = cache.get(label) {
+            if at.elapsed() < MINIMIZED_TTL {
+                return *minimized;
+            }
+        }
+        let minimized = window.is_minimized().unwrap_or(false);
+        cache.insert(label.to_string(), (minimized, Instant::now()));
+        minimized
+    }
+
+    /// 去掉已关闭窗口的订阅与缓存
+    fn prune(&self, alive: &HashMap<String, WebviewWindow>) {
+        self.subscriptions
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .retain(|label, _| alive.contains_key(label));
+        self.minimized
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .retain(|label, _| alive.contains_key(label));
+        self.held
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .by_window
+            .retain(|label, _| alive.contains_key(label));
+    }
+}
+
+fn emit_held(app: &AppHandle, label: &str, held: Vec<HeldEvent>) {
+    for e in held {
+        let _ = app.emit_to(
+            EventTarget::webview_window(label.to_string()),
+            &e.event,
+            e.payload,
+        );
+    }
+}
+
+/// 补发窗口最小化期间暂缓的事件
+pub fn flush_window(app: &AppHandle, label: &str) {
+    let router = app.state::<EventRouter>();
+    router
+        .minimized
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .remove(label);
+    let held = router
+        .held
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .take_window(label);
+    emit_held(app, label, held);
+}
+
+/// 任务结束时补发其暂缓的事件，保证输出先于最终结果到达，即使窗口仍处于最小化
+pub fn flush_job(app: &AppHandle, request_id: &str) {
+    let held = app
+        .state::<EventRouter>()
+        .held
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .take_job(request_id);
+    for (label, e) in held {
+        emit_held(app, &label, vec![e]);
+    }
+}
+
+/// 新建窗口后调用：窗口恢复或获得焦点时补发暂缓的事件
+pub fn attach(app: &AppHandle, window: &WebviewWindow) {
+    if app.try_state::<EventRouter>().is_none() {
+        return;
+    }
+    let (app, label) = (app.clone(), window.label().to_string());
+    window.on_window_event(move |event| {
+        if matches!(event, WindowEvent::Focused(true) | WindowEvent::Resized(_)) {
+            flush_window(&app, &label);
+        }
+    });
+}
+
+/// 把任务相关事件发往关心该任务的窗口；`heavy` 为逐 token 一类的高频事件，
+/// 最小化窗口暂缓接收，之后补发
+pub fn emit_job_event<S: Serialize + Clone>(
+    app: &AppHandle,
+    event: &str,
+    request_id: &str,
+    payload: S,
+    heavy: bool,
+) {
+    let router = app.state::<EventRouter>();
+    let project = router
+        .job_projects
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .get(request_id)
+        .cloned()
+        .flatten();
+    let windows = app.webview_windows();
+    router.prune(&windows);
+    for (label, window) in &windows {
+        if !router.wants(label, request_id, project.as_deref()) {
+            continue;
+        }
+        if heavy && router.is_minimized(label, window) {
+            if let Ok(value) = serde_json::to_value(payload.clone()) {
+                router
+                    .held
+                    .lock()
+                    .unwrap_or_else(|e| e.into_inner())
+                    .hold(label, event, request_id, value);
+            }
+            continue;
+        }
+        let held = router
+            .held
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .take_window(label);
+        emit_held(app, label, held);
+        let _ = app.emit_to(
+            EventTarget::webview_window(label.clone()),
+            event,
+            payload.clone(),
+        );
+    }
+}
+
+/// 调用方窗口只接收指定项目/任务的事件（覆盖之前的订阅）
+#[tauri::command]
+pub async fn window_subscribe(
+    window: WebviewWindow,
+    router: tauri::State<'_, EventRouter>,
+    projects: Option<Vec<String>>,
+    jobs: Option<Vec<String>>,
+) -> Result<(), String> {
+    guarded("window_subscribe", async move {
+        let sub = WindowSubscription {
+            projects: projects.unwrap_or_default().into_iter().collect(),
+            jobs: jobs.unwrap_or_default().into_iter().collect(),
+        };
+        router
+            .subscriptions
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(window.label().to_string(), sub);
+        Ok(())
+    })
+    .await
+}
+
+/// 取消订阅，恢复接收全部任务事件
+#[tauri::command]
+pub async fn window_unsubscribe(
+    window: WebviewWindow,
+    router: tauri::State<'_, EventRouter>,
+) -> Result<(), String> {
+    guarded("window_unsubscribe", async move {
+        router
+            .subscriptions
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(window.label());
+        Ok(())
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn window_subscriptions(
+    router: tauri::State<'_, EventRouter>,
+) -> Result<HashMap<String, WindowSubscription>, String> {
+    guarded("window_subscriptions", async move {
+        Ok(router
+            .subscriptions
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone())
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn chunk(text: &str, phase: &str) -> Value {
+        json!({
+            "_event": true,
+            "type": "llm_stream_chunk",
+            "data": { "chunk": text, "phase": phase },
+            "iteration": 1,
+        })
+    }
+
+    fn text(held: &[HeldEvent]) -> String {
+        held.iter()
+            .filter_map(|e| e.payload["data"]["chunk"].as_str())
+            .collect()
+    }
+
+    #[test]
+    fn minimized_window_keeps_all_chunk_text() {
+        let mut held = HeldEvents::default();
+        let pieces = ["思考", "中", "……", " done"];
+        for p in pieces {
+            held.hold("main", "bridge-event", "stream-1", chunk(p, "reasoning"));
+        }
+        let out = held.take_window("main");
+        assert_eq!(out.len(), 1);
+        assert_eq!(text(&out), pieces.concat());
+        assert!(held.take_window("main").is_empty());
+    }
+
+    #[test]
+    fn chunks_merge_only_within_phase_and_job() {
+        let mut held = HeldEvents::default();
+        held.hold("main", "bridge-event", "stream-1", chunk("a", "reasoning"));
+        held.hold("main", "bridge-event", "stream-1", chunk("b", "answer"));
+        held.hold("main", "bridge-event", "stream-2", chunk("c", "answer"));
+        held.hold("main", "bridge-event", "stream-1", chunk("d", "answer"));
+        let out = held.take_window("main");
+        assert_eq!(out.len(), 4);
+        assert_eq!(text(&out), "abcd");
+    }
+
+    #[test]
+    fn take_job_leaves_other_jobs_in_order() {
+        let mut held = HeldEvents::default();
+        held.hold("main", "bridge-event", "stream-1", chunk("a", "answer"));
+        held.hold("main", "bridge-event", "stream-2", chunk("x", "answer"));
+        held.hold("side", "bridge-event", "stream-1", chunk("b", "answer"));
+        held.hold("main", "bridge-event", "stream-2", chunk("y", "reasoning"));
+        let mut job = held.take_job("stream-1");
+        job.sort_by(|a, b| a.0.cmp(&b.0));
+        assert_eq!(job.len(), 2);
+        assert_eq!(job[0].0, "main");
+        assert_eq!(text(&[job[0].1.clone()]), "a");
+        assert_eq!(text(&[job[1].1.clone()]), "b");
+        assert_eq!(text(&held.take_window("main")), "xy");
+        assert!(held.by_window.is_empty());
+    }
+}
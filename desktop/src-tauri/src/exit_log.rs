@@ -0,0 +1,75 @@
+use crate::bridge::{wait_exit, BridgeExit, ExitWatch};
+use crate::bridge_error::BridgeError;
+use crate::panics::guarded;
+use crate::sessions;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use tauri::{AppHandle, Emitter};
+
+static APP: OnceLock<AppHandle> = OnceLock::new();
+
+/// 记录 bridge 进程的意外退出：每个 bridge 状态一份，跨重启保留最近一次。
+/// 主动结束（停止、重启、关闭）前调用 `release`，之后的退出不算意外
+#[derive(Debug, Default)]
+pub struct ExitLog {
+    /// 当前受监视的进程的序号，0 表示没有；不用 pid，回放用的模拟 bridge 没有 pid
+    current: AtomicU64,
+    next: AtomicU64,
+    last: Mutex<Option<BridgeExit>>,
+}
+
+impl ExitLog {
+    /// 监视新安装的 bridge 进程；意外退出时记录并发送 `bridge-exited`
+    pub fn watch(self: &Arc<Self>, exit: ExitWatch) {
+        let token = self.next.fetch_add(1, Ordering::SeqCst) + 1;
+        self.current.store(token, Ordering::SeqCst);
+        let log = self.clone();
+        tauri::async_runtime::spawn(async move {
+            let exit = wait_exit(exit).await;
+            if log
+                .current
+                .compare_exchange(token, 0, Ordering::SeqCst, Ordering::SeqCst)
+                .is_err()
+            {
+                return;
+            }
+            *log.last.lock().unwrap_or_else(|e| e.into_inner()) = Some(exit.clone());
+            if let Some(app) = APP.get() {
+                let _ = app.emit(
+                    "bridge-exited",
+                    serde_json::json!({
+                        "exit": exit,
+                        "error": BridgeError::exited(&exit),
+                    }),
+                );
+            }
+        });
+    }
+
+    /// 即将主动结束当前进程
+    pub fn release(&self) {
+        self.current.store(0, Ordering::SeqCst);
+    }
+
+    pub fn last(&self) -> Option<BridgeExit> {
+        self.last.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+}
+
+pub fn attach(app: AppHandle) {
+    let _ = APP.set(app);
+}
+
+/// 最近一次意外退出的退出码、原因与 stderr 末尾；未发生过时为 null
+#[tauri::command]
+pub async fn bridge_last_exit(
+    app: AppHandle,
+    session: Option<String>,
+) -> Result<Option<BridgeExit>, BridgeError> {
+    guarded("bridge_last_exit", async move {
+        let state = sessions::resolve(&app, session.as_deref())?;
+        let exits = state.lock().await.exits.clone();
+        Ok(exits.last())
+    })
+    .await
+}
@@ -0,0 +1,204 @@
+use crate::panics::guarded;
+use serde::Serialize;
+use serde_json::Value;
+use std::path::Path;
+
+/// 由提示词生成的文件名所在的请求字段；只清理最后一级文件名，目录部分保持原样
+const NAME_KEYS: &[&str] = &["output", "output_filename"];
+/// 文件名字节数上限（ext4 等文件系统的单级名称上限）
+const MAX_NAME_BYTES: usize = 255;
+/// 超过该长度的“扩展名”视为名称的一部分
+const MAX_EXT_CHARS: usize = 16;
+/// Windows 上不能作为文件名（不论扩展名）的设备名
+const RESERVED: &[&str] = &[
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8",
+    "COM9", "COM¹", "COM²", "COM³", "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8",
+    "LPT9", "LPT¹", "LPT²", "LPT³",
+];
+
+#[derive(Clone, Debug, Serialize)]
+pub struct SanitizedName {
+    pub name: String,
+    pub changed: bool,
+    /// 做过的处理：unsafe_chars / trailing_dots_spaces / reserved_name / too_long / empty
+    pub issues: Vec<&'static str>,
+}
+
+fn is_unsafe(c: char) -> bool {
+    c.is_control() || matches!(c, '<' | '>' | ':' | '"' | '/' | '\\' | '|' | '?' | '*')
+}
+
+/// 按字节截断到字符边界
+fn truncate_bytes(s: &str, max: usize) -> &str {
+    let mut end = s.len().min(max);
+    while !s.is_char_boundary(end) {
+        end -= 1;
+    }
+    &s[..end]
+}
+
+/// 得到各平台都能创建的文件名：替换非法字符与控制字符，去掉首尾空格与结尾的点，
+/// 避开 Windows 设备名（CON、NUL 等），超长时截断名称并保留扩展名；结果为空时用 `fallback`
+pub fn sanitize_file_name(name: &str, fallback: &str) -> SanitizedName {
+    let mut issues = Vec::new();
+    let mut out: String = name
+        .chars()
+        .map(|c| if is_unsafe(c) { '_' } else { c })
+        .collect();
+    if out != name {
+        issues.push("unsafe_chars");
+    }
+
+    let trimmed = out.trim_start().trim_end_matches(['.', ' ']).to_string();
+    if trimmed.len() != out.trim_start().len() {
+        issues.push("trailing_dots_spaces");
+    }
+    out = trimmed;
+
+    let (stem, ext) = match out.rsplit_once('.') {
+        Some((s, e)) if !s.is_empty() && !e.is_empty() && e.chars().count() <= MAX_EXT_CHARS => {
+            (s.to_string(), format!(".{}", e))
+        }
+        _ => (out.clone(), String::new()),
+    };
+    let device = stem.split('.').next().unwrap_or_default().trim_end();
+    let mut stem = if RESERVED.iter().any(|r| r.eq_ignore_ascii_case(device)) {
+        issues.push("reserved_name");
+        format!("_{}", stem)
+    } else {
+        stem
+    };
+
+    if stem.len() + ext.len() > MAX_NAME_BYTES {
+        issues.push("too_long");
+        let keep = MAX_NAME_BYTES.saturating_sub(ext.len());
+        stem = truncate_bytes(&stem, keep)
+            .trim_end_matches(['.', ' '])
+            .to_string();
+    }
+
+    let name_out = if stem.trim_matches('.').is_empty() {
+        issues.push("empty");
+        format!("{}{}", fallback, ext)
+    } else {
+        format!("{}{}", stem, ext)
+    };
+    SanitizedName {
+        changed: name_out != name,
+        name: name_out,
+        issues,
+    }
+}
+
+/// 清理路径的最后一级文件名
+fn sanitize_last(path: &str, fallback: &str) -> String {
+    let p = Path::new(path);
+    let Some(name) = p.file_name().map(|n| n.to_string_lossy().into_owned()) else {
+        return path.to_string();
+    };
+    let clean = sanitize_file_name(&name, fallback);
+    if !clean.changed {
+        return path.to_string();
+    }
+    p.with_file_name(clean.name).to_string_lossy().into_owned()
+}
+
+/// 发送前清理请求中由提示词生成的输出文件名，避免 bridge 保存时因名称非法失败
+pub fn sanitize_payload(payload: &mut Value) {
+    let Some(obj) = payload.as_object_mut() else {
+        return;
+    };
+    for key in NAME_KEYS {
+        if let Some(Value::String(s)) = obj.get_mut(*key) {
+            let trimmed = s.trim();
+            if !trimmed.is_empty() {
+                *s = sanitize_last(trimmed, "model");
+            }
+        }
+    }
+}
+
+/// 检查并清理文件名，返回清理结果与做过的处理
+#[tauri::command]
+pub async fn sanitize_filename(
+    name: String,
+    fallback: Option<String>,
+) -> Result<SanitizedName, String> {
+    guarded("sanitize_filename", async move {
+        let fallback = fallback
+            .as_deref()
+            .map(str::trim)
+            .filter(|f| !f.is_empty())
+            .unwrap_or("untitled");
+        Ok(sanitize_file_name(&name, fallback))
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    /// 与 tests/test_filenames.py 相同的用例，保证两端规则一致
+    fn clean(name: &str) -> String {
+        sanitize_file_name(name, "model").name
+    }
+
+    #[test]
+    fn plain_name_unchanged() {
+        assert_eq!(clean("heat_sink.mph"), "heat_sink.mph");
+        assert_eq!(clean("散热器 模型.mph"), "散热器 模型.mph");
+        assert!(!sanitize_file_name("heat_sink.mph", "model").changed);
+    }
+
+    #[test]
+    fn unsafe_chars_replaced() {
+        assert_eq!(clean("a<b>:c\"d|e?f*.mph"), "a_b__c_d_e_f_.mph");
+        assert_eq!(clean("dir/name\\x.mph"), "dir_name_x.mph");
+        assert_eq!(clean("tab\there.mph"), "tab_here.mph");
+        assert_eq!(
+            sanitize_file_name("a?b.mph", "model").issues,
+            vec!["unsafe_chars"]
+        );
+    }
+
+    #[test]
+    fn trailing_dots_and_spaces() {
+        assert_eq!(clean("  model. . "), "model");
+        assert_eq!(clean("model.mph."), "model.mph");
+    }
+
+    #[test]
+    fn reserved_names() {
+        assert_eq!(clean("CON"), "_CON");
+        assert_eq!(clean("nul.mph"), "_nul.mph");
+        assert_eq!(clean("Com1.tar.gz"), "_Com1.tar.gz");
+        assert_eq!(clean("LPT¹.txt"), "_LPT¹.txt");
+        assert_eq!(clean("console.mph"), "console.mph");
+    }
+
+    #[test]
+    fn empty_uses_fallback() {
+        assert_eq!(clean(""), "model");
+        assert_eq!(sanitize_file_name("...", "untitled").name, "untitled");
+        assert_eq!(clean(".mph"), ".mph");
+    }
+
+    #[test]
+    fn too_long_keeps_extension() {
+        let out = clean(&format!("{}.mph", "模".repeat(200)));
+        assert!(out.ends_with(".mph"));
+        assert!(out.len() <= MAX_NAME_BYTES);
+        assert_eq!(out[..out.len() - 4], "模".repeat((255 - 4) / 3));
+    }
+
+    #[test]
+    fn payload_only_last_component_cleaned() {
+        let mut payload = json!({ "output": "out/dir/a:b.mph", "title": "a:b" });
+        sanitize_payload(&mut payload);
+        let expected = Path::new("out/dir").join("a_b.mph");
+        assert_eq!(payload["output"], json!(expected.to_string_lossy()));
+        assert_eq!(payload["title"], "a:b");
+    }
+}
@@ -0,0 +1,225 @@
+use crate::dispatcher::{read_line_keeping_head, LineRead};
+use serde_json::Value;
+use tokio::io::{AsyncBufRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+/// 长度前缀的字节数（大端 u32）
+const PREFIX_BYTES: usize = 4;
+/// 设为 `lines` 时只提供按行分帧，便于直接查看原始管道内容
+const FRAMING_ENV: &str = "MPH_AGENT_BRIDGE_FRAMING";
+
+/// bridge 管道的分帧方式。握手（就绪信号与 hello）固定按行进行，之后双方切换到协商结果
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Framing {
+    /// 每条消息一行 JSON
+    Lines,
+    /// 4 字节大端长度 + UTF-8 JSON；大段网格/结果数据不受行缓冲与换行影响
+    LengthPrefixed,
+}
+
+impl Framing {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Lines => "lines",
+            Self::LengthPrefixed => "length_prefixed",
+        }
+    }
+
+    /// hello 中按偏好顺序提供的分帧方式
+    pub fn offered() -> Vec<&'static str> {
+        let lines_only = std::env::var(FRAMING_ENV)
+            .map(|v| v.trim().eq_ignore_ascii_case("lines"))
+            .unwrap_or(false);
+        if lines_only {
+            vec![Self::Lines.name()]
+        } else {
+            vec![Self::LengthPrefixed.name(), Self::Lines.name()]
+        }
+    }
+
+    /// hello 回复选定的分帧方式；旧版 bridge 不回报时按行
+    pub fn from_reply(reply: &Value) -> Result<Self, String> {
+        match reply.get("framing").and_then(|v| v.as_str()) {
+            None | Some("lines") => Ok(Self::Lines),
+            Some("length_prefixed") if Self::offered().contains(&"length_prefixed") => {
+                Ok(Self::LengthPrefixed)
+            }
+            Some(other) => Err(format!("bridge 选择了未提供的分帧方式 `{}`", other)),
+        }
+    }
+}
+
+/// 写入一条消息；调用方负责加锁，保证并发写入不交错
+pub async fn write_frame<W: AsyncWrite + Unpin>(
+    writer: &mut W,
+    framing: Framing,
+    text: &str,
+) -> std::io::Result<()> {
+    match framing {
+        Framing::Lines => writer.write_all(format!("{}\n", text).as_bytes()).await?,
+        Framing::LengthPrefixed => {
+            let len = u32::try_from(text.len()).map_err(|_| {
+                std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    format!("消息过长（{} 字节）", text.len()),
+                )
+            })?;
+            writer.write_all(&len.to_be_bytes()).await?;
+            writer.write_all(text.as_bytes()).await?;
+        }
+    }
+    writer.flush().await
+}
+
+/// 长度前缀模式下读到的一条原始消息
+pub enum Frame {
+    Eof,
+    Data(Vec<u8>),
+    /// 超过长度上限的消息，已整条跳过，只保留开头的若干字节
+    TooLong {
+        len: usize,
+        head: Vec<u8>,
+    },
+}
+
+/// 读取一条长度前缀消息：超过 `max` 的消息整条跳过，只报告长度与开头至多 `head` 个字节；
+/// 在消息中途遇到 EOF 视为管道错误
+async fn read_prefixed<R: AsyncBufRead + Unpin>(
+    reader: &mut R,
+    max: usize,
+    head: usize,
+) -> std::io::Result<Frame> {
+    let mut prefix = [0u8; PREFIX_BYTES];
+    let mut filled = 0;
+    while filled < PREFIX_BYTES {
+        let n = reader.read(&mut prefix[filled..]).await?;
+        if n == 0 {
+            if filled == 0 {
+                return Ok(Frame::Eof);
+            }
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::UnexpectedEof,
+                "长度前缀不完整",
+            ));
+        }
+        filled += n;
+    }
+    let len = u32::from_be_bytes(prefix) as usize;
+    if len > max {
+        let mut kept = vec![0u8; head.min(len)];
+        reader.read_exact(&mut kept).await?;
+        let rest = (len - kept.len()) as u64;
+        let skipped =
+            tokio::io::copy(&mut (&mut *reader).take(rest), &mut tokio::io::sink()).await?;
+        if skipped < rest {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::UnexpectedEof,
+                "消息不完整",
+            ));
+        }
+        return Ok(Frame::TooLong { len, head: kept });
+    }
+    let mut buf = vec![0u8; len];
+    reader.read_exact(&mut buf).await?;
+    Ok(Frame::Data(buf))
+}
+
+/// 按分帧方式读取一条消息；按行分帧时结果含结尾换行。超长消息保留开头至多 `head` 个字节
+pub async fn read_message<R: AsyncBufRead + Unpin>(
+    reader: &mut R,
+    framing: Framing,
+    max: usize,
+    head: usize,
+) -> std::io::Result<Frame> {
+    if framing == Framing::LengthPrefixed {
+        return read_prefixed(reader, max, head).await;
+    }
+    let (line, kept) = read_line_keeping_head(reader, max, head).await?;
+    Ok(match line {
+        LineRead::Eof => Frame::Eof,
+        LineRead::Line(line) => Frame::Data(line.into_bytes()),
+        LineRead::TooLong(len) => Frame::TooLong { len, head: kept },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+    use tokio::io::BufReader;
+
+    fn block_on<F: std::future::Future>(future: F) -> F::Output {
+        tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap()
+            .block_on(future)
+    }
+
+    /// 逐条读到 EOF 或出错为止
+    fn read_all(bytes: &[u8], max: usize, head: usize) -> (Vec<Frame>, std::io::Result<()>) {
+        block_on(async {
+            let mut reader = BufReader::with_capacity(16, bytes);
+            let mut frames = Vec::new();
+            loop {
+                match read_message(&mut reader, Framing::LengthPrefixed, max, head).await {
+                    Ok(Frame::Eof) => return (frames, Ok(())),
+                    Ok(frame) => frames.push(frame),
+                    Err(e) => return (frames, Err(e)),
+                }
+            }
+        })
+    }
+
+    proptest! {
+        /// 任意字节：不 panic、不超出上限分配，截断的输入以 UnexpectedEof 结束
+        #[test]
+        fn prefixed_reader_is_bounded_on_any_bytes(
+            bytes in prop::collection::vec(any::<u8>(), 0..2048),
+            max in 0usize..256,
+            head in 0usize..64,
+        ) {
+            let (frames, end) = read_all(&bytes, max, head);
+            for frame in frames {
+                match frame {
+                    Frame::Data(data) => prop_assert!(data.len() <= max),
+                    Frame::TooLong { len, head: kept } => {
+                        prop_assert!(len > max);
+                        prop_assert!(kept.len() <= head);
+                    }
+                    Frame::Eof => unreachable!(),
+                }
+            }
+            if let Err(e) = end {
+                prop_assert_eq!(e.kind(), std::io::ErrorKind::UnexpectedEof);
+            }
+        }
+
+        /// 合法分帧的任意内容原样读回，超长的整条跳过且不影响后续消息
+        #[test]
+        fn prefixed_frames_round_trip(
+            payloads in prop::collection::vec(prop::collection::vec(any::<u8>(), 0..300), 0..8),
+            max in 0usize..256,
+            head in 0usize..64,
+        ) {
+            let mut bytes = Vec::new();
+            for payload in &payloads {
+                bytes.extend_from_slice(&(payload.len() as u32).to_be_bytes());
+                bytes.extend_from_slice(payload);
+            }
+            let (frames, end) = read_all(&bytes, max, head);
+            prop_assert!(end.is_ok());
+            prop_assert_eq!(frames.len(), payloads.len());
+            for (frame, payload) in frames.into_iter().zip(&payloads) {
+                match frame {
+                    Frame::Data(data) => prop_assert_eq!(&data, payload),
+                    Frame::TooLong { len, head: kept } => {
+                        prop_assert!(payload.len() > max);
+                        prop_assert_eq!(len, payload.len());
+                        prop_assert_eq!(&kept[..], &payload[..head.min(len)]);
+                    }
+                    Frame::Eof => unreachable!(),
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,424 @@
+use crate::panics::guarded;
+use crate::store::save_json;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use tauri::http::{header, Request, Response, StatusCode};
+use tauri::{AppHandle, Manager, Runtime, UriSchemeContext};
+
+/// 前端 3D 预览通过该协议加载规范化后的几何
+pub const PROTOCOL: &str = "geometry";
+const CACHE_DIR: &str = "geometry";
+const DEFAULT_MAX_TRIANGLES: usize = 300_000;
+/// 超过该大小的源文件不做预览
+const MAX_SOURCE_BYTES: u64 = 1024 * 1024 * 1024;
+/// 顶点聚类的初始网格分辨率，逐次减半直到三角形数满足上限
+const INITIAL_GRID: u32 = 2048;
+const MIN_GRID: u32 = 8;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct GeometryPreview {
+    pub id: String,
+    pub url: String,
+    pub triangles: usize,
+    pub original_triangles: usize,
+    pub decimated: bool,
+    /// 预览几何统一换算为米
+    pub bbox_min: [f32; 3],
+    pub bbox_max: [f32; 3],
+    pub source_unit: String,
+}
+
+type Triangle = [[f32; 3]; 3];
+
+fn unit_scale(unit: &str) -> Result<f32, String> {
+    Ok(match unit {
+        "m" => 1.0,
+        "cm" => 0.01,
+        "mm" => 0.001,
+        "um" | "µm" => 1e-6,
+        "in" => 0.0254,
+        "ft" => 0.3048,
+        other => return Err(format!("不支持的长度单位: {}", other)),
+    })
+}
+
+fn parse_f32(s: Option<&str>) -> Result<f32, String> {
+    let v: f32 = s
+        .ok_or("坐标不完整")?
+        .parse()
+        .map_err(|_| "坐标不是数字".to_string())?;
+    if v.is_finite() {
+        Ok(v)
+    } else {
+        Err("坐标包含 NaN/Inf".to_string())
+    }
+}
+
+fn read_vec3<'a>(mut it: impl Iterator<Item = &'a str>) -> Result<[f32; 3], String> {
+    Ok([
+        parse_f32(it.next())?,
+        parse_f32(it.next())?,
+        parse_f32(it.next())?,
+    ])
+}
+
+/// 二进制 STL：80 字节头 + u32 三角形数 + 每个三角形 50 字节
+fn parse_stl_binary(data: &[u8]) -> Option<Result<Vec<Triangle>, String>> {
+    let count = u32::from_le_bytes(data.get(80..84)?.try_into().ok()?) as usize;
+    if data.len() != 84 + count * 50 {
+        return None;
+    }
+    let f = |b: &[u8], i: usize| f32::from_le_bytes([b[i], b[i + 1], b[i + 2], b[i + 3]]);
+    let mut out = Vec::with_capacity(count);
+    for rec in data[84..].chunks_exact(50) {
+        let mut tri = [[0.0; 3]; 3];
+        for (v, vertex) in tri.iter_mut().enumerate() {
+            for (a, coord) in vertex.iter_mut().enumerate() {
+                *coord = f(rec, 12 + v * 12 + a * 4);
+            }
+        }
+        if tri.iter().flatten().any(|c| !c.is_finite()) {
+            return Some(Err("坐标包含 NaN/Inf".to_string()));
+        }
+        out.push(tri);
+    }
+    Some(Ok(out))
+}
+
+fn parse_stl_ascii(text: &str) -> Result<Vec<Triangle>, String> {
+    let mut vertices = Vec::new();
+    for line in text.lines() {
+        let mut it = line.split_whitespace();
+        if it.next() == Some("vertex") {
+            vertices.push(read_vec3(it)?);
+        }
+    }
+    if vertices.len() % 3 != 0 {
+        return Err("STL 顶点数不是 3 的倍数".to_string());
+    }
+    Ok(vertices
+        .chunks_exact(3)
+        .map(|c| [c[0], c[1], c[2]])
+        .collect())
+}
+
+/// OBJ 只取 v / f，多边形面按扇形拆成三角形；支持负索引
+fn parse_obj(text: &str) -> Result<Vec<Triangle>, String> {
+    let mut vertices: Vec<[f32; 3]> = Vec::new();
+    let mut out = Vec::new();
+    for line in text.lines() {
+        let mut it = line.split_whitespace();
+        match it.next() {
+            Some("v") => vertices.push(read_vec3(it)?),
+            Some("f") => {
+                let idx = it
+                    .map(|tok| {
+                        let raw: i64 = tok
+                            .split('/')
+                            .next()
+                            .unwrap_or_default()
+                            .parse()
+                            .map_err(|_| format!("无效的面索引: {}", tok))?;
+                        let i = if raw < 0 {
+                            vertices.len() as i64 + raw
+                        } else {
+                            raw - 1
+                        };
+                        usize::try_from(i)
+                            .ok()
+                            .filter(|i| *i < vertices.len())
+                            .ok_or_else(|| format!("面索引越界: {}", tok))
+                    })
+                    .collect::<Result<Vec<usize>, String>>()?;
+                for k in 1..idx.len().saturating_sub(1) {
+                    out.push([vertices[idx[0]], vertices[idx[k]], vertices[idx[k + 1]]]);
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(out)
+}
+
+fn load_triangles(path: &Path) -> Result<Vec<Triangle>, String> {
+    let ext = path
+        .extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    let data = std::fs::read(path).map_err(|e| format!("读取几何文件失败: {}", e))?;
+    let tris = match ext.as_str() {
+        "stl" => match parse_stl_binary(&data) {
+            Some(r) => r?,
+            None => parse_stl_ascii(&String::from_utf8_lossy(&data))?,
+        },
+        "obj" => parse_obj(&String::from_utf8_lossy(&data))?,
+        other => return Err(format!("不支持的几何格式: {}", other)),
+    };
+    if tris.is_empty() {
+        return Err("几何文件中没有三角形".to_string());
+    }
+    Ok(tris)
+}
+
+fn bbox(tris: &[Triangle]) -> ([f32; 3], [f32; 3]) {
+    let mut min = [f32::MAX; 3];
+    let mut max = [f32::MIN; 3];
+    for v in tris.iter().flatten() {
+        for a in 0..3 {
+            min[a] = min[a].min(v[a]);
+            max[a] = max[a].max(v[a]);
+        }
+    }
+    (min, max)
+}
+
+/// 顶点聚类简化：同一网格单元内的顶点合并为均值，丢弃退化和重复的三角形
+fn cluster(tris: &[Triangle], grid: u32) -> Vec<Triangle> {
+    let (min, max) = bbox(tris);
+    let extent = (0..3).map(|a| max[a] - min[a]).fold(0.0f32, f32::max);
+    let cell = (extent / grid as f32).max(f32::MIN_POSITIVE);
+    let key = |v: &[f32; 3]| {
+        [
+            ((v[0] - min[0]) / cell) as i32,
+            ((v[1] - min[1]) / cell) as i32,
+            ((v[2] - min[2]) / cell) as i32,
+        ]
+    };
+    let mut cells: HashMap<[i32; 3], usize> = HashMap::new();
+    let mut sums: Vec<([f64; 3], u32)> = Vec::new();
+    let mut indexed: Vec<[usize; 3]> = Vec::new();
+    let mut seen = HashSet::new();
+    for tri in tris {
+        let ids = tri.map(|v| {
+            let id = *cells.entry(key(&v)).or_insert_with(|| {
+                sums.push(([0.0; 3], 0));
+                sums.len() - 1
+            });
+            let s = &mut sums[id];
+            for (sum, c) in s.0.iter_mut().zip(v) {
+                *sum += c as f64;
+            }
+            s.1 += 1;
+            id
+        });
+        if ids[0] == ids[1] || ids[1] == ids[2] || ids[0] == ids[2] {
+            continue;
+        }
+        let mut sorted = ids;
+        sorted.sort_unstable();
+        if seen.insert(sorted) {
+            indexed.push(ids);
+        }
+    }
+    let centers: Vec<[f32; 3]> = sums
+        .iter()
+        .map(|(s, n)| s.map(|c| (c / *n as f64) as f32))
+        .collect();
+    indexed
+        .into_iter()
+        .map(|ids| ids.map(|i| centers[i]))
+        .collect()
+}
+
+fn decimate(tris: Vec<Triangle>, max_triangles: usize) -> Vec<Triangle> {
+    if tris.len() <= max_triangles {
+        return tris;
+    }
+    let mut grid = INITIAL_GRID;
+    loop {
+        let out = cluster(&tris, grid);
+        if out.len() <= max_triangles || grid <= MIN_GRID {
+            return out;
+        }
+        grid /= 2;
+    }
+}
+
+fn normal(t: &Triangle) -> [f32; 3] {
+    let u = [t[1][0] - t[0][0], t[1][1] - t[0][1], t[1][2] - t[0][2]];
+    let v = [t[2][0] - t[0][0], t[2][1] - t[0][1], t[2][2] - t[0][2]];
+    let n = [
+        u[1] * v[2] - u[2] * v[1],
+        u[2] * v[0] - u[0] * v[2],
+        u[0] * v[1] - u[1] * v[0],
+    ];
+    let len = (n[0] * n[0] + n[1] * n[1] + n[2] * n[2]).sqrt();
+    if len > 0.0 {
+        n.map(|c| c / len)
+    } else {
+        [0.0; 3]
+    }
+}
+
+fn write_stl_binary(tris: &[Triangle]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(84 + tris.len() * 50);
+    let mut header = [0u8; 80];
+    header[..14].copy_from_slice(b"mph-agent mesh");
+    out.extend_from_slice(&header);
+    out.extend_from_slice(&(tris.len() as u32).to_le_bytes());
+    for t in tris {
+        for c in normal(t).iter().chain(t.iter().flatten()) {
+            out.extend_from_slice(&c.to_le_bytes());
+        }
+        out.extend_from_slice(&[0, 0]);
+    }
+    out
+}
+
+/// 缓存键：源文件路径、大小、修改时间与处理参数
+fn cache_id(path: &Path, unit: &str, max_triangles: usize) -> Result<String, String> {
+    let meta = std::fs::metadata(path).map_err(|e| e.to_string())?;
+    let modified = meta
+        .modified()
+        .ok()
+        .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+        .map(|d| d.as_nanos())
+        .unwrap_or(0);
+    let mut h = Sha256::new();
+    h.update(path.to_string_lossy().as_bytes());
+    h.update(format!("|{}|{}|{}|{}", meta.len(), modified, unit, max_triangles).as_bytes());
+    let hex: String = h.finalize().iter().map(|b| format!("{:02x}", b)).collect();
+    Ok(hex[..32].to_string())
+}
+
+fn cache_dir(app: &AppHandle<impl Runtime>) -> Result<PathBuf, String> {
+    app.path()
+        .app_cache_dir()
+        .map(|d| d.join(CACHE_DIR))
+        .map_err(|e| e.to_string())
+}
+
+fn protocol_url(id: &str) -> String {
+    // Windows/Android 上 webview 只接受 http://<scheme>.localhost 形式
+    #[cfg(any(target_os = "windows", target_os = "android"))]
+    return format!("http://{}.localhost/{}.stl", PROTOCOL, id);
+    #[cfg(not(any(target_os = "windows", target_os = "android")))]
+    return format!("{}://localhost/{}.stl", PROTOCOL, id);
+}
+
+/// 缓存元数据与几何文件同名，命中缓存时无需重新解析
+fn prepare(
+    source: &Path,
+    cache: &Path,
+    unit: &str,
+    max_triangles: usize,
+) -> Result<GeometryPreview, String> {
+    let id = cache_id(source, unit, max_triangles)?;
+    let stl = cache.join(format!("{}.stl", id));
+    let meta = cache.join(format!("{}.json", id));
+    if stl.is_file() {
+        if let Some(p) = std::fs::read_to_string(&meta)
+            .ok()
+            .and_then(|t| serde_json::from_str::<GeometryPreview>(&t).ok())
+        {
+            return Ok(p);
+        }
+    }
+
+    let scale = unit_scale(unit)?;
+    let mut tris = load_triangles(source)?;
+    let original = tris.len();
+    if scale != 1.0 {
+        for v in tris.iter_mut().flatten() {
+            *v = v.map(|c| c * scale);
+        }
+    }
+    let tris = decimate(tris, max_triangles);
+    let (bbox_min, bbox_max) = bbox(&tris);
+    let preview = GeometryPreview {
+        url: protocol_url(&id),
+        id,
+        triangles: tris.len(),
+        original_triangles: original,
+        decimated: tris.len() < original,
+        bbox_min,
+        bbox_max,
+        source_unit: unit.to_string(),
+    };
+    std::fs::create_dir_all(cache).map_err(|e| format!("创建缓存目录失败: {}", e))?;
+    std::fs::write(&stl, write_stl_binary(&tris)).map_err(|e| format!("写入缓存失败: {}", e))?;
+    save_json(&meta, &preview)?;
+    Ok(preview)
+}
+
+/// `geometry://` 协议：只提供缓存目录中已处理过的几何，按 id 访问
+pub fn handle_protocol<R: Runtime>(
+    ctx: UriSchemeContext<'_, R>,
+    request: Request<Vec<u8>>,
+) -> Response<Vec<u8>> {
+    let not_found = || {
+        Response::builder()
+            .status(StatusCode::NOT_FOUND)
+            .body(Vec::new())
+            .unwrap_or_default()
+    };
+    let name = request.uri().path().trim_start_matches('/');
+    let Some(id) = name.strip_suffix(".stl") else {
+        return not_found();
+    };
+    if id.len() != 32 || !id.chars().all(|c| c.is_ascii_hexdigit()) {
+        return not_found();
+    }
+    let Ok(dir) = cache_dir(ctx.app_handle()) else {
+        return not_found();
+    };
+    match std::fs::read(dir.join(name)) {
+        Ok(body) => Response::builder()
+            .header(header::CONTENT_TYPE, "model/stl")
+            // id 由内容参数决定，可长期缓存
+            .header(header::CACHE_CONTROL, "public, max-age=31536000, immutable")
+            .header(header::ACCESS_CONTROL_ALLOW_ORIGIN, "*")
+            .body(body)
+            .unwrap_or_default(),
+        Err(_) => not_found(),
+    }
+}
+
+/// 按默认参数处理几何并写入预览缓存，供产物后处理预先生成
+pub(crate) fn prepare_cached(
+    app: &AppHandle,
+    source: &Path,
+    unit: &str,
+    max_triangles: Option<usize>,
+) -> Result<GeometryPreview, String> {
+    let size = std::fs::metadata(source)
+        .map_err(|_| "文件不存在".to_string())?
+        .len();
+    if size > MAX_SOURCE_BYTES {
+        return Err("几何文件过大，无法预览".to_string());
+    }
+    let max_triangles = max_triangles.unwrap_or(DEFAULT_MAX_TRIANGLES).max(1);
+    prepare(source, &cache_dir(app)?, unit, max_triangles)
+}
+
+/// 校验并规范化导出的 STL/OBJ（换算为米、超过上限时简化），返回预览协议地址
+#[tauri::command]
+pub async fn geometry_prepare(
+    app: AppHandle,
+    path: String,
+    unit: Option<String>,
+    max_triangles: Option<usize>,
+) -> Result<GeometryPreview, String> {
+    guarded("geometry_prepare", async move {
+        let source = PathBuf::from(path.trim());
+        let size = std::fs::metadata(&source)
+            .map_err(|_| "文件不存在".to_string())?
+            .len();
+        if size > MAX_SOURCE_BYTES {
+            return Err("几何文件过大，无法预览".to_string());
+        }
+        let unit = unit
+            .map(|u| u.trim().to_lowercase())
+            .filter(|u| !u.is_empty())
+            .unwrap_or_else(|| "m".to_string());
+        unit_scale(&unit)?;
+        let max_triangles = max_triangles.unwrap_or(DEFAULT_MAX_TRIANGLES).max(1);
+        let cache = cache_dir(&app)?;
+        tauri::async_runtime::spawn_blocking(move || prepare(&source, &cache, &unit, max_triangles))
+            .await
+            .map_err(|e| e.to_string())?
+    })
+    .await
+}
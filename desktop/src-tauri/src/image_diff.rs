@@ -0,0 +1,643 @@
+use crate::artifacts::sha256_file;
+use crate::panics::guarded;
+use crate::sandbox::check_path;
+use serde::Serialize;
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Manager};
+
+/// 差异图缓存目录（app cache 下）
+const CACHE_DIR: &str = "image_diffs";
+/// 单张图片的像素上限，超出时拒绝比较
+const MAX_PIXELS: u64 = 40_000_000;
+/// SSIM 窗口半径，窗口为 (2r+1)×(2r+1)
+const RADIUS: usize = 3;
+/// SSIM 常数，按 8 位灰度取 (0.01·255)² 与 (0.03·255)²
+const C1: f64 = 6.5025;
+const C2: f64 = 58.5225;
+/// 局部 SSIM 低于该值的像素算作“有变化”
+const CHANGED_SSIM: f32 = 0.95;
+
+/// 灰度图（0–255），透明部分按白底合成
+struct Luma {
+    width: usize,
+    height: usize,
+    data: Vec<f32>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ImageDiff {
+    /// 平均 SSIM，1 表示结构完全一致
+    pub ssim: f64,
+    /// 两个文件内容完全相同
+    pub identical: bool,
+    /// 局部 SSIM 低于阈值的像素占比
+    pub changed_fraction: f64,
+    /// 有变化区域的包围框 `[x, y, 宽, 高]`；没有变化时为 null
+    pub changed_region: Option<[usize; 4]>,
+    pub width: usize,
+    pub height: usize,
+    /// 两张图尺寸不同，已把 b 缩放到 a 的尺寸再比较
+    pub resized: bool,
+    /// 差异图：a 的淡化灰度底图上以红色标出变化处
+    pub diff_path: String,
+    /// 是否由 GPU 计算
+    pub accelerated: bool,
+}
+
+fn load(path: &Path) -> Result<Luma, String> {
+    let file =
+        std::fs::File::open(path).map_err(|e| format!("打开 {} 失败: {}", path.display(), e))?;
+    let mut decoder = png::Decoder::new(BufReader::new(file));
+    decoder.set_transformations(png::Transformations::normalize_to_color8());
+    let mut reader = decoder
+        .read_info()
+        .map_err(|e| format!("{} 不是可读取的 PNG: {}", path.display(), e))?;
+    let (w, h) = (reader.info().width, reader.info().height);
+    if w as u64 * h as u64 > MAX_PIXELS {
+        return Err(format!("{} 过大（{}×{}）", path.display(), w, h));
+    }
+    let mut buf = vec![0; reader.output_buffer_size()];
+    let frame = reader
+        .next_frame(&mut buf)
+        .map_err(|e| format!("解码 {} 失败: {}", path.display(), e))?;
+    let channels = frame.color_type.samples();
+    let data = buf[..frame.buffer_size()]
+        .chunks_exact(channels)
+        .map(|px| {
+            let (l, a) = match *px {
+                [g] => (g as f32, 255.0),
+                [g, a] => (g as f32, a as f32),
+                [r, g, b] => (
+                    0.299 * r as f32 + 0.587 * g as f32 + 0.114 * b as f32,
+                    255.0,
+                ),
+                [r, g, b, a, ..] => (
+                    0.299 * r as f32 + 0.587 * g as f32 + 0.114 * b as f32,
+                    a as f32,
+                ),
+                [] => (255.0, 255.0),
+            };
+            let alpha = a / 255.0;
+            l * alpha + 255.0 * (1.0 - alpha)
+        })
+        .collect();
+    Ok(Luma {
+        width: frame.width as usize,
+        height: frame.height as usize,
+        data,
+    })
+}
+
+/// 双线性缩放到指定尺寸
+fn resize(src: &Luma, width: usize, height: usize) -> Luma {
+    let sx = src.width as f32 / width as f32;
+    let sy = src.height as f32 / height as f32;
+    let at = |x: usize, y: usize| src.data[y * src.width + x];
+    let mut data = Vec::with_capacity(width * height);
+    for y in 0..height {
+        let fy = ((y as f32 + 0.5) * sy - 0.5).clamp(0.0, (src.height - 1) as f32);
+        let (y0, ty) = (fy as usize, fy.fract());
+        let y1 = (y0 + 1).min(src.height - 1);
+        for x in 0..width {
+            let fx = ((x as f32 + 0.5) * sx - 0.5).clamp(0.0, (src.width - 1) as f32);
+            let (x0, tx) = (fx as usize, fx.fract());
+            let x1 = (x0 + 1).min(src.width - 1);
+            let top = at(x0, y0) * (1.0 - tx) + at(x1, y0) * tx;
+            let bottom = at(x0, y1) * (1.0 - tx) + at(x1, y1) * tx;
+            data.push(top * (1.0 - ty) + bottom * ty);
+        }
+    }
+    Luma {
+        width,
+        height,
+        data,
+    }
+}
+
+/// 逐像素的局部 SSIM：用积分图求窗口内的均值、方差与协方差，窗口在边缘处截断
+fn ssim_map_cpu(a: &Luma, b: &Luma) -> Vec<f32> {
+    let (w, h) = (a.width, a.height);
+    let stride = w + 1;
+    let mut sums = vec![[0f64; 5]; stride * (h + 1)];
+    for y in 0..h {
+        let mut row = [0f64; 5];
+        for x in 0..w {
+            let (va, vb) = (a.data[y * w + x] as f64, b.data[y * w + x] as f64);
+            for (acc, v) in row.iter_mut().zip([va, vb, va * va, vb * vb, va * vb]) {
+                *acc += v;
+            }
+            let above = sums[y * stride + x + 1];
+            let cell = &mut sums[(y + 1) * stride + x + 1];
+            for ((c, up), r) in cell.iter_mut().zip(above).zip(row) {
+                *c = up + r;
+            }
+        }
+    }
+    let mut out = Vec::with_capacity(w * h);
+    for y in 0..h {
+        let (y0, y1) = (y.saturating_sub(RADIUS), (y + RADIUS + 1).min(h));
+        for x in 0..w {
+            let (x0, x1) = (x.saturating_sub(RADIUS), (x + RADIUS + 1).min(w));
+            let n = ((x1 - x0) * (y1 - y0)) as f64;
+            let mut s = [0f64; 5];
+            for (k, v) in s.iter_mut().enumerate() {
+                *v = (sums[y1 * stride + x1][k]
+                    - sums[y0 * stride + x1][k]
+                    - sums[y1 * stride + x0][k]
+                    + sums[y0 * stride + x0][k])
+                    / n;
+            }
+            let [mx, my, exx, eyy, exy] = s;
+            let (vx, vy, cov) = (exx - mx * mx, eyy - my * my, exy - mx * my);
+            let ssim = ((2.0 * mx * my + C1) * (2.0 * cov + C2))
+                / ((mx * mx + my * my + C1) * (vx + vy + C2));
+            out.push(ssim as f32);
+        }
+    }
+    out
+}
+
+/// 差异图：a 的灰度淡化为底，按局部 SSIM 的下降程度叠加红色
+fn write_diff(path: &Path, a: &Luma, map: &[f32]) -> Result<(), String> {
+    let mut rgba = Vec::with_capacity(a.data.len() * 4);
+    for (l, s) in a.data.iter().zip(map) {
+        let base = 0.35 * l + 0.65 * 255.0;
+        let t = ((1.0 - s) * 4.0).clamp(0.0, 1.0);
+        let mix = |c: f32| (base * (1.0 - t) + c * t).round() as u8;
+        rgba.extend_from_slice(&[mix(230.0), mix(30.0), mix(30.0), 255]);
+    }
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| format!("创建 {} 失败: {}", dir.display(), e))?;
+    }
+    let file =
+        std::fs::File::create(path).map_err(|e| format!("创建 {} 失败: {}", path.display(), e))?;
+    let mut encoder = png::Encoder::new(
+        std::io::BufWriter::new(file),
+        a.width as u32,
+        a.height as u32,
+    );
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder
+        .write_header()
+        .and_then(|mut w| w.write_image_data(&rgba))
+        .map_err(|e| format!("写入差异图失败: {}", e))
+}
+
+fn summarize(
+    a: &Luma,
+    map: &[f32],
+    identical: bool,
+    resized: bool,
+    diff_path: &Path,
+    accelerated: bool,
+) -> Result<ImageDiff, String> {
+    write_diff(diff_path, a, map)?;
+    let mut changed = 0usize;
+    let mut bounds: Option<[usize; 4]> = None;
+    for (i, s) in map.iter().enumerate() {
+        if *s >= CHANGED_SSIM {
+            continue;
+        }
+        changed += 1;
+        let (x, y) = (i % a.width, i / a.width);
+        let b = bounds.get_or_insert([x, y, x, y]);
+        b[0] = b[0].min(x);
+        b[1] = b[1].min(y);
+        b[2] = b[2].max(x);
+        b[3] = b[3].max(y);
+    }
+    let total = map.len().max(1);
+    Ok(ImageDiff {
+        ssim: map.iter().map(|s| *s as f64).sum::<f64>() / total as f64,
+        identical,
+        changed_fraction: changed as f64 / total as f64,
+        changed_region: bounds.map(|[x0, y0, x1, y1]| [x0, y0, x1 - x0 + 1, y1 - y0 + 1]),
+        width: a.width,
+        height: a.height,
+        resized,
+        diff_path: diff_path.to_string_lossy().into_owned(),
+        accelerated,
+    })
+}
+
+#[cfg(feature = "gpu")]
+mod gpu {
+    use wgpu::util::DeviceExt;
+
+    /// 与 CPU 版相同的窗口与常数，每个线程直接累加自己的窗口
+    const SHADER: &str = r#"
+struct Params { width: u32, height: u32, radius: u32, pad: u32 };
+@group(0) @binding(0) var<uniform> params: Params;
+@group(0) @binding(1) var<storage, read> a: array<f32>;
+@group(0) @binding(2) var<storage, read> b: array<f32>;
+@group(0) @binding(3) var<storage, read_write> out: array<f32>;
+
+const C1: f32 = 6.5025;
+const C2: f32 = 58.5225;
+
+@compute @workgroup_size(16, 16)
+fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+    if (id.x >= params.width || id.y >= params.height) {
+        return;
+    }
+    let r = i32(params.radius);
+    let x0 = max(i32(id.x) - r, 0);
+    let x1 = min(i32(id.x) + r, i32(params.width) - 1);
+    let y0 = max(i32(id.y) - r, 0);
+    let y1 = min(i32(id.y) + r, i32(params.height) - 1);
+    var sx = 0.0;
+    var sy = 0.0;
+    var sxx = 0.0;
+    var syy = 0.0;
+    var sxy = 0.0;
+    for (var y = y0; y <= y1; y = y + 1) {
+        for (var x = x0; x <= x1; x = x + 1) {
+            let i = u32(y) * params.width + u32(x);
+            let va = a[i];
+            let vb = b[i];
+            sx = sx + va;
+            sy = sy + vb;
+            sxx = sxx + va * va;
+            syy = syy + vb * vb;
+            sxy = sxy + va * vb;
+        }
+    }
+    let n = f32((x1 - x0 + 1) * (y1 - y0 + 1));
+    let mx = sx / n;
+    let my = sy / n;
+    let vx = sxx / n - mx * mx;
+    let vy = syy / n - my * my;
+    let cov = sxy / n - mx * my;
+    out[id.y * params.width + id.x] = ((2.0 * mx * my + C1) * (2.0 * cov + C2))
+        / ((mx * mx + my * my + C1) * (vx + vy + C2));
+}
+"#;
+
+    fn bytes(data: &[f32]) -> Vec<u8> {
+        data.iter().flat_map(|v| v.to_le_bytes()).collect()
+    }
+
+    /// 在 GPU 上计算局部 SSIM；没有可用适配器或图片超出缓冲区上限时返回错误，由调用方退回 CPU
+    pub async fn ssim_map(
+        a: &[f32],
+        b: &[f32],
+        width: usize,
+        height: usize,
+    ) -> Result<Vec<f32>, String> {
+        let instance = wgpu::Instance::new(wgpu::InstanceDescriptor::default());
+        let adapter = instance
+            .request_adapter(&wgpu::RequestAdapterOptions {
+                power_preference: wgpu::PowerPreference::HighPerformance,
+                ..Default::default()
+            })
+            .await
+            .ok_or("没有可用的 GPU 适配器")?;
+        let size = (a.len() * 4) as u64;
+        if size > adapter.limits().max_storage_buffer_binding_size as u64 {
+            return Err("图片超出 GPU 存储缓冲区上限".to_string());
+        }
+        let (device, queue) = adapter
+            .request_device(&wgpu::DeviceDescriptor::default(), None)
+            .await
+            .map_err(|e| e.to_string())?;
+
+        let params: Vec<u8> = [width as u32, height as u32, super::RADIUS as u32, 0]
+            .iter()
+            .flat_map(|v| v.to_le_bytes())
+            .collect();
+        let init = |label, contents: &[u8], usage| {
+            device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some(label),
+                contents,
+                usage,
+            })
+        };
+        let params = init("ssim-params", &params, wgpu::BufferUsages::UNIFORM);
+        let buf_a = init("ssim-a", &bytes(a), wgpu::BufferUsages::STORAGE);
+        let buf_b = init("ssim-b", &bytes(b), wgpu::BufferUsages::STORAGE);
+        let out = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("ssim-out"),
+            size,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        });
+        let staging = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("ssim-staging"),
+            size,
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("ssim"),
+            source: wgpu::ShaderSource::Wgsl(SHADER.into()),
+        });
+        let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("ssim"),
+            layout: None,
+            module: &module,
+            entry_point: "main",
+            compilation_options: Default::default(),
+            cache: None,
+        });
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("ssim"),
+            layout: &pipeline.get_bind_group_layout(0),
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: params.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: buf_a.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: buf_b.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 3,
+                    resource: out.as_entire_binding(),
+                },
+            ],
+        });
+
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+        {
+            let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor::default());
+            pass.set_pipeline(&pipeline);
+            pass.set_bind_group(0, &bind_group, &[]);
+            pass.dispatch_workgroups((width as u32).div_ceil(16), (height as u32).div_ceil(16), 1);
+        }
+        encoder.copy_buffer_to_buffer(&out, 0, &staging, 0, size);
+        queue.submit(Some(encoder.finish()));
+
+        let slice = staging.slice(..);
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        slice.map_async(wgpu::MapMode::Read, move |r| {
+            let _ = tx.send(r);
+        });
+        device.poll(wgpu::Maintain::Wait);
+        rx.await
+            .map_err(|e| e.to_string())?
+            .map_err(|e| e.to_string())?;
+        let map = slice
+            .get_mapped_range()
+            .chunks_exact(4)
+            .map(|c| f32::from_le_bytes([c[0], c[1], c[2], c[3]]))
+            .collect();
+        staging.unmap();
+        Ok(map)
+    }
+}
+
+fn diff_path(app: &AppHandle, a: &Path, b: &Path, out: Option<&str>) -> Result<PathBuf, String> {
+    if let Some(out) = out.map(str::trim).filter(|s| !s.is_empty()) {
+        let path = PathBuf::from(out);
+        check_path(&path)?;
+        return Ok(path);
+    }
+    let (ha, hb) = (sha256_file(a)?, sha256_file(b)?);
+    let dir = app
+        .path()
+        .app_cache_dir()
+        .map_err(|e| e.to_string())?
+        .join(CACHE_DIR);
+    Ok(dir.join(format!("{}-{}.png", &ha[..16], &hb[..16])))
+}
+
+/// 比较两张导出的结果图（PNG）：给出平均 SSIM、变化像素占比与区域，并生成标出差异的 PNG。
+/// 尺寸不同时把 b 缩放到 a 的尺寸；以 `gpu` feature 构建时优先用 GPU 计算，不可用时退回 CPU
+#[tauri::command]
+pub async fn compare_images(
+    app: AppHandle,
+    a: String,
+    b: String,
+    out: Option<String>,
+    gpu: Option<bool>,
+) -> Result<ImageDiff, String> {
+    guarded("compare_images", async move {
+        let (a, b) = (PathBuf::from(a.trim()), PathBuf::from(b.trim()));
+        let diff_path = diff_path(&app, &a, &b, out.as_deref())?;
+        let (la, lb, identical) = tauri::async_runtime::spawn_blocking(move || {
+            let identical = std::fs::read(&a).ok() == std::fs::read(&b).ok();
+            let (la, lb) = (load(&a)?, load(&b)?);
+            Ok::<_, String>((la, lb, identical))
+        })
+        .await
+        .map_err(|e| e.to_string())??;
+        let resized = (la.width, la.height) != (lb.width, lb.height);
+        let lb = if resized {
+            resize(&lb, la.width, la.height)
+        } else {
+            lb
+        };
+
+        #[cfg(feature = "gpu")]
+        let gpu_map = if gpu.unwrap_or(true) {
+            match gpu::ssim_map(&la.data, &lb.data, la.width, la.height).await {
+                Ok(map) => Some(map),
+                Err(e) => {
+                    eprintln!("Warning: GPU 计算图像差异失败，改用 CPU: {}", e);
+                    None
+                }
+            }
+        } else {
+            None
+        };
+        #[cfg(not(feature = "gpu"))]
+        let gpu_map: Option<Vec<f32>> = {
+            let _ = gpu;
+            None
+        };
+
+        tauri::async_runtime::spawn_blocking(move || {
+            let accelerated = gpu_map.is_some();
+            let map = gpu_map.unwrap_or_else(|| ssim_map_cpu(&la, &lb));
+            summarize(&la, &map, identical, resized, &diff_path, accelerated)
+        })
+        .await
+        .map_err(|e| e.to_string())?
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir() -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "mph-image-diff-{}-{}",
+            std::process::id(),
+            crate::store::now_ms()
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    /// 带渐变与棋盘纹理的测试图，各窗口内都有方差
+    fn pattern(width: usize, height: usize) -> Luma {
+        let data = (0..width * height)
+            .map(|i| {
+                let (x, y) = (i % width, i / width);
+                let checker = if (x / 4 + y / 4) % 2 == 0 { 60.0 } else { 0.0 };
+                (x * 150 / width) as f32 + checker + (y % 7) as f32 * 5.0
+            })
+            .collect();
+        Luma {
+            width,
+            height,
+            data,
+        }
+    }
+
+    /// 把 `[x, y, 宽, 高]` 范围内的像素涂成黑色
+    fn blot(src: &Luma, [x0, y0, w, h]: [usize; 4]) -> Luma {
+        let mut data = src.data.clone();
+        for y in y0..y0 + h {
+            for x in x0..x0 + w {
+                data[y * src.width + x] = 0.0;
+            }
+        }
+        Luma { data, ..*src }
+    }
+
+    fn mean(map: &[f32]) -> f64 {
+        map.iter().map(|s| *s as f64).sum::<f64>() / map.len() as f64
+    }
+
+    fn write_png(path: &Path, width: u32, height: u32, color: png::ColorType, data: &[u8]) {
+        let file = std::fs::File::create(path).unwrap();
+        let mut encoder = png::Encoder::new(std::io::BufWriter::new(file), width, height);
+        encoder.set_color(color);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder
+            .write_header()
+            .unwrap()
+            .write_image_data(data)
+            .unwrap();
+    }
+
+    #[test]
+    fn identical_images_score_one() {
+        let a = pattern(48, 32);
+        let map = ssim_map_cpu(&a, &a);
+        assert_eq!(map.len(), 48 * 32);
+        assert!(map.iter().all(|s| (s - 1.0).abs() < 1e-6));
+
+        let dir = temp_dir();
+        let diff = summarize(&a, &map, true, false, &dir.join("diff.png"), false).unwrap();
+        assert!((diff.ssim - 1.0).abs() < 1e-6);
+        assert_eq!(diff.changed_fraction, 0.0);
+        assert_eq!(diff.changed_region, None);
+        let written = load(&dir.join("diff.png")).unwrap();
+        assert_eq!((written.width, written.height), (48, 32));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn perturbation_lowers_score_around_the_change() {
+        let a = pattern(48, 32);
+        let small = ssim_map_cpu(&a, &blot(&a, [20, 10, 4, 4]));
+        let large = ssim_map_cpu(&a, &blot(&a, [10, 5, 20, 20]));
+        assert!(mean(&small) < 0.99);
+        assert!(mean(&large) < mean(&small));
+
+        let dir = temp_dir();
+        let diff = summarize(&a, &small, false, false, &dir.join("diff.png"), false).unwrap();
+        assert!(diff.changed_fraction > 0.0);
+        let [x, y, w, h] = diff.changed_region.unwrap();
+        // 变化区域包住涂黑的方块，且只向外扩展一个窗口半径
+        assert!(x <= 20 && y <= 10 && x + w >= 24 && y + h >= 14);
+        assert!(x + RADIUS >= 20 && y + RADIUS >= 10);
+        assert!(x + w <= 24 + RADIUS && y + h <= 14 + RADIUS);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn resize_keeps_same_size_and_flat_images() {
+        let a = pattern(20, 10);
+        let same = resize(&a, 20, 10);
+        assert_eq!(same.data, a.data);
+
+        let flat = Luma {
+            width: 3,
+            height: 2,
+            data: vec![128.0; 6],
+        };
+        for (w, h) in [(1, 1), (7, 5), (40, 3)] {
+            let scaled = resize(&flat, w, h);
+            assert_eq!((scaled.width, scaled.height), (w, h));
+            assert!(scaled.data.iter().all(|v| (v - 128.0).abs() < 1e-4));
+        }
+
+        // 放大后再缩回原尺寸，与原图结构一致
+        let back = resize(&resize(&a, 40, 20), 20, 10);
+        assert!(mean(&ssim_map_cpu(&a, &back)) > 0.9);
+    }
+
+    #[test]
+    fn transparent_pixels_composite_on_white() {
+        let dir = temp_dir();
+        let rgba = dir.join("rgba.png");
+        // 不透明黑、全透明黑、半透明黑、不透明纯绿
+        #[rustfmt::skip]
+        let pixels = [
+            0, 0, 0, 255,
+            0, 0, 0, 0,
+            0, 0, 0, 128,
+            0, 255, 0, 255,
+        ];
+        write_png(&rgba, 4, 1, png::ColorType::Rgba, &pixels);
+        let luma = load(&rgba).unwrap();
+        assert_eq!((luma.width, luma.height), (4, 1));
+        let expected = [0.0, 255.0, 255.0 * (1.0 - 128.0 / 255.0), 0.587 * 255.0];
+        for (got, want) in luma.data.iter().zip(expected) {
+            assert!((got - want).abs() < 1e-3, "{} != {}", got, want);
+        }
+
+        let gray_alpha = dir.join("gray-alpha.png");
+        write_png(
+            &gray_alpha,
+            2,
+            1,
+            png::ColorType::GrayscaleAlpha,
+            &[100, 255, 100, 0],
+        );
+        assert_eq!(load(&gray_alpha).unwrap().data, [100.0, 255.0]);
+
+        std::fs::write(dir.join("broken.png"), b"not a png").unwrap();
+        assert!(load(&dir.join("broken.png")).is_err());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[cfg(feature = "gpu")]
+    #[test]
+    fn gpu_and_cpu_maps_agree() {
+        let a = pattern(37, 21);
+        let b = blot(&a, [5, 5, 9, 6]);
+        let run = gpu::ssim_map(&a.data, &b.data, a.width, a.height);
+        let gpu_map = match tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap()
+            .block_on(run)
+        {
+            Ok(map) => map,
+            // 没有可用的适配器（如无显卡的 CI）时无从比较
+            Err(e) => {
+                eprintln!("跳过 GPU 比较: {}", e);
+                return;
+            }
+        };
+        let cpu_map = ssim_map_cpu(&a, &b);
+        assert_eq!(gpu_map.len(), cpu_map.len());
+        for (g, c) in gpu_map.iter().zip(&cpu_map) {
+            assert!((g - c).abs() < 1e-2, "gpu {} cpu {}", g, c);
+        }
+    }
+}
@@ -0,0 +1,157 @@
+use crate::artifacts::sha256_file;
+use crate::bridge_error::BridgeError;
+use crate::panics::guarded;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+/// 与 bridge 脚本/可执行文件同目录的哈希清单
+pub const MANIFEST_NAME: &str = "bridge-manifest.json";
+/// 打包流水线可在编译期注入 sidecar 的哈希，比旁置清单更难被一并篡改
+const EMBEDDED_SIDECAR_SHA256: Option<&str> = option_env!("MPH_AGENT_BRIDGE_SHA256");
+
+/// 用户显式放行被篡改的 bridge，仅对本次运行有效
+static OVERRIDE: AtomicBool = AtomicBool::new(false);
+static LAST_REPORT: Mutex<Option<IntegrityReport>> = Mutex::new(None);
+
+#[derive(Default, Deserialize)]
+struct Manifest {
+    /// 相对清单所在目录的路径 → sha256
+    #[serde(default)]
+    files: BTreeMap<String, String>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct FileCheck {
+    pub path: String,
+    pub expected: Option<String>,
+    pub actual: Option<String>,
+    pub ok: bool,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct IntegrityReport {
+    /// verified / unverified（开发模式下没有可比对的清单）/ missing（安装包缺少清单）/ mismatch
+    pub status: String,
+    pub files: Vec<FileCheck>,
+    pub overridden: bool,
+}
+
+fn load_manifest(dir: &Path) -> Option<Manifest> {
+    let text = std::fs::read_to_string(dir.join(MANIFEST_NAME)).ok()?;
+    serde_json::from_str(&text).ok()
+}
+
+fn check_file(path: &Path, expected: Option<String>) -> FileCheck {
+    let actual = sha256_file(path).ok();
+    let ok = match (&expected, &actual) {
+        (Some(e), Some(a)) => e.eq_ignore_ascii_case(a),
+        _ => false,
+    };
+    FileCheck {
+        path: path.to_string_lossy().into_owned(),
+        expected,
+        actual,
+        ok,
+    }
+}
+
+/// 启动 bridge 前校验 `files`（位于 `dir` 下）与清单列出的全部文件；清单存在时，未列入清单的
+/// `files` 也视为不符。打包模式（`sidecar`）下缺少清单且没有编译期哈希即拒绝启动；
+/// 开发模式下没有任何参考哈希时视为未校验并放行。哈希不符时拒绝启动，除非用户已显式放行。
+pub fn verify_before_launch(dir: &Path, files: &[PathBuf], sidecar: bool) -> Result<(), String> {
+    let manifest = load_manifest(dir);
+    let embedded = if sidecar {
+        EMBEDDED_SIDECAR_SHA256
+    } else {
+        None
+    };
+    let rel_of = |f: &Path| {
+        f.strip_prefix(dir)
+            .unwrap_or(f)
+            .to_string_lossy()
+            .replace('\\', "/")
+    };
+    let mut targets: BTreeMap<String, (PathBuf, Option<String>)> = BTreeMap::new();
+    for f in files {
+        let expected = embedded
+            .map(String::from)
+            .or_else(|| manifest.as_ref()?.files.get(&rel_of(f)).cloned());
+        targets.insert(rel_of(f), (f.clone(), expected));
+    }
+    for (rel, hash) in manifest.iter().flat_map(|m| m.files.iter()) {
+        targets
+            .entry(rel.clone())
+            .or_insert_with(|| (dir.join(rel), Some(hash.clone())));
+    }
+    let checks: Vec<FileCheck> = targets
+        .into_values()
+        .map(|(f, expected)| check_file(&f, expected))
+        .collect();
+
+    let overridden = OVERRIDE.load(Ordering::SeqCst);
+    let unverified = manifest.is_none() && embedded.is_none();
+    let status = if unverified && sidecar {
+        "missing"
+    } else if unverified {
+        "unverified"
+    } else if checks.iter().all(|c| c.ok) {
+        "verified"
+    } else {
+        "mismatch"
+    };
+    let mismatched: Vec<String> = if unverified && sidecar {
+        vec![format!("缺少 {}", MANIFEST_NAME)]
+    } else if unverified {
+        Vec::new()
+    } else {
+        checks
+            .iter()
+            .filter(|c| !c.ok)
+            .map(|c| c.path.clone())
+            .collect()
+    };
+    *LAST_REPORT.lock().unwrap_or_else(|e| e.into_inner()) = Some(IntegrityReport {
+        status: status.to_string(),
+        files: checks,
+        overridden,
+    });
+
+    if mismatched.is_empty() {
+        return Ok(());
+    }
+    if overridden {
+        eprintln!(
+            "Warning: bridge 完整性校验失败，已按用户选择继续启动: {}",
+            mismatched.join(", ")
+        );
+        return Ok(());
+    }
+    Err(format!(
+        "Bridge 完整性校验失败，文件可能被篡改，已拒绝启动: {}",
+        mismatched.join(", ")
+    ))
+}
+
+#[tauri::command]
+pub async fn bridge_integrity_status() -> Result<Option<IntegrityReport>, BridgeError> {
+    guarded("bridge_integrity_status", async move {
+        Ok(LAST_REPORT
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone())
+    })
+    .await
+}
+
+/// 放行（或撤销放行）校验失败的 bridge；放行后需重新初始化 bridge 才会生效
+#[tauri::command]
+pub async fn bridge_integrity_override(enabled: bool) -> Result<(), BridgeError> {
+    guarded("bridge_integrity_override", async move {
+        OVERRIDE.store(enabled, Ordering::SeqCst);
+        Ok(())
+    })
+    .await
+}
@@ -0,0 +1,407 @@
+use crate::artifacts::sha256_file;
+use crate::bridge::{ensure_bridge_ready, stop_child, BridgeState};
+use crate::jobs::JobRegistry;
+use crate::panics::guarded;
+use crate::policy::CommandPolicy;
+use crate::proxy;
+use crate::store::{load_json, now_ms, save_json};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+use tokio::process::Command;
+
+const TEMURIN_API: &str = "https://api.adoptium.net/v3/assets/latest";
+/// 读不到当前运行时版本时按该大版本查询
+const DEFAULT_FEATURE: u32 = 17;
+const IDLE_POLL: Duration = Duration::from_secs(1);
+
+static UPDATING: AtomicBool = AtomicBool::new(false);
+
+/// app data 下 jdk/ 目录的状态文件
+#[derive(Clone, Default, Serialize, Deserialize)]
+struct JdkState {
+    #[serde(default)]
+    version: Option<String>,
+    #[serde(default)]
+    previous_version: Option<String>,
+    #[serde(default)]
+    updated_at: Option<u64>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct JdkRelease {
+    pub version: String,
+    pub link: String,
+    pub checksum: String,
+    pub name: String,
+    pub size: u64,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct JdkUpdateInfo {
+    pub current: Option<String>,
+    pub java_home: Option<String>,
+    pub latest: Option<JdkRelease>,
+    pub update_available: bool,
+}
+
+fn jdk_dir(data_dir: &Path) -> PathBuf {
+    data_dir.join("jdk")
+}
+
+fn java_exe(home: &Path) -> PathBuf {
+    #[cfg(target_os = "windows")]
+    return home.join("bin").join("java.exe");
+    #[cfg(not(target_os = "windows"))]
+    return home.join("bin").join("java");
+}
+
+/// 应用内更新过的 JDK（app data 下的 jdk/current）
+pub fn managed_java_home(data_dir: &Path) -> Option<PathBuf> {
+    let home = jdk_dir(data_dir).join("current");
+    java_exe(&home).is_file().then_some(home)
+}
+
+/// 从 JDK 的 release 文件读取 JAVA_VERSION
+fn runtime_version(home: &Path) -> Option<String> {
+    let text = std::fs::read_to_string(home.join("release")).ok()?;
+    text.lines()
+        .find_map(|l| l.strip_prefix("JAVA_VERSION="))
+        .map(|v| v.trim().trim_matches('"').to_string())
+}
+
+/// 大版本号：17.0.9 → 17，1.8.0_392 → 8
+fn feature_of(version: &str) -> Option<u32> {
+    let mut parts = version.split(['.', '_', '+', '-']);
+    match parts.next()?.parse().ok()? {
+        1 => parts.next()?.parse().ok(),
+        n => Some(n),
+    }
+}
+
+fn platform() -> Result<(&'static str, &'static str), String> {
+    let os = match std::env::consts::OS {
+        "windows" => "windows",
+        "macos" => "mac",
+        "linux" => "linux",
+        other => return Err(format!("不支持的平台: {}", other)),
+    };
+    let arch = match std::env::consts::ARCH {
+        "x86_64" => "x64",
+        "aarch64" => "aarch64",
+        other => return Err(format!("不支持的架构: {}", other)),
+    };
+    Ok((os, arch))
+}
+
+/// 系统自带 curl（Windows 10 起内置）
+async fn curl(args: &[&str]) -> Result<Vec<u8>, String> {
+    let out = Command::new("curl")
+        .args(["-fsSL", "--retry", "2"])
+        .args(args)
+        .envs(proxy::env_vars())
+        .output()
+        .await
+        .map_err(|e| format!("调用 curl 失败: {}", e))?;
+    if !out.status.success() {
+        return Err(format!(
+            "下载失败: {}",
+            String::from_utf8_lossy(&out.stderr).trim()
+        ));
+    }
+    Ok(out.stdout)
+}
+
+/// 查询 Temurin 该大版本最新的 JDK 包
+async fn latest_release(feature: u32) -> Result<JdkRelease, String> {
+    let (os, arch) = platform()?;
+    let url = format!(
+        "{}/{}/hotspot?os={}&architecture={}&image_type=jdk&vendor=eclipse",
+        TEMURIN_API, feature, os, arch
+    );
+    let body = curl(&[&url]).await?;
+    let assets: Value = serde_json::from_slice(&body).map_err(|e| e.to_string())?;
+    let asset = assets
+        .as_array()
+        .and_then(|a| a.first())
+        .ok_or_else(|| format!("Temurin 没有 JDK {} 的发布", feature))?;
+    let package = &asset["binary"]["package"];
+    let field = |v: &Value| v.as_str().unwrap_or_default().to_string();
+    let version = asset["version"]["semver"]
+        .as_str()
+        .or_else(|| asset["release_name"].as_str())
+        .unwrap_or_default();
+    let release = JdkRelease {
+        // 与 release 文件的 JAVA_VERSION 对齐，去掉 +build 后缀
+        version: version.split('+').next().unwrap_or_default().to_string(),
+        link: field(&package["link"]),
+        checksum: field(&package["checksum"]),
+        name: field(&package["name"]),
+        size: package["size"].as_u64().unwrap_or(0),
+    };
+    if release.link.is_empty() || release.checksum.is_empty() || release.name.is_empty() {
+        return Err("Temurin 返回的发布信息不完整".to_string());
+    }
+    Ok(release)
+}
+
+async fn current_java_home(state: &BridgeState) -> Option<PathBuf> {
+    state.lock().await.bundled_java_home.clone()
+}
+
+async fn check(state: &BridgeState) -> Result<JdkUpdateInfo, String> {
+    let home = current_java_home(state)
+        .await
+        .ok_or("当前未使用应用内置 JDK，无需在应用内更新")?;
+    let current = runtime_version(&home);
+    let feature = current
+        .as_deref()
+        .and_then(feature_of)
+        .unwrap_or(DEFAULT_FEATURE);
+    let latest = latest_release(feature).await?;
+    Ok(JdkUpdateInfo {
+        update_available: current.as_deref() != Some(latest.version.as_str()),
+        current,
+        java_home: Some(home.to_string_lossy().into_owned()),
+        latest: Some(latest),
+    })
+}
+
+/// 解压后的 JDK 可能在顶层目录或 macOS 的 Contents/Home 下
+fn find_java_home(dir: &Path, depth: u32) -> Option<PathBuf> {
+    if java_exe(dir).is_file() {
+        return Some(dir.to_path_buf());
+    }
+    if depth == 0 {
+        return None;
+    }
+    std::fs::read_dir(dir)
+        .ok()?
+        .flatten()
+        .filter(|e| e.path().is_dir())
+        .find_map(|e| find_java_home(&e.path(), depth - 1))
+}
+
+/// 兼容性探测：java -version 可运行，且大版本与预期一致
+async fn probe(home: &Path, feature: u32) -> Result<String, String> {
+    let out = Command::new(java_exe(home))
+        .arg("-version")
+        .output()
+        .await
+        .map_err(|e| format!("运行 java 失败: {}", e))?;
+    if !out.status.success() {
+        return Err(format!(
+            "java -version 失败: {}",
+            String::from_utf8_lossy(&out.stderr).trim()
+        ));
+    }
+    let version = runtime_version(home).ok_or("新 JDK 缺少 release 文件")?;
+    if feature_of(&version) != Some(feature) {
+        return Err(format!(
+            "新 JDK 版本 {} 与预期大版本 {} 不符",
+            version, feature
+        ));
+    }
+    Ok(version)
+}
+
+fn emit_stage(app: &AppHandle, stage: &str, message: Option<String>) {
+    let _ = app.emit(
+        "jdk-update",
+        serde_json::json!({ "stage": stage, "message": message }),
+    );
+}
+
+/// 下载、校验并解压到 staging，返回新 JDK 的 home 与版本
+async fn stage(
+    jdk: &Path,
+    release: &JdkRelease,
+    feature: u32,
+) -> Result<(PathBuf, String), String> {
+    let staging = jdk.join("staging");
+    let _ = std::fs::remove_dir_all(&staging);
+    let extract = staging.join("extract");
+    std::fs::create_dir_all(&extract).map_err(|e| format!("创建目录失败: {}", e))?;
+
+    let archive = staging.join(&release.name);
+    let archive_str = archive.to_string_lossy().into_owned();
+    curl(&["-o", &archive_str, &release.link]).await?;
+    let actual = sha256_file(&archive)?;
+    if !actual.eq_ignore_ascii_case(&release.checksum) {
+        return Err(format!(
+            "JDK 安装包校验失败: 期望 {}，实际 {}",
+            release.checksum, actual
+        ));
+    }
+
+    // Windows 10 起自带的 bsdtar 也能解 zip
+    let out = Command::new("tar")
+        .arg("-xf")
+        .arg(&archive)
+        .arg("-C")
+        .arg(&extract)
+        .output()
+        .await
+        .map_err(|e| format!("解压失败: {}", e))?;
+    if !out.status.success() {
+        return Err(format!(
+            "解压失败: {}",
+            String::from_utf8_lossy(&out.stderr).trim()
+        ));
+    }
+    let _ = std::fs::remove_file(&archive);
+    let home = find_java_home(&extract, 3).ok_or("安装包中未找到 java 可执行文件")?;
+    let version = probe(&home, feature).await?;
+    Ok((home, version))
+}
+
+/// 等 bridge 空闲后停掉它，把 staged JDK 换成 current；旧的 current 移到 previous。
+/// 返回切换前的 java home，用于回滚
+async fn swap(
+    state: &BridgeState,
+    jobs: &JobRegistry,
+    jdk: &Path,
+    staged: &Path,
+) -> Result<Option<PathBuf>, String> {
+    let current = jdk.join("current");
+    let previous = jdk.join("previous");
+    loop {
+        let mut guard = state.lock().await;
+        let busy = guard.active_streams > 0
+            || guard.dispatcher.as_ref().is_some_and(|d| d.in_flight() > 0);
+        if busy || jobs.running().is_some() {
+            drop(guard);
+            tokio::time::sleep(IDLE_POLL).await;
+            continue;
+        }
+        let old_home = guard.bundled_java_home.clone();
+        stop_child(&mut guard).await;
+        let _ = std::fs::remove_dir_all(&previous);
+        if current.exists() {
+            std::fs::rename(&current, &previous).map_err(|e| format!("移动旧 JDK 失败: {}", e))?;
+        }
+        if let Err(e) = std::fs::rename(staged, &current) {
+            if previous.exists() {
+                let _ = std::fs::rename(&previous, &current);
+            }
+            return Err(format!("安装新 JDK 失败: {}", e));
+        }
+        guard.bundled_java_home = Some(current);
+        guard.init_error = None;
+        return Ok(old_home);
+    }
+}
+
+/// 新 JDK 起不来 bridge 时恢复原来的运行时
+async fn rollback(state: &BridgeState, jdk: &Path, old_home: Option<PathBuf>) {
+    let current = jdk.join("current");
+    let previous = jdk.join("previous");
+    {
+        let mut guard = state.lock().await;
+        stop_child(&mut guard).await;
+        let _ = std::fs::remove_dir_all(&current);
+        if previous.exists() {
+            if let Err(e) = std::fs::rename(&previous, &current) {
+                eprintln!("Warning: 恢复旧 JDK 失败: {}", e);
+            }
+        }
+        guard.bundled_java_home = old_home;
+        guard.init_error = None;
+    }
+    if let Err(e) = ensure_bridge_ready(state).await {
+        eprintln!("Warning: 回滚 JDK 后 bridge 启动失败: {}", e);
+    }
+}
+
+async fn update(
+    app: &AppHandle,
+    state: &BridgeState,
+    jobs: &JobRegistry,
+) -> Result<JdkUpdateInfo, String> {
+    let data_dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
+    let jdk = jdk_dir(&data_dir);
+    emit_stage(app, "checking", None);
+    let info = check(state).await?;
+    let Some(release) = info.latest.clone().filter(|_| info.update_available) else {
+        emit_stage(app, "up_to_date", None);
+        return Ok(info);
+    };
+    let feature = feature_of(&release.version).unwrap_or(DEFAULT_FEATURE);
+
+    emit_stage(app, "downloading", Some(release.version.clone()));
+    let (staged, version) = match stage(&jdk, &release, feature).await {
+        Ok(v) => v,
+        Err(e) => {
+            let _ = std::fs::remove_dir_all(jdk.join("staging"));
+            return Err(e);
+        }
+    };
+
+    emit_stage(app, "waiting_idle", None);
+    let swapped = swap(state, jobs, &jdk, &staged).await;
+    let _ = std::fs::remove_dir_all(jdk.join("staging"));
+    let old_home = swapped?;
+
+    emit_stage(app, "verifying", None);
+    if let Err(e) = ensure_bridge_ready(state).await {
+        emit_stage(app, "rolling_back", Some(e.to_string()));
+        rollback(state, &jdk, old_home).await;
+        return Err(format!("新 JDK 未通过兼容性检查，已回滚: {}", e));
+    }
+
+    let state_path = jdk.join("state.json");
+    let previous: JdkState = load_json(&state_path);
+    let _ = save_json(
+        &state_path,
+        &JdkState {
+            version: Some(version.clone()),
+            previous_version: previous.version.or(info.current.clone()),
+            updated_at: Some(now_ms()),
+        },
+    );
+    emit_stage(app, "done", Some(version.clone()));
+    Ok(JdkUpdateInfo {
+        current: Some(version),
+        java_home: Some(jdk.join("current").to_string_lossy().into_owned()),
+        latest: Some(release),
+        update_available: false,
+    })
+}
+
+#[tauri::command]
+pub async fn jdk_check_update(
+    state: tauri::State<'_, BridgeState>,
+) -> Result<JdkUpdateInfo, String> {
+    guarded(
+        "jdk_check_update",
+        async move { check(state.inner()).await },
+    )
+    .await
+}
+
+/// 下载并切换到最新的 Temurin JDK；会等正在运行的任务结束后再重启 bridge，
+/// 新运行时启动失败则自动回滚。进度通过 `jdk-update` 事件推送
+#[tauri::command]
+pub async fn jdk_update(
+    app: AppHandle,
+    state: tauri::State<'_, BridgeState>,
+    jobs: tauri::State<'_, JobRegistry>,
+    policy: tauri::State<'_, CommandPolicy>,
+) -> Result<JdkUpdateInfo, String> {
+    guarded("jdk_update", async move {
+        policy.ensure_writable("更新 JDK")?;
+        if UPDATING.swap(true, Ordering::SeqCst) {
+            return Err("JDK 更新已在进行中".to_string());
+        }
+        let result = update(&app, state.inner(), jobs.inner()).await;
+        UPDATING.store(false, Ordering::SeqCst);
+        if let Err(ref e) = result {
+            emit_stage(&app, "failed", Some(e.clone()));
+        }
+        result
+    })
+    .await
+}
@@ -0,0 +1,454 @@
+use crate::abort::AbortStrategy;
+use crate::bridge::{find_project_root, submit_stream_job};
+use crate::changes::JobChanges;
+use crate::panics::guarded;
+use crate::paths::output_dir;
+use crate::policy::CommandPolicy;
+use crate::remap::{PathRemap, RemapChange};
+use crate::results::KeyResult;
+use crate::sandbox::check_path;
+use crate::settings::project_output_dir;
+use crate::store::{load_json, now_ms, save_json};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tauri::AppHandle;
+use tokio::sync::watch;
+
+/// 任务历史最多保留条数
+const MAX_JOBS: usize = 500;
+/// 写入任务记录前从 payload 中剔除的敏感字段
+const SENSITIVE_KEYS: &[&str] = &["api_key", "token", "password", "secret"];
+/// 请求中传给 bridge 的任务工作目录
+pub const JOB_DIR_KEY: &str = "job_dir";
+/// 项目输出目录下存放各任务工作目录的子目录
+const JOBS_SUBDIR: &str = "jobs";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobStatus {
+    Queued,
+    /// 许可证席位已满，等待其他会话释放
+    WaitingForLicense,
+    Running,
+    Succeeded,
+    Failed,
+    Aborted,
+}
+
+impl JobStatus {
+    pub fn is_finished(self) -> bool {
+        matches!(self, Self::Succeeded | Self::Failed | Self::Aborted)
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct JobRecord {
+    pub id: String,
+    pub cmd: String,
+    #[serde(default)]
+    pub project: Option<String>,
+    pub status: JobStatus,
+    #[serde(default)]
+    pub payload: Value,
+    pub created_at: u64,
+    #[serde(default)]
+    pub started_at: Option<u64>,
+    #[serde(default)]
+    pub finished_at: Option<u64>,
+    #[serde(default)]
+    pub message: Option<String>,
+    #[serde(default)]
+    pub artifacts: Vec<String>,
+    /// 由 job_rerun 基于哪个任务重新提交
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rerun_of: Option<String>,
+    /// 覆盖全局的中止策略
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub abort_strategy: Option<AbortStrategy>,
+    /// 各阶段开始时距任务开始的毫秒数，用于估算相似任务的剩余时间
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub stage_offsets: BTreeMap<String, u64>,
+    /// 从 bridge 导出的关键结果文件中提取的标量（如最高温度、f0 处的 S11）
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub metrics: BTreeMap<String, KeyResult>,
+    /// 任务结束时汇总的变更摘要，由 job_changes 返回
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub changes: Option<JobChanges>,
+    /// 任务独立的工作目录，bridge 的导出与模型文件写在其中
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub work_dir: Option<String>,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+struct JobStore {
+    jobs: Vec<JobRecord>,
+}
+
+/// 任务登记表：记录每次流式建模任务的请求、状态与产物，落盘到 app data 目录。
+/// 变更通过 `subscribe()` 广播，托盘菜单等据此重建。
+pub struct JobRegistry {
+    path: Option<PathBuf>,
+    store: Mutex<JobStore>,
+    paused: watch::Sender<bool>,
+    changes: watch::Sender<u64>,
+}
+
+impl Default for JobRegistry {
+    fn default() -> Self {
+        Self {
+            path: None,
+            store: Mutex::new(JobStore::default()),
+            paused: watch::channel(false).0,
+            changes: watch::channel(0).0,
+        }
+    }
+}
+
+/// 任务所属项目：优先显式 `project`，否则取工作目录
+pub fn project_of(payload: &Value) -> Option<String> {
+    ["project", "workspace_dir"]
+        .iter()
+        .filter_map(|k| payload.get(*k).and_then(|v| v.as_str()))
+        .map(str::trim)
+        .find(|s| !s.is_empty())
+        .map(String::from)
+}
+
+/// 任务的工作目录 `<项目输出目录>/jobs/<任务 ID>`：项目输出目录为请求的 workspace_dir
+/// （项目设置指定了 output_dir 时取其下的该目录），未指定时取模型默认输出目录。任务 ID 不能作为目录名或沙箱不允许写入时返回 None，沿用原有输出位置
+pub fn job_dir(payload: &Value, id: &str) -> Option<PathBuf> {
+    let valid_id = !id.is_empty()
+        && id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if !valid_id {
+        return None;
+    }
+    let base = payload
+        .get("workspace_dir")
+        .and_then(|v| v.as_str())
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(PathBuf::from)
+        .map(|p| project_output_dir(&p).unwrap_or(p))
+        .or_else(|| output_dir(&find_project_root()))?;
+    let dir = base.join(JOBS_SUBDIR).join(id);
+    check_path(&dir).ok()?;
+    Some(dir)
+}
+
+/// 剔除敏感字段；工作目录另存在 `work_dir`，重新运行时重新分配，不随 payload 沿用
+pub(crate) fn redact_payload(payload: &Value) -> Value {
+    let mut out = payload.clone();
+    if let Some(obj) = out.as_object_mut() {
+        obj.retain(|k, _| !SENSITIVE_KEYS.contains(&k.as_str()) && k != JOB_DIR_KEY);
+    }
+    out
+}
+
+impl JobRegistry {
+    pub fn load(path: PathBuf) -> Self {
+        let mut store: JobStore = load_json(&path);
+        // 上次退出时仍在运行/排队的任务不可能再完成
+        for job in store.jobs.iter_mut().filter(|j| !j.status.is_finished()) {
+            job.status = JobStatus::Failed;
+            job.message = Some("应用退出时任务尚未完成".to_string());
+        }
+        Self {
+            path: Some(path),
+            store: Mutex::new(store),
+            ..Self::default()
+        }
+    }
+
+    fn update<T>(&self, f: impl FnOnce(&mut JobStore) -> T) -> T {
+        let mut store = self.store.lock().unwrap_or_else(|e| e.into_inner());
+        let out = f(&mut store);
+        if let Some(ref p) = self.path {
+            if let Err(e) = save_json(p, &*store) {
+                eprintln!("Warning: 保存任务记录失败: {}", e);
+            }
+        }
+        drop(store);
+        self.changes.send_modify(|v| *v += 1);
+        out
+    }
+
+    fn with_job(&self, id: &str, f: impl FnOnce(&mut JobRecord)) {
+        self.update(|s| {
+            if let Some(job) = s.jobs.iter_mut().find(|j| j.id == id) {
+                f(job);
+            }
+        });
+    }
+
+    pub fn create(
+        &self,
+        id: &str,
+        cmd: &str,
+        payload: &Value,
+        rerun_of: Option<&str>,
+    ) -> JobRecord {
+        let job = JobRecord {
+            id: id.to_string(),
+            cmd: cmd.to_string(),
+            project: project_of(payload),
+            status: JobStatus::Queued,
+            payload: redact_payload(payload),
+            created_at: now_ms(),
+            started_at: None,
+            finished_at: None,
+            message: None,
+            artifacts: Vec::new(),
+            rerun_of: rerun_of.map(String::from),
+            abort_strategy: None,
+            stage_offsets: BTreeMap::new(),
+            metrics: BTreeMap::new(),
+            changes: None,
+            work_dir: payload
+                .get(JOB_DIR_KEY)
+                .and_then(|v| v.as_str())
+                .map(String::from),
+        };
+        self.update(|s| {
+            s.jobs.push(job.clone());
+            if s.jobs.len() > MAX_JOBS {
+                let excess = s.jobs.len() - MAX_JOBS;
+                s.jobs.drain(..excess);
+            }
+        });
+        job
+    }
+
+    pub fn mark_waiting_for_license(&self, id: &str) {
+        self.with_job(id, |j| j.status = JobStatus::WaitingForLicense);
+    }
+
+    pub fn mark_running(&self, id: &str) {
+        self.with_job(id, |j| {
+            j.status = JobStatus::Running;
+            j.started_at = Some(now_ms());
+        });
+    }
+
+    pub fn finish(&self, id: &str, status: JobStatus, message: Option<String>) {
+        self.with_job(id, |j| {
+            j.status = status;
+            j.finished_at = Some(now_ms());
+            j.message = message;
+        });
+    }
+
+    pub fn set_stage_offsets(&self, id: &str, offsets: BTreeMap<String, u64>) {
+        self.with_job(id, |j| j.stage_offsets = offsets);
+    }
+
+    pub fn set_metrics(&self, id: &str, metrics: BTreeMap<String, KeyResult>) {
+        self.with_job(id, |j| j.metrics = metrics);
+    }
+
+    pub fn set_changes(&self, id: &str, changes: JobChanges) {
+        self.with_job(id, |j| j.changes = Some(changes));
+    }
+
+    pub fn set_abort_strategy(&self, id: &str, strategy: Option<AbortStrategy>) {
+        self.with_job(id, |j| j.abort_strategy = strategy);
+    }
+
+    pub fn add_artifact(&self, id: &str, path: &str) {
+        self.with_job(id, |j| {
+            if !j.artifacts.iter().any(|a| a == path) {
+                j.artifacts.push(path.to_string());
+            }
+        });
+    }
+
+    /// 产物被移动或删除后同步任务记录
+    pub fn replace_artifact(&self, id: &str, from: &str, to: Option<&str>) {
+        self.with_job(id, |j| {
+            j.artifacts.retain(|a| a != from);
+            if let Some(to) = to {
+                j.artifacts.push(to.to_string());
+            }
+        });
+    }
+
+    pub fn get(&self, id: &str) -> Option<JobRecord> {
+        let store = self.store.lock().unwrap_or_else(|e| e.into_inner());
+        store.jobs.iter().find(|j| j.id == id).cloned()
+    }
+
+    /// 按时间倒序列出任务，可按项目过滤
+    pub fn list(&self, project: Option<&str>, limit: usize) -> Vec<JobRecord> {
+        let store = self.store.lock().unwrap_or_else(|e| e.into_inner());
+        store
+            .jobs
+            .iter()
+            .rev()
+            .filter(|j| project.is_none() || j.project.as_deref() == project)
+            .take(limit)
+            .cloned()
+            .collect()
+    }
+
+    pub fn running(&self) -> Option<JobRecord> {
+        let store = self.store.lock().unwrap_or_else(|e| e.into_inner());
+        store
+            .jobs
+            .iter()
+            .rev()
+            .find(|j| j.status == JobStatus::Running)
+            .cloned()
+    }
+
+    /// 出现过的项目（按最近使用排序）
+    pub fn projects(&self) -> Vec<String> {
+        let store = self.store.lock().unwrap_or_else(|e| e.into_inner());
+        let mut out: Vec<String> = Vec::new();
+        for p in store.jobs.iter().rev().filter_map(|j| j.project.as_ref()) {
+            if !out.contains(p) {
+                out.push(p.clone());
+            }
+        }
+        out
+    }
+
+    /// 改写任务记录中的项目、产物路径与请求里的路径参数（重新运行时沿用）
+    pub fn remap(&self, remap: &PathRemap, apply: bool) -> Vec<RemapChange> {
+        let rewrite = |store: &mut JobStore| {
+            let mut changes = Vec::new();
+            for job in store.jobs.iter_mut() {
+                if let Some(ref mut project) = job.project {
+                    remap.rewrite("jobs", &job.id, "project", project, &mut changes);
+                }
+                for artifact in job.artifacts.iter_mut() {
+                    remap.rewrite("jobs", &job.id, "artifacts", artifact, &mut changes);
+                }
+                if let Some(ref mut dir) = job.work_dir {
+                    remap.rewrite("jobs", &job.id, "work_dir", dir, &mut changes);
+                }
+                if let Some(obj) = job.payload.as_object_mut() {
+                    for (key, value) in obj.iter_mut() {
+                        if let Value::String(s) = value {
+                            let field = format!("payload.{}", key);
+                            remap.rewrite("jobs", &job.id, &field, s, &mut changes);
+                        }
+                    }
+                }
+            }
+            changes
+        };
+        if apply {
+            self.update(rewrite)
+        } else {
+            let jobs = self
+                .store
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .jobs
+                .clone();
+            rewrite(&mut JobStore { jobs })
+        }
+    }
+
+    pub fn is_paused(&self) -> bool {
+        *self.paused.borrow()
+    }
+
+    pub fn set_paused(&self, paused: bool) {
+        self.paused.send_replace(paused);
+        self.changes.send_modify(|v| *v += 1);
+    }
+
+    /// 队列暂停时，新提交的任务在此等待
+    pub async fn wait_until_resumed(&self) {
+        let mut rx = self.paused.subscribe();
+        let _ = rx.wait_for(|paused| !*paused).await;
+    }
+
+    pub fn subscribe(&self) -> watch::Receiver<u64> {
+        self.changes.subscribe()
+    }
+}
+
+#[tauri::command]
+pub async fn jobs_list(
+    jobs: tauri::State<'_, JobRegistry>,
+    project: Option<String>,
+    limit: Option<usize>,
+) -> Result<Vec<JobRecord>, String> {
+    guarded("jobs_list", async move {
+        Ok(jobs.list(project.as_deref(), limit.unwrap_or(100)))
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn job_get(
+    jobs: tauri::State<'_, JobRegistry>,
+    job_id: String,
+) -> Result<JobRecord, String> {
+    guarded("job_get", async move {
+        jobs.get(&job_id)
+            .ok_or_else(|| format!("未找到任务: {}", job_id))
+    })
+    .await
+}
+
+/// 浅层 JSON merge：overrides 的顶层键覆盖原值，值为 null 时删除该键
+fn merge_overrides(payload: &Value, overrides: Option<Value>) -> Result<Value, String> {
+    let mut out = payload.as_object().cloned().unwrap_or_default();
+    match overrides {
+        None | Some(Value::Null) => {}
+        Some(Value::Object(map)) => {
+            for (k, v) in map {
+                if v.is_null() {
+                    out.remove(&k);
+                } else {
+                    out.insert(k, v);
+                }
+            }
+        }
+        Some(_) => return Err("overrides 必须是 JSON 对象".to_string()),
+    }
+    Ok(Value::Object(out))
+}
+
+/// 以历史任务的请求为基础、合并 overrides 后作为新任务重新提交。
+/// 记录中的敏感字段（api_key 等）已被剔除，需要时由前端在 overrides 中补上
+#[tauri::command]
+pub async fn job_rerun(
+    app: AppHandle,
+    jobs: tauri::State<'_, JobRegistry>,
+    policy: tauri::State<'_, CommandPolicy>,
+    job_id: String,
+    overrides: Option<Value>,
+    request_id: Option<String>,
+) -> Result<Value, String> {
+    guarded("job_rerun", async move {
+        policy.ensure_writable("重新运行任务")?;
+        let job = jobs
+            .get(&job_id)
+            .ok_or_else(|| format!("未找到任务: {}", job_id))?;
+        let payload = merge_overrides(&job.payload, overrides)?;
+        submit_stream_job(&app, job.cmd, payload, request_id, Some(&job.id), None)
+            .await
+            .map_err(String::from)
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn queue_set_paused(
+    jobs: tauri::State<'_, JobRegistry>,
+    paused: bool,
+) -> Result<(), String> {
+    guarded("queue_set_paused", async move {
+        jobs.set_paused(paused);
+        Ok(())
+    })
+    .await
+}
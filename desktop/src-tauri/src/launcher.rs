@@ -0,0 +1,53 @@
+use crate::bridge::{init_bridge, BridgeHandles};
+use crate::bridge_error::BridgeError;
+use crate::remote;
+use std::future::Future;
+use std::path::PathBuf;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+
+pub type LaunchFuture = Pin<Box<dyn Future<Output = Result<BridgeHandles, BridgeError>> + Send>>;
+
+/// 启动一个 bridge 实例：交出已完成握手的管道多路复用器、退出状态与进程句柄。
+/// 默认启动 Python 子进程；开发与集成测试时可换成按录制回放的模拟实现
+pub trait BridgeLauncher: Send + Sync {
+    fn launch(&self, bundled_java_home: Option<PathBuf>) -> LaunchFuture;
+
+    /// 显示在状态与日志中的说明
+    fn describe(&self) -> String;
+}
+
+/// 真实的 bridge：Python 脚本（开发模式）或安装包内的可执行文件
+pub struct ProcessBridge;
+
+impl BridgeLauncher for ProcessBridge {
+    fn launch(&self, bundled_java_home: Option<PathBuf>) -> LaunchFuture {
+        Box::pin(init_bridge(bundled_java_home))
+    }
+
+    fn describe(&self) -> String {
+        "process".to_string()
+    }
+}
+
+/// 之后启动 bridge 时使用的实现；已运行的实例不受影响。
+/// 未指定时按设置连接远程 bridge，否则启动本机进程
+static ACTIVE: Mutex<Option<Arc<dyn BridgeLauncher>>> = Mutex::new(None);
+
+pub fn set_active(launcher: Option<Arc<dyn BridgeLauncher>>) {
+    *ACTIVE.lock().unwrap_or_else(|e| e.into_inner()) = launcher;
+}
+
+pub fn current() -> Arc<dyn BridgeLauncher> {
+    ACTIVE
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone()
+        .or_else(remote::launcher)
+        .unwrap_or_else(|| Arc::new(ProcessBridge))
+}
+
+/// 用当前实现启动一个 bridge 实例
+pub async fn launch(bundled_java_home: Option<PathBuf>) -> Result<BridgeHandles, BridgeError> {
+    current().launch(bundled_java_home).await
+}
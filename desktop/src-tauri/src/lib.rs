@@ -1,12 +1,198 @@
+mod abort;
+mod artifact_menu;
+mod artifacts;
+mod attachments;
+mod backend;
+mod backups;
+mod benchmark;
 mod bridge;
+mod bridge_error;
+mod changes;
+mod codec;
+mod commands;
+mod compare;
+mod comsol;
+mod debug_console;
+mod dialogs;
+mod dispatcher;
+mod env_cache;
+mod eta;
+mod event_routing;
+mod exit_log;
+mod filenames;
+mod framing;
+mod geometry;
+mod image_diff;
+mod integrity;
+mod jdk;
+mod jobs;
+mod launcher;
+mod license;
+mod limits;
+mod log_level;
+mod logs;
+mod maintenance;
+mod materials;
+mod messages;
+mod migrations;
+mod mphserver;
+mod notes;
+mod notifications;
+mod notifier;
+mod panics;
+mod paths;
+mod permissions;
+mod pipelines;
+mod policy;
+mod postprocess;
+mod process;
+mod profiles;
+mod progress;
+mod progress_window;
+mod protocol;
+mod proxy;
+mod recent;
+mod remap;
+mod remote;
+mod replay;
+mod repro;
+mod request_queue;
+mod results;
+mod sandbox;
+mod scripts;
+mod search;
+mod selftest;
+mod services;
+mod session_options;
+mod sessions;
+mod settings;
+mod size_limits;
+mod ssh;
+mod standby;
+mod stats;
+mod stderr_log;
+mod storage;
+mod store;
+mod stream;
+mod tables;
+mod timeouts;
+mod transcript;
+mod tray;
+mod units;
+mod validate;
+mod viewers;
+mod zoom;
 
+use abort::{
+    abort_strategy_get, abort_strategy_set, bridge_cancel_stream, job_set_abort_strategy,
+    AbortSettings,
+};
+use artifact_menu::artifact_context_menu;
+use artifacts::{
+    artifact_confirm_overwrite, artifact_lock_status, artifact_register, artifacts_list,
+    artifacts_verify, spawn_artifact_watcher, ArtifactRegistry,
+};
+use attachments::{attachment_remove, attachments_ingest, attachments_list, AttachmentStore};
+use backend::bridge_health;
+use backups::{backup_get_policy, backup_set_policy, backups_list, restore_backup, BackupManager};
+use benchmark::bridge_benchmark;
 use bridge::{
-    bridge_abort, bridge_ensure_ready, bridge_init_status, bridge_send, bridge_send_stream, bundled_java_home_from_app,
-    init_bridge, open_in_folder, open_path, BridgeState, BridgeStateInner,
+    bridge_abort, bridge_capabilities, bridge_ensure_ready, bridge_init_status, bridge_send,
+    bridge_send_stream, bridge_shutdown, bridge_start, bridge_stop, bundled_java_home_from_app,
+    emit_init_result, install_handles, open_in_folder, open_path, shutdown_bridge,
+    spawn_supervisor, BridgeState, BridgeStateInner, SHUTDOWN_GRACE,
+};
+use changes::{job_changes, ChangeTracker};
+use commands::list_commands;
+use compare::compare_jobs;
+use comsol::{comsol_installs, open_in_comsol};
+use debug_console::{
+    debug_console_close, debug_console_history, debug_console_open, debug_console_set_paused,
+};
+use dialogs::{
+    dialog_dir_set, dialog_dirs_get, dialog_open_file, dialog_save_file, DialogDirStore,
+};
+use env_cache::{refresh_environment_cache, spawn_environment_probe};
+use eta::job_eta;
+use event_routing::{window_subscribe, window_subscriptions, window_unsubscribe, EventRouter};
+use exit_log::bridge_last_exit;
+use filenames::sanitize_filename;
+use geometry::geometry_prepare;
+use image_diff::compare_images;
+use integrity::{bridge_integrity_override, bridge_integrity_status};
+use jdk::{jdk_check_update, jdk_update};
+use jobs::{job_get, job_rerun, jobs_list, queue_set_paused, JobRegistry};
+use license::{license_set_seats, license_status, LicenseGuard};
+use log_level::bridge_set_log_level;
+use logs::{logs_get_retention, logs_rotate, logs_set_retention, logs_usage, LogRotation};
+use maintenance::{
+    maintenance_defer, maintenance_run, maintenance_status, spawn_maintenance, Maintenance,
+};
+use materials::{materials_refresh, materials_search, MaterialCache};
+use migrations::migration_report;
+use mphserver::{
+    comsol_server_profiles, connect_comsol_server, disconnect_comsol_server, mphserver_start,
+    mphserver_status, mphserver_stop, start_managed, MphServer,
+};
+use notes::{project_notes_get, project_notes_set};
+use notifications::{
+    event_ack, events_unacked, notifications_list, spawn_redelivery, NotificationCenter,
+};
+use notifier::{notifier_get, notifier_set, notifier_test, Notifier};
+use panics::{catch_panics, diagnostics_panics};
+use paths::get_app_paths;
+use permissions::{permission_forget, permission_respond, permissions_list, HostPermissions};
+use pipelines::{
+    pipeline_cancel, pipeline_get, pipeline_retry, pipeline_submit, pipelines_list,
+    PipelineRegistry,
+};
+use policy::{policy_get, policy_set, CommandPolicy};
+use postprocess::{
+    postprocess_run, postprocess_status, postprocessor_set, postprocessors_list,
+    spawn_postprocess_pool, PostProcessors,
+};
+use profiles::{profile_delete, profiles_list, switch_profile};
+use progress::{job_progress_current, ProgressAggregator};
+use progress_window::{
+    progress_window_close, progress_window_open, progress_window_set_click_through,
+    progress_window_snap,
 };
+use recent::{recent_models_add, recent_models_clear, recent_models_list, RecentModels};
+use remap::remap_root;
+use replay::{bridge_replay_start, bridge_replay_stop};
+use repro::capture_repro_state;
+use request_queue::{bridge_queue_cancel, bridge_queue_status};
+use results::results_trend;
+use sandbox::{sandbox_get, sandbox_set, Sandbox};
+use scripts::export_job_script;
+use search::{search_everything, search_reindex, spawn_search_indexer, SearchIndex};
+use selftest::{selftest_get, selftest_run, selftest_set, SelfTest};
+use services::{get_service_endpoints, service_port_range_set, ServiceRegistry};
+use session_options::{bridge_get_session_options, bridge_set_session_options, SessionOptions};
+use sessions::{bridge_session_close, bridge_session_create, bridge_session_list, BridgeSessions};
+use settings::{
+    settings_effective, settings_get, settings_reload, spawn_settings_watcher, LiveSettings,
+};
+use ssh::remote_connect_ssh;
+use standby::{bridge_standby_status, Standby};
+use stats::bridge_stats;
+use std::path::PathBuf;
 use std::sync::Arc;
+use stderr_log::bridge_get_stderr;
+use storage::{
+    cleanup_suggestions, job_remove_work_dir, storage_get_config, storage_set_failed_outputs,
+    storage_set_quota, storage_usage, StorageManager,
+};
+use stream::{stream_set_rate, StreamRegistry};
+use tables::{table_close, table_open, table_rows, TableRegistry};
 use tauri::Manager;
+use timeouts::{bridge_timeouts_get, bridge_timeouts_set, RequestTimeouts};
 use tokio::sync::Mutex;
+use transcript::{bridge_recording_export, bridge_recording_start, bridge_recording_stop};
+use units::{convert_value, parse_quantity};
+use validate::validate_request;
+use viewers::{open_in_viewer, viewers_get, viewers_set, ViewerRegistry};
+use zoom::{window_zoom_get, window_zoom_reset, window_zoom_set, ZoomSettings};
 
 #[tauri::command]
 fn apply_window_icon(window: tauri::WebviewWindow) {
@@ -16,63 +202,374 @@ fn apply_window_icon(window: tauri::WebviewWindow) {
     }
 }
 
+/// 从 app data 目录加载持久化状态；目录不可用时退回内存态
+fn load_store<T: Default>(dir: &Option<PathBuf>, file: &str, load: fn(PathBuf) -> T) -> T {
+    dir.as_ref().map(|d| load(d.join(file))).unwrap_or_default()
+}
+
+/// 退出或切换档案重启前关闭全部子进程：bridge、会话、热备 bridge 与托管的 mphserver
+pub(crate) async fn shutdown_children(app: &tauri::AppHandle) {
+    let state = app.state::<BridgeState>().inner().clone();
+    shutdown_bridge(&state, SHUTDOWN_GRACE).await;
+    sessions::shutdown_all(app, SHUTDOWN_GRACE).await;
+    standby::discard(app).await;
+    app.state::<MphServer>().shutdown();
+}
+
+#[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
+    sandbox::run_sandbox_exec();
     tauri::Builder::default()
         .plugin(tauri_plugin_dialog::init())
-        .manage(Arc::new(Mutex::new(BridgeStateInner {
-            stdin: None,
-            reader: None,
-            child: None,
-            child_pid: None,
-            stream_active: false,
-            init_in_progress: false,
-            bundled_java_home: None,
-            init_error: None,
-            stderr_buf: Arc::new(std::sync::Mutex::new(String::new())),
-        })))
-        .invoke_handler(tauri::generate_handler![
+        .manage(Arc::new(Mutex::new(BridgeStateInner::new(None))))
+        .manage(BridgeSessions::default())
+        .manage(Standby::default())
+        .manage(StreamRegistry::default())
+        .manage(ProgressAggregator::default())
+        .manage(ChangeTracker::default())
+        .manage(TableRegistry::default())
+        .manage(EventRouter::default())
+        .manage(SessionOptions::default())
+        .register_uri_scheme_protocol(geometry::PROTOCOL, geometry::handle_protocol)
+        .invoke_handler(catch_panics(tauri::generate_handler![
             bridge_send,
             bridge_send_stream,
             bridge_abort,
             bridge_ensure_ready,
             bridge_init_status,
+            bridge_capabilities,
+            bridge_integrity_status,
+            bridge_integrity_override,
+            stream_set_rate,
+            event_ack,
+            events_unacked,
+            notifications_list,
+            job_progress_current,
+            job_eta,
+            jobs_list,
+            job_get,
+            queue_set_paused,
+            recent_models_list,
+            recent_models_add,
+            recent_models_clear,
+            artifacts_list,
+            artifact_register,
+            artifact_confirm_overwrite,
+            artifact_lock_status,
+            backups_list,
+            restore_backup,
+            backup_get_policy,
+            backup_set_policy,
+            storage_usage,
+            storage_get_config,
+            storage_set_quota,
+            storage_set_failed_outputs,
+            cleanup_suggestions,
+            export_job_script,
+            comsol_installs,
+            open_in_comsol,
+            mphserver_status,
+            mphserver_start,
+            mphserver_stop,
+            connect_comsol_server,
+            disconnect_comsol_server,
+            comsol_server_profiles,
+            viewers_get,
+            viewers_set,
+            open_in_viewer,
+            geometry_prepare,
+            table_open,
+            table_rows,
+            table_close,
+            convert_value,
+            parse_quantity,
+            materials_search,
+            materials_refresh,
+            policy_get,
+            policy_set,
+            jdk_check_update,
+            jdk_update,
+            sandbox_get,
+            sandbox_set,
+            migration_report,
+            profiles_list,
+            switch_profile,
+            profile_delete,
+            progress_window_open,
+            progress_window_close,
+            progress_window_snap,
+            progress_window_set_click_through,
+            debug_console_open,
+            debug_console_close,
+            debug_console_set_paused,
+            debug_console_history,
             open_path,
             open_in_folder,
             apply_window_icon,
-        ])
+            list_commands,
+            bridge_benchmark,
+            selftest_get,
+            selftest_set,
+            selftest_run,
+            get_app_paths,
+            dialog_open_file,
+            dialog_save_file,
+            dialog_dirs_get,
+            dialog_dir_set,
+            job_rerun,
+            compare_jobs,
+            abort_strategy_get,
+            abort_strategy_set,
+            job_set_abort_strategy,
+            pipeline_submit,
+            pipelines_list,
+            pipeline_get,
+            pipeline_retry,
+            pipeline_cancel,
+            notifier_get,
+            notifier_set,
+            notifier_test,
+            get_service_endpoints,
+            capture_repro_state,
+            service_port_range_set,
+            window_subscribe,
+            window_unsubscribe,
+            window_subscriptions,
+            artifact_context_menu,
+            diagnostics_panics,
+            bridge_set_session_options,
+            bridge_get_session_options,
+            bridge_timeouts_get,
+            bridge_timeouts_set,
+            settings_get,
+            settings_reload,
+            bridge_get_stderr,
+            results_trend,
+            bridge_shutdown,
+            postprocessors_list,
+            postprocessor_set,
+            postprocess_status,
+            postprocess_run,
+            bridge_cancel_stream,
+            bridge_queue_status,
+            bridge_queue_cancel,
+            remap_root,
+            artifacts_verify,
+            job_changes,
+            license_status,
+            license_set_seats,
+            window_zoom_get,
+            window_zoom_set,
+            window_zoom_reset,
+            attachments_ingest,
+            attachments_list,
+            attachment_remove,
+            logs_usage,
+            logs_get_retention,
+            logs_set_retention,
+            logs_rotate,
+            bridge_start,
+            bridge_stop,
+            validate_request,
+            bridge_session_create,
+            bridge_session_list,
+            bridge_session_close,
+            permission_respond,
+            permissions_list,
+            permission_forget,
+            bridge_standby_status,
+            bridge_stats,
+            refresh_environment_cache,
+            job_remove_work_dir,
+            bridge_last_exit,
+            bridge_set_log_level,
+            bridge_recording_start,
+            bridge_recording_stop,
+            bridge_recording_export,
+            sanitize_filename,
+            settings_effective,
+            bridge_replay_start,
+            bridge_replay_stop,
+            bridge_health,
+            compare_images,
+            search_everything,
+            search_reindex,
+            remote_connect_ssh,
+            project_notes_get,
+            project_notes_set,
+            maintenance_status,
+            maintenance_run,
+            maintenance_defer,
+        ]))
         .setup(|app| {
+            let profile = profiles::resolve(
+                &app.path().app_data_dir().ok(),
+                &app.path().app_config_dir().ok(),
+            );
+            let data_dir = profile.data_dir.clone();
+            let config_dir = profile.config_dir.clone();
+            // 必须先于各状态加载，避免旧格式被当作解析失败而重置
+            let migration = migrations::run(&data_dir, &config_dir);
+            app.manage(load_store(&config_dir, "policy.json", CommandPolicy::load));
+            app.manage(load_store(&config_dir, "sandbox.json", Sandbox::load));
+            app.manage(load_store(
+                &config_dir,
+                "permissions.json",
+                HostPermissions::load,
+            ));
+            app.manage(load_store(&config_dir, "selftest.json", SelfTest::load));
+            app.manage(load_store(&config_dir, "abort.json", AbortSettings::load));
+            app.manage(load_store(
+                &config_dir,
+                "timeouts.json",
+                RequestTimeouts::load,
+            ));
+            app.manage(load_store(&config_dir, "settings.json", LiveSettings::load));
+            app.manage(load_store(
+                &config_dir,
+                "dialog_dirs.json",
+                DialogDirStore::load,
+            ));
+            app.manage(load_store(&config_dir, "notifier.json", Notifier::load));
+            app.manage(load_store(
+                &config_dir,
+                "postprocess.json",
+                PostProcessors::load,
+            ));
+            app.manage(load_store(&config_dir, "license.json", LicenseGuard::load));
+            app.manage(load_store(&config_dir, "zoom.json", ZoomSettings::load));
+            app.manage(load_store(&config_dir, "logs.json", LogRotation::load));
+            app.manage(load_store(&data_dir, "ports.json", ServiceRegistry::load));
+            app.manage(load_store(
+                &data_dir,
+                "notifications.json",
+                NotificationCenter::load,
+            ));
+            app.manage(load_store(&data_dir, "jobs.json", JobRegistry::load));
+            app.manage(load_store(
+                &data_dir,
+                "pipelines.json",
+                PipelineRegistry::load,
+            ));
+            app.manage(load_store(
+                &data_dir,
+                "recent_models.json",
+                RecentModels::load,
+            ));
+            app.manage(load_store(
+                &data_dir,
+                "artifacts.json",
+                ArtifactRegistry::load,
+            ));
+            app.manage(load_store(&data_dir, "backups", BackupManager::load));
+            app.manage(load_store(&data_dir, "attachments", AttachmentStore::load));
+            app.manage(load_store(&data_dir, "storage.json", StorageManager::load));
+            app.manage(load_store(&data_dir, "mphserver.json", MphServer::load));
+            app.manage(load_store(&data_dir, "viewers.json", ViewerRegistry::load));
+            app.manage(load_store(
+                &data_dir,
+                "materials_cache.sqlite3",
+                MaterialCache::load,
+            ));
+            app.manage(load_store(&data_dir, "maintenance.json", Maintenance::load));
+            app.manage(load_store(
+                &data_dir,
+                "search_index.sqlite3",
+                SearchIndex::load,
+            ));
+            if let Some(ref dir) = data_dir {
+                env_cache::load(dir.join("environment.json"));
+            }
+            if !migration.steps.is_empty() {
+                let payload = serde_json::to_value(&migration).unwrap_or_default();
+                app.state::<NotificationCenter>().deliver(
+                    app.handle(),
+                    "migration-report",
+                    payload,
+                );
+            }
+            app.manage(migration);
+            if let Err(e) = profiles::create_windows(app, &profile) {
+                eprintln!("Warning: {}", e);
+            }
+            app.manage(profile);
+            panics::install_hook(app.handle().clone());
+            stderr_log::attach(app.handle().clone());
+            exit_log::attach(app.handle().clone());
+            spawn_redelivery(app.handle().clone());
+            spawn_artifact_watcher(app.handle().clone());
+            spawn_postprocess_pool(app.handle().clone());
+            spawn_supervisor(app.handle().clone());
+            spawn_settings_watcher(app.handle().clone());
+            spawn_search_indexer(app.handle().clone());
+            spawn_maintenance(app.handle().clone());
+            app.handle().on_menu_event(artifact_menu::handle_menu_event);
+            if let Err(e) = tray::setup_tray(app) {
+                eprintln!("Warning: 创建系统托盘失败: {}", e);
+            }
+            if app.state::<MphServer>().config().enabled
+                && !app.state::<CommandPolicy>().is_read_only()
+            {
+                if let Err(e) = start_managed(app.handle()) {
+                    eprintln!("Warning: 启动 mphserver 失败: {}", e);
+                }
+            }
+
+            replay::init_from_env();
             let state = app.state::<BridgeState>().inner().clone();
-            let java_home = bundled_java_home_from_app(app);
+            let java_home = app
+                .state::<LiveSettings>()
+                .java_home()
+                .or_else(|| bundled_java_home_from_app(app));
+            let app_handle = app.handle().clone();
+            let autostart = app.state::<LiveSettings>().bridge_autostart();
+            // 在首帧之前标记为启动中：此后到达的 bridge_send 立即得到 starting 错误，
+            // 而不是再各自拉起一个 bridge 进程
+            {
+                let mut guard = state.blocking_lock();
+                guard.bundled_java_home = java_home.clone();
+                guard.init_in_progress = autostart;
+                guard.init_error = None;
+            }
+            spawn_environment_probe(app.handle().clone());
+            if !autostart {
+                // 延迟启动：第一个建模请求或 bridge_start 时再拉起 Python 与 JVM
+                return Ok(());
+            }
             tauri::async_runtime::spawn(async move {
-                {
-                    let mut guard = state.lock().await;
-                    guard.bundled_java_home = java_home.clone();
-                    guard.init_in_progress = true;
-                    guard.init_error = None;
-                }
-                match init_bridge(java_home.clone()).await {
+                let started = std::time::Instant::now();
+                match launcher::launch(java_home.clone()).await {
                     Ok(handles) => {
                         let mut guard = state.lock().await;
                         guard.bundled_java_home = java_home;
-                        guard.stdin = Some(handles.stdin);
-                        guard.reader = Some(handles.reader);
-                        guard.child = Some(handles.child);
-                        guard.child_pid = Some(handles.pid);
-                        guard.stderr_buf = handles.stderr_buf;
-                        guard.init_error = None;
-                        guard.init_in_progress = false;
+                        install_handles(&mut guard, handles);
+                        drop(guard);
+                        emit_init_result(&app_handle, Ok(()), started.elapsed());
+                        standby::refill(&app_handle);
+                        if app_handle.state::<SelfTest>().enabled()
+                            && !app_handle.state::<CommandPolicy>().is_read_only()
+                        {
+                            selftest::run(&app_handle).await;
+                        }
                     }
                     Err(e) => {
                         eprintln!("Warning: Failed to initialize Python bridge: {}", e);
                         let mut guard = state.lock().await;
                         guard.bundled_java_home = java_home;
-                        guard.init_error = Some(e);
+                        guard.init_error = Some(e.clone());
                         guard.init_in_progress = false;
+                        drop(guard);
+                        emit_init_result(&app_handle, Err(&e), started.elapsed());
                     }
                 }
             });
             Ok(())
         })
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while running tauri application")
+        .run(|app, event| {
+            if let tauri::RunEvent::Exit = event {
+                tauri::async_runtime::block_on(shutdown_children(app));
+            }
+        });
 }
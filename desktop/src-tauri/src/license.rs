@@ -0,0 +1,192 @@
+use crate::jobs::JobRegistry;
+use crate::mphserver::{attach_endpoint, MphServer};
+use crate::panics::guarded;
+use crate::store::{load_json, save_json};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeSet;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+use tokio::sync::Notify;
+
+/// 等待席位时重新检查的间隔：托管 mphserver 的启停不经过这里，需要轮询
+const RECHECK_INTERVAL: Duration = Duration::from_secs(5);
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct LicenseConfig {
+    /// 可同时使用的 COMSOL 许可证席位；未设置时不限制
+    #[serde(default)]
+    pub seats: Option<u32>,
+}
+
+#[derive(Default)]
+struct Seats {
+    /// 持有席位的任务
+    holders: BTreeSet<String>,
+    /// 等待席位的任务
+    waiting: BTreeSet<String>,
+}
+
+/// 许可证席位守卫：托管的 mphserver 与不连接 server、在 bridge 内独立启动 COMSOL 的任务
+/// 各占一个席位。超出配置的席位数时任务以 waiting_for_license 状态排队，
+/// 而不是在 Java 层准备数分钟后才因许可证耗尽失败
+#[derive(Default)]
+pub struct LicenseGuard {
+    path: Option<PathBuf>,
+    config: Mutex<LicenseConfig>,
+    seats: Mutex<Seats>,
+    notify: Notify,
+}
+
+/// 任务持有的席位，释放时唤醒等待的任务
+pub struct SeatLease {
+    app: AppHandle,
+    job_id: String,
+}
+
+impl Drop for SeatLease {
+    fn drop(&mut self) {
+        let guard = self.app.state::<LicenseGuard>();
+        guard.lock().holders.remove(&self.job_id);
+        guard.notify.notify_waiters();
+    }
+}
+
+/// 等待中的任务提前返回时移出等待列表
+struct WaitEntry<'a> {
+    guard: &'a LicenseGuard,
+    job_id: &'a str,
+}
+
+impl Drop for WaitEntry<'_> {
+    fn drop(&mut self) {
+        self.guard.lock().waiting.remove(self.job_id);
+    }
+}
+
+/// 请求会在 bridge 进程内启动 COMSOL（而不是连接已有的 server）时才需要单独的席位
+fn needs_seat(app: &AppHandle, payload: &Value) -> bool {
+    let mut probe = payload.clone();
+    attach_endpoint(app, &mut probe);
+    probe.get("comsol_server").is_none()
+}
+
+impl LicenseGuard {
+    pub fn load(path: PathBuf) -> Self {
+        let config: LicenseConfig = load_json(&path);
+        Self {
+            path: Some(path),
+            config: Mutex::new(config),
+            ..Self::default()
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Seats> {
+        self.seats.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn config(&self) -> LicenseConfig {
+        self.config
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    fn set_config(&self, config: LicenseConfig) -> Result<(), String> {
+        if let Some(ref p) = self.path {
+            save_json(p, &config)?;
+        }
+        *self.config.lock().unwrap_or_else(|e| e.into_inner()) = config;
+        self.notify.notify_waiters();
+        Ok(())
+    }
+
+    /// 托管 mphserver 运行时占用的席位
+    fn server_seats(app: &AppHandle) -> u32 {
+        u32::from(app.state::<MphServer>().endpoint().is_some())
+    }
+
+    fn try_take(&self, app: &AppHandle, job_id: &str) -> bool {
+        let limit = self.config().seats;
+        let mut seats = self.lock();
+        let in_use = seats.holders.len() as u32 + Self::server_seats(app);
+        if limit.is_some_and(|max| in_use >= max) {
+            seats.waiting.insert(job_id.to_string());
+            return false;
+        }
+        seats.waiting.remove(job_id);
+        seats.holders.insert(job_id.to_string());
+        true
+    }
+
+    /// 为任务取得席位；没有空闲席位时把任务标为 waiting_for_license 并等待。
+    /// 连接 server 的任务不占用席位，返回 None
+    pub async fn acquire(
+        &self,
+        app: &AppHandle,
+        job_id: &str,
+        payload: &Value,
+    ) -> Option<SeatLease> {
+        if !needs_seat(app, payload) {
+            return None;
+        }
+        let _entry = WaitEntry {
+            guard: self,
+            job_id,
+        };
+        let mut marked = false;
+        loop {
+            let mut notified = std::pin::pin!(self.notify.notified());
+            notified.as_mut().enable();
+            if self.try_take(app, job_id) {
+                return Some(SeatLease {
+                    app: app.clone(),
+                    job_id: job_id.to_string(),
+                });
+            }
+            if !marked {
+                app.state::<JobRegistry>().mark_waiting_for_license(job_id);
+                marked = true;
+            }
+            let _ = tokio::time::timeout(RECHECK_INTERVAL, notified).await;
+        }
+    }
+
+    fn status(&self, app: &AppHandle) -> Value {
+        let seats = self.lock();
+        let server = Self::server_seats(app);
+        serde_json::json!({
+            "seats": self.config().seats,
+            "in_use": seats.holders.len() as u32 + server,
+            "managed_server": server > 0,
+            "jobs": seats.holders,
+            "waiting": seats.waiting,
+        })
+    }
+}
+
+/// 席位配置与当前占用：托管 mphserver、持有席位的任务与等待席位的任务
+#[tauri::command]
+pub async fn license_status(
+    app: AppHandle,
+    guard: tauri::State<'_, LicenseGuard>,
+) -> Result<Value, String> {
+    guarded("license_status", async move { Ok(guard.status(&app)) }).await
+}
+
+/// 设置许可证席位数；传 null 取消限制
+#[tauri::command]
+pub async fn license_set_seats(
+    guard: tauri::State<'_, LicenseGuard>,
+    seats: Option<u32>,
+) -> Result<(), String> {
+    guarded("license_set_seats", async move {
+        if seats == Some(0) {
+            return Err("席位数至少为 1".to_string());
+        }
+        guard.set_config(LicenseConfig { seats })
+    })
+    .await
+}
@@ -0,0 +1,339 @@
+use crate::process::TreeGuard;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::sync::Mutex;
+
+/// 超出内存上限被结束的错误前缀
+pub const RESOURCE_LIMIT: &str = "ResourceLimit:";
+const MIN_MEMORY_MB: u64 = 256;
+/// Python 与 JVM 分配失败时写到 stderr 的内容；rlimit 与 Job Object 不直接结束进程，而是让分配失败
+const OOM_MARKERS: &[&str] = &[
+    "MemoryError",
+    "java.lang.OutOfMemoryError",
+    "Cannot allocate memory",
+];
+
+/// bridge 子进程（含 COMSOL 的 JVM 等派生进程）的资源上限，设置文件中的 `resource_limits`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ResourceLimits {
+    /// 内存上限（MB），超出时 bridge 被结束并返回 ResourceLimit 错误
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub memory_mb: Option<u64>,
+    /// CPU 占用上限，占全部核心的百分比（1–100），超出时限速而不结束
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cpu_percent: Option<u32>,
+}
+
+impl ResourceLimits {
+    pub fn parse(value: &Value) -> Result<Self, String> {
+        let limits: Self =
+            serde_json::from_value(value.clone()).map_err(|e| format!("格式错误: {}", e))?;
+        if limits.memory_mb.is_some_and(|m| m < MIN_MEMORY_MB) {
+            return Err(format!("memory_mb 不能小于 {}", MIN_MEMORY_MB));
+        }
+        if limits.cpu_percent.is_some_and(|c| !(1..=100).contains(&c)) {
+            return Err("cpu_percent 应在 1 ~ 100 之间".to_string());
+        }
+        Ok(limits)
+    }
+
+    fn is_empty(&self) -> bool {
+        self.memory_mb.is_none() && self.cpu_percent.is_none()
+    }
+}
+
+/// 下一次启动 bridge 时使用的上限；设置文件变化后即时更新，已运行的进程不受影响
+static ACTIVE: Mutex<ResourceLimits> = Mutex::new(ResourceLimits {
+    memory_mb: None,
+    cpu_percent: None,
+});
+
+pub fn set_active(limits: ResourceLimits) {
+    *ACTIVE.lock().unwrap_or_else(|e| e.into_inner()) = limits;
+}
+
+pub(crate) fn active() -> ResourceLimits {
+    *ACTIVE.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// 一次启动实际采用的限制方式
+enum Enforcement {
+    None,
+    /// systemd 用户 scope（cgroup v2），超出内存时由内核 OOM 结束整个 scope
+    #[cfg(target_os = "linux")]
+    Scope(String),
+    /// prlimit 设置的数据段上限（RLIMIT_DATA），只能限制内存。不用地址空间上限（RLIMIT_AS）：
+    /// JVM 启动时按最大堆预留大段虚拟地址，远超实际占用，按物理内存设定的 RLIMIT_AS 会让它
+    /// 直接无法启动。RLIMIT_DATA 统计私有可写映射（堆与匿名 mmap），是已提交内存的近似，
+    /// 但不计共享内存与文件映射，也不会像 cgroup 那样由内核在超限时结束进程，只让分配失败
+    #[cfg(target_os = "linux")]
+    Rlimit,
+    /// 进程所在的 Job Object（由 TreeGuard 持有，随最后一个副本关闭）
+    #[cfg(target_os = "windows")]
+    Job(TreeGuard),
+}
+
+/// 已应用到某个 bridge 进程的资源限制；进程退出后据此判断是否因超限被结束
+pub struct AppliedLimits {
+    limits: ResourceLimits,
+    enforcement: Enforcement,
+}
+
+#[cfg(target_os = "linux")]
+fn on_path(name: &str) -> bool {
+    std::env::var_os("PATH")
+        .is_some_and(|paths| std::env::split_paths(&paths).any(|d| d.join(name).is_file()))
+}
+
+/// systemd 用户实例可用时才能创建 scope；只检测一次
+#[cfg(target_os = "linux")]
+fn systemd_scope_available() -> bool {
+    static AVAILABLE: std::sync::OnceLock<bool> = std::sync::OnceLock::new();
+    *AVAILABLE.get_or_init(|| {
+        on_path("systemd-run")
+            && std::process::Command::new("systemd-run")
+                .args(["--user", "--scope", "--quiet", "--", "true"])
+                .stdout(std::process::Stdio::null())
+                .stderr(std::process::Stdio::null())
+                .status()
+                .is_ok_and(|s| s.success())
+    })
+}
+
+/// 按当前上限包装 bridge 启动命令（Linux）；Windows 在启动后由 `attach` 在其 Job Object 上设置上限
+pub fn wrap(program: String, args: Vec<String>) -> (String, Vec<String>, AppliedLimits) {
+    let limits = active();
+    let none = |program, args| {
+        let applied = AppliedLimits {
+            limits,
+            enforcement: Enforcement::None,
+        };
+        (program, args, applied)
+    };
+    if limits.is_empty() {
+        return none(program, args);
+    }
+    #[cfg(target_os = "linux")]
+    {
+        use std::sync::atomic::{AtomicU64, Ordering};
+        static NEXT_UNIT: AtomicU64 = AtomicU64::new(1);
+        if systemd_scope_available() {
+            let unit = format!(
+                "comsol-agent-bridge-{}-{}.scope",
+                std::process::id(),
+                NEXT_UNIT.fetch_add(1, Ordering::Relaxed)
+            );
+            let mut wrapped: Vec<String> = ["--user", "--scope", "--quiet", "--unit", &unit]
+                .iter()
+                .map(|s| s.to_string())
+                .collect();
+            if let Some(mb) = limits.memory_mb {
+                wrapped.extend(["-p".into(), format!("MemoryMax={}M", mb)]);
+                wrapped.extend(["-p".into(), "MemorySwapMax=0".into()]);
+            }
+            if let Some(percent) = limits.cpu_percent {
+                // CPUQuota 以单个核心为 100%
+                let cores = std::thread::available_parallelism().map_or(1, |n| n.get()) as u32;
+                wrapped.extend(["-p".into(), format!("CPUQuota={}%", percent * cores)]);
+            }
+            wrapped.push("--".into());
+            wrapped.push(program);
+            wrapped.extend(args);
+            let applied = AppliedLimits {
+                limits,
+                enforcement: Enforcement::Scope(unit),
+            };
+            return ("systemd-run".to_string(), wrapped, applied);
+        }
+        if limits.cpu_percent.is_some() {
+            eprintln!("Warning: systemd 用户实例不可用，bridge 的 CPU 上限未生效");
+        }
+        if let Some(mb) = limits.memory_mb.filter(|_| on_path("prlimit")) {
+            let mut wrapped = vec![format!("--data={}", mb * 1024 * 1024), "--".to_string()];
+            wrapped.push(program);
+            wrapped.extend(args);
+            let applied = AppliedLimits {
+                limits,
+                enforcement: Enforcement::Rlimit,
+            };
+            return ("prlimit".to_string(), wrapped, applied);
+        }
+        eprintln!("Warning: 未找到 systemd-run 或 prlimit，bridge 的资源上限未生效");
+    }
+    none(program, args)
+}
+
+#[cfg(target_os = "windows")]
+mod job {
+    use super::ResourceLimits;
+    use windows_sys::Win32::System::JobObjects::{
+        JobObjectCpuRateControlInformation, JobObjectExtendedLimitInformation,
+        QueryInformationJobObject, SetInformationJobObject, JOBOBJECT_CPU_RATE_CONTROL_INFORMATION,
+        JOBOBJECT_EXTENDED_LIMIT_INFORMATION, JOB_OBJECT_CPU_RATE_CONTROL_ENABLE,
+        JOB_OBJECT_CPU_RATE_CONTROL_HARD_CAP, JOB_OBJECT_LIMIT_JOB_MEMORY,
+        JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE,
+    };
+
+    /// 在进程所在的 Job Object 上设置上限；之后派生的 JVM 等进程同样受限
+    pub fn apply(job: usize, limits: &ResourceLimits) -> Result<(), String> {
+        unsafe {
+            // 扩展信息整体覆盖，需保留 contain 设置的 KILL_ON_JOB_CLOSE
+            let mut info: JOBOBJECT_EXTENDED_LIMIT_INFORMATION = std::mem::zeroed();
+            info.BasicLimitInformation.LimitFlags = JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE;
+            if let Some(mb) = limits.memory_mb {
+                info.BasicLimitInformation.LimitFlags |= JOB_OBJECT_LIMIT_JOB_MEMORY;
+                info.JobMemoryLimit = (mb * 1024 * 1024) as usize;
+            }
+            let ok = SetInformationJobObject(
+                job as _,
+                JobObjectExtendedLimitInformation,
+                &info as *const _ as *const _,
+                std::mem::size_of::<JOBOBJECT_EXTENDED_LIMIT_INFORMATION>() as u32,
+            );
+            if ok == 0 {
+                return Err(std::io::Error::last_os_error().to_string());
+            }
+            if let Some(percent) = limits.cpu_percent {
+                let mut cpu: JOBOBJECT_CPU_RATE_CONTROL_INFORMATION = std::mem::zeroed();
+                cpu.ControlFlags =
+                    JOB_OBJECT_CPU_RATE_CONTROL_ENABLE | JOB_OBJECT_CPU_RATE_CONTROL_HARD_CAP;
+                // CpuRate 以 1/100 百分点为单位
+                cpu.Anonymous.CpuRate = percent * 100;
+                SetInformationJobObject(
+                    job as _,
+                    JobObjectCpuRateControlInformation,
+                    &cpu as *const _ as *const _,
+                    std::mem::size_of::<JOBOBJECT_CPU_RATE_CONTROL_INFORMATION>() as u32,
+                );
+            }
+            Ok(())
+        }
+    }
+
+    /// Job 内进程的内存峰值（字节）
+    pub fn peak_memory(job: usize) -> Option<u64> {
+        unsafe {
+            let mut info: JOBOBJECT_EXTENDED_LIMIT_INFORMATION = std::mem::zeroed();
+            let ok = QueryInformationJobObject(
+                job as _,
+                JobObjectExtendedLimitInformation,
+                &mut info as *mut _ as *mut _,
+                std::mem::size_of::<JOBOBJECT_EXTENDED_LIMIT_INFORMATION>() as u32,
+                std::ptr::null_mut(),
+            );
+            (ok != 0).then_some(info.PeakJobMemoryUsed as u64)
+        }
+    }
+}
+
+impl AppliedLimits {
+    /// 不受资源上限约束的进程，如只负责转发的 ssh 客户端（bridge 运行在远程主机上）
+    pub fn unlimited() -> Self {
+        Self {
+            limits: ResourceLimits::default(),
+            enforcement: Enforcement::None,
+        }
+    }
+
+    /// 进程启动并经 [`crate::process::contain`] 建立结束范围后调用：Windows 上在其 Job Object 上设置上限
+    pub fn attach(&mut self, tree: &TreeGuard) {
+        #[cfg(target_os = "windows")]
+        if let (Some(job), false) = (tree.job_handle(), self.limits.is_empty()) {
+            match job::apply(job, &self.limits) {
+                Ok(()) => self.enforcement = Enforcement::Job(tree.clone()),
+                Err(e) => eprintln!("Warning: 设置 bridge 资源上限失败: {}", e),
+            }
+        }
+        let _ = tree;
+    }
+
+    /// 进程意外退出后判断是否因超出内存上限被结束；是则返回错误说明
+    pub fn violation(&self, stderr_tail: &str) -> Option<String> {
+        let mb = self.limits.memory_mb?;
+        let hit = match self.enforcement {
+            Enforcement::None => false,
+            #[cfg(target_os = "linux")]
+            Enforcement::Scope(ref unit) => std::process::Command::new("systemctl")
+                .args(["--user", "show", "-p", "Result", "--value", unit])
+                .output()
+                .is_ok_and(|o| String::from_utf8_lossy(&o.stdout).trim() == "oom-kill"),
+            #[cfg(target_os = "linux")]
+            Enforcement::Rlimit => false,
+            #[cfg(target_os = "windows")]
+            Enforcement::Job(ref tree) => tree
+                .job_handle()
+                .and_then(job::peak_memory)
+                .is_some_and(|peak| peak * 100 >= mb * 1024 * 1024 * 95),
+        };
+        let hit = hit
+            || (!matches!(self.enforcement, Enforcement::None)
+                && OOM_MARKERS.iter().any(|m| stderr_tail.contains(m)));
+        hit.then(|| format!("bridge 子进程超出内存上限 {} MB 被结束", mb))
+    }
+}
+
+impl Drop for AppliedLimits {
+    fn drop(&mut self) {
+        match self.enforcement {
+            Enforcement::None => {}
+            // 失败的 scope 会保留到 reset-failed，以便退出后查询 Result
+            #[cfg(target_os = "linux")]
+            Enforcement::Scope(ref unit) => {
+                let _ = std::process::Command::new("systemctl")
+                    .args(["--user", "reset-failed", unit])
+                    .stdout(std::process::Stdio::null())
+                    .stderr(std::process::Stdio::null())
+                    .status();
+            }
+            #[cfg(target_os = "linux")]
+            Enforcement::Rlimit => {}
+            #[cfg(target_os = "windows")]
+            Enforcement::Job(_) => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn parse_accepts_partial_and_empty_limits() {
+        assert_eq!(
+            ResourceLimits::parse(&json!({})),
+            Ok(ResourceLimits::default())
+        );
+        let limits = ResourceLimits::parse(&json!({ "memory_mb": 4096 })).unwrap();
+        assert_eq!(limits.memory_mb, Some(4096));
+        assert_eq!(limits.cpu_percent, None);
+        let limits =
+            ResourceLimits::parse(&json!({ "memory_mb": 256, "cpu_percent": 100 })).unwrap();
+        assert_eq!(limits.memory_mb, Some(MIN_MEMORY_MB));
+        assert_eq!(limits.cpu_percent, Some(100));
+    }
+
+    #[test]
+    fn parse_rejects_out_of_range_values() {
+        assert!(ResourceLimits::parse(&json!({ "memory_mb": 255 })).is_err());
+        assert!(ResourceLimits::parse(&json!({ "cpu_percent": 0 })).is_err());
+        assert!(ResourceLimits::parse(&json!({ "cpu_percent": 101 })).is_err());
+        assert!(ResourceLimits::parse(&json!({ "memory_mb": -1 })).is_err());
+    }
+
+    #[test]
+    fn parse_rejects_unknown_fields_and_wrong_types() {
+        assert!(ResourceLimits::parse(&json!({ "memory": 1024 })).is_err());
+        assert!(ResourceLimits::parse(&json!({ "memory_mb": "1024" })).is_err());
+        assert!(ResourceLimits::parse(&json!(null)).is_err());
+    }
+
+    #[test]
+    fn empty_limits_only_without_any_value() {
+        assert!(ResourceLimits::default().is_empty());
+        assert!(!ResourceLimits::parse(&json!({ "cpu_percent": 50 }))
+            .unwrap()
+            .is_empty());
+    }
+}
@@ -0,0 +1,117 @@
+use crate::bridge::check_cmd_supported;
+use crate::bridge_error::BridgeError;
+use crate::debug_console::{self, Direction};
+use crate::dispatcher::recv;
+use crate::panics::guarded;
+use crate::sessions;
+use serde_json::Value;
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::AppHandle;
+
+pub const LEVELS: &[&str] = &["error", "warn", "info", "debug", "trace"];
+/// 等待 bridge 确认的时间；控制消息在读取线程当场处理，不受正在执行的任务影响
+const ACK_WAIT: Duration = Duration::from_secs(2);
+
+/// 运行时选定的日志级别；未设置时桌面端只输出警告，bridge 沿用自身配置
+static LEVEL: Mutex<Option<&'static str>> = Mutex::new(None);
+
+fn parse(level: &str) -> Result<&'static str, String> {
+    let level = level.trim().to_ascii_lowercase();
+    LEVELS
+        .iter()
+        .find(|l| **l == level)
+        .copied()
+        .ok_or_else(|| format!("日志级别应为 {} 之一", LEVELS.join(" / ")))
+}
+
+/// 删除设置项时恢复默认；已运行的 bridge 保持当前级别到下次启动
+pub fn reset() {
+    set(None);
+}
+
+fn set(level: Option<&'static str>) {
+    *LEVEL.lock().unwrap_or_else(|e| e.into_inner()) = level;
+}
+
+pub fn current() -> Option<&'static str> {
+    *LEVEL.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// 是否输出该级别的桌面端日志
+pub fn enabled(level: &str) -> bool {
+    let rank = |l: &str| LEVELS.iter().position(|x| *x == l);
+    match (current().and_then(rank), rank(level)) {
+        (Some(cur), Some(lvl)) => lvl <= cur,
+        (None, lvl) => lvl <= rank("warn"),
+        _ => false,
+    }
+}
+
+/// 新启动的 bridge 通过环境变量 LOG_LEVEL 沿用当前级别（loguru 的级别名）
+pub fn python_level() -> Option<&'static str> {
+    current().map(|l| match l {
+        "warn" => "WARNING",
+        "error" => "ERROR",
+        "info" => "INFO",
+        "debug" => "DEBUG",
+        _ => "TRACE",
+    })
+}
+
+/// 发送 `{"cmd":"set_log_level","level":...}`，返回 bridge 是否确认；
+/// 未运行或不支持该控制消息的 bridge 在下次启动时经环境变量生效
+async fn send_level(state: &crate::bridge::BridgeState, level: &str) -> bool {
+    let dispatcher = {
+        let guard = state.lock().await;
+        if check_cmd_supported(&guard, "set_log_level").is_err() {
+            return false;
+        }
+        match guard.dispatcher.clone() {
+            Some(d) => d,
+            None => return false,
+        }
+    };
+    let (id, mut rx) = dispatcher.register();
+    let line = serde_json::json!({ "cmd": "set_log_level", "level": level, "_id": id }).to_string();
+    debug_console::record(Direction::Out, &line);
+    if dispatcher.write_line(&line).await.is_err() {
+        dispatcher.cancel(&id);
+        return false;
+    }
+    match tokio::time::timeout(ACK_WAIT, recv(&mut rx)).await {
+        Ok(Ok(Ok(ack))) => ack.get("ok").and_then(Value::as_bool) == Some(true),
+        Ok(_) => false,
+        Err(_) => {
+            dispatcher.cancel(&id);
+            false
+        }
+    }
+}
+
+/// 设置桌面端级别并通知全部正在运行的 bridge 会话，返回已确认的会话 ID
+pub async fn apply(app: &AppHandle, level: &str) -> Result<Vec<String>, String> {
+    let level = parse(level)?;
+    set(Some(level));
+    let mut applied = Vec::new();
+    for (id, state) in sessions::all(app) {
+        if send_level(&state, level).await {
+            applied.push(id);
+        }
+    }
+    Ok(applied)
+}
+
+/// 运行时切换日志详细程度（桌面端与 bridge），不需重启；不写入设置文件，
+/// 应用重启后恢复设置中的 log_level
+#[tauri::command]
+pub async fn bridge_set_log_level(app: AppHandle, level: String) -> Result<Value, BridgeError> {
+    guarded("bridge_set_log_level", async move {
+        let applied = apply(&app, &level).await?;
+        Ok(serde_json::json!({
+            "level": current(),
+            "sessions": applied,
+        }))
+    })
+    .await
+}
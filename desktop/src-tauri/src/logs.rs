@@ -0,0 +1,397 @@
+use crate::bridge::find_project_root;
+use crate::panics::guarded;
+use crate::store::{load_json, now_ms, save_json};
+use crate::transcript::TRANSCRIPTS_DIR;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::SystemTime;
+use tauri::{AppHandle, Manager};
+
+/// bridge 在 MPH_AGENT_BRIDGE_DEBUG=1 时写入系统临时目录的调试日志
+const BRIDGE_DEBUG_LOG: &str = "mph-agent-bridge-debug.log";
+/// 轮转出的日志段放在 app data 的该目录下，按类别分子目录
+pub(crate) const LOGS_DIR: &str = "logs";
+const DAY_MS: u64 = 24 * 60 * 60 * 1000;
+const MB: u64 = 1024 * 1024;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct LogRetention {
+    /// 持续写入的日志超过该大小（MB）时切出一段并压缩
+    pub max_segment_mb: u64,
+    /// 不再写入的日志超过该天数后压缩
+    pub compress_after_days: u64,
+    /// 超过该天数的日志删除
+    pub retention_days: u64,
+    /// 每个类别的总大小上限（MB），超出时从最旧的开始删除
+    pub max_total_mb: u64,
+}
+
+impl Default for LogRetention {
+    fn default() -> Self {
+        Self {
+            max_segment_mb: 20,
+            compress_after_days: 3,
+            retention_days: 30,
+            max_total_mb: 1024,
+        }
+    }
+}
+
+/// 一类日志：持续追加的单个文件，或按对话分文件的目录
+struct LogCategory {
+    name: &'static str,
+    /// 持续追加、需要按大小切段的日志
+    live: Option<PathBuf>,
+    /// 存放日志段或按对话分开的日志文件的目录
+    dirs: Vec<PathBuf>,
+}
+
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct LogUsage {
+    pub category: String,
+    pub locations: Vec<String>,
+    pub files: usize,
+    pub bytes: u64,
+    /// 其中已压缩的部分
+    pub compressed_files: usize,
+    pub compressed_bytes: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub oldest_ms: Option<u64>,
+}
+
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct RotationReport {
+    pub rotated: usize,
+    pub compressed: usize,
+    pub deleted: usize,
+    pub freed_bytes: u64,
+    pub errors: Vec<String>,
+    pub finished_at: u64,
+}
+
+struct LogFile {
+    path: PathBuf,
+    size: u64,
+    modified_ms: u64,
+}
+
+impl LogFile {
+    fn compressed(&self) -> bool {
+        self.path.extension().is_some_and(|e| e == "gz")
+    }
+}
+
+fn modified_ms(meta: &std::fs::Metadata) -> u64 {
+    meta.modified()
+        .ok()
+        .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+fn log_files(dir: &Path) -> Vec<LogFile> {
+    let Ok(rd) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    rd.flatten()
+        .filter_map(|e| {
+            let meta = e.metadata().ok().filter(|m| m.is_file())?;
+            let name = e.file_name().to_string_lossy().into_owned();
+            if name.ends_with(".tmp") {
+                return None;
+            }
+            Some(LogFile {
+                path: e.path(),
+                size: meta.len(),
+                modified_ms: modified_ms(&meta),
+            })
+        })
+        .collect()
+}
+
+/// Python 端对话日志：`<项目根>/.context/<会话>/dialog_logs`
+fn dialog_log_dirs() -> Vec<PathBuf> {
+    let Some(root) = find_project_root() else {
+        return Vec::new();
+    };
+    let Ok(rd) = std::fs::read_dir(root.join(".context")) else {
+        return Vec::new();
+    };
+    rd.flatten()
+        .map(|e| e.path().join("dialog_logs"))
+        .filter(|d| d.is_dir())
+        .collect()
+}
+
+fn categories(app: &AppHandle) -> Vec<LogCategory> {
+    let logs = app.path().app_data_dir().ok().map(|d| d.join(LOGS_DIR));
+    let segments = logs.as_ref().map(|d| d.join("bridge_debug"));
+    vec![
+        LogCategory {
+            name: "bridge_debug",
+            live: Some(std::env::temp_dir().join(BRIDGE_DEBUG_LOG)),
+            dirs: segments.into_iter().collect(),
+        },
+        LogCategory {
+            name: "dialog_logs",
+            live: None,
+            dirs: dialog_log_dirs(),
+        },
+        LogCategory {
+            name: "transcripts",
+            live: None,
+            dirs: logs.map(|d| d.join(TRANSCRIPTS_DIR)).into_iter().collect(),
+        },
+    ]
+}
+
+/// gzip 压缩 `src` 写到 `dest`，先写临时文件再 rename
+fn gzip_to(src: &Path, dest: &Path) -> Result<(), String> {
+    let tmp = dest.with_extension("gz.tmp");
+    let result = (|| -> std::io::Result<()> {
+        let mut input = std::fs::File::open(src)?;
+        let mut encoder = GzEncoder::new(std::fs::File::create(&tmp)?, Compression::default());
+        std::io::copy(&mut input, &mut encoder)?;
+        encoder.finish()?.sync_all()
+    })();
+    if let Err(e) = result {
+        let _ = std::fs::remove_file(&tmp);
+        return Err(format!("压缩 {} 失败: {}", src.display(), e));
+    }
+    std::fs::rename(&tmp, dest).map_err(|e| format!("压缩 {} 失败: {}", src.display(), e))
+}
+
+/// 就地压缩已停止写入的日志，保留原修改时间以便按天数清理
+fn compress_in_place(file: &LogFile) -> Result<u64, String> {
+    let mut name = file.path.file_name().unwrap_or_default().to_os_string();
+    name.push(".gz");
+    let dest = file.path.with_file_name(name);
+    gzip_to(&file.path, &dest)?;
+    let mtime = std::fs::metadata(&file.path)
+        .and_then(|m| m.modified())
+        .unwrap_or_else(|_| SystemTime::now());
+    if let Ok(f) = std::fs::File::options().write(true).open(&dest) {
+        let _ = f.set_modified(mtime);
+    }
+    std::fs::remove_file(&file.path)
+        .map_err(|e| format!("删除 {} 失败: {}", file.path.display(), e))?;
+    let compressed = std::fs::metadata(&dest).map(|m| m.len()).unwrap_or(0);
+    Ok(file.size.saturating_sub(compressed))
+}
+
+/// 持续追加的日志超过上限时复制出一段压缩保存，再清空原文件（写入方以追加模式打开，不受影响）
+fn rotate_live(live: &Path, segments: &Path, max_bytes: u64) -> Result<bool, String> {
+    let Ok(meta) = std::fs::metadata(live) else {
+        return Ok(false);
+    };
+    if meta.len() <= max_bytes {
+        return Ok(false);
+    }
+    std::fs::create_dir_all(segments).map_err(|e| format!("创建日志目录失败: {}", e))?;
+    let stem = live
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "log".to_string());
+    let dest = segments.join(format!("{}-{}.log.gz", stem, now_ms()));
+    gzip_to(live, &dest)?;
+    std::fs::File::options()
+        .write(true)
+        .open(live)
+        .and_then(|f| f.set_len(0))
+        .map_err(|e| format!("清空 {} 失败: {}", live.display(), e))?;
+    Ok(true)
+}
+
+fn rotate_category(category: &LogCategory, policy: &LogRetention, report: &mut RotationReport) {
+    let now = now_ms();
+    if let (Some(live), Some(segments)) = (&category.live, category.dirs.first()) {
+        match rotate_live(live, segments, policy.max_segment_mb.max(1) * MB) {
+            Ok(true) => report.rotated += 1,
+            Ok(false) => {}
+            Err(e) => report.errors.push(e),
+        }
+    }
+
+    let compress_before = now.saturating_sub(policy.compress_after_days * DAY_MS);
+    let delete_before = now.saturating_sub(policy.retention_days.max(1) * DAY_MS);
+    let mut kept = Vec::new();
+    for dir in &category.dirs {
+        for file in log_files(dir) {
+            if file.modified_ms < delete_before {
+                match std::fs::remove_file(&file.path) {
+                    Ok(()) => {
+                        report.deleted += 1;
+                        report.freed_bytes += file.size;
+                    }
+                    Err(e) => {
+                        report
+                            .errors
+                            .push(format!("删除 {} 失败: {}", file.path.display(), e))
+                    }
+                }
+                continue;
+            }
+            if !file.compressed() && file.modified_ms < compress_before {
+                match compress_in_place(&file) {
+                    Ok(freed) => {
+                        report.compressed += 1;
+                        report.freed_bytes += freed;
+                    }
+                    Err(e) => report.errors.push(e),
+                }
+                continue;
+            }
+            kept.push(file);
+        }
+    }
+
+    // 总量超限时从最旧的开始删除
+    let max_total = policy.max_total_mb.max(1) * MB;
+    let mut total: u64 = kept.iter().map(|f| f.size).sum();
+    kept.sort_by_key(|f| f.modified_ms);
+    for file in kept {
+        if total <= max_total {
+            break;
+        }
+        if std::fs::remove_file(&file.path).is_ok() {
+            total -= file.size;
+            report.deleted += 1;
+            report.freed_bytes += file.size;
+        }
+    }
+}
+
+fn usage_of(category: &LogCategory) -> LogUsage {
+    let mut usage = LogUsage {
+        category: category.name.to_string(),
+        ..LogUsage::default()
+    };
+    let live = category.live.iter().filter_map(|p| {
+        let meta = std::fs::metadata(p).ok()?;
+        Some(LogFile {
+            path: p.clone(),
+            size: meta.len(),
+            modified_ms: modified_ms(&meta),
+        })
+    });
+    let files: Vec<LogFile> = live
+        .chain(category.dirs.iter().flat_map(|d| log_files(d)))
+        .collect();
+    usage.locations = category
+        .live
+        .iter()
+        .chain(category.dirs.iter())
+        .map(|p| p.to_string_lossy().into_owned())
+        .collect();
+    for f in &files {
+        usage.files += 1;
+        usage.bytes += f.size;
+        if f.compressed() {
+            usage.compressed_files += 1;
+            usage.compressed_bytes += f.size;
+        }
+    }
+    usage.oldest_ms = files.iter().map(|f| f.modified_ms).min();
+    usage
+}
+
+/// 日志轮转与保留策略：bridge 调试日志按大小切段压缩，对话日志按天数压缩，
+/// 超过保留天数或总量上限的旧日志删除
+#[derive(Default)]
+pub struct LogRotation {
+    path: Option<PathBuf>,
+    policy: Mutex<LogRetention>,
+    /// 同一时间只运行一次轮转
+    running: Mutex<()>,
+}
+
+impl LogRotation {
+    pub fn load(path: PathBuf) -> Self {
+        let policy: LogRetention = load_json(&path);
+        Self {
+            path: Some(path),
+            policy: Mutex::new(policy),
+            running: Mutex::new(()),
+        }
+    }
+
+    pub fn policy(&self) -> LogRetention {
+        self.policy
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    fn set_policy(&self, policy: LogRetention) -> Result<(), String> {
+        if let Some(ref p) = self.path {
+            save_json(p, &policy)?;
+        }
+        *self.policy.lock().unwrap_or_else(|e| e.into_inner()) = policy;
+        Ok(())
+    }
+
+    fn rotate(&self, app: &AppHandle) -> RotationReport {
+        let _running = self.running.lock().unwrap_or_else(|e| e.into_inner());
+        let policy = self.policy();
+        let mut report = RotationReport::default();
+        for category in categories(app) {
+            rotate_category(&category, &policy, &mut report);
+        }
+        report.finished_at = now_ms();
+        report
+    }
+}
+
+/// 按当前策略轮转一次；由维护调度在空闲时定期调用
+pub(crate) async fn rotate_now(app: &AppHandle) -> Result<RotationReport, String> {
+    let app = app.clone();
+    tauri::async_runtime::spawn_blocking(move || app.state::<LogRotation>().rotate(&app))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// 各类日志当前占用的磁盘空间
+#[tauri::command]
+pub async fn logs_usage(app: AppHandle) -> Result<Vec<LogUsage>, String> {
+    guarded("logs_usage", async move {
+        tauri::async_runtime::spawn_blocking(move || {
+            categories(&app).iter().map(usage_of).collect()
+        })
+        .await
+        .map_err(|e| e.to_string())
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn logs_get_retention(
+    rotation: tauri::State<'_, LogRotation>,
+) -> Result<LogRetention, String> {
+    guarded("logs_get_retention", async move { Ok(rotation.policy()) }).await
+}
+
+#[tauri::command]
+pub async fn logs_set_retention(
+    rotation: tauri::State<'_, LogRotation>,
+    policy: LogRetention,
+) -> Result<(), String> {
+    guarded("logs_set_retention", async move {
+        if policy.retention_days == 0 {
+            return Err("保留天数至少为 1".to_string());
+        }
+        if policy.max_segment_mb == 0 || policy.max_total_mb == 0 {
+            return Err("大小上限至少为 1 MB".to_string());
+        }
+        rotation.set_policy(policy)
+    })
+    .await
+}
+
+/// 立即按当前策略轮转一次
+#[tauri::command]
+pub async fn logs_rotate(app: AppHandle) -> Result<RotationReport, String> {
+    guarded("logs_rotate", async move { rotate_now(&app).await }).await
+}
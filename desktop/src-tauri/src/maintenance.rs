@@ -0,0 +1,438 @@
+use crate::artifacts::background_integrity_scan;
+use crate::jobs::JobRegistry;
+use crate::logs::rotate_now;
+use crate::panics::guarded;
+use crate::policy::CommandPolicy;
+use crate::search::SearchIndex;
+use crate::sessions;
+use crate::store::{load_json, now_ms, save_json};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+use tokio::sync::Notify;
+
+/// 调度检查的间隔；手动触发时立即唤醒
+const TICK: Duration = Duration::from_secs(30);
+/// 没有任务运行、bridge 没有在途请求持续这么久才算空闲
+const IDLE_AFTER_MS: u64 = 3 * 60 * 1000;
+/// 推迟的上限
+const MAX_DEFER_MINUTES: u64 = 7 * 24 * 60;
+/// 缓存目录中超过该天数未修改的文件删除
+const CACHE_MAX_AGE_MS: u64 = 30 * 24 * 60 * 60 * 1000;
+/// 缓存目录的总大小上限，超出时从最旧的文件开始删除
+const CACHE_MAX_BYTES: u64 = 2 * 1024 * 1024 * 1024;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MaintenanceTask {
+    /// 清理缓存目录（几何预览、图像比较结果等）中过期与超量的文件
+    CacheCleanup,
+    /// 重新计算已登记产物的哈希，标记缺失与损坏
+    IntegrityScan,
+    /// 合并搜索索引段，收回增删文档后的空闲空间
+    IndexCompaction,
+    /// 按保留策略轮转、压缩与删除日志
+    LogRotation,
+}
+
+impl MaintenanceTask {
+    const ALL: [Self; 4] = [
+        Self::LogRotation,
+        Self::IndexCompaction,
+        Self::CacheCleanup,
+        Self::IntegrityScan,
+    ];
+
+    fn interval_ms(self) -> u64 {
+        const HOUR_MS: u64 = 60 * 60 * 1000;
+        match self {
+            Self::LogRotation => HOUR_MS,
+            Self::IndexCompaction => 6 * HOUR_MS,
+            Self::CacheCleanup => 24 * HOUR_MS,
+            Self::IntegrityScan => 6 * HOUR_MS,
+        }
+    }
+}
+
+/// 每项维护的运行记录，跨重启保留
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct TaskRecord {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_run: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_duration_ms: Option<u64>,
+    /// 上次运行的结果摘要
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_result: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_error: Option<String>,
+    /// 在此之前不自动运行
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deferred_until: Option<u64>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct TaskStatus {
+    pub task: MaintenanceTask,
+    pub interval_secs: u64,
+    #[serde(flatten)]
+    pub record: TaskRecord,
+    /// 下次到期时间；到期后还需等到空闲才运行
+    pub next_due: u64,
+    pub running: bool,
+    /// 已手动触发、等待空闲时运行
+    pub pending: bool,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct MaintenanceStatus {
+    pub idle: bool,
+    /// 最近一次观察到忙碌（任务运行或 bridge 有在途请求）的时间
+    pub last_busy: u64,
+    pub tasks: Vec<TaskStatus>,
+}
+
+#[derive(Default)]
+struct Inner {
+    records: BTreeMap<MaintenanceTask, TaskRecord>,
+    pending: Vec<MaintenanceTask>,
+    running: Option<MaintenanceTask>,
+    last_busy: u64,
+}
+
+impl Inner {
+    fn due(&self, task: MaintenanceTask, now: u64) -> bool {
+        let record = self.records.get(&task);
+        if self.pending.contains(&task) {
+            return true;
+        }
+        if record
+            .and_then(|r| r.deferred_until)
+            .is_some_and(|t| now < t)
+        {
+            return false;
+        }
+        now >= next_due(task, record)
+    }
+}
+
+fn next_due(task: MaintenanceTask, record: Option<&TaskRecord>) -> u64 {
+    let after_run = record
+        .and_then(|r| r.last_run)
+        .map_or(0, |t| t + task.interval_ms());
+    after_run.max(record.and_then(|r| r.deferred_until).unwrap_or(0))
+}
+
+/// 空闲时运行的后台维护：缓存清理、产物完整性巡检、索引压缩与日志轮转。
+/// 只在没有任务运行、bridge 没有在途请求一段时间后才运行，一次一项，
+/// 可手动立即触发或推迟
+pub struct Maintenance {
+    path: Option<PathBuf>,
+    inner: Mutex<Inner>,
+    /// 同一时间只运行一项维护
+    run_lock: tokio::sync::Mutex<()>,
+    wake: Notify,
+}
+
+impl Default for Maintenance {
+    fn default() -> Self {
+        Self {
+            path: None,
+            inner: Mutex::new(Inner {
+                last_busy: now_ms(),
+                ..Inner::default()
+            }),
+            run_lock: tokio::sync::Mutex::new(()),
+            wake: Notify::new(),
+        }
+    }
+}
+
+impl Maintenance {
+    pub fn load(path: PathBuf) -> Self {
+        let records = load_json(&path);
+        let this = Self {
+            path: Some(path),
+            ..Self::default()
+        };
+        this.lock().records = records;
+        this
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Inner> {
+        self.inner.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn save(&self, inner: &Inner) {
+        if let Some(ref p) = self.path {
+            if let Err(e) = save_json(p, &inner.records) {
+                eprintln!("Warning: 保存维护记录失败: {}", e);
+            }
+        }
+    }
+
+    fn status(&self, idle: bool) -> MaintenanceStatus {
+        let inner = self.lock();
+        let tasks = MaintenanceTask::ALL
+            .iter()
+            .map(|&task| {
+                let record = inner.records.get(&task).cloned().unwrap_or_default();
+                TaskStatus {
+                    task,
+                    interval_secs: task.interval_ms() / 1000,
+                    next_due: next_due(task, Some(&record)),
+                    record,
+                    running: inner.running == Some(task),
+                    pending: inner.pending.contains(&task),
+                }
+            })
+            .collect();
+        MaintenanceStatus {
+            idle,
+            last_busy: inner.last_busy,
+            tasks,
+        }
+    }
+
+    fn request(&self, tasks: &[MaintenanceTask]) {
+        let mut inner = self.lock();
+        for &task in tasks {
+            if !inner.pending.contains(&task) {
+                inner.pending.push(task);
+            }
+        }
+        drop(inner);
+        self.wake.notify_one();
+    }
+
+    fn defer(&self, tasks: &[MaintenanceTask], until: Option<u64>) {
+        let mut inner = self.lock();
+        for &task in tasks {
+            inner.pending.retain(|t| *t != task);
+            inner.records.entry(task).or_default().deferred_until = until;
+        }
+        self.save(&inner);
+    }
+}
+
+/// 当前是否忙碌：有任务在运行，或任一 bridge 会话有在途请求
+async fn busy(app: &AppHandle) -> bool {
+    if app.state::<JobRegistry>().running().is_some() {
+        return true;
+    }
+    for (_, state) in sessions::all(app) {
+        let guard = state.lock().await;
+        if guard.active_streams > 0 || guard.dispatcher.as_ref().is_some_and(|d| d.in_flight() > 0)
+        {
+            return true;
+        }
+    }
+    false
+}
+
+/// 记录忙碌并判断是否已空闲足够久
+async fn observe_idle(app: &AppHandle) -> bool {
+    let now = now_ms();
+    let busy = busy(app).await;
+    let maintenance = app.state::<Maintenance>();
+    let mut inner = maintenance.lock();
+    if busy {
+        inner.last_busy = now;
+    }
+    !busy && now.saturating_sub(inner.last_busy) >= IDLE_AFTER_MS
+}
+
+/// 运行一项维护并记录结果
+async fn run_task(app: &AppHandle, task: MaintenanceTask) {
+    let maintenance = app.state::<Maintenance>();
+    let _running = maintenance.run_lock.lock().await;
+    maintenance.lock().running = Some(task);
+    let started = now_ms();
+    let result = match task {
+        MaintenanceTask::CacheCleanup => clean_cache(app).await,
+        MaintenanceTask::IntegrityScan => {
+            let summary = background_integrity_scan(app).await;
+            serde_json::to_value(summary).map_err(|e| e.to_string())
+        }
+        MaintenanceTask::IndexCompaction => app
+            .state::<SearchIndex>()
+            .compact()
+            .and_then(|r| serde_json::to_value(r).map_err(|e| e.to_string())),
+        MaintenanceTask::LogRotation => rotate_now(app)
+            .await
+            .and_then(|r| serde_json::to_value(r).map_err(|e| e.to_string())),
+    };
+    let mut inner = maintenance.lock();
+    inner.running = None;
+    inner.pending.retain(|t| *t != task);
+    let record = inner.records.entry(task).or_default();
+    record.last_run = Some(started);
+    record.last_duration_ms = Some(now_ms().saturating_sub(started));
+    match result {
+        Ok(v) => {
+            record.last_result = Some(v);
+            record.last_error = None;
+        }
+        Err(e) => {
+            eprintln!("Warning: 维护任务 {:?} 失败: {}", task, e);
+            record.last_error = Some(e);
+        }
+    }
+    maintenance.save(&inner);
+}
+
+/// 后台调度：定期检查空闲状态，空闲时每轮运行一项到期或手动触发的维护，
+/// 运行前再次确认空闲，任务开始后不打断
+pub fn spawn_maintenance(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            let maintenance = app.state::<Maintenance>();
+            let _ = tokio::time::timeout(TICK, maintenance.wake.notified()).await;
+            if !observe_idle(&app).await {
+                continue;
+            }
+            let now = now_ms();
+            let next = {
+                let inner = maintenance.lock();
+                inner.pending.first().copied().or_else(|| {
+                    MaintenanceTask::ALL
+                        .into_iter()
+                        .find(|&t| inner.due(t, now))
+                })
+            };
+            if let Some(task) = next {
+                run_task(&app, task).await;
+                // 接着检查是否还有到期的维护，仍需空闲才会运行
+                maintenance.wake.notify_one();
+            }
+        }
+    });
+}
+
+struct CacheFile {
+    path: PathBuf,
+    size: u64,
+    modified_ms: u64,
+}
+
+fn cache_files(dir: &Path, out: &mut Vec<CacheFile>) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    for e in entries.flatten() {
+        let Ok(meta) = e.metadata() else {
+            continue;
+        };
+        if meta.is_dir() {
+            cache_files(&e.path(), out);
+            continue;
+        }
+        let modified_ms = meta
+            .modified()
+            .ok()
+            .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+            .map_or(0, |d| d.as_millis() as u64);
+        out.push(CacheFile {
+            path: e.path(),
+            size: meta.len(),
+            modified_ms,
+        });
+    }
+}
+
+/// 缓存目录中过期的文件全部删除，之后仍超出总大小上限时从最旧的开始删除
+fn prune_cache(dir: &Path) -> Value {
+    let mut files = Vec::new();
+    cache_files(dir, &mut files);
+    files.sort_by_key(|f| f.modified_ms);
+    let now = now_ms();
+    let mut total: u64 = files.iter().map(|f| f.size).sum();
+    let (mut removed, mut freed, mut errors) = (0usize, 0u64, Vec::new());
+    for f in &files {
+        let expired = now.saturating_sub(f.modified_ms) > CACHE_MAX_AGE_MS;
+        if !expired && total <= CACHE_MAX_BYTES {
+            continue;
+        }
+        match std::fs::remove_file(&f.path) {
+            Ok(()) => {
+                removed += 1;
+                freed += f.size;
+                total -= f.size;
+            }
+            Err(e) => errors.push(format!("{}: {}", f.path.display(), e)),
+        }
+    }
+    serde_json::json!({
+        "removed": removed,
+        "freed_bytes": freed,
+        "remaining_bytes": total,
+        "errors": errors,
+    })
+}
+
+async fn clean_cache(app: &AppHandle) -> Result<Value, String> {
+    let dir = app.path().app_cache_dir().map_err(|e| e.to_string())?;
+    tauri::async_runtime::spawn_blocking(move || prune_cache(&dir))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+fn selected(task: Option<MaintenanceTask>) -> Vec<MaintenanceTask> {
+    task.map_or_else(|| MaintenanceTask::ALL.to_vec(), |t| vec![t])
+}
+
+/// 各项维护的上次运行结果、下次到期时间，以及当前是否空闲
+#[tauri::command]
+pub async fn maintenance_status(app: AppHandle) -> Result<MaintenanceStatus, String> {
+    guarded("maintenance_status", async move {
+        let idle = observe_idle(&app).await;
+        Ok(app.state::<Maintenance>().status(idle))
+    })
+    .await
+}
+
+/// 手动触发维护（不指定 `task` 时为全部）。空闲时立即运行并等待完成；
+/// 有任务运行时排入待办、等空闲后运行，`force` 时不等待空闲
+#[tauri::command]
+pub async fn maintenance_run(
+    app: AppHandle,
+    task: Option<MaintenanceTask>,
+    force: Option<bool>,
+) -> Result<MaintenanceStatus, String> {
+    guarded("maintenance_run", async move {
+        app.state::<CommandPolicy>()
+            .ensure_writable("执行维护任务")?;
+        let tasks = selected(task);
+        if !force.unwrap_or(false) && busy(&app).await {
+            app.state::<Maintenance>().request(&tasks);
+        } else {
+            for task in tasks {
+                run_task(&app, task).await;
+            }
+        }
+        maintenance_status(app).await
+    })
+    .await
+}
+
+/// 推迟维护 `minutes` 分钟（不指定 `task` 时为全部），同时撤销已手动触发的待办；传 0 取消推迟
+#[tauri::command]
+pub async fn maintenance_defer(
+    app: AppHandle,
+    task: Option<MaintenanceTask>,
+    minutes: u64,
+) -> Result<MaintenanceStatus, String> {
+    guarded("maintenance_defer", async move {
+        if minutes > MAX_DEFER_MINUTES {
+            return Err(format!("最多推迟 {} 分钟", MAX_DEFER_MINUTES));
+        }
+        let until = (minutes > 0).then(|| now_ms() + minutes * 60 * 1000);
+        app.state::<Maintenance>().defer(&selected(task), until);
+        maintenance_status(app).await
+    })
+    .await
+}
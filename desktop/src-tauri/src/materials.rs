@@ -0,0 +1,473 @@
+use crate::bridge::{send_request, BridgeState};
+use crate::comsol::detect_installs;
+use crate::panics::guarded;
+use crate::store::{memory_db, now_ms, open_db};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashSet};
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+const DEFAULT_LIMIT: usize = 20;
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Material {
+    pub name: String,
+    /// 所属材料库，如 Built-in、AC/DC
+    #[serde(default)]
+    pub library: Option<String>,
+    #[serde(default)]
+    pub source: Option<String>,
+    /// 属性名 → 表达式（含单位），如 "k" → "130[W/(m*K)]"
+    #[serde(default)]
+    pub properties: BTreeMap<String, String>,
+}
+
+#[derive(Clone, Default)]
+struct CacheData {
+    comsol_version: Option<String>,
+    fetched_at: Option<u64>,
+    items: Vec<Material>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct MaterialMatch {
+    pub score: u32,
+    #[serde(flatten)]
+    pub material: Material,
+}
+
+/// materials：材料本体，name_lower 建索引供前缀查询；
+/// materials_fts：名称与材料库名的 trigram 全文索引，供子串查询（rowid 与 materials.id 一致）
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS meta (key TEXT PRIMARY KEY, value TEXT NOT NULL);
+    CREATE TABLE IF NOT EXISTS materials (
+        id INTEGER PRIMARY KEY,
+        name TEXT NOT NULL,
+        name_lower TEXT NOT NULL,
+        library TEXT,
+        source TEXT,
+        properties TEXT NOT NULL
+    );
+    CREATE INDEX IF NOT EXISTS materials_name_lower ON materials(name_lower);
+    CREATE VIRTUAL TABLE IF NOT EXISTS materials_fts
+        USING fts5(name, library, tokenize = 'trigram');
+";
+
+/// trigram 索引只能匹配至少 3 个字符的子串
+const TRIGRAM: usize = 3;
+
+/// 材料库缓存：首次查询时从 bridge 拉取并写入 SQLite（`materials_cache.sqlite3`），
+/// COMSOL 版本变化后失效，之后的自动补全按索引在本地查询
+pub struct MaterialCache {
+    db: Mutex<Connection>,
+    /// 同一时刻只拉取一次，连续按键不会触发多次 bridge 往返
+    fetching: tokio::sync::Mutex<()>,
+}
+
+impl Default for MaterialCache {
+    fn default() -> Self {
+        Self::with_db(memory_db(SCHEMA))
+    }
+}
+
+fn row_material(row: &rusqlite::Row) -> rusqlite::Result<(i64, Material)> {
+    let properties: String = row.get(4)?;
+    let material = Material {
+        name: row.get(1)?,
+        library: row.get(2)?,
+        source: row.get(3)?,
+        properties: serde_json::from_str(&properties).unwrap_or_default(),
+    };
+    Ok((row.get(0)?, material))
+}
+
+const COLUMNS: &str = "m.id, m.name, m.library, m.source, m.properties";
+
+/// 子序列匹配的最高得分；前缀、子串命中都高于它
+const SUBSEQUENCE_MAX: u32 = 200;
+
+/// FTS5 查询串：整体作为一个短语，内部的双引号按规则加倍
+fn fts_phrase(query: &str) -> String {
+    format!("\"{}\"", query.replace('"', "\"\""))
+}
+
+impl MaterialCache {
+    pub fn load(path: PathBuf) -> Self {
+        // 旧版本把缓存整个存成 JSON，已不再读取
+        let _ = std::fs::remove_file(path.with_file_name("materials_cache.json"));
+        Self::with_db(open_db(&path, SCHEMA))
+    }
+
+    fn with_db(conn: Connection) -> Self {
+        Self {
+            db: Mutex::new(conn),
+            fetching: tokio::sync::Mutex::default(),
+        }
+    }
+
+    fn db(&self) -> std::sync::MutexGuard<'_, Connection> {
+        self.db.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn meta(conn: &Connection, key: &str) -> Option<String> {
+        conn.query_row("SELECT value FROM meta WHERE key = ?1", [key], |r| r.get(0))
+            .optional()
+            .ok()
+            .flatten()
+    }
+
+    fn is_fresh(&self, version: &Option<String>) -> bool {
+        let conn = self.db();
+        Self::meta(&conn, "fetched_at").is_some() && Self::meta(&conn, "comsol_version") == *version
+    }
+
+    fn len(&self) -> usize {
+        self.db()
+            .query_row("SELECT count(*) FROM materials", [], |r| r.get::<_, i64>(0))
+            .map_or(0, |n| n as usize)
+    }
+
+    /// 整体替换缓存内容；写入失败时保留原内容
+    fn replace(&self, data: CacheData) {
+        let mut conn = self.db();
+        let result = (|| -> rusqlite::Result<()> {
+            let tx = conn.transaction()?;
+            tx.execute_batch(
+                "DELETE FROM materials; DELETE FROM materials_fts; DELETE FROM meta;",
+            )?;
+            {
+                let mut insert = tx.prepare(
+                    "INSERT INTO materials (id, name, name_lower, library, source, properties)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                )?;
+                let mut index = tx.prepare(
+                    "INSERT INTO materials_fts (rowid, name, library) VALUES (?1, ?2, ?3)",
+                )?;
+                for (id, m) in data.items.iter().enumerate() {
+                    let properties = serde_json::to_string(&m.properties).unwrap_or_default();
+                    insert.execute(params![
+                        id as i64,
+                        m.name,
+                        m.name.to_lowercase(),
+                        m.library,
+                        m.source,
+                        properties
+                    ])?;
+                    index.execute(params![id as i64, m.name, m.library])?;
+                }
+                let mut meta = tx.prepare("INSERT INTO meta (key, value) VALUES (?1, ?2)")?;
+                if let Some(ref version) = data.comsol_version {
+                    meta.execute(["comsol_version", version])?;
+                }
+                if let Some(at) = data.fetched_at {
+                    meta.execute(["fetched_at", &at.to_string()])?;
+                }
+            }
+            tx.commit()
+        })();
+        if let Err(e) = result {
+            eprintln!("Warning: 保存材料缓存失败: {}", e);
+        }
+    }
+
+    /// 按索引查出可能匹配的材料：名称前缀走 name_lower 索引，
+    /// 名称或材料库名的子串走 trigram 全文索引（查询不足 3 个字符时只查前缀）
+    fn indexed(&self, query: &str, limit: usize) -> rusqlite::Result<Vec<(i64, Material)>> {
+        let conn = self.db();
+        if query.is_empty() {
+            let sql = format!(
+                "SELECT {} FROM materials m ORDER BY length(m.name), m.name LIMIT ?1",
+                COLUMNS
+            );
+            let mut stmt = conn.prepare(&sql)?;
+            let rows = stmt.query_map([limit as i64], row_material)?;
+            return rows.collect();
+        }
+        let prefix = format!(
+            "SELECT {} FROM materials m
+             WHERE m.name_lower >= ?1 AND m.name_lower < ?1 || char(1114111)",
+            COLUMNS
+        );
+        if query.chars().count() < TRIGRAM {
+            let mut stmt = conn.prepare(&prefix)?;
+            let rows = stmt.query_map([query], row_material)?;
+            return rows.collect();
+        }
+        let sql = format!(
+            "{} UNION SELECT {} FROM materials_fts f JOIN materials m ON m.id = f.rowid
+             WHERE materials_fts MATCH ?2",
+            prefix, COLUMNS
+        );
+        let mut stmt = conn.prepare(&sql)?;
+        let rows = stmt.query_map(params![query, fts_phrase(query)], row_material)?;
+        rows.collect()
+    }
+
+    fn all(&self) -> rusqlite::Result<Vec<(i64, Material)>> {
+        let conn = self.db();
+        let mut stmt = conn.prepare(&format!("SELECT {} FROM materials m", COLUMNS))?;
+        let rows = stmt.query_map([], row_material)?;
+        rows.collect()
+    }
+
+    fn search(&self, query: &str, limit: usize) -> Vec<MaterialMatch> {
+        let query = query.trim().to_lowercase();
+        let score = |m: &Material| {
+            if query.is_empty() {
+                return 1;
+            }
+            let lib = m.library.as_deref().unwrap_or_default();
+            // 名称命中优先于材料库名命中
+            fuzzy_score(&m.name, &query).max(fuzzy_score(lib, &query) / 2)
+        };
+        let warn = |e: rusqlite::Error| {
+            eprintln!("Warning: 查询材料缓存失败: {}", e);
+            Vec::new()
+        };
+        let mut seen = HashSet::new();
+        let mut out: Vec<MaterialMatch> = Vec::new();
+        let mut add = |(id, material): (i64, Material), out: &mut Vec<MaterialMatch>| {
+            let score = score(&material);
+            if score > 0 && seen.insert(id) {
+                out.push(MaterialMatch { score, material });
+            }
+        };
+        for row in self.indexed(&query, limit).unwrap_or_else(warn) {
+            add(row, &mut out);
+        }
+        // 索引查不到只按子序列命中的材料；它们排在前缀、子串命中之后，不足 limit 条时才逐条匹配
+        let strong = out.iter().filter(|m| m.score > SUBSEQUENCE_MAX).count();
+        if !query.is_empty() && strong < limit {
+            for row in self.all().unwrap_or_else(warn) {
+                add(row, &mut out);
+            }
+        }
+        out.sort_by(|a, b| {
+            b.score
+                .cmp(&a.score)
+                .then_with(|| a.material.name.len().cmp(&b.material.name.len()))
+                .then_with(|| a.material.name.cmp(&b.material.name))
+        });
+        out.truncate(limit);
+        out
+    }
+}
+
+/// 模糊匹配打分（越大越好，0 表示不匹配）：完全相等 > 前缀 > 词首 > 子串 > 按序子序列
+fn fuzzy_score(candidate: &str, query: &str) -> u32 {
+    let c = candidate.to_lowercase();
+    if c == query {
+        return 1000;
+    }
+    if c.starts_with(query) {
+        return 800;
+    }
+    if let Some(pos) = c.find(query) {
+        let word_start = c[..pos].ends_with([' ', '-', '_', '(', '/']);
+        return if word_start { 600 } else { 400 } - (pos as u32).min(100);
+    }
+    // 子序列：字符按顺序出现，间隔越小分越高
+    let mut gaps = 0u32;
+    let mut last: Option<usize> = None;
+    let mut chars = c.char_indices();
+    for q in query.chars() {
+        let Some((i, _)) = chars.by_ref().find(|(_, ch)| *ch == q) else {
+            return 0;
+        };
+        if let Some(l) = last {
+            gaps += (i - l - 1) as u32;
+        }
+        last = Some(i);
+    }
+    200u32.saturating_sub(gaps * 5).max(1)
+}
+
+fn parse_materials(resp: &Value) -> Vec<Material> {
+    resp["materials"]
+        .as_array()
+        .map(|items| {
+            items
+                .iter()
+                .filter_map(|v| match v {
+                    Value::String(name) => Some(Material {
+                        name: name.clone(),
+                        ..Material::default()
+                    }),
+                    _ => serde_json::from_value(v.clone()).ok(),
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// 当前默认使用的 COMSOL 版本（最新安装）
+async fn current_version() -> Option<String> {
+    tauri::async_runtime::spawn_blocking(detect_installs)
+        .await
+        .ok()?
+        .into_iter()
+        .next()
+        .map(|i| i.version)
+}
+
+async fn ensure_cache(
+    state: &BridgeState,
+    cache: &MaterialCache,
+    force: bool,
+) -> Result<(), String> {
+    let version = current_version().await;
+    if !force && cache.is_fresh(&version) {
+        return Ok(());
+    }
+    let _guard = cache.fetching.lock().await;
+    // 等锁期间可能已被其他请求刷新
+    if !force && cache.is_fresh(&version) {
+        return Ok(());
+    }
+    let resp = send_request(
+        state,
+        "materials_list",
+        serde_json::json!({ "comsol_version": version }),
+    )
+    .await?;
+    if resp["ok"].as_bool() != Some(true) {
+        return Err(resp["message"]
+            .as_str()
+            .unwrap_or("bridge 获取材料库失败")
+            .to_string());
+    }
+    cache.replace(CacheData {
+        comsol_version: resp["comsol_version"]
+            .as_str()
+            .map(String::from)
+            .or(version),
+        fetched_at: Some(now_ms()),
+        items: parse_materials(&resp),
+    });
+    Ok(())
+}
+
+/// 按名称模糊搜索材料；缓存缺失或 COMSOL 版本变化时先从 bridge 拉取
+#[tauri::command]
+pub async fn materials_search(
+    state: tauri::State<'_, BridgeState>,
+    cache: tauri::State<'_, MaterialCache>,
+    query: String,
+    limit: Option<usize>,
+) -> Result<Vec<MaterialMatch>, String> {
+    guarded("materials_search", async move {
+        ensure_cache(state.inner(), cache.inner(), false).await?;
+        Ok(cache.search(&query, limit.unwrap_or(DEFAULT_LIMIT)))
+    })
+    .await
+}
+
+/// 强制重新拉取材料库，返回材料数
+#[tauri::command]
+pub async fn materials_refresh(
+    state: tauri::State<'_, BridgeState>,
+    cache: tauri::State<'_, MaterialCache>,
+) -> Result<usize, String> {
+    guarded("materials_refresh", async move {
+        ensure_cache(state.inner(), cache.inner(), true).await?;
+        Ok(cache.len())
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn material(name: &str, library: &str) -> Material {
+        Material {
+            name: name.to_string(),
+            library: Some(library.to_string()),
+            properties: BTreeMap::from([("k".to_string(), "1[W/(m*K)]".to_string())]),
+            ..Material::default()
+        }
+    }
+
+    fn cache(items: Vec<Material>) -> MaterialCache {
+        let cache = MaterialCache::default();
+        cache.replace(CacheData {
+            comsol_version: Some("6.2".to_string()),
+            fetched_at: Some(1),
+            items,
+        });
+        cache
+    }
+
+    fn names(matches: &[MaterialMatch]) -> Vec<&str> {
+        matches.iter().map(|m| m.material.name.as_str()).collect()
+    }
+
+    #[test]
+    fn search_ranks_prefix_substring_and_subsequence() {
+        let cache = cache(vec![
+            material("Structural steel", "Built-in"),
+            material("Steel AISI 4340", "Built-in"),
+            material("Stainless Steel", "Built-in"),
+            material("Aluminum", "Built-in"),
+            material("Copper", "AC/DC"),
+        ]);
+        assert_eq!(
+            names(&cache.search("steel", 10)),
+            ["Steel AISI 4340", "Stainless Steel", "Structural steel"]
+        );
+        // 不足 3 个字符只走前缀索引，再由子序列匹配补足；同分时短名在前，再按名称排序
+        assert_eq!(
+            names(&cache.search("st", 10)),
+            ["Stainless Steel", "Steel AISI 4340", "Structural steel"]
+        );
+        assert_eq!(names(&cache.search("alm", 10)), ["Aluminum"]);
+        // 材料库名的命中排在名称命中之后
+        assert_eq!(names(&cache.search("ac/dc", 10)), ["Copper"]);
+        assert_eq!(cache.search("", 2).len(), 2);
+        assert_eq!(cache.search("steel", 1).len(), 1);
+        assert!(cache.search("zzz", 10).is_empty());
+    }
+
+    #[test]
+    fn search_handles_fts_syntax_in_query() {
+        let cache = cache(vec![material("Air \"dry\" (NASA)", "Built-in")]);
+        assert_eq!(
+            names(&cache.search("\"dry\" (", 10)),
+            ["Air \"dry\" (NASA)"]
+        );
+        assert_eq!(names(&cache.search("nasa) OR", 10)), Vec::<&str>::new());
+    }
+
+    #[test]
+    fn replace_keeps_properties_and_freshness() {
+        let cache = cache(vec![material("Copper", "AC/DC")]);
+        assert!(cache.is_fresh(&Some("6.2".to_string())));
+        assert!(!cache.is_fresh(&Some("6.3".to_string())));
+        let found = cache.search("copper", 1);
+        assert_eq!(found[0].score, 1000);
+        assert_eq!(found[0].material.properties["k"], "1[W/(m*K)]");
+        assert_eq!(found[0].material.library.as_deref(), Some("AC/DC"));
+
+        cache.replace(CacheData::default());
+        assert_eq!(cache.len(), 0);
+        assert!(!cache.is_fresh(&None));
+    }
+
+    #[test]
+    fn cache_persists_across_loads() {
+        let dir =
+            std::env::temp_dir().join(format!("mph-materials-{}-{}", std::process::id(), now_ms()));
+        let path = dir.join("materials_cache.sqlite3");
+        MaterialCache::load(path.clone()).replace(CacheData {
+            comsol_version: Some("6.2".to_string()),
+            fetched_at: Some(1),
+            items: vec![material("Copper", "AC/DC")],
+        });
+        let reloaded = MaterialCache::load(path);
+        assert!(reloaded.is_fresh(&Some("6.2".to_string())));
+        assert_eq!(names(&reloaded.search("cop", 5)), ["Copper"]);
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
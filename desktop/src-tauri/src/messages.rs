@@ -0,0 +1,132 @@
+/// 界面文案目录：后端直接展示给用户的文字（系统对话框标题、文件类型名等）按语言集中在这里
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Locale {
+    ZhCn,
+    EnUs,
+}
+
+impl Locale {
+    /// 显式指定优先，否则取系统语言环境变量；无法判断时用中文
+    pub fn resolve(requested: Option<&str>) -> Self {
+        let from_env = || {
+            ["LC_ALL", "LC_MESSAGES", "LANG"]
+                .iter()
+                .find_map(|k| std::env::var(k).ok().filter(|v| !v.is_empty()))
+        };
+        match requested.map(String::from).or_else(from_env) {
+            Some(tag) if tag.to_ascii_lowercase().starts_with("en") => Locale::EnUs,
+            _ => Locale::ZhCn,
+        }
+    }
+}
+
+pub struct FilterText {
+    pub name: &'static str,
+    pub extensions: &'static [&'static str],
+}
+
+pub struct DialogText {
+    pub title: &'static str,
+    pub filters: &'static [FilterText],
+}
+
+const MODEL_EXTS: &[&str] = &["mph"];
+const IMAGE_EXTS: &[&str] = &["png", "jpg", "jpeg", "svg"];
+const TABLE_EXTS: &[&str] = &["csv", "txt", "dat"];
+const REPORT_EXTS: &[&str] = &["html", "pdf", "md"];
+const SCRIPT_EXTS: &[&str] = &["py", "java", "m"];
+
+/// 各用途对话框的标题与文件类型；未收录的用途返回 None，由调用方自行提供
+pub fn dialog_text(locale: Locale, purpose: &str) -> Option<DialogText> {
+    use Locale::*;
+    let (title, filters): (&'static str, &'static [FilterText]) = match (purpose, locale) {
+        ("open_model", ZhCn) => (
+            "打开模型",
+            &[FilterText {
+                name: "COMSOL 模型",
+                extensions: MODEL_EXTS,
+            }],
+        ),
+        ("open_model", EnUs) => (
+            "Open Model",
+            &[FilterText {
+                name: "COMSOL Model",
+                extensions: MODEL_EXTS,
+            }],
+        ),
+        ("save_model", ZhCn) => (
+            "保存模型",
+            &[FilterText {
+                name: "COMSOL 模型",
+                extensions: MODEL_EXTS,
+            }],
+        ),
+        ("save_model", EnUs) => (
+            "Save Model",
+            &[FilterText {
+                name: "COMSOL Model",
+                extensions: MODEL_EXTS,
+            }],
+        ),
+        ("export_image", ZhCn) => (
+            "导出图片",
+            &[FilterText {
+                name: "图片",
+                extensions: IMAGE_EXTS,
+            }],
+        ),
+        ("export_image", EnUs) => (
+            "Export Image",
+            &[FilterText {
+                name: "Images",
+                extensions: IMAGE_EXTS,
+            }],
+        ),
+        ("export_table", ZhCn) => (
+            "导出数据表",
+            &[FilterText {
+                name: "数据表",
+                extensions: TABLE_EXTS,
+            }],
+        ),
+        ("export_table", EnUs) => (
+            "Export Table",
+            &[FilterText {
+                name: "Tables",
+                extensions: TABLE_EXTS,
+            }],
+        ),
+        ("save_report", ZhCn) => (
+            "保存报告",
+            &[FilterText {
+                name: "报告",
+                extensions: REPORT_EXTS,
+            }],
+        ),
+        ("save_report", EnUs) => (
+            "Save Report",
+            &[FilterText {
+                name: "Reports",
+                extensions: REPORT_EXTS,
+            }],
+        ),
+        ("open_script", ZhCn) => (
+            "打开脚本",
+            &[FilterText {
+                name: "脚本",
+                extensions: SCRIPT_EXTS,
+            }],
+        ),
+        ("open_script", EnUs) => (
+            "Open Script",
+            &[FilterText {
+                name: "Scripts",
+                extensions: SCRIPT_EXTS,
+            }],
+        ),
+        ("artifact_export", ZhCn) => ("导出副本", &[]),
+        ("artifact_export", EnUs) => ("Export a Copy", &[]),
+        _ => return None,
+    };
+    Some(DialogText { title, filters })
+}
@@ -0,0 +1,466 @@
+use crate::artifacts::TrackedArtifact;
+use crate::jobs::{redact_payload, JobRecord};
+use crate::mphserver::MphServerConfig;
+use crate::notifications::Notification;
+use crate::panics::guarded;
+use crate::policy::PolicyFile;
+use crate::recent::RecentModel;
+use crate::sandbox::SandboxConfig;
+use crate::storage::StorageConfig;
+use crate::store::{load_json, now_ms, save_json};
+use crate::viewers::ViewerConfig;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// 每个目录下记录各数据文件 schema 版本的文件
+const VERSIONS_FILE: &str = "schema_versions.json";
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Scope {
+    Data,
+    Config,
+}
+
+/// 受版本管理的数据文件
+struct Tracked {
+    scope: Scope,
+    file: &'static str,
+    version: u32,
+    /// 迁移完成后确认能被对应结构解析
+    validate: fn(&Value) -> Result<(), String>,
+}
+
+/// 把 `file` 从 `from` 版本升级到 `from + 1`；返回写入报告的说明
+struct Migration {
+    file: &'static str,
+    from: u32,
+    description: &'static str,
+    apply: fn(&mut Value) -> Result<String, String>,
+}
+
+const TRACKED: &[Tracked] = &[
+    Tracked {
+        scope: Scope::Data,
+        file: "jobs.json",
+        version: 1,
+        validate: |v| parses::<Vec<JobRecord>>(&v["jobs"]),
+    },
+    Tracked {
+        scope: Scope::Data,
+        file: "artifacts.json",
+        version: 1,
+        validate: parses::<Vec<TrackedArtifact>>,
+    },
+    Tracked {
+        scope: Scope::Data,
+        file: "recent_models.json",
+        version: 1,
+        validate: parses::<Vec<RecentModel>>,
+    },
+    Tracked {
+        scope: Scope::Data,
+        file: "notifications.json",
+        version: 1,
+        validate: |v| parses::<Vec<Notification>>(&v["items"]),
+    },
+    Tracked {
+        scope: Scope::Data,
+        file: "storage.json",
+        version: 1,
+        validate: parses::<StorageConfig>,
+    },
+    Tracked {
+        scope: Scope::Data,
+        file: "mphserver.json",
+        version: 1,
+        validate: parses::<MphServerConfig>,
+    },
+    Tracked {
+        scope: Scope::Data,
+        file: "viewers.json",
+        version: 1,
+        validate: parses::<ViewerConfig>,
+    },
+    Tracked {
+        scope: Scope::Config,
+        file: "policy.json",
+        version: 1,
+        validate: parses::<PolicyFile>,
+    },
+    Tracked {
+        scope: Scope::Config,
+        file: "sandbox.json",
+        version: 1,
+        validate: parses::<SandboxConfig>,
+    },
+];
+
+/// 按 (file, from) 顺序执行；没有登记步骤的版本跳跃视为仅更新版本号
+const MIGRATIONS: &[Migration] = &[
+    Migration {
+        file: "jobs.json",
+        from: 0,
+        description: "任务历史改为 {jobs: [...]}，丢弃无法解析的记录并清除敏感字段",
+        apply: migrate_jobs_v1,
+    },
+    Migration {
+        file: "artifacts.json",
+        from: 0,
+        description: "丢弃无法解析的产物记录，避免整张登记表被重置",
+        apply: |v| keep_parsable::<TrackedArtifact>(v),
+    },
+    Migration {
+        file: "recent_models.json",
+        from: 0,
+        description: "丢弃无法解析的最近模型条目",
+        apply: |v| keep_parsable::<RecentModel>(v),
+    },
+];
+
+#[derive(Default, Serialize, Deserialize)]
+struct SchemaVersions {
+    #[serde(default)]
+    app_version: Option<String>,
+    #[serde(default)]
+    files: BTreeMap<String, u32>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct MigrationStep {
+    pub file: String,
+    pub from: u32,
+    pub to: u32,
+    pub description: String,
+    pub ok: bool,
+    pub message: Option<String>,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct MigrationReport {
+    pub app_version: String,
+    pub previous_app_version: Option<String>,
+    pub ran_at: u64,
+    /// 迁移前的原始文件备份位置
+    pub backup_dir: Option<String>,
+    pub steps: Vec<MigrationStep>,
+}
+
+fn parses<T: DeserializeOwned>(v: &Value) -> Result<(), String> {
+    serde_json::from_value::<T>(v.clone())
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+}
+
+/// 数组中只保留能解析为 `T` 的条目
+fn keep_parsable<T: DeserializeOwned>(v: &mut Value) -> Result<String, String> {
+    let items = v.as_array_mut().ok_or("不是数组")?;
+    let before = items.len();
+    items.retain(|item| parses::<T>(item).is_ok());
+    Ok(format!(
+        "保留 {} 条，丢弃 {} 条",
+        items.len(),
+        before - items.len()
+    ))
+}
+
+fn migrate_jobs_v1(v: &mut Value) -> Result<String, String> {
+    if v.is_array() {
+        *v = serde_json::json!({ "jobs": v.take() });
+    }
+    let jobs = v
+        .get_mut("jobs")
+        .ok_or("缺少 jobs 字段")?
+        .as_array_mut()
+        .ok_or("jobs 不是数组")?;
+    for job in jobs.iter_mut() {
+        if let Some(payload) = job.get_mut("payload") {
+            *payload = redact_payload(payload);
+        }
+    }
+    keep_parsable::<JobRecord>(&mut v["jobs"])
+}
+
+struct Runner {
+    backup_dir: PathBuf,
+    backed_up: bool,
+    report: MigrationReport,
+}
+
+impl Runner {
+    fn backup(&mut self, scope: Scope, path: &Path) -> Result<(), String> {
+        let sub = match scope {
+            Scope::Data => "data",
+            Scope::Config => "config",
+        };
+        let dir = self.backup_dir.join(sub);
+        std::fs::create_dir_all(&dir).map_err(|e| format!("创建备份目录失败: {}", e))?;
+        std::fs::copy(path, dir.join(path.file_name().unwrap_or_default()))
+            .map_err(|e| format!("备份 {} 失败: {}", path.display(), e))?;
+        self.backed_up = true;
+        Ok(())
+    }
+
+    fn step(
+        &mut self,
+        file: &str,
+        from: u32,
+        to: u32,
+        description: &str,
+        result: Result<String, String>,
+    ) {
+        let ok = result.is_ok();
+        let message = match result {
+            Ok(m) if m.is_empty() => None,
+            Ok(m) => Some(m),
+            Err(e) => Some(e),
+        };
+        if !ok {
+            eprintln!(
+                "Warning: 迁移 {} v{} → v{} 失败: {}",
+                file,
+                from,
+                to,
+                message.as_deref().unwrap_or_default()
+            );
+        }
+        self.report.steps.push(MigrationStep {
+            file: file.to_string(),
+            from,
+            to,
+            description: description.to_string(),
+            ok,
+            message,
+        });
+    }
+
+    /// 迁移单个文件，返回迁移后的版本号
+    fn migrate_file(&mut self, tracked: &Tracked, path: &Path, from: u32) -> u32 {
+        let target = tracked.version;
+        if from > target {
+            self.step(
+                tracked.file,
+                from,
+                target,
+                "由更新版本的应用写入",
+                Err("保持原样，未做降级".to_string()),
+            );
+            return from;
+        }
+        let text = match std::fs::read_to_string(path) {
+            Ok(t) => t,
+            Err(e) => {
+                self.step(tracked.file, from, target, "读取文件", Err(e.to_string()));
+                return from;
+            }
+        };
+        let mut value: Value = match serde_json::from_str(&text) {
+            Ok(v) => v,
+            Err(e) => {
+                let backed = self.backup(tracked.scope, path);
+                let note = match backed {
+                    Ok(()) => format!("无法解析，原文件已备份: {}", e),
+                    Err(b) => format!("无法解析: {}；{}", e, b),
+                };
+                self.step(tracked.file, from, target, "解析 JSON", Err(note));
+                return from;
+            }
+        };
+        if from == target {
+            if let Err(e) = (tracked.validate)(&value) {
+                let _ = self.backup(tracked.scope, path);
+                self.step(tracked.file, from, target, "校验数据结构", Err(e));
+            }
+            return from;
+        }
+
+        if let Err(e) = self.backup(tracked.scope, path) {
+            self.step(tracked.file, from, target, "备份原文件", Err(e));
+            return from;
+        }
+        for version in from..target {
+            let Some(m) = MIGRATIONS
+                .iter()
+                .find(|m| m.file == tracked.file && m.from == version)
+            else {
+                continue;
+            };
+            let result = (m.apply)(&mut value);
+            let failed = result.is_err();
+            self.step(tracked.file, version, version + 1, m.description, result);
+            if failed {
+                // 已备份的原文件保持不动，下次启动会重试
+                return from;
+            }
+        }
+        if let Err(e) = (tracked.validate)(&value) {
+            self.step(tracked.file, from, target, "校验迁移结果", Err(e));
+            return from;
+        }
+        if let Err(e) = save_json(path, &value) {
+            self.step(tracked.file, from, target, "写回文件", Err(e));
+            return from;
+        }
+        target
+    }
+
+    fn run_dir(&mut self, scope: Scope, dir: &Path) {
+        let versions_path = dir.join(VERSIONS_FILE);
+        let mut versions: SchemaVersions = load_json(&versions_path);
+        if scope == Scope::Data {
+            self.report.previous_app_version = versions.app_version.clone();
+        }
+        for tracked in TRACKED.iter().filter(|t| t.scope == scope) {
+            let path = dir.join(tracked.file);
+            let version = if path.is_file() {
+                // 未记录版本的已有文件来自引入迁移框架之前的版本
+                let from = versions.files.get(tracked.file).copied().unwrap_or(0);
+                self.migrate_file(tracked, &path, from)
+            } else {
+                tracked.version
+            };
+            versions.files.insert(tracked.file.to_string(), version);
+        }
+        versions.app_version = Some(self.report.app_version.clone());
+        if let Err(e) = save_json(&versions_path, &versions) {
+            eprintln!("Warning: 保存 schema 版本失败: {}", e);
+        }
+    }
+}
+
+/// 启动时、各状态加载之前运行：备份旧数据，按顺序执行迁移，并保存本次报告
+pub fn run(data_dir: &Option<PathBuf>, config_dir: &Option<PathBuf>) -> MigrationReport {
+    let Some(data) = data_dir else {
+        return MigrationReport::default();
+    };
+    let ran_at = now_ms();
+    let migrations_dir = data.join("migrations");
+    let mut runner = Runner {
+        backup_dir: migrations_dir.join(ran_at.to_string()),
+        backed_up: false,
+        report: MigrationReport {
+            app_version: env!("CARGO_PKG_VERSION").to_string(),
+            ran_at,
+            ..MigrationReport::default()
+        },
+    };
+    runner.run_dir(Scope::Data, data);
+    if let Some(config) = config_dir {
+        runner.run_dir(Scope::Config, config);
+    }
+    if runner.backed_up {
+        runner.report.backup_dir = Some(runner.backup_dir.to_string_lossy().into_owned());
+    }
+    let report = runner.report;
+    if !report.steps.is_empty() {
+        if let Err(e) = save_json(&migrations_dir.join("last_report.json"), &report) {
+            eprintln!("Warning: 保存迁移报告失败: {}", e);
+        }
+    }
+    report
+}
+
+/// 本次启动的迁移报告；没有执行任何迁移时 steps 为空
+#[tauri::command]
+pub async fn migration_report(
+    report: tauri::State<'_, MigrationReport>,
+) -> Result<MigrationReport, String> {
+    guarded(
+        "migration_report",
+        async move { Ok(report.inner().clone()) },
+    )
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "mph-migrations-{}-{}-{}",
+            name,
+            std::process::id(),
+            now_ms()
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn job(id: &str) -> Value {
+        json!({
+            "id": id,
+            "cmd": "run",
+            "status": "succeeded",
+            "created_at": 1,
+            "payload": { "input": "x", "api_key": "sk-1", "job_dir": "/w" },
+        })
+    }
+
+    #[test]
+    fn jobs_v1_wraps_array_and_redacts() {
+        let mut v = json!([job("a"), { "id": "broken" }, job("b")]);
+        let note = migrate_jobs_v1(&mut v).unwrap();
+        assert_eq!(note, "保留 2 条，丢弃 1 条");
+        let jobs = v["jobs"].as_array().unwrap();
+        assert_eq!(jobs.len(), 2);
+        assert_eq!(jobs[0]["payload"], json!({ "input": "x" }));
+        assert!(migrate_jobs_v1(&mut json!({ "other": [] })).is_err());
+    }
+
+    #[test]
+    fn run_migrates_legacy_files_once() {
+        let data = temp_dir("data");
+        std::fs::write(
+            data.join("jobs.json"),
+            json!([job("a"), { "id": "broken" }]).to_string(),
+        )
+        .unwrap();
+        let report = run(&Some(data.clone()), &None);
+        assert_eq!(report.steps.len(), 1);
+        assert!(report.steps[0].ok);
+        assert_eq!((report.steps[0].from, report.steps[0].to), (0, 1));
+        let backup = PathBuf::from(report.backup_dir.unwrap());
+        assert!(backup.join("data").join("jobs.json").is_file());
+
+        let migrated: Value = load_json(&data.join("jobs.json"));
+        assert_eq!(migrated["jobs"].as_array().unwrap().len(), 1);
+        let versions: SchemaVersions = load_json(&data.join(VERSIONS_FILE));
+        assert_eq!(versions.files.get("jobs.json"), Some(&1));
+        assert_eq!(versions.files.get("artifacts.json"), Some(&1));
+
+        // 已是当前版本：不再迁移
+        assert!(run(&Some(data.clone()), &None).steps.is_empty());
+        let _ = std::fs::remove_dir_all(data);
+    }
+
+    #[test]
+    fn newer_and_unreadable_files_are_left_alone() {
+        let data = temp_dir("newer");
+        let newer = json!({ "jobs": [], "future": true }).to_string();
+        std::fs::write(data.join("jobs.json"), &newer).unwrap();
+        std::fs::write(data.join("artifacts.json"), "{not json").unwrap();
+        save_json(
+            &data.join(VERSIONS_FILE),
+            &json!({ "files": { "jobs.json": 9 } }),
+        )
+        .unwrap();
+
+        let report = run(&Some(data.clone()), &None);
+        assert!(report.steps.iter().all(|s| !s.ok));
+        assert_eq!(
+            std::fs::read_to_string(data.join("jobs.json")).unwrap(),
+            newer
+        );
+        let versions: SchemaVersions = load_json(&data.join(VERSIONS_FILE));
+        assert_eq!(versions.files.get("jobs.json"), Some(&9));
+        assert_eq!(versions.files.get("artifacts.json"), Some(&0));
+        // 无法解析的原文件已备份，下次启动仍会重试
+        let backup = PathBuf::from(report.backup_dir.unwrap());
+        assert!(backup.join("data").join("artifacts.json").is_file());
+        let _ = std::fs::remove_dir_all(data);
+    }
+}
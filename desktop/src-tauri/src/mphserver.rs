@@ -0,0 +1,446 @@
+use crate::comsol::{find_install, ComsolInstall};
+use crate::jobs::project_of;
+use crate::panics::guarded;
+use crate::policy::CommandPolicy;
+use crate::process::{contain, isolate_group, reap, ChildHandle, ExitReceiver};
+use crate::services::ServiceRegistry;
+use crate::store::{load_json, now_ms, save_json};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::net::{SocketAddr, TcpStream, ToSocketAddrs};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager};
+use tokio::process::{Child, Command};
+
+const HOST: &str = "127.0.0.1";
+const SERVICE: &str = "mphserver";
+/// mphserver 首次启动需加载许可证与 JVM，给足时间
+const READY_TIMEOUT: Duration = Duration::from_secs(90);
+const MAX_RESTARTS: u32 = 5;
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(3);
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct MphServerConfig {
+    /// 启用后应用启动时自动拉起 mphserver
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default)]
+    pub version: Option<String>,
+    #[serde(default)]
+    pub port: Option<u16>,
+    /// 连接已有 COMSOL server 的配置，按项目保存；键为空串表示默认
+    #[serde(default)]
+    pub connections: HashMap<String, ServerProfile>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ServerProfile {
+    pub host: String,
+    pub port: u16,
+    pub connected_at: u64,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct MphServerStatus {
+    /// stopped / starting / running / restarting / failed
+    pub state: String,
+    pub host: String,
+    pub port: Option<u16>,
+    pub version: Option<String>,
+    pub pid: Option<u32>,
+    pub restarts: u32,
+    pub error: Option<String>,
+}
+
+impl Default for MphServerStatus {
+    fn default() -> Self {
+        Self {
+            state: "stopped".to_string(),
+            host: HOST.to_string(),
+            port: None,
+            version: None,
+            pid: None,
+            restarts: 0,
+            error: None,
+        }
+    }
+}
+
+/// 由 Rust 层托管的 comsolmphserver：选端口、等待就绪、异常退出自动重启、应用退出时关闭。
+/// bridge 通过请求中的 `comsol_server` 字段获知 host/port，可独立于 Python 进程重启。
+#[derive(Default)]
+pub struct MphServer {
+    path: Option<PathBuf>,
+    config: Mutex<MphServerConfig>,
+    status: Mutex<MphServerStatus>,
+    child: Mutex<Option<ChildHandle>>,
+    /// 每次 start/stop 递增，旧的监护任务据此退出
+    generation: AtomicU64,
+}
+
+impl MphServer {
+    pub fn load(path: PathBuf) -> Self {
+        let config: MphServerConfig = load_json(&path);
+        Self {
+            path: Some(path),
+            config: Mutex::new(config),
+            ..Self::default()
+        }
+    }
+
+    pub fn config(&self) -> MphServerConfig {
+        self.config
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    fn save_config(&self, config: MphServerConfig) {
+        if let Some(ref p) = self.path {
+            if let Err(e) = save_json(p, &config) {
+                eprintln!("Warning: 保存 mphserver 配置失败: {}", e);
+            }
+        }
+        *self.config.lock().unwrap_or_else(|e| e.into_inner()) = config;
+    }
+
+    pub fn status(&self) -> MphServerStatus {
+        self.status
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    fn set_status(&self, app: &AppHandle, f: impl FnOnce(&mut MphServerStatus)) {
+        let snapshot = {
+            let mut st = self.status.lock().unwrap_or_else(|e| e.into_inner());
+            f(&mut st);
+            st.clone()
+        };
+        let services = app.state::<ServiceRegistry>();
+        match (snapshot.state.as_str(), snapshot.port) {
+            ("running", Some(port)) => services.publish(SERVICE, &snapshot.host, port),
+            _ => services.withdraw(SERVICE),
+        }
+        let _ = app.emit("mphserver-status", snapshot);
+    }
+
+    /// 运行中时返回 (host, port)
+    pub fn endpoint(&self) -> Option<(String, u16)> {
+        let st = self.status.lock().unwrap_or_else(|e| e.into_inner());
+        match (st.state.as_str(), st.port) {
+            ("running", Some(p)) => Some((st.host.clone(), p)),
+            _ => None,
+        }
+    }
+
+    /// 停止监护并结束进程；应用退出时同步调用
+    pub fn shutdown(&self) {
+        self.generation.fetch_add(1, Ordering::SeqCst);
+        if let Some(child) = self.child.lock().unwrap_or_else(|e| e.into_inner()).take() {
+            child.kill_now();
+        }
+        let mut st = self.status.lock().unwrap_or_else(|e| e.into_inner());
+        st.pid = None;
+        st.state = "stopped".to_string();
+    }
+}
+
+fn spawn_server(install: &ComsolInstall, port: u16) -> Result<Child, String> {
+    let exe = install
+        .server_executable
+        .as_ref()
+        .ok_or_else(|| format!("COMSOL {} 未安装 mphserver", install.version))?;
+    let mut builder = Command::new(exe);
+    builder
+        .args(["-port", &port.to_string(), "-multi", "on", "-login", "auto"])
+        .stdin(std::process::Stdio::null())
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .kill_on_drop(true);
+    isolate_group(&mut builder);
+    #[cfg(target_os = "windows")]
+    {
+        const CREATE_NO_WINDOW: u32 = 0x08000000;
+        builder.creation_flags(CREATE_NO_WINDOW);
+    }
+    builder
+        .spawn()
+        .map_err(|e| format!("启动 mphserver 失败: {}", e))
+}
+
+/// 轮询端口直到可连接；进程提前退出或超时则失败
+async fn wait_ready(exited: &mut ExitReceiver, port: u16) -> Result<(), String> {
+    let addr: SocketAddr = format!("{}:{}", HOST, port)
+        .parse()
+        .map_err(|e| format!("{}", e))?;
+    let deadline = Instant::now() + READY_TIMEOUT;
+    loop {
+        if let Ok(status) = exited.try_recv() {
+            return Err(match status {
+                Ok(s) => format!("mphserver 启动后退出: {}", s),
+                Err(e) => format!("mphserver 启动后退出: {}", e),
+            });
+        }
+        let ok = tauri::async_runtime::spawn_blocking(move || {
+            TcpStream::connect_timeout(&addr, Duration::from_millis(500)).is_ok()
+        })
+        .await
+        .unwrap_or(false);
+        if ok {
+            return Ok(());
+        }
+        if Instant::now() >= deadline {
+            return Err("等待 mphserver 就绪超时".to_string());
+        }
+        tokio::time::sleep(Duration::from_millis(500)).await;
+    }
+}
+
+/// 监护循环：启动 → 等待就绪 → 等待退出；非主动停止的退出按退避重启
+async fn supervise(app: AppHandle, install: ComsolInstall, port: u16, generation: u64) {
+    let server = app.state::<MphServer>();
+    let current = || server.generation.load(Ordering::SeqCst) == generation;
+    let mut restarts = 0u32;
+    loop {
+        let child = match spawn_server(&install, port) {
+            Ok(c) => c,
+            Err(e) => {
+                server.set_status(&app, |s| {
+                    s.state = "failed".to_string();
+                    s.error = Some(e);
+                });
+                return;
+            }
+        };
+        let tree = contain(&child);
+        let (child, mut exited) = reap(child, tree);
+        let pid = Some(child.pid);
+        *server.child.lock().unwrap_or_else(|e| e.into_inner()) = Some(child.clone());
+        server.set_status(&app, |s| {
+            s.state = "starting".to_string();
+            s.port = Some(port);
+            s.version = Some(install.version.clone());
+            s.pid = pid;
+            s.restarts = restarts;
+            s.error = None;
+        });
+
+        let ready = wait_ready(&mut exited, port).await;
+        if !current() {
+            return;
+        }
+        let error = match ready {
+            Ok(()) => {
+                server.set_status(&app, |s| s.state = "running".to_string());
+                let exit = exited.await;
+                if !current() {
+                    return;
+                }
+                match exit {
+                    Ok(Ok(status)) => format!("mphserver 意外退出: {}", status),
+                    Ok(Err(e)) => format!("mphserver 状态未知: {}", e),
+                    Err(_) => "mphserver 回收任务异常结束".to_string(),
+                }
+            }
+            Err(e) => {
+                child.kill();
+                e
+            }
+        };
+
+        restarts += 1;
+        if restarts > MAX_RESTARTS {
+            server.set_status(&app, |s| {
+                s.state = "failed".to_string();
+                s.pid = None;
+                s.error = Some(format!("{}（已重启 {} 次，放弃）", error, MAX_RESTARTS));
+            });
+            return;
+        }
+        server.set_status(&app, |s| {
+            s.state = "restarting".to_string();
+            s.pid = None;
+            s.restarts = restarts;
+            s.error = Some(error);
+        });
+        tokio::time::sleep(Duration::from_secs(2u64.pow(restarts.min(5)))).await;
+        if !current() {
+            return;
+        }
+    }
+}
+
+/// 按当前配置启动（或重启）托管的 mphserver
+pub fn start_managed(app: &AppHandle) -> Result<(), String> {
+    let server = app.state::<MphServer>();
+    let config = server.config();
+    let install = find_install(config.version.as_deref())?;
+    server.shutdown();
+    let port = app
+        .state::<ServiceRegistry>()
+        .allocate(SERVICE, HOST, config.port)?;
+    let generation = server.generation.fetch_add(1, Ordering::SeqCst) + 1;
+    tauri::async_runtime::spawn(supervise(app.clone(), install, port, generation));
+    Ok(())
+}
+
+/// 请求未自带 `comsol_server` 时，把要连接的 server 交给 bridge：
+/// 优先该项目保存的连接，其次默认连接，最后是托管的 mphserver
+pub fn attach_endpoint(app: &AppHandle, payload: &mut Value) {
+    let server = app.state::<MphServer>();
+    let project = project_of(payload).unwrap_or_default();
+    let endpoint = {
+        let config = server.config.lock().unwrap_or_else(|e| e.into_inner());
+        config
+            .connections
+            .get(&project)
+            .or_else(|| config.connections.get(""))
+            .map(|p| (p.host.clone(), p.port))
+    }
+    .or_else(|| server.endpoint());
+    let Some((host, port)) = endpoint else {
+        return;
+    };
+    if let Some(obj) = payload.as_object_mut() {
+        obj.entry("comsol_server")
+            .or_insert_with(|| serde_json::json!({ "host": host, "port": port }));
+    }
+}
+
+/// 在超时内尝试建立 TCP 连接
+async fn check_reachable(host: &str, port: u16) -> Result<(), String> {
+    let target = format!("{}:{}", host, port);
+    tauri::async_runtime::spawn_blocking(move || {
+        let addrs = target
+            .to_socket_addrs()
+            .map_err(|e| format!("无法解析 {}: {}", target, e))?;
+        let mut last_err = format!("无法解析 {}", target);
+        for addr in addrs {
+            match TcpStream::connect_timeout(&addr, CONNECT_TIMEOUT) {
+                Ok(_) => return Ok(()),
+                Err(e) => last_err = format!("无法连接 {}: {}", target, e),
+            }
+        }
+        Err(last_err)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+pub async fn mphserver_status(
+    server: tauri::State<'_, MphServer>,
+) -> Result<MphServerStatus, String> {
+    guarded("mphserver_status", async move { Ok(server.status()) }).await
+}
+
+#[tauri::command]
+pub async fn mphserver_start(
+    app: AppHandle,
+    server: tauri::State<'_, MphServer>,
+    policy: tauri::State<'_, CommandPolicy>,
+    version: Option<String>,
+    port: Option<u16>,
+) -> Result<(), String> {
+    guarded("mphserver_start", async move {
+        policy.ensure_writable("启动 mphserver")?;
+        let mut config = server.config();
+        config.enabled = true;
+        config.version = version;
+        config.port = port;
+        server.save_config(config);
+        start_managed(&app)
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn mphserver_stop(
+    app: AppHandle,
+    server: tauri::State<'_, MphServer>,
+) -> Result<(), String> {
+    guarded("mphserver_stop", async move {
+        let mut config = server.config();
+        config.enabled = false;
+        server.save_config(config);
+        server.shutdown();
+        server.set_status(&app, |s| {
+            *s = MphServerStatus {
+                restarts: s.restarts,
+                ..MphServerStatus::default()
+            }
+        });
+        Ok(())
+    })
+    .await
+}
+
+/// 连接已有的 COMSOL server（本机或远程）；检查可达后按项目保存连接配置
+#[tauri::command]
+pub async fn connect_comsol_server(
+    server: tauri::State<'_, MphServer>,
+    host: String,
+    port: u16,
+    project: Option<String>,
+) -> Result<ServerProfile, String> {
+    guarded("connect_comsol_server", async move {
+        let host = host.trim();
+        if host.is_empty() {
+            return Err("主机地址为空".to_string());
+        }
+        check_reachable(host, port).await?;
+        let profile = ServerProfile {
+            host: host.to_string(),
+            port,
+            connected_at: now_ms(),
+        };
+        let mut config = server.config();
+        config
+            .connections
+            .insert(project.unwrap_or_default(), profile.clone());
+        server.save_config(config);
+        Ok(profile)
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn disconnect_comsol_server(
+    server: tauri::State<'_, MphServer>,
+    project: Option<String>,
+) -> Result<(), String> {
+    guarded("disconnect_comsol_server", async move {
+        let mut config = server.config();
+        config.connections.remove(&project.unwrap_or_default());
+        server.save_config(config);
+        Ok(())
+    })
+    .await
+}
+
+/// 列出已保存的连接；`reachable` 为当前可达性
+#[tauri::command]
+pub async fn comsol_server_profiles(
+    server: tauri::State<'_, MphServer>,
+) -> Result<Vec<Value>, String> {
+    guarded("comsol_server_profiles", async move {
+        let mut out = Vec::new();
+        for (project, p) in server.config().connections {
+            let reachable = check_reachable(&p.host, p.port).await.is_ok();
+            out.push(serde_json::json!({
+                "project": if project.is_empty() { None } else { Some(project) },
+                "host": p.host,
+                "port": p.port,
+                "connected_at": p.connected_at,
+                "reachable": reachable,
+            }));
+        }
+        Ok(out)
+    })
+    .await
+}
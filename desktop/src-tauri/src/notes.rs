@@ -0,0 +1,172 @@
+use crate::panics::guarded;
+use crate::sandbox::check_path;
+use crate::store::{now_ms, save_text};
+use serde::Serialize;
+use std::cmp::Reverse;
+use std::path::{Path, PathBuf};
+
+/// 项目笔记放在项目目录下，与项目设置同在 `.mph-agent` 中，随项目一起复制与归档
+pub const NOTES_FILE: &str = ".mph-agent/notes.md";
+const HISTORY_DIR: &str = ".mph-agent/notes-history";
+const MAX_NOTES_BYTES: usize = 1024 * 1024;
+/// 每个项目保留的历史版本数
+const MAX_VERSIONS: usize = 50;
+/// 两次自动存档的最短间隔：编辑器频繁自动保存时不会每次都留下一个版本
+const VERSION_INTERVAL_MS: u64 = 5 * 60 * 1000;
+
+#[derive(Clone, Debug, Serialize)]
+pub struct NotesVersion {
+    /// 存档时间（毫秒），取历史版本时作为版本号
+    pub id: u64,
+    pub size: u64,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ProjectNotes {
+    pub project: String,
+    pub path: String,
+    /// Markdown 原文；笔记尚未创建时为空
+    pub content: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub updated_at: Option<u64>,
+    /// 取的是历史版本时为其版本号
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version: Option<u64>,
+    /// 历史版本，最新的在前
+    pub versions: Vec<NotesVersion>,
+}
+
+fn project_dir(project: &str) -> Result<PathBuf, String> {
+    let project = project.trim();
+    if project.is_empty() {
+        return Err("项目为空".to_string());
+    }
+    let dir = PathBuf::from(project);
+    if !dir.is_dir() {
+        return Err(format!("项目目录不存在: {}", project));
+    }
+    Ok(dir)
+}
+
+fn modified_ms(path: &Path) -> Option<u64> {
+    let modified = std::fs::metadata(path).ok()?.modified().ok()?;
+    let since = modified.duration_since(std::time::UNIX_EPOCH).ok()?;
+    Some(since.as_millis() as u64)
+}
+
+fn version_path(dir: &Path, id: u64) -> PathBuf {
+    dir.join(HISTORY_DIR).join(format!("{}.md", id))
+}
+
+fn versions(dir: &Path) -> Vec<NotesVersion> {
+    let Ok(entries) = std::fs::read_dir(dir.join(HISTORY_DIR)) else {
+        return Vec::new();
+    };
+    let mut out: Vec<NotesVersion> = entries
+        .flatten()
+        .filter_map(|e| {
+            let path = e.path();
+            if path.extension().is_none_or(|x| x != "md") {
+                return None;
+            }
+            let id = path.file_stem()?.to_str()?.parse().ok()?;
+            let size = e.metadata().ok()?.len();
+            Some(NotesVersion { id, size })
+        })
+        .collect();
+    out.sort_by_key(|v| Reverse(v.id));
+    out
+}
+
+/// 项目笔记的内容，供会话与复现包导出附带；没有笔记或内容为空时为 None
+pub fn read(project: &Path) -> Option<String> {
+    std::fs::read_to_string(project.join(NOTES_FILE))
+        .ok()
+        .filter(|s| !s.trim().is_empty())
+}
+
+fn load(project: &str, dir: &Path, version: Option<u64>) -> Result<ProjectNotes, String> {
+    let path = dir.join(NOTES_FILE);
+    let (content, updated_at) = match version {
+        Some(id) => {
+            let content = std::fs::read_to_string(version_path(dir, id))
+                .map_err(|_| format!("项目笔记没有版本 {}", id))?;
+            (content, Some(id))
+        }
+        None => (
+            std::fs::read_to_string(&path).unwrap_or_default(),
+            modified_ms(&path),
+        ),
+    };
+    Ok(ProjectNotes {
+        project: project.trim().to_string(),
+        path: path.to_string_lossy().into_owned(),
+        content,
+        updated_at,
+        version,
+        versions: versions(dir),
+    })
+}
+
+/// 覆盖前把原内容存为历史版本：距上一个版本不足间隔时跳过，`checkpoint` 时总是存档
+fn archive(dir: &Path, previous: &str, checkpoint: bool) -> Result<(), String> {
+    let now = now_ms();
+    let existing = versions(dir);
+    let due = existing
+        .first()
+        .is_none_or(|v| now.saturating_sub(v.id) >= VERSION_INTERVAL_MS);
+    if !checkpoint && !due {
+        return Ok(());
+    }
+    save_text(&version_path(dir, now), previous)?;
+    for old in existing.iter().skip(MAX_VERSIONS - 1) {
+        if let Err(e) = std::fs::remove_file(version_path(dir, old.id)) {
+            eprintln!("Warning: 删除旧的项目笔记版本失败: {}", e);
+        }
+    }
+    Ok(())
+}
+
+/// 读取项目笔记；指定 `version` 时取该历史版本
+#[tauri::command]
+pub async fn project_notes_get(
+    project: String,
+    version: Option<u64>,
+) -> Result<ProjectNotes, String> {
+    guarded("project_notes_get", async move {
+        let dir = project_dir(&project)?;
+        load(&project, &dir, version)
+    })
+    .await
+}
+
+/// 保存项目笔记（Markdown）。内容有变化时先把原内容存为历史版本，
+/// 供编辑器自动保存调用；手动保存或恢复旧版本前传 `checkpoint` 强制存档
+#[tauri::command]
+pub async fn project_notes_set(
+    project: String,
+    content: String,
+    checkpoint: Option<bool>,
+) -> Result<ProjectNotes, String> {
+    guarded("project_notes_set", async move {
+        if content.len() > MAX_NOTES_BYTES {
+            return Err(format!(
+                "项目笔记过大（{} 字节，上限 {}）",
+                content.len(),
+                MAX_NOTES_BYTES
+            ));
+        }
+        let dir = project_dir(&project)?;
+        let path = dir.join(NOTES_FILE);
+        check_path(&path)?;
+        let previous = std::fs::read_to_string(&path).ok();
+        if previous.as_deref() != Some(content.as_str()) {
+            if let Some(prev) = previous.filter(|p| !p.trim().is_empty()) {
+                archive(&dir, &prev, checkpoint.unwrap_or(false))?;
+            }
+            save_text(&path, &content)?;
+        }
+        load(&project, &dir, None)
+    })
+    .await
+}
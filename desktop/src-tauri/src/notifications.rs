@@ -0,0 +1,254 @@
+use crate::panics::guarded;
+use crate::store::{load_json, now_ms, save_json};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter, Manager};
+
+/// 未确认的关键事件最多发送次数（含首次）；用完后仅保留在通知中心，等前端拉取
+const MAX_DELIVERIES: u32 = 5;
+const REDELIVER_AFTER_MS: u64 = 10_000;
+const REDELIVER_CHECK_SECS: u64 = 5;
+/// 已确认通知保留的历史条数
+const MAX_ACKED_HISTORY: usize = 200;
+/// 未确认通知最多保留的条数，超出时丢弃最旧的
+const MAX_UNACKED: usize = 500;
+/// 未确认通知的保留期限（7 天），过期后不再等待确认
+const UNACKED_TTL_MS: u64 = 7 * 24 * 3600 * 1000;
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Notification {
+    pub id: u64,
+    pub event: String,
+    pub payload: Value,
+    pub created_at: u64,
+    #[serde(default)]
+    pub acked: bool,
+    #[serde(default)]
+    pub deliveries: u32,
+    #[serde(default)]
+    pub last_sent_at: u64,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+struct NotificationStore {
+    next_id: u64,
+    items: Vec<Notification>,
+}
+
+impl NotificationStore {
+    /// 丢弃过期的未确认通知，并把未确认的条数限制在 MAX_UNACKED 以内；返回是否有改动
+    fn prune(&mut self, now: u64) -> bool {
+        let before = self.items.len();
+        self.items
+            .retain(|n| n.acked || now.saturating_sub(n.created_at) < UNACKED_TTL_MS);
+        let unacked = self.items.iter().filter(|n| !n.acked).count();
+        if unacked > MAX_UNACKED {
+            // items 按创建顺序追加，靠前的最旧
+            let mut excess = unacked - MAX_UNACKED;
+            self.items.retain(|n| {
+                if !n.acked && excess > 0 {
+                    excess -= 1;
+                    false
+                } else {
+                    true
+                }
+            });
+        }
+        self.items.len() != before
+    }
+}
+
+/// 通知中心：关键事件（artifact-created、job-finished 等）落盘保存，
+/// 直到前端通过 `event_ack` 确认收到，避免 webview 重载时丢失「求解已完成」这类消息。
+#[derive(Default)]
+pub struct NotificationCenter {
+    path: Option<PathBuf>,
+    store: Mutex<NotificationStore>,
+}
+
+impl NotificationCenter {
+    pub fn load(path: PathBuf) -> Self {
+        let store = load_json(&path);
+        Self {
+            path: Some(path),
+            store: Mutex::new(store),
+        }
+    }
+
+    fn persist(&self, store: &NotificationStore) {
+        if let Some(ref p) = self.path {
+            if let Err(e) = save_json(p, store) {
+                eprintln!("Warning: 保存通知中心失败: {}", e);
+            }
+        }
+    }
+
+    /// 以至少一次语义发送关键事件，返回 delivery_id
+    pub fn deliver(&self, app: &AppHandle, event: &str, payload: Value) -> u64 {
+        let mut store = self.store.lock().unwrap_or_else(|e| e.into_inner());
+        store.next_id += 1;
+        let mut n = Notification {
+            id: store.next_id,
+            event: event.to_string(),
+            payload,
+            created_at: now_ms(),
+            acked: false,
+            deliveries: 0,
+            last_sent_at: 0,
+        };
+        emit_notification(app, &mut n);
+        let id = n.id;
+        store.items.push(n);
+        store.prune(now_ms());
+        self.persist(&store);
+        id
+    }
+
+    pub fn ack(&self, id: u64) -> bool {
+        let mut store = self.store.lock().unwrap_or_else(|e| e.into_inner());
+        let Some(n) = store.items.iter_mut().find(|n| n.id == id) else {
+            return false;
+        };
+        n.acked = true;
+        let acked = store.items.iter().filter(|n| n.acked).count();
+        if acked > MAX_ACKED_HISTORY {
+            let mut excess = acked - MAX_ACKED_HISTORY;
+            store.items.retain(|n| {
+                if n.acked && excess > 0 {
+                    excess -= 1;
+                    false
+                } else {
+                    true
+                }
+            });
+        }
+        self.persist(&store);
+        true
+    }
+
+    pub fn list(&self, include_acked: bool) -> Vec<Notification> {
+        let store = self.store.lock().unwrap_or_else(|e| e.into_inner());
+        store
+            .items
+            .iter()
+            .filter(|n| include_acked || !n.acked)
+            .cloned()
+            .collect()
+    }
+
+    fn redeliver_due(&self, app: &AppHandle) {
+        let mut store = self.store.lock().unwrap_or_else(|e| e.into_inner());
+        let now = now_ms();
+        let mut changed = store.prune(now);
+        for n in store.items.iter_mut() {
+            if !n.acked
+                && n.deliveries < MAX_DELIVERIES
+                && now.saturating_sub(n.last_sent_at) >= REDELIVER_AFTER_MS
+            {
+                emit_notification(app, n);
+                changed = true;
+            }
+        }
+        if changed {
+            self.persist(&store);
+        }
+    }
+}
+
+fn emit_notification(app: &AppHandle, n: &mut Notification) {
+    let mut payload = n.payload.clone();
+    if let Some(obj) = payload.as_object_mut() {
+        obj.insert("delivery_id".into(), Value::from(n.id));
+    }
+    let _ = app.emit(&n.event, payload);
+    n.deliveries += 1;
+    n.last_sent_at = now_ms();
+}
+
+/// 后台定期重发未确认的关键事件
+pub fn spawn_redelivery(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            tokio::time::sleep(std::time::Duration::from_secs(REDELIVER_CHECK_SECS)).await;
+            app.state::<NotificationCenter>().redeliver_due(&app);
+        }
+    });
+}
+
+#[tauri::command]
+pub async fn event_ack(
+    notifications: tauri::State<'_, NotificationCenter>,
+    delivery_id: u64,
+) -> Result<bool, String> {
+    guarded(
+        "event_ack",
+        async move { Ok(notifications.ack(delivery_id)) },
+    )
+    .await
+}
+
+/// 前端（重）加载后调用，取回所有尚未确认的关键事件
+#[tauri::command]
+pub async fn events_unacked(
+    notifications: tauri::State<'_, NotificationCenter>,
+) -> Result<Vec<Notification>, String> {
+    guarded(
+        "events_unacked",
+        async move { Ok(notifications.list(false)) },
+    )
+    .await
+}
+
+#[tauri::command]
+pub async fn notifications_list(
+    notifications: tauri::State<'_, NotificationCenter>,
+) -> Result<Vec<Notification>, String> {
+    guarded(
+        "notifications_list",
+        async move { Ok(notifications.list(true)) },
+    )
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(id: u64, created_at: u64, acked: bool) -> Notification {
+        Notification {
+            id,
+            event: "job-finished".into(),
+            payload: Value::Null,
+            created_at,
+            acked,
+            deliveries: 1,
+            last_sent_at: created_at,
+        }
+    }
+
+    #[test]
+    fn prune_expires_old_unacked_only() {
+        let now = UNACKED_TTL_MS + 1_000;
+        let mut store = NotificationStore {
+            next_id: 3,
+            items: vec![item(1, 0, false), item(2, 0, true), item(3, now, false)],
+        };
+        assert!(store.prune(now));
+        let ids: Vec<u64> = store.items.iter().map(|n| n.id).collect();
+        assert_eq!(ids, vec![2, 3]);
+        assert!(!store.prune(now));
+    }
+
+    #[test]
+    fn prune_caps_unacked_dropping_oldest() {
+        let mut store = NotificationStore::default();
+        for id in 1..=(MAX_UNACKED as u64 + 3) {
+            store.items.push(item(id, 0, false));
+        }
+        store.prune(0);
+        assert_eq!(store.items.len(), MAX_UNACKED);
+        assert_eq!(store.items[0].id, 4);
+    }
+}
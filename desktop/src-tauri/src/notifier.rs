@@ -0,0 +1,545 @@
+use crate::jobs::{JobRecord, JobRegistry, JobStatus};
+use crate::panics::guarded;
+use crate::proxy;
+use crate::store::{load_json, now_ms, save_json};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager};
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+
+/// 单次投递的超时，避免外部服务卡住时堆积 curl 进程
+const SEND_TIMEOUT_SECS: &str = "30";
+
+/// 通知渠道；邮件与 webhook 都交给系统 curl 发送
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Sink {
+    /// POST 完整的 JSON 事件
+    Webhook {
+        url: String,
+        #[serde(default)]
+        headers: Vec<String>,
+    },
+    /// Slack 兼容的 incoming webhook（也适用于 Mattermost、飞书等 `{"text"}` 格式）
+    Slack { url: String },
+    Smtp {
+        /// 如 `smtps://smtp.example.com:465` 或 `smtp://host:587`（自动 STARTTLS）
+        url: String,
+        from: String,
+        to: Vec<String>,
+        #[serde(default)]
+        username: Option<String>,
+        /// 存放密码的环境变量名，密码本身不写入配置文件
+        #[serde(default)]
+        password_env: Option<String>,
+    },
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SinkConfig {
+    pub name: String,
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    #[serde(flatten)]
+    pub sink: Sink,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct NotifierConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_true")]
+    pub on_success: bool,
+    #[serde(default = "default_true")]
+    pub on_failure: bool,
+    /// 短于该时长的任务不通知，避免交互式小命令刷屏
+    #[serde(default)]
+    pub min_duration_secs: u64,
+    #[serde(default)]
+    pub sinks: Vec<SinkConfig>,
+}
+
+impl Default for NotifierConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            on_success: true,
+            on_failure: true,
+            min_duration_secs: 0,
+            sinks: Vec::new(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct SinkResult {
+    pub name: String,
+    pub ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// 任务结束后的外部通知（邮件 / webhook），用于无人值守的夜间参数扫描
+#[derive(Default)]
+pub struct Notifier {
+    path: Option<PathBuf>,
+    config: Mutex<NotifierConfig>,
+}
+
+impl Notifier {
+    pub fn load(path: PathBuf) -> Self {
+        let config: NotifierConfig = load_json(&path);
+        Self {
+            path: Some(path),
+            config: Mutex::new(config),
+        }
+    }
+
+    fn get(&self) -> NotifierConfig {
+        self.config
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    fn set(&self, config: NotifierConfig) -> Result<(), String> {
+        let mut guard = self.config.lock().unwrap_or_else(|e| e.into_inner());
+        *guard = config;
+        match self.path {
+            Some(ref p) => save_json(p, &*guard),
+            None => Ok(()),
+        }
+    }
+}
+
+fn has_line_break(value: &str) -> bool {
+    value.contains(['\r', '\n'])
+}
+
+fn check_webhook_url(name: &str, url: &str) -> Result<(), String> {
+    if url.starts_with("https://") || url.starts_with("http://") {
+        Ok(())
+    } else {
+        Err(format!("{}: webhook 地址必须是 http(s) URL", name))
+    }
+}
+
+fn validate(config: &NotifierConfig) -> Result<(), String> {
+    let mut names = std::collections::HashSet::new();
+    for s in &config.sinks {
+        if s.name.trim().is_empty() || !names.insert(s.name.trim()) {
+            return Err(format!("通知渠道名为空或重复: {}", s.name));
+        }
+        let url = match &s.sink {
+            Sink::Webhook { url, headers } => {
+                if headers.iter().any(|h| has_line_break(h)) {
+                    return Err(format!("{}: 请求头不能包含换行", s.name));
+                }
+                check_webhook_url(&s.name, url)?;
+                url
+            }
+            Sink::Slack { url } => {
+                check_webhook_url(&s.name, url)?;
+                url
+            }
+            Sink::Smtp { url, from, to, .. } => {
+                if !(url.starts_with("smtp://") || url.starts_with("smtps://")) {
+                    return Err(format!(
+                        "{}: 邮件服务器地址必须以 smtp:// 或 smtps:// 开头",
+                        s.name
+                    ));
+                }
+                if from.trim().is_empty() || to.is_empty() {
+                    return Err(format!("{}: 需要填写发件人与收件人", s.name));
+                }
+                // 发件人与收件人原样写入邮件头，换行会注入额外的头或正文
+                if has_line_break(from) || to.iter().any(|t| has_line_break(t)) {
+                    return Err(format!("{}: 发件人与收件人不能包含换行", s.name));
+                }
+                url
+            }
+        };
+        if url.chars().any(char::is_whitespace) {
+            return Err(format!("{}: 地址不能包含空白字符", s.name));
+        }
+    }
+    Ok(())
+}
+
+#[derive(Clone, Debug, Serialize)]
+struct JobSummary {
+    id: String,
+    name: String,
+    cmd: String,
+    project: Option<String>,
+    status: JobStatus,
+    duration_secs: Option<u64>,
+    message: Option<String>,
+    artifacts: Vec<String>,
+}
+
+impl JobSummary {
+    fn from_job(job: &JobRecord) -> Self {
+        let name = ["job_name", "name", "model_name"]
+            .iter()
+            .find_map(|k| job.payload.get(*k).and_then(Value::as_str))
+            .filter(|s| !s.trim().is_empty())
+            .unwrap_or(&job.cmd)
+            .to_string();
+        Self {
+            id: job.id.clone(),
+            name,
+            cmd: job.cmd.clone(),
+            project: job.project.clone(),
+            status: job.status,
+            duration_secs: job
+                .started_at
+                .zip(job.finished_at)
+                .map(|(s, f)| f.saturating_sub(s) / 1000),
+            message: job.message.clone(),
+            artifacts: job.artifacts.clone(),
+        }
+    }
+
+    fn subject(&self) -> String {
+        let verdict = match self.status {
+            JobStatus::Succeeded => "完成",
+            JobStatus::Aborted => "已中止",
+            _ => "失败",
+        };
+        format!("[COMSOL Agent] 任务{}: {}", verdict, self.name)
+    }
+
+    fn text(&self) -> String {
+        let mut lines = vec![self.subject(), format!("任务 ID: {}", self.id)];
+        if let Some(ref p) = self.project {
+            lines.push(format!("项目: {}", p));
+        }
+        if let Some(d) = self.duration_secs {
+            lines.push(format!(
+                "耗时: {}:{:02}:{:02}",
+                d / 3600,
+                d / 60 % 60,
+                d % 60
+            ));
+        }
+        if let Some(ref m) = self.message {
+            lines.push(format!("信息: {}", m));
+        }
+        if !self.artifacts.is_empty() {
+            lines.push("产物:".to_string());
+            lines.extend(self.artifacts.iter().map(|a| format!("  {}", a)));
+        }
+        lines.join("\n")
+    }
+}
+
+/// 运行 curl 并把 body 写入其标准输入
+async fn curl_stdin(args: &[String], body: &[u8]) -> Result<(), String> {
+    let mut command = Command::new("curl");
+    command
+        .args(["-fsS", "-m", SEND_TIMEOUT_SECS])
+        .args(args)
+        .envs(proxy::env_vars())
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped());
+    #[cfg(target_os = "windows")]
+    {
+        const CREATE_NO_WINDOW: u32 = 0x08000000;
+        command.creation_flags(CREATE_NO_WINDOW);
+    }
+    let mut child = command
+        .spawn()
+        .map_err(|e| format!("调用 curl 失败: {}", e))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(body).await.map_err(|e| e.to_string())?;
+    }
+    let out = child.wait_with_output().await.map_err(|e| e.to_string())?;
+    if !out.status.success() {
+        return Err(format!(
+            "发送失败: {}",
+            String::from_utf8_lossy(&out.stderr).trim()
+        ));
+    }
+    Ok(())
+}
+
+/// 仅当前用户可读的临时 curl 配置文件（`-K`），让密码与请求头不出现在进程命令行里；drop 时删除
+struct CurlConfig(PathBuf);
+
+/// 同一毫秒内并发投递的任务各用各的配置文件
+static CONFIG_SEQ: AtomicU64 = AtomicU64::new(0);
+
+impl CurlConfig {
+    fn create(options: &[(&str, &str)]) -> Result<Self, String> {
+        use std::io::Write;
+        let path = std::env::temp_dir().join(format!(
+            "mph-agent-curl-{}-{}-{}.conf",
+            std::process::id(),
+            now_ms(),
+            CONFIG_SEQ.fetch_add(1, Ordering::Relaxed)
+        ));
+        let mut open = std::fs::OpenOptions::new();
+        open.write(true).create_new(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            open.mode(0o600);
+        }
+        let mut file = open
+            .open(&path)
+            .map_err(|e| format!("创建 curl 配置失败: {}", e))?;
+        let config = Self(path);
+        let text: String = options
+            .iter()
+            .map(|(k, v)| format!("{} = {}\n", k, config_quote(v)))
+            .collect();
+        file.write_all(text.as_bytes())
+            .map_err(|e| format!("写入 curl 配置失败: {}", e))?;
+        Ok(config)
+    }
+}
+
+impl Drop for CurlConfig {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.0);
+    }
+}
+
+/// curl 配置文件中的双引号字符串
+fn config_quote(value: &str) -> String {
+    let mut out = String::with_capacity(value.len() + 2);
+    out.push('"');
+    for c in value.chars() {
+        match c {
+            '"' | '\\' => {
+                out.push('\\');
+                out.push(c);
+            }
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            _ => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+fn base64(data: &[u8]) -> String {
+    const TABLE: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let b = [
+            chunk[0],
+            *chunk.get(1).unwrap_or(&0),
+            *chunk.get(2).unwrap_or(&0),
+        ];
+        let n = (b[0] as u32) << 16 | (b[1] as u32) << 8 | b[2] as u32;
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(TABLE[(n >> (18 - 6 * i) & 63) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+/// 组装 RFC 5322 邮件；主题含中文，按 RFC 2047 编码
+fn mail_message(from: &str, to: &[String], subject: &str, text: &str) -> String {
+    format!(
+        "From: {}\r\nTo: {}\r\nSubject: =?UTF-8?B?{}?=\r\nMIME-Version: 1.0\r\nContent-Type: text/plain; charset=UTF-8\r\nContent-Transfer-Encoding: 8bit\r\n\r\n{}\r\n",
+        from,
+        to.join(", "),
+        base64(subject.as_bytes()),
+        text.replace('\n', "\r\n")
+    )
+}
+
+async fn send(sink: &Sink, event: &Value, subject: &str, text: &str) -> Result<(), String> {
+    match sink {
+        Sink::Webhook { url, headers } => {
+            let mut args = vec![
+                "-X".to_string(),
+                "POST".to_string(),
+                "-H".to_string(),
+                "Content-Type: application/json".to_string(),
+            ];
+            // 自定义请求头多为鉴权令牌，与密码一样经临时配置文件传入，不出现在进程列表里
+            let config = if headers.is_empty() {
+                None
+            } else {
+                let options: Vec<(&str, &str)> =
+                    headers.iter().map(|h| ("header", h.as_str())).collect();
+                let config = CurlConfig::create(&options)?;
+                args.extend(["-K".to_string(), config.0.to_string_lossy().into_owned()]);
+                Some(config)
+            };
+            args.extend(["--data-binary".to_string(), "@-".to_string(), url.clone()]);
+            let res = curl_stdin(&args, event.to_string().as_bytes()).await;
+            drop(config);
+            res
+        }
+        Sink::Slack { url } => {
+            let body = serde_json::json!({ "text": text });
+            let args = [
+                "-X",
+                "POST",
+                "-H",
+                "Content-Type: application/json",
+                "--data-binary",
+                "@-",
+                url,
+            ]
+            .map(String::from);
+            curl_stdin(&args, body.to_string().as_bytes()).await
+        }
+        Sink::Smtp {
+            url,
+            from,
+            to,
+            username,
+            password_env,
+        } => {
+            let mut args = vec![
+                "--url".to_string(),
+                url.clone(),
+                "--mail-from".to_string(),
+                from.clone(),
+            ];
+            if url.starts_with("smtp://") {
+                args.push("--ssl-reqd".to_string());
+            }
+            for rcpt in to {
+                args.extend(["--mail-rcpt".to_string(), rcpt.clone()]);
+            }
+            // 标准输入用于邮件正文，凭据经临时配置文件传入
+            let credentials = match username {
+                Some(user) => {
+                    let password = match password_env {
+                        Some(var) => {
+                            std::env::var(var).map_err(|_| format!("未设置环境变量 {}", var))?
+                        }
+                        None => String::new(),
+                    };
+                    let config =
+                        CurlConfig::create(&[("user", &format!("{}:{}", user, password))])?;
+                    args.extend(["-K".to_string(), config.0.to_string_lossy().into_owned()]);
+                    Some(config)
+                }
+                None => None,
+            };
+            args.extend(["-T".to_string(), "-".to_string()]);
+            let res = curl_stdin(&args, mail_message(from, to, subject, text).as_bytes()).await;
+            drop(credentials);
+            res
+        }
+    }
+}
+
+async fn send_all(sinks: &[SinkConfig], summary: &JobSummary, event: &str) -> Vec<SinkResult> {
+    let subject = summary.subject();
+    let text = summary.text();
+    let event = serde_json::json!({
+        "event": event,
+        "sent_at": now_ms(),
+        "job": summary,
+    });
+    let mut results = Vec::new();
+    for s in sinks.iter().filter(|s| s.enabled) {
+        let res = send(&s.sink, &event, &subject, &text).await;
+        if let Err(ref e) = res {
+            eprintln!("Warning: 通知渠道 {} 发送失败: {}", s.name, e);
+        }
+        results.push(SinkResult {
+            name: s.name.clone(),
+            ok: res.is_ok(),
+            error: res.err(),
+        });
+    }
+    results
+}
+
+/// 任务结束后按配置在后台发送外部通知
+pub fn notify_job_finished(app: &AppHandle, request_id: &str) {
+    let config = app.state::<Notifier>().get();
+    if !config.enabled || config.sinks.iter().all(|s| !s.enabled) {
+        return;
+    }
+    let Some(job) = app.state::<JobRegistry>().get(request_id) else {
+        return;
+    };
+    let summary = JobSummary::from_job(&job);
+    let wanted = match job.status {
+        JobStatus::Succeeded => config.on_success,
+        _ => config.on_failure,
+    };
+    if !wanted || summary.duration_secs.unwrap_or(0) < config.min_duration_secs {
+        return;
+    }
+    tauri::async_runtime::spawn(async move {
+        send_all(&config.sinks, &summary, "job-finished").await;
+    });
+}
+
+#[tauri::command]
+pub async fn notifier_get(notifier: tauri::State<'_, Notifier>) -> Result<NotifierConfig, String> {
+    guarded("notifier_get", async move { Ok(notifier.get()) }).await
+}
+
+#[tauri::command]
+pub async fn notifier_set(
+    notifier: tauri::State<'_, Notifier>,
+    config: NotifierConfig,
+) -> Result<(), String> {
+    guarded("notifier_set", async move {
+        validate(&config)?;
+        notifier.set(config)
+    })
+    .await
+}
+
+/// 向指定渠道（不指定则全部启用的渠道）发送一条测试通知
+#[tauri::command]
+pub async fn notifier_test(
+    notifier: tauri::State<'_, Notifier>,
+    sink: Option<String>,
+) -> Result<Vec<SinkResult>, String> {
+    guarded("notifier_test", async move {
+        let config = notifier.get();
+        let sinks: Vec<SinkConfig> = config
+            .sinks
+            .into_iter()
+            .filter(|s| sink.as_deref().is_none_or(|n| n == s.name))
+            .map(|s| SinkConfig { enabled: true, ..s })
+            .collect();
+        if sinks.is_empty() {
+            return Err("没有可测试的通知渠道".to_string());
+        }
+        let now = now_ms();
+        let summary = JobSummary {
+            id: "test".to_string(),
+            name: "测试通知".to_string(),
+            cmd: "notifier_test".to_string(),
+            project: None,
+            status: JobStatus::Succeeded,
+            duration_secs: Some(0),
+            message: Some(format!("通知渠道配置正确（{}）", now)),
+            artifacts: Vec::new(),
+        };
+        Ok(send_all(&sinks, &summary, "notifier-test").await)
+    })
+    .await
+}
@@ -0,0 +1,202 @@
+use crate::store::now_ms;
+use futures_util::FutureExt;
+use serde::Serialize;
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::future::Future;
+use std::panic::{AssertUnwindSafe, PanicHookInfo};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, OnceLock};
+use tauri::ipc::Invoke;
+use tauri::{AppHandle, Emitter};
+
+/// 诊断信息里保留的最近 panic 条数
+const RECENT_LIMIT: usize = 20;
+
+static PANIC_COUNT: AtomicU64 = AtomicU64::new(0);
+static ASYNC_PANIC_COUNT: AtomicU64 = AtomicU64::new(0);
+static RECENT: Mutex<VecDeque<PanicRecord>> = Mutex::new(VecDeque::new());
+static APP: OnceLock<AppHandle> = OnceLock::new();
+
+thread_local! {
+    /// 当前线程正在分发的命令，供 panic hook 标注来源
+    static CURRENT_COMMAND: RefCell<Option<String>> = const { RefCell::new(None) };
+    /// 当前线程正在轮询的异步命令（见 `guarded`），内层在后
+    static POLLING: RefCell<Vec<&'static str>> = const { RefCell::new(Vec::new()) };
+}
+
+fn panic_message(command: &str, message: &str) -> String {
+    format!("命令 {} 内部错误: {}", command, message)
+}
+
+/// 与分发期间 panic 相同的拒绝结构
+fn rejection(command: &str, message: &str) -> serde_json::Value {
+    serde_json::json!({
+        "kind": "panic",
+        "command": command,
+        "message": panic_message(command, message),
+    })
+}
+
+/// 轮询期间登记在 `POLLING` 中；panic 展开时同样会出栈
+struct Polling;
+
+impl Polling {
+    fn enter(command: &'static str) -> Self {
+        let _ = POLLING.try_with(|p| p.borrow_mut().push(command));
+        Polling
+    }
+}
+
+impl Drop for Polling {
+    fn drop(&mut self) {
+        let _ = POLLING.try_with(|p| p.borrow_mut().pop());
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct PanicRecord {
+    pub ts: u64,
+    pub command: Option<String>,
+    pub thread: String,
+    /// 发生在异步命令的 future 中（而非分发期间）
+    pub asynchronous: bool,
+    pub message: String,
+    pub location: Option<String>,
+    pub backtrace: String,
+}
+
+fn payload_message(payload: &dyn std::any::Any) -> String {
+    payload
+        .downcast_ref::<&str>()
+        .map(|s| s.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "未知 panic".to_string())
+}
+
+fn record(info: &PanicHookInfo<'_>) {
+    let count = PANIC_COUNT.fetch_add(1, Ordering::SeqCst) + 1;
+    let dispatching = CURRENT_COMMAND
+        .try_with(|c| c.borrow().clone())
+        .ok()
+        .flatten();
+    // 不在分发期间时，看当前线程是否正在轮询某个异步命令
+    let polled_command = match dispatching {
+        Some(_) => None,
+        None => POLLING
+            .try_with(|p| p.borrow().last().map(|c| c.to_string()))
+            .ok()
+            .flatten(),
+    };
+    let message = payload_message(info.payload());
+    if polled_command.is_some() {
+        ASYNC_PANIC_COUNT.fetch_add(1, Ordering::SeqCst);
+    }
+    let record = PanicRecord {
+        ts: now_ms(),
+        asynchronous: polled_command.is_some(),
+        command: dispatching.or(polled_command),
+        thread: std::thread::current()
+            .name()
+            .unwrap_or("<unnamed>")
+            .to_string(),
+        message,
+        location: info
+            .location()
+            .map(|l| format!("{}:{}:{}", l.file(), l.line(), l.column())),
+        backtrace: std::backtrace::Backtrace::force_capture().to_string(),
+    };
+    eprintln!(
+        "Warning: panic #{} (命令 {}，线程 {}) at {}: {}\n{}",
+        count,
+        record.command.as_deref().unwrap_or("-"),
+        record.thread,
+        record.location.as_deref().unwrap_or("?"),
+        record.message,
+        record.backtrace
+    );
+    if let Some(app) = APP.get() {
+        let _ = app.emit(
+            "command-panicked",
+            serde_json::json!({
+                "count": count,
+                "command": record.command,
+                "message": record.message,
+                "location": record.location,
+            }),
+        );
+    }
+    let mut recent = RECENT.lock().unwrap_or_else(|e| e.into_inner());
+    if recent.len() >= RECENT_LIMIT {
+        recent.pop_front();
+    }
+    recent.push_back(record);
+}
+
+/// 安装 panic hook：记录回溯与计数并通知前端。状态锁均容忍中毒，bridge 与应用继续运行
+pub fn install_hook(app: AppHandle) {
+    let _ = APP.set(app);
+    std::panic::set_hook(Box::new(record));
+}
+
+/// 包装命令分发：分发期间（参数解析、同步命令）的 panic 不再向上穿透事件循环，
+/// 而是以 `{ kind: "panic", command, message }` 拒绝该次调用；异步命令体由 `guarded` 包装
+pub fn catch_panics<F>(handler: F) -> impl Fn(Invoke) -> bool + Send + Sync + 'static
+where
+    F: Fn(Invoke) -> bool + Send + Sync + 'static,
+{
+    move |invoke: Invoke| {
+        let command = invoke.message.command().to_string();
+        let resolver = invoke.resolver.clone();
+        CURRENT_COMMAND.with(|c| *c.borrow_mut() = Some(command.clone()));
+        let result = std::panic::catch_unwind(AssertUnwindSafe(|| handler(invoke)));
+        CURRENT_COMMAND.with(|c| *c.borrow_mut() = None);
+        match result {
+            Ok(handled) => handled,
+            Err(payload) => {
+                resolver.reject(rejection(&command, &payload_message(payload.as_ref())));
+                true
+            }
+        }
+    }
+}
+
+/// 异步命令的 future 由 tauri 自行 spawn，panic 后调用方永远等不到结果；
+/// 命令体包在这里，panic 时返回错误而不是让调用方一直等待
+pub async fn guarded<T, E, F>(command: &'static str, fut: F) -> Result<T, E>
+where
+    F: Future<Output = Result<T, E>>,
+    E: From<String>,
+{
+    let mut fut = Box::pin(fut);
+    let polled = std::future::poll_fn(move |cx| {
+        let _polling = Polling::enter(command);
+        fut.as_mut().poll(cx)
+    });
+    match AssertUnwindSafe(polled).catch_unwind().await {
+        Ok(result) => result,
+        Err(payload) => Err(E::from(panic_message(
+            command,
+            &payload_message(payload.as_ref()),
+        ))),
+    }
+}
+
+/// 本次运行以来捕获的 panic 次数（其中异步命令中的次数）与最近记录
+#[tauri::command]
+pub async fn diagnostics_panics() -> Result<serde_json::Value, String> {
+    guarded("diagnostics_panics", async move {
+        let recent: Vec<PanicRecord> = RECENT
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .iter()
+            .cloned()
+            .collect();
+        Ok(serde_json::json!({
+            "count": PANIC_COUNT.load(Ordering::SeqCst),
+            "async_count": ASYNC_PANIC_COUNT.load(Ordering::SeqCst),
+            "recent": recent,
+        }))
+    })
+    .await
+}
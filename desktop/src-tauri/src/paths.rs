@@ -0,0 +1,87 @@
+use crate::bridge::{find_bundled_bridge_exe, find_project_root, BridgeState};
+use crate::panics::guarded;
+use crate::profiles::ActiveProfile;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Manager};
+
+/// 与 Python 端 `Settings.model_output_dir` 对应的环境变量 / .env 键
+const OUTPUT_DIR_KEY: &str = "MODEL_OUTPUT_DIR";
+
+#[derive(Clone, Debug, Serialize)]
+pub struct AppPaths {
+    pub profile: String,
+    /// 开发模式下的仓库根（含 pyproject.toml）；安装包运行时通常为 None
+    pub project_root: Option<String>,
+    /// 当前档案的数据/配置目录
+    pub data_dir: Option<String>,
+    pub config_dir: Option<String>,
+    pub log_dir: Option<String>,
+    pub cache_dir: Option<String>,
+    pub temp_dir: Option<String>,
+    pub resource_dir: Option<String>,
+    pub bridge_exe: Option<String>,
+    pub java_home: Option<String>,
+    /// 模型默认输出目录，与 bridge 的解析顺序一致：环境变量 > .env > `<项目根>/models`
+    pub output_dir: Option<String>,
+}
+
+fn display(p: impl AsRef<Path>) -> String {
+    p.as_ref().to_string_lossy().into_owned()
+}
+
+/// 读取 .env 中的 `KEY=value`（忽略注释与引号，键不区分大小写）
+fn dotenv_value(file: &Path, key: &str) -> Option<String> {
+    let text = std::fs::read_to_string(file).ok()?;
+    text.lines().find_map(|line| {
+        let line = line.trim();
+        let (k, v) = line.split_once('=')?;
+        let k = k.trim().trim_start_matches("export ").trim();
+        if line.starts_with('#') || !k.eq_ignore_ascii_case(key) {
+            return None;
+        }
+        let v = v.trim().trim_matches(|c| c == '"' || c == '\'');
+        (!v.is_empty()).then(|| v.to_string())
+    })
+}
+
+pub(crate) fn output_dir(root: &Option<PathBuf>) -> Option<PathBuf> {
+    if let Some(v) = std::env::var(OUTPUT_DIR_KEY)
+        .ok()
+        .filter(|v| !v.trim().is_empty())
+    {
+        return Some(PathBuf::from(v.trim()));
+    }
+    let root = root.as_ref()?;
+    dotenv_value(&root.join(".env"), OUTPUT_DIR_KEY)
+        .map(PathBuf::from)
+        .or_else(|| Some(root.join("models")))
+}
+
+/// 前端与技术支持使用的实际路径，避免在前端硬编码目录约定
+#[tauri::command]
+pub async fn get_app_paths(
+    app: AppHandle,
+    state: tauri::State<'_, BridgeState>,
+    profile: tauri::State<'_, ActiveProfile>,
+) -> Result<AppPaths, String> {
+    guarded("get_app_paths", async move {
+        let path = app.path();
+        let root = find_project_root();
+        let java_home = state.inner().lock().await.bundled_java_home.clone();
+        Ok(AppPaths {
+            profile: profile.name.clone(),
+            project_root: root.as_ref().map(display),
+            data_dir: profile.data_dir.as_ref().map(display),
+            config_dir: profile.config_dir.as_ref().map(display),
+            log_dir: path.app_log_dir().ok().map(display),
+            cache_dir: path.app_cache_dir().ok().map(display),
+            temp_dir: path.temp_dir().ok().map(display),
+            resource_dir: path.resource_dir().ok().map(display),
+            bridge_exe: find_bundled_bridge_exe().map(display),
+            java_home: java_home.map(display),
+            output_dir: output_dir(&root).map(display),
+        })
+    })
+    .await
+}
@@ -0,0 +1,520 @@
+use crate::bridge::open_path;
+use crate::debug_console::{self, Direction};
+use crate::dispatcher::Dispatcher;
+use crate::panics::guarded;
+use crate::store::{load_json, save_json};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+#[cfg(target_os = "windows")]
+use std::os::windows::process::CommandExt;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+use tokio::sync::oneshot;
+
+/// 等待用户答复的时间，超时按拒绝处理（不记住）
+const PROMPT_TIMEOUT: Duration = Duration::from_secs(120);
+const SHELL_TIMEOUT: Duration = Duration::from_secs(120);
+const MAX_READ_BYTES: u64 = 4 * 1024 * 1024;
+const MAX_SHELL_OUTPUT: usize = 64 * 1024;
+/// 请求不属于任何项目时，记住的决定存在此键下
+const GLOBAL_SCOPE: &str = "*";
+
+static NEXT_PROMPT_ID: AtomicU64 = AtomicU64::new(1);
+
+/// bridge 请求宿主执行的操作（`host_request` 事件的 data）
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum HostAction {
+    OpenUrl {
+        url: String,
+    },
+    ReadFile {
+        path: String,
+    },
+    RunShell {
+        command: String,
+        #[serde(default)]
+        cwd: Option<String>,
+    },
+}
+
+impl HostAction {
+    /// 把路径换成真实路径（解析符号链接与 `..`），使规则与执行的对象一致；
+    /// 未指定 cwd 的命令在本进程的当前目录中运行，规则也按该目录记
+    fn canonical(self) -> Result<Self, String> {
+        let real = |p: &Path| {
+            std::fs::canonicalize(p)
+                .map(|p| p.display().to_string())
+                .map_err(|e| format!("无法解析路径 {}: {}", p.display(), e))
+        };
+        Ok(match self {
+            Self::OpenUrl { url } => Self::OpenUrl { url },
+            Self::ReadFile { path } => Self::ReadFile {
+                path: real(Path::new(&path))?,
+            },
+            Self::RunShell { command, cwd } => {
+                let dir = match cwd.filter(|d| !d.trim().is_empty()) {
+                    Some(dir) => PathBuf::from(dir),
+                    None => std::env::current_dir().map_err(|e| e.to_string())?,
+                };
+                Self::RunShell {
+                    command,
+                    cwd: Some(real(&dir)?),
+                }
+            }
+        })
+    }
+
+    /// 记住决定时使用的规则：网址按站点、文件按所在目录、命令按工作目录与完整命令行。
+    /// 路径应已经过 [`HostAction::canonical`]
+    fn rule(&self) -> String {
+        match self {
+            Self::OpenUrl { url } => {
+                let (scheme, rest) = url.split_once("://").unwrap_or(("", url));
+                let host = rest.split(['/', '?', '#']).next().unwrap_or("");
+                format!(
+                    "open_url:{}://{}",
+                    scheme.to_lowercase(),
+                    host.to_lowercase()
+                )
+            }
+            Self::ReadFile { path } => {
+                let dir = Path::new(path).parent().unwrap_or(Path::new(path));
+                format!("read_file:{}", dir.display())
+            }
+            // cwd 加引号，避免含空格的目录与命令的边界混淆
+            Self::RunShell { command, cwd } => format!(
+                "run_shell:{} {}",
+                Value::from(cwd.as_deref().unwrap_or("")),
+                command.trim()
+            ),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Decision {
+    Allow,
+    Deny,
+}
+
+/// 项目 → 规则 → 记住的决定
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct PermissionRules {
+    #[serde(default)]
+    pub projects: BTreeMap<String, BTreeMap<String, Decision>>,
+}
+
+struct Answer {
+    allow: bool,
+    remember: bool,
+}
+
+/// bridge 请求宿主执行操作（打开网址、读取工作区外的文件、运行 shell 命令）时的授权：
+/// 先查按项目记住的决定，没有则通过 `permission-request` 事件询问用户
+#[derive(Default)]
+pub struct HostPermissions {
+    path: Option<PathBuf>,
+    rules: Mutex<PermissionRules>,
+    pending: Mutex<HashMap<String, oneshot::Sender<Answer>>>,
+}
+
+fn scope_of(project: Option<&str>) -> String {
+    project.unwrap_or(GLOBAL_SCOPE).to_string()
+}
+
+impl HostPermissions {
+    pub fn load(path: PathBuf) -> Self {
+        let rules: PermissionRules = load_json(&path);
+        Self {
+            path: Some(path),
+            rules: Mutex::new(rules),
+            pending: Mutex::default(),
+        }
+    }
+
+    fn rules(&self) -> PermissionRules {
+        self.rules.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    fn update(&self, f: impl FnOnce(&mut PermissionRules)) -> Result<(), String> {
+        let mut rules = self.rules.lock().unwrap_or_else(|e| e.into_inner());
+        let mut next = rules.clone();
+        f(&mut next);
+        next.projects.retain(|_, r| !r.is_empty());
+        if let Some(ref p) = self.path {
+            save_json(p, &next)?;
+        }
+        *rules = next;
+        Ok(())
+    }
+
+    fn remembered(&self, project: Option<&str>, rule: &str) -> Option<Decision> {
+        let rules = self.rules.lock().unwrap_or_else(|e| e.into_inner());
+        rules.projects.get(&scope_of(project))?.get(rule).copied()
+    }
+
+    /// 询问用户；超时或窗口关闭时按拒绝处理
+    async fn prompt(&self, app: &AppHandle, mut detail: Value) -> Answer {
+        let prompt_id = format!("perm-{}", NEXT_PROMPT_ID.fetch_add(1, Ordering::Relaxed));
+        let (tx, rx) = oneshot::channel();
+        self.pending
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(prompt_id.clone(), tx);
+        detail["prompt_id"] = Value::String(prompt_id.clone());
+        detail["timeout_secs"] = Value::from(PROMPT_TIMEOUT.as_secs());
+        let _ = app.emit("permission-request", detail);
+        let answer = tokio::time::timeout(PROMPT_TIMEOUT, rx).await;
+        self.pending
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(&prompt_id);
+        match answer {
+            Ok(Ok(answer)) => answer,
+            _ => {
+                let _ = app.emit(
+                    "permission-expired",
+                    serde_json::json!({ "prompt_id": prompt_id }),
+                );
+                Answer {
+                    allow: false,
+                    remember: false,
+                }
+            }
+        }
+    }
+}
+
+/// 位于请求的工作目录之内的文件不需要询问
+fn inside_workspace(path: &str, workspace: Option<&str>) -> bool {
+    let (Some(workspace), Ok(path)) = (workspace, std::fs::canonicalize(path)) else {
+        return false;
+    };
+    std::fs::canonicalize(workspace).is_ok_and(|w| path.starts_with(w))
+}
+
+fn truncate(bytes: &[u8]) -> String {
+    let end = bytes.len().min(MAX_SHELL_OUTPUT);
+    String::from_utf8_lossy(&bytes[..end]).into_owned()
+}
+
+async fn perform(action: &HostAction) -> Result<Value, String> {
+    match action {
+        HostAction::OpenUrl { url } => {
+            let scheme = url.split_once(':').map(|(s, _)| s.to_lowercase());
+            if !matches!(scheme.as_deref(), Some("http" | "https" | "mailto")) {
+                return Err(format!("只允许打开 http/https/mailto 链接: {}", url));
+            }
+            open_path(url.clone()).await?;
+            Ok(Value::Null)
+        }
+        HostAction::ReadFile { path } => {
+            let path = PathBuf::from(path);
+            tauri::async_runtime::spawn_blocking(move || {
+                let size = std::fs::metadata(&path).map_err(|e| e.to_string())?.len();
+                if size > MAX_READ_BYTES {
+                    return Err(format!(
+                        "文件过大（{} 字节，上限 {}）: {}",
+                        size,
+                        MAX_READ_BYTES,
+                        path.display()
+                    ));
+                }
+                let bytes = std::fs::read(&path).map_err(|e| e.to_string())?;
+                let content = String::from_utf8(bytes)
+                    .map_err(|_| format!("不是文本文件: {}", path.display()))?;
+                Ok(serde_json::json!({ "path": path, "bytes": size, "content": content }))
+            })
+            .await
+            .map_err(|e| e.to_string())?
+        }
+        HostAction::RunShell { command, cwd } => {
+            #[cfg(target_os = "windows")]
+            let mut builder = {
+                const CREATE_NO_WINDOW: u32 = 0x08000000;
+                let mut b = tokio::process::Command::new("cmd");
+                b.args(["/C", command]).creation_flags(CREATE_NO_WINDOW);
+                b
+            };
+            #[cfg(not(target_os = "windows"))]
+            let mut builder = {
+                let mut b = tokio::process::Command::new("sh");
+                b.args(["-c", command]);
+                b
+            };
+            if let Some(dir) = cwd.as_deref().filter(|d| !d.trim().is_empty()) {
+                builder.current_dir(dir);
+            }
+            builder
+                .stdin(std::process::Stdio::null())
+                .stdout(std::process::Stdio::piped())
+                .stderr(std::process::Stdio::piped())
+                .kill_on_drop(true);
+            let child = builder
+                .spawn()
+                .map_err(|e| format!("启动命令失败: {}", e))?;
+            let output = tokio::time::timeout(SHELL_TIMEOUT, child.wait_with_output())
+                .await
+                .map_err(|_| format!("命令在 {} 秒内未结束", SHELL_TIMEOUT.as_secs()))?
+                .map_err(|e| e.to_string())?;
+            Ok(serde_json::json!({
+                "exit_code": output.status.code(),
+                "stdout": truncate(&output.stdout),
+                "stderr": truncate(&output.stderr),
+            }))
+        }
+    }
+}
+
+/// 授权并执行一条 `host_request`，结果以 `{"cmd":"host_reply","target":<host_id>,...}` 写回 bridge
+async fn resolve(
+    app: &AppHandle,
+    request_id: &str,
+    project: Option<&str>,
+    workspace: Option<&str>,
+    data: &Value,
+) -> Result<Value, (bool, String)> {
+    let action: HostAction = serde_json::from_value(data.clone())
+        .map_err(|e| (false, format!("无法识别的宿主操作: {}", e)))?;
+    let action = action.canonical().map_err(|e| (false, e))?;
+    let permissions = app.state::<HostPermissions>();
+    let rule = action.rule();
+    let allowed = match &action {
+        HostAction::ReadFile { path } if inside_workspace(path, workspace) => true,
+        _ => match permissions.remembered(project, &rule) {
+            Some(decision) => decision == Decision::Allow,
+            None => {
+                let detail = serde_json::json!({
+                    "request_id": request_id,
+                    "project": project,
+                    "rule": rule,
+                    "action": action,
+                    "reason": data.get("reason"),
+                });
+                let answer = permissions.prompt(app, detail).await;
+                if answer.remember {
+                    let decision = if answer.allow {
+                        Decision::Allow
+                    } else {
+                        Decision::Deny
+                    };
+                    let scope = scope_of(project);
+                    if let Err(e) = permissions.update(|r| {
+                        r.projects
+                            .entry(scope)
+                            .or_default()
+                            .insert(rule.clone(), decision);
+                    }) {
+                        eprintln!("Warning: 保存权限决定失败: {}", e);
+                    }
+                }
+                answer.allow
+            }
+        },
+    };
+    if !allowed {
+        return Err((true, format!("用户未允许该操作: {}", rule)));
+    }
+    perform(&action).await.map_err(|e| (false, e))
+}
+
+/// 流式请求中收到 `host_request` 事件时调用；在后台处理，不阻塞事件读取
+pub fn handle_host_request(
+    app: &AppHandle,
+    dispatcher: Arc<Dispatcher>,
+    request_id: &str,
+    project: Option<String>,
+    workspace: Option<String>,
+    data: Value,
+) {
+    let app = app.clone();
+    let request_id = request_id.to_string();
+    tauri::async_runtime::spawn(async move {
+        let host_id = data.get("host_id").cloned().unwrap_or(Value::Null);
+        let reply = match resolve(
+            &app,
+            &request_id,
+            project.as_deref(),
+            workspace.as_deref(),
+            &data,
+        )
+        .await
+        {
+            Ok(result) => serde_json::json!({ "ok": true, "result": result }),
+            Err((denied, message)) => {
+                serde_json::json!({ "ok": false, "denied": denied, "message": message })
+            }
+        };
+        let mut line = reply;
+        line["cmd"] = Value::String("host_reply".to_string());
+        line["target"] = host_id;
+        let line = line.to_string();
+        debug_console::record(Direction::Out, &line);
+        if let Err(e) = dispatcher.write_line(&line).await {
+            eprintln!("Warning: 回复 host_request 失败: {}", e);
+        }
+    });
+}
+
+/// 答复一条 `permission-request`；`remember` 为 true 时按项目记住该决定。
+/// 答复后发出 `permission-resolved`，让其他窗口收起同一条询问
+#[tauri::command]
+pub async fn permission_respond(
+    app: AppHandle,
+    permissions: tauri::State<'_, HostPermissions>,
+    prompt_id: String,
+    allow: bool,
+    remember: Option<bool>,
+) -> Result<(), String> {
+    guarded("permission_respond", async move {
+        let tx = permissions
+            .pending
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(&prompt_id)
+            .ok_or_else(|| format!("权限请求不存在或已过期: {}", prompt_id))?;
+        let _ = tx.send(Answer {
+            allow,
+            remember: remember.unwrap_or(false),
+        });
+        let _ = app.emit(
+            "permission-resolved",
+            serde_json::json!({ "prompt_id": prompt_id }),
+        );
+        Ok(())
+    })
+    .await
+}
+
+/// 已记住的决定，按项目分组（`*` 为不属于任何项目的请求）
+#[tauri::command]
+pub async fn permissions_list(
+    permissions: tauri::State<'_, HostPermissions>,
+) -> Result<PermissionRules, String> {
+    guarded("permissions_list", async move { Ok(permissions.rules()) }).await
+}
+
+/// 忘记记住的决定：指定 `rule` 时只删该条，否则清除该项目的全部决定
+#[tauri::command]
+pub async fn permission_forget(
+    permissions: tauri::State<'_, HostPermissions>,
+    project: Option<String>,
+    rule: Option<String>,
+) -> Result<(), String> {
+    guarded("permission_forget", async move {
+        let scope = scope_of(project.as_deref());
+        permissions.update(|r| match rule {
+            Some(rule) => {
+                if let Some(rules) = r.projects.get_mut(&scope) {
+                    rules.remove(&rule);
+                }
+            }
+            None => {
+                r.projects.remove(&scope);
+            }
+        })
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "mph-permissions-{}-{}-{}",
+            name,
+            std::process::id(),
+            NEXT_PROMPT_ID.fetch_add(1, Ordering::Relaxed)
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::canonicalize(dir).unwrap()
+    }
+
+    fn shell(command: &str, cwd: Option<&Path>) -> HostAction {
+        HostAction::RunShell {
+            command: command.to_string(),
+            cwd: cwd.map(|d| d.display().to_string()),
+        }
+    }
+
+    #[test]
+    fn open_url_rule_is_per_site() {
+        let a = HostAction::OpenUrl {
+            url: "HTTPS://Example.com/a?b=1".to_string(),
+        };
+        let b = HostAction::OpenUrl {
+            url: "https://example.com#top".to_string(),
+        };
+        assert_eq!(a.rule(), "open_url:https://example.com");
+        assert_eq!(a.rule(), b.rule());
+    }
+
+    #[test]
+    fn run_shell_rule_includes_cwd() {
+        let one = temp_dir("cwd-one");
+        let two = temp_dir("cwd-two");
+        let a = shell("make", Some(&one)).canonical().unwrap();
+        let b = shell("make", Some(&two)).canonical().unwrap();
+        assert_ne!(a.rule(), b.rule());
+        assert!(a.rule().starts_with("run_shell:\""));
+        assert!(a.rule().ends_with("\" make"));
+
+        // 未指定 cwd 时按当前目录记，与显式写出当前目录相同
+        let here = std::env::current_dir().unwrap();
+        assert_eq!(
+            shell("make", None).canonical().unwrap().rule(),
+            shell("make", Some(&here)).canonical().unwrap().rule()
+        );
+        // 经 `..` 绕回同一目录也是同一条规则
+        let dotted = one.join("..").join(one.file_name().unwrap());
+        assert_eq!(
+            shell("make", Some(&dotted)).canonical().unwrap().rule(),
+            a.rule()
+        );
+        let _ = std::fs::remove_dir_all(one);
+        let _ = std::fs::remove_dir_all(two);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn read_file_rule_follows_symlinks() {
+        let allowed = temp_dir("allowed");
+        let secret = temp_dir("secret");
+        std::fs::write(secret.join("key"), "x").unwrap();
+        std::os::unix::fs::symlink(secret.join("key"), allowed.join("link")).unwrap();
+
+        let via_link = HostAction::ReadFile {
+            path: allowed.join("link").display().to_string(),
+        }
+        .canonical()
+        .unwrap();
+        assert_eq!(via_link.rule(), format!("read_file:{}", secret.display()));
+        match via_link {
+            HostAction::ReadFile { path } => {
+                assert_eq!(path, secret.join("key").display().to_string())
+            }
+            other => panic!("{:?}", other),
+        }
+        let _ = std::fs::remove_dir_all(allowed);
+        let _ = std::fs::remove_dir_all(secret);
+    }
+
+    #[test]
+    fn missing_paths_are_rejected() {
+        let dir = temp_dir("missing");
+        let missing = HostAction::ReadFile {
+            path: dir.join("nope").display().to_string(),
+        };
+        assert!(missing.canonical().is_err());
+        assert!(shell("ls", Some(&dir.join("nope"))).canonical().is_err());
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
@@ -0,0 +1,511 @@
+use crate::abort::abort_running;
+use crate::bridge::submit_stream_job;
+use crate::jobs::{project_of, JobRegistry};
+use crate::panics::guarded;
+use crate::policy::CommandPolicy;
+use crate::store::{load_json, now_ms, save_json};
+use crate::stream::next_request_id;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashSet};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter, Manager};
+
+/// 流水线历史最多保留条数
+const MAX_PIPELINES: usize = 100;
+const MAX_STEPS: usize = 32;
+const UPDATE_EVENT: &str = "pipeline-update";
+/// 下游步骤 payload 中注入上游产物的字段
+const UPSTREAM_KEY: &str = "upstream_artifacts";
+
+static SEQ: AtomicU64 = AtomicU64::new(1);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StepStatus {
+    Pending,
+    Running,
+    Succeeded,
+    Failed,
+    /// 上游失败或流水线被取消，未执行
+    Skipped,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct StepSpec {
+    /// 流水线内唯一的步骤名，如 geometry / solve / export
+    pub key: String,
+    pub cmd: String,
+    #[serde(default)]
+    pub payload: Value,
+    #[serde(default)]
+    pub depends_on: Vec<String>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PipelineStep {
+    pub key: String,
+    pub cmd: String,
+    pub payload: Value,
+    pub depends_on: Vec<String>,
+    pub status: StepStatus,
+    /// 最近一次执行对应的任务；重试会产生新任务
+    #[serde(default)]
+    pub job_id: Option<String>,
+    #[serde(default)]
+    pub attempts: u32,
+    #[serde(default)]
+    pub artifacts: Vec<String>,
+    #[serde(default)]
+    pub message: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Pipeline {
+    pub id: String,
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub project: Option<String>,
+    pub created_at: u64,
+    #[serde(default)]
+    pub finished_at: Option<u64>,
+    #[serde(default)]
+    pub cancelled: bool,
+    pub steps: Vec<PipelineStep>,
+}
+
+impl Pipeline {
+    fn is_active(&self) -> bool {
+        self.steps
+            .iter()
+            .any(|s| matches!(s.status, StepStatus::Pending | StepStatus::Running))
+    }
+
+    fn step(&self, key: &str) -> Option<&PipelineStep> {
+        self.steps.iter().find(|s| s.key == key)
+    }
+}
+
+#[derive(Default, Serialize, Deserialize)]
+struct PipelineStore {
+    pipelines: Vec<Pipeline>,
+}
+
+/// 任务流水线：按依赖关系依次提交步骤，每一步都是独立的任务，可单独重试；
+/// 上游产物通过 `upstream_artifacts` 与 `${step.artifact}` 占位符传给下游
+#[derive(Default)]
+pub struct PipelineRegistry {
+    path: Option<PathBuf>,
+    store: Mutex<PipelineStore>,
+    /// 正在调度的流水线，防止同一条被重复驱动
+    driving: Mutex<HashSet<String>>,
+}
+
+impl PipelineRegistry {
+    pub fn load(path: PathBuf) -> Self {
+        let mut store: PipelineStore = load_json(&path);
+        // 上次退出时未完成的步骤不会再继续，留待用户重试
+        for p in store.pipelines.iter_mut() {
+            for s in p.steps.iter_mut() {
+                match s.status {
+                    StepStatus::Running => {
+                        s.status = StepStatus::Failed;
+                        s.message = Some("应用退出时步骤尚未完成".to_string());
+                    }
+                    StepStatus::Pending => s.status = StepStatus::Skipped,
+                    _ => {}
+                }
+            }
+        }
+        Self {
+            path: Some(path),
+            store: Mutex::new(store),
+            ..Self::default()
+        }
+    }
+
+    fn update<T>(&self, id: &str, f: impl FnOnce(&mut Pipeline) -> T) -> Option<(T, Pipeline)> {
+        let mut store = self.store.lock().unwrap_or_else(|e| e.into_inner());
+        let p = store.pipelines.iter_mut().find(|p| p.id == id)?;
+        let out = f(p);
+        if !p.is_active() && p.finished_at.is_none() {
+            p.finished_at = Some(now_ms());
+        }
+        let snapshot = p.clone();
+        if let Some(ref path) = self.path {
+            if let Err(e) = save_json(path, &*store) {
+                eprintln!("Warning: 保存流水线失败: {}", e);
+            }
+        }
+        Some((out, snapshot))
+    }
+
+    fn insert(&self, pipeline: Pipeline) {
+        let mut store = self.store.lock().unwrap_or_else(|e| e.into_inner());
+        store.pipelines.push(pipeline);
+        if store.pipelines.len() > MAX_PIPELINES {
+            let excess = store.pipelines.len() - MAX_PIPELINES;
+            store.pipelines.drain(..excess);
+        }
+        if let Some(ref path) = self.path {
+            if let Err(e) = save_json(path, &*store) {
+                eprintln!("Warning: 保存流水线失败: {}", e);
+            }
+        }
+    }
+
+    fn get(&self, id: &str) -> Option<Pipeline> {
+        let store = self.store.lock().unwrap_or_else(|e| e.into_inner());
+        store.pipelines.iter().find(|p| p.id == id).cloned()
+    }
+
+    fn list(&self, limit: usize) -> Vec<Pipeline> {
+        let store = self.store.lock().unwrap_or_else(|e| e.into_inner());
+        store.pipelines.iter().rev().take(limit).cloned().collect()
+    }
+}
+
+/// 校验步骤：名称唯一、依赖存在、无环；返回拓扑序（同层保持提交顺序）
+fn topo_order(steps: &[StepSpec]) -> Result<Vec<usize>, String> {
+    if steps.is_empty() {
+        return Err("流水线至少需要一个步骤".to_string());
+    }
+    if steps.len() > MAX_STEPS {
+        return Err(format!("流水线步骤不能超过 {} 个", MAX_STEPS));
+    }
+    let mut index = BTreeMap::new();
+    for (i, s) in steps.iter().enumerate() {
+        if s.key.trim().is_empty() || s.cmd.trim().is_empty() {
+            return Err("步骤的 key 与 cmd 不能为空".to_string());
+        }
+        if index.insert(s.key.as_str(), i).is_some() {
+            return Err(format!("步骤名重复: {}", s.key));
+        }
+    }
+    let mut indegree = vec![0usize; steps.len()];
+    for (i, s) in steps.iter().enumerate() {
+        for d in &s.depends_on {
+            if !index.contains_key(d.as_str()) {
+                return Err(format!("步骤 {} 依赖不存在的步骤 {}", s.key, d));
+            }
+            if d == &s.key {
+                return Err(format!("步骤 {} 不能依赖自身", s.key));
+            }
+        }
+        indegree[i] = s.depends_on.len();
+    }
+    let mut order = Vec::with_capacity(steps.len());
+    let mut done = vec![false; steps.len()];
+    while order.len() < steps.len() {
+        let Some(i) = (0..steps.len()).find(|&i| !done[i] && indegree[i] == 0) else {
+            return Err("步骤依赖存在环".to_string());
+        };
+        done[i] = true;
+        order.push(i);
+        for (j, s) in steps.iter().enumerate() {
+            indegree[j] -= s.depends_on.iter().filter(|d| *d == &steps[i].key).count();
+        }
+    }
+    Ok(order)
+}
+
+/// 把字符串中的 `${step.artifact}` 替换为该步骤的第一个产物路径
+fn substitute(value: &mut Value, artifacts: &BTreeMap<String, Vec<String>>) {
+    match value {
+        Value::String(s) if s.contains("${") => {
+            for (key, paths) in artifacts {
+                if let Some(first) = paths.first() {
+                    *s = s.replace(&format!("${{{}.artifact}}", key), first);
+                }
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(|v| substitute(v, artifacts)),
+        Value::Object(map) => map.values_mut().for_each(|v| substitute(v, artifacts)),
+        _ => {}
+    }
+}
+
+/// 选出下一个可执行的步骤并标记为运行中；依赖失败/跳过的步骤级联标记为跳过
+fn next_step(p: &mut Pipeline, request_id: &str) -> Option<(String, String, Value)> {
+    loop {
+        let mut changed = false;
+        for i in 0..p.steps.len() {
+            if p.steps[i].status != StepStatus::Pending {
+                continue;
+            }
+            let blocked = p.cancelled
+                || p.steps[i].depends_on.iter().any(|d| {
+                    p.step(d).is_some_and(|s| {
+                        matches!(s.status, StepStatus::Failed | StepStatus::Skipped)
+                    })
+                });
+            if blocked {
+                p.steps[i].status = StepStatus::Skipped;
+                changed = true;
+            }
+        }
+        if !changed {
+            break;
+        }
+    }
+    let i = p.steps.iter().position(|s| {
+        s.status == StepStatus::Pending
+            && s.depends_on
+                .iter()
+                .all(|d| p.step(d).is_some_and(|u| u.status == StepStatus::Succeeded))
+    })?;
+    let upstream: BTreeMap<String, Vec<String>> = p.steps[i]
+        .depends_on
+        .iter()
+        .filter_map(|d| p.step(d).map(|u| (d.clone(), u.artifacts.clone())))
+        .collect();
+    let step = &mut p.steps[i];
+    let mut payload = step.payload.clone();
+    substitute(&mut payload, &upstream);
+    if let Some(obj) = payload.as_object_mut() {
+        if !upstream.is_empty() {
+            obj.insert(
+                UPSTREAM_KEY.to_string(),
+                serde_json::to_value(&upstream).unwrap_or_default(),
+            );
+        }
+        if let (Some(project), false) = (&p.project, obj.contains_key("project")) {
+            obj.insert("project".to_string(), Value::String(project.clone()));
+        }
+    }
+    step.status = StepStatus::Running;
+    step.job_id = Some(request_id.to_string());
+    step.attempts += 1;
+    step.message = None;
+    step.artifacts.clear();
+    Some((step.key.clone(), step.cmd.clone(), payload))
+}
+
+fn emit_update(app: &AppHandle, pipeline: &Pipeline) {
+    let _ = app.emit(UPDATE_EVENT, pipeline);
+}
+
+/// 依次执行流水线中就绪的步骤，直到没有可执行的步骤
+async fn drive(app: AppHandle, id: String) {
+    let registry = app.state::<PipelineRegistry>();
+    if !registry
+        .driving
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .insert(id.clone())
+    {
+        return;
+    }
+    loop {
+        let request_id = next_request_id();
+        let Some((next, snapshot)) = registry.update(&id, |p| next_step(p, &request_id)) else {
+            break;
+        };
+        emit_update(&app, &snapshot);
+        let Some((key, cmd, payload)) = next else {
+            break;
+        };
+        let result =
+            submit_stream_job(&app, cmd, payload, Some(request_id.clone()), None, None).await;
+        let (ok, message) = match result {
+            Ok(v) => (
+                v["ok"].as_bool() == Some(true),
+                v["message"].as_str().map(String::from),
+            ),
+            Err(e) => (false, Some(e.into())),
+        };
+        let artifacts = app
+            .state::<JobRegistry>()
+            .get(&request_id)
+            .map(|j| j.artifacts)
+            .unwrap_or_default();
+        if let Some((_, snapshot)) = registry.update(&id, |p| {
+            if let Some(s) = p.steps.iter_mut().find(|s| s.key == key) {
+                s.status = if ok {
+                    StepStatus::Succeeded
+                } else {
+                    StepStatus::Failed
+                };
+                s.message = message;
+                s.artifacts = artifacts;
+            }
+        }) {
+            emit_update(&app, &snapshot);
+        }
+    }
+    registry
+        .driving
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .remove(&id);
+    // 退出调度前若被重试重新置为待执行，则再驱动一轮
+    if registry.get(&id).is_some_and(|p| {
+        p.steps.iter().any(|s| s.status == StepStatus::Pending)
+            && !p.steps.iter().any(|s| s.status == StepStatus::Running)
+    }) {
+        spawn_drive(&app, &id);
+    }
+}
+
+fn spawn_drive(app: &AppHandle, id: &str) {
+    let app = app.clone();
+    let id = id.to_string();
+    tauri::async_runtime::spawn(drive(app, id));
+}
+
+/// 提交一条流水线：步骤按依赖顺序逐个作为独立任务执行
+#[tauri::command]
+pub async fn pipeline_submit(
+    app: AppHandle,
+    registry: tauri::State<'_, PipelineRegistry>,
+    policy: tauri::State<'_, CommandPolicy>,
+    steps: Vec<StepSpec>,
+    name: Option<String>,
+    project: Option<String>,
+) -> Result<Pipeline, String> {
+    guarded("pipeline_submit", async move {
+        policy.ensure_writable("提交流水线")?;
+        let order = topo_order(&steps)?;
+        let project = project
+            .or_else(|| steps.iter().find_map(|s| project_of(&s.payload)))
+            .filter(|p| !p.trim().is_empty());
+        let created_at = now_ms();
+        let pipeline = Pipeline {
+            id: format!(
+                "pipeline-{}-{}",
+                created_at,
+                SEQ.fetch_add(1, Ordering::Relaxed)
+            ),
+            name,
+            project,
+            created_at,
+            finished_at: None,
+            cancelled: false,
+            steps: order
+                .into_iter()
+                .map(|i| {
+                    let s = &steps[i];
+                    PipelineStep {
+                        key: s.key.trim().to_string(),
+                        cmd: s.cmd.trim().to_string(),
+                        payload: s.payload.clone(),
+                        depends_on: s.depends_on.clone(),
+                        status: StepStatus::Pending,
+                        job_id: None,
+                        attempts: 0,
+                        artifacts: Vec::new(),
+                        message: None,
+                    }
+                })
+                .collect(),
+        };
+        registry.insert(pipeline.clone());
+        spawn_drive(&app, &pipeline.id);
+        Ok(pipeline)
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn pipelines_list(
+    registry: tauri::State<'_, PipelineRegistry>,
+    limit: Option<usize>,
+) -> Result<Vec<Pipeline>, String> {
+    guarded("pipelines_list", async move {
+        Ok(registry.list(limit.unwrap_or(50)))
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn pipeline_get(
+    registry: tauri::State<'_, PipelineRegistry>,
+    pipeline_id: String,
+) -> Result<Pipeline, String> {
+    guarded("pipeline_get", async move {
+        registry
+            .get(&pipeline_id)
+            .ok_or_else(|| format!("未找到流水线: {}", pipeline_id))
+    })
+    .await
+}
+
+/// 重试失败的步骤（不指定时重试全部失败步骤），并恢复其下游被跳过的步骤
+#[tauri::command]
+pub async fn pipeline_retry(
+    app: AppHandle,
+    registry: tauri::State<'_, PipelineRegistry>,
+    policy: tauri::State<'_, CommandPolicy>,
+    pipeline_id: String,
+    step: Option<String>,
+) -> Result<Pipeline, String> {
+    guarded("pipeline_retry", async move {
+        policy.ensure_writable("重试流水线")?;
+        let (res, snapshot) = registry
+            .update(&pipeline_id, |p| {
+                if p.is_active() {
+                    return Err("流水线仍在执行".to_string());
+                }
+                let targets: Vec<String> = match step {
+                    Some(ref key) => match p.step(key) {
+                        Some(s) if s.status == StepStatus::Succeeded => {
+                            return Err(format!("步骤 {} 已成功，无需重试", key))
+                        }
+                        Some(_) => vec![key.clone()],
+                        None => return Err(format!("未找到步骤: {}", key)),
+                    },
+                    None => p
+                        .steps
+                        .iter()
+                        .filter(|s| s.status != StepStatus::Succeeded)
+                        .map(|s| s.key.clone())
+                        .collect(),
+                };
+                p.cancelled = false;
+                p.finished_at = None;
+                for s in p.steps.iter_mut() {
+                    if targets.contains(&s.key) || s.status == StepStatus::Skipped {
+                        s.status = StepStatus::Pending;
+                    }
+                }
+                Ok(())
+            })
+            .ok_or_else(|| format!("未找到流水线: {}", pipeline_id))?;
+        res?;
+        emit_update(&app, &snapshot);
+        spawn_drive(&app, &pipeline_id);
+        Ok(snapshot)
+    })
+    .await
+}
+
+/// 取消流水线：尚未开始的步骤标记为跳过，正在执行的步骤按中止策略中止
+#[tauri::command]
+pub async fn pipeline_cancel(
+    app: AppHandle,
+    registry: tauri::State<'_, PipelineRegistry>,
+    pipeline_id: String,
+) -> Result<(), String> {
+    guarded("pipeline_cancel", async move {
+        let (running, snapshot) = registry
+            .update(&pipeline_id, |p| {
+                p.cancelled = true;
+                for s in p.steps.iter_mut() {
+                    if s.status == StepStatus::Pending {
+                        s.status = StepStatus::Skipped;
+                    }
+                }
+                p.steps.iter().any(|s| s.status == StepStatus::Running)
+            })
+            .ok_or_else(|| format!("未找到流水线: {}", pipeline_id))?;
+        emit_update(&app, &snapshot);
+        if running {
+            abort_running(&app).await?;
+        }
+        Ok(())
+    })
+    .await
+}
@@ -0,0 +1,291 @@
+use crate::panics::guarded;
+use crate::store::{load_json, save_json};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+/// 管理员下发的策略文件位置可用该环境变量覆盖
+const ADMIN_POLICY_ENV: &str = "MPH_AGENT_POLICY";
+/// 以 `--read-only` 启动时强制只读，应用内无法关闭
+const READ_ONLY_FLAG: &str = "--read-only";
+/// 只读模式下仍允许的 bridge cmd：浏览历史与查看产物，均不启动 COMSOL、不占用许可证
+const READ_ONLY_CMDS: &[&str] = &[
+    "case_library_list",
+    "case_library_sync_status",
+    "context_get_summary",
+    "context_history",
+    "context_prompt_context",
+    "context_show",
+    "context_stats",
+    "doc_kb_search",
+    "doc_kb_status",
+    "list_apis",
+    "models_list",
+    "ops_catalog",
+    "skills_list_local",
+];
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct PolicyFile {
+    /// 允许前端调用的 bridge cmd；None 表示不限制
+    #[serde(default)]
+    pub allowed_cmds: Option<BTreeSet<String>>,
+    /// 管理员策略设为 locked 时，用户不能在应用内修改
+    #[serde(default)]
+    pub locked: bool,
+    /// 只读查看模式：只开放历史浏览、产物查看与导出，拒绝提交任务
+    #[serde(default)]
+    pub read_only: bool,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct PolicyInfo {
+    pub allowed_cmds: Option<BTreeSet<String>>,
+    pub locked: bool,
+    pub read_only: bool,
+    /// 只读由启动参数强制
+    pub read_only_forced: bool,
+    /// 生效策略的来源文件
+    pub source: Option<String>,
+}
+
+/// 命令白名单（教学/展台部署用）：管理员策略优先，其次是用户在应用内保存的策略
+#[derive(Default)]
+pub struct CommandPolicy {
+    user_path: Option<PathBuf>,
+    policy: Mutex<PolicyFile>,
+    /// 当前生效策略来自哪个文件
+    source: Mutex<Option<PathBuf>>,
+    read_only_forced: bool,
+}
+
+/// 系统级策略文件：Windows 在 %ProgramData%，其他平台在 /etc
+fn admin_policy_path() -> Option<PathBuf> {
+    if let Ok(p) = std::env::var(ADMIN_POLICY_ENV) {
+        return Some(PathBuf::from(p));
+    }
+    #[cfg(target_os = "windows")]
+    return std::env::var("ProgramData")
+        .ok()
+        .map(|d| PathBuf::from(d).join("mph-agent").join("policy.json"));
+    #[cfg(not(target_os = "windows"))]
+    return Some(PathBuf::from("/etc/mph-agent/policy.json"));
+}
+
+impl CommandPolicy {
+    /// 管理员策略 locked 时强制生效；否则它只是默认值，用户保存过的策略优先
+    pub fn load(user_path: PathBuf) -> Self {
+        let admin: Option<PolicyFile> = admin_policy_path()
+            .filter(|p| p.is_file())
+            .map(|p| load_json(&p));
+        let (policy, source) = match admin {
+            Some(a) if a.locked || !user_path.is_file() => (a, admin_policy_path()),
+            _ => (load_json(&user_path), Some(user_path.clone())),
+        };
+        Self {
+            user_path: Some(user_path),
+            policy: Mutex::new(policy),
+            source: Mutex::new(source),
+            read_only_forced: std::env::args().any(|a| a == READ_ONLY_FLAG),
+        }
+    }
+
+    pub fn is_read_only(&self) -> bool {
+        self.read_only_forced
+            || self
+                .policy
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .read_only
+    }
+
+    /// 会占用许可证或改动数据的宿主侧操作在只读模式下拒绝
+    pub fn ensure_writable(&self, action: &str) -> Result<(), String> {
+        if self.is_read_only() {
+            return Err(format!("只读模式下不可{}", action));
+        }
+        Ok(())
+    }
+
+    pub fn info(&self) -> PolicyInfo {
+        let policy = self.policy.lock().unwrap_or_else(|e| e.into_inner());
+        PolicyInfo {
+            allowed_cmds: policy.allowed_cmds.clone(),
+            locked: policy.locked,
+            read_only: self.read_only_forced || policy.read_only,
+            read_only_forced: self.read_only_forced,
+            source: self
+                .source
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .as_ref()
+                .map(|p| p.to_string_lossy().into_owned()),
+        }
+    }
+
+    pub fn check(&self, cmd: &str) -> Result<(), String> {
+        let policy = self.policy.lock().unwrap_or_else(|e| e.into_inner());
+        if (self.read_only_forced || policy.read_only) && !READ_ONLY_CMDS.contains(&cmd) {
+            return Err(format!("只读模式下禁止执行命令: {}", cmd));
+        }
+        match policy.allowed_cmds {
+            Some(ref allowed) if !allowed.contains(cmd) => {
+                Err(format!("策略禁止执行命令: {}", cmd))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// 只改动传入的项：`allowed_cmds` 为 None 时保留原白名单，取消限制需显式传 `clear_allowed`
+    pub fn set(
+        &self,
+        allowed_cmds: Option<BTreeSet<String>>,
+        clear_allowed: bool,
+        read_only: Option<bool>,
+    ) -> Result<(), String> {
+        let mut policy = self.policy.lock().unwrap_or_else(|e| e.into_inner());
+        if policy.locked {
+            return Err("命令策略由管理员锁定，无法修改".to_string());
+        }
+        if clear_allowed && allowed_cmds.is_some() {
+            return Err("不能同时设置并清除命令白名单".to_string());
+        }
+        // 与 --read-only 一样，只读一旦生效就不能在应用内关闭，只能改策略文件
+        if read_only == Some(false) && (self.read_only_forced || policy.read_only) {
+            return Err("只读模式无法在应用内关闭".to_string());
+        }
+        if clear_allowed {
+            policy.allowed_cmds = None;
+        } else if allowed_cmds.is_some() {
+            policy.allowed_cmds = allowed_cmds;
+        }
+        if let Some(r) = read_only {
+            policy.read_only = r;
+        }
+        *self.source.lock().unwrap_or_else(|e| e.into_inner()) = self.user_path.clone();
+        match self.user_path {
+            Some(ref p) => save_json(p, &*policy),
+            None => Ok(()),
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn policy_get(policy: tauri::State<'_, CommandPolicy>) -> Result<PolicyInfo, String> {
+    guarded("policy_get", async move { Ok(policy.info()) }).await
+}
+
+/// 设置允许的命令列表，`clear_allowed_cmds` 为 true 时取消限制；未传的项保持原设置。
+/// 只读只能开启不能关闭
+#[tauri::command]
+pub async fn policy_set(
+    policy: tauri::State<'_, CommandPolicy>,
+    allowed_cmds: Option<Vec<String>>,
+    clear_allowed_cmds: Option<bool>,
+    read_only: Option<bool>,
+) -> Result<(), String> {
+    guarded("policy_set", async move {
+        policy.set(
+            allowed_cmds.map(|v| {
+                v.into_iter()
+                    .map(|c| c.trim().to_string())
+                    .filter(|c| !c.is_empty())
+                    .collect()
+            }),
+            clear_allowed_cmds.unwrap_or(false),
+            read_only,
+        )
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cmds(list: &[&str]) -> BTreeSet<String> {
+        list.iter().map(|c| c.to_string()).collect()
+    }
+
+    fn kiosk() -> CommandPolicy {
+        let policy = CommandPolicy::default();
+        policy
+            .set(Some(cmds(&["run", "ping"])), false, None)
+            .unwrap();
+        policy
+    }
+
+    #[test]
+    fn setting_read_only_keeps_the_allowlist() {
+        let policy = kiosk();
+        policy.set(None, false, Some(true)).unwrap();
+        let info = policy.info();
+        assert!(info.read_only);
+        assert_eq!(info.allowed_cmds, Some(cmds(&["ping", "run"])));
+    }
+
+    #[test]
+    fn allowlist_is_cleared_only_on_request() {
+        let policy = kiosk();
+        policy.set(None, false, None).unwrap();
+        assert!(policy.info().allowed_cmds.is_some());
+        assert!(policy.set(Some(cmds(&["run"])), true, None).is_err());
+        policy.set(None, true, None).unwrap();
+        assert_eq!(policy.info().allowed_cmds, None);
+        assert!(policy.check("export_script").is_ok());
+    }
+
+    #[test]
+    fn check_applies_allowlist_and_read_only() {
+        let policy = kiosk();
+        assert!(policy.check("run").is_ok());
+        assert!(policy.check("export_script").is_err());
+        policy.set(None, true, Some(true)).unwrap();
+        assert!(policy.check("run").is_err());
+        assert!(policy.check("models_list").is_ok());
+        assert!(policy.ensure_writable("提交任务").is_err());
+    }
+
+    #[test]
+    fn read_only_cannot_be_turned_off() {
+        let policy = CommandPolicy::default();
+        policy.set(None, false, Some(true)).unwrap();
+        assert!(policy.set(None, false, Some(false)).is_err());
+        assert!(policy.is_read_only());
+    }
+
+    #[test]
+    fn locked_policy_rejects_changes() {
+        let policy = CommandPolicy::default();
+        policy.policy.lock().unwrap().locked = true;
+        assert!(policy.set(None, true, None).is_err());
+        assert!(policy.set(None, false, Some(true)).is_err());
+        assert!(!policy.is_read_only());
+    }
+
+    #[test]
+    fn set_saves_to_the_user_policy_file() {
+        let dir = std::env::temp_dir().join(format!(
+            "mph-policy-{}-{}",
+            std::process::id(),
+            crate::store::now_ms()
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("policy.json");
+        let policy = CommandPolicy {
+            user_path: Some(path.clone()),
+            ..CommandPolicy::default()
+        };
+        policy.set(Some(cmds(&["run"])), false, None).unwrap();
+        policy.set(None, false, Some(true)).unwrap();
+        let saved: PolicyFile = load_json(&path);
+        assert_eq!(saved.allowed_cmds, Some(cmds(&["run"])));
+        assert!(saved.read_only);
+        assert_eq!(
+            policy.info().source,
+            Some(path.to_string_lossy().into_owned())
+        );
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,366 @@
+use crate::artifacts::sha256_file;
+use crate::geometry::prepare_cached;
+use crate::panics::guarded;
+use crate::store::{load_json, now_ms, save_json};
+use crate::tables::table_summary;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
+use tauri::{AppHandle, Emitter, Manager};
+use tokio::sync::{mpsc, Semaphore};
+
+/// 同时运行的后处理步骤数
+const WORKERS: usize = 2;
+/// 保留处理结果的产物数，超出时丢弃最早的
+const MAX_TRACKED: usize = 1000;
+
+/// 一个后处理步骤：对匹配扩展名的新产物执行一次，结果记入状态并通知前端
+struct Processor {
+    id: &'static str,
+    description: &'static str,
+    /// 默认适用的扩展名；为空表示所有文件
+    extensions: &'static [&'static str],
+    run: fn(&AppHandle, &Path, &Value) -> Result<Value, String>,
+}
+
+const PROCESSORS: &[Processor] = &[
+    Processor {
+        id: "hash",
+        description: "计算 SHA-256 与文件大小",
+        extensions: &[],
+        run: |_, path, _| {
+            let size = std::fs::metadata(path).map_err(|e| e.to_string())?.len();
+            Ok(serde_json::json!({ "sha256": sha256_file(path)?, "size": size }))
+        },
+    },
+    Processor {
+        id: "table_index",
+        description: "为 CSV/TXT 结果表建立索引，记录列名与行数",
+        extensions: &["csv", "txt", "dat"],
+        run: |_, path, _| table_summary(path),
+    },
+    Processor {
+        id: "geometry_preview",
+        description: "把导出的 STL/OBJ 规范化为 3D 预览缓存",
+        extensions: &["stl", "obj"],
+        run: |app, path, options| {
+            let unit = options["unit"].as_str().unwrap_or("m").to_lowercase();
+            let max_triangles = options["max_triangles"].as_u64().map(|n| n as usize);
+            let preview = prepare_cached(app, path, &unit, max_triangles)?;
+            serde_json::to_value(preview).map_err(|e| e.to_string())
+        },
+    },
+];
+
+/// 单个步骤的配置；未配置的步骤按默认启用、默认扩展名运行
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct ProcessorSettings {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub enabled: Option<bool>,
+    /// 覆盖适用的扩展名（不含点）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub extensions: Option<Vec<String>>,
+    /// 传给步骤的参数，如 geometry_preview 的 `{"unit": "mm"}`
+    #[serde(default, skip_serializing_if = "Value::is_null")]
+    pub options: Value,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ProcessOutcome {
+    pub processor: String,
+    pub ok: bool,
+    #[serde(skip_serializing_if = "Value::is_null")]
+    pub output: Value,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// 处理时源文件的修改时间，文件未变时不重复处理
+    pub source_modified_ms: u64,
+    pub finished_at: u64,
+}
+
+struct Task {
+    path: PathBuf,
+    processor: &'static Processor,
+    options: Value,
+}
+
+/// 产物后处理登记表：各步骤的配置、处理结果与后台任务队列
+#[derive(Default)]
+pub struct PostProcessors {
+    path: Option<PathBuf>,
+    settings: Mutex<BTreeMap<String, ProcessorSettings>>,
+    results: Mutex<Vec<(String, Vec<ProcessOutcome>)>>,
+    pending: Mutex<HashSet<(String, &'static str)>>,
+    queue: OnceLock<mpsc::UnboundedSender<Task>>,
+}
+
+fn modified_ms(path: &Path) -> u64 {
+    std::fs::metadata(path)
+        .and_then(|m| m.modified())
+        .ok()
+        .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+fn extension_of(path: &Path) -> String {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or("")
+        .to_ascii_lowercase()
+}
+
+impl PostProcessors {
+    pub fn load(path: PathBuf) -> Self {
+        let settings: BTreeMap<String, ProcessorSettings> = load_json(&path);
+        Self {
+            path: Some(path),
+            settings: Mutex::new(settings),
+            ..Self::default()
+        }
+    }
+
+    fn settings(&self) -> BTreeMap<String, ProcessorSettings> {
+        self.settings
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    fn set(&self, id: &str, settings: ProcessorSettings) -> Result<(), String> {
+        let mut all = self.settings.lock().unwrap_or_else(|e| e.into_inner());
+        all.insert(id.to_string(), settings);
+        match self.path {
+            Some(ref p) => save_json(p, &*all),
+            None => Ok(()),
+        }
+    }
+
+    fn outcomes(&self, path: &str) -> Vec<ProcessOutcome> {
+        self.results
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .iter()
+            .find(|(p, _)| p == path)
+            .map(|(_, o)| o.clone())
+            .unwrap_or_default()
+    }
+
+    /// 文件自上次成功处理后未变化
+    fn is_current(&self, path: &str, processor: &str, modified: u64) -> bool {
+        self.outcomes(path)
+            .iter()
+            .any(|o| o.processor == processor && o.ok && o.source_modified_ms == modified)
+    }
+
+    fn record(&self, path: &str, outcome: ProcessOutcome) {
+        let mut results = self.results.lock().unwrap_or_else(|e| e.into_inner());
+        match results.iter_mut().find(|(p, _)| p == path) {
+            Some((_, list)) => {
+                list.retain(|o| o.processor != outcome.processor);
+                list.push(outcome);
+            }
+            None => {
+                if results.len() >= MAX_TRACKED {
+                    results.remove(0);
+                }
+                results.push((path.to_string(), vec![outcome]));
+            }
+        }
+    }
+
+    /// 把产物交给适用且启用的步骤；`force` 为 true 时文件未变也重新处理
+    fn enqueue(&self, path: &Path, force: bool) -> Vec<&'static str> {
+        let Some(queue) = self.queue.get() else {
+            return Vec::new();
+        };
+        if !path.is_file() {
+            return Vec::new();
+        }
+        let key = path.to_string_lossy().into_owned();
+        let ext = extension_of(path);
+        let modified = modified_ms(path);
+        let settings = self.settings();
+        let mut queued = Vec::new();
+        for processor in PROCESSORS {
+            let conf = settings.get(processor.id).cloned().unwrap_or_default();
+            if conf.enabled == Some(false) {
+                continue;
+            }
+            let applies = match conf.extensions {
+                Some(ref exts) => exts.iter().any(|e| e.eq_ignore_ascii_case(&ext)),
+                None => {
+                    processor.extensions.is_empty() || processor.extensions.contains(&ext.as_str())
+                }
+            };
+            if !applies || (!force && self.is_current(&key, processor.id, modified)) {
+                continue;
+            }
+            let fresh = self
+                .pending
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .insert((key.clone(), processor.id));
+            if !fresh {
+                continue;
+            }
+            let task = Task {
+                path: path.to_path_buf(),
+                processor,
+                options: conf.options,
+            };
+            if queue.send(task).is_ok() {
+                queued.push(processor.id);
+            }
+        }
+        queued
+    }
+}
+
+pub fn is_known(id: &str) -> bool {
+    PROCESSORS.iter().any(|p| p.id == id)
+}
+
+/// 新产物登记后调用：在后台任务池中运行适用的后处理步骤
+pub fn submit(app: &AppHandle, path: &str) {
+    app.state::<PostProcessors>()
+        .enqueue(Path::new(path), false);
+}
+
+async fn run_task(app: AppHandle, task: Task) {
+    let key = task.path.to_string_lossy().into_owned();
+    let modified = modified_ms(&task.path);
+    let worker_app = app.clone();
+    let Task {
+        path,
+        processor,
+        options,
+    } = task;
+    let result =
+        tauri::async_runtime::spawn_blocking(move || (processor.run)(&worker_app, &path, &options))
+            .await
+            .unwrap_or_else(|e| Err(format!("后处理任务异常结束: {}", e)));
+    if let Err(ref e) = result {
+        eprintln!("Warning: 后处理 {} 失败 ({}): {}", processor.id, key, e);
+    }
+    let outcome = ProcessOutcome {
+        processor: processor.id.to_string(),
+        ok: result.is_ok(),
+        error: result.as_ref().err().cloned(),
+        output: result.unwrap_or(Value::Null),
+        source_modified_ms: modified,
+        finished_at: now_ms(),
+    };
+    let processors = app.state::<PostProcessors>();
+    processors
+        .pending
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .remove(&(key.clone(), processor.id));
+    let _ = app.emit(
+        "artifact-processed",
+        serde_json::json!({ "path": key, "outcome": outcome }),
+    );
+    processors.record(&key, outcome);
+}
+
+/// 启动后处理任务池：队列中的步骤最多 WORKERS 个并发执行
+pub fn spawn_postprocess_pool(app: AppHandle) {
+    let (tx, mut rx) = mpsc::unbounded_channel::<Task>();
+    if app.state::<PostProcessors>().queue.set(tx).is_err() {
+        return;
+    }
+    let permits = Arc::new(Semaphore::new(WORKERS));
+    tauri::async_runtime::spawn(async move {
+        while let Some(task) = rx.recv().await {
+            let Ok(permit) = permits.clone().acquire_owned().await else {
+                break;
+            };
+            let app = app.clone();
+            tauri::async_runtime::spawn(async move {
+                run_task(app, task).await;
+                drop(permit);
+            });
+        }
+    });
+}
+
+/// 可用的后处理步骤及当前配置
+#[tauri::command]
+pub async fn postprocessors_list(
+    processors: tauri::State<'_, PostProcessors>,
+) -> Result<Vec<Value>, String> {
+    guarded("postprocessors_list", async move {
+        let settings = processors.settings();
+        Ok(PROCESSORS
+            .iter()
+            .map(|p| {
+                serde_json::json!({
+                    "id": p.id,
+                    "description": p.description,
+                    "extensions": p.extensions,
+                    "settings": settings.get(p.id).cloned().unwrap_or_default(),
+                })
+            })
+            .collect())
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn postprocessor_set(
+    processors: tauri::State<'_, PostProcessors>,
+    id: String,
+    settings: ProcessorSettings,
+) -> Result<(), String> {
+    guarded("postprocessor_set", async move {
+        if !is_known(&id) {
+            return Err(format!("未知的后处理步骤: {}", id));
+        }
+        processors.set(&id, settings)
+    })
+    .await
+}
+
+/// 某个产物的后处理结果与仍在排队的步骤
+#[tauri::command]
+pub async fn postprocess_status(
+    processors: tauri::State<'_, PostProcessors>,
+    path: String,
+) -> Result<Value, String> {
+    guarded("postprocess_status", async move {
+        let path = path.trim().to_string();
+        let pending: Vec<&str> = processors
+            .pending
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .iter()
+            .filter(|(p, _)| *p == path)
+            .map(|(_, id)| *id)
+            .collect();
+        Ok(serde_json::json!({
+            "path": path,
+            "outcomes": processors.outcomes(&path),
+            "pending": pending,
+        }))
+    })
+    .await
+}
+
+/// 手动对文件重新运行全部适用的后处理步骤，返回已排队的步骤
+#[tauri::command]
+pub async fn postprocess_run(
+    processors: tauri::State<'_, PostProcessors>,
+    path: String,
+) -> Result<Vec<&'static str>, String> {
+    guarded("postprocess_run", async move {
+        let path = PathBuf::from(path.trim());
+        if !path.is_file() {
+            return Err("文件不存在".to_string());
+        }
+        Ok(processors.enqueue(&path, true))
+    })
+    .await
+}
@@ -0,0 +1,381 @@
+use std::future::Future;
+use std::process::ExitStatus;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::task::Poll;
+use tokio::process::{Child, Command};
+use tokio::sync::{mpsc, oneshot};
+
+pub type ExitReceiver = oneshot::Receiver<std::io::Result<ExitStatus>>;
+
+/// 子进程句柄：Child 由回收任务独占并 `wait()`，结束进程也交给它完成。
+/// 进程一被回收，句柄即失效，不会误杀之后复用了同一 pid 的无关进程。
+/// 结束时连同其派生的进程（COMSOL 的 JVM 等）一起结束
+#[derive(Clone, Debug)]
+pub struct ChildHandle {
+    pub pid: u32,
+    /// 进程启动时间（各平台原始格式），按 pid 发信号前用来确认仍是同一进程
+    start_time: Option<String>,
+    reaped: Arc<AtomicBool>,
+    kill_tx: mpsc::UnboundedSender<()>,
+    tree: TreeGuard,
+}
+
+impl ChildHandle {
+    pub fn is_alive(&self) -> bool {
+        !self.reaped.load(Ordering::SeqCst)
+    }
+
+    /// 请回收任务结束进程；已退出时无操作
+    pub fn kill(&self) {
+        let _ = self.kill_tx.send(());
+    }
+
+    /// 只能按 pid 操作（发送信号、应用退出时同步结束）时使用：
+    /// 进程未被回收且启动时间与登记时一致才返回 pid
+    pub fn verified_pid(&self) -> Option<u32> {
+        if !self.is_alive() {
+            return None;
+        }
+        let expected = self.start_time.as_ref()?;
+        (process_start_time(self.pid).as_ref() == Some(expected)).then_some(self.pid)
+    }
+
+    /// 没有对应进程的句柄（回放录制的模拟 bridge）：pid 为 0、不记启动时间，
+    /// 因而从不按 pid 发信号；结束请求送到返回的通道，持有方停止后调用 `mark_reaped`
+    pub fn detached() -> (Self, mpsc::UnboundedReceiver<()>) {
+        let (kill_tx, kill_rx) = mpsc::unbounded_channel();
+        let handle = Self {
+            pid: 0,
+            start_time: None,
+            reaped: Arc::new(AtomicBool::new(false)),
+            kill_tx,
+            tree: TreeGuard::default(),
+        };
+        (handle, kill_rx)
+    }
+
+    pub fn mark_reaped(&self) {
+        self.reaped.store(true, Ordering::SeqCst);
+    }
+
+    /// 同步结束进程，供回收任务可能已随运行时停止的退出路径使用。
+    /// 有 Job Object 时直接结束整个 Job，即使进程已被回收也不会波及无关进程
+    pub fn kill_now(&self) {
+        self.kill();
+        if !self.tree.terminate_job() {
+            if let Some(pid) = self.verified_pid() {
+                kill_tree(pid);
+            }
+        }
+    }
+}
+
+/// 子进程及其派生进程的结束范围，进程启动后立即由 [`contain`] 建立。
+/// Unix 为 [`isolate_group`] 建立的进程组；Windows 为设置了 KILL_ON_JOB_CLOSE 的 Job Object，
+/// 之后派生的进程都归入其中，即使父进程先退出、孙进程被重新挂靠（COMSOL 的 JVM）也能一并结束。
+/// 最后一个副本释放时 Job 随之关闭，其中残留的进程由系统结束
+#[derive(Clone, Debug, Default)]
+pub struct TreeGuard {
+    #[cfg(windows)]
+    job: Option<Arc<job::Job>>,
+}
+
+impl TreeGuard {
+    /// 结束以 pid 为根的进程树：Windows 上有 Job 时结束整个 Job，否则按父子关系结束
+    pub fn kill(&self, pid: u32) {
+        if !self.terminate_job() {
+            kill_tree(pid);
+        }
+    }
+
+    /// 结束 Job 中的全部进程；没有 Job（非 Windows 或创建失败）时返回 false
+    fn terminate_job(&self) -> bool {
+        #[cfg(windows)]
+        if let Some(ref job) = self.job {
+            return job.terminate();
+        }
+        false
+    }
+
+    /// Job Object 句柄，供设置资源上限与查询内存峰值
+    #[cfg(windows)]
+    pub fn job_handle(&self) -> Option<usize> {
+        self.job.as_ref().map(|j| j.handle())
+    }
+}
+
+/// 进程启动后立即调用，建立其进程树的结束范围（Windows 上把进程加入新的 Job Object）；
+/// 失败时退回按父子关系结束
+pub fn contain(child: &Child) -> TreeGuard {
+    #[cfg(windows)]
+    {
+        let job = child.id().and_then(|pid| match job::Job::assign(pid) {
+            Ok(job) => Some(Arc::new(job)),
+            Err(e) => {
+                eprintln!("Warning: 无法把子进程 {} 加入 Job Object: {}", pid, e);
+                None
+            }
+        });
+        TreeGuard { job }
+    }
+    #[cfg(not(windows))]
+    {
+        let _ = child;
+        TreeGuard::default()
+    }
+}
+
+#[cfg(windows)]
+mod job {
+    use windows_sys::Win32::Foundation::CloseHandle;
+    use windows_sys::Win32::System::JobObjects::{
+        AssignProcessToJobObject, CreateJobObjectW, JobObjectExtendedLimitInformation,
+        SetInformationJobObject, TerminateJobObject, JOBOBJECT_EXTENDED_LIMIT_INFORMATION,
+        JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE,
+    };
+    use windows_sys::Win32::System::Threading::{
+        OpenProcess, PROCESS_SET_QUOTA, PROCESS_TERMINATE,
+    };
+
+    /// 关闭句柄时结束其中全部进程的 Job Object
+    #[derive(Debug)]
+    pub struct Job(usize);
+
+    impl Job {
+        pub fn assign(pid: u32) -> Result<Self, String> {
+            unsafe {
+                let handle = CreateJobObjectW(std::ptr::null(), std::ptr::null());
+                if handle.is_null() {
+                    return Err(std::io::Error::last_os_error().to_string());
+                }
+                // 先交给 Job，之后任一步失败时由 Drop 关闭句柄
+                let job = Job(handle as usize);
+                let mut info: JOBOBJECT_EXTENDED_LIMIT_INFORMATION = std::mem::zeroed();
+                info.BasicLimitInformation.LimitFlags = JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE;
+                let ok = SetInformationJobObject(
+                    handle,
+                    JobObjectExtendedLimitInformation,
+                    &info as *const _ as *const _,
+                    std::mem::size_of::<JOBOBJECT_EXTENDED_LIMIT_INFORMATION>() as u32,
+                );
+                if ok == 0 {
+                    return Err(std::io::Error::last_os_error().to_string());
+                }
+                let process = OpenProcess(PROCESS_SET_QUOTA | PROCESS_TERMINATE, 0, pid);
+                if process.is_null() {
+                    return Err(std::io::Error::last_os_error().to_string());
+                }
+                let ok = AssignProcessToJobObject(handle, process);
+                CloseHandle(process);
+                if ok == 0 {
+                    return Err(std::io::Error::last_os_error().to_string());
+                }
+                Ok(job)
+            }
+        }
+
+        pub fn handle(&self) -> usize {
+            self.0
+        }
+
+        pub fn terminate(&self) -> bool {
+            unsafe { TerminateJobObject(self.0 as _, 1) != 0 }
+        }
+    }
+
+    impl Drop for Job {
+        fn drop(&mut self) {
+            unsafe {
+                CloseHandle(self.0 as _);
+            }
+        }
+    }
+}
+
+/// 让子进程自成一个进程组（Unix），之后可按组结束它派生的所有进程。
+/// Windows 在启动后由 [`contain`] 把进程加入 Job Object，无需额外设置
+pub fn isolate_group(builder: &mut Command) {
+    #[cfg(unix)]
+    builder.process_group(0);
+    #[cfg(not(unix))]
+    let _ = builder;
+}
+
+/// 强制结束进程及其派生的进程（Windows 上按父子关系，已被重新挂靠的孙进程会漏掉，
+/// 有 [`TreeGuard`] 时应经由它结束）。
+/// 调用方需保证 pid 仍属于该子进程（尚未被回收，或已核对启动时间）
+pub fn kill_tree(pid: u32) {
+    #[cfg(unix)]
+    {
+        // 先结束整个进程组；子进程不是组长（未经 isolate_group 启动）时该组不存在，退回单个 pid
+        let group = std::process::Command::new("kill")
+            .args(["-9", "--", &format!("-{}", pid)])
+            .status();
+        if !group.is_ok_and(|s| s.success()) {
+            let _ = std::process::Command::new("kill")
+                .args(["-9", &pid.to_string()])
+                .status();
+        }
+    }
+    #[cfg(windows)]
+    {
+        let _ = std::process::Command::new("taskkill")
+            .args(["/F", "/T", "/PID"])
+            .arg(pid.to_string())
+            .status();
+    }
+}
+
+/// 进程及其派生的进程的 pid（kill_tree 将结束的范围），用于事后报告；查询失败时只含 pid 本身
+pub fn process_tree(pid: u32) -> Vec<u32> {
+    #[cfg(unix)]
+    {
+        // isolate_group 启动的子进程是组长，组内即其派生的所有进程
+        let mut pids: Vec<u32> = std::process::Command::new("pgrep")
+            .args(["-g", &pid.to_string()])
+            .output()
+            .map(|o| {
+                String::from_utf8_lossy(&o.stdout)
+                    .split_whitespace()
+                    .filter_map(|p| p.parse().ok())
+                    .collect()
+            })
+            .unwrap_or_default();
+        if !pids.contains(&pid) {
+            pids.insert(0, pid);
+        }
+        pids
+    }
+    #[cfg(windows)]
+    {
+        let output = std::process::Command::new("powershell")
+            .args([
+                "-NoProfile",
+                "-NonInteractive",
+                "-Command",
+                "Get-CimInstance Win32_Process | ForEach-Object { \"$($_.ProcessId) $($_.ParentProcessId)\" }",
+            ])
+            .output();
+        let pairs: Vec<(u32, u32)> = output
+            .map(|o| {
+                String::from_utf8_lossy(&o.stdout)
+                    .lines()
+                    .filter_map(|l| {
+                        let (child, parent) = l.trim().split_once(' ')?;
+                        Some((child.parse().ok()?, parent.parse().ok()?))
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        // 按父子关系逐层展开，与 taskkill /T 的范围一致
+        let mut pids = vec![pid];
+        let mut i = 0;
+        while i < pids.len() {
+            let parent = pids[i];
+            for &(child, p) in &pairs {
+                if p == parent && child != parent && !pids.contains(&child) {
+                    pids.push(child);
+                }
+            }
+            i += 1;
+        }
+        pids
+    }
+}
+
+/// 读取进程启动时间；进程不存在时返回 None
+pub fn process_start_time(pid: u32) -> Option<String> {
+    #[cfg(target_os = "linux")]
+    {
+        // comm 字段可能含空格，从最后一个 ')' 之后开始数；starttime 是第 22 个字段
+        let stat = std::fs::read_to_string(format!("/proc/{}/stat", pid)).ok()?;
+        let rest = &stat[stat.rfind(')')? + 1..];
+        rest.split_whitespace().nth(19).map(String::from)
+    }
+    #[cfg(target_os = "windows")]
+    {
+        let output = std::process::Command::new("powershell")
+            .args([
+                "-NoProfile",
+                "-NonInteractive",
+                "-Command",
+                &format!("(Get-Process -Id {}).StartTime.ToFileTimeUtc()", pid),
+            ])
+            .output()
+            .ok()?;
+        let text = String::from_utf8_lossy(&output.stdout).trim().to_string();
+        (output.status.success() && !text.is_empty()).then_some(text)
+    }
+    #[cfg(not(any(target_os = "linux", target_os = "windows")))]
+    {
+        let output = std::process::Command::new("ps")
+            .args(["-o", "lstart=", "-p", &pid.to_string()])
+            .output()
+            .ok()?;
+        let text = String::from_utf8_lossy(&output.stdout).trim().to_string();
+        (output.status.success() && !text.is_empty()).then_some(text)
+    }
+}
+
+enum Step {
+    Exited(std::io::Result<ExitStatus>),
+    Kill,
+}
+
+/// 把 Child 交给回收任务：持续 `wait()`，收到结束请求时结束整棵进程树后继续等待回收。
+/// tree 为启动后由 [`contain`] 建立的结束范围
+pub fn reap(mut child: Child, tree: TreeGuard) -> (ChildHandle, ExitReceiver) {
+    let pid = child.id().unwrap_or(0);
+    let start_time = process_start_time(pid);
+    let reaped = Arc::new(AtomicBool::new(false));
+    let (kill_tx, mut kill_rx) = mpsc::unbounded_channel::<()>();
+    let (exit_tx, exit_rx) = oneshot::channel();
+    let flag = reaped.clone();
+    let killer = tree.clone();
+    tokio::spawn(async move {
+        let mut requests_open = true;
+        let status = loop {
+            let step = {
+                let mut wait = std::pin::pin!(child.wait());
+                std::future::poll_fn(|cx| {
+                    if let Poll::Ready(status) = wait.as_mut().poll(cx) {
+                        return Poll::Ready(Step::Exited(status));
+                    }
+                    if requests_open {
+                        match kill_rx.poll_recv(cx) {
+                            Poll::Ready(Some(())) => return Poll::Ready(Step::Kill),
+                            // 句柄全部丢弃，之后只等待退出
+                            Poll::Ready(None) => requests_open = false,
+                            Poll::Pending => {}
+                        }
+                    }
+                    Poll::Pending
+                })
+                .await
+            };
+            match step {
+                Step::Exited(status) => break status,
+                Step::Kill => {
+                    // 尚未 wait 到退出，pid 不会被复用
+                    killer.kill(pid);
+                    if let Err(e) = child.start_kill() {
+                        eprintln!("Warning: 结束子进程 {} 失败: {}", pid, e);
+                    }
+                }
+            }
+        };
+        flag.store(true, Ordering::SeqCst);
+        let _ = exit_tx.send(status);
+    });
+    (
+        ChildHandle {
+            pid,
+            start_time,
+            reaped,
+            kill_tx,
+            tree,
+        },
+        exit_rx,
+    )
+}
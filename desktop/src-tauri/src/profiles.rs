@@ -0,0 +1,287 @@
+use crate::event_routing;
+use crate::panics::guarded;
+use crate::store::{load_json, now_ms, save_json};
+use crate::zoom;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager, WebviewWindowBuilder};
+
+/// 默认档案直接使用 app data / app config 根目录，兼容引入档案之前的数据
+pub const DEFAULT_PROFILE: &str = "default";
+const PROFILES_FILE: &str = "profiles.json";
+const PROFILE_ENV: &str = "MPH_AGENT_PROFILE";
+const MAX_NAME_LEN: usize = 32;
+/// 与 agent/utils/config.py 中的 DATA_DIR_ENV / ENV_FILE_ENV 对应
+const BRIDGE_DATA_ENV: &str = "MPH_AGENT_DATA_DIR";
+const BRIDGE_ENV_FILE_ENV: &str = "MPH_AGENT_ENV_FILE";
+
+/// 非默认档案下 bridge 的数据目录与 .env 路径；默认档案为 None，沿用安装目录与项目根 .env
+static BRIDGE_DIRS: Mutex<Option<(PathBuf, PathBuf)>> = Mutex::new(None);
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ProfileInfo {
+    pub name: String,
+    pub created_at: u64,
+    #[serde(default)]
+    pub last_used_at: Option<u64>,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+struct ProfilesFile {
+    /// 下次启动使用的档案
+    #[serde(default)]
+    active: Option<String>,
+    #[serde(default)]
+    profiles: Vec<ProfileInfo>,
+}
+
+/// 本次运行使用的档案：设置、密钥（webview 存储）、任务历史、最近列表都按档案隔离
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct ActiveProfile {
+    pub name: String,
+    pub data_dir: Option<PathBuf>,
+    pub config_dir: Option<PathBuf>,
+    /// webview 的 localStorage 等数据目录；默认档案沿用系统默认位置
+    pub webview_dir: Option<PathBuf>,
+}
+
+/// 档案名只允许字母、数字、`-`、`_`，避免拼进路径时越界
+fn validate_name(name: &str) -> Result<String, String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("档案名为空".to_string());
+    }
+    if name.chars().count() > MAX_NAME_LEN {
+        return Err(format!("档案名不能超过 {} 个字符", MAX_NAME_LEN));
+    }
+    if !name
+        .chars()
+        .all(|c| c.is_alphanumeric() || c == '-' || c == '_')
+    {
+        return Err("档案名只能包含字母、数字、- 和 _".to_string());
+    }
+    Ok(name.to_string())
+}
+
+/// 命令行 `--profile <name>` / `--profile=<name>`
+fn profile_from_args() -> Option<String> {
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if let Some(v) = arg.strip_prefix("--profile=") {
+            return Some(v.to_string());
+        }
+        if arg == "--profile" {
+            return args.next();
+        }
+    }
+    None
+}
+
+fn profile_dir(base: &Option<PathBuf>, name: &str) -> Option<PathBuf> {
+    base.as_ref().map(|b| {
+        if name == DEFAULT_PROFILE {
+            b.clone()
+        } else {
+            b.join("profiles").join(name)
+        }
+    })
+}
+
+fn profiles_path(base_data: &Path) -> PathBuf {
+    base_data.join(PROFILES_FILE)
+}
+
+/// 启动时确定档案：命令行参数 > 环境变量 > 上次选择 > 默认档案
+pub fn resolve(base_data: &Option<PathBuf>, base_config: &Option<PathBuf>) -> ActiveProfile {
+    let file: ProfilesFile = base_data
+        .as_ref()
+        .map(|d| load_json(&profiles_path(d)))
+        .unwrap_or_default();
+    let requested = profile_from_args()
+        .or_else(|| std::env::var(PROFILE_ENV).ok())
+        .or(file.active);
+    let name = match requested.map(|n| validate_name(&n)) {
+        Some(Ok(n)) => n,
+        Some(Err(e)) => {
+            eprintln!("Warning: 忽略无效的档案名: {}", e);
+            DEFAULT_PROFILE.to_string()
+        }
+        None => DEFAULT_PROFILE.to_string(),
+    };
+    if let Some(ref d) = base_data {
+        if let Err(e) = touch(d, &name, false) {
+            eprintln!("Warning: 记录档案失败: {}", e);
+        }
+    }
+    let data_dir = profile_dir(base_data, &name);
+    let webview_dir = (name != DEFAULT_PROFILE)
+        .then(|| data_dir.as_ref().map(|d| d.join("webview")))
+        .flatten();
+    let config_dir = profile_dir(base_config, &name);
+    if name != DEFAULT_PROFILE {
+        if let (Some(data), Some(config)) = (&data_dir, &config_dir) {
+            set_bridge_dirs(data.join("bridge"), config.join(".env"));
+        }
+    }
+    ActiveProfile {
+        data_dir,
+        config_dir,
+        webview_dir,
+        name,
+    }
+}
+
+fn set_bridge_dirs(data: PathBuf, env_file: PathBuf) {
+    for dir in [Some(data.as_path()), env_file.parent()]
+        .into_iter()
+        .flatten()
+    {
+        if let Err(e) = std::fs::create_dir_all(dir) {
+            eprintln!("Warning: 创建档案目录 {} 失败: {}", dir.display(), e);
+        }
+    }
+    *BRIDGE_DIRS.lock().unwrap_or_else(|e| e.into_inner()) = Some((data, env_file));
+}
+
+fn bridge_dirs() -> Option<(PathBuf, PathBuf)> {
+    BRIDGE_DIRS
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone()
+}
+
+/// 启动 bridge 时附加的环境变量，使 Python 端的 .context 与 .env 也按档案隔离
+pub fn env_vars() -> Vec<(&'static str, PathBuf)> {
+    match bridge_dirs() {
+        Some((data, env_file)) => vec![(BRIDGE_DATA_ENV, data), (BRIDGE_ENV_FILE_ENV, env_file)],
+        None => Vec::new(),
+    }
+}
+
+/// 沙箱需放行写入的档案目录（bridge 数据目录与 .env 所在目录）
+pub fn bridge_writable_dirs() -> Vec<PathBuf> {
+    match bridge_dirs() {
+        Some((data, env_file)) => std::iter::once(data)
+            .chain(env_file.parent().map(Path::to_path_buf))
+            .collect(),
+        None => Vec::new(),
+    }
+}
+
+/// 登记档案的使用时间；`activate` 时同时设为下次启动的档案
+fn touch(base_data: &Path, name: &str, activate: bool) -> Result<(), String> {
+    let path = profiles_path(base_data);
+    let mut file: ProfilesFile = load_json(&path);
+    let now = now_ms();
+    match file.profiles.iter_mut().find(|p| p.name == name) {
+        Some(p) => p.last_used_at = Some(now),
+        None => file.profiles.push(ProfileInfo {
+            name: name.to_string(),
+            created_at: now,
+            last_used_at: Some(now),
+        }),
+    }
+    if activate {
+        file.active = Some(name.to_string());
+    }
+    save_json(&path, &file)
+}
+
+/// tauri.conf.json 中 `create: false` 的窗口在这里创建，以便按档案指定 webview 数据目录
+pub fn create_windows(app: &tauri::App, profile: &ActiveProfile) -> Result<(), String> {
+    for config in app.config().app.windows.iter().filter(|w| !w.create) {
+        let mut builder =
+            WebviewWindowBuilder::from_config(app.handle(), config).map_err(|e| e.to_string())?;
+        if let Some(ref dir) = profile.webview_dir {
+            builder = builder.data_directory(dir.clone());
+        }
+        let window = builder
+            .build()
+            .map_err(|e| format!("创建窗口 {} 失败: {}", config.label, e))?;
+        zoom::attach(app.handle(), &window);
+        event_routing::attach(app.handle(), &window);
+    }
+    Ok(())
+}
+
+/// 其他窗口应与主窗口共用当前档案的 webview 数据目录
+pub fn webview_data_dir(app: &AppHandle) -> Option<PathBuf> {
+    app.try_state::<ActiveProfile>()?.webview_dir.clone()
+}
+
+fn base_data_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    app.path().app_data_dir().map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn profiles_list(
+    app: AppHandle,
+    active: tauri::State<'_, ActiveProfile>,
+) -> Result<serde_json::Value, String> {
+    guarded("profiles_list", async move {
+        let file: ProfilesFile = load_json(&profiles_path(&base_data_dir(&app)?));
+        Ok(serde_json::json!({
+            "active": active.name,
+            "profiles": file.profiles,
+        }))
+    })
+    .await
+}
+
+/// 切换到指定档案（不存在则新建），随后重启应用使其生效；
+/// `restart` 不会触发 RunEvent::Exit，所以先走一遍与退出相同的子进程关闭流程
+#[tauri::command]
+pub async fn switch_profile(
+    app: AppHandle,
+    active: tauri::State<'_, ActiveProfile>,
+    name: String,
+) -> Result<(), String> {
+    guarded("switch_profile", async move {
+        let name = validate_name(&name)?;
+        if name == active.name {
+            return Ok(());
+        }
+        touch(&base_data_dir(&app)?, &name, true)?;
+        crate::shutdown_children(&app).await;
+        app.restart()
+    })
+    .await
+}
+
+/// 删除档案及其全部数据；不能删除当前档案或默认档案
+#[tauri::command]
+pub async fn profile_delete(
+    app: AppHandle,
+    active: tauri::State<'_, ActiveProfile>,
+    name: String,
+) -> Result<(), String> {
+    guarded("profile_delete", async move {
+        let name = validate_name(&name)?;
+        if name == DEFAULT_PROFILE || name == active.name {
+            return Err("不能删除默认档案或当前使用的档案".to_string());
+        }
+        let base_data = base_data_dir(&app)?;
+        let base_config = app.path().app_config_dir().ok();
+        for dir in [
+            profile_dir(&Some(base_data.clone()), &name),
+            profile_dir(&base_config, &name),
+        ]
+        .into_iter()
+        .flatten()
+        {
+            if dir.exists() {
+                std::fs::remove_dir_all(&dir)
+                    .map_err(|e| format!("删除 {} 失败: {}", dir.display(), e))?;
+            }
+        }
+        let path = profiles_path(&base_data);
+        let mut file: ProfilesFile = load_json(&path);
+        file.profiles.retain(|p| p.name != name);
+        if file.active.as_deref() == Some(name.as_str()) {
+            file.active = None;
+        }
+        save_json(&path, &file)
+    })
+    .await
+}
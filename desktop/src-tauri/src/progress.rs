@@ -0,0 +1,206 @@
+use crate::eta::{estimate, Baseline};
+use crate::panics::guarded;
+use crate::store::now_ms;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
+
+/// 规划阶段各 task_phase 对应的进度下限（百分比）
+const PHASE_FLOORS: &[(&str, f64)] = &[
+    ("planning", 5.0),
+    ("scanning_capabilities", 10.0),
+    ("thinking", 15.0),
+    ("planning_clarify", 18.0),
+    ("planning_steps", 20.0),
+    ("plan_confirmed", 25.0),
+];
+/// 执行步骤占用的进度区间：25% ~ 95%，剩余留给保存/收尾
+const STEPS_START: f64 = 25.0;
+const STEPS_SPAN: f64 = 70.0;
+
+#[derive(Clone, Debug, Serialize)]
+pub struct JobProgress {
+    pub request_id: String,
+    pub cmd: String,
+    pub stage: String,
+    pub percent: f64,
+    pub message: Option<String>,
+    pub started_at: u64,
+    pub updated_at: u64,
+    pub finished: bool,
+    pub ok: Option<bool>,
+    /// 预计剩余毫秒数
+    pub eta_ms: Option<u64>,
+    pub eta_basis: Option<&'static str>,
+    /// 参与估算的相似历史任务数
+    pub eta_samples: usize,
+    /// 各阶段首次进入时距任务开始的毫秒数，结束后写入任务记录供后续估算
+    #[serde(skip)]
+    pub stage_offsets: BTreeMap<String, u64>,
+    #[serde(skip)]
+    baseline: Option<Arc<Baseline>>,
+}
+
+impl JobProgress {
+    fn update_eta(&mut self, now: u64) {
+        let (eta, basis) = estimate(self, self.baseline.as_deref(), now);
+        self.eta_ms = eta;
+        self.eta_basis = Some(basis);
+    }
+}
+
+#[derive(Default)]
+struct ProgressInner {
+    jobs: HashMap<String, JobProgress>,
+    current: Option<String>,
+}
+
+/// 进度聚合器：把 bridge 的流式事件折算成「阶段 + 百分比」，供进度窗口、托盘等复用
+#[derive(Default)]
+pub struct ProgressAggregator {
+    inner: Mutex<ProgressInner>,
+}
+
+impl ProgressAggregator {
+    /// `baseline` 为相似历史任务的耗时，用于估算剩余时间
+    pub fn start(&self, request_id: &str, cmd: &str, baseline: Option<Baseline>) -> JobProgress {
+        let now = now_ms();
+        let mut job = JobProgress {
+            request_id: request_id.to_string(),
+            cmd: cmd.to_string(),
+            stage: "starting".to_string(),
+            percent: 0.0,
+            message: None,
+            started_at: now,
+            updated_at: now,
+            finished: false,
+            ok: None,
+            eta_ms: None,
+            eta_basis: None,
+            eta_samples: baseline.as_ref().map_or(0, |b| b.samples),
+            stage_offsets: BTreeMap::new(),
+            baseline: baseline.map(Arc::new),
+        };
+        job.update_eta(now);
+        let mut inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        inner.jobs.retain(|_, j| !j.finished);
+        inner.jobs.insert(request_id.to_string(), job.clone());
+        inner.current = Some(request_id.to_string());
+        job
+    }
+
+    /// 根据一条流式事件更新进度；有变化时返回新的快照
+    pub fn observe(&self, request_id: &str, event: &Value) -> Option<JobProgress> {
+        let mut inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        let job = inner.jobs.get_mut(request_id)?;
+        let data = &event["data"];
+        let (stage, percent, message) = match event.get("type").and_then(|t| t.as_str())? {
+            "task_phase" => {
+                let phase = data["phase"].as_str()?;
+                let floor = PHASE_FLOORS
+                    .iter()
+                    .find(|(p, _)| *p == phase)
+                    .map(|(_, f)| *f);
+                (phase.to_string(), floor, None)
+            }
+            "capability_scan_progress" => {
+                let scanned = data["scanned"].as_f64().unwrap_or(0.0);
+                let total = data["total"].as_f64().unwrap_or(0.0);
+                let pct = if total > 0.0 {
+                    Some(10.0 + 5.0 * (scanned / total).min(1.0))
+                } else {
+                    None
+                };
+                ("scanning_capabilities".to_string(), pct, None)
+            }
+            kind @ ("step_start" | "step_end") => {
+                // 规划阶段预告的步骤（status=planned）不代表执行进度
+                if data["status"].as_str() == Some("planned") {
+                    return None;
+                }
+                let stage = data["step_type"].as_str().unwrap_or("step").to_string();
+                let pct = match (data["index"].as_f64(), data["total"].as_f64()) {
+                    (Some(i), Some(t)) if t > 0.0 => {
+                        let done = if kind == "step_end" { i } else { i - 1.0 };
+                        Some(STEPS_START + STEPS_SPAN * (done / t).clamp(0.0, 1.0))
+                    }
+                    _ => None,
+                };
+                (stage, pct, data["message"].as_str().map(String::from))
+            }
+            "run_end" => (
+                "finished".to_string(),
+                Some(100.0),
+                data["message"].as_str().map(String::from),
+            ),
+            _ => return None,
+        };
+        let now = now_ms();
+        job.stage_offsets
+            .entry(stage.clone())
+            .or_insert_with(|| now.saturating_sub(job.started_at));
+        job.stage = stage;
+        if let Some(p) = percent {
+            // 进度只前进不后退，避免界面来回跳动
+            job.percent = job.percent.max(p);
+        }
+        if message.is_some() {
+            job.message = message;
+        }
+        job.updated_at = now;
+        job.update_eta(now);
+        Some(job.clone())
+    }
+
+    /// 按当前时刻重新计算剩余时间
+    pub fn refresh_eta(&self, request_id: &str) -> Option<JobProgress> {
+        let mut inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        let job = inner.jobs.get_mut(request_id)?;
+        job.update_eta(now_ms());
+        Some(job.clone())
+    }
+
+    pub fn finish(
+        &self,
+        request_id: &str,
+        ok: bool,
+        message: Option<String>,
+    ) -> Option<JobProgress> {
+        let mut inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        let job = inner.jobs.get_mut(request_id)?;
+        job.finished = true;
+        job.ok = Some(ok);
+        job.eta_ms = Some(0);
+        job.stage = "finished".to_string();
+        if ok {
+            job.percent = 100.0;
+        }
+        if message.is_some() {
+            job.message = message;
+        }
+        job.updated_at = now_ms();
+        Some(job.clone())
+    }
+
+    /// 当前（最近启动的）任务进度
+    pub fn current(&self) -> Option<JobProgress> {
+        let inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        inner
+            .current
+            .as_ref()
+            .and_then(|id| inner.jobs.get(id))
+            .cloned()
+    }
+}
+
+#[tauri::command]
+pub async fn job_progress_current(
+    progress: tauri::State<'_, ProgressAggregator>,
+) -> Result<Option<JobProgress>, String> {
+    guarded(
+        "job_progress_current",
+        async move { Ok(progress.current()) },
+    )
+    .await
+}
@@ -0,0 +1,123 @@
+use crate::event_routing;
+use crate::panics::guarded;
+use crate::profiles::webview_data_dir;
+use crate::zoom;
+use tauri::{
+    AppHandle, Manager, PhysicalPosition, WebviewUrl, WebviewWindow, WebviewWindowBuilder,
+};
+
+pub const PROGRESS_WINDOW_LABEL: &str = "progress-mini";
+const WIDTH: f64 = 300.0;
+const HEIGHT: f64 = 96.0;
+/// 贴角时与屏幕工作区边缘的间距（逻辑像素）
+const MARGIN: f64 = 16.0;
+const DEFAULT_CORNER: &str = "bottom-right";
+
+/// 把窗口吸附到当前显示器工作区（不含任务栏）的指定角落
+fn snap_to_corner(window: &WebviewWindow, corner: &str) -> Result<(), String> {
+    let monitor = window
+        .current_monitor()
+        .map_err(|e| e.to_string())?
+        .ok_or("无法获取当前显示器")?;
+    let area = monitor.work_area();
+    let size = window.outer_size().map_err(|e| e.to_string())?;
+    let margin = (MARGIN * monitor.scale_factor()) as i32;
+
+    let left = area.position.x + margin;
+    let top = area.position.y + margin;
+    let right = area.position.x + area.size.width as i32 - size.width as i32 - margin;
+    let bottom = area.position.y + area.size.height as i32 - size.height as i32 - margin;
+    let (x, y) = match corner {
+        "top-left" => (left, top),
+        "top-right" => (right, top),
+        "bottom-left" => (left, bottom),
+        "bottom-right" => (right, bottom),
+        other => return Err(format!("未知的角落位置: {}", other)),
+    };
+    window
+        .set_position(PhysicalPosition::new(x, y))
+        .map_err(|e| e.to_string())
+}
+
+fn progress_window(app: &AppHandle) -> Result<WebviewWindow, String> {
+    app.get_webview_window(PROGRESS_WINDOW_LABEL)
+        .ok_or_else(|| "进度窗口未打开".to_string())
+}
+
+/// 打开置顶的迷你进度窗口（无边框、不占任务栏），内容由 `job-progress` 事件驱动
+#[tauri::command]
+pub async fn progress_window_open(
+    app: AppHandle,
+    corner: Option<String>,
+    click_through: Option<bool>,
+) -> Result<(), String> {
+    guarded("progress_window_open", async move {
+        let window = match app.get_webview_window(PROGRESS_WINDOW_LABEL) {
+            Some(w) => {
+                w.show().map_err(|e| e.to_string())?;
+                w
+            }
+            None => {
+                let mut builder = WebviewWindowBuilder::new(
+                    &app,
+                    PROGRESS_WINDOW_LABEL,
+                    WebviewUrl::App("index.html#/mini-progress".into()),
+                )
+                .title("任务进度")
+                .inner_size(WIDTH, HEIGHT)
+                .decorations(false)
+                .always_on_top(true)
+                .resizable(false)
+                .skip_taskbar(true)
+                .focused(false);
+                if let Some(dir) = webview_data_dir(&app) {
+                    builder = builder.data_directory(dir);
+                }
+                let window = builder
+                    .build()
+                    .map_err(|e| format!("创建进度窗口失败: {}", e))?;
+                zoom::attach(&app, &window);
+                event_routing::attach(&app, &window);
+                window
+            }
+        };
+        snap_to_corner(&window, corner.as_deref().unwrap_or(DEFAULT_CORNER))?;
+        window
+            .set_ignore_cursor_events(click_through.unwrap_or(false))
+            .map_err(|e| e.to_string())
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn progress_window_close(app: AppHandle) -> Result<(), String> {
+    guarded("progress_window_close", async move {
+        if let Some(w) = app.get_webview_window(PROGRESS_WINDOW_LABEL) {
+            w.close().map_err(|e| e.to_string())?;
+        }
+        Ok(())
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn progress_window_snap(app: AppHandle, corner: String) -> Result<(), String> {
+    guarded("progress_window_snap", async move {
+        snap_to_corner(&progress_window(&app)?, &corner)
+    })
+    .await
+}
+
+/// 开启后鼠标事件穿透到下层应用，窗口仅作展示
+#[tauri::command]
+pub async fn progress_window_set_click_through(
+    app: AppHandle,
+    enabled: bool,
+) -> Result<(), String> {
+    guarded("progress_window_set_click_through", async move {
+        progress_window(&app)?
+            .set_ignore_cursor_events(enabled)
+            .map_err(|e| e.to_string())
+    })
+    .await
+}
@@ -0,0 +1,153 @@
+use crate::bridge::BridgeCapabilities;
+use crate::bridge_error::BridgeError;
+use crate::codec::Codec;
+use crate::debug_console::{self, Direction};
+use crate::dispatcher::{excerpt, read_bounded_line, LineRead, MAX_LINE_BYTES};
+use crate::framing::Framing;
+use serde_json::Value;
+use tokio::io::{AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
+
+/// 桌面端实现的 bridge 协议版本，握手时随 hello 发送
+pub const PROTOCOL_VERSION: u32 = 2;
+/// 桌面端还能对接的最旧 bridge 协议版本
+pub const MIN_BRIDGE_PROTOCOL: u32 = 2;
+/// bridge 声明命令列表时必须包含的命令
+const REQUIRED_CMDS: &[&str] = &["ping", "run", "shutdown"];
+
+fn incompatible(bridge_protocol: Option<u32>, detail: &str) -> BridgeError {
+    BridgeError::incompatible(
+        bridge_protocol,
+        format!(
+            "Incompatible: bridge 协议不兼容 (host protocol: {}, bridge protocol: {})：{}",
+            PROTOCOL_VERSION,
+            bridge_protocol.map_or_else(|| "unknown".to_string(), |v| v.to_string()),
+            detail
+        ),
+    )
+}
+
+/// 握手协商的结果
+pub struct Negotiated {
+    /// 回复中声明了命令列表时以其为准
+    pub capabilities: Option<BridgeCapabilities>,
+    /// 回复之后双方改用的分帧方式
+    pub framing: Framing,
+    /// bridge 输出的编码
+    pub codec: Codec,
+}
+
+/// 读取 hello 的回复；跳过 import 阶段可能打印到 stdout 的杂项与事件行
+async fn read_reply<R: AsyncRead + Unpin>(reader: &mut BufReader<R>) -> Result<Value, String> {
+    loop {
+        let line = match read_bounded_line(reader, MAX_LINE_BYTES)
+            .await
+            .map_err(|e| format!("读取 hello 回复失败: {}", e))?
+        {
+            LineRead::Line(line) => line,
+            LineRead::TooLong(n) => return Err(format!("hello 回复过长（{} 字节）", n)),
+            LineRead::Eof => return Err("Python 进程在回复 hello 前退出（stdout EOF）".to_string()),
+        };
+        debug_console::record(Direction::In, &line);
+        let trimmed = line.trim();
+        if !trimmed.starts_with('{') {
+            continue;
+        }
+        let reply: Value = serde_json::from_str(trimmed).map_err(|e| {
+            format!(
+                "hello 回复 JSON 解析失败: {} (内容: {})",
+                e,
+                excerpt(trimmed)
+            )
+        })?;
+        if reply.get("_event").and_then(|v| v.as_bool()) != Some(true) {
+            return Ok(reply);
+        }
+    }
+}
+
+/// 就绪信号之后、接受请求之前协商协议版本：发送 `{"cmd":"hello","protocol":N}`，
+/// 校验 bridge 回报的版本与命令列表。不兼容时返回带双方版本号的 Incompatible，
+/// 读写或解析回复失败为 SpawnFailed。hello 同时提供可用的分帧方式与编码，由 bridge 选定
+pub async fn negotiate<W, R>(
+    stdin: &mut W,
+    reader: &mut BufReader<R>,
+) -> Result<Negotiated, BridgeError>
+where
+    W: AsyncWrite + Unpin,
+    R: AsyncRead + Unpin,
+{
+    let framings = Framing::offered();
+    let hello = serde_json::json!({
+        "cmd": "hello",
+        "protocol": PROTOCOL_VERSION,
+        "codecs": Codec::offered(&framings),
+        "framing": framings,
+    })
+    .to_string();
+    debug_console::record(Direction::Out, &hello);
+    stdin
+        .write_all(format!("{}\n", hello).as_bytes())
+        .await
+        .map_err(|e| BridgeError::spawn_failed(format!("发送 hello 失败: {}", e)))?;
+    stdin
+        .flush()
+        .await
+        .map_err(|e| BridgeError::spawn_failed(format!("发送 hello 失败: {}", e)))?;
+
+    let reply = read_reply(reader)
+        .await
+        .map_err(BridgeError::spawn_failed)?;
+    let Some(protocol) = reply.get("protocol").and_then(|v| v.as_u64()) else {
+        // 旧版 bridge 不认识 hello，会回复「未知命令」之类的错误
+        let said = reply
+            .get("message")
+            .and_then(|v| v.as_str())
+            .unwrap_or_default();
+        return Err(incompatible(
+            None,
+            &format!(
+                "bridge 未回报协议版本，可能是旧版本（回复: {}）",
+                excerpt(said)
+            ),
+        ));
+    };
+    let protocol = u32::try_from(protocol).unwrap_or(u32::MAX);
+    if protocol < MIN_BRIDGE_PROTOCOL {
+        return Err(incompatible(
+            Some(protocol),
+            &format!(
+                "bridge 版本过旧，桌面端至少需要协议 {}，请更新 Python 端",
+                MIN_BRIDGE_PROTOCOL
+            ),
+        ));
+    }
+    if let Some(min_host) = reply.get("min_protocol").and_then(|v| v.as_u64()) {
+        if u64::from(PROTOCOL_VERSION) < min_host {
+            return Err(incompatible(
+                Some(protocol),
+                &format!("bridge 要求桌面端协议至少为 {}，请更新桌面端", min_host),
+            ));
+        }
+    }
+
+    let capabilities = BridgeCapabilities::from_ready(&reply);
+    if let Some(ref caps) = capabilities {
+        let missing: Vec<&str> = REQUIRED_CMDS
+            .iter()
+            .copied()
+            .filter(|c| !caps.cmds.contains_key(*c))
+            .collect();
+        if !missing.is_empty() {
+            return Err(incompatible(
+                Some(protocol),
+                &format!("bridge 缺少必需命令: {}", missing.join(", ")),
+            ));
+        }
+    }
+    let framing = Framing::from_reply(&reply).map_err(BridgeError::spawn_failed)?;
+    Ok(Negotiated {
+        capabilities,
+        codec: Codec::from_reply(&reply, framing).map_err(BridgeError::spawn_failed)?,
+        framing,
+    })
+}
@@ -0,0 +1,62 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::sync::Mutex;
+
+/// 本机的网络代理，设置文件中的 `proxy`：字符串表示 HTTP 与 HTTPS 共用同一代理，
+/// 对象可分别指定并给出不走代理的主机
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ProxyConfig {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub http: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub https: Option<String>,
+    /// 逗号分隔的主机或域名后缀
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub no_proxy: Option<String>,
+}
+
+impl ProxyConfig {
+    pub fn parse(value: &Value) -> Result<Self, String> {
+        let config = match value {
+            Value::String(url) => Self {
+                http: Some(url.clone()),
+                https: Some(url.clone()),
+                no_proxy: None,
+            },
+            _ => serde_json::from_value(value.clone()).map_err(|e| format!("格式错误: {}", e))?,
+        };
+        for url in [&config.http, &config.https].into_iter().flatten() {
+            if !url.contains("://") {
+                return Err(format!("代理地址应包含协议，如 http://host:port: {}", url));
+            }
+        }
+        Ok(config)
+    }
+}
+
+/// 子进程（bridge、curl）使用的代理；设置文件变化后即时更新，已运行的 bridge 下次启动时生效
+static ACTIVE: Mutex<Option<ProxyConfig>> = Mutex::new(None);
+
+pub fn set_active(config: Option<ProxyConfig>) {
+    *ACTIVE.lock().unwrap_or_else(|e| e.into_inner()) = config;
+}
+
+/// 传给子进程的代理环境变量；大小写两种写法都设置，curl 与 Python 各取其一
+pub fn env_vars() -> Vec<(&'static str, String)> {
+    let Some(config) = ACTIVE.lock().unwrap_or_else(|e| e.into_inner()).clone() else {
+        return Vec::new();
+    };
+    let mut vars = Vec::new();
+    let pairs = [
+        (["HTTP_PROXY", "http_proxy"], config.http),
+        (["HTTPS_PROXY", "https_proxy"], config.https),
+        (["NO_PROXY", "no_proxy"], config.no_proxy),
+    ];
+    for (names, value) in pairs {
+        if let Some(v) = value.filter(|v| !v.trim().is_empty()) {
+            vars.extend(names.map(|n| (n, v.trim().to_string())));
+        }
+    }
+    vars
+}
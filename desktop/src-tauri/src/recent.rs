@@ -0,0 +1,133 @@
+use crate::panics::guarded;
+use crate::remap::{PathRemap, RemapChange};
+use crate::store::{load_json, now_ms, save_json};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tokio::sync::watch;
+
+const MAX_RECENT: usize = 20;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RecentModel {
+    pub path: String,
+    #[serde(default)]
+    pub project: Option<String>,
+    pub opened_at: u64,
+}
+
+/// 最近生成/打开的模型列表，变更通过 `subscribe()` 通知托盘等
+pub struct RecentModels {
+    path: Option<PathBuf>,
+    items: Mutex<Vec<RecentModel>>,
+    changes: watch::Sender<u64>,
+}
+
+impl Default for RecentModels {
+    fn default() -> Self {
+        Self {
+            path: None,
+            items: Mutex::new(Vec::new()),
+            changes: watch::channel(0).0,
+        }
+    }
+}
+
+impl RecentModels {
+    pub fn load(path: PathBuf) -> Self {
+        let items: Vec<RecentModel> = load_json(&path);
+        Self {
+            path: Some(path),
+            items: Mutex::new(items),
+            ..Self::default()
+        }
+    }
+
+    fn update<T>(&self, f: impl FnOnce(&mut Vec<RecentModel>) -> T) -> T {
+        let mut items = self.items.lock().unwrap_or_else(|e| e.into_inner());
+        let out = f(&mut items);
+        if let Some(ref p) = self.path {
+            if let Err(e) = save_json(p, &*items) {
+                eprintln!("Warning: 保存最近模型列表失败: {}", e);
+            }
+        }
+        drop(items);
+        self.changes.send_modify(|v| *v += 1);
+        out
+    }
+
+    pub fn touch(&self, path: &str, project: Option<String>) {
+        self.update(|items| {
+            items.retain(|m| m.path != path);
+            items.insert(
+                0,
+                RecentModel {
+                    path: path.to_string(),
+                    project,
+                    opened_at: now_ms(),
+                },
+            );
+            items.truncate(MAX_RECENT);
+        });
+    }
+
+    pub fn list(&self) -> Vec<RecentModel> {
+        self.items.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    pub fn clear(&self) {
+        self.update(|items| items.clear());
+    }
+
+    pub fn remap(&self, remap: &PathRemap, apply: bool) -> Vec<RemapChange> {
+        let rewrite = |items: &mut Vec<RecentModel>| {
+            let mut changes = Vec::new();
+            for m in items.iter_mut() {
+                let entry = m.path.clone();
+                remap.rewrite("recent", &entry, "path", &mut m.path, &mut changes);
+                if let Some(ref mut project) = m.project {
+                    remap.rewrite("recent", &entry, "project", project, &mut changes);
+                }
+            }
+            changes
+        };
+        if apply {
+            self.update(rewrite)
+        } else {
+            rewrite(&mut self.list())
+        }
+    }
+
+    pub fn subscribe(&self) -> watch::Receiver<u64> {
+        self.changes.subscribe()
+    }
+}
+
+#[tauri::command]
+pub async fn recent_models_list(
+    recent: tauri::State<'_, RecentModels>,
+) -> Result<Vec<RecentModel>, String> {
+    guarded("recent_models_list", async move { Ok(recent.list()) }).await
+}
+
+#[tauri::command]
+pub async fn recent_models_add(
+    recent: tauri::State<'_, RecentModels>,
+    path: String,
+    project: Option<String>,
+) -> Result<(), String> {
+    guarded("recent_models_add", async move {
+        recent.touch(path.trim(), project);
+        Ok(())
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn recent_models_clear(recent: tauri::State<'_, RecentModels>) -> Result<(), String> {
+    guarded("recent_models_clear", async move {
+        recent.clear();
+        Ok(())
+    })
+    .await
+}
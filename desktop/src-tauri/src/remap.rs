@@ -0,0 +1,223 @@
+use crate::artifacts::ArtifactRegistry;
+use crate::jobs::JobRegistry;
+use crate::panics::guarded;
+use crate::policy::CommandPolicy;
+use crate::recent::RecentModels;
+use serde::Serialize;
+use std::path::Path;
+use tauri::{AppHandle, Manager};
+
+/// 把以旧根目录开头的路径改写到新根目录，如网络盘从 `Z:\sim` 重新挂载为 `Y:\sim`
+pub struct PathRemap {
+    old: String,
+    new: String,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct RemapChange {
+    /// artifacts / recent / jobs
+    pub store: &'static str,
+    /// 条目标识：产物与最近模型为原路径，任务为任务 ID
+    pub entry: String,
+    pub field: String,
+    pub from: String,
+    pub to: String,
+    /// 新路径当前是否存在，预览时据此判断前缀是否填对
+    pub exists: bool,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct RemapReport {
+    pub changes: Vec<RemapChange>,
+    /// 新路径不存在的条目数
+    pub missing: usize,
+    pub applied: bool,
+}
+
+fn trim_root(prefix: &str) -> &str {
+    prefix.trim().trim_end_matches(['/', '\\'])
+}
+
+fn same_prefix(a: &str, b: &str) -> bool {
+    let a = a.replace('\\', "/");
+    let b = b.replace('\\', "/");
+    // Windows 路径（含盘符）不区分大小写
+    if cfg!(windows) {
+        a.eq_ignore_ascii_case(&b)
+    } else {
+        a == b
+    }
+}
+
+impl PathRemap {
+    pub fn new(old_prefix: &str, new_prefix: &str) -> Result<Self, String> {
+        let (old, new) = (trim_root(old_prefix), trim_root(new_prefix));
+        if old.is_empty() || new.is_empty() {
+            return Err("路径前缀不能为空或根目录".to_string());
+        }
+        if same_prefix(old, new) {
+            return Err("新旧路径前缀相同".to_string());
+        }
+        Ok(Self {
+            old: old.to_string(),
+            new: new.to_string(),
+        })
+    }
+
+    /// 路径位于旧根目录下时返回改写后的路径；只按完整的路径段匹配
+    pub fn apply(&self, path: &str) -> Option<String> {
+        let head = path.get(..self.old.len())?;
+        let rest = &path[self.old.len()..];
+        if !same_prefix(head, &self.old) || !(rest.is_empty() || rest.starts_with(['/', '\\'])) {
+            return None;
+        }
+        Some(format!("{}{}", self.new, rest))
+    }
+
+    /// 改写一个路径字段，并记录变更
+    pub fn rewrite(
+        &self,
+        store: &'static str,
+        entry: &str,
+        field: &str,
+        value: &mut String,
+        changes: &mut Vec<RemapChange>,
+    ) {
+        let Some(to) = self.apply(value) else {
+            return;
+        };
+        changes.push(RemapChange {
+            store,
+            entry: entry.to_string(),
+            field: field.to_string(),
+            from: std::mem::replace(value, to.clone()),
+            exists: Path::new(&to).exists(),
+            to,
+        });
+    }
+}
+
+/// 批量改写产物登记、最近模型与任务记录中位于 `old_prefix` 下的路径。
+/// `dry_run` 为 true 时只返回将受影响的条目，不做修改
+#[tauri::command]
+pub async fn remap_root(
+    app: AppHandle,
+    old_prefix: String,
+    new_prefix: String,
+    dry_run: Option<bool>,
+) -> Result<RemapReport, String> {
+    guarded("remap_root", async move {
+        let remap = PathRemap::new(&old_prefix, &new_prefix)?;
+        let apply = !dry_run.unwrap_or(false);
+        if apply {
+            app.state::<CommandPolicy>().ensure_writable("重映射路径")?;
+        }
+        let mut changes = app.state::<ArtifactRegistry>().remap(&remap, apply);
+        changes.extend(app.state::<RecentModels>().remap(&remap, apply));
+        changes.extend(app.state::<JobRegistry>().remap(&remap, apply));
+        Ok(RemapReport {
+            missing: changes.iter().filter(|c| !c.exists).count(),
+            applied: apply && !changes.is_empty(),
+            changes,
+        })
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::recent::RecentModels;
+    use crate::store::now_ms;
+    use std::path::PathBuf;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "mph-remap-{}-{}-{}",
+            name,
+            std::process::id(),
+            now_ms()
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn new_rejects_empty_root_and_same_prefix() {
+        assert!(PathRemap::new("", "/mnt/new").is_err());
+        assert!(PathRemap::new("/", "/mnt/new").is_err());
+        assert!(PathRemap::new("/mnt/old", "  ").is_err());
+        assert!(PathRemap::new("/mnt/old/", "/mnt/old").is_err());
+        assert!(PathRemap::new(r"Z:\sim", "Z:/sim/").is_err());
+        assert!(PathRemap::new("/mnt/old", "/mnt/new").is_ok());
+    }
+
+    #[test]
+    fn apply_matches_whole_segments_only() {
+        let remap = PathRemap::new("/mnt/old/", "/mnt/new").unwrap();
+        assert_eq!(
+            remap.apply("/mnt/old/case/m.mph").as_deref(),
+            Some("/mnt/new/case/m.mph")
+        );
+        assert_eq!(remap.apply("/mnt/old").as_deref(), Some("/mnt/new"));
+        assert_eq!(remap.apply("/mnt/older/m.mph"), None);
+        assert_eq!(remap.apply("/mnt/ol"), None);
+        assert_eq!(remap.apply("/home/mnt/old/m.mph"), None);
+        // 截断位置落在多字节字符中间时不匹配，也不 panic
+        assert_eq!(PathRemap::new("/a", "/b").unwrap().apply("/é"), None);
+    }
+
+    #[test]
+    fn apply_accepts_either_separator() {
+        let remap = PathRemap::new(r"Z:\sim", r"Y:\sim").unwrap();
+        assert_eq!(
+            remap.apply(r"Z:\sim\a.mph").as_deref(),
+            Some(r"Y:\sim\a.mph")
+        );
+        assert_eq!(
+            remap.apply("Z:/sim/a.mph").as_deref(),
+            Some(r"Y:\sim/a.mph")
+        );
+        assert_eq!(
+            remap.apply(r"z:\sim\a.mph").is_some(),
+            cfg!(windows),
+            "盘符大小写只在 Windows 上忽略"
+        );
+    }
+
+    #[test]
+    fn rewrite_records_change_and_whether_target_exists() {
+        let dir = temp_dir("rewrite");
+        std::fs::write(dir.join("found.mph"), b"").unwrap();
+        let new_root = dir.to_string_lossy().into_owned();
+        let remap = PathRemap::new("/mnt/old", &new_root).unwrap();
+        let mut changes = Vec::new();
+        let mut found = "/mnt/old/found.mph".to_string();
+        let mut lost = "/mnt/old/lost.mph".to_string();
+        let mut other = "/elsewhere/m.mph".to_string();
+        remap.rewrite("artifacts", "a", "path", &mut found, &mut changes);
+        remap.rewrite("artifacts", "b", "path", &mut lost, &mut changes);
+        remap.rewrite("artifacts", "c", "path", &mut other, &mut changes);
+        assert_eq!(changes.len(), 2);
+        assert_eq!(changes[0].from, "/mnt/old/found.mph");
+        assert_eq!(changes[0].to, found);
+        assert!(changes[0].exists);
+        assert!(!changes[1].exists);
+        assert_eq!(other, "/elsewhere/m.mph");
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn recent_remap_previews_without_writing() {
+        let recent = RecentModels::default();
+        recent.touch("/mnt/old/m.mph", Some("/mnt/old".to_string()));
+        let remap = PathRemap::new("/mnt/old", "/mnt/new").unwrap();
+        let preview = recent.remap(&remap, false);
+        assert_eq!(preview.len(), 2);
+        assert_eq!(recent.list()[0].path, "/mnt/old/m.mph");
+        recent.remap(&remap, true);
+        let item = &recent.list()[0];
+        assert_eq!(item.path, "/mnt/new/m.mph");
+        assert_eq!(item.project.as_deref(), Some("/mnt/new"));
+    }
+}
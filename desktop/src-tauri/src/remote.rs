@@ -0,0 +1,540 @@
+use crate::abort::{abort_in_band, AbortReport};
+use crate::backend::{Bridge, BridgeFuture, BridgeStatus};
+use crate::bridge::{
+    wait_for_handshake, BridgeExit, BridgeHandles, BridgeState, StdioBridge, HANDSHAKE_TIMEOUT_SECS,
+};
+use crate::bridge_error::BridgeError;
+use crate::dispatcher::{read_bounded_line, Dispatcher, LineRead, MAX_LINE_BYTES};
+use crate::launcher::{BridgeLauncher, LaunchFuture};
+use crate::process::ChildHandle;
+use crate::protocol::negotiate;
+use crate::stderr_log::new_buf;
+use crate::store::now_ms;
+use futures_util::stream::{SplitSink, SplitStream};
+use futures_util::{SinkExt, StreamExt};
+use rustls::pki_types::ServerName;
+use rustls::{ClientConfig, RootCertStore};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::future::Future;
+use std::path::PathBuf;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::AppHandle;
+use tokio::io::{
+    AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader, DuplexStream, ReadHalf,
+    WriteHalf,
+};
+use tokio::net::TcpStream;
+use tokio::sync::watch;
+use tokio_rustls::TlsConnector;
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::http::header::AUTHORIZATION;
+use tokio_tungstenite::tungstenite::http::{HeaderValue, StatusCode};
+use tokio_tungstenite::tungstenite::protocol::WebSocketConfig;
+use tokio_tungstenite::tungstenite::{Error as WsError, Message};
+use tokio_tungstenite::{Connector, MaybeTlsStream, WebSocketStream};
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+const PIPE_BYTES: usize = 64 * 1024;
+const DEFAULT_WS_PATH: &str = "/bridge";
+/// 单条 WebSocket 消息的上限，防止异常的长度字段耗尽内存
+const MAX_MESSAGE_BYTES: usize = 64 << 20;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Transport {
+    /// 认证一行 JSON 之后直接转发字节流
+    #[default]
+    Tcp,
+    /// 字节流装在二进制消息中，便于经过反向代理
+    Websocket,
+}
+
+/// 设置文件中的 `remote`：连接到远程机器上的 `cli.py bridge-server`，
+/// 在那台机器上运行 bridge 与 COMSOL。删除该项后新启动的 bridge 恢复为本机子进程
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RemoteConfig {
+    pub host: String,
+    pub port: u16,
+    #[serde(default)]
+    pub transport: Transport,
+    /// 以 TLS 连接（WebSocket 时为 wss://），按 Mozilla 根证书校验服务端证书
+    #[serde(default)]
+    pub tls: bool,
+    /// 与服务端的 `--token` 一致；非本机地址只在 TLS 连接上发送
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token: Option<String>,
+    /// WebSocket 路径，默认 `/bridge`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+}
+
+/// 本机地址：令牌不会离开这台机器，允许明文发送
+fn is_loopback(host: &str) -> bool {
+    host.eq_ignore_ascii_case("localhost")
+        || host
+            .parse::<std::net::IpAddr>()
+            .is_ok_and(|ip| ip.is_loopback())
+}
+
+impl RemoteConfig {
+    pub fn parse(value: &Value) -> Result<Self, String> {
+        let config: Self =
+            serde_json::from_value(value.clone()).map_err(|e| format!("格式错误: {}", e))?;
+        // 这些值会写进请求行与请求头，换行等控制字符可用来注入额外的头
+        let no_control = |name: &str, v: &str| {
+            if v.contains(char::is_control) {
+                Err(format!("{} 不能含换行等控制字符", name))
+            } else {
+                Ok(())
+            }
+        };
+        no_control("host", &config.host)?;
+        if config.host.trim().is_empty() {
+            return Err("host 不能为空".to_string());
+        }
+        if config
+            .host
+            .contains(|c: char| c.is_whitespace() || "/?#@[]".contains(c))
+        {
+            return Err(format!("host 应为主机名或 IP 地址: {}", config.host));
+        }
+        if config.port == 0 {
+            return Err("port 应在 1 ~ 65535 之间".to_string());
+        }
+        if let Some(ref p) = config.path {
+            no_control("path", p)?;
+            if !p.starts_with('/') || p.contains(char::is_whitespace) {
+                return Err(format!("path 应以 / 开头且不含空白: {}", p));
+            }
+        }
+        if let Some(ref token) = config.token {
+            no_control("token", token)?;
+            if !config.tls && !is_loopback(&config.host) {
+                return Err(format!(
+                    "连接非本机地址 {} 时令牌会以明文发送，请启用 tls",
+                    config.host
+                ));
+            }
+        }
+        Ok(config)
+    }
+
+    fn ws_path(&self) -> &str {
+        self.path.as_deref().unwrap_or(DEFAULT_WS_PATH)
+    }
+
+    /// 地址中的主机部分，IPv6 地址加方括号
+    fn authority(&self) -> String {
+        if self.host.contains(':') {
+            format!("[{}]:{}", self.host, self.port)
+        } else {
+            format!("{}:{}", self.host, self.port)
+        }
+    }
+
+    fn endpoint(&self) -> String {
+        let scheme = match (self.transport, self.tls) {
+            (Transport::Tcp, false) => "tcp",
+            (Transport::Tcp, true) => "tls",
+            (Transport::Websocket, false) => "ws",
+            (Transport::Websocket, true) => "wss",
+        };
+        match self.transport {
+            Transport::Tcp => format!("{}://{}", scheme, self.authority()),
+            Transport::Websocket => format!("{}://{}{}", scheme, self.authority(), self.ws_path()),
+        }
+    }
+}
+
+/// 当前生效的远程配置；设置文件变化后即时更新，已连接的 bridge 下次启动时生效
+static ACTIVE: Mutex<Option<RemoteConfig>> = Mutex::new(None);
+
+pub fn set_active(config: Option<RemoteConfig>) {
+    *ACTIVE.lock().unwrap_or_else(|e| e.into_inner()) = config;
+}
+
+pub fn is_active() -> bool {
+    ACTIVE.lock().unwrap_or_else(|e| e.into_inner()).is_some()
+}
+
+/// 配置了远程 bridge 时的启动实现
+pub fn launcher() -> Option<Arc<dyn BridgeLauncher>> {
+    let config = ACTIVE.lock().unwrap_or_else(|e| e.into_inner()).clone()?;
+    Some(Arc::new(RemoteLauncher { config }))
+}
+
+/// 连接远程 bridge 服务：服务端为每个连接启动一个 bridge，
+/// 连接建立后的就绪信号、hello 协商与分帧和本机子进程完全相同
+pub struct RemoteLauncher {
+    config: RemoteConfig,
+}
+
+impl BridgeLauncher for RemoteLauncher {
+    fn launch(&self, _bundled_java_home: Option<PathBuf>) -> LaunchFuture {
+        Box::pin(connect(self.config.clone()))
+    }
+
+    fn describe(&self) -> String {
+        format!("remote: {}", self.config.endpoint())
+    }
+}
+
+/// 远程 bridge：排队、流式事件与任务登记沿用本机 bridge 的实现，只有中止不同——
+/// 无法向远端进程发信号，改为协议内的取消消息，未及时结束时断开重连
+pub struct RemoteBridge {
+    app: AppHandle,
+    state: BridgeState,
+    local: StdioBridge,
+}
+
+impl RemoteBridge {
+    pub fn new(app: AppHandle, state: BridgeState, session: Option<&str>) -> Self {
+        Self {
+            local: StdioBridge::new(app.clone(), state.clone(), session),
+            app,
+            state,
+        }
+    }
+}
+
+impl Bridge for RemoteBridge {
+    fn send(&self, cmd: &str, payload: Value, timeout: Duration) -> BridgeFuture<'_, Value> {
+        self.local.send(cmd, payload, timeout)
+    }
+
+    fn send_stream(
+        &self,
+        cmd: String,
+        payload: Value,
+        request_id: Option<String>,
+    ) -> BridgeFuture<'_, Value> {
+        self.local.send_stream(cmd, payload, request_id)
+    }
+
+    fn abort(&self) -> BridgeFuture<'_, AbortReport> {
+        Box::pin(abort_in_band(&self.app, &self.state))
+    }
+
+    fn health(&self) -> BridgeFuture<'_, BridgeStatus> {
+        self.local.health()
+    }
+}
+
+async fn connect(config: RemoteConfig) -> Result<BridgeHandles, BridgeError> {
+    let endpoint = config.endpoint();
+    let opened = async {
+        match config.transport {
+            Transport::Tcp => open_stream(&config).await,
+            Transport::Websocket => open_websocket(&config).await,
+        }
+    };
+    let connection = match tokio::time::timeout(CONNECT_TIMEOUT, opened).await {
+        Ok(opened) => opened.map_err(BridgeError::spawn_failed)?,
+        Err(_) => {
+            return Err(BridgeError::spawn_failed(format!(
+                "连接远程 bridge 超时 ({})",
+                endpoint
+            )))
+        }
+    };
+
+    let (mut stdin, stdout, exit, child) = Link::start(connection);
+    let mut reader = BufReader::new(stdout);
+    let handshake = async {
+        let capabilities = wait_for_handshake(&mut reader)
+            .await
+            .map_err(|e| BridgeError::spawn_failed(format!("远程 bridge 握手失败: {}", e)))?;
+        let negotiated = negotiate(&mut stdin, &mut reader)
+            .await
+            .map_err(|e| match e {
+                BridgeError::Incompatible { .. } => e,
+                e => e.map_message(|m| format!("远程 bridge 协议协商失败: {}", m)),
+            })?;
+        Ok::<_, BridgeError>((capabilities, negotiated))
+    };
+    let timeout = Duration::from_secs(HANDSHAKE_TIMEOUT_SECS);
+    let (capabilities, negotiated) = match tokio::time::timeout(timeout, handshake).await {
+        Ok(Ok(done)) => done,
+        Ok(Err(e)) => {
+            child.kill();
+            return Err(e);
+        }
+        Err(_) => {
+            child.kill();
+            return Err(BridgeError::spawn_failed(format!(
+                "远程 bridge 握手超时 ({}s): {}",
+                HANDSHAKE_TIMEOUT_SECS, endpoint
+            )));
+        }
+    };
+    Ok(BridgeHandles {
+        dispatcher: Dispatcher::start(stdin, reader, negotiated.framing, negotiated.codec),
+        exit,
+        child,
+        stderr_buf: new_buf(),
+        capabilities: negotiated.capabilities.or(capabilities),
+    })
+}
+
+/// TCP 或 TLS 上的字节流
+trait ByteStream: AsyncRead + AsyncWrite + Send + Unpin {}
+
+impl<T: AsyncRead + AsyncWrite + Send + Unpin> ByteStream for T {}
+
+type WsStream = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+/// 已认证的连接
+enum Connection {
+    /// 读端保留认证时已缓冲的数据
+    Stream {
+        read: BufReader<ReadHalf<Box<dyn ByteStream>>>,
+        write: WriteHalf<Box<dyn ByteStream>>,
+    },
+    Websocket(Box<WsStream>),
+}
+
+/// 校验服务端证书的 TLS 客户端配置，信任 Mozilla 根证书（webpki-roots）
+fn tls_config() -> Result<Arc<ClientConfig>, String> {
+    let roots = RootCertStore {
+        roots: webpki_roots::TLS_SERVER_ROOTS.to_vec(),
+    };
+    let provider = Arc::new(rustls::crypto::ring::default_provider());
+    let config = ClientConfig::builder_with_provider(provider)
+        .with_safe_default_protocol_versions()
+        .map_err(|e| format!("初始化 TLS 失败: {}", e))?
+        .with_root_certificates(roots)
+        .with_no_client_auth();
+    Ok(Arc::new(config))
+}
+
+/// TCP（可选 TLS）：先发一行 `{"token": ...}`，服务端回复 `{"ok": true}` 后开始转发
+async fn open_stream(config: &RemoteConfig) -> Result<Connection, String> {
+    let endpoint = config.endpoint();
+    let tcp = TcpStream::connect((config.host.as_str(), config.port))
+        .await
+        .map_err(|e| format!("连接远程 bridge 失败 ({}): {}", endpoint, e))?;
+    let _ = tcp.set_nodelay(true);
+    let stream: Box<dyn ByteStream> = if config.tls {
+        let name = ServerName::try_from(config.host.clone())
+            .map_err(|e| format!("无效的主机名 {}: {}", config.host, e))?;
+        let tls = TlsConnector::from(tls_config()?)
+            .connect(name, tcp)
+            .await
+            .map_err(|e| format!("TLS 握手失败 ({}): {}", endpoint, e))?;
+        Box::new(tls)
+    } else {
+        Box::new(tcp)
+    };
+    let (read, mut write) = tokio::io::split(stream);
+    let mut read = BufReader::new(read);
+    authenticate(&mut read, &mut write, config).await?;
+    Ok(Connection::Stream { read, write })
+}
+
+async fn authenticate(
+    read: &mut BufReader<ReadHalf<Box<dyn ByteStream>>>,
+    write: &mut WriteHalf<Box<dyn ByteStream>>,
+    config: &RemoteConfig,
+) -> Result<(), String> {
+    let hello = serde_json::json!({ "token": config.token }).to_string() + "\n";
+    write
+        .write_all(hello.as_bytes())
+        .await
+        .map_err(|e| format!("发送认证信息失败: {}", e))?;
+    let line = match read_bounded_line(read, MAX_LINE_BYTES).await {
+        Ok(LineRead::Line(line)) => line,
+        Ok(_) => return Err("远程 bridge 未回复认证结果".to_string()),
+        Err(e) => return Err(format!("读取认证结果失败: {}", e)),
+    };
+    let reply: Value = serde_json::from_str(line.trim()).unwrap_or_default();
+    if reply.get("ok").and_then(Value::as_bool) == Some(true) {
+        return Ok(());
+    }
+    Err(format!(
+        "远程 bridge 拒绝连接: {}",
+        reply
+            .get("message")
+            .and_then(Value::as_str)
+            .unwrap_or("未知原因")
+    ))
+}
+
+/// WebSocket（TLS 时为 wss）：令牌放在 Authorization 头中，升级响应（含 Sec-WebSocket-Accept）
+/// 由 tungstenite 校验
+async fn open_websocket(config: &RemoteConfig) -> Result<Connection, String> {
+    let endpoint = config.endpoint();
+    let mut request = endpoint
+        .as_str()
+        .into_client_request()
+        .map_err(|e| format!("无效的远程地址 {}: {}", endpoint, e))?;
+    if let Some(ref token) = config.token {
+        let value = HeaderValue::from_str(&format!("Bearer {}", token))
+            .map_err(|_| "token 含有不能放入请求头的字符".to_string())?;
+        request.headers_mut().insert(AUTHORIZATION, value);
+    }
+    let limits = WebSocketConfig {
+        max_message_size: Some(MAX_MESSAGE_BYTES),
+        max_frame_size: Some(MAX_MESSAGE_BYTES),
+        ..Default::default()
+    };
+    let connector = if config.tls {
+        Connector::Rustls(tls_config()?)
+    } else {
+        Connector::Plain
+    };
+    let (socket, _) = tokio_tungstenite::connect_async_tls_with_config(
+        request,
+        Some(limits),
+        true,
+        Some(connector),
+    )
+    .await
+    .map_err(|e| match e {
+        WsError::Http(ref response)
+            if matches!(
+                response.status(),
+                StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN
+            ) =>
+        {
+            "远程 bridge 拒绝连接: 令牌无效".to_string()
+        }
+        e => format!("WebSocket 握手失败 ({}): {}", endpoint, e),
+    })?;
+    Ok(Connection::Websocket(Box::new(socket)))
+}
+
+type Pump = Pin<Box<dyn Future<Output = std::io::Result<()>> + Send>>;
+
+/// 一条远程连接：在本地管道与网络连接之间转发字节，对上层表现得和子进程的 stdin/stdout 一样。
+/// 连接断开时写入退出状态；结束请求（stop_child 等）到达时中断转发并关闭连接
+struct Link {
+    exit: watch::Sender<Option<BridgeExit>>,
+    handle: ChildHandle,
+}
+
+impl Link {
+    fn start(
+        connection: Connection,
+    ) -> (
+        DuplexStream,
+        DuplexStream,
+        watch::Receiver<Option<BridgeExit>>,
+        ChildHandle,
+    ) {
+        let (stdin, upstream) = tokio::io::duplex(PIPE_BYTES);
+        let (downstream, stdout) = tokio::io::duplex(PIPE_BYTES);
+        let (handle, mut kill_rx) = ChildHandle::detached();
+        let (exit_tx, exit_rx) = watch::channel(None);
+        let link = Arc::new(Self {
+            exit: exit_tx,
+            handle: handle.clone(),
+        });
+
+        let (up, down): (Pump, Pump) = match connection {
+            Connection::Stream { read, write } => (
+                Box::pin(pump_up(upstream, write)),
+                Box::pin(pump_down(read, downstream)),
+            ),
+            Connection::Websocket(socket) => {
+                let (sink, stream) = (*socket).split();
+                (
+                    Box::pin(pump_up_websocket(upstream, sink)),
+                    Box::pin(pump_down_websocket(stream, downstream)),
+                )
+            }
+        };
+        let up = tokio::spawn(up);
+        let ended = link.clone();
+        let down = tokio::spawn(async move {
+            let reason = match down.await {
+                Ok(()) => "远程连接已断开".to_string(),
+                Err(e) => format!("远程连接中断: {}", e),
+            };
+            ended.finish(&reason);
+        });
+
+        tokio::spawn(async move {
+            if kill_rx.recv().await.is_some() {
+                up.abort();
+                down.abort();
+                link.finish("远程连接已关闭");
+            }
+        });
+        (stdin, stdout, exit_rx, handle)
+    }
+
+    fn finish(&self, reason: &str) {
+        self.exit.send_if_modified(|exit| {
+            if exit.is_some() {
+                return false;
+            }
+            *exit = Some(BridgeExit {
+                pid: 0,
+                exit_code: None,
+                signal: None,
+                reason: reason.to_string(),
+                exited_at: now_ms(),
+                stderr_tail: String::new(),
+                limit: None,
+            });
+            true
+        });
+        self.handle.mark_reaped();
+    }
+}
+
+/// 本地写入 -> 网络；本地管道关闭时关闭连接的写方向
+async fn pump_up(
+    mut upstream: DuplexStream,
+    mut write: WriteHalf<Box<dyn ByteStream>>,
+) -> std::io::Result<()> {
+    tokio::io::copy(&mut upstream, &mut write).await?;
+    write.shutdown().await
+}
+
+/// 网络 -> 本地读取
+async fn pump_down(
+    mut read: BufReader<ReadHalf<Box<dyn ByteStream>>>,
+    mut downstream: DuplexStream,
+) -> std::io::Result<()> {
+    tokio::io::copy(&mut read, &mut downstream).await?;
+    Ok(())
+}
+
+/// 本地写入 -> 二进制消息；本地管道关闭时发送关闭帧
+async fn pump_up_websocket(
+    mut upstream: DuplexStream,
+    mut sink: SplitSink<WsStream, Message>,
+) -> std::io::Result<()> {
+    let mut buf = vec![0u8; PIPE_BYTES];
+    loop {
+        let n = upstream.read(&mut buf).await?;
+        if n == 0 {
+            break;
+        }
+        sink.send(Message::binary(buf[..n].to_vec()))
+            .await
+            .map_err(std::io::Error::other)?;
+    }
+    sink.close().await.map_err(std::io::Error::other)
+}
+
+/// 消息 -> 本地读取；ping 由 tungstenite 自动应答
+async fn pump_down_websocket(
+    mut stream: SplitStream<WsStream>,
+    mut downstream: DuplexStream,
+) -> std::io::Result<()> {
+    while let Some(message) = stream.next().await {
+        match message.map_err(std::io::Error::other)? {
+            Message::Binary(data) => downstream.write_all(&data).await?,
+            Message::Text(text) => downstream.write_all(text.as_bytes()).await?,
+            Message::Close(_) => break,
+            _ => {}
+        }
+    }
+    Ok(())
+}
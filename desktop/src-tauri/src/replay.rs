@@ -0,0 +1,504 @@
+use crate::bridge::{
+    ensure_bridge_ready, shutdown_bridge, BridgeCapabilities, BridgeExit, BridgeHandles,
+    BridgeState, SHUTDOWN_GRACE,
+};
+use crate::bridge_error::BridgeError;
+use crate::codec::Codec;
+use crate::dispatcher::{read_bounded_line, Dispatcher, LineRead, MAX_LINE_BYTES};
+use crate::framing::Framing;
+use crate::launcher::{self, BridgeLauncher, LaunchFuture};
+use crate::panics::guarded;
+use crate::process::ChildHandle;
+use crate::standby;
+use crate::stderr_log::new_buf;
+use crate::store::now_ms;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+use tokio::io::{AsyncWriteExt, BufReader, DuplexStream};
+use tokio::sync::{watch, Mutex};
+
+/// 启动时指定回放录制的环境变量，便于没有 COMSOL 的机器上跑集成测试
+pub const REPLAY_ENV: &str = "MPH_AGENT_REPLAY";
+/// 内存管道的缓冲区大小
+const PIPE_BYTES: usize = 1024 * 1024;
+/// 回放速度上限
+const MAX_SPEED: f64 = 1000.0;
+/// 没有录制也直接确认的控制消息；其余未录制的命令返回失败响应
+const CONTROL_CMDS: &[&str] = &["ping", "cancel", "host_reply", "set_log_level", "shutdown"];
+
+/// 录制中的一次请求及其全部输出（流式事件与最终响应），按距请求发出的毫秒数排列
+#[derive(Clone, Debug)]
+struct Exchange {
+    replies: Vec<(u64, Value)>,
+}
+
+/// 解析后的录制：按命令分组，同一命令的多次请求按录制顺序依次回放
+#[derive(Debug)]
+pub struct Transcript {
+    path: PathBuf,
+    exchanges: HashMap<String, Vec<Exchange>>,
+    capabilities: Option<BridgeCapabilities>,
+    /// 找不到对应请求的输出行数
+    skipped: usize,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ReplayInfo {
+    pub path: String,
+    pub speed: f64,
+    /// 各命令录制到的请求次数
+    pub commands: BTreeMap<String, usize>,
+    pub skipped: usize,
+}
+
+fn read_text(path: &Path) -> Result<String, String> {
+    let bytes =
+        std::fs::read(path).map_err(|e| format!("读取录制 {} 失败: {}", path.display(), e))?;
+    if !path.to_string_lossy().ends_with(".gz") {
+        return Ok(String::from_utf8_lossy(&bytes).into_owned());
+    }
+    let mut text = String::new();
+    flate2::read::GzDecoder::new(&bytes[..])
+        .read_to_string(&mut text)
+        .map_err(|e| format!("解压录制 {} 失败: {}", path.display(), e))?;
+    Ok(text)
+}
+
+impl Transcript {
+    /// 读取 transcript 模块写出的 NDJSON 录制（可为日志保留策略压缩后的 .gz）
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text = read_text(path)?;
+        let mut sent: HashMap<String, (String, u64, usize)> = HashMap::new();
+        let mut exchanges: HashMap<String, Vec<Exchange>> = HashMap::new();
+        let mut capabilities = None;
+        let mut skipped = 0;
+        for (i, line) in text.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let entry: Value = serde_json::from_str(line)
+                .map_err(|e| format!("录制第 {} 行不是有效的 JSON: {}", i + 1, e))?;
+            let ts = entry["ts"].as_u64().unwrap_or_default();
+            let id = entry["id"].as_str().map(String::from);
+            match entry["kind"].as_str() {
+                Some("request") => {
+                    let (Some(id), Some(cmd)) = (id, entry["cmd"].as_str()) else {
+                        continue;
+                    };
+                    let list = exchanges.entry(cmd.to_string()).or_default();
+                    list.push(Exchange {
+                        replies: Vec::new(),
+                    });
+                    sent.insert(id, (cmd.to_string(), ts, list.len() - 1));
+                }
+                Some("event") | Some("response") => {
+                    let data = &entry["data"];
+                    if data.get("_ready").and_then(Value::as_bool) == Some(true) {
+                        capabilities = BridgeCapabilities::from_ready(data);
+                        continue;
+                    }
+                    let Some((cmd, sent_at, index)) = id.as_ref().and_then(|id| sent.get(id))
+                    else {
+                        skipped += 1;
+                        continue;
+                    };
+                    if let Some(exchange) = exchanges.get_mut(cmd).and_then(|l| l.get_mut(*index)) {
+                        exchange
+                            .replies
+                            .push((ts.saturating_sub(*sent_at), data.clone()));
+                    }
+                }
+                _ => {}
+            }
+        }
+        if exchanges.is_empty() {
+            return Err(format!("录制 {} 中没有请求记录", path.display()));
+        }
+        Ok(Self {
+            path: path.to_path_buf(),
+            exchanges,
+            capabilities,
+            skipped,
+        })
+    }
+}
+
+/// 按录制回放的模拟 bridge：不启动 Python，收到请求时取录制中同一命令的下一次记录，
+/// 按原先的时间间隔（除以 `speed`）送回其流式事件与最终响应。同一命令的记录用完后重复最后一次
+pub struct MockBridge {
+    transcript: Arc<Transcript>,
+    speed: f64,
+}
+
+impl MockBridge {
+    pub fn new(transcript: Transcript, speed: f64) -> Self {
+        Self {
+            transcript: Arc::new(transcript),
+            speed,
+        }
+    }
+
+    pub fn info(&self) -> ReplayInfo {
+        ReplayInfo {
+            path: self.transcript.path.to_string_lossy().into_owned(),
+            speed: self.speed,
+            commands: self
+                .transcript
+                .exchanges
+                .iter()
+                .map(|(cmd, list)| (cmd.clone(), list.len()))
+                .collect(),
+            skipped: self.transcript.skipped,
+        }
+    }
+}
+
+impl BridgeLauncher for MockBridge {
+    fn launch(&self, _bundled_java_home: Option<PathBuf>) -> LaunchFuture {
+        let transcript = self.transcript.clone();
+        let speed = self.speed;
+        Box::pin(async move { Ok(start_replay(transcript, speed)) })
+    }
+
+    fn describe(&self) -> String {
+        format!("replay: {}", self.transcript.path.display())
+    }
+}
+
+/// 一个回放实例的状态：送回输出的管道与退出状态
+struct Replay {
+    transcript: Arc<Transcript>,
+    speed: f64,
+    /// 同一命令已回放的次数
+    cursors: std::sync::Mutex<HashMap<String, usize>>,
+    out: Mutex<Option<DuplexStream>>,
+    exit: watch::Sender<Option<BridgeExit>>,
+    handle: ChildHandle,
+}
+
+impl Replay {
+    fn next_exchange(&self, cmd: &str) -> Option<Exchange> {
+        let list = self.transcript.exchanges.get(cmd)?;
+        let mut cursors = self.cursors.lock().unwrap_or_else(|e| e.into_inner());
+        let cursor = cursors.entry(cmd.to_string()).or_default();
+        let exchange = list.get(*cursor).or_else(|| list.last()).cloned();
+        *cursor += 1;
+        exchange
+    }
+
+    async fn send(&self, mut data: Value, id: &Value) -> bool {
+        if let Some(obj) = data.as_object_mut() {
+            obj.insert("_id".to_string(), id.clone());
+        }
+        let mut out = self.out.lock().await;
+        let Some(pipe) = out.as_mut() else {
+            return false;
+        };
+        let line = data.to_string() + "\n";
+        pipe.write_all(line.as_bytes()).await.is_ok()
+    }
+
+    /// 结束回放：关闭输出管道（读取任务随之结束）并写入退出状态
+    async fn finish(&self, exit_code: Option<i32>, reason: &str) {
+        self.out.lock().await.take();
+        self.exit.send_if_modified(|exit| {
+            if exit.is_some() {
+                return false;
+            }
+            *exit = Some(BridgeExit {
+                pid: 0,
+                exit_code,
+                signal: None,
+                reason: reason.to_string(),
+                exited_at: now_ms(),
+                stderr_tail: String::new(),
+                limit: None,
+            });
+            true
+        });
+        self.handle.mark_reaped();
+    }
+
+    /// 回放一条请求的全部输出；没有录制时按控制消息确认或返回失败
+    async fn answer(self: Arc<Self>, request: Value) {
+        let id = request.get("_id").cloned().unwrap_or(Value::Null);
+        let cmd = request["cmd"].as_str().unwrap_or_default().to_string();
+        let Some(exchange) = self.next_exchange(&cmd) else {
+            let reply = if CONTROL_CMDS.contains(&cmd.as_str()) {
+                serde_json::json!({ "ok": true, "message": "回放模式" })
+            } else {
+                serde_json::json!({
+                    "ok": false,
+                    "message": format!("回放录制中没有 `{}` 的记录", cmd),
+                })
+            };
+            self.send(reply, &id).await;
+            return;
+        };
+        let mut elapsed = 0u64;
+        for (after_ms, data) in exchange.replies {
+            let due = (after_ms as f64 / self.speed) as u64;
+            if due > elapsed {
+                tokio::time::sleep(Duration::from_millis(due - elapsed)).await;
+                elapsed = due;
+            }
+            if !self.send(data, &id).await {
+                return;
+            }
+        }
+    }
+}
+
+/// 建立内存管道，启动读取请求与响应结束请求的任务，交出与真实 bridge 相同的句柄
+fn start_replay(transcript: Arc<Transcript>, speed: f64) -> BridgeHandles {
+    let (to_mock, from_app) = tokio::io::duplex(PIPE_BYTES);
+    let (to_app, from_mock) = tokio::io::duplex(PIPE_BYTES);
+    let (handle, mut kill_rx) = ChildHandle::detached();
+    let (exit_tx, exit_rx) = watch::channel(None);
+    let capabilities = transcript.capabilities.clone();
+    let replay = Arc::new(Replay {
+        transcript,
+        speed,
+        cursors: Default::default(),
+        out: Mutex::new(Some(to_app)),
+        exit: exit_tx,
+        handle: handle.clone(),
+    });
+
+    let killed = replay.clone();
+    tokio::spawn(async move {
+        if kill_rx.recv().await.is_some() {
+            killed.finish(None, "回放已被结束").await;
+        }
+    });
+
+    let serving = replay.clone();
+    tokio::spawn(async move {
+        let mut reader = BufReader::new(from_app);
+        loop {
+            let line = match read_bounded_line(&mut reader, MAX_LINE_BYTES).await {
+                Ok(LineRead::Line(line)) => line,
+                Ok(LineRead::TooLong(_)) => continue,
+                Ok(LineRead::Eof) | Err(_) => break,
+            };
+            let Ok(request) = serde_json::from_str::<Value>(line.trim()) else {
+                continue;
+            };
+            if request["cmd"] == "shutdown" {
+                serving.clone().answer(request).await;
+                break;
+            }
+            tokio::spawn(serving.clone().answer(request));
+        }
+        serving.finish(Some(0), "回放结束").await;
+        // 让等待结束请求的任务退出
+        serving.handle.kill();
+    });
+
+    BridgeHandles {
+        dispatcher: Dispatcher::start(
+            to_mock,
+            BufReader::new(from_mock),
+            Framing::Lines,
+            Codec::Json,
+        ),
+        exit: exit_rx,
+        child: handle,
+        stderr_buf: new_buf(),
+        capabilities,
+    }
+}
+
+fn parse_speed(speed: Option<f64>) -> Result<f64, String> {
+    match speed.unwrap_or(1.0) {
+        s if s.is_finite() && s > 0.0 && s <= MAX_SPEED => Ok(s),
+        _ => Err(format!("回放速度应在 0 ~ {} 之间（不含 0）", MAX_SPEED)),
+    }
+}
+
+/// 换用新的 bridge 实现：结束备用进程与默认会话当前的 bridge，再按新实现启动
+async fn relaunch(app: &AppHandle) -> Result<(), BridgeError> {
+    standby::discard(app).await;
+    let state = app.state::<BridgeState>().inner().clone();
+    shutdown_bridge(&state, SHUTDOWN_GRACE).await;
+    ensure_bridge_ready(&state).await
+}
+
+/// 启动时按环境变量 MPH_AGENT_REPLAY 进入回放模式；录制无法读取时照常启动真实 bridge
+pub fn init_from_env() {
+    let Some(path) = std::env::var(REPLAY_ENV)
+        .ok()
+        .filter(|p| !p.trim().is_empty())
+    else {
+        return;
+    };
+    match Transcript::load(Path::new(path.trim())) {
+        Ok(transcript) => {
+            eprintln!("回放模式: {}", path.trim());
+            launcher::set_active(Some(Arc::new(MockBridge::new(transcript, 1.0))));
+        }
+        Err(e) => eprintln!("Warning: {} 指定的录制无法回放: {}", REPLAY_ENV, e),
+    }
+}
+
+/// 用录制的 NDJSON 回放代替 Python bridge 驱动界面，用于复现界面问题；
+/// `speed` 为回放速度倍数，默认按原速
+#[tauri::command]
+pub async fn bridge_replay_start(
+    app: AppHandle,
+    path: String,
+    speed: Option<f64>,
+) -> Result<ReplayInfo, BridgeError> {
+    guarded("bridge_replay_start", async move {
+        let path = PathBuf::from(path.trim());
+        let mock = MockBridge::new(Transcript::load(&path)?, parse_speed(speed)?);
+        let info = mock.info();
+        launcher::set_active(Some(Arc::new(mock)));
+        relaunch(&app).await?;
+        Ok(info)
+    })
+    .await
+}
+
+/// 退出回放模式，重新启动真实的 bridge
+#[tauri::command]
+pub async fn bridge_replay_stop(app: AppHandle) -> Result<Value, BridgeError> {
+    guarded("bridge_replay_stop", async move {
+        let was = launcher::current().describe();
+        launcher::set_active(None);
+        relaunch(&app).await?;
+        Ok(serde_json::json!({ "stopped": was }))
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dispatcher::{is_final, recv};
+    use std::io::Write;
+
+    /// 录制样例：两次 `status`、一次带两条进度事件的 `run`，以及一条找不到请求的事件
+    const FIXTURE: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/replay.ndjson");
+
+    fn block_on<F: std::future::Future>(future: F) -> F::Output {
+        tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap()
+            .block_on(future)
+    }
+
+    fn temp_dir() -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("mph-replay-{}-{}", std::process::id(), now_ms()));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    /// 经 dispatcher 发出一条请求，收齐到最终响应为止的全部输出
+    async fn exchange(handles: &BridgeHandles, cmd: &str) -> Vec<Value> {
+        let dispatcher = &handles.dispatcher;
+        let (id, mut rx) = dispatcher.register();
+        let line = serde_json::json!({ "cmd": cmd, "_id": id }).to_string();
+        dispatcher.write_line(&line).await.unwrap();
+        let mut out = Vec::new();
+        loop {
+            let item = recv(&mut rx).await.unwrap().unwrap();
+            let last = is_final(&item);
+            out.push(item);
+            if last {
+                return out;
+            }
+        }
+    }
+
+    #[test]
+    fn load_groups_exchanges_by_command() {
+        let transcript = Transcript::load(Path::new(FIXTURE)).unwrap();
+        let info = MockBridge::new(transcript, 1.0).info();
+        assert_eq!(
+            info.commands,
+            BTreeMap::from([("run".to_string(), 1), ("status".to_string(), 2)])
+        );
+        assert_eq!(info.skipped, 1);
+
+        let transcript = Transcript::load(Path::new(FIXTURE)).unwrap();
+        let cmds = transcript.capabilities.as_ref().unwrap().cmds.clone();
+        assert_eq!(cmds.keys().collect::<Vec<_>>(), ["run", "status"]);
+        let run = &transcript.exchanges["run"][0].replies;
+        let delays: Vec<u64> = run.iter().map(|(after, _)| *after).collect();
+        assert_eq!(delays, [10, 20, 30]);
+        assert_eq!(run[2].1["message"], "完成");
+    }
+
+    #[test]
+    fn load_reads_gzipped_transcripts() {
+        let dir = temp_dir();
+        let path = dir.join("replay.ndjson.gz");
+        let mut gz = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        gz.write_all(&std::fs::read(FIXTURE).unwrap()).unwrap();
+        std::fs::write(&path, gz.finish().unwrap()).unwrap();
+
+        let transcript = Transcript::load(&path).unwrap();
+        assert_eq!(transcript.exchanges["status"].len(), 2);
+        assert_eq!(transcript.skipped, 1);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn load_rejects_bad_or_empty_transcripts() {
+        let dir = temp_dir();
+        let empty = dir.join("empty.ndjson");
+        std::fs::write(&empty, "{\"ts\":1,\"kind\":\"start\"}\n\n").unwrap();
+        assert!(Transcript::load(&empty)
+            .unwrap_err()
+            .contains("没有请求记录"));
+
+        let broken = dir.join("broken.ndjson");
+        std::fs::write(&broken, "{\"ts\":1,\"kind\":\"start\"}\nnot json\n").unwrap();
+        assert!(Transcript::load(&broken).unwrap_err().contains("第 2 行"));
+
+        assert!(Transcript::load(&dir.join("missing.ndjson")).is_err());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn replays_send_and_stream_exchanges_through_dispatcher() {
+        let transcript = Arc::new(Transcript::load(Path::new(FIXTURE)).unwrap());
+        block_on(async {
+            let handles = start_replay(transcript, MAX_SPEED);
+            assert!(handles.capabilities.is_some());
+
+            // 同一命令按录制顺序回放，用完后重复最后一次
+            let first = exchange(&handles, "status").await;
+            assert_eq!(first.len(), 1);
+            assert_eq!(first[0]["message"], "第一次");
+            assert_eq!(exchange(&handles, "status").await[0]["message"], "第二次");
+            assert_eq!(exchange(&handles, "status").await[0]["message"], "第二次");
+
+            // 流式请求：先送回进度事件，再送回最终响应
+            let run = exchange(&handles, "run").await;
+            let progress: Vec<&Value> = run.iter().map(|v| &v["progress"]).collect();
+            assert_eq!(progress, [&0.5.into(), &1.0.into(), &Value::Null]);
+            assert_eq!(run[2]["message"], "完成");
+
+            // 未录制的命令：控制消息直接确认，其余返回失败
+            assert_eq!(exchange(&handles, "ping").await[0]["ok"], true);
+            let missing = exchange(&handles, "mesh").await;
+            assert_eq!(missing[0]["ok"], false);
+            assert!(missing[0]["message"].as_str().unwrap().contains("mesh"));
+
+            exchange(&handles, "shutdown").await;
+            let mut exit = handles.exit.clone();
+            let exit = exit.wait_for(Option::is_some).await.unwrap().clone();
+            assert_eq!(exit.unwrap().exit_code, Some(0));
+        });
+    }
+}
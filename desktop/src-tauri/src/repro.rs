@@ -0,0 +1,228 @@
+use crate::bridge::{last_launch, BridgeState};
+use crate::debug_console::Direction;
+use crate::env_cache;
+use crate::jobs::JobRegistry;
+use crate::notes;
+use crate::panics::guarded;
+use crate::paths::get_app_paths;
+use crate::profiles::ActiveProfile;
+use crate::store::{now_ms, save_json};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager};
+
+pub const BUNDLE_FORMAT: &str = "mph-agent-repro";
+pub const BUNDLE_VERSION: u32 = 1;
+/// 常驻记录的最近流量行数；调试控制台关闭时也保留，供复现包使用
+const RECENT_LIMIT: usize = 500;
+const MAX_LINE_BYTES: usize = 16 * 1024;
+/// 名称中含这些片段的字段在导出时替换为占位符
+const SECRET_HINTS: &[&str] = &["key", "token", "secret", "password", "credential"];
+const REDACTED: &str = "***";
+
+static RECENT: Mutex<VecDeque<TranscriptLine>> = Mutex::new(VecDeque::new());
+
+#[derive(Clone, Debug, Serialize)]
+pub struct TranscriptLine {
+    pub ts: u64,
+    pub direction: Direction,
+    pub line: String,
+}
+
+/// 一次请求及其收到的全部响应行（流式事件 + 最终结果），mock/replay 传输按顺序回放
+#[derive(Clone, Debug, Serialize)]
+pub struct ReplayExchange {
+    pub request: Value,
+    pub responses: Vec<Value>,
+}
+
+/// 由 debug_console::record 调用：始终保留最近的原始流量
+pub fn remember(direction: Direction, line: &str) {
+    let line = line.trim_end_matches(['\r', '\n']);
+    let mut end = line.len().min(MAX_LINE_BYTES);
+    while !line.is_char_boundary(end) {
+        end -= 1;
+    }
+    let mut recent = RECENT.lock().unwrap_or_else(|e| e.into_inner());
+    if recent.len() >= RECENT_LIMIT {
+        recent.pop_front();
+    }
+    recent.push_back(TranscriptLine {
+        ts: now_ms(),
+        direction,
+        line: line[..end].to_string(),
+    });
+}
+
+fn is_secret(key: &str) -> bool {
+    let key = key.to_ascii_lowercase();
+    SECRET_HINTS.iter().any(|h| key.contains(h))
+}
+
+/// 递归替换敏感字段
+fn redact(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            for (k, v) in map.iter_mut() {
+                if is_secret(k) && !v.is_null() {
+                    *v = Value::String(REDACTED.to_string());
+                } else {
+                    redact(v);
+                }
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(redact),
+        _ => {}
+    }
+}
+
+/// JSON 行解析后脱敏；非 JSON 行原样保留为字符串
+pub(crate) fn redacted_line(line: &str) -> Value {
+    match serde_json::from_str::<Value>(line) {
+        Ok(mut v) => {
+            redact(&mut v);
+            v
+        }
+        Err(_) => Value::String(line.to_string()),
+    }
+}
+
+/// 按请求切分流量得到可回放的请求/响应序列：带 `_id` 的行按关联 ID 配对，
+/// 旧版 bridge 未回显时归入之前最近的一条请求
+fn replay_exchanges(transcript: &[TranscriptLine]) -> Vec<ReplayExchange> {
+    let mut out: Vec<ReplayExchange> = Vec::new();
+    let mut by_id: HashMap<String, usize> = HashMap::new();
+    for t in transcript {
+        match t.direction {
+            Direction::Out => {
+                let request = redacted_line(&t.line);
+                if let Some(id) = request.get("_id").and_then(|v| v.as_str()) {
+                    by_id.insert(id.to_string(), out.len());
+                }
+                out.push(ReplayExchange {
+                    request,
+                    responses: Vec::new(),
+                });
+            }
+            Direction::In => {
+                let response = redacted_line(&t.line);
+                let index = response
+                    .get("_id")
+                    .and_then(|v| v.as_str())
+                    .and_then(|id| by_id.get(id).copied())
+                    .or_else(|| out.len().checked_sub(1));
+                if let Some(ex) = index.and_then(|i| out.get_mut(i)) {
+                    ex.responses.push(response);
+                }
+            }
+            Direction::Err => {}
+        }
+    }
+    out
+}
+
+/// 配置目录下的全部 JSON 设置（脱敏）
+fn collect_settings(config_dir: &Option<PathBuf>) -> BTreeMap<String, Value> {
+    let Some(dir) = config_dir else {
+        return BTreeMap::new();
+    };
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return BTreeMap::new();
+    };
+    entries
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| p.extension().is_some_and(|e| e == "json"))
+        .filter_map(|p| {
+            let text = std::fs::read_to_string(&p).ok()?;
+            let mut v: Value = serde_json::from_str(&text).ok()?;
+            redact(&mut v);
+            Some((p.file_name()?.to_string_lossy().into_owned(), v))
+        })
+        .collect()
+}
+
+/// 冻结当前状态并导出复现包：bridge 流量与可回放的请求序列、任务请求及其项目笔记、环境报告与设置。
+/// 不指定 job_id 时取正在运行的任务，否则取最近一个任务
+#[tauri::command]
+pub async fn capture_repro_state(
+    app: AppHandle,
+    job_id: Option<String>,
+    path: Option<String>,
+) -> Result<String, String> {
+    guarded("capture_repro_state", async move {
+        let transcript: Vec<TranscriptLine> = RECENT
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .iter()
+            .cloned()
+            .collect();
+        let jobs = app.state::<JobRegistry>();
+        let job = match job_id {
+            Some(ref id) => Some(jobs.get(id).ok_or_else(|| format!("未找到任务: {}", id))?),
+            None => jobs.running().or_else(|| jobs.list(None, 1).pop()),
+        };
+        let project_notes = job
+            .as_ref()
+            .and_then(|j| j.project.as_deref())
+            .and_then(|p| notes::read(Path::new(p)));
+        let job = job.map(|j| {
+            let mut v = serde_json::to_value(j).unwrap_or_default();
+            redact(&mut v);
+            v
+        });
+
+        let profile = app.state::<ActiveProfile>();
+        let paths = get_app_paths(app.clone(), app.state::<BridgeState>(), profile.clone()).await?;
+        let (init_error, capabilities) = {
+            let guard = app.state::<BridgeState>().inner().lock().await;
+            (guard.init_error.clone(), guard.capabilities.clone())
+        };
+        let captured_at = now_ms();
+        let bundle = serde_json::json!({
+            "format": BUNDLE_FORMAT,
+            "version": BUNDLE_VERSION,
+            "captured_at": captured_at,
+            "app": {
+                "version": app.package_info().version.to_string(),
+                "os": std::env::consts::OS,
+                "arch": std::env::consts::ARCH,
+            },
+            "environment": {
+                "paths": paths,
+                "launch": last_launch(),
+                "init_error": init_error,
+                "capabilities": capabilities,
+                "probe": env_cache::snapshot(),
+            },
+            "settings": collect_settings(&profile.config_dir),
+            "job": job,
+            "project_notes": project_notes,
+            "replay": replay_exchanges(&transcript),
+            "transcript": transcript
+                .iter()
+                .map(|t| serde_json::json!({
+                    "ts": t.ts,
+                    "direction": t.direction,
+                    "line": redacted_line(&t.line),
+                }))
+                .collect::<Vec<_>>(),
+        });
+
+        let target = match path.map(|p| p.trim().to_string()).filter(|p| !p.is_empty()) {
+            Some(p) => PathBuf::from(p),
+            None => profile
+                .data_dir
+                .as_ref()
+                .ok_or("无法确定数据目录")?
+                .join("repro")
+                .join(format!("repro-{}.json", captured_at)),
+        };
+        save_json(&target, &bundle)?;
+        Ok(target.to_string_lossy().into_owned())
+    })
+    .await
+}
@@ -0,0 +1,228 @@
+use crate::bridge::BridgeState;
+use crate::bridge_error::BridgeError;
+use crate::panics::guarded;
+use crate::store::now_ms;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::sync::{Arc, Mutex};
+use tokio::sync::Notify;
+
+/// 请求中指定优先级的字段，发送前移除
+const PRIORITY_KEY: &str = "_priority";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Priority {
+    /// 快速查询，排在所有普通请求之前
+    High,
+    Normal,
+}
+
+impl Priority {
+    /// 请求中的 `_priority` 优先于调用方给出的默认值，并从请求中移除
+    pub fn take(payload: &mut Value, default: Priority) -> Priority {
+        payload
+            .as_object_mut()
+            .and_then(|obj| obj.remove(PRIORITY_KEY))
+            .and_then(|v| serde_json::from_value(v).ok())
+            .unwrap_or(default)
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct QueuedRequest {
+    pub ticket: String,
+    pub cmd: String,
+    pub priority: Priority,
+    /// 流式请求的前端请求 ID
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
+    pub enqueued_at: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub started_at: Option<u64>,
+    #[serde(skip)]
+    seq: u64,
+    #[serde(skip)]
+    cancelled: bool,
+}
+
+#[derive(Default)]
+struct Inner {
+    running: Option<QueuedRequest>,
+    waiting: Vec<QueuedRequest>,
+    next_seq: u64,
+}
+
+impl Inner {
+    /// 下一个该发送的请求：高优先级在前，同级按入队顺序
+    fn next_up(&self) -> Option<usize> {
+        self.waiting
+            .iter()
+            .enumerate()
+            .min_by_key(|(_, e)| (e.priority != Priority::High, e.seq))
+            .map(|(i, _)| i)
+    }
+}
+
+/// bridge 命令的发送队列。bridge 逐条处理请求，长时间的流式任务执行期间，
+/// 其他请求在这里排队而不是提前写入管道：高优先级的查询在任务结束后最先发送，
+/// 尚未发送的请求可以取消，等待响应的超时也只从真正发送时开始计算。
+/// bridge 在握手时声明可并行的只读查询不经过此队列
+#[derive(Default)]
+pub struct RequestQueue {
+    inner: Mutex<Inner>,
+    notify: Notify,
+}
+
+/// 持有期间独占 bridge；释放后唤醒排队的请求
+pub struct QueueSlot {
+    queue: Arc<RequestQueue>,
+}
+
+impl Drop for QueueSlot {
+    fn drop(&mut self) {
+        self.queue.lock().running = None;
+        self.queue.notify.notify_waiters();
+    }
+}
+
+/// 排队中的调用方提前返回时移除其条目，避免堵住后面的请求
+struct WaitGuard<'a> {
+    queue: &'a RequestQueue,
+    ticket: &'a str,
+}
+
+impl Drop for WaitGuard<'_> {
+    fn drop(&mut self) {
+        let removed = {
+            let mut inner = self.queue.lock();
+            let before = inner.waiting.len();
+            inner.waiting.retain(|e| e.ticket != self.ticket);
+            before != inner.waiting.len()
+        };
+        if removed {
+            self.queue.notify.notify_waiters();
+        }
+    }
+}
+
+impl RequestQueue {
+    fn lock(&self) -> std::sync::MutexGuard<'_, Inner> {
+        self.inner.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// 排队直到轮到本请求；排队期间被取消时返回 Aborted
+    pub async fn acquire(
+        self: &Arc<Self>,
+        cmd: &str,
+        priority: Priority,
+        request_id: Option<&str>,
+    ) -> Result<QueueSlot, BridgeError> {
+        let ticket = {
+            let mut inner = self.lock();
+            inner.next_seq += 1;
+            let seq = inner.next_seq;
+            let ticket = format!("q{}", seq);
+            inner.waiting.push(QueuedRequest {
+                ticket: ticket.clone(),
+                cmd: cmd.to_string(),
+                priority,
+                request_id: request_id.map(String::from),
+                enqueued_at: now_ms(),
+                started_at: None,
+                seq,
+                cancelled: false,
+            });
+            ticket
+        };
+        let _guard = WaitGuard {
+            queue: self,
+            ticket: &ticket,
+        };
+        loop {
+            let mut notified = std::pin::pin!(self.notify.notified());
+            notified.as_mut().enable();
+            {
+                let mut inner = self.lock();
+                let Some(pos) = inner.waiting.iter().position(|e| e.ticket == ticket) else {
+                    return Err(BridgeError::Aborted {
+                        message: format!("Cancelled: 命令 `{}` 已移出队列", cmd),
+                    });
+                };
+                if inner.waiting[pos].cancelled {
+                    return Err(BridgeError::Aborted {
+                        message: format!("Cancelled: 命令 `{}` 在排队时被取消", cmd),
+                    });
+                }
+                if inner.running.is_none() && inner.next_up() == Some(pos) {
+                    let mut entry = inner.waiting.remove(pos);
+                    entry.started_at = Some(now_ms());
+                    inner.running = Some(entry);
+                    return Ok(QueueSlot {
+                        queue: self.clone(),
+                    });
+                }
+            }
+            notified.await;
+        }
+    }
+
+    /// 取消排队中的请求（按 ticket 或流式请求 ID）
+    fn cancel(&self, id: &str) -> Result<(), String> {
+        {
+            let mut inner = self.lock();
+            if let Some(entry) = inner
+                .waiting
+                .iter_mut()
+                .find(|e| e.ticket == id || e.request_id.as_deref() == Some(id))
+            {
+                entry.cancelled = true;
+            } else if inner
+                .running
+                .as_ref()
+                .is_some_and(|e| e.ticket == id || e.request_id.as_deref() == Some(id))
+            {
+                return Err("请求已在执行；流式任务请使用 bridge_cancel_stream".to_string());
+            } else {
+                return Err(format!("队列中没有该请求: {}", id));
+            }
+        }
+        self.notify.notify_waiters();
+        Ok(())
+    }
+
+    fn status(&self) -> Value {
+        let inner = self.lock();
+        let mut waiting = inner.waiting.clone();
+        waiting.sort_by_key(|e| (e.priority != Priority::High, e.seq));
+        serde_json::json!({
+            "running": inner.running,
+            "depth": waiting.len(),
+            "waiting": waiting,
+        })
+    }
+}
+
+/// 正在执行的请求与按发送顺序排列的排队请求
+#[tauri::command]
+pub async fn bridge_queue_status(
+    state: tauri::State<'_, BridgeState>,
+) -> Result<Value, BridgeError> {
+    guarded("bridge_queue_status", async move {
+        let queue = state.inner().lock().await.queue.clone();
+        Ok(queue.status())
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn bridge_queue_cancel(
+    state: tauri::State<'_, BridgeState>,
+    ticket: String,
+) -> Result<(), BridgeError> {
+    guarded("bridge_queue_cancel", async move {
+        let queue = state.inner().lock().await.queue.clone();
+        queue.cancel(ticket.trim()).map_err(BridgeError::from)
+    })
+    .await
+}
@@ -0,0 +1,258 @@
+use crate::jobs::{JobRecord, JobRegistry, JobStatus};
+use crate::panics::guarded;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Manager};
+
+/// 关键结果文件名（不含扩展名）
+const KEY_RESULTS_STEM: &str = "key_results";
+const KEY_RESULTS_EXTS: &[&str] = &["json", "csv", "txt"];
+/// 关键结果文件应当很小，超过即视为误判
+const MAX_FILE_BYTES: u64 = 64 * 1024;
+const MAX_METRICS: usize = 200;
+/// 结果文件的修改时间允许晚于任务结束的余量
+const WRITE_SLACK_MS: u64 = 5_000;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct KeyResult {
+    pub value: f64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub unit: Option<String>,
+}
+
+fn is_key_results(path: &Path) -> bool {
+    let stem_ok = path
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .is_some_and(|s| s.eq_ignore_ascii_case(KEY_RESULTS_STEM));
+    let ext_ok = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| KEY_RESULTS_EXTS.contains(&e.to_ascii_lowercase().as_str()));
+    stem_ok && ext_ok
+}
+
+fn modified_ms(path: &Path) -> Option<u64> {
+    std::fs::metadata(path)
+        .ok()?
+        .modified()
+        .ok()?
+        .duration_since(std::time::UNIX_EPOCH)
+        .ok()
+        .map(|d| d.as_millis() as u64)
+}
+
+/// 任务的关键结果文件：登记为产物的，以及产物目录里在任务运行期间写入的
+fn key_results_files(job: &JobRecord) -> Vec<PathBuf> {
+    let mut out: BTreeSet<PathBuf> = job
+        .artifacts
+        .iter()
+        .map(PathBuf::from)
+        .filter(|p| is_key_results(p) && p.is_file())
+        .collect();
+    if let (Some(start), Some(end)) = (job.started_at, job.finished_at) {
+        let dirs: BTreeSet<PathBuf> = job
+            .artifacts
+            .iter()
+            .filter_map(|a| Path::new(a).parent().map(Path::to_path_buf))
+            .collect();
+        for dir in dirs {
+            let Ok(entries) = std::fs::read_dir(&dir) else {
+                continue;
+            };
+            out.extend(
+                entries
+                    .flatten()
+                    .map(|e| e.path())
+                    .filter(|p| is_key_results(p) && p.is_file())
+                    .filter(|p| {
+                        modified_ms(p).is_some_and(|m| m >= start && m <= end + WRITE_SLACK_MS)
+                    }),
+            );
+        }
+    }
+    out.into_iter().collect()
+}
+
+/// JSON：`{"T_max": 351.2, "S11_f0": {"value": -18.3, "unit": "dB"}}`
+fn parse_json(text: &str) -> Result<BTreeMap<String, KeyResult>, String> {
+    let root: Value = serde_json::from_str(text).map_err(|e| format!("JSON 解析失败: {}", e))?;
+    let obj = root.as_object().ok_or("顶层应为对象")?;
+    Ok(obj
+        .iter()
+        .filter_map(|(k, v)| {
+            let result = match v {
+                Value::Number(n) => KeyResult {
+                    value: n.as_f64()?,
+                    unit: None,
+                },
+                Value::Object(_) => KeyResult {
+                    value: v.get("value")?.as_f64()?,
+                    unit: v.get("unit").and_then(|u| u.as_str()).map(String::from),
+                },
+                _ => return None,
+            };
+            Some((k.clone(), result))
+        })
+        .collect())
+}
+
+/// 文本：每行 `名称, 数值[, 单位]` 或 `名称 = 数值 [单位]`，`#` / `%` 开头为注释
+fn parse_text(text: &str) -> BTreeMap<String, KeyResult> {
+    let mut out = BTreeMap::new();
+    for line in text.lines().map(str::trim) {
+        if line.is_empty() || line.starts_with('#') || line.starts_with('%') {
+            continue;
+        }
+        let (name, rest) = match line.split_once('=') {
+            Some((n, r)) => (n, r.trim()),
+            None => match line.split_once([',', '\t', ';']) {
+                Some((n, r)) => (n, r.trim()),
+                None => continue,
+            },
+        };
+        let mut parts = rest
+            .split(|c: char| c == ',' || c == ';' || c.is_whitespace())
+            .filter(|p| !p.is_empty());
+        let Some(value) = parts.next().and_then(|v| v.parse::<f64>().ok()) else {
+            continue;
+        };
+        let name = name.trim().trim_matches('"');
+        if name.is_empty() {
+            continue;
+        }
+        out.insert(
+            name.to_string(),
+            KeyResult {
+                value,
+                unit: parts.next().map(String::from),
+            },
+        );
+    }
+    out
+}
+
+fn parse_file(path: &Path) -> Result<BTreeMap<String, KeyResult>, String> {
+    let size = std::fs::metadata(path).map_err(|e| e.to_string())?.len();
+    if size > MAX_FILE_BYTES {
+        return Err(format!("文件过大（{} 字节）", size));
+    }
+    let text = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let is_json = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| e.eq_ignore_ascii_case("json"));
+    let mut metrics = if is_json {
+        parse_json(&text)?
+    } else {
+        parse_text(&text)
+    };
+    metrics.retain(|_, r| r.value.is_finite());
+    Ok(metrics)
+}
+
+/// 任务结束后读取其关键结果文件，写入任务记录
+pub async fn extract_key_results(app: &AppHandle, request_id: &str) {
+    let jobs = app.state::<JobRegistry>();
+    let Some(job) = jobs.get(request_id) else {
+        return;
+    };
+    let metrics = tauri::async_runtime::spawn_blocking(move || {
+        let mut out = BTreeMap::new();
+        for path in key_results_files(&job) {
+            match parse_file(&path) {
+                Ok(m) => out.extend(m),
+                Err(e) => eprintln!("Warning: 解析关键结果 {} 失败: {}", path.display(), e),
+            }
+        }
+        out.into_iter()
+            .take(MAX_METRICS)
+            .collect::<BTreeMap<_, _>>()
+    })
+    .await
+    .unwrap_or_default();
+    if !metrics.is_empty() {
+        jobs.set_metrics(request_id, metrics);
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct TrendPoint {
+    pub job_id: String,
+    pub ts: u64,
+    pub value: f64,
+    pub status: JobStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rerun_of: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ResultsTrend {
+    pub metric: String,
+    pub project: Option<String>,
+    pub unit: Option<String>,
+    /// 按时间先后排列
+    pub points: Vec<TrendPoint>,
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+    /// 最后一次相对第一次的变化 last - first
+    pub change: Option<f64>,
+    /// 相对变化 (last - first) / |first|
+    pub rel_change: Option<f64>,
+}
+
+/// 某个关键结果在历史任务中的变化，用于判断反复调整提示词是否真的改进了设计
+#[tauri::command]
+pub async fn results_trend(
+    jobs: tauri::State<'_, JobRegistry>,
+    metric: String,
+    project: Option<String>,
+    limit: Option<usize>,
+) -> Result<ResultsTrend, String> {
+    guarded("results_trend", async move {
+        let mut history = jobs.list(project.as_deref(), usize::MAX);
+        history.reverse();
+        let mut unit = None;
+        let mut points: Vec<TrendPoint> = history
+            .into_iter()
+            .filter_map(|j| {
+                let r = j.metrics.get(&metric)?;
+                if unit.is_none() {
+                    unit = r.unit.clone();
+                }
+                Some(TrendPoint {
+                    ts: j.finished_at.unwrap_or(j.created_at),
+                    value: r.value,
+                    job_id: j.id,
+                    status: j.status,
+                    rerun_of: j.rerun_of,
+                })
+            })
+            .collect();
+        if let Some(limit) = limit {
+            let skip = points.len().saturating_sub(limit);
+            points.drain(..skip);
+        }
+        let values = || points.iter().map(|p| p.value);
+        let first = points.first().map(|p| p.value);
+        let change = first
+            .zip(points.last().map(|p| p.value))
+            .map(|(a, b)| b - a);
+        Ok(ResultsTrend {
+            min: values().reduce(f64::min),
+            max: values().reduce(f64::max),
+            rel_change: first
+                .zip(change)
+                .filter(|(a, _)| *a != 0.0)
+                .map(|(a, d)| d / a.abs()),
+            change,
+            metric,
+            project,
+            unit,
+            points,
+        })
+    })
+    .await
+}
@@ -0,0 +1,1029 @@
+use crate::panics::guarded;
+use crate::standby;
+use crate::store::{load_json, save_json};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::path::{Component, Path, PathBuf};
+use std::sync::Mutex;
+use tauri::AppHandle;
+
+/// 请求中代表写入位置的字段
+const WRITE_KEYS: &[&str] = &["workspace_dir", "output_dir", "output", "output_filename"];
+/// 也可以只给文件名的字段：相对路径由 bridge 放到工作区
+const FILE_NAME_KEYS: &[&str] = &["output", "output_filename"];
+/// 本程序以此参数启动时只作为 Windows 沙箱的启动器，见 `run_sandbox_exec`
+pub const SANDBOX_EXEC_ARG: &str = "--sandbox-exec";
+/// 标为低完整性的目录及其原先的标签，与 sandbox.json 同目录；程序异常退出后下次启动据此恢复
+const LABELS_FILE: &str = "sandbox-labels.json";
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct SandboxConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// 允许写入的项目/输出目录
+    #[serde(default)]
+    pub roots: Vec<String>,
+}
+
+/// 当前生效的可写目录；None 表示未启用沙箱。bridge 启动时读取
+static ACTIVE_ROOTS: Mutex<Option<Vec<PathBuf>>> = Mutex::new(None);
+/// Windows 上为 bridge 标为低完整性的目录
+static LABELS: Mutex<Labels> = Mutex::new(Labels::new());
+static LABELS_PATH: Mutex<Option<PathBuf>> = Mutex::new(None);
+
+/// 工作区沙箱：bridge 只能写项目/输出目录（及系统临时目录），接受路径的命令拒绝越界位置。
+/// Linux 上借助 bubblewrap 把其余文件系统挂为只读、/tmp 换成私有 tmpfs；Windows 上以低完整性的
+/// 受限令牌启动并放入限制界面操作的 Job Object，只有标记为低完整性的工作区与运行所需目录可写，
+/// 这些目录在 bridge 退出后恢复原先的标签。没有可用的系统隔离时拒绝启动 bridge。
+#[derive(Default)]
+pub struct Sandbox {
+    path: Option<PathBuf>,
+    config: Mutex<SandboxConfig>,
+}
+
+fn apply(config: &SandboxConfig) {
+    let roots = config.enabled.then(|| {
+        let mut roots: Vec<PathBuf> = config
+            .roots
+            .iter()
+            .filter_map(|r| std::fs::canonicalize(r).ok())
+            .collect();
+        if let Ok(tmp) = std::fs::canonicalize(std::env::temp_dir()) {
+            roots.push(tmp);
+        }
+        roots
+    });
+    *ACTIVE_ROOTS.lock().unwrap_or_else(|e| e.into_inner()) = roots;
+}
+
+pub(crate) fn active_roots() -> Option<Vec<PathBuf>> {
+    ACTIVE_ROOTS
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone()
+}
+
+impl Sandbox {
+    pub fn load(path: PathBuf) -> Self {
+        let config: SandboxConfig = load_json(&path);
+        apply(&config);
+        restore_leftover_labels(path.with_file_name(LABELS_FILE));
+        Self {
+            path: Some(path),
+            config: Mutex::new(config),
+        }
+    }
+
+    pub fn config(&self) -> SandboxConfig {
+        self.config
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    pub fn set(&self, config: SandboxConfig) -> Result<(), String> {
+        apply(&config);
+        let mut current = self.config.lock().unwrap_or_else(|e| e.into_inner());
+        *current = config;
+        match self.path {
+            Some(ref p) => save_json(p, &*current),
+            None => Ok(()),
+        }
+    }
+}
+
+/// 规范化路径：对尚不存在的部分，规范化最近的已存在祖先后再拼接，且不允许 `..`
+fn normalize(path: &Path) -> Result<PathBuf, String> {
+    let mut existing = path.to_path_buf();
+    let mut rest: Vec<std::ffi::OsString> = Vec::new();
+    while !existing.exists() {
+        let name = existing
+            .file_name()
+            .ok_or_else(|| format!("无效路径: {}", path.display()))?
+            .to_os_string();
+        rest.push(name);
+        if !existing.pop() {
+            return Err(format!("无效路径: {}", path.display()));
+        }
+    }
+    let mut out = std::fs::canonicalize(&existing).map_err(|e| e.to_string())?;
+    for part in rest.iter().rev() {
+        if Path::new(part)
+            .components()
+            .any(|c| matches!(c, Component::ParentDir))
+        {
+            return Err(format!("路径包含 ..: {}", path.display()));
+        }
+        out.push(part);
+    }
+    Ok(out)
+}
+
+/// 沙箱启用时，`path` 必须位于某个允许目录之下
+pub fn check_path(path: &Path) -> Result<(), String> {
+    let Some(roots) = active_roots() else {
+        return Ok(());
+    };
+    if !path.is_absolute() {
+        return Err(format!("沙箱模式下需使用绝对路径: {}", path.display()));
+    }
+    let p = normalize(path)?;
+    if roots.iter().any(|r| p.starts_with(r)) {
+        Ok(())
+    } else {
+        Err(format!("路径不在允许的工作区内: {}", path.display()))
+    }
+}
+
+/// 沙箱启用时，相对的输出位置只能是单个文件名，否则可借 `..` 或子目录写到工作区之外
+fn check_file_name(key: &str, path: &Path) -> Result<(), String> {
+    if active_roots().is_none() {
+        return Ok(());
+    }
+    let mut components = path.components();
+    match (components.next(), components.next()) {
+        (Some(Component::Normal(_)), None) => Ok(()),
+        _ => Err(format!(
+            "沙箱模式下 {} 只能是文件名或绝对路径: {}",
+            key,
+            path.display()
+        )),
+    }
+}
+
+/// 检查请求里的写入位置；`output` / `output_filename` 只是文件名时由 bridge 放到工作区
+pub fn check_payload(payload: &Value) -> Result<(), String> {
+    for key in WRITE_KEYS {
+        if let Some(p) = payload.get(*key).and_then(|v| v.as_str()) {
+            let p = Path::new(p.trim());
+            if p.as_os_str().is_empty() {
+                continue;
+            }
+            if FILE_NAME_KEYS.contains(key) && !p.is_absolute() {
+                check_file_name(key, p)?;
+            } else {
+                check_path(p)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// 标为低完整性的目录：原先的完整性标签（SDDL，没有显式标签时为空）与正在使用它的 bridge 数。
+/// 同一目录可能同时被多个会话的 bridge 使用，最后一个退出时才恢复
+#[derive(Default, Serialize, Deserialize)]
+struct Labels {
+    original: BTreeMap<PathBuf, String>,
+    #[serde(skip)]
+    users: BTreeMap<PathBuf, usize>,
+}
+
+impl Labels {
+    const fn new() -> Self {
+        Self {
+            original: BTreeMap::new(),
+            users: BTreeMap::new(),
+        }
+    }
+
+    /// 登记一个使用该目录的 bridge。首个使用者先由 `read` 取得原标签（上次未能恢复的沿用记录中的），
+    /// 返回 true 表示需要标记
+    #[cfg(any(target_os = "windows", test))]
+    fn take(
+        &mut self,
+        dir: &Path,
+        read: impl FnOnce(&Path) -> Result<String, String>,
+    ) -> Result<bool, String> {
+        if let Some(users) = self.users.get_mut(dir) {
+            *users += 1;
+            return Ok(false);
+        }
+        if !self.original.contains_key(dir) {
+            self.original.insert(dir.to_path_buf(), read(dir)?);
+        }
+        self.users.insert(dir.to_path_buf(), 1);
+        Ok(true)
+    }
+
+    /// 一个 bridge 不再使用该目录；返回 true 表示已没有使用者，应恢复原标签
+    fn give_back(&mut self, dir: &Path) -> bool {
+        match self.users.get_mut(dir) {
+            Some(users) if *users > 1 => {
+                *users -= 1;
+                false
+            }
+            Some(_) => {
+                self.users.remove(dir);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+fn save_labels(labels: &Labels) {
+    let path = LABELS_PATH
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone();
+    if let Some(path) = path {
+        if let Err(e) = save_json(&path, labels) {
+            eprintln!("Warning: 保存低完整性标记记录失败: {}", e);
+        }
+    }
+}
+
+fn restore_label(dir: &Path, original: &str) -> Result<(), String> {
+    #[cfg(target_os = "windows")]
+    {
+        unsafe { restricted::restore_label(dir, original) }
+    }
+    #[cfg(not(target_os = "windows"))]
+    {
+        let _ = (dir, original);
+        Ok(())
+    }
+}
+
+/// 恢复已没有 bridge 使用的目录的原标签；恢复失败的留在记录中，下次启动时重试
+fn restore_unused(labels: &mut Labels, dirs: &[PathBuf]) {
+    for dir in dirs {
+        let Some(original) = labels.original.get(dir) else {
+            continue;
+        };
+        match restore_label(dir, original) {
+            Ok(()) => {
+                labels.original.remove(dir);
+            }
+            Err(e) => eprintln!("Warning: 恢复 {} 的完整性标签失败: {}", dir.display(), e),
+        }
+    }
+    save_labels(labels);
+}
+
+/// 启动时恢复上次运行（异常退出）遗留的低完整性标记
+fn restore_leftover_labels(path: PathBuf) {
+    *LABELS_PATH.lock().unwrap_or_else(|e| e.into_inner()) = Some(path.clone());
+    let leftover: Labels = load_json(&path);
+    if leftover.original.is_empty() {
+        return;
+    }
+    let dirs: Vec<PathBuf> = leftover.original.keys().cloned().collect();
+    let mut labels = LABELS.lock().unwrap_or_else(|e| e.into_inner());
+    labels.original = leftover.original;
+    restore_unused(&mut labels, &dirs);
+}
+
+/// bridge 运行期间对可写目录的低完整性标记（只在 Windows 上持有目录）。
+/// 随 bridge 进程保留到其退出，释放时把不再有 bridge 使用的目录恢复为原先的标签
+#[derive(Default)]
+pub struct LabelLease {
+    dirs: Vec<PathBuf>,
+}
+
+impl Drop for LabelLease {
+    fn drop(&mut self) {
+        if self.dirs.is_empty() {
+            return;
+        }
+        let mut labels = LABELS.lock().unwrap_or_else(|e| e.into_inner());
+        let unused: Vec<PathBuf> = self
+            .dirs
+            .iter()
+            .filter(|d| labels.give_back(d))
+            .cloned()
+            .collect();
+        restore_unused(&mut labels, &unused);
+    }
+}
+
+/// 把可写目录标为低完整性：先记下各目录原先的标签并写入记录，再逐个标记
+#[cfg(target_os = "windows")]
+fn acquire_labels(dirs: &[PathBuf]) -> Result<LabelLease, String> {
+    // 出错返回时锁先于租约释放（局部变量按声明的逆序释放），租约随即交还已登记的目录
+    let mut lease = LabelLease::default();
+    let mut fresh = Vec::new();
+    {
+        let mut labels = LABELS.lock().unwrap_or_else(|e| e.into_inner());
+        for dir in dirs {
+            if labels.take(dir, |d| unsafe { restricted::read_label(d) })? {
+                fresh.push(dir.clone());
+            }
+            lease.dirs.push(dir.clone());
+        }
+        save_labels(&labels);
+    }
+    unsafe {
+        let mut low = restricted::low_label_sid()?;
+        for dir in &fresh {
+            restricted::label_low(dir, &mut low)?;
+        }
+    }
+    Ok(lease)
+}
+
+/// bridge 运行时还要写入的用户目录：COMSOL 偏好设置与恢复文件（~/.comsol）、uv 缓存（开发模式经 uv 启动）。
+/// 绑定或标记前须已存在，不存在时创建
+fn runtime_writable_dirs() -> Vec<PathBuf> {
+    let home = std::env::var_os(if cfg!(target_os = "windows") {
+        "USERPROFILE"
+    } else {
+        "HOME"
+    })
+    .map(PathBuf::from);
+    let uv_cache = std::env::var_os("UV_CACHE_DIR")
+        .map(PathBuf::from)
+        .or_else(|| {
+            if cfg!(target_os = "windows") {
+                std::env::var_os("LOCALAPPDATA").map(|d| PathBuf::from(d).join("uv").join("cache"))
+            } else {
+                std::env::var_os("XDG_CACHE_HOME")
+                    .map(PathBuf::from)
+                    .or_else(|| home.as_ref().map(|h| h.join(".cache")))
+                    .map(|d| d.join("uv"))
+            }
+        });
+    home.map(|h| h.join(".comsol"))
+        .into_iter()
+        .chain(uv_cache)
+        .chain(crate::profiles::bridge_writable_dirs())
+        .filter(|d| std::fs::create_dir_all(d).is_ok())
+        .filter_map(|d| std::fs::canonicalize(d).ok())
+        .collect()
+}
+
+/// bwrap 参数：根文件系统只读，/tmp 为私有 tmpfs（系统临时目录本身不再绑定），其余可写目录原样绑定
+#[cfg(any(target_os = "linux", test))]
+fn bwrap_args(writable: &[PathBuf]) -> Vec<String> {
+    let mut args: Vec<String> = [
+        "--ro-bind",
+        "/",
+        "/",
+        "--dev",
+        "/dev",
+        "--proc",
+        "/proc",
+        "--tmpfs",
+        "/tmp",
+        "--die-with-parent",
+    ]
+    .iter()
+    .map(|s| s.to_string())
+    .collect();
+    for r in writable.iter().filter(|r| r.as_path() != Path::new("/tmp")) {
+        let r = r.to_string_lossy().into_owned();
+        args.extend(["--bind".to_string(), r.clone(), r]);
+    }
+    args
+}
+
+/// 沙箱启用时包装 bridge 启动命令：Linux 上包进 bwrap，根文件系统只读，允许目录、
+/// `extra_writable` 与运行所需目录可写；Windows 上把这些目录标为低完整性后经本程序的沙箱启动器运行，
+/// 返回的租约须保留到 bridge 退出，释放时恢复原标签。
+/// 没有可用的隔离手段时返回错误，不以未隔离的方式启动
+pub fn wrap_command(
+    program: String,
+    args: Vec<String>,
+    extra_writable: &[PathBuf],
+) -> Result<(String, Vec<String>, LabelLease), String> {
+    let Some(roots) = active_roots() else {
+        return Ok((program, args, LabelLease::default()));
+    };
+    let writable: Vec<PathBuf> = roots
+        .into_iter()
+        .chain(extra_writable.iter().cloned())
+        .chain(runtime_writable_dirs())
+        .collect();
+    #[cfg(target_os = "linux")]
+    {
+        let bwrap = std::env::var_os("PATH")
+            .and_then(|paths| {
+                std::env::split_paths(&paths)
+                    .map(|d| d.join("bwrap"))
+                    .find(|p| p.is_file())
+            })
+            .ok_or_else(|| {
+                "沙箱已启用，但未找到 bubblewrap (bwrap)，无法隔离 bridge；请安装 bubblewrap 或关闭沙箱"
+                    .to_string()
+            })?;
+        let mut wrapped = bwrap_args(&writable);
+        wrapped.push("--".to_string());
+        wrapped.push(program);
+        wrapped.extend(args);
+        Ok((
+            bwrap.to_string_lossy().into_owned(),
+            wrapped,
+            LabelLease::default(),
+        ))
+    }
+    #[cfg(target_os = "windows")]
+    {
+        // 系统临时目录不整体标记，启动器改用其下的私有目录作为 bridge 的 TEMP
+        let tmp = std::fs::canonicalize(std::env::temp_dir()).ok();
+        let exe = std::env::current_exe()
+            .map_err(|e| format!("沙箱已启用，但无法定位沙箱启动器: {}", e))?;
+        let labelled: Vec<PathBuf> = writable
+            .into_iter()
+            .filter(|d| Some(d) != tmp.as_ref())
+            .collect();
+        let lease = acquire_labels(&labelled)?;
+        let mut wrapped = vec![SANDBOX_EXEC_ARG.to_string(), "--".to_string(), program];
+        wrapped.extend(args);
+        Ok((exe.to_string_lossy().into_owned(), wrapped, lease))
+    }
+    #[cfg(not(any(target_os = "linux", target_os = "windows")))]
+    {
+        let _ = (writable, program, args);
+        Err("沙箱已启用，但当前系统没有可用的进程隔离，无法启动 bridge；请关闭沙箱".to_string())
+    }
+}
+
+/// 取出沙箱启动器参数 `-- <program> [args...]` 中的命令
+fn parse_exec_args(args: impl IntoIterator<Item = String>) -> Vec<String> {
+    let mut args = args.into_iter().peekable();
+    if args.peek().map(String::as_str) == Some("--") {
+        args.next();
+    }
+    args.collect()
+}
+
+/// 本程序以 `--sandbox-exec -- <program> [args...]` 启动时作为沙箱启动器：
+/// 以低完整性受限令牌启动 bridge 并放入 Job Object，等其退出后以相同的退出码退出，不进入界面。
+/// 可写目录已由主程序标为低完整性（见 `wrap_command`），启动器被结束时也由主程序恢复。
+/// 其他情况下直接返回
+pub fn run_sandbox_exec() {
+    let mut argv = std::env::args();
+    if argv.nth(1).as_deref() != Some(SANDBOX_EXEC_ARG) {
+        return;
+    }
+    let command = parse_exec_args(argv);
+    #[cfg(target_os = "windows")]
+    let code = match restricted::exec(&command) {
+        Ok(code) => code,
+        Err(e) => {
+            eprintln!("Error: 沙箱启动 bridge 失败: {}", e);
+            1
+        }
+    };
+    #[cfg(not(target_os = "windows"))]
+    let code = {
+        eprintln!("Error: 当前系统不支持 {}: {:?}", SANDBOX_EXEC_ARG, command);
+        1
+    };
+    std::process::exit(code);
+}
+
+#[cfg(target_os = "windows")]
+mod restricted {
+    use std::os::windows::ffi::OsStrExt;
+    use std::path::Path;
+    use windows_sys::Win32::Foundation::{CloseHandle, LocalFree, ERROR_SUCCESS, HANDLE};
+    use windows_sys::Win32::Security::Authorization::{
+        ConvertSecurityDescriptorToStringSecurityDescriptorW,
+        ConvertStringSecurityDescriptorToSecurityDescriptorW, GetNamedSecurityInfoW,
+        SetNamedSecurityInfoW, SDDL_REVISION_1, SE_FILE_OBJECT,
+    };
+    use windows_sys::Win32::Security::{
+        AddMandatoryAce, CreateRestrictedToken, CreateWellKnownSid, GetLengthSid,
+        GetSecurityDescriptorSacl, InitializeAcl, SetTokenInformation, TokenIntegrityLevel,
+        WinBuiltinAdministratorsSid, WinLowLabelSid, ACL, ACL_REVISION, CONTAINER_INHERIT_ACE,
+        DISABLE_MAX_PRIVILEGE, LABEL_SECURITY_INFORMATION, LUA_TOKEN, OBJECT_INHERIT_ACE,
+        PSECURITY_DESCRIPTOR, SECURITY_MAX_SID_SIZE, SID_AND_ATTRIBUTES, TOKEN_ADJUST_DEFAULT,
+        TOKEN_ASSIGN_PRIMARY, TOKEN_DUPLICATE, TOKEN_MANDATORY_LABEL, TOKEN_QUERY,
+    };
+    use windows_sys::Win32::System::Console::{
+        GetStdHandle, STD_ERROR_HANDLE, STD_INPUT_HANDLE, STD_OUTPUT_HANDLE,
+    };
+    use windows_sys::Win32::System::JobObjects::{
+        AssignProcessToJobObject, CreateJobObjectW, JobObjectBasicUIRestrictions,
+        JobObjectExtendedLimitInformation, SetInformationJobObject,
+        JOBOBJECT_BASIC_UI_RESTRICTIONS, JOBOBJECT_EXTENDED_LIMIT_INFORMATION,
+        JOB_OBJECT_LIMIT_DIE_ON_UNHANDLED_EXCEPTION, JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE,
+        JOB_OBJECT_UILIMIT_DESKTOP, JOB_OBJECT_UILIMIT_DISPLAYSETTINGS,
+        JOB_OBJECT_UILIMIT_EXITWINDOWS, JOB_OBJECT_UILIMIT_GLOBALATOMS, JOB_OBJECT_UILIMIT_HANDLES,
+        JOB_OBJECT_UILIMIT_READCLIPBOARD, JOB_OBJECT_UILIMIT_SYSTEMPARAMETERS,
+        JOB_OBJECT_UILIMIT_WRITECLIPBOARD,
+    };
+    use windows_sys::Win32::System::SystemServices::{
+        SE_GROUP_INTEGRITY, SYSTEM_MANDATORY_LABEL_NO_WRITE_UP,
+    };
+    use windows_sys::Win32::System::Threading::{
+        CreateProcessAsUserW, GetCurrentProcess, GetExitCodeProcess, OpenProcessToken,
+        ResumeThread, TerminateProcess, WaitForSingleObject, CREATE_SUSPENDED, INFINITE,
+        PROCESS_INFORMATION, STARTF_USESTDHANDLES, STARTUPINFOW,
+    };
+
+    /// 句柄随作用域关闭；Job 句柄关闭时其中的进程一并结束
+    struct Owned(HANDLE);
+
+    impl Drop for Owned {
+        fn drop(&mut self) {
+            if !self.0.is_null() {
+                unsafe {
+                    CloseHandle(self.0);
+                }
+            }
+        }
+    }
+
+    fn last_error(what: &str) -> String {
+        format!("{}: {}", what, std::io::Error::last_os_error())
+    }
+
+    fn wide(path: &Path) -> Vec<u16> {
+        path.as_os_str()
+            .encode_wide()
+            .chain(std::iter::once(0))
+            .collect()
+    }
+
+    /// 按 Windows 命令行规则给参数加引号
+    fn quote(arg: &str) -> String {
+        if !arg.is_empty() && !arg.contains([' ', '\t', '"']) {
+            return arg.to_string();
+        }
+        let mut out = String::from('"');
+        let mut backslashes = 0;
+        for c in arg.chars() {
+            if c == '\\' {
+                backslashes += 1;
+                continue;
+            }
+            let escaped = if c == '"' {
+                backslashes * 2 + 1
+            } else {
+                backslashes
+            };
+            out.extend(std::iter::repeat_n('\\', escaped));
+            out.push(c);
+            backslashes = 0;
+        }
+        out.extend(std::iter::repeat_n('\\', backslashes * 2));
+        out.push('"');
+        out
+    }
+
+    /// 当前令牌的受限副本：去掉除 SeChangeNotify 外的全部特权，管理员组只用于拒绝访问
+    unsafe fn restricted_token() -> Result<Owned, String> {
+        let mut token: HANDLE = std::ptr::null_mut();
+        let access = TOKEN_DUPLICATE | TOKEN_QUERY | TOKEN_ASSIGN_PRIMARY | TOKEN_ADJUST_DEFAULT;
+        if OpenProcessToken(GetCurrentProcess(), access, &mut token) == 0 {
+            return Err(last_error("读取进程令牌失败"));
+        }
+        let token = Owned(token);
+        let mut sid = [0u8; SECURITY_MAX_SID_SIZE as usize];
+        let mut size = SECURITY_MAX_SID_SIZE;
+        let admins = sid.as_mut_ptr().cast();
+        if CreateWellKnownSid(
+            WinBuiltinAdministratorsSid,
+            std::ptr::null_mut(),
+            admins,
+            &mut size,
+        ) == 0
+        {
+            return Err(last_error("创建管理员组 SID 失败"));
+        }
+        let disable = SID_AND_ATTRIBUTES {
+            Sid: admins,
+            Attributes: 0,
+        };
+        let mut restricted: HANDLE = std::ptr::null_mut();
+        let ok = CreateRestrictedToken(
+            token.0,
+            DISABLE_MAX_PRIVILEGE | LUA_TOKEN,
+            1,
+            &disable,
+            0,
+            std::ptr::null(),
+            0,
+            std::ptr::null(),
+            &mut restricted,
+        );
+        if ok == 0 {
+            return Err(last_error("创建受限令牌失败"));
+        }
+        Ok(Owned(restricted))
+    }
+
+    /// 低完整性标签 SID（S-1-16-4096）
+    pub(super) unsafe fn low_label_sid() -> Result<[u8; SECURITY_MAX_SID_SIZE as usize], String> {
+        let mut sid = [0u8; SECURITY_MAX_SID_SIZE as usize];
+        let mut size = SECURITY_MAX_SID_SIZE;
+        if CreateWellKnownSid(
+            WinLowLabelSid,
+            std::ptr::null_mut(),
+            sid.as_mut_ptr().cast(),
+            &mut size,
+        ) == 0
+        {
+            return Err(last_error("创建低完整性 SID 失败"));
+        }
+        Ok(sid)
+    }
+
+    /// 把目录标记为低完整性并由其下的文件与子目录继承，低完整性进程才能写入。
+    /// 只改完整性标签，不改 DACL；原标签先由 `read_label` 记下，用完后以 `restore_label` 恢复
+    pub(super) unsafe fn label_low(dir: &Path, low: &mut [u8]) -> Result<(), String> {
+        // ACL 头 + 一条 SYSTEM_MANDATORY_LABEL_ACE，按 DWORD 对齐
+        let mut buf = [0u32; 32];
+        let acl: *mut ACL = buf.as_mut_ptr().cast();
+        let sid = low.as_mut_ptr().cast();
+        let size = std::mem::size_of::<ACL>() as u32 + 12 + GetLengthSid(sid);
+        let ok = InitializeAcl(acl, size, ACL_REVISION) != 0
+            && AddMandatoryAce(
+                acl,
+                ACL_REVISION,
+                OBJECT_INHERIT_ACE | CONTAINER_INHERIT_ACE,
+                SYSTEM_MANDATORY_LABEL_NO_WRITE_UP,
+                sid,
+            ) != 0;
+        if !ok {
+            return Err(last_error("构造完整性标签失败"));
+        }
+        let err = SetNamedSecurityInfoW(
+            wide(dir).as_ptr(),
+            SE_FILE_OBJECT,
+            LABEL_SECURITY_INFORMATION,
+            std::ptr::null_mut(),
+            std::ptr::null_mut(),
+            std::ptr::null(),
+            acl,
+        );
+        if err != ERROR_SUCCESS {
+            return Err(format!(
+                "标记可写目录 {} 失败: {}",
+                dir.display(),
+                std::io::Error::from_raw_os_error(err as i32)
+            ));
+        }
+        Ok(())
+    }
+
+    /// 目录当前的完整性标签，以 SDDL 表示（如 `S:(ML;OICI;NW;;;LW)`）；没有标签时为空
+    pub(super) unsafe fn read_label(dir: &Path) -> Result<String, String> {
+        let mut sd: PSECURITY_DESCRIPTOR = std::ptr::null_mut();
+        let err = GetNamedSecurityInfoW(
+            wide(dir).as_ptr(),
+            SE_FILE_OBJECT,
+            LABEL_SECURITY_INFORMATION,
+            std::ptr::null_mut(),
+            std::ptr::null_mut(),
+            std::ptr::null_mut(),
+            std::ptr::null_mut(),
+            &mut sd,
+        );
+        if err != ERROR_SUCCESS {
+            return Err(format!(
+                "读取 {} 的完整性标签失败: {}",
+                dir.display(),
+                std::io::Error::from_raw_os_error(err as i32)
+            ));
+        }
+        let mut text: *mut u16 = std::ptr::null_mut();
+        let mut len = 0u32;
+        let ok = ConvertSecurityDescriptorToStringSecurityDescriptorW(
+            sd,
+            SDDL_REVISION_1,
+            LABEL_SECURITY_INFORMATION,
+            &mut text,
+            &mut len,
+        );
+        let err = last_error("转换完整性标签失败");
+        LocalFree(sd);
+        if ok == 0 {
+            return Err(err);
+        }
+        let sddl = String::from_utf16_lossy(std::slice::from_raw_parts(text, len as usize));
+        LocalFree(text.cast());
+        Ok(sddl.trim_end_matches('\0').to_string())
+    }
+
+    /// 恢复 `read_label` 记下的标签；原先没有标签时清除标记（恢复为从上级继承）
+    pub(super) unsafe fn restore_label(dir: &Path, sddl: &str) -> Result<(), String> {
+        let mut empty = [0u32; 8];
+        let mut sacl: *mut ACL = empty.as_mut_ptr().cast();
+        let mut sd: PSECURITY_DESCRIPTOR = std::ptr::null_mut();
+        if sddl.contains('(') {
+            let text: Vec<u16> = sddl.encode_utf16().chain(std::iter::once(0)).collect();
+            if ConvertStringSecurityDescriptorToSecurityDescriptorW(
+                text.as_ptr(),
+                SDDL_REVISION_1,
+                &mut sd,
+                std::ptr::null_mut(),
+            ) == 0
+            {
+                return Err(last_error("解析完整性标签失败"));
+            }
+            let (mut present, mut defaulted) = (0, 0);
+            if GetSecurityDescriptorSacl(sd, &mut present, &mut sacl, &mut defaulted) == 0
+                || present == 0
+            {
+                LocalFree(sd);
+                return Err(format!("记录的完整性标签无效: {}", sddl));
+            }
+        } else if InitializeAcl(sacl, std::mem::size_of::<ACL>() as u32, ACL_REVISION) == 0 {
+            return Err(last_error("构造空标签失败"));
+        }
+        let err = SetNamedSecurityInfoW(
+            wide(dir).as_ptr(),
+            SE_FILE_OBJECT,
+            LABEL_SECURITY_INFORMATION,
+            std::ptr::null_mut(),
+            std::ptr::null_mut(),
+            std::ptr::null(),
+            sacl,
+        );
+        if !sd.is_null() {
+            LocalFree(sd);
+        }
+        if err != ERROR_SUCCESS {
+            return Err(format!(
+                "恢复 {} 的完整性标签失败: {}",
+                dir.display(),
+                std::io::Error::from_raw_os_error(err as i32)
+            ));
+        }
+        Ok(())
+    }
+
+    /// 把令牌降为低完整性：不能写入未标记为低完整性的文件、目录与注册表项
+    unsafe fn lower_integrity(token: &Owned, low: &mut [u8]) -> Result<(), String> {
+        let label = TOKEN_MANDATORY_LABEL {
+            Label: SID_AND_ATTRIBUTES {
+                Sid: low.as_mut_ptr().cast(),
+                Attributes: SE_GROUP_INTEGRITY as u32,
+            },
+        };
+        let size =
+            std::mem::size_of::<TOKEN_MANDATORY_LABEL>() as u32 + GetLengthSid(label.Label.Sid);
+        if SetTokenInformation(
+            token.0,
+            TokenIntegrityLevel,
+            &label as *const _ as *const _,
+            size,
+        ) == 0
+        {
+            return Err(last_error("降低令牌完整性失败"));
+        }
+        Ok(())
+    }
+
+    /// 进程退出时结束、禁止读写剪贴板与操作其他进程窗口等界面行为的 Job Object
+    unsafe fn sandbox_job() -> Result<Owned, String> {
+        let job = Owned(CreateJobObjectW(std::ptr::null(), std::ptr::null()));
+        if job.0.is_null() {
+            return Err(last_error("创建 Job Object 失败"));
+        }
+        let mut limits: JOBOBJECT_EXTENDED_LIMIT_INFORMATION = std::mem::zeroed();
+        limits.BasicLimitInformation.LimitFlags =
+            JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE | JOB_OBJECT_LIMIT_DIE_ON_UNHANDLED_EXCEPTION;
+        let ui = JOBOBJECT_BASIC_UI_RESTRICTIONS {
+            UIRestrictionsClass: JOB_OBJECT_UILIMIT_DESKTOP
+                | JOB_OBJECT_UILIMIT_DISPLAYSETTINGS
+                | JOB_OBJECT_UILIMIT_EXITWINDOWS
+                | JOB_OBJECT_UILIMIT_GLOBALATOMS
+                | JOB_OBJECT_UILIMIT_HANDLES
+                | JOB_OBJECT_UILIMIT_READCLIPBOARD
+                | JOB_OBJECT_UILIMIT_SYSTEMPARAMETERS
+                | JOB_OBJECT_UILIMIT_WRITECLIPBOARD,
+        };
+        let ok = SetInformationJobObject(
+            job.0,
+            JobObjectExtendedLimitInformation,
+            &limits as *const _ as *const _,
+            std::mem::size_of::<JOBOBJECT_EXTENDED_LIMIT_INFORMATION>() as u32,
+        ) != 0
+            && SetInformationJobObject(
+                job.0,
+                JobObjectBasicUIRestrictions,
+                &ui as *const _ as *const _,
+                std::mem::size_of::<JOBOBJECT_BASIC_UI_RESTRICTIONS>() as u32,
+            ) != 0;
+        if !ok {
+            return Err(last_error("设置 Job Object 限制失败"));
+        }
+        Ok(job)
+    }
+
+    /// 以低完整性受限令牌挂起启动命令，加入 Job 后再恢复运行，保证 bridge 及其派生的进程都受限；
+    /// 系统临时目录下的私有目录（本程序专用，保留低完整性标签）作为 bridge 的 TEMP。
+    /// stdio 直接交给 bridge，返回其退出码
+    pub fn exec(command: &[String]) -> Result<i32, String> {
+        if command.is_empty() {
+            return Err("缺少要启动的命令".to_string());
+        }
+        let tmp = std::env::temp_dir().join("mph-agent-sandbox");
+        std::fs::create_dir_all(&tmp).map_err(|e| format!("创建沙箱临时目录失败: {}", e))?;
+        // 由 bridge 继承（含 PyInstaller 解包与 JVM 临时文件）
+        std::env::set_var("TEMP", &tmp);
+        std::env::set_var("TMP", &tmp);
+        let mut line: Vec<u16> = command
+            .iter()
+            .map(|a| quote(a))
+            .collect::<Vec<_>>()
+            .join(" ")
+            .encode_utf16()
+            .chain(std::iter::once(0))
+            .collect();
+        unsafe {
+            let mut low = low_label_sid()?;
+            label_low(&tmp, &mut low)?;
+            let token = restricted_token()?;
+            lower_integrity(&token, &mut low)?;
+            let job = sandbox_job()?;
+            let mut startup: STARTUPINFOW = std::mem::zeroed();
+            startup.cb = std::mem::size_of::<STARTUPINFOW>() as u32;
+            startup.dwFlags = STARTF_USESTDHANDLES;
+            startup.hStdInput = GetStdHandle(STD_INPUT_HANDLE);
+            startup.hStdOutput = GetStdHandle(STD_OUTPUT_HANDLE);
+            startup.hStdError = GetStdHandle(STD_ERROR_HANDLE);
+            let mut info: PROCESS_INFORMATION = std::mem::zeroed();
+            let ok = CreateProcessAsUserW(
+                token.0,
+                std::ptr::null(),
+                line.as_mut_ptr(),
+                std::ptr::null(),
+                std::ptr::null(),
+                1,
+                CREATE_SUSPENDED,
+                std::ptr::null(),
+                std::ptr::null(),
+                &startup,
+                &mut info,
+            );
+            if ok == 0 {
+                return Err(last_error(&format!("启动 {} 失败", command[0])));
+            }
+            let process = Owned(info.hProcess);
+            let thread = Owned(info.hThread);
+            if AssignProcessToJobObject(job.0, process.0) == 0 {
+                let err = last_error("加入 Job Object 失败");
+                TerminateProcess(process.0, 1);
+                return Err(err);
+            }
+            ResumeThread(thread.0);
+            drop(thread);
+            WaitForSingleObject(process.0, INFINITE);
+            let mut code = 1u32;
+            GetExitCodeProcess(process.0, &mut code);
+            Ok(code as i32)
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn sandbox_get(sandbox: tauri::State<'_, Sandbox>) -> Result<SandboxConfig, String> {
+    guarded("sandbox_get", async move { Ok(sandbox.config()) }).await
+}
+
+/// 修改沙箱设置；对 bridge 的限制在下次启动 bridge 时生效
+#[tauri::command]
+pub async fn sandbox_set(
+    app: AppHandle,
+    sandbox: tauri::State<'_, Sandbox>,
+    enabled: bool,
+    roots: Vec<String>,
+) -> Result<(), String> {
+    guarded("sandbox_set", async move {
+        let roots: Vec<String> = roots
+            .into_iter()
+            .map(|r| r.trim().to_string())
+            .filter(|r| !r.is_empty())
+            .collect();
+        if enabled && roots.is_empty() {
+            return Err("启用沙箱时至少需要一个工作区目录".to_string());
+        }
+        for r in &roots {
+            if !Path::new(r).is_dir() {
+                return Err(format!("目录不存在: {}", r));
+            }
+        }
+        sandbox.set(SandboxConfig { enabled, roots })?;
+        // 按旧目录预热的备用进程直接换掉
+        if standby::discard(&app).await {
+            standby::refill(&app);
+        }
+        Ok(())
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "mph-sandbox-{}-{}-{}",
+            name,
+            std::process::id(),
+            crate::store::now_ms()
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn check_payload_confines_write_locations() {
+        // ACTIVE_ROOTS 为全局状态，启用与关闭放在同一个测试里
+        let root = temp_dir("root");
+        let outside = root.ancestors().last().unwrap().join("mph-sandbox-out.mph");
+        apply(&SandboxConfig {
+            enabled: true,
+            roots: vec![root.to_string_lossy().into_owned()],
+        });
+
+        for ok in [
+            json!({ "workspace_dir": root }),
+            json!({ "output": "model.mph" }),
+            json!({ "output": root.join("new").join("model.mph") }),
+            json!({ "output_filename": " ", "cmd": "run" }),
+        ] {
+            assert!(check_payload(&ok).is_ok(), "{}", ok);
+        }
+        for bad in [
+            json!({ "output_dir": outside }),
+            json!({ "output": "../model.mph" }),
+            json!({ "output_filename": "sub/model.mph" }),
+            json!({ "workspace_dir": "relative/dir" }),
+            json!({ "output": root.join("new").join("..").join("model.mph") }),
+        ] {
+            assert!(check_payload(&bad).is_err(), "{}", bad);
+        }
+
+        apply(&SandboxConfig::default());
+        assert!(check_payload(&json!({ "output_dir": outside })).is_ok());
+        assert!(check_payload(&json!({ "output": "../model.mph" })).is_ok());
+        let _ = std::fs::remove_dir_all(root);
+    }
+
+    #[test]
+    fn bwrap_uses_private_tmp() {
+        let args = bwrap_args(&[PathBuf::from("/tmp"), PathBuf::from("/work")]);
+        let joined = args.join(" ");
+        assert!(joined.starts_with("--ro-bind / / "));
+        assert!(joined.contains("--tmpfs /tmp"));
+        assert!(joined.ends_with("--bind /work /work"));
+        assert!(!joined.contains("--bind /tmp"));
+    }
+
+    #[test]
+    fn parse_exec_args_strips_separator() {
+        let args = ["--", "py", "-m", "x"].map(String::from);
+        assert_eq!(parse_exec_args(args), vec!["py", "-m", "x"]);
+        let args = ["py", "--"].map(String::from);
+        assert_eq!(parse_exec_args(args), vec!["py", "--"]);
+    }
+
+    #[test]
+    fn labels_restore_only_after_last_user() {
+        let (work, out) = (PathBuf::from("C:\\work"), PathBuf::from("D:\\out"));
+        let mut labels = Labels::new();
+        let mut reads = Vec::new();
+        let mut read = |d: &Path| {
+            reads.push(d.to_path_buf());
+            Ok(format!("S:{}", d.display()))
+        };
+        assert!(labels.take(&work, &mut read).unwrap());
+        assert!(labels.take(&out, &mut read).unwrap());
+        // 第二个会话的 bridge 使用同一目录：不再读取（此时已是低完整性），也不重复标记
+        assert!(!labels.take(&work, &mut read).unwrap());
+        assert_eq!(reads, [work.clone(), out.clone()]);
+
+        assert!(!labels.give_back(&work));
+        assert!(labels.give_back(&work));
+        assert!(!labels.give_back(&work));
+        assert_eq!(labels.original[&work], "S:C:\\work");
+
+        // 记录只含原标签，不含使用者
+        let saved = serde_json::to_value(&labels).unwrap();
+        assert_eq!(saved["original"].as_object().unwrap().len(), 2);
+        assert!(saved.get("users").is_none());
+    }
+
+    #[test]
+    fn labels_keep_recorded_original_and_skip_unreadable_dirs() {
+        let dir = PathBuf::from("C:\\work");
+        let mut labels = Labels::new();
+        // 上次未能恢复的目录沿用记录中的原标签，而不是读取当前（仍为低完整性的）标签
+        labels.original.insert(dir.clone(), String::new());
+        assert!(labels
+            .take(&dir, |_| Ok("S:(ML;OICI;NW;;;LW)".to_string()))
+            .unwrap());
+        assert_eq!(labels.original[&dir], "");
+
+        let other = PathBuf::from("D:\\gone");
+        assert!(labels
+            .take(&other, |_| Err("拒绝访问".to_string()))
+            .is_err());
+        assert!(!labels.give_back(&other));
+        assert!(!labels.original.contains_key(&other));
+
+        // 非 Windows 上恢复为空操作：已无使用者的目录从记录中移除
+        assert!(labels.give_back(&dir));
+        restore_unused(&mut labels, std::slice::from_ref(&dir));
+        assert!(labels.original.is_empty());
+    }
+}
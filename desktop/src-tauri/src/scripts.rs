@@ -0,0 +1,109 @@
+use crate::bridge::{send_request, BridgeState};
+use crate::jobs::JobRegistry;
+use crate::notes;
+use crate::panics::guarded;
+use crate::policy::CommandPolicy;
+use crate::sandbox::check_path;
+use crate::store::{now_ms, save_json};
+use serde::Serialize;
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+
+#[derive(Serialize)]
+struct ScriptManifest<'a> {
+    job_id: &'a str,
+    cmd: &'a str,
+    project: Option<&'a str>,
+    format: &'a str,
+    script: String,
+    model_paths: &'a [String],
+    /// 已剔除敏感字段的原始请求
+    request: &'a Value,
+    /// 所属项目的笔记（建模假设等），随脚本一起留档
+    #[serde(skip_serializing_if = "Option::is_none")]
+    project_notes: Option<String>,
+    exported_at: u64,
+}
+
+/// 把任务执行过的建模代码导出为可独立运行的 COMSOL Java / MATLAB 脚本，并在旁边写入清单。
+/// bridge 需加载任务产出的模型再另存为脚本，会占用许可证，只读模式下不可用
+#[tauri::command]
+pub async fn export_job_script(
+    state: tauri::State<'_, BridgeState>,
+    jobs: tauri::State<'_, JobRegistry>,
+    policy: tauri::State<'_, CommandPolicy>,
+    job_id: String,
+    format: String,
+    path: String,
+) -> Result<String, String> {
+    guarded("export_job_script", async move {
+        policy.ensure_writable("导出脚本")?;
+        let job = jobs
+            .get(&job_id)
+            .ok_or_else(|| format!("未找到任务: {}", job_id))?;
+        let (format, ext) = match format.trim().to_lowercase().as_str() {
+            "java" => ("java", "java"),
+            "m" | "matlab" => ("matlab", "m"),
+            other => return Err(format!("不支持的脚本格式: {}", other)),
+        };
+        let path = path.trim();
+        if path.is_empty() {
+            return Err("路径为空".to_string());
+        }
+        let mut out = PathBuf::from(path);
+        if out.extension().is_none() {
+            out.set_extension(ext);
+        }
+        check_path(&out)?;
+
+        let resp = send_request(
+            state.inner(),
+            "export_script",
+            serde_json::json!({
+                "job_id": job.id,
+                "format": format,
+                "model_path": job.artifacts.last(),
+                "conversation_id": job.payload.get("conversation_id"),
+                "input": job.payload.get("input"),
+            }),
+        )
+        .await?;
+        if resp["ok"].as_bool() != Some(true) {
+            return Err(resp["message"]
+                .as_str()
+                .unwrap_or("bridge 导出脚本失败")
+                .to_string());
+        }
+        let code = resp["code"]
+            .as_str()
+            .or_else(|| resp["script"].as_str())
+            .ok_or("bridge 未返回脚本代码")?;
+
+        if let Some(dir) = out.parent() {
+            std::fs::create_dir_all(dir).map_err(|e| format!("创建目录失败: {}", e))?;
+        }
+        std::fs::write(&out, code).map_err(|e| format!("写入 {} 失败: {}", out.display(), e))?;
+
+        let script_name = out
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let manifest = ScriptManifest {
+            job_id: &job.id,
+            cmd: &job.cmd,
+            project: job.project.as_deref(),
+            format,
+            script: script_name,
+            model_paths: &job.artifacts,
+            request: &job.payload,
+            project_notes: job
+                .project
+                .as_deref()
+                .and_then(|p| notes::read(Path::new(p))),
+            exported_at: now_ms(),
+        };
+        save_json(&out.with_extension("manifest.json"), &manifest)?;
+        Ok(out.to_string_lossy().into_owned())
+    })
+    .await
+}
@@ -0,0 +1,732 @@
+use crate::artifacts::ArtifactRegistry;
+use crate::bridge::find_project_root;
+use crate::jobs::JobRegistry;
+use crate::panics::guarded;
+use crate::recent::RecentModels;
+use crate::store::{memory_db, now_ms, open_db};
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+const INDEX_FIRST_DELAY: Duration = Duration::from_secs(5);
+const INDEX_INTERVAL: Duration = Duration::from_secs(60);
+/// 搜索时索引早于此时间则先增量更新一次
+const STALE_MS: u64 = 10_000;
+const DEFAULT_LIMIT: usize = 20;
+const MAX_LIMIT: usize = 200;
+const SNIPPET_CHARS: usize = 60;
+/// 标题列在 BM25 中的权重（正文为 1）
+const TITLE_WEIGHT: f64 = 2.0;
+
+/// docs：文档原文与内容指纹；docs_fts：按 [`tokenize`] 切好的词（空格分隔）建的 FTS5 索引，
+/// rowid 与 docs.id 一致；docs_vocab：索引中的词表，供维护报告统计
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS docs (
+        id INTEGER PRIMARY KEY,
+        doc_id TEXT NOT NULL UNIQUE,
+        kind TEXT NOT NULL,
+        key TEXT NOT NULL,
+        title TEXT NOT NULL,
+        body TEXT NOT NULL,
+        project TEXT,
+        updated_at INTEGER NOT NULL,
+        fingerprint TEXT NOT NULL
+    );
+    CREATE VIRTUAL TABLE IF NOT EXISTS docs_fts
+        USING fts5(title, body, tokenize = 'unicode61 remove_diacritics 0');
+    CREATE VIRTUAL TABLE IF NOT EXISTS docs_vocab USING fts5vocab(docs_fts, 'row');
+";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DocKind {
+    /// .mph 模型文件：最近打开的模型与登记的模型产物
+    Model,
+    Job,
+    /// 非模型的产物文件（导出的图片、表格等）
+    Artifact,
+    /// Python 端的对话会话 `<项目根>/.context/<会话>`
+    Session,
+}
+
+impl DocKind {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Model => "model",
+            Self::Job => "job",
+            Self::Artifact => "artifact",
+            Self::Session => "session",
+        }
+    }
+
+    fn parse(s: &str) -> Option<Self> {
+        serde_json::from_value(Value::String(s.to_string())).ok()
+    }
+}
+
+struct Document {
+    kind: DocKind,
+    key: String,
+    title: String,
+    body: String,
+    project: Option<String>,
+    updated_at: u64,
+}
+
+impl Document {
+    fn id(&self) -> String {
+        format!("{:?}:{}", self.kind, self.key)
+    }
+
+    /// 内容指纹随索引落盘，跨版本、跨启动保持稳定
+    fn fingerprint(&self) -> String {
+        let mut h = Sha256::new();
+        for part in [&self.title, &self.body] {
+            h.update(part.as_bytes());
+            h.update([0]);
+        }
+        h.update(self.project.as_deref().unwrap_or("\u{0}").as_bytes());
+        h.update(self.updated_at.to_le_bytes());
+        format!("{:x}", h.finalize())
+    }
+}
+
+/// 模型、任务、产物与对话会话的全文索引，存于 SQLite FTS5（`search_index.sqlite3`），
+/// 由后台任务按内容指纹增量更新，重启后无需重建
+pub struct SearchIndex {
+    db: Mutex<Connection>,
+    /// 本次运行中最近一次增量更新的时间；启动后首次搜索前先核对一遍
+    indexed_at: AtomicU64,
+}
+
+impl Default for SearchIndex {
+    fn default() -> Self {
+        Self::with_db(memory_db(SCHEMA))
+    }
+}
+
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct IndexUpdate {
+    pub added: usize,
+    pub updated: usize,
+    pub removed: usize,
+    pub documents: usize,
+}
+
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct CompactReport {
+    pub documents: usize,
+    pub terms: usize,
+    /// 合并索引段并 VACUUM 后收回的空间（字节）
+    pub reclaimed: usize,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct SearchHit {
+    pub kind: DocKind,
+    /// 模型与产物为文件路径，任务为任务 ID，会话为会话目录名
+    pub key: String,
+    pub title: String,
+    pub snippet: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub project: Option<String>,
+    pub score: f64,
+    pub updated_at: u64,
+}
+
+/// FTS5 查询：各词作为短语取并集，最后一个词按前缀匹配
+fn fts_query(terms: &[String]) -> String {
+    let last = terms.len() - 1;
+    terms
+        .iter()
+        .enumerate()
+        .map(|(i, t)| {
+            let phrase = format!("\"{}\"", t.replace('"', "\"\""));
+            if i == last {
+                phrase + "*"
+            } else {
+                phrase
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" OR ")
+}
+
+/// 文档命中的查询词数：最后一个词按前缀匹配，其余按整词匹配
+fn matched_terms(indexed: &str, terms: &[String]) -> usize {
+    let words: HashSet<&str> = indexed.split_whitespace().collect();
+    let last = terms.len() - 1;
+    terms
+        .iter()
+        .enumerate()
+        .filter(|(i, t)| {
+            if *i == last {
+                words.iter().any(|w| w.starts_with(t.as_str()))
+            } else {
+                words.contains(t.as_str())
+            }
+        })
+        .count()
+}
+
+impl SearchIndex {
+    pub fn load(path: PathBuf) -> Self {
+        Self::with_db(open_db(&path, SCHEMA))
+    }
+
+    fn with_db(conn: Connection) -> Self {
+        Self {
+            db: Mutex::new(conn),
+            indexed_at: AtomicU64::new(0),
+        }
+    }
+
+    fn db(&self) -> std::sync::MutexGuard<'_, Connection> {
+        self.db.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn is_stale(&self) -> bool {
+        now_ms().saturating_sub(self.indexed_at.load(Ordering::Relaxed)) > STALE_MS
+    }
+
+    /// 以一轮采集到的全部文档替换索引：内容未变的文档不重新分词
+    fn apply(&self, docs: Vec<Document>) -> Result<IndexUpdate, String> {
+        let update = self
+            .write(docs)
+            .map_err(|e| format!("写入搜索索引失败: {}", e))?;
+        self.indexed_at.store(now_ms(), Ordering::Relaxed);
+        Ok(update)
+    }
+
+    fn write(&self, docs: Vec<Document>) -> rusqlite::Result<IndexUpdate> {
+        let mut conn = self.db();
+        let tx = conn.transaction()?;
+        let mut update = IndexUpdate::default();
+        // doc_id -> (rowid, 指纹)
+        let known: HashMap<String, (i64, String)> = {
+            let mut stmt = tx.prepare("SELECT doc_id, id, fingerprint FROM docs")?;
+            let rows = stmt.query_map([], |r| Ok((r.get(0)?, (r.get(1)?, r.get(2)?))))?;
+            rows.collect::<rusqlite::Result<_>>()?
+        };
+        let mut seen = HashSet::new();
+        {
+            let mut upsert = tx.prepare(
+                "INSERT INTO docs (doc_id, kind, key, title, body, project, updated_at, fingerprint)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+                 ON CONFLICT(doc_id) DO UPDATE SET
+                     title = excluded.title, body = excluded.body, project = excluded.project,
+                     updated_at = excluded.updated_at, fingerprint = excluded.fingerprint
+                 RETURNING id",
+            )?;
+            let mut unindex = tx.prepare("DELETE FROM docs_fts WHERE rowid = ?1")?;
+            let mut index =
+                tx.prepare("INSERT INTO docs_fts (rowid, title, body) VALUES (?1, ?2, ?3)")?;
+            for doc in docs {
+                let id = doc.id();
+                if !seen.insert(id.clone()) {
+                    continue;
+                }
+                let fingerprint = doc.fingerprint();
+                match known.get(&id) {
+                    Some((_, f)) if *f == fingerprint => continue,
+                    Some((row, _)) => {
+                        unindex.execute([row])?;
+                        update.updated += 1;
+                    }
+                    None => update.added += 1,
+                }
+                let row: i64 = upsert.query_row(
+                    params![
+                        id,
+                        doc.kind.as_str(),
+                        doc.key,
+                        doc.title,
+                        doc.body,
+                        doc.project,
+                        doc.updated_at as i64,
+                        fingerprint
+                    ],
+                    |r| r.get(0),
+                )?;
+                index.execute(params![
+                    row,
+                    tokenize(&doc.title).join(" "),
+                    tokenize(&doc.body).join(" ")
+                ])?;
+            }
+            let mut remove = tx.prepare("DELETE FROM docs WHERE id = ?1")?;
+            for (id, (row, _)) in &known {
+                if !seen.contains(id) {
+                    unindex.execute([row])?;
+                    remove.execute([row])?;
+                    update.removed += 1;
+                }
+            }
+        }
+        update.documents =
+            tx.query_row("SELECT count(*) FROM docs", [], |r| r.get::<_, i64>(0))? as usize;
+        tx.commit()?;
+        Ok(update)
+    }
+
+    /// 合并 FTS5 索引段并 VACUUM，收回增删文档后留下的空间；由维护调度在空闲时调用
+    pub fn compact(&self) -> Result<CompactReport, String> {
+        let conn = self.db();
+        let count = |sql: &str| conn.query_row(sql, [], |r| r.get::<_, i64>(0));
+        let size = "SELECT page_count * page_size FROM pragma_page_count(), pragma_page_size()";
+        let run = || -> rusqlite::Result<CompactReport> {
+            let before = count(size)?;
+            conn.execute_batch("INSERT INTO docs_fts (docs_fts) VALUES ('optimize'); VACUUM;")?;
+            Ok(CompactReport {
+                documents: count("SELECT count(*) FROM docs")? as usize,
+                terms: count("SELECT count(*) FROM docs_vocab")? as usize,
+                reclaimed: before.saturating_sub(count(size)?).max(0) as usize,
+            })
+        };
+        run().map_err(|e| format!("整理搜索索引失败: {}", e))
+    }
+
+    /// FTS5 的 BM25 排序（标题加权）；最后一个查询词按前缀匹配，便于边输入边搜索
+    pub fn search(&self, query: &str, kinds: &[DocKind], limit: usize) -> Vec<SearchHit> {
+        let terms = tokenize(query);
+        if terms.is_empty() {
+            return Vec::new();
+        }
+        let run = || -> rusqlite::Result<Vec<SearchHit>> {
+            let conn = self.db();
+            let mut stmt = conn.prepare(
+                "SELECT d.kind, d.key, d.title, d.body, d.project, d.updated_at,
+                        f.title || ' ' || f.body, bm25(docs_fts, ?2, 1.0)
+                 FROM docs_fts f JOIN docs d ON d.id = f.rowid
+                 WHERE docs_fts MATCH ?1",
+            )?;
+            let rows = stmt.query_map(params![fts_query(&terms), TITLE_WEIGHT], |r| {
+                let Some(kind) = DocKind::parse(&r.get::<_, String>(0)?) else {
+                    return Ok(None);
+                };
+                let body: String = r.get(3)?;
+                let indexed: String = r.get(6)?;
+                // bm25() 越小越相关；命中全部查询词的排在只命中部分的前面
+                let coverage = matched_terms(&indexed, &terms) as f64 / terms.len() as f64;
+                let bm25: f64 = r.get(7)?;
+                Ok(Some(SearchHit {
+                    kind,
+                    key: r.get(1)?,
+                    title: r.get(2)?,
+                    snippet: snippet(&body, &terms),
+                    project: r.get(4)?,
+                    score: -bm25 * coverage * coverage,
+                    updated_at: r.get::<_, i64>(5)? as u64,
+                }))
+            })?;
+            let mut hits = Vec::new();
+            for hit in rows {
+                match hit? {
+                    Some(h) if kinds.is_empty() || kinds.contains(&h.kind) => hits.push(h),
+                    _ => {}
+                }
+            }
+            Ok(hits)
+        };
+        let mut hits = run().unwrap_or_else(|e| {
+            eprintln!("Warning: 查询搜索索引失败: {}", e);
+            Vec::new()
+        });
+        hits.sort_by(|a, b| {
+            b.score
+                .total_cmp(&a.score)
+                .then(b.updated_at.cmp(&a.updated_at))
+        });
+        hits.truncate(limit);
+        hits
+    }
+}
+
+fn is_cjk(c: char) -> bool {
+    matches!(c as u32,
+        0x3040..=0x30FF | 0x3400..=0x4DBF | 0x4E00..=0x9FFF | 0xAC00..=0xD7AF | 0xF900..=0xFAFF)
+}
+
+/// 字母数字按词切分并转小写；中日韩文字没有分隔符，按相邻两字切分（单字成段时保留单字）
+fn tokenize(text: &str) -> Vec<String> {
+    let mut out = Vec::new();
+    let mut word = String::new();
+    let mut cjk: Vec<char> = Vec::new();
+    let flush_cjk = |cjk: &mut Vec<char>, out: &mut Vec<String>| {
+        if cjk.len() == 1 {
+            out.push(cjk[0].to_string());
+        }
+        out.extend(cjk.windows(2).map(|w| w.iter().collect::<String>()));
+        cjk.clear();
+    };
+    for c in text.chars() {
+        if is_cjk(c) {
+            if !word.is_empty() {
+                out.push(std::mem::take(&mut word));
+            }
+            cjk.push(c);
+        } else {
+            flush_cjk(&mut cjk, &mut out);
+            if c.is_alphanumeric() {
+                word.extend(c.to_lowercase());
+            } else if !word.is_empty() {
+                out.push(std::mem::take(&mut word));
+            }
+        }
+    }
+    flush_cjk(&mut cjk, &mut out);
+    if !word.is_empty() {
+        out.push(word);
+    }
+    out
+}
+
+/// 正文中第一个命中处前后的一段文字；没有命中时取开头
+fn snippet(body: &str, terms: &[String]) -> String {
+    let chars: Vec<char> = body.chars().collect();
+    let lower: Vec<char> = chars
+        .iter()
+        .map(|c| c.to_lowercase().next().unwrap_or(*c))
+        .collect();
+    let pos = terms
+        .iter()
+        .filter_map(|t| {
+            let t: Vec<char> = t.chars().collect();
+            lower.windows(t.len()).position(|w| w == t.as_slice())
+        })
+        .min()
+        .unwrap_or(0);
+    let start = pos.saturating_sub(SNIPPET_CHARS / 3);
+    let end = (start + SNIPPET_CHARS).min(chars.len());
+    let mut s: String = chars[start..end].iter().collect();
+    s = s.split_whitespace().collect::<Vec<_>>().join(" ");
+    if start > 0 {
+        s.insert(0, '…');
+    }
+    if end < chars.len() {
+        s.push('…');
+    }
+    s
+}
+
+fn modified_ms(meta: &std::fs::Metadata) -> u64 {
+    meta.modified()
+        .ok()
+        .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+fn file_name(path: &str) -> String {
+    Path::new(path)
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_else(|| path.to_string())
+}
+
+fn is_model(path: &str) -> bool {
+    Path::new(path)
+        .extension()
+        .is_some_and(|e| e.eq_ignore_ascii_case("mph"))
+}
+
+fn job_prompt(payload: &Value) -> Option<&str> {
+    payload
+        .get("input")
+        .and_then(|v| v.as_str())
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+}
+
+/// 采集全部待索引文档；读取文件元数据与会话记录，需在阻塞线程中调用
+fn collect(app: &AppHandle) -> Vec<Document> {
+    let jobs = app.state::<JobRegistry>().list(None, usize::MAX);
+    let mut docs = Vec::new();
+    // 产物路径 -> 生成它的任务的请求，模型文件借此能按建模需求搜到
+    let mut prompts: HashMap<&str, &str> = HashMap::new();
+    for job in &jobs {
+        let prompt = job_prompt(&job.payload);
+        if let Some(p) = prompt {
+            for a in &job.artifacts {
+                prompts.entry(a.as_str()).or_insert(p);
+            }
+        }
+        let mut body = vec![job.cmd.clone()];
+        body.extend(prompt.map(String::from));
+        body.extend(job.message.clone());
+        body.extend(job.artifacts.iter().map(|a| file_name(a)));
+        docs.push(Document {
+            kind: DocKind::Job,
+            key: job.id.clone(),
+            title: prompt
+                .map(|p| p.chars().take(80).collect())
+                .unwrap_or_else(|| format!("{} {}", job.cmd, job.id)),
+            body: body.join("\n"),
+            project: job.project.clone(),
+            updated_at: job.finished_at.or(job.started_at).unwrap_or(job.created_at),
+        });
+    }
+
+    // 路径 -> (项目, 请求 ID, 最近打开/登记时间)
+    let mut files: BTreeMap<String, (Option<String>, Option<String>, u64)> = BTreeMap::new();
+    for a in app.state::<ArtifactRegistry>().list() {
+        files.insert(a.path, (a.project, a.request_id, a.registered_at));
+    }
+    for m in app.state::<RecentModels>().list() {
+        let e = files.entry(m.path).or_insert((None, None, 0));
+        e.0 = e.0.take().or(m.project);
+        e.2 = e.2.max(m.opened_at);
+    }
+    for (path, (project, request_id, touched)) in files {
+        let meta = std::fs::metadata(&path).ok();
+        let name = file_name(&path);
+        let mut body = vec![path.clone()];
+        body.extend(prompts.get(path.as_str()).map(|p| p.to_string()));
+        body.extend(request_id);
+        if let Some(ref meta) = meta {
+            body.push(format!("{} bytes", meta.len()));
+        } else {
+            body.push("missing".to_string());
+        }
+        docs.push(Document {
+            kind: if is_model(&path) {
+                DocKind::Model
+            } else {
+                DocKind::Artifact
+            },
+            key: path,
+            title: name,
+            body: body.join("\n"),
+            project,
+            updated_at: meta.as_ref().map(modified_ms).unwrap_or(0).max(touched),
+        });
+    }
+
+    docs.extend(collect_sessions());
+    docs
+}
+
+/// Python 端对话会话：`<项目根>/.context/<会话>/history.json` 中的用户输入与回复摘要
+fn collect_sessions() -> Vec<Document> {
+    let Some(root) = find_project_root() else {
+        return Vec::new();
+    };
+    let Ok(rd) = std::fs::read_dir(root.join(".context")) else {
+        return Vec::new();
+    };
+    let mut docs = Vec::new();
+    for entry in rd.flatten() {
+        let history = entry.path().join("history.json");
+        let Ok(meta) = std::fs::metadata(&history) else {
+            continue;
+        };
+        let Ok(text) = std::fs::read_to_string(&history) else {
+            continue;
+        };
+        let Ok(Value::Array(items)) = serde_json::from_str::<Value>(&text) else {
+            continue;
+        };
+        let id = entry.file_name().to_string_lossy().into_owned();
+        let field = |item: &Value, k: &str| {
+            item.get(k)
+                .and_then(|v| v.as_str())
+                .map(str::trim)
+                .filter(|s| !s.is_empty())
+                .map(String::from)
+        };
+        let title = items
+            .iter()
+            .find_map(|i| field(i, "user_input"))
+            .map(|s| s.chars().take(80).collect())
+            .unwrap_or_else(|| id.clone());
+        let body: Vec<String> = items
+            .iter()
+            .flat_map(|i| {
+                ["user_input", "assistant_summary", "model_path", "error"]
+                    .into_iter()
+                    .filter_map(|k| field(i, k))
+            })
+            .collect();
+        docs.push(Document {
+            kind: DocKind::Session,
+            key: id,
+            title,
+            body: body.join("\n"),
+            project: None,
+            updated_at: modified_ms(&meta),
+        });
+    }
+    docs
+}
+
+async fn refresh(app: &AppHandle) -> Result<IndexUpdate, String> {
+    let handle = app.clone();
+    let docs = tauri::async_runtime::spawn_blocking(move || collect(&handle))
+        .await
+        .map_err(|e| e.to_string())?;
+    app.state::<SearchIndex>().apply(docs)
+}
+
+/// 后台定期增量更新索引；任务运行期间跳过，避免与建模争抢磁盘
+pub fn spawn_search_indexer(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(INDEX_FIRST_DELAY).await;
+        loop {
+            if app.state::<JobRegistry>().running().is_none() {
+                if let Err(e) = refresh(&app).await {
+                    eprintln!("Warning: 更新搜索索引失败: {}", e);
+                }
+            }
+            tokio::time::sleep(INDEX_INTERVAL).await;
+        }
+    });
+}
+
+/// 在模型、任务、产物与对话会话中搜索，按相关度排序；可用 `kinds` 限定类别
+#[tauri::command]
+pub async fn search_everything(
+    app: AppHandle,
+    query: String,
+    limit: Option<usize>,
+    kinds: Option<Vec<DocKind>>,
+) -> Result<Vec<SearchHit>, String> {
+    guarded("search_everything", async move {
+        if app.state::<SearchIndex>().is_stale() {
+            refresh(&app).await?;
+        }
+        let limit = limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+        Ok(app
+            .state::<SearchIndex>()
+            .search(&query, &kinds.unwrap_or_default(), limit))
+    })
+    .await
+}
+
+/// 立即重建索引中有变化的部分，返回增删数量
+#[tauri::command]
+pub async fn search_reindex(app: AppHandle) -> Result<IndexUpdate, String> {
+    guarded("search_reindex", async move { refresh(&app).await }).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn doc(kind: DocKind, key: &str, title: &str, body: &str, updated_at: u64) -> Document {
+        Document {
+            kind,
+            key: key.to_string(),
+            title: title.to_string(),
+            body: body.to_string(),
+            project: None,
+            updated_at,
+        }
+    }
+
+    fn keys(hits: &[SearchHit]) -> Vec<&str> {
+        hits.iter().map(|h| h.key.as_str()).collect()
+    }
+
+    #[test]
+    fn tokenize_splits_words_and_cjk_bigrams() {
+        assert_eq!(
+            tokenize("Heat-Transfer in_Solids v2"),
+            ["heat", "transfer", "in", "solids", "v2"]
+        );
+        assert_eq!(tokenize("传热模型"), ["传热", "热模", "模型"]);
+        // 单字成段时保留单字；中英文混排在边界处断开
+        assert_eq!(tokenize("热 COMSOL模型"), ["热", "comsol", "模型"]);
+        assert_eq!(tokenize("ÄrZte"), ["ärzte"]);
+        assert!(tokenize(" -- ,. ").is_empty());
+    }
+
+    #[test]
+    fn search_ranks_by_relevance_and_coverage() {
+        let index = SearchIndex::default();
+        index
+            .apply(vec![
+                doc(DocKind::Job, "both", "heat sink", "thermal run", 1),
+                doc(DocKind::Job, "one", "heat", "heat heat exchanger", 2),
+                doc(DocKind::Model, "body", "model.mph", "a heat study", 3),
+                doc(DocKind::Session, "cjk", "散热器建模", "铝制散热器", 4),
+            ])
+            .unwrap();
+        // 命中全部查询词的排在前面
+        assert_eq!(keys(&index.search("heat sink", &[], 10))[0], "both");
+        // 标题命中优先于只在正文中出现
+        let hits = index.search("heat", &[], 10);
+        assert_eq!(keys(&hits).last(), Some(&"body"));
+        // 最后一个词按前缀匹配
+        assert_eq!(keys(&index.search("exch", &[], 10)), ["one"]);
+        assert_eq!(index.search("heat exch", &[], 10)[0].key, "one");
+        // 中文按相邻两字检索
+        assert_eq!(keys(&index.search("散热", &[], 10)), ["cjk"]);
+        assert_eq!(keys(&index.search("建模", &[], 10)), ["cjk"]);
+        // 按类别过滤与截断
+        assert_eq!(keys(&index.search("heat", &[DocKind::Model], 10)), ["body"]);
+        assert_eq!(index.search("heat", &[], 1).len(), 1);
+        assert!(index.search("", &[], 10).is_empty());
+        assert!(index.search("\"*", &[], 10).is_empty());
+    }
+
+    #[test]
+    fn apply_updates_incrementally() {
+        let index = SearchIndex::default();
+        let first = index
+            .apply(vec![
+                doc(DocKind::Job, "a", "alpha", "first", 1),
+                doc(DocKind::Job, "b", "beta", "second", 1),
+                doc(DocKind::Job, "b", "beta", "duplicate", 1),
+            ])
+            .unwrap();
+        assert_eq!((first.added, first.updated, first.removed), (2, 0, 0));
+        assert_eq!(first.documents, 2);
+
+        let second = index
+            .apply(vec![
+                doc(DocKind::Job, "a", "alpha", "first", 1),
+                doc(DocKind::Job, "b", "beta", "changed", 2),
+                doc(DocKind::Model, "c", "gamma", "third", 1),
+            ])
+            .unwrap();
+        assert_eq!((second.added, second.updated, second.removed), (1, 1, 0));
+        assert!(index.search("second", &[], 10).is_empty());
+        assert_eq!(keys(&index.search("changed", &[], 10)), ["b"]);
+
+        let third = index
+            .apply(vec![doc(DocKind::Model, "c", "gamma", "third", 1)])
+            .unwrap();
+        assert_eq!((third.added, third.updated, third.removed), (0, 0, 2));
+        assert_eq!(third.documents, 1);
+        assert!(index.search("alpha", &[], 10).is_empty());
+
+        let report = index.compact().unwrap();
+        assert_eq!(report.documents, 1);
+        assert_eq!(report.terms, 2);
+    }
+
+    #[test]
+    fn index_persists_across_loads() {
+        let dir =
+            std::env::temp_dir().join(format!("mph-search-{}-{}", std::process::id(), now_ms()));
+        let path = dir.join("search_index.sqlite3");
+        let docs = || vec![doc(DocKind::Job, "a", "传热模型", "body", 1)];
+        SearchIndex::load(path.clone()).apply(docs()).unwrap();
+
+        let reloaded = SearchIndex::load(path);
+        assert!(reloaded.is_stale());
+        assert_eq!(keys(&reloaded.search("传热", &[], 10)), ["a"]);
+        // 内容未变的文档重启后不重新索引
+        let update = reloaded.apply(docs()).unwrap();
+        assert_eq!((update.added, update.updated, update.removed), (0, 0, 0));
+        assert!(!reloaded.is_stale());
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
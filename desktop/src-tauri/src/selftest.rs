@@ -0,0 +1,153 @@
+use crate::bridge::{send_request, BridgeState};
+use crate::license::LicenseGuard;
+use crate::panics::guarded;
+use crate::policy::CommandPolicy;
+use crate::store::{load_json, now_ms, save_json};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::Instant;
+use tauri::{AppHandle, Emitter, Manager};
+
+pub const SELFTEST_EVENT: &str = "bridge-selftest";
+
+/// 自检会启动 COMSOL 并占用许可证，默认关闭，由用户在设置中开启
+fn default_enabled() -> bool {
+    false
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct SelfTestConfig {
+    /// bridge 就绪后是否自动在后台跑一次自检
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+}
+
+impl Default for SelfTestConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_enabled(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct SelfTestResult {
+    pub ok: bool,
+    pub message: String,
+    pub elapsed_ms: u64,
+    pub finished_at: u64,
+    /// bridge 返回的 COMSOL / Java 信息，失败时可能为空
+    #[serde(skip_serializing_if = "serde_json::Value::is_null")]
+    pub detail: serde_json::Value,
+}
+
+/// 启动自检：新建并立即丢弃一个空模型，提前暴露 COMSOL/Java 配置问题
+#[derive(Default)]
+pub struct SelfTest {
+    path: Option<PathBuf>,
+    config: Mutex<SelfTestConfig>,
+    last: Mutex<Option<SelfTestResult>>,
+}
+
+impl SelfTest {
+    pub fn load(path: PathBuf) -> Self {
+        let config: SelfTestConfig = load_json(&path);
+        Self {
+            path: Some(path),
+            config: Mutex::new(config),
+            ..Self::default()
+        }
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.config
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .enabled
+    }
+
+    fn set_enabled(&self, enabled: bool) -> Result<(), String> {
+        let mut config = self.config.lock().unwrap_or_else(|e| e.into_inner());
+        config.enabled = enabled;
+        match self.path {
+            Some(ref p) => save_json(p, &*config),
+            None => Ok(()),
+        }
+    }
+
+    fn last(&self) -> Option<SelfTestResult> {
+        self.last.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+}
+
+/// 执行一次自检并以 `bridge-selftest` 事件广播结果；结果同时保留，供晚于事件加载的前端查询
+pub async fn run(app: &AppHandle) -> SelfTestResult {
+    // 与普通任务一样排队占用许可证席位，避免自检挤掉正在等待的任务
+    let seat_id = format!("selftest-{}", now_ms());
+    let _seat = app
+        .state::<LicenseGuard>()
+        .acquire(app, &seat_id, &serde_json::json!({}))
+        .await;
+    let start = Instant::now();
+    let state = app.state::<BridgeState>();
+    let (ok, message, detail) =
+        match send_request(state.inner(), "selftest", serde_json::json!({})).await {
+            Ok(resp) => (
+                resp["ok"].as_bool() == Some(true),
+                resp["message"].as_str().unwrap_or_default().to_string(),
+                resp.get("detail").cloned().unwrap_or_default(),
+            ),
+            Err(e) => (false, e.into(), serde_json::Value::Null),
+        };
+    let result = SelfTestResult {
+        ok,
+        message,
+        elapsed_ms: start.elapsed().as_millis() as u64,
+        finished_at: now_ms(),
+        detail,
+    };
+    if !result.ok {
+        eprintln!("Warning: bridge 自检失败: {}", result.message);
+    }
+    *app.state::<SelfTest>()
+        .last
+        .lock()
+        .unwrap_or_else(|e| e.into_inner()) = Some(result.clone());
+    let _ = app.emit(SELFTEST_EVENT, &result);
+    result
+}
+
+#[tauri::command]
+pub async fn selftest_get(
+    selftest: tauri::State<'_, SelfTest>,
+) -> Result<serde_json::Value, String> {
+    guarded("selftest_get", async move {
+        Ok(serde_json::json!({
+            "enabled": selftest.enabled(),
+            "last": selftest.last(),
+        }))
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn selftest_set(
+    selftest: tauri::State<'_, SelfTest>,
+    enabled: bool,
+) -> Result<(), String> {
+    guarded("selftest_set", async move { selftest.set_enabled(enabled) }).await
+}
+
+/// 手动重跑自检（例如修改 COMSOL 路径之后）
+#[tauri::command]
+pub async fn selftest_run(
+    app: AppHandle,
+    policy: tauri::State<'_, CommandPolicy>,
+) -> Result<SelfTestResult, String> {
+    guarded("selftest_run", async move {
+        policy.ensure_writable("运行自检")?;
+        Ok(run(&app).await)
+    })
+    .await
+}
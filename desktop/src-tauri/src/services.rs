@@ -0,0 +1,239 @@
+use crate::panics::guarded;
+use crate::store::{load_json, now_ms, save_json};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::net::TcpListener;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+/// 外部工具发现服务地址的文件，写在数据目录下
+pub const ENDPOINTS_FILE: &str = "endpoints.json";
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct PortRange {
+    pub start: u16,
+    pub end: u16,
+}
+
+impl PortRange {
+    fn contains(&self, port: u16) -> bool {
+        (self.start..=self.end).contains(&port)
+    }
+}
+
+/// 各服务的默认端口段；COMSOL 默认 2036，被占用时向后顺延
+fn default_range(service: &str) -> PortRange {
+    match service {
+        "mphserver" => PortRange {
+            start: 2036,
+            end: 2085,
+        },
+        _ => PortRange {
+            start: 17360,
+            end: 17409,
+        },
+    }
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+struct ServicePortConfig {
+    #[serde(default)]
+    range: Option<PortRange>,
+    /// 上次选定的端口，下次优先沿用，外部工具的配置不必频繁改动
+    #[serde(default)]
+    selected: Option<u16>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ServiceEndpoint {
+    pub service: String,
+    pub host: String,
+    pub port: u16,
+    pub since: u64,
+}
+
+/// 本应用启动的本地服务的端口分配与地址登记：
+/// 启动前检测端口冲突并在配置的端口段内另选，选中的端口持久化；
+/// 运行中的地址同时写入 `endpoints.json` 供外部工具发现
+#[derive(Default)]
+pub struct ServiceRegistry {
+    path: Option<PathBuf>,
+    endpoints_path: Option<PathBuf>,
+    config: Mutex<BTreeMap<String, ServicePortConfig>>,
+    endpoints: Mutex<BTreeMap<String, ServiceEndpoint>>,
+}
+
+fn port_free(host: &str, port: u16) -> bool {
+    TcpListener::bind((host, port)).is_ok()
+}
+
+impl ServiceRegistry {
+    pub fn load(path: PathBuf) -> Self {
+        let config = load_json(&path);
+        let endpoints_path = path.parent().map(|d| d.join(ENDPOINTS_FILE));
+        // 上次运行留下的地址已失效，启动时清空
+        if let Some(ref p) = endpoints_path {
+            let _ = std::fs::remove_file(p);
+        }
+        Self {
+            path: Some(path),
+            endpoints_path,
+            config: Mutex::new(config),
+            ..Self::default()
+        }
+    }
+
+    fn range(&self, service: &str) -> PortRange {
+        self.config
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(service)
+            .and_then(|c| c.range)
+            .unwrap_or_else(|| default_range(service))
+    }
+
+    fn update(&self, service: &str, f: impl FnOnce(&mut ServicePortConfig)) {
+        let mut config = self.config.lock().unwrap_or_else(|e| e.into_inner());
+        f(config.entry(service.to_string()).or_default());
+        if let Some(ref p) = self.path {
+            if let Err(e) = save_json(p, &*config) {
+                eprintln!("Warning: 保存端口配置失败: {}", e);
+            }
+        }
+    }
+
+    /// 为服务选择可用端口：指定端口 > 上次选定 > 端口段内第一个空闲端口
+    pub fn allocate(&self, service: &str, host: &str, wanted: Option<u16>) -> Result<u16, String> {
+        let range = self.range(service);
+        let selected = self
+            .config
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(service)
+            .and_then(|c| c.selected);
+        if let Some(p) = wanted.filter(|p| !port_free(host, *p)) {
+            eprintln!(
+                "Warning: {} 端口 {} 已被占用，改在 {}-{} 内另选",
+                service, p, range.start, range.end
+            );
+        }
+        let port = wanted
+            .into_iter()
+            .chain(selected.filter(|p| range.contains(*p)))
+            .chain(range.start..=range.end)
+            .find(|p| port_free(host, *p))
+            .ok_or_else(|| {
+                format!(
+                    "{} 找不到可用端口（{}-{} 均被占用）",
+                    service, range.start, range.end
+                )
+            })?;
+        self.update(service, |c| c.selected = Some(port));
+        Ok(port)
+    }
+
+    pub fn publish(&self, service: &str, host: &str, port: u16) {
+        let mut endpoints = self.endpoints.lock().unwrap_or_else(|e| e.into_inner());
+        if endpoints
+            .get(service)
+            .is_some_and(|e| e.host == host && e.port == port)
+        {
+            return;
+        }
+        endpoints.insert(
+            service.to_string(),
+            ServiceEndpoint {
+                service: service.to_string(),
+                host: host.to_string(),
+                port,
+                since: now_ms(),
+            },
+        );
+        self.write_endpoints(&endpoints);
+    }
+
+    pub fn withdraw(&self, service: &str) {
+        let mut endpoints = self.endpoints.lock().unwrap_or_else(|e| e.into_inner());
+        if endpoints.remove(service).is_some() {
+            self.write_endpoints(&endpoints);
+        }
+    }
+
+    fn write_endpoints(&self, endpoints: &BTreeMap<String, ServiceEndpoint>) {
+        if let Some(ref p) = self.endpoints_path {
+            let doc = serde_json::json!({
+                "pid": std::process::id(),
+                "updated_at": now_ms(),
+                "services": endpoints,
+            });
+            if let Err(e) = save_json(p, &doc) {
+                eprintln!("Warning: 写入服务地址文件失败: {}", e);
+            }
+        }
+    }
+}
+
+/// 列出当前运行中的本地服务地址，以及各服务的端口段与上次选定端口
+#[tauri::command]
+pub async fn get_service_endpoints(
+    registry: tauri::State<'_, ServiceRegistry>,
+) -> Result<serde_json::Value, String> {
+    guarded("get_service_endpoints", async move {
+        let endpoints: Vec<ServiceEndpoint> = registry
+            .endpoints
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .values()
+            .cloned()
+            .collect();
+        let config = registry
+            .config
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone();
+        let ports: BTreeMap<String, serde_json::Value> = config
+            .iter()
+            .map(|(name, c)| {
+                (
+                    name.clone(),
+                    serde_json::json!({
+                        "range": c.range.unwrap_or_else(|| default_range(name)),
+                        "selected": c.selected,
+                    }),
+                )
+            })
+            .collect();
+        Ok(serde_json::json!({
+            "endpoints": endpoints,
+            "ports": ports,
+            "endpoints_file": registry.endpoints_path,
+        }))
+    })
+    .await
+}
+
+/// 配置服务的端口段（传 None 恢复默认）
+#[tauri::command]
+pub async fn service_port_range_set(
+    registry: tauri::State<'_, ServiceRegistry>,
+    service: String,
+    range: Option<PortRange>,
+) -> Result<(), String> {
+    guarded("service_port_range_set", async move {
+        if let Some(r) = range {
+            if r.start == 0 || r.start > r.end {
+                return Err(format!("无效的端口段: {}-{}", r.start, r.end));
+            }
+        }
+        registry.update(&service, |c| {
+            c.range = range;
+            if c.selected
+                .is_some_and(|p| range.is_some_and(|r| !r.contains(p)))
+            {
+                c.selected = None;
+            }
+        });
+        Ok(())
+    })
+    .await
+}
@@ -0,0 +1,69 @@
+use crate::bridge_error::BridgeError;
+use crate::panics::guarded;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager};
+
+/// 本次会话内对 bridge 请求的临时覆盖；不落盘，应用退出即失效
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct SessionOverrides {
+    /// agent 详细日志
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub verbose: Option<bool>,
+    /// 只规划不执行
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dry_run: Option<bool>,
+    /// 临时换用的 LLM 后端与模型
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub backend: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+}
+
+#[derive(Default)]
+pub struct SessionOptions(Mutex<SessionOverrides>);
+
+impl SessionOptions {
+    pub fn get(&self) -> SessionOverrides {
+        self.0.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+}
+
+/// 把会话覆盖写入请求，覆盖请求中的同名字段
+pub fn apply_session_options(app: &AppHandle, payload: &mut Value) {
+    let overrides = app.state::<SessionOptions>().get();
+    let Ok(Value::Object(fields)) = serde_json::to_value(overrides) else {
+        return;
+    };
+    if let Some(obj) = payload.as_object_mut() {
+        obj.extend(fields);
+    }
+}
+
+/// 设置会话临时选项（整体替换，传 None 清空），作用于之后的请求
+#[tauri::command]
+pub async fn bridge_set_session_options(
+    options: tauri::State<'_, SessionOptions>,
+    opts: Option<SessionOverrides>,
+) -> Result<SessionOverrides, BridgeError> {
+    guarded("bridge_set_session_options", async move {
+        let mut opts = opts.unwrap_or_default();
+        opts.backend = opts.backend.filter(|s| !s.trim().is_empty());
+        opts.model = opts.model.filter(|s| !s.trim().is_empty());
+        *options.0.lock().unwrap_or_else(|e| e.into_inner()) = opts.clone();
+        Ok(opts)
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn bridge_get_session_options(
+    options: tauri::State<'_, SessionOptions>,
+) -> Result<SessionOverrides, BridgeError> {
+    guarded(
+        "bridge_get_session_options",
+        async move { Ok(options.get()) },
+    )
+    .await
+}
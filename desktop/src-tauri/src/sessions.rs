@@ -0,0 +1,294 @@
+use crate::bridge::{shutdown_bridge, BridgeState, BridgeStateInner, SHUTDOWN_GRACE};
+use crate::bridge_error::BridgeError;
+use crate::launcher::BridgeLauncher;
+use crate::panics::guarded;
+use crate::policy::CommandPolicy;
+use crate::store::now_ms;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+/// 随应用启动的 bridge；不传 session 的请求都发往它
+pub const DEFAULT_SESSION: &str = "default";
+/// 同时存在的具名会话上限（不含默认会话），每个会话是一个独立的 Python/JVM 进程
+const MAX_SESSIONS: usize = 8;
+
+static NEXT_SESSION_ID: AtomicU64 = AtomicU64::new(1);
+
+struct Session {
+    state: BridgeState,
+    label: Option<String>,
+    project: Option<String>,
+    created_ms: u64,
+    /// 固定使用远程启动实现时的说明（如 `ssh user@host`）
+    remote: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct SessionInfo {
+    pub id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub project: Option<String>,
+    pub created_ms: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub remote: Option<String>,
+    pub running: bool,
+    pub initializing: bool,
+    /// 在途的流式请求与普通请求数
+    pub active_streams: usize,
+    pub in_flight: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pid: Option<u32>,
+}
+
+/// 具名 bridge 会话：每个会话有独立的子进程、请求队列与 COMSOL 会话，
+/// 用于同时打开多个项目/模型而互不阻塞。默认会话仍由 `BridgeState` 管理
+#[derive(Default)]
+pub struct BridgeSessions {
+    sessions: Mutex<BTreeMap<String, Session>>,
+}
+
+impl BridgeSessions {
+    fn lock(&self) -> std::sync::MutexGuard<'_, BTreeMap<String, Session>> {
+        self.sessions.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn get(&self, id: &str) -> Option<BridgeState> {
+        self.lock().get(id).map(|s| s.state.clone())
+    }
+
+    /// 补上会话的名称、项目与创建时间
+    fn annotate(&self, info: &mut SessionInfo) {
+        if let Some(s) = self.lock().get(&info.id) {
+            info.label = s.label.clone();
+            info.project = s.project.clone();
+            info.created_ms = s.created_ms;
+            info.remote = s.remote.clone();
+        }
+    }
+
+    fn states(&self) -> Vec<(String, BridgeState)> {
+        self.lock()
+            .iter()
+            .map(|(id, s)| (id.clone(), s.state.clone()))
+            .collect()
+    }
+}
+
+/// 按会话 ID 取 bridge 状态；未指定或为 `default` 时返回默认会话
+pub fn resolve(app: &AppHandle, session: Option<&str>) -> Result<BridgeState, String> {
+    match session.map(str::trim).filter(|s| !s.is_empty()) {
+        None | Some(DEFAULT_SESSION) => Ok(app.state::<BridgeState>().inner().clone()),
+        Some(id) => app
+            .state::<BridgeSessions>()
+            .get(id)
+            .ok_or_else(|| format!("bridge 会话不存在: {}", id)),
+    }
+}
+
+/// 会话的 bridge 是否运行在远程主机上（无法按 pid 发信号，中止改走协议内取消）
+pub fn is_remote(app: &AppHandle, session: Option<&str>) -> bool {
+    match session.map(str::trim).filter(|s| !s.is_empty()) {
+        None | Some(DEFAULT_SESSION) => false,
+        Some(id) => app
+            .state::<BridgeSessions>()
+            .lock()
+            .get(id)
+            .is_some_and(|s| s.remote.is_some()),
+    }
+}
+
+/// 默认会话与全部具名会话
+pub fn all(app: &AppHandle) -> Vec<(String, BridgeState)> {
+    let mut out = vec![(
+        DEFAULT_SESSION.to_string(),
+        app.state::<BridgeState>().inner().clone(),
+    )];
+    out.extend(app.state::<BridgeSessions>().states());
+    out
+}
+
+/// 查找正在执行指定流式请求的会话，供按请求 ID 取消时定位
+pub async fn state_of_stream(app: &AppHandle, stream_id: &str) -> Option<BridgeState> {
+    let default = app.state::<BridgeState>().inner().clone();
+    if default.lock().await.stream_ids.contains_key(stream_id) {
+        return Some(default);
+    }
+    for (_, state) in app.state::<BridgeSessions>().states() {
+        if state.lock().await.stream_ids.contains_key(stream_id) {
+            return Some(state);
+        }
+    }
+    None
+}
+
+/// 退出应用时关闭全部具名会话
+pub async fn shutdown_all(app: &AppHandle, grace: Duration) {
+    let sessions = std::mem::take(&mut *app.state::<BridgeSessions>().lock());
+    for (id, session) in sessions {
+        if !shutdown_bridge(&session.state, grace).await {
+            eprintln!("Warning: bridge 会话 {} 未能正常退出", id);
+        }
+    }
+}
+
+async fn describe(id: &str, state: &BridgeState) -> SessionInfo {
+    let guard = state.lock().await;
+    SessionInfo {
+        id: id.to_string(),
+        label: None,
+        project: None,
+        created_ms: 0,
+        remote: None,
+        running: guard.dispatcher.is_some(),
+        initializing: guard.init_in_progress,
+        active_streams: guard.active_streams,
+        in_flight: guard.dispatcher.as_ref().map_or(0, |d| d.in_flight()),
+        pid: guard.child.as_ref().map(|c| c.pid),
+    }
+}
+
+/// 新建一个具名会话；bridge 进程在该会话的第一个请求时启动，沿用默认会话的 JAVA_HOME
+#[tauri::command]
+pub async fn bridge_session_create(
+    app: AppHandle,
+    policy: tauri::State<'_, CommandPolicy>,
+    id: Option<String>,
+    label: Option<String>,
+    project: Option<String>,
+) -> Result<SessionInfo, BridgeError> {
+    guarded("bridge_session_create", async move {
+        policy.ensure_writable("创建 bridge 会话")?;
+        let (id, state) = create(&app, id, label, project, None).await?;
+        Ok(info(&app, &id, &state).await)
+    })
+    .await
+}
+
+/// 登记一个具名会话并返回其 ID 与状态；`launcher` 为该会话固定使用的启动实现
+pub(crate) async fn create(
+    app: &AppHandle,
+    id: Option<String>,
+    label: Option<String>,
+    project: Option<String>,
+    launcher: Option<Arc<dyn BridgeLauncher>>,
+) -> Result<(String, BridgeState), BridgeError> {
+    let id = match id.map(|s| s.trim().to_string()).filter(|s| !s.is_empty()) {
+        Some(id) if id == DEFAULT_SESSION => {
+            return Err(BridgeError::from("默认会话无需创建"));
+        }
+        Some(id) => id,
+        None => format!(
+            "session-{}",
+            NEXT_SESSION_ID.fetch_add(1, Ordering::Relaxed)
+        ),
+    };
+    let java_home = app
+        .state::<BridgeState>()
+        .lock()
+        .await
+        .bundled_java_home
+        .clone();
+    let sessions = app.state::<BridgeSessions>();
+    let mut inner = BridgeStateInner::new(java_home);
+    let remote = launcher.as_ref().map(|l| l.describe());
+    inner.launcher = launcher;
+    let state: BridgeState = Arc::new(tokio::sync::Mutex::new(inner));
+    let session = Session {
+        state: state.clone(),
+        label,
+        project,
+        created_ms: now_ms(),
+        remote,
+    };
+    {
+        let mut map = sessions.lock();
+        if map.contains_key(&id) {
+            return Err(BridgeError::from(format!("bridge 会话已存在: {}", id)));
+        }
+        if map.len() >= MAX_SESSIONS {
+            return Err(BridgeError::from(format!(
+                "bridge 会话数已达上限 {}，请先关闭不用的会话",
+                MAX_SESSIONS
+            )));
+        }
+        map.insert(id.clone(), session);
+    }
+    Ok((id, state))
+}
+
+/// 会话的运行状态与登记信息
+pub(crate) async fn info(app: &AppHandle, id: &str, state: &BridgeState) -> SessionInfo {
+    let mut info = describe(id, state).await;
+    app.state::<BridgeSessions>().annotate(&mut info);
+    info
+}
+
+/// 撤销登记并结束会话的 bridge，用于创建后未能连通的会话
+pub(crate) async fn discard(app: &AppHandle, id: &str) {
+    let removed = app.state::<BridgeSessions>().lock().remove(id);
+    if let Some(session) = removed {
+        shutdown_bridge(&session.state, SHUTDOWN_GRACE).await;
+    }
+}
+
+/// 列出默认会话与全部具名会话的运行状态
+#[tauri::command]
+pub async fn bridge_session_list(app: AppHandle) -> Result<Vec<SessionInfo>, BridgeError> {
+    guarded("bridge_session_list", async move {
+        let default = app.state::<BridgeState>().inner().clone();
+        let mut out = vec![describe(DEFAULT_SESSION, &default).await];
+        let sessions = app.state::<BridgeSessions>();
+        for (id, state) in sessions.states() {
+            let mut info = describe(&id, &state).await;
+            sessions.annotate(&mut info);
+            out.push(info);
+        }
+        Ok(out)
+    })
+    .await
+}
+
+/// 关闭具名会话并结束其 bridge 进程；有任务运行时需 `force`
+#[tauri::command]
+pub async fn bridge_session_close(
+    app: AppHandle,
+    id: String,
+    force: Option<bool>,
+    grace_secs: Option<u64>,
+) -> Result<serde_json::Value, BridgeError> {
+    guarded("bridge_session_close", async move {
+        if id == DEFAULT_SESSION {
+            return Err(BridgeError::from(
+                "默认会话不能关闭；停止进程请用 bridge_stop",
+            ));
+        }
+        let sessions = app.state::<BridgeSessions>();
+        let state = sessions
+            .get(&id)
+            .ok_or_else(|| format!("bridge 会话不存在: {}", id))?;
+        {
+            let guard = state.lock().await;
+            let busy = guard.active_streams > 0
+                || guard.dispatcher.as_ref().is_some_and(|d| d.in_flight() > 0);
+            if busy && !force.unwrap_or(false) {
+                return Err(BridgeError::from(format!(
+                    "bridge 会话 {} 正在执行任务；确认关闭请传 force",
+                    id
+                )));
+            }
+        }
+        sessions.lock().remove(&id);
+        let grace = grace_secs
+            .map(Duration::from_secs)
+            .unwrap_or(SHUTDOWN_GRACE);
+        let clean = shutdown_bridge(&state, grace).await;
+        Ok(serde_json::json!({ "closed": true, "clean": clean }))
+    })
+    .await
+}
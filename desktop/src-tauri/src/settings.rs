@@ -0,0 +1,737 @@
+use crate::bridge::find_project_root;
+use crate::limits::{self, ResourceLimits};
+use crate::log_level;
+use crate::panics::guarded;
+use crate::paths::output_dir;
+use crate::postprocess::{self, ProcessorSettings};
+use crate::proxy::{self, ProxyConfig};
+use crate::remote::{self, RemoteConfig};
+use crate::size_limits::{self, SizeLimits};
+use crate::standby;
+use crate::timeouts::{RequestTimeouts, TimeoutConfig};
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Component, Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime};
+use tauri::{AppHandle, Emitter, Manager};
+use tokio::sync::mpsc;
+
+/// 无法监听文件系统时，检查设置文件修改时间的间隔
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+/// 收到变化通知后稍等再读取，合并一次保存产生的多个事件
+const DEBOUNCE: Duration = Duration::from_millis(100);
+const THEMES: &[&str] = &["light", "dark", "system"];
+/// 需要重启应用才生效的设置项
+const RESTART_KEYS: &[&str] = &["java_home", "bridge_autostart"];
+/// 项目设置文件（相对项目目录），可随项目提交到 git
+pub const PROJECT_SETTINGS_FILE: &str = ".mph-agent/settings.json";
+/// 只能写在本机设置中的项：本机路径、JDK、代理与界面偏好，放进项目设置会随项目泄露
+const MACHINE_KEYS: &[&str] = &[
+    "java_home",
+    "proxy",
+    "remote",
+    "theme",
+    "log_level",
+    "bridge_autostart",
+    "bridge_warm_standby",
+];
+/// 只能写在项目设置中的项
+const PROJECT_KEYS: &[&str] = &["output_dir"];
+
+/// 设置的来源层，优先级从低到高
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Layer {
+    Default,
+    Machine,
+    Project,
+}
+
+/// 设置文件中的一处问题；语法错误带行列号，取值错误带设置项名与其所在行
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct SettingsError {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub key: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub column: Option<usize>,
+    pub message: String,
+}
+
+#[derive(Default)]
+struct Inner {
+    modified: Option<SystemTime>,
+    /// 当前生效的取值
+    applied: Map<String, Value>,
+    /// 启动时读到的需重启项，用来判断改动是否仍待重启
+    startup: Map<String, Value>,
+    pending_restart: BTreeMap<String, Value>,
+    errors: Vec<SettingsError>,
+}
+
+/// 可手工编辑的本机设置文件（settings.json）。外部修改后自动重新读取：
+/// timeouts / log_level / theme / proxy / remote / bridge_warm_standby / resource_limits / size_limits 即时生效，java_home / bridge_autostart 等记为待重启。
+/// 项目目录下另有项目设置（PROJECT_SETTINGS_FILE），同名项覆盖本机设置，见 `settings_effective`
+#[derive(Default)]
+pub struct LiveSettings {
+    path: Option<PathBuf>,
+    inner: Mutex<Inner>,
+}
+
+/// 设置项所在的行列号，便于在报错里指出位置
+fn locate(text: &str, key: &str) -> (Option<usize>, Option<usize>) {
+    let needle = format!("\"{}\"", key);
+    text.lines()
+        .enumerate()
+        .find_map(|(i, l)| l.find(&needle).map(|c| (Some(i + 1), Some(c + 1))))
+        .unwrap_or((None, None))
+}
+
+fn validate_key(key: &str, value: &Value, layer: Layer) -> Result<(), String> {
+    if layer == Layer::Project && MACHINE_KEYS.contains(&key) {
+        return Err("只能写在本机设置中，项目设置会随项目共享".to_string());
+    }
+    if layer == Layer::Machine && PROJECT_KEYS.contains(&key) {
+        return Err(format!("只能写在项目设置 {} 中", PROJECT_SETTINGS_FILE));
+    }
+    let one_of = |allowed: &[&str]| match value.as_str() {
+        Some(s) if allowed.contains(&s) => Ok(()),
+        _ => Err(format!("取值应为 {} 之一", allowed.join(" / "))),
+    };
+    match key {
+        "timeouts" => serde_json::from_value::<TimeoutConfig>(value.clone())
+            .map(|_| ())
+            .map_err(|e| format!("格式错误: {}", e)),
+        "log_level" => one_of(log_level::LEVELS),
+        "theme" => one_of(THEMES),
+        "bridge_autostart" | "bridge_warm_standby" => value
+            .as_bool()
+            .map(|_| ())
+            .ok_or_else(|| "应为 true 或 false".to_string()),
+        "resource_limits" => ResourceLimits::parse(value).map(|_| ()),
+        "size_limits" => SizeLimits::parse(value).map(|_| ()),
+        "proxy" => ProxyConfig::parse(value).map(|_| ()),
+        "remote" => RemoteConfig::parse(value).map(|_| ()),
+        "postprocess" => {
+            let steps: BTreeMap<String, ProcessorSettings> =
+                serde_json::from_value(value.clone()).map_err(|e| format!("格式错误: {}", e))?;
+            match steps.keys().find(|id| !postprocess::is_known(id)) {
+                Some(id) => Err(format!("未知的后处理步骤: {}", id)),
+                None => Ok(()),
+            }
+        }
+        // 相对项目目录，项目换到别的机器或目录后仍然有效
+        "output_dir" => match value.as_str().map(Path::new) {
+            Some(p)
+                if !p.as_os_str().is_empty()
+                    && p.components().all(|c| matches!(c, Component::Normal(_))) =>
+            {
+                Ok(())
+            }
+            _ => Err("应为项目目录内的相对路径".to_string()),
+        },
+        "java_home" => match value.as_str() {
+            Some(s) if Path::new(s).is_dir() => Ok(()),
+            Some(s) => Err(format!("目录不存在: {}", s)),
+            None => Err("应为目录路径字符串".to_string()),
+        },
+        _ => Err("未知设置项".to_string()),
+    }
+}
+
+/// 解析设置文本：语法错误整体失败；取值有误的项跳过并报告，其余照常生效
+fn parse(text: &str, layer: Layer) -> (Map<String, Value>, Vec<SettingsError>) {
+    if text.trim().is_empty() {
+        return (Map::new(), Vec::new());
+    }
+    let root: Value = match serde_json::from_str(text) {
+        Ok(v) => v,
+        Err(e) => {
+            return (
+                Map::new(),
+                vec![SettingsError {
+                    key: None,
+                    line: Some(e.line()),
+                    column: Some(e.column()),
+                    message: format!("JSON 语法错误: {}", e),
+                }],
+            )
+        }
+    };
+    let Value::Object(fields) = root else {
+        return (
+            Map::new(),
+            vec![SettingsError {
+                key: None,
+                line: Some(1),
+                column: Some(1),
+                message: "设置文件顶层应为对象".to_string(),
+            }],
+        );
+    };
+    let mut valid = Map::new();
+    let mut errors = Vec::new();
+    for (key, value) in fields {
+        match validate_key(&key, &value, layer) {
+            Ok(()) => {
+                valid.insert(key, value);
+            }
+            Err(message) => {
+                let (line, column) = locate(text, &key);
+                errors.push(SettingsError {
+                    key: Some(key),
+                    line,
+                    column,
+                    message,
+                });
+            }
+        }
+    }
+    (valid, errors)
+}
+
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+impl LiveSettings {
+    pub fn load(path: PathBuf) -> Self {
+        let text = std::fs::read_to_string(&path).unwrap_or_default();
+        let (applied, errors) = parse(&text, Layer::Machine);
+        for e in &errors {
+            eprintln!("Warning: 设置文件 {}: {}", path.display(), e.message);
+        }
+        let startup = applied
+            .iter()
+            .filter(|(k, _)| RESTART_KEYS.contains(&k.as_str()))
+            .map(|(k, v)| (k.clone(), v.clone()))
+            .collect();
+        Self {
+            inner: Mutex::new(Inner {
+                modified: modified_time(&path),
+                applied,
+                startup,
+                pending_restart: BTreeMap::new(),
+                errors,
+            }),
+            path: Some(path),
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Inner> {
+        self.inner.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// 启动时由设置文件指定的 JAVA_HOME
+    pub fn java_home(&self) -> Option<PathBuf> {
+        self.lock()
+            .startup
+            .get("java_home")?
+            .as_str()
+            .map(PathBuf::from)
+    }
+
+    /// 启动时是否立即拉起 bridge；为 false 时等到第一个建模请求或 bridge_start 才启动
+    pub fn bridge_autostart(&self) -> bool {
+        self.lock()
+            .startup
+            .get("bridge_autostart")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(true)
+    }
+
+    /// 是否保留一个预热的备用 bridge 进程，中止后直接接替；关闭可省下一个 Python 进程的内存
+    pub fn bridge_warm_standby(&self) -> bool {
+        self.lock()
+            .applied
+            .get("bridge_warm_standby")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false)
+    }
+
+    fn snapshot(&self) -> Value {
+        let inner = self.lock();
+        serde_json::json!({
+            "path": self.path,
+            "values": inner.applied,
+            "pending_restart": inner.pending_restart,
+            "errors": inner.errors,
+        })
+    }
+}
+
+/// 把当前生效的即时项应用到各模块（请求超时、日志级别、代理与 bridge 资源上限需要写入其他状态）
+fn apply_hot(app: &AppHandle, key: &str, value: &Value) {
+    match key {
+        "timeouts" => {
+            if let Ok(config) = serde_json::from_value::<TimeoutConfig>(value.clone()) {
+                if let Err(e) = app.state::<RequestTimeouts>().set(config) {
+                    eprintln!("Warning: 应用超时设置失败: {}", e);
+                }
+            }
+        }
+        // 同时通知正在运行的 bridge；删除该项时恢复默认
+        "log_level" => match value.as_str() {
+            Some(level) => {
+                let (app, level) = (app.clone(), level.to_string());
+                tauri::async_runtime::spawn(async move {
+                    if let Err(e) = log_level::apply(&app, &level).await {
+                        eprintln!("Warning: 应用日志级别失败: {}", e);
+                    }
+                });
+            }
+            None => log_level::reset(),
+        },
+        // 删除该项时 value 为 null，恢复为不限制；运行中的 bridge 下次启动时生效，
+        // 按旧上限预热的备用进程直接换掉
+        "resource_limits" => {
+            limits::set_active(ResourceLimits::parse(value).unwrap_or_default());
+            if app.try_state::<standby::Standby>().is_some() {
+                let app = app.clone();
+                tauri::async_runtime::spawn(async move {
+                    if standby::discard(&app).await {
+                        standby::refill(&app);
+                    }
+                });
+            }
+        }
+        // 删除该项时恢复默认上限，对运行中的 bridge 立即生效
+        "size_limits" => size_limits::set_active(SizeLimits::parse(value).unwrap_or_default()),
+        // 新启动的 bridge 与 curl 使用新代理
+        "proxy" => proxy::set_active(ProxyConfig::parse(value).ok()),
+        // 新启动的 bridge 连接到远程服务；删除该项后恢复为本机进程
+        "remote" => remote::set_active(RemoteConfig::parse(value).ok()),
+        _ => {}
+    }
+}
+
+/// 按新内容更新设置后需要向外通知的变化
+#[derive(Default)]
+struct Reloaded {
+    /// 即时项的新取值，删除的项为 null
+    changed: Map<String, Value>,
+    /// 待重启项有变化时为变化后的全部待重启项
+    pending_restart: Option<BTreeMap<String, Value>>,
+    errors: Vec<SettingsError>,
+}
+
+impl LiveSettings {
+    /// 以设置文件的新内容更新状态：即时项直接生效，需重启项与启动时的取值不同则记为待重启；
+    /// 语法错误时保留上一次的全部取值，取值有误的项保留该项上一次的取值
+    fn update(&self, text: &str, modified: Option<SystemTime>) -> Reloaded {
+        let (values, errors) = parse(text, Layer::Machine);
+        let mut out = Reloaded::default();
+        let syntax_failed = errors.iter().any(|e| e.key.is_none());
+        let mut inner = self.lock();
+        inner.modified = modified;
+        if !syntax_failed {
+            let before = inner.pending_restart.clone();
+            let keys: BTreeSet<String> =
+                inner.applied.keys().chain(values.keys()).cloned().collect();
+            for key in keys {
+                if errors
+                    .iter()
+                    .any(|e| e.key.as_deref() == Some(key.as_str()))
+                {
+                    continue;
+                }
+                let new = values.get(&key).cloned();
+                if RESTART_KEYS.contains(&key.as_str()) {
+                    match new {
+                        Some(v) if inner.startup.get(&key) != Some(&v) => {
+                            inner.pending_restart.insert(key.clone(), v);
+                        }
+                        None if inner.startup.contains_key(&key) => {
+                            inner.pending_restart.insert(key.clone(), Value::Null);
+                        }
+                        _ => {
+                            inner.pending_restart.remove(&key);
+                        }
+                    }
+                    continue;
+                }
+                if inner.applied.get(&key) == new.as_ref() {
+                    continue;
+                }
+                match new {
+                    Some(v) => {
+                        inner.applied.insert(key.clone(), v.clone());
+                        out.changed.insert(key, v);
+                    }
+                    None => {
+                        inner.applied.remove(&key);
+                        out.changed.insert(key, Value::Null);
+                    }
+                }
+            }
+            if before != inner.pending_restart {
+                out.pending_restart = Some(inner.pending_restart.clone());
+            }
+        }
+        inner.errors = errors.clone();
+        out.errors = errors;
+        out
+    }
+}
+
+/// 重新读取设置文件；`force` 为 false 时文件未变化则跳过
+fn reload(app: &AppHandle, force: bool) {
+    let settings = app.state::<LiveSettings>();
+    let Some(ref path) = settings.path else {
+        return;
+    };
+    let modified = modified_time(path);
+    if !force && settings.lock().modified == modified {
+        return;
+    }
+    let text = std::fs::read_to_string(path).unwrap_or_default();
+    let Reloaded {
+        changed,
+        pending_restart,
+        errors,
+    } = settings.update(&text, modified);
+
+    for (key, value) in &changed {
+        apply_hot(app, key, value);
+    }
+    if !changed.is_empty() {
+        let _ = app.emit("settings-changed", &changed);
+    }
+    if let Some(pending) = pending_restart {
+        let _ = app.emit("settings-pending-restart", &pending);
+    }
+    if !errors.is_empty() {
+        for e in &errors {
+            eprintln!(
+                "Warning: 设置文件 {} 第 {} 行: {}",
+                path.display(),
+                e.line.map(|l| l.to_string()).unwrap_or_else(|| "?".into()),
+                e.message
+            );
+        }
+        let _ = app.emit(
+            "settings-error",
+            serde_json::json!({ "path": path, "errors": errors }),
+        );
+    }
+}
+
+/// 监听设置文件所在的目录：编辑器保存时常先写临时文件再改名替换，只监听文件本身会在替换后失效。
+/// 涉及设置文件的事件送入 `tx`
+fn watch(path: &Path, tx: mpsc::UnboundedSender<()>) -> notify::Result<RecommendedWatcher> {
+    let dir = path
+        .parent()
+        .filter(|d| !d.as_os_str().is_empty())
+        .unwrap_or(Path::new("."));
+    let _ = std::fs::create_dir_all(dir);
+    let name = path.file_name().map(|n| n.to_os_string());
+    let mut watcher =
+        notify::recommended_watcher(move |event: notify::Result<notify::Event>| match event {
+            Ok(event) => {
+                if event.paths.iter().any(|p| p.file_name() == name.as_deref()) {
+                    let _ = tx.send(());
+                }
+            }
+            Err(e) => eprintln!("Warning: 监听设置文件出错: {}", e),
+        })?;
+    watcher.watch(dir, RecursiveMode::NonRecursive)?;
+    Ok(watcher)
+}
+
+/// 设置文件变化后自动校验并应用：优先用文件系统通知，一次保存产生的多个事件合并处理；
+/// 无法监听时退回按 POLL_INTERVAL 轮询修改时间
+pub fn spawn_settings_watcher(app: AppHandle) {
+    let settings = app.state::<LiveSettings>();
+    let startup: Vec<(String, Value)> = settings
+        .lock()
+        .applied
+        .iter()
+        .map(|(k, v)| (k.clone(), v.clone()))
+        .collect();
+    for (key, value) in &startup {
+        apply_hot(&app, key, value);
+    }
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    let watcher = settings
+        .path
+        .as_deref()
+        .and_then(|path| match watch(path, tx) {
+            Ok(w) => Some(w),
+            Err(e) => {
+                eprintln!("Warning: 无法监听设置文件变化，改为定时检查: {}", e);
+                None
+            }
+        });
+    tauri::async_runtime::spawn(async move {
+        // 监听器随任务存活
+        let mut watcher = watcher;
+        loop {
+            if watcher.is_some() {
+                if rx.recv().await.is_none() {
+                    watcher = None;
+                    continue;
+                }
+                tokio::time::sleep(DEBOUNCE).await;
+                while rx.try_recv().is_ok() {}
+            } else {
+                tokio::time::sleep(POLL_INTERVAL).await;
+            }
+            reload(&app, false);
+        }
+    });
+}
+
+/// 读取项目设置；文件不存在时为空
+fn load_project(project: &Path) -> (PathBuf, Map<String, Value>, Vec<SettingsError>) {
+    let path = project.join(PROJECT_SETTINGS_FILE);
+    let text = std::fs::read_to_string(&path).unwrap_or_default();
+    let (values, errors) = parse(&text, Layer::Project);
+    (path, values, errors)
+}
+
+/// 项目设置中的输出目录（已按项目目录解析）；未设置或设置有误时为 None
+pub fn project_output_dir(project: &Path) -> Option<PathBuf> {
+    let (_, values, _) = load_project(project);
+    values
+        .get("output_dir")
+        .and_then(|v| v.as_str())
+        .map(|d| project.join(d))
+}
+
+/// 合并后的设置：内置默认 < 本机设置 < 项目设置，同名项整体覆盖（对象类取值不逐字段合并），
+/// 每项注明取自哪一层；项目设置的 output_dir 解析为绝对路径
+fn effective(settings: &LiveSettings, project: Option<&Path>) -> Value {
+    let mut values = Map::new();
+    let mut sources = Map::new();
+    let mut set = |key: &str, value: Value, layer: Layer| {
+        values.insert(key.to_string(), value);
+        sources.insert(key.to_string(), serde_json::json!(layer));
+    };
+    set("bridge_autostart", Value::Bool(true), Layer::Default);
+    // 备用进程常驻一份 Python 解释器的内存，需显式开启
+    set("bridge_warm_standby", Value::Bool(false), Layer::Default);
+    let default_output = match project {
+        Some(p) => Some(p.to_path_buf()),
+        None => output_dir(&find_project_root()),
+    };
+    if let Some(dir) = default_output {
+        set("output_dir", serde_json::json!(dir), Layer::Default);
+    }
+    let machine = settings.lock().applied.clone();
+    for (key, value) in machine {
+        set(&key, value, Layer::Machine);
+    }
+    let mut project_info = Value::Null;
+    if let Some(project) = project {
+        let (path, values, errors) = load_project(project);
+        for (key, value) in values {
+            let value = match key.as_str() {
+                "output_dir" => serde_json::json!(project.join(value.as_str().unwrap_or_default())),
+                _ => value,
+            };
+            set(&key, value, Layer::Project);
+        }
+        project_info = serde_json::json!({
+            "dir": project,
+            "path": path,
+            "exists": path.is_file(),
+            "errors": errors,
+        });
+    }
+    serde_json::json!({
+        "values": values,
+        "sources": sources,
+        "machine": {
+            "path": settings.path,
+            "errors": settings.lock().errors,
+        },
+        "project": project_info,
+    })
+}
+
+/// 当前生效的设置、待重启的改动与最近一次读取发现的问题
+#[tauri::command]
+pub async fn settings_get(settings: tauri::State<'_, LiveSettings>) -> Result<Value, String> {
+    guarded("settings_get", async move { Ok(settings.snapshot()) }).await
+}
+
+/// 立即重新读取设置文件
+#[tauri::command]
+pub async fn settings_reload(app: AppHandle) -> Result<Value, String> {
+    guarded("settings_reload", async move {
+        reload(&app, true);
+        Ok(app.state::<LiveSettings>().snapshot())
+    })
+    .await
+}
+
+/// 合并本机设置与项目设置后的实际取值及各项来源；未指定项目时只合并默认与本机设置
+#[tauri::command]
+pub async fn settings_effective(
+    settings: tauri::State<'_, LiveSettings>,
+    project: Option<String>,
+) -> Result<Value, String> {
+    guarded("settings_effective", async move {
+        let project = project
+            .as_deref()
+            .map(str::trim)
+            .filter(|p| !p.is_empty())
+            .map(PathBuf::from);
+        if let Some(ref p) = project {
+            if !p.is_dir() {
+                return Err(format!("项目目录不存在: {}", p.display()));
+            }
+        }
+        Ok(effective(&settings, project.as_deref()))
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn temp_file(name: &str, text: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "mph-settings-{}-{}-{}",
+            name,
+            std::process::id(),
+            crate::store::now_ms()
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("settings.json");
+        std::fs::write(&path, text).unwrap();
+        path
+    }
+
+    #[test]
+    fn parse_reports_syntax_errors_with_position() {
+        let (values, errors) = parse("{\n  \"theme\": \"dark\",\n}", Layer::Machine);
+        assert!(values.is_empty());
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].key, None);
+        assert_eq!(errors[0].line, Some(3));
+        assert!(errors[0].message.starts_with("JSON 语法错误"));
+
+        let (_, errors) = parse("[1]", Layer::Machine);
+        assert_eq!(errors[0].message, "设置文件顶层应为对象");
+        assert_eq!(parse("  ", Layer::Machine), (Map::new(), Vec::new()));
+    }
+
+    #[test]
+    fn parse_skips_invalid_values_and_keeps_the_rest() {
+        let text = "{\n  \"theme\": \"blue\",\n  \"bridge_autostart\": false,\n  \"nope\": 1\n}";
+        let (values, errors) = parse(text, Layer::Machine);
+        assert_eq!(values.get("bridge_autostart"), Some(&json!(false)));
+        assert_eq!(values.len(), 1);
+        let theme = errors
+            .iter()
+            .find(|e| e.key.as_deref() == Some("theme"))
+            .unwrap();
+        assert_eq!((theme.line, theme.column), (Some(2), Some(3)));
+        assert!(errors
+            .iter()
+            .any(|e| e.key.as_deref() == Some("nope") && e.message == "未知设置项"));
+    }
+
+    #[test]
+    fn parse_enforces_layers() {
+        let (values, errors) = parse(r#"{"output_dir": "out"}"#, Layer::Machine);
+        assert!(values.is_empty());
+        assert!(errors[0].message.contains(PROJECT_SETTINGS_FILE));
+        let (values, errors) = parse(r#"{"theme": "dark"}"#, Layer::Project);
+        assert!(values.is_empty());
+        assert_eq!(errors[0].key.as_deref(), Some("theme"));
+        let (values, _) = parse(r#"{"output_dir": "out/run"}"#, Layer::Project);
+        assert_eq!(values.get("output_dir"), Some(&json!("out/run")));
+        let (_, errors) = parse(r#"{"output_dir": "../out"}"#, Layer::Project);
+        assert_eq!(errors.len(), 1);
+    }
+
+    #[test]
+    fn update_keeps_previous_values_on_errors() {
+        let settings = LiveSettings::default();
+        let first = settings.update(r#"{"theme": "dark"}"#, None);
+        assert_eq!(first.changed.get("theme"), Some(&json!("dark")));
+        assert!(first.pending_restart.is_none());
+
+        // 语法错误：整份设置保持不变
+        let broken = settings.update(r#"{"theme": "light""#, None);
+        assert!(broken.changed.is_empty());
+        assert_eq!(broken.errors.len(), 1);
+        assert_eq!(settings.lock().applied.get("theme"), Some(&json!("dark")));
+
+        // 单项取值有误：该项保留上一次的取值，其余照常更新
+        let partial = settings.update(r#"{"theme": "blue", "log_level": "debug"}"#, None);
+        assert_eq!(partial.changed.len(), 1);
+        assert_eq!(settings.lock().applied.get("theme"), Some(&json!("dark")));
+        assert_eq!(settings.lock().errors.len(), 1);
+
+        // 删除的项以 null 通知
+        let removed = settings.update(r#"{"log_level": "debug"}"#, None);
+        assert_eq!(removed.changed.get("theme"), Some(&Value::Null));
+        assert!(settings.lock().errors.is_empty());
+    }
+
+    #[test]
+    fn restart_keys_are_queued_not_applied() {
+        let path = temp_file("restart", r#"{"bridge_autostart": true}"#);
+        let settings = LiveSettings::load(path.clone());
+
+        let off = settings.update(r#"{"bridge_autostart": false}"#, None);
+        assert!(off.changed.is_empty());
+        assert_eq!(
+            off.pending_restart,
+            Some(BTreeMap::from([(
+                "bridge_autostart".to_string(),
+                json!(false)
+            )]))
+        );
+        // 重启前仍按启动时的取值
+        assert!(settings.bridge_autostart());
+
+        // 同样的内容再读一次不重复通知
+        let again = settings.update(r#"{"bridge_autostart": false}"#, None);
+        assert!(again.pending_restart.is_none());
+
+        // 改回启动时的取值即不再待重启
+        let back = settings.update(r#"{"bridge_autostart": true}"#, None);
+        assert_eq!(back.pending_restart, Some(BTreeMap::new()));
+
+        // 删除启动时存在的项，重启后恢复默认
+        let removed = settings.update("{}", None);
+        assert_eq!(
+            removed.pending_restart,
+            Some(BTreeMap::from([(
+                "bridge_autostart".to_string(),
+                Value::Null
+            )]))
+        );
+        let _ = std::fs::remove_dir_all(path.parent().unwrap());
+    }
+
+    #[test]
+    fn watcher_reports_replaced_settings_file() {
+        let path = temp_file("watch", "{}");
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let _watcher = watch(&path, tx).unwrap();
+        // 编辑器式保存：写临时文件后改名替换
+        let tmp = path.with_file_name("settings.json.tmp");
+        std::fs::write(&tmp, r#"{"theme": "dark"}"#).unwrap();
+        std::fs::rename(&tmp, &path).unwrap();
+        let deadline = std::time::Instant::now() + Duration::from_secs(5);
+        while rx.try_recv().is_err() {
+            assert!(
+                std::time::Instant::now() < deadline,
+                "未收到设置文件变化通知"
+            );
+            std::thread::sleep(Duration::from_millis(20));
+        }
+        let _ = std::fs::remove_dir_all(path.parent().unwrap());
+    }
+}
@@ -0,0 +1,161 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::sync::Mutex;
+
+const MIB: usize = 1024 * 1024;
+/// 各项上限的取值范围：过小会挡住正常的请求与结果，过大失去保护作用
+const REQUEST_RANGE: (usize, usize) = (64 * 1024, 1024 * MIB);
+const RESPONSE_RANGE: (usize, usize) = (MIB, 1024 * MIB);
+const EVENT_MIN: usize = 4096;
+const BUFFERED_EVENTS_RANGE: (usize, usize) = (100, 1_000_000);
+
+/// 请求与 bridge 输出的大小上限，设置文件中的 `size_limits`（各项可省略，取默认值）。
+/// 异常的 bridge 输出超大的行时丢弃或截断并明确报错，而不是把整段读进内存
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields, default)]
+pub struct SizeLimits {
+    /// 单条请求序列化后的字节数上限，超出时不发送并报错
+    pub max_request_bytes: usize,
+    /// 单条 bridge 输出的字节数上限，超出的整条跳过：最终响应以协议错误结束请求
+    pub max_response_bytes: usize,
+    /// 单个流式事件的字节数上限，超出的事件换成截断标记，任务继续
+    pub max_event_bytes: usize,
+    /// 每个请求尚未被取走的事件数上限，超出的事件丢弃，之后补一条丢弃计数
+    pub max_buffered_events: usize,
+}
+
+impl Default for SizeLimits {
+    fn default() -> Self {
+        DEFAULT
+    }
+}
+
+const DEFAULT: SizeLimits = SizeLimits {
+    max_request_bytes: 16 * MIB,
+    max_response_bytes: 64 * MIB,
+    max_event_bytes: 8 * MIB,
+    max_buffered_events: 10_000,
+};
+
+impl SizeLimits {
+    pub fn parse(value: &Value) -> Result<Self, String> {
+        let limits: Self =
+            serde_json::from_value(value.clone()).map_err(|e| format!("格式错误: {}", e))?;
+        let in_range = |name: &str, v: usize, (lo, hi): (usize, usize)| {
+            if (lo..=hi).contains(&v) {
+                Ok(())
+            } else {
+                Err(format!("{} 应在 {} ~ {} 之间", name, lo, hi))
+            }
+        };
+        in_range("max_request_bytes", limits.max_request_bytes, REQUEST_RANGE)?;
+        in_range(
+            "max_response_bytes",
+            limits.max_response_bytes,
+            RESPONSE_RANGE,
+        )?;
+        in_range(
+            "max_event_bytes",
+            limits.max_event_bytes,
+            (EVENT_MIN, limits.max_response_bytes),
+        )?;
+        in_range(
+            "max_buffered_events",
+            limits.max_buffered_events,
+            BUFFERED_EVENTS_RANGE,
+        )?;
+        Ok(limits)
+    }
+
+    /// 请求超过上限时返回错误，调用方不发送
+    pub fn check_request(&self, line: &str) -> Result<(), String> {
+        if line.len() > self.max_request_bytes {
+            return Err(format!(
+                "PayloadTooLarge: 请求过大（{} 字节，上限 {}），未发送",
+                line.len(),
+                self.max_request_bytes
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// 当前生效的上限；设置文件变化后即时更新，对运行中的 bridge 同样生效
+static ACTIVE: Mutex<SizeLimits> = Mutex::new(DEFAULT);
+
+pub fn set_active(limits: SizeLimits) {
+    *ACTIVE.lock().unwrap_or_else(|e| e.into_inner()) = limits;
+}
+
+pub fn current() -> SizeLimits {
+    *ACTIVE.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn omitted_fields_take_defaults() {
+        assert_eq!(SizeLimits::parse(&json!({})), Ok(SizeLimits::default()));
+        let limits = SizeLimits::parse(&json!({ "max_buffered_events": 500 })).unwrap();
+        assert_eq!(limits.max_buffered_events, 500);
+        assert_eq!(limits.max_request_bytes, DEFAULT.max_request_bytes);
+        assert_eq!(limits.max_response_bytes, DEFAULT.max_response_bytes);
+    }
+
+    #[test]
+    fn range_bounds_are_inclusive() {
+        let limits = SizeLimits::parse(&json!({
+            "max_request_bytes": REQUEST_RANGE.0,
+            "max_response_bytes": RESPONSE_RANGE.1,
+            "max_event_bytes": EVENT_MIN,
+            "max_buffered_events": BUFFERED_EVENTS_RANGE.1,
+        }))
+        .unwrap();
+        assert_eq!(limits.max_request_bytes, REQUEST_RANGE.0);
+        assert!(SizeLimits::parse(&json!({ "max_request_bytes": REQUEST_RANGE.0 - 1 })).is_err());
+        assert!(SizeLimits::parse(&json!({ "max_response_bytes": RESPONSE_RANGE.1 + 1 })).is_err());
+        assert!(SizeLimits::parse(&json!({ "max_event_bytes": EVENT_MIN - 1 })).is_err());
+        assert!(SizeLimits::parse(&json!({ "max_buffered_events": 99 })).is_err());
+    }
+
+    #[test]
+    fn event_limit_cannot_exceed_response_limit() {
+        let err = SizeLimits::parse(&json!({
+            "max_response_bytes": MIB,
+            "max_event_bytes": MIB + 1,
+        }))
+        .unwrap_err();
+        assert!(err.starts_with("max_event_bytes"));
+        // 只调小响应上限时，默认的事件上限随之越界
+        assert!(SizeLimits::parse(&json!({ "max_response_bytes": 2 * MIB })).is_err());
+        assert!(SizeLimits::parse(&json!({
+            "max_response_bytes": 2 * MIB,
+            "max_event_bytes": 2 * MIB,
+        }))
+        .is_ok());
+    }
+
+    #[test]
+    fn malformed_values_rejected() {
+        assert!(SizeLimits::parse(&json!({ "max_request": 1024 })).is_err());
+        assert!(SizeLimits::parse(&json!({ "max_request_bytes": -1 })).is_err());
+        assert!(SizeLimits::parse(&json!({ "max_request_bytes": "16M" })).is_err());
+        assert!(SizeLimits::parse(&json!(null)).is_err());
+    }
+
+    #[test]
+    fn check_request_allows_up_to_limit() {
+        let limits = SizeLimits {
+            max_request_bytes: 8,
+            ..SizeLimits::default()
+        };
+        assert!(limits.check_request("12345678").is_ok());
+        let err = limits.check_request("123456789").unwrap_err();
+        assert!(err.starts_with("PayloadTooLarge:"));
+        // 按 UTF-8 字节计算
+        assert!(limits.check_request("模型模").is_err());
+    }
+}
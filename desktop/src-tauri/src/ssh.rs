@@ -0,0 +1,398 @@
+use crate::bridge::{attach_bridge_child, ensure_bridge_ready, BridgeHandles, LaunchSnapshot};
+use crate::bridge_error::BridgeError;
+use crate::launcher::{BridgeLauncher, LaunchFuture};
+use crate::limits::AppliedLimits;
+use crate::log_level;
+use crate::panics::guarded;
+use crate::policy::CommandPolicy;
+use crate::process::isolate_group;
+use crate::sandbox::LabelLease;
+use crate::sessions::{self, SessionInfo};
+use std::path::PathBuf;
+use std::sync::Arc;
+use tauri::AppHandle;
+use tokio::process::Command;
+
+#[cfg(target_os = "windows")]
+#[allow(unused_imports)]
+use std::os::windows::process::CommandExt;
+
+const DEFAULT_PYTHON: &str = "python3";
+/// 建立 ssh 连接的超时（秒），之后的就绪等待沿用 bridge 的握手超时
+const CONNECT_TIMEOUT_SECS: u32 = 15;
+
+/// 经 ssh 启动 bridge 的远程主机与项目位置。远程主机需为类 Unix shell，
+/// 认证沿用本机的 ssh 配置（密钥、ssh-agent、~/.ssh/config），不支持交互式输入密码
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SshTarget {
+    /// 主机名、IP 或 ~/.ssh/config 中的 Host 别名
+    pub host: String,
+    pub user: Option<String>,
+    pub port: Option<u16>,
+    /// 私钥文件，未指定时由 ssh 按配置选择
+    pub identity_file: Option<String>,
+    /// 远程主机上项目根目录（含 cli.py）
+    pub remote_dir: String,
+    /// 远程主机上的 Python 命令，按 shell 命令原样执行，默认 `python3`
+    pub python: Option<String>,
+}
+
+impl SshTarget {
+    fn validate(&self) -> Result<(), String> {
+        // 以 - 开头的值会被 ssh 当成选项
+        let plain = |name: &str, v: &str| {
+            if v.trim().is_empty() || v.starts_with('-') || v.contains(char::is_whitespace) {
+                Err(format!("{} 不能为空、以 - 开头或含空白: {}", name, v))
+            } else {
+                Ok(())
+            }
+        };
+        plain("host", &self.host)?;
+        if let Some(ref user) = self.user {
+            plain("user", user)?;
+            if user.contains('@') {
+                return Err(format!("user 不能含 @: {}", user));
+            }
+        }
+        if self.port == Some(0) {
+            return Err("port 应在 1 ~ 65535 之间".to_string());
+        }
+        if self.remote_dir.trim().is_empty() {
+            return Err("remoteDir 不能为空".to_string());
+        }
+        if self.python.as_deref().is_some_and(|p| p.trim().is_empty()) {
+            return Err("python 不能为空".to_string());
+        }
+        Ok(())
+    }
+
+    /// 显示用的目标，如 `ssh user@host:2222`
+    fn destination(&self) -> String {
+        let mut out = String::from("ssh ");
+        if let Some(ref user) = self.user {
+            out.push_str(user);
+            out.push('@');
+        }
+        out.push_str(&self.host);
+        if let Some(port) = self.port {
+            out.push_str(&format!(":{}", port));
+        }
+        out
+    }
+
+    /// 在远程 shell 中执行的命令：进入项目目录后以 exec 启动 bridge，
+    /// ssh 通道关闭时 bridge 读到 stdin 结束即自行退出
+    fn remote_command(&self) -> String {
+        let mut env = vec![
+            "PYTHONIOENCODING=utf-8".to_string(),
+            "PYTHONUNBUFFERED=1".to_string(),
+            "PYTHONPATH=\"$PWD\"".to_string(),
+        ];
+        if let Some(level) = log_level::python_level() {
+            env.push(format!("LOG_LEVEL={}", level));
+        }
+        format!(
+            "cd {} && {} exec {} cli.py tui-bridge",
+            shell_quote(self.remote_dir.trim()),
+            env.join(" "),
+            self.python.as_deref().map_or(DEFAULT_PYTHON, str::trim)
+        )
+    }
+
+    fn ssh_args(&self) -> Vec<String> {
+        let mut args: Vec<String> = vec![
+            // 不分配终端：bridge 的 stdio 是二进制安全的管道
+            "-T".into(),
+            "-o".into(),
+            "BatchMode=yes".into(),
+            "-o".into(),
+            format!("ConnectTimeout={}", CONNECT_TIMEOUT_SECS),
+            "-o".into(),
+            "ServerAliveInterval=15".into(),
+            "-o".into(),
+            "ServerAliveCountMax=4".into(),
+        ];
+        if let Some(port) = self.port {
+            args.push("-p".into());
+            args.push(port.to_string());
+        }
+        if let Some(ref identity) = self.identity_file {
+            args.push("-i".into());
+            args.push(identity.clone());
+        }
+        if let Some(ref user) = self.user {
+            args.push("-l".into());
+            args.push(user.clone());
+        }
+        args.push("--".into());
+        args.push(self.host.clone());
+        args.push(self.remote_command());
+        args
+    }
+}
+
+/// POSIX shell 单引号转义
+fn shell_quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', r"'\''"))
+}
+
+/// 经系统 ssh 客户端在远程主机上运行 `cli.py tui-bridge`，bridge 的 stdio 即 ssh 的 stdio。
+/// 本机持有的是 ssh 进程：结束它即关闭通道，远程 bridge 随之退出
+pub struct SshLauncher {
+    target: SshTarget,
+}
+
+impl BridgeLauncher for SshLauncher {
+    fn launch(&self, _bundled_java_home: Option<PathBuf>) -> LaunchFuture {
+        Box::pin(spawn_over_ssh(self.target.clone()))
+    }
+
+    fn describe(&self) -> String {
+        self.target.destination()
+    }
+}
+
+async fn spawn_over_ssh(target: SshTarget) -> Result<BridgeHandles, BridgeError> {
+    let args = target.ssh_args();
+    // JAVA_HOME 由远程主机决定，本机的设置与之无关
+    let mut snapshot = LaunchSnapshot::new(&None);
+    snapshot.record_command("ssh", "ssh", &args, None);
+
+    let mut builder = Command::new("ssh");
+    builder
+        .args(&args)
+        .stdin(std::process::Stdio::piped())
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped());
+    isolate_group(&mut builder);
+    #[cfg(target_os = "windows")]
+    {
+        const CREATE_NO_WINDOW: u32 = 0x08000000;
+        builder.creation_flags(CREATE_NO_WINDOW);
+    }
+    let child = builder.spawn().map_err(|e| {
+        BridgeError::spawn_failed(format!(
+            "启动 ssh 失败（请确认已安装 OpenSSH 客户端）({}): {}",
+            target.destination(),
+            e
+        ))
+    })?;
+    attach_bridge_child(
+        child,
+        AppliedLimits::unlimited(),
+        LabelLease::default(),
+        snapshot,
+    )
+    .await
+}
+
+/// 经 ssh 在远程主机上启动 bridge 并登记为具名会话；立即连接，连不通时不保留会话。
+/// 之后带该会话 ID 的请求都发往远程 bridge，意外退出后按需经 ssh 重新启动
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn remote_connect_ssh(
+    app: AppHandle,
+    policy: tauri::State<'_, CommandPolicy>,
+    host: String,
+    user: Option<String>,
+    port: Option<u16>,
+    identity_file: Option<String>,
+    remote_dir: String,
+    python: Option<String>,
+    id: Option<String>,
+    label: Option<String>,
+    project: Option<String>,
+) -> Result<SessionInfo, BridgeError> {
+    guarded("remote_connect_ssh", async move {
+        policy.ensure_writable("连接远程 bridge")?;
+        let target = SshTarget {
+            host: host.trim().to_string(),
+            user: user.map(|u| u.trim().to_string()).filter(|u| !u.is_empty()),
+            port,
+            identity_file: identity_file.filter(|f| !f.trim().is_empty()),
+            remote_dir,
+            python,
+        };
+        target.validate()?;
+        let launcher: Arc<dyn BridgeLauncher> = Arc::new(SshLauncher { target });
+        let (id, state) = sessions::create(&app, id, label, project, Some(launcher)).await?;
+        if let Err(e) = ensure_bridge_ready(&state).await {
+            sessions::discard(&app, &id).await;
+            return Err(e);
+        }
+        Ok(sessions::info(&app, &id, &state).await)
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn target(host: &str) -> SshTarget {
+        SshTarget {
+            host: host.to_string(),
+            user: None,
+            port: None,
+            identity_file: None,
+            remote_dir: "/srv/mph-agent".to_string(),
+            python: None,
+        }
+    }
+
+    #[test]
+    fn validate_rejects_option_like_and_malformed_values() {
+        let cases: Vec<(SshTarget, Option<&str>)> = vec![
+            (target("lab-01"), None),
+            (target("10.0.0.5"), None),
+            (target("-oProxyCommand=sh"), Some("host")),
+            (target(""), Some("host")),
+            (target("lab 01"), Some("host")),
+            (
+                SshTarget {
+                    user: Some("alice".into()),
+                    port: Some(2222),
+                    ..target("lab-01")
+                },
+                None,
+            ),
+            (
+                SshTarget {
+                    user: Some("-lroot".into()),
+                    ..target("lab-01")
+                },
+                Some("user"),
+            ),
+            (
+                SshTarget {
+                    user: Some("alice@evil".into()),
+                    ..target("lab-01")
+                },
+                Some("@"),
+            ),
+            (
+                SshTarget {
+                    port: Some(0),
+                    ..target("lab-01")
+                },
+                Some("port"),
+            ),
+            (
+                SshTarget {
+                    port: Some(65535),
+                    ..target("lab-01")
+                },
+                None,
+            ),
+            (
+                SshTarget {
+                    remote_dir: "  ".into(),
+                    ..target("lab-01")
+                },
+                Some("remoteDir"),
+            ),
+            (
+                SshTarget {
+                    python: Some(" ".into()),
+                    ..target("lab-01")
+                },
+                Some("python"),
+            ),
+        ];
+        for (t, expected) in cases {
+            match (t.validate(), expected) {
+                (Ok(()), None) => {}
+                (Err(e), Some(needle)) => assert!(e.contains(needle), "{:?}: {}", t, e),
+                (got, _) => panic!("{:?}: 意外的结果 {:?}", t, got),
+            }
+        }
+    }
+
+    #[test]
+    fn ssh_args_end_options_before_host() {
+        let cases: Vec<(SshTarget, &[&str], &str)> = vec![
+            (target("lab-01"), &[], "ssh lab-01"),
+            (
+                SshTarget {
+                    port: Some(2222),
+                    ..target("lab-01")
+                },
+                &["-p", "2222"],
+                "ssh lab-01:2222",
+            ),
+            (
+                SshTarget {
+                    user: Some("alice".into()),
+                    identity_file: Some("/home/alice/.ssh/id_lab".into()),
+                    ..target("lab-01")
+                },
+                &["-i", "/home/alice/.ssh/id_lab", "-l", "alice"],
+                "ssh alice@lab-01",
+            ),
+            (
+                SshTarget {
+                    user: Some("alice".into()),
+                    port: Some(22),
+                    ..target("lab-01")
+                },
+                &["-p", "22", "-l", "alice"],
+                "ssh alice@lab-01:22",
+            ),
+        ];
+        for (t, options, destination) in cases {
+            let args = t.ssh_args();
+            assert_eq!(args[..3], ["-T", "-o", "BatchMode=yes"]);
+            let dashes = args.iter().position(|a| a == "--").unwrap();
+            // 固定的 -o 选项之后依次是端口、私钥与用户
+            assert_eq!(args[dashes - options.len()..dashes], *options);
+            assert_eq!(args.len(), dashes + 3);
+            assert_eq!(args[dashes + 1], t.host);
+            assert_eq!(args[dashes + 2], t.remote_command());
+            assert_eq!(t.destination(), destination);
+        }
+    }
+
+    #[test]
+    fn remote_command_quotes_remote_dir() {
+        let t = SshTarget {
+            remote_dir: " /srv/it's here; rm -rf ~ ".into(),
+            python: Some(" /opt/py/bin/python ".into()),
+            ..target("lab-01")
+        };
+        let command = t.remote_command();
+        assert!(command.starts_with(r"cd '/srv/it'\''s here; rm -rf ~' && "));
+        assert!(command.ends_with(" exec /opt/py/bin/python cli.py tui-bridge"));
+        assert!(target("lab-01")
+            .remote_command()
+            .ends_with(" exec python3 cli.py tui-bridge"));
+    }
+
+    #[test]
+    fn shell_quote_cases() {
+        let cases = [
+            ("", "''"),
+            ("plain", "'plain'"),
+            ("a b", "'a b'"),
+            ("it's", r"'it'\''s'"),
+            ("''", r"''\'''\'''"),
+            ("$HOME `id` \"x\"", "'$HOME `id` \"x\"'"),
+        ];
+        for (input, quoted) in cases {
+            assert_eq!(shell_quote(input), quoted);
+        }
+    }
+
+    /// 经 sh 展开后应得到原字符串
+    #[cfg(unix)]
+    #[test]
+    fn shell_quote_round_trips_through_sh() {
+        for input in ["it's", "a'b'c", "$(id); `id`", "x\ny", "\\'"] {
+            let out = std::process::Command::new("sh")
+                .arg("-c")
+                .arg(format!("printf %s {}", shell_quote(input)))
+                .output()
+                .unwrap();
+            assert_eq!(String::from_utf8_lossy(&out.stdout), input);
+        }
+    }
+}
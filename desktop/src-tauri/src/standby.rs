@@ -0,0 +1,137 @@
+use crate::bridge::{BridgeHandles, BridgeState};
+use crate::launcher;
+use crate::limits::{self, ResourceLimits};
+use crate::panics::guarded;
+use crate::sandbox;
+use crate::settings::LiveSettings;
+use serde::Serialize;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tauri::{AppHandle, Manager};
+use tokio::sync::Mutex;
+
+/// 备用进程启动时生效的条件；与下一次启动不一致时备用进程不能接替
+#[derive(PartialEq)]
+struct LaunchEnv {
+    java_home: Option<PathBuf>,
+    /// 沙箱可写目录，None 表示未启用沙箱
+    sandbox_roots: Option<Vec<PathBuf>>,
+    limits: ResourceLimits,
+}
+
+impl LaunchEnv {
+    fn current(java_home: Option<PathBuf>) -> Self {
+        Self {
+            java_home,
+            sandbox_roots: sandbox::active_roots(),
+            limits: limits::active(),
+        }
+    }
+}
+
+/// 已完成握手、尚未连接 COMSOL 的备用进程，及其启动时的条件
+struct Spare {
+    handles: BridgeHandles,
+    env: LaunchEnv,
+}
+
+impl Spare {
+    fn alive(&self) -> bool {
+        self.handles.exit.borrow().is_none()
+    }
+
+    fn discard(self) {
+        self.handles.child.kill();
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct StandbyStatus {
+    pub enabled: bool,
+    pub ready: bool,
+    pub warming: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pid: Option<u32>,
+}
+
+/// 默认会话的预热备用进程：Python 解释器与依赖已加载，中止后直接接替，
+/// 不必等待冷启动；接替后在后台再预热一个
+#[derive(Default)]
+pub struct Standby {
+    spare: Mutex<Option<Spare>>,
+    warming: AtomicBool,
+}
+
+fn enabled(app: &AppHandle) -> bool {
+    app.try_state::<LiveSettings>()
+        .is_some_and(|s| s.bridge_warm_standby())
+}
+
+/// 在后台预热一个备用进程；未启用、已有可用备用或正在预热时不做任何事
+pub fn refill(app: &AppHandle) {
+    let standby = app.state::<Standby>();
+    if !enabled(app) || standby.warming.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let standby = app.state::<Standby>();
+        let java_home = app
+            .state::<BridgeState>()
+            .lock()
+            .await
+            .bundled_java_home
+            .clone();
+        let env = LaunchEnv::current(java_home.clone());
+        let usable = standby
+            .spare
+            .lock()
+            .await
+            .as_ref()
+            .is_some_and(|s| s.alive() && s.env == env);
+        if !usable {
+            match launcher::launch(java_home).await {
+                Ok(handles) => {
+                    let spare = Spare { handles, env };
+                    if let Some(old) = standby.spare.lock().await.replace(spare) {
+                        old.discard();
+                    }
+                }
+                Err(e) => eprintln!("Warning: 预热备用 bridge 失败: {}", e),
+            }
+        }
+        standby.warming.store(false, Ordering::SeqCst);
+    });
+}
+
+/// 取出可用的备用进程：已退出，或 JAVA_HOME、沙箱目录、资源上限已变更的备用直接丢弃
+pub async fn take(app: &AppHandle, java_home: &Option<PathBuf>) -> Option<BridgeHandles> {
+    let spare = app.state::<Standby>().spare.lock().await.take()?;
+    if !enabled(app) || !spare.alive() || spare.env != LaunchEnv::current(java_home.clone()) {
+        spare.discard();
+        return None;
+    }
+    Some(spare.handles)
+}
+
+/// 结束备用进程（停止 bridge 释放内存、退出应用时）；返回是否确有备用进程
+pub async fn discard(app: &AppHandle) -> bool {
+    let spare = app.state::<Standby>().spare.lock().await.take();
+    spare.map(Spare::discard).is_some()
+}
+
+/// 备用进程状态
+#[tauri::command]
+pub async fn bridge_standby_status(app: AppHandle) -> Result<StandbyStatus, String> {
+    guarded("bridge_standby_status", async move {
+        let standby = app.state::<Standby>();
+        let spare = standby.spare.lock().await;
+        Ok(StandbyStatus {
+            enabled: enabled(&app),
+            ready: spare.as_ref().is_some_and(|s| s.alive()),
+            warming: standby.warming.load(Ordering::SeqCst),
+            pid: spare.as_ref().map(|s| s.handles.child.pid),
+        })
+    })
+    .await
+}
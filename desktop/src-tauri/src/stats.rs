@@ -0,0 +1,158 @@
+use crate::bridge_error::BridgeError;
+use crate::panics::guarded;
+use crate::sessions;
+use serde::Serialize;
+use serde_json::Value;
+use std::sync::atomic::{AtomicU64, Ordering};
+use sysinfo::{Pid, ProcessesToUpdate, System, MINIMUM_CPU_UPDATE_INTERVAL};
+use tauri::AppHandle;
+
+/// 系统可用内存低于总内存的该比例时视为内存吃紧
+const LOW_MEMORY_RATIO: f64 = 0.1;
+
+/// 发往 bridge 的请求计数，跨重启累计
+#[derive(Debug, Default)]
+pub struct RequestCounters {
+    requests: AtomicU64,
+    streams: AtomicU64,
+    failed: AtomicU64,
+    timed_out: AtomicU64,
+}
+
+impl RequestCounters {
+    pub fn started(&self, stream: bool) {
+        let counter = if stream {
+            &self.streams
+        } else {
+            &self.requests
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// 按结果计数：超时单独统计，其余错误与 `ok: false` 的响应记为失败
+    pub fn finished(&self, result: &Result<Value, BridgeError>) {
+        let counter = match result {
+            Err(BridgeError::Timeout { .. }) => &self.timed_out,
+            Err(_) => &self.failed,
+            Ok(v) if v.get("ok").and_then(|o| o.as_bool()) == Some(false) => &self.failed,
+            Ok(_) => return,
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn snapshot(&self) -> Value {
+        serde_json::json!({
+            "requests": self.requests.load(Ordering::Relaxed),
+            "streams": self.streams.load(Ordering::Relaxed),
+            "failed": self.failed.load(Ordering::Relaxed),
+            "timed_out": self.timed_out.load(Ordering::Relaxed),
+        })
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ProcessStats {
+    pub pid: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parent: Option<u32>,
+    pub name: String,
+    /// 占单个核心的百分比，多核满载时可超过 100
+    pub cpu_percent: f32,
+    pub rss_bytes: u64,
+}
+
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct TreeStats {
+    pub cpu_percent: f32,
+    pub rss_bytes: u64,
+    pub uptime_secs: u64,
+    /// bridge 进程及其派生进程（COMSOL 的 JVM 等），第一项为 bridge 本身
+    pub processes: Vec<ProcessStats>,
+    pub total_memory: u64,
+    pub available_memory: u64,
+    /// 系统可用内存不足总量的 10%，界面可提示用户
+    pub memory_pressure: bool,
+}
+
+/// 采样进程树的 CPU 与内存；CPU 占用需要间隔两次刷新才能算出
+fn sample_tree(root: u32) -> TreeStats {
+    let mut sys = System::new();
+    sys.refresh_processes(ProcessesToUpdate::All, true);
+    std::thread::sleep(MINIMUM_CPU_UPDATE_INTERVAL);
+    sys.refresh_processes(ProcessesToUpdate::All, true);
+    sys.refresh_memory();
+
+    let mut pids = vec![Pid::from_u32(root)];
+    let mut i = 0;
+    while i < pids.len() {
+        let parent = pids[i];
+        for (pid, process) in sys.processes() {
+            if process.parent() == Some(parent) && !pids.contains(pid) {
+                pids.push(*pid);
+            }
+        }
+        i += 1;
+    }
+
+    let mut stats = TreeStats {
+        total_memory: sys.total_memory(),
+        available_memory: sys.available_memory(),
+        ..TreeStats::default()
+    };
+    stats.memory_pressure = stats.total_memory > 0
+        && (stats.available_memory as f64) < stats.total_memory as f64 * LOW_MEMORY_RATIO;
+    for pid in pids {
+        let Some(process) = sys.processes().get(&pid) else {
+            continue;
+        };
+        if pid.as_u32() == root {
+            stats.uptime_secs = process.run_time();
+        }
+        stats.cpu_percent += process.cpu_usage();
+        stats.rss_bytes += process.memory();
+        stats.processes.push(ProcessStats {
+            pid: pid.as_u32(),
+            parent: process.parent().map(|p| p.as_u32()),
+            name: process.name().to_string_lossy().into_owned(),
+            cpu_percent: process.cpu_usage(),
+            rss_bytes: process.memory(),
+        });
+    }
+    stats
+}
+
+/// bridge 子进程（含 JVM 等派生进程）的 CPU、内存与运行时长，以及请求计数。
+/// 采样约需 200 毫秒；bridge 未运行时只返回计数
+#[tauri::command]
+pub async fn bridge_stats(app: AppHandle, session: Option<String>) -> Result<Value, BridgeError> {
+    guarded("bridge_stats", async move {
+        let state = sessions::resolve(&app, session.as_deref())?;
+        let (pid, counters, in_flight, active_streams) = {
+            let guard = state.lock().await;
+            (
+                guard.child.as_ref().and_then(|c| c.verified_pid()),
+                guard.counters.snapshot(),
+                guard.dispatcher.as_ref().map_or(0, |d| d.in_flight()),
+                guard.active_streams,
+            )
+        };
+        let tree = match pid {
+            Some(pid) => Some(
+                tauri::async_runtime::spawn_blocking(move || sample_tree(pid))
+                    .await
+                    .map_err(|e| e.to_string())?,
+            ),
+            None => None,
+        };
+        let mut requests = counters;
+        requests["in_flight"] = Value::from(in_flight);
+        requests["active_streams"] = Value::from(active_streams);
+        Ok(serde_json::json!({
+            "running": pid.is_some(),
+            "pid": pid,
+            "process": tree,
+            "requests": requests,
+        }))
+    })
+    .await
+}
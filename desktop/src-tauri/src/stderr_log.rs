@@ -0,0 +1,104 @@
+use crate::bridge::BridgeState;
+use crate::bridge_error::BridgeError;
+use crate::panics::guarded;
+use crate::store::now_ms;
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex, OnceLock};
+use tauri::{AppHandle, Emitter};
+
+/// 环形缓冲保留的行数与单行长度上限
+const MAX_LINES: usize = 2000;
+const MAX_LINE_BYTES: usize = 4096;
+/// bridge_get_stderr 默认返回的行数
+const DEFAULT_TAIL: usize = 200;
+
+static APP: OnceLock<AppHandle> = OnceLock::new();
+
+#[derive(Clone, Debug, Serialize)]
+pub struct StderrLine {
+    pub ts: u64,
+    pub line: String,
+}
+
+/// bridge stderr 的最近若干行；超出容量时丢弃最旧的行
+#[derive(Default)]
+pub struct StderrRing {
+    lines: VecDeque<StderrLine>,
+    dropped: u64,
+}
+
+pub type StderrBuf = Arc<Mutex<StderrRing>>;
+
+pub fn new_buf() -> StderrBuf {
+    Arc::new(Mutex::new(StderrRing::default()))
+}
+
+fn truncate(line: &str) -> String {
+    if line.len() <= MAX_LINE_BYTES {
+        return line.to_string();
+    }
+    let mut end = MAX_LINE_BYTES;
+    while !line.is_char_boundary(end) {
+        end -= 1;
+    }
+    format!("{}…", &line[..end])
+}
+
+impl StderrRing {
+    fn push(&mut self, line: StderrLine) {
+        if self.lines.len() >= MAX_LINES {
+            self.lines.pop_front();
+            self.dropped += 1;
+        }
+        self.lines.push_back(line);
+    }
+
+    pub fn tail(&self, n: usize) -> Vec<StderrLine> {
+        let skip = self.lines.len().saturating_sub(n);
+        self.lines.iter().skip(skip).cloned().collect()
+    }
+
+    /// 最近 n 行拼成的文本，附在错误信息后面
+    pub fn tail_text(&self, n: usize) -> String {
+        self.tail(n)
+            .into_iter()
+            .map(|l| l.line)
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+/// 设置接收 `bridge-stderr` 事件的应用；bridge 可能在此之前启动，之前的行只进缓冲
+pub fn attach(app: AppHandle) {
+    let _ = APP.set(app);
+}
+
+/// 记录一行 stderr 并转发给前端
+pub fn record_line(buf: &StderrBuf, raw: &str) {
+    let line = StderrLine {
+        ts: now_ms(),
+        line: truncate(raw.trim_end_matches(['\r', '\n'])),
+    };
+    if let Some(app) = APP.get() {
+        let _ = app.emit("bridge-stderr", &line);
+    }
+    buf.lock().unwrap_or_else(|e| e.into_inner()).push(line);
+}
+
+/// 当前 bridge 进程 stderr 的最近 `lines` 行（默认 200）
+#[tauri::command]
+pub async fn bridge_get_stderr(
+    state: tauri::State<'_, BridgeState>,
+    lines: Option<usize>,
+) -> Result<serde_json::Value, BridgeError> {
+    guarded("bridge_get_stderr", async move {
+        let buf = state.inner().lock().await.stderr_buf.clone();
+        let ring = buf.lock().unwrap_or_else(|e| e.into_inner());
+        Ok(serde_json::json!({
+            "lines": ring.tail(lines.unwrap_or(DEFAULT_TAIL).min(MAX_LINES)),
+            "dropped": ring.dropped,
+        }))
+    })
+    .await
+}
@@ -0,0 +1,568 @@
+use crate::artifacts::{sha256_file, ArtifactRegistry};
+use crate::backups::BackupManager;
+use crate::bridge::find_project_root;
+use crate::jobs::{JobRecord, JobRegistry, JobStatus};
+use crate::notifications::NotificationCenter;
+use crate::panics::guarded;
+use crate::paths::output_dir;
+use crate::store::{load_json, now_ms, save_json};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager};
+
+const DAY_MS: u64 = 24 * 60 * 60 * 1000;
+/// 超过该时间的结果导出文件视为可清理的旧扫描结果
+const OLD_RESULT_AGE_MS: u64 = 30 * DAY_MS;
+/// 临时文件超过该时间仍在，说明写入进程早已退出
+const STALE_TEMP_AGE_MS: u64 = 60 * 60 * 1000;
+const RESULT_EXTS: &[&str] = &["csv", "txt", "dat", "vtu", "vtk", "png", "jpg"];
+const TEMP_EXTS: &[&str] = &["tmp", "lock", "recovery"];
+/// 失败任务输出的隔离目录，建在原文件所在目录下
+const QUARANTINE_DIR: &str = "failed";
+
+/// 任务失败或中止后，其写了一半的输出如何处理
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FailedOutputs {
+    #[default]
+    Keep,
+    /// 移入同目录下的 `failed/` 子目录
+    Quarantine,
+    Delete,
+}
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct StorageConfig {
+    /// 未单独设置的项目使用的配额（MB），None 表示不限制
+    #[serde(default)]
+    pub default_quota_mb: Option<u64>,
+    #[serde(default)]
+    pub project_quota_mb: HashMap<String, u64>,
+    #[serde(default)]
+    pub failed_outputs: FailedOutputs,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ProjectUsage {
+    pub project: Option<String>,
+    pub bytes: u64,
+    pub files: usize,
+    pub quota_bytes: Option<u64>,
+    pub over_quota: bool,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct CleanupItem {
+    pub path: String,
+    /// old_sweep_result / duplicate_model / stale_temp
+    pub kind: String,
+    pub bytes: u64,
+    pub reason: String,
+    pub project: Option<String>,
+    pub modified_ms: u64,
+}
+
+/// 产物存储配额：按项目统计模型与备份占用，超额时提醒一次
+#[derive(Default)]
+pub struct StorageManager {
+    path: Option<PathBuf>,
+    config: Mutex<StorageConfig>,
+    warned: Mutex<HashSet<Option<String>>>,
+}
+
+impl StorageManager {
+    pub fn load(path: PathBuf) -> Self {
+        let config: StorageConfig = load_json(&path);
+        Self {
+            path: Some(path),
+            config: Mutex::new(config),
+            ..Self::default()
+        }
+    }
+
+    pub fn config(&self) -> StorageConfig {
+        self.config
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    pub fn set_quota(&self, project: Option<&str>, quota_mb: Option<u64>) -> Result<(), String> {
+        let mut config = self.config.lock().unwrap_or_else(|e| e.into_inner());
+        match (project, quota_mb) {
+            (None, q) => config.default_quota_mb = q,
+            (Some(p), Some(q)) => {
+                config.project_quota_mb.insert(p.to_string(), q);
+            }
+            (Some(p), None) => {
+                config.project_quota_mb.remove(p);
+            }
+        }
+        self.warned
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clear();
+        match self.path {
+            Some(ref p) => save_json(p, &*config),
+            None => Ok(()),
+        }
+    }
+
+    fn set_failed_outputs(&self, mode: FailedOutputs) -> Result<(), String> {
+        let mut config = self.config.lock().unwrap_or_else(|e| e.into_inner());
+        config.failed_outputs = mode;
+        match self.path {
+            Some(ref p) => save_json(p, &*config),
+            None => Ok(()),
+        }
+    }
+
+    fn quota_bytes(&self, project: Option<&str>) -> Option<u64> {
+        let config = self.config.lock().unwrap_or_else(|e| e.into_inner());
+        project
+            .and_then(|p| config.project_quota_mb.get(p).copied())
+            .or(config.default_quota_mb)
+            .map(|mb| mb.saturating_mul(1024 * 1024))
+    }
+}
+
+struct FileInfo {
+    path: PathBuf,
+    size: u64,
+    modified_ms: u64,
+}
+
+fn file_info(path: &Path) -> Option<FileInfo> {
+    let meta = std::fs::metadata(path).ok()?;
+    if !meta.is_file() {
+        return None;
+    }
+    let modified_ms = meta
+        .modified()
+        .ok()
+        .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0);
+    Some(FileInfo {
+        path: path.to_path_buf(),
+        size: meta.len(),
+        modified_ms,
+    })
+}
+
+fn ext_of(path: &Path) -> String {
+    path.extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or_default()
+}
+
+/// 请求的 `output`：相对路径写在任务工作目录下，没有工作目录的旧任务写在默认输出目录
+fn job_output(job: &JobRecord) -> Option<PathBuf> {
+    job.payload
+        .get("output")
+        .and_then(Value::as_str)
+        .map(str::trim)
+        .filter(|o| !o.is_empty())
+        .and_then(|o| {
+            let p = PathBuf::from(o);
+            if p.is_absolute() {
+                Some(p)
+            } else {
+                job.work_dir
+                    .as_ref()
+                    .map(PathBuf::from)
+                    .or_else(|| output_dir(&find_project_root()))
+                    .map(|d| d.join(p))
+            }
+        })
+}
+
+/// 失败任务可能写了一半的文件：登记的产物与请求的 `output`，且在任务开始后被修改过
+fn partial_outputs(job: &JobRecord) -> Vec<PathBuf> {
+    let started = job.started_at.unwrap_or(job.created_at);
+    let output = job_output(job);
+    let mut seen = HashSet::new();
+    job.artifacts
+        .iter()
+        .map(PathBuf::from)
+        .chain(output)
+        .filter(|p| seen.insert(p.clone()))
+        .filter(|p| {
+            p.parent()
+                .and_then(Path::file_name)
+                .is_none_or(|d| d != QUARANTINE_DIR)
+        })
+        .filter(|p| file_info(p).is_some_and(|i| i.modified_ms >= started))
+        .collect()
+}
+
+/// 移入 `failed/`，文件名前加任务 ID 避免同名冲突
+fn quarantine(path: &Path, request_id: &str) -> Result<String, String> {
+    let dir = path
+        .parent()
+        .ok_or_else(|| format!("无效路径: {}", path.display()))?
+        .join(QUARANTINE_DIR);
+    std::fs::create_dir_all(&dir).map_err(|e| format!("创建 {} 失败: {}", dir.display(), e))?;
+    let name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let target = dir.join(format!("{}-{}", request_id, name));
+    std::fs::rename(path, &target).map_err(|e| format!("移动 {} 失败: {}", path.display(), e))?;
+    Ok(target.to_string_lossy().into_owned())
+}
+
+/// 删除任务的工作目录：先改名再删除，删除中途失败也不会留下看似完整的目录；
+/// 目录下登记的产物同步从产物登记与任务记录中移除，返回被移除的产物
+fn remove_work_dir(app: &AppHandle, job: &JobRecord) -> Result<Vec<String>, String> {
+    let Some(ref dir) = job.work_dir else {
+        return Err(format!("任务 {} 没有独立的工作目录", job.id));
+    };
+    let dir = Path::new(dir);
+    if dir.exists() {
+        let name = dir.file_name().unwrap_or_default().to_string_lossy();
+        let trash = dir.with_file_name(format!(".{}.removing", name));
+        std::fs::rename(dir, &trash).map_err(|e| format!("移动 {} 失败: {}", dir.display(), e))?;
+        std::fs::remove_dir_all(&trash)
+            .map_err(|e| format!("删除 {} 失败: {}", trash.display(), e))?;
+    }
+    let artifacts = app.state::<ArtifactRegistry>();
+    let jobs = app.state::<JobRegistry>();
+    let removed: Vec<String> = job
+        .artifacts
+        .iter()
+        .filter(|a| Path::new(a).starts_with(dir))
+        .cloned()
+        .collect();
+    for path in &removed {
+        artifacts.relocate(path, None);
+        jobs.replace_artifact(&job.id, path, None);
+    }
+    Ok(removed)
+}
+
+/// 按设置隔离或删除失败/中止任务的半成品输出，同步更新产物登记与任务记录；
+/// 删除模式下有独立工作目录的任务整目录删除
+pub fn handle_failed_outputs(app: &AppHandle, request_id: &str) {
+    let mode = app.state::<StorageManager>().config().failed_outputs;
+    if mode == FailedOutputs::Keep {
+        return;
+    }
+    let jobs = app.state::<JobRegistry>();
+    let Some(job) = jobs.get(request_id) else {
+        return;
+    };
+    let artifacts = app.state::<ArtifactRegistry>();
+    let mut handled = Vec::new();
+    if mode == FailedOutputs::Delete && job.work_dir.is_some() {
+        match remove_work_dir(app, &job) {
+            Ok(removed) => handled.extend(
+                removed
+                    .into_iter()
+                    .map(|from| serde_json::json!({ "from": from, "to": null })),
+            ),
+            Err(e) => eprintln!("Warning: 清理失败任务输出出错: {}", e),
+        }
+    }
+    for path in partial_outputs(&job) {
+        let from = path.to_string_lossy().into_owned();
+        let result = match mode {
+            FailedOutputs::Quarantine => quarantine(&path, request_id).map(Some),
+            _ => std::fs::remove_file(&path)
+                .map(|_| None)
+                .map_err(|e| format!("删除 {} 失败: {}", from, e)),
+        };
+        match result {
+            Ok(to) => {
+                artifacts.relocate(&from, to.as_deref());
+                jobs.replace_artifact(request_id, &from, to.as_deref());
+                handled.push(serde_json::json!({ "from": from, "to": to }));
+            }
+            Err(e) => eprintln!("Warning: 清理失败任务输出出错: {}", e),
+        }
+    }
+    if !handled.is_empty() {
+        app.state::<NotificationCenter>().deliver(
+            app,
+            "job-outputs-cleaned",
+            serde_json::json!({
+                "request_id": request_id,
+                "mode": mode,
+                "files": handled,
+            }),
+        );
+    }
+}
+
+/// 按项目汇总已登记产物及其备份的磁盘占用
+pub fn project_usage(app: &AppHandle) -> Vec<ProjectUsage> {
+    let storage = app.state::<StorageManager>();
+    let backups = app.state::<BackupManager>().list(None);
+    let mut totals: BTreeMap<Option<String>, (u64, usize)> = BTreeMap::new();
+    for a in app.state::<ArtifactRegistry>().list() {
+        let entry = totals.entry(a.project.clone()).or_default();
+        if let Some(info) = file_info(Path::new(&a.path)) {
+            entry.0 += info.size;
+            entry.1 += 1;
+        }
+        for b in backups.iter().filter(|b| b.source == a.path) {
+            entry.0 += b.size;
+            entry.1 += 1;
+        }
+    }
+    totals
+        .into_iter()
+        .map(|(project, (bytes, files))| {
+            let quota_bytes = storage.quota_bytes(project.as_deref());
+            ProjectUsage {
+                over_quota: quota_bytes.is_some_and(|q| bytes > q),
+                project,
+                bytes,
+                files,
+                quota_bytes,
+            }
+        })
+        .collect()
+}
+
+/// 新产物登记后检查所属项目是否超出配额；每次越线只提醒一次。
+/// 统计需逐个读取文件信息，放到阻塞线程执行，不拖慢调用方所在的事件读取循环
+pub fn check_quota(app: &AppHandle, project: Option<&str>) {
+    let app = app.clone();
+    let project = project.map(String::from);
+    tauri::async_runtime::spawn_blocking(move || check_quota_blocking(&app, project.as_deref()));
+}
+
+fn check_quota_blocking(app: &AppHandle, project: Option<&str>) {
+    let Some(usage) = project_usage(app)
+        .into_iter()
+        .find(|u| u.project.as_deref() == project)
+    else {
+        return;
+    };
+    let storage = app.state::<StorageManager>();
+    let mut warned = storage.warned.lock().unwrap_or_else(|e| e.into_inner());
+    let key = project.map(String::from);
+    if !usage.over_quota {
+        warned.remove(&key);
+        return;
+    }
+    if warned.insert(key) {
+        drop(warned);
+        app.state::<NotificationCenter>().deliver(
+            app,
+            "storage-quota-exceeded",
+            serde_json::json!({
+                "project": usage.project,
+                "bytes": usage.bytes,
+                "quota_bytes": usage.quota_bytes,
+            }),
+        );
+    }
+}
+
+/// 临时文件是否属于运行中的任务：在其工作目录下，或是其产物/输出的锁文件、
+/// 恢复文件（`model.mph.lock`、`~$model.mph` 等）。COMSOL 打开模型期间锁文件一直存在
+fn held_by_running_job(path: &Path, running: &[JobRecord]) -> bool {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    let owner = match name.strip_prefix("~$") {
+        Some(rest) => path.with_file_name(rest),
+        None => path.with_extension(""),
+    };
+    running.iter().any(|job| {
+        job.work_dir.as_ref().is_some_and(|d| path.starts_with(d))
+            || job
+                .artifacts
+                .iter()
+                .map(PathBuf::from)
+                .chain(job_output(job))
+                .any(|a| a == owner)
+    })
+}
+
+/// 扫描产物所在目录，列出可清理的文件，按可回收空间从大到小排序
+fn collect_suggestions(app: &AppHandle, project: Option<&str>) -> Vec<CleanupItem> {
+    let now = now_ms();
+    let running: Vec<JobRecord> = app
+        .state::<JobRegistry>()
+        .list(None, usize::MAX)
+        .into_iter()
+        .filter(|j| j.status == JobStatus::Running)
+        .collect();
+    let mut dirs: BTreeMap<PathBuf, Option<String>> = BTreeMap::new();
+    for a in app.state::<ArtifactRegistry>().list() {
+        if project.is_some() && a.project.as_deref() != project {
+            continue;
+        }
+        if let Some(dir) = Path::new(&a.path).parent() {
+            dirs.entry(dir.to_path_buf()).or_insert(a.project);
+        }
+    }
+
+    let mut out = Vec::new();
+    let mut item = |info: &FileInfo, kind: &str, reason: String, project: &Option<String>| {
+        out.push(CleanupItem {
+            path: info.path.to_string_lossy().into_owned(),
+            kind: kind.to_string(),
+            bytes: info.size,
+            reason,
+            project: project.clone(),
+            modified_ms: info.modified_ms,
+        })
+    };
+
+    for (dir, proj) in &dirs {
+        let Ok(rd) = std::fs::read_dir(dir) else {
+            continue;
+        };
+        let files: Vec<FileInfo> = rd.flatten().filter_map(|e| file_info(&e.path())).collect();
+
+        let mut models_by_size: HashMap<u64, Vec<&FileInfo>> = HashMap::new();
+        for f in &files {
+            let ext = ext_of(&f.path);
+            let name = f.path.file_name().unwrap_or_default().to_string_lossy();
+            let age = now.saturating_sub(f.modified_ms);
+            if ext == "mph" {
+                models_by_size.entry(f.size).or_default().push(f);
+            } else if TEMP_EXTS.contains(&ext.as_str()) || name.starts_with("~$") {
+                if age > STALE_TEMP_AGE_MS && !held_by_running_job(&f.path, &running) {
+                    item(f, "stale_temp", "残留的临时文件".to_string(), proj);
+                }
+            } else if RESULT_EXTS.contains(&ext.as_str()) && age > OLD_RESULT_AGE_MS {
+                item(
+                    f,
+                    "old_sweep_result",
+                    format!("{} 天前导出的结果", age / DAY_MS),
+                    proj,
+                );
+            }
+        }
+
+        // 大小相同的模型再比较哈希，内容相同时保留最新一份
+        for group in models_by_size.values().filter(|g| g.len() > 1) {
+            let mut by_hash: HashMap<String, Vec<&FileInfo>> = HashMap::new();
+            for f in group {
+                if let Ok(h) = sha256_file(&f.path) {
+                    by_hash.entry(h).or_default().push(f);
+                }
+            }
+            for mut same in by_hash.into_values().filter(|g| g.len() > 1) {
+                same.sort_by_key(|f| std::cmp::Reverse(f.modified_ms));
+                let keep = same[0].path.to_string_lossy().into_owned();
+                for f in &same[1..] {
+                    item(f, "duplicate_model", format!("与 {} 内容相同", keep), proj);
+                }
+            }
+        }
+    }
+
+    // 系统临时目录中 bridge 遗留的文件（日志除外）
+    if project.is_none() {
+        if let Ok(rd) = std::fs::read_dir(std::env::temp_dir()) {
+            for info in rd.flatten().filter_map(|e| {
+                let name = e.file_name().to_string_lossy().into_owned();
+                if name.starts_with("mph-agent") && !name.ends_with(".log") {
+                    file_info(&e.path())
+                } else {
+                    None
+                }
+            }) {
+                if now.saturating_sub(info.modified_ms) > DAY_MS {
+                    item(
+                        &info,
+                        "stale_temp",
+                        "bridge 遗留的临时文件".to_string(),
+                        &None,
+                    );
+                }
+            }
+        }
+    }
+
+    let mut seen = BTreeSet::new();
+    out.retain(|i| seen.insert(i.path.clone()));
+    out.sort_by_key(|i| std::cmp::Reverse(i.bytes));
+    out
+}
+
+#[tauri::command]
+pub async fn storage_usage(app: AppHandle) -> Result<Vec<ProjectUsage>, String> {
+    guarded("storage_usage", async move {
+        tauri::async_runtime::spawn_blocking(move || project_usage(&app))
+            .await
+            .map_err(|e| e.to_string())
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn storage_get_config(
+    storage: tauri::State<'_, StorageManager>,
+) -> Result<StorageConfig, String> {
+    guarded("storage_get_config", async move { Ok(storage.config()) }).await
+}
+
+/// `project` 为空时设置默认配额；`quota_mb` 为空表示取消限制
+#[tauri::command]
+pub async fn storage_set_quota(
+    storage: tauri::State<'_, StorageManager>,
+    project: Option<String>,
+    quota_mb: Option<u64>,
+) -> Result<(), String> {
+    guarded("storage_set_quota", async move {
+        storage.set_quota(project.as_deref(), quota_mb)
+    })
+    .await
+}
+
+/// 设置失败/中止任务的输出处理方式：keep / quarantine / delete
+#[tauri::command]
+pub async fn storage_set_failed_outputs(
+    storage: tauri::State<'_, StorageManager>,
+    mode: FailedOutputs,
+) -> Result<(), String> {
+    guarded("storage_set_failed_outputs", async move {
+        storage.set_failed_outputs(mode)
+    })
+    .await
+}
+
+/// 删除已结束任务的工作目录及其中登记的产物
+#[tauri::command]
+pub async fn job_remove_work_dir(app: AppHandle, request_id: String) -> Result<Value, String> {
+    guarded("job_remove_work_dir", async move {
+        let job = app
+            .state::<JobRegistry>()
+            .get(&request_id)
+            .ok_or_else(|| format!("任务不存在: {}", request_id))?;
+        if !job.status.is_finished() {
+            return Err(format!("任务 {} 尚未结束", request_id));
+        }
+        let removed = tauri::async_runtime::spawn_blocking(move || remove_work_dir(&app, &job))
+            .await
+            .map_err(|e| e.to_string())??;
+        Ok(serde_json::json!({ "request_id": request_id, "removed": removed }))
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn cleanup_suggestions(
+    app: AppHandle,
+    project: Option<String>,
+    limit: Option<usize>,
+) -> Result<Vec<CleanupItem>, String> {
+    guarded("cleanup_suggestions", async move {
+        let mut items = tauri::async_runtime::spawn_blocking(move || {
+            collect_suggestions(&app, project.as_deref())
+        })
+        .await
+        .map_err(|e| e.to_string())?;
+        items.truncate(limit.unwrap_or(100));
+        Ok(items)
+    })
+    .await
+}
@@ -0,0 +1,73 @@
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+/// 读取 JSON 文件；文件不存在或解析失败时返回默认值
+pub fn load_json<T: DeserializeOwned + Default>(path: &Path) -> T {
+    std::fs::read_to_string(path)
+        .ok()
+        .and_then(|s| serde_json::from_str(&s).ok())
+        .unwrap_or_default()
+}
+
+/// 先写临时文件再 rename，避免写到一半崩溃留下损坏的 JSON
+pub fn save_json<T: Serialize>(path: &Path, value: &T) -> Result<(), String> {
+    let text = serde_json::to_string_pretty(value).map_err(|e| e.to_string())?;
+    save_text(path, &text)
+}
+
+/// 同 save_json，写入的是文本原文
+pub fn save_text(path: &Path, text: &str) -> Result<(), String> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| format!("创建目录失败: {}", e))?;
+    }
+    let tmp = tmp_path(path);
+    std::fs::write(&tmp, text).map_err(|e| format!("写入 {} 失败: {}", tmp.display(), e))?;
+    std::fs::rename(&tmp, path).map_err(|e| format!("写入 {} 失败: {}", path.display(), e))
+}
+
+/// 打开（必要时创建）SQLite 数据库并建表；文件无法打开或已损坏时删除重建，
+/// 仍失败则退回内存数据库，本次运行照常可用，只是不落盘
+pub fn open_db(path: &Path, schema: &str) -> rusqlite::Connection {
+    let open = || -> rusqlite::Result<rusqlite::Connection> {
+        if let Some(dir) = path.parent() {
+            let _ = std::fs::create_dir_all(dir);
+        }
+        let conn = rusqlite::Connection::open(path)?;
+        conn.execute_batch(schema)?;
+        Ok(conn)
+    };
+    let conn = open().or_else(|e| {
+        eprintln!("Warning: 数据库 {} 不可用，重建: {}", path.display(), e);
+        let _ = std::fs::remove_file(path);
+        open()
+    });
+    conn.unwrap_or_else(|e| {
+        eprintln!(
+            "Warning: 重建数据库 {} 失败，本次改用内存数据库: {}",
+            path.display(),
+            e
+        );
+        memory_db(schema)
+    })
+}
+
+/// 内存中的 SQLite 数据库（未配置数据目录时与测试中使用）
+pub fn memory_db(schema: &str) -> rusqlite::Connection {
+    let conn = rusqlite::Connection::open_in_memory().expect("打开内存数据库失败");
+    conn.execute_batch(schema).expect("内存数据库建表失败");
+    conn
+}
+
+fn tmp_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".tmp");
+    path.with_file_name(name)
+}
+
+pub fn now_ms() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
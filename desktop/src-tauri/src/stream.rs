@@ -0,0 +1,232 @@
+use crate::event_routing::emit_job_event;
+use crate::panics::guarded;
+use serde_json::Value;
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+use tauri::AppHandle;
+use tokio::sync::{mpsc, watch};
+use tokio::task::JoinHandle;
+use tokio::time::Instant;
+
+/// 流式请求的事件发射控制表：request_id -> 限速配置（每秒最多发射的事件数）
+#[derive(Default)]
+pub struct StreamRegistry {
+    streams: std::sync::Mutex<HashMap<String, watch::Sender<Option<f64>>>>,
+}
+
+static NEXT_STREAM_ID: AtomicU64 = AtomicU64::new(1);
+
+pub fn next_request_id() -> String {
+    format!("stream-{}", NEXT_STREAM_ID.fetch_add(1, Ordering::Relaxed))
+}
+
+/// 发射队列中的条目：普通事件，或检测到的 bridge 序号缺口
+enum Outgoing {
+    Event(Value),
+    Gap { expected: u64, received: u64 },
+}
+
+/// 单个流的事件发射器：读循环只负责 push，实际 emit 在独立任务中完成，
+/// 这样前端限速时子进程 stdout 仍被持续读取，管道不会阻塞求解器。
+///
+/// 发往前端的每条事件都带 (request_id, seq)，seq 在单个流内严格递增。
+/// bridge 为每条事件附带请求内递增的 `seq`（见 tui_bridge `_emit_event`），据此丢弃
+/// 重连/重放造成的重复事件，并以 `stream-gap` 事件显式报告缺失区间，而不是静默乱序。
+pub struct StreamEmitter {
+    request_id: String,
+    tx: mpsc::UnboundedSender<Outgoing>,
+    task: JoinHandle<()>,
+    last_bridge_seq: Option<u64>,
+}
+
+impl StreamRegistry {
+    pub fn start(&self, app: AppHandle, request_id: String) -> StreamEmitter {
+        let (rate_tx, rate_rx) = watch::channel(None);
+        self.streams
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(request_id.clone(), rate_tx);
+        let (tx, rx) = mpsc::unbounded_channel();
+        let task = tokio::spawn(run_emitter(app, request_id.clone(), rx, rate_rx));
+        StreamEmitter {
+            request_id,
+            tx,
+            task,
+            last_bridge_seq: None,
+        }
+    }
+
+    pub fn set_rate(
+        &self,
+        request_id: &str,
+        max_events_per_sec: Option<f64>,
+    ) -> Result<(), String> {
+        let rate = max_events_per_sec.filter(|r| r.is_finite() && *r > 0.0);
+        let guard = self.streams.lock().unwrap_or_else(|e| e.into_inner());
+        let tx = guard
+            .get(request_id)
+            .ok_or_else(|| format!("未找到流式请求: {}", request_id))?;
+        tx.send_replace(rate);
+        Ok(())
+    }
+
+    fn remove(&self, request_id: &str) {
+        self.streams
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(request_id);
+    }
+}
+
+impl StreamEmitter {
+    pub fn push(&mut self, mut event: Value) {
+        if let Some(bridge_seq) = event.get("seq").and_then(|v| v.as_u64()) {
+            match self.last_bridge_seq {
+                Some(last) if bridge_seq <= last => return,
+                Some(last) if bridge_seq > last + 1 => {
+                    let _ = self.tx.send(Outgoing::Gap {
+                        expected: last + 1,
+                        received: bridge_seq,
+                    });
+                }
+                _ => {}
+            }
+            self.last_bridge_seq = Some(bridge_seq);
+        }
+        if let Some(obj) = event.as_object_mut() {
+            if let Some(bridge_seq) = obj.remove("seq") {
+                obj.insert("bridge_seq".into(), bridge_seq);
+            }
+            obj.insert("request_id".into(), Value::String(self.request_id.clone()));
+        }
+        let _ = self.tx.send(Outgoing::Event(event));
+    }
+
+    /// 关闭通道并等待积压事件全部发出，保证事件先于最终响应到达前端
+    pub async fn finish(self, registry: &StreamRegistry) {
+        registry.remove(&self.request_id);
+        drop(self.tx);
+        let _ = self.task.await;
+    }
+}
+
+fn emit_interval(rate_rx: &watch::Receiver<Option<f64>>) -> Option<Duration> {
+    (*rate_rx.borrow()).map(|r| Duration::from_secs_f64(1.0 / r))
+}
+
+/// 积压时合并相邻的同阶段 llm_stream_chunk，减少前端需要渲染的事件数
+fn push_coalesced(pending: &mut VecDeque<Outgoing>, item: Outgoing) {
+    if let (Some(Outgoing::Event(last)), Outgoing::Event(event)) = (pending.back_mut(), &item) {
+        if is_mergeable_chunk(last, event) {
+            let extra = event["data"]["chunk"].as_str().unwrap_or_default();
+            if let Some(Value::String(chunk)) =
+                last.get_mut("data").and_then(|d| d.get_mut("chunk"))
+            {
+                chunk.push_str(extra);
+                return;
+            }
+        }
+    }
+    pending.push_back(item);
+}
+
+pub(crate) fn is_mergeable_chunk(a: &Value, b: &Value) -> bool {
+    let is_chunk = |v: &Value| {
+        v.get("type").and_then(|t| t.as_str()) == Some("llm_stream_chunk")
+            && v["data"]["chunk"].is_string()
+    };
+    is_chunk(a)
+        && is_chunk(b)
+        && a["data"]["phase"] == b["data"]["phase"]
+        && a.get("iteration") == b.get("iteration")
+}
+
+fn emit_outgoing(app: &AppHandle, request_id: &str, seq: u64, item: Outgoing) {
+    match item {
+        Outgoing::Event(mut event) => {
+            if let Some(obj) = event.as_object_mut() {
+                obj.insert("seq".into(), Value::from(seq));
+            }
+            // 逐 token 的输出是高频事件，最小化的窗口不必接收
+            let heavy = event["type"].as_str() == Some("llm_stream_chunk");
+            emit_job_event(app, "bridge-event", request_id, &event, heavy);
+        }
+        Outgoing::Gap { expected, received } => {
+            emit_job_event(
+                app,
+                "stream-gap",
+                request_id,
+                serde_json::json!({
+                    "request_id": request_id,
+                    "seq": seq,
+                    "expected_bridge_seq": expected,
+                    "received_bridge_seq": received,
+                    "missing": received - expected,
+                }),
+                false,
+            );
+        }
+    }
+}
+
+async fn run_emitter(
+    app: AppHandle,
+    request_id: String,
+    mut rx: mpsc::UnboundedReceiver<Outgoing>,
+    rate_rx: watch::Receiver<Option<f64>>,
+) {
+    let mut pending: VecDeque<Outgoing> = VecDeque::new();
+    let mut last_emit: Option<Instant> = None;
+    let mut seq: u64 = 0;
+
+    loop {
+        let interval = emit_interval(&rate_rx);
+        let mut next_slot = None;
+        while !pending.is_empty() {
+            if let (Some(iv), Some(last)) = (interval, last_emit) {
+                if Instant::now() < last + iv {
+                    next_slot = Some(last + iv);
+                    break;
+                }
+            }
+            if let Some(item) = pending.pop_front() {
+                seq += 1;
+                emit_outgoing(&app, &request_id, seq, item);
+                last_emit = Some(Instant::now());
+            }
+        }
+
+        let received = match next_slot {
+            None => rx.recv().await,
+            Some(at) => match tokio::time::timeout_at(at, rx.recv()).await {
+                Ok(v) => v,
+                Err(_) => continue,
+            },
+        };
+
+        match received {
+            Some(item) => push_coalesced(&mut pending, item),
+            None => {
+                // 流已结束：不再限速，把剩余事件一次性发完
+                for item in pending.drain(..) {
+                    seq += 1;
+                    emit_outgoing(&app, &request_id, seq, item);
+                }
+                break;
+            }
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn stream_set_rate(
+    registry: tauri::State<'_, StreamRegistry>,
+    request_id: String,
+    max_events_per_sec: Option<f64>,
+) -> Result<(), String> {
+    guarded("stream_set_rate", async move {
+        registry.set_rate(&request_id, max_events_per_sec)
+    })
+    .await
+}
@@ -0,0 +1,447 @@
+use crate::panics::guarded;
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering as CmpOrdering;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+/// 单次最多返回的行数
+const MAX_PAGE_ROWS: usize = 2000;
+/// 每个表缓存的排序/过滤视图数
+const MAX_CACHED_VIEWS: usize = 4;
+/// 同时打开的表数，超出时关闭最早打开的
+const MAX_OPEN_TABLES: usize = 16;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Delimiter {
+    Char(u8),
+    /// COMSOL 导出的 .txt 用连续空白分隔
+    Whitespace,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Deserialize)]
+pub struct TableSort {
+    pub column: usize,
+    #[serde(default)]
+    pub desc: bool,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Deserialize)]
+pub struct TableFilter {
+    /// 为空时匹配任意列
+    #[serde(default)]
+    pub column: Option<usize>,
+    /// 不区分大小写的子串匹配
+    pub query: String,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct TableInfo {
+    pub handle: u64,
+    pub path: String,
+    pub columns: Vec<String>,
+    pub rows: usize,
+    /// COMSOL 导出文件开头以 % 开头的元数据行
+    pub metadata: Vec<String>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct TablePage {
+    pub offset: usize,
+    /// 过滤后的总行数
+    pub total: usize,
+    pub rows: Vec<Vec<String>>,
+}
+
+type ViewKey = (Option<TableSort>, Option<TableFilter>);
+
+/// 行索引：只记录每行的字节偏移，按需随机读取，百万行级文件也只占几 MB
+struct TableIndex {
+    path: PathBuf,
+    delimiter: Delimiter,
+    columns: Vec<String>,
+    metadata: Vec<String>,
+    offsets: Vec<u64>,
+    views: Mutex<Vec<(ViewKey, Arc<Vec<u32>>)>>,
+}
+
+/// 已打开的表，handle 在本次运行内有效
+#[derive(Default)]
+pub struct TableRegistry {
+    next: AtomicU64,
+    tables: Mutex<Vec<(u64, Arc<TableIndex>)>>,
+}
+
+impl TableRegistry {
+    fn insert(&self, table: TableIndex) -> u64 {
+        let handle = self.next.fetch_add(1, Ordering::SeqCst) + 1;
+        let mut tables = self.tables.lock().unwrap_or_else(|e| e.into_inner());
+        if tables.len() >= MAX_OPEN_TABLES {
+            tables.remove(0);
+        }
+        tables.push((handle, Arc::new(table)));
+        handle
+    }
+
+    fn get(&self, handle: u64) -> Result<Arc<TableIndex>, String> {
+        self.tables
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .iter()
+            .find(|(h, _)| *h == handle)
+            .map(|(_, t)| t.clone())
+            .ok_or_else(|| format!("表已关闭或不存在: {}", handle))
+    }
+
+    fn remove(&self, handle: u64) {
+        self.tables
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .retain(|(h, _)| *h != handle);
+    }
+}
+
+fn detect_delimiter(line: &str) -> Delimiter {
+    [b',', b';', b'\t']
+        .into_iter()
+        .map(|d| (d, line.bytes().filter(|b| *b == d).count()))
+        .filter(|(_, n)| *n > 0)
+        .max_by_key(|(_, n)| *n)
+        .map(|(d, _)| Delimiter::Char(d))
+        .unwrap_or(Delimiter::Whitespace)
+}
+
+/// 按分隔符拆分一行；支持双引号包裹与 "" 转义（不支持字段内换行）
+fn split_line(line: &str, delimiter: Delimiter) -> Vec<String> {
+    let d = match delimiter {
+        Delimiter::Whitespace => return line.split_whitespace().map(String::from).collect(),
+        Delimiter::Char(d) => d as char,
+    };
+    let mut out = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            c if c == d && !quoted => out.push(std::mem::take(&mut field)),
+            c => field.push(c),
+        }
+    }
+    out.push(field);
+    out.into_iter().map(|f| f.trim().to_string()).collect()
+}
+
+fn trim_eol(line: &str) -> &str {
+    line.trim_end_matches(['\r', '\n'])
+}
+
+fn looks_numeric(fields: &[String]) -> bool {
+    !fields.is_empty() && fields.iter().all(|f| f.parse::<f64>().is_ok())
+}
+
+fn build_index(path: &Path) -> Result<TableIndex, String> {
+    let file = File::open(path).map_err(|e| format!("打开表格失败: {}", e))?;
+    let mut reader = BufReader::new(file);
+    let mut offsets = Vec::new();
+    let mut metadata = Vec::new();
+    let mut columns: Option<Vec<String>> = None;
+    let mut delimiter = None;
+    let mut pos = 0u64;
+    let mut buf = Vec::new();
+    loop {
+        buf.clear();
+        let n = reader
+            .read_until(b'\n', &mut buf)
+            .map_err(|e| format!("读取表格失败: {}", e))?;
+        if n == 0 {
+            break;
+        }
+        let start = pos;
+        pos += n as u64;
+        let line = String::from_utf8_lossy(&buf);
+        let line = trim_eol(&line);
+        if line.trim().is_empty() {
+            continue;
+        }
+        if offsets.is_empty() {
+            if let Some(meta) = line.strip_prefix('%') {
+                metadata.push(meta.trim().to_string());
+                continue;
+            }
+            if columns.is_none() {
+                let d = *delimiter.get_or_insert_with(|| detect_delimiter(line));
+                let fields = split_line(line, d);
+                if !looks_numeric(&fields) {
+                    columns = Some(fields);
+                    continue;
+                }
+            }
+        }
+        if delimiter.is_none() {
+            delimiter = Some(detect_delimiter(line));
+        }
+        offsets.push(start);
+    }
+    let delimiter = delimiter.unwrap_or(Delimiter::Char(b','));
+    // COMSOL 把列名写在最后一行 % 注释里
+    let columns = match columns {
+        Some(c) => c,
+        None => metadata
+            .last()
+            .map(|m| split_line(m, delimiter))
+            .unwrap_or_default(),
+    };
+    Ok(TableIndex {
+        path: path.to_path_buf(),
+        delimiter,
+        columns,
+        metadata,
+        offsets,
+        views: Mutex::new(Vec::new()),
+    })
+}
+
+impl TableIndex {
+    fn read_row(&self, file: &mut BufReader<File>, row: usize) -> Result<Vec<String>, String> {
+        let offset = self.offsets[row];
+        file.seek(SeekFrom::Start(offset))
+            .map_err(|e| e.to_string())?;
+        let mut buf = Vec::new();
+        file.read_until(b'\n', &mut buf)
+            .map_err(|e| e.to_string())?;
+        Ok(split_line(
+            trim_eol(&String::from_utf8_lossy(&buf)),
+            self.delimiter,
+        ))
+    }
+
+    /// 顺序扫描全部数据行
+    fn scan(&self, mut f: impl FnMut(usize, Vec<String>)) -> Result<(), String> {
+        let mut file = File::open(&self.path).map_err(|e| e.to_string())?;
+        let Some(&first) = self.offsets.first() else {
+            return Ok(());
+        };
+        file.seek(SeekFrom::Start(first))
+            .map_err(|e| e.to_string())?;
+        let mut reader = BufReader::new(file);
+        let mut buf = Vec::new();
+        let mut row = 0;
+        let mut pos = first;
+        while row < self.offsets.len() {
+            buf.clear();
+            let n = reader
+                .read_until(b'\n', &mut buf)
+                .map_err(|e| e.to_string())?;
+            if n == 0 {
+                break;
+            }
+            let start = pos;
+            pos += n as u64;
+            if start != self.offsets[row] {
+                continue;
+            }
+            f(
+                row,
+                split_line(trim_eol(&String::from_utf8_lossy(&buf)), self.delimiter),
+            );
+            row += 1;
+        }
+        Ok(())
+    }
+
+    /// 计算（或取缓存的）排序/过滤后的行号列表
+    fn view(&self, key: &ViewKey) -> Result<Arc<Vec<u32>>, String> {
+        if let Some((_, v)) = self
+            .views
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .iter()
+            .find(|(k, _)| k == key)
+        {
+            return Ok(v.clone());
+        }
+        let (sort, filter) = key;
+        let query = filter.as_ref().map(|f| f.query.to_lowercase());
+        let mut rows: Vec<u32> = Vec::new();
+        let mut sort_keys: Vec<(Option<f64>, String)> = Vec::new();
+        self.scan(|row, fields| {
+            if let (Some(f), Some(q)) = (filter, &query) {
+                let hit = match f.column {
+                    Some(c) => fields.get(c).is_some_and(|v| v.to_lowercase().contains(q)),
+                    None => fields.iter().any(|v| v.to_lowercase().contains(q)),
+                };
+                if !hit {
+                    return;
+                }
+            }
+            if let Some(s) = sort {
+                let v = fields.get(s.column).cloned().unwrap_or_default();
+                sort_keys.push((v.parse::<f64>().ok(), v));
+            }
+            rows.push(row as u32);
+        })?;
+        if let Some(s) = sort {
+            let mut order: Vec<usize> = (0..rows.len()).collect();
+            // 数值优先按数值比较，其余按文本；非数值排在数值之后
+            order.sort_by(|&a, &b| {
+                let (ka, kb) = (&sort_keys[a], &sort_keys[b]);
+                let ord = match (ka.0, kb.0) {
+                    (Some(x), Some(y)) => x.partial_cmp(&y).unwrap_or(CmpOrdering::Equal),
+                    (Some(_), None) => CmpOrdering::Less,
+                    (None, Some(_)) => CmpOrdering::Greater,
+                    (None, None) => ka.1.cmp(&kb.1),
+                };
+                if s.desc {
+                    ord.reverse()
+                } else {
+                    ord
+                }
+            });
+            rows = order.into_iter().map(|i| rows[i]).collect();
+        }
+        let rows = Arc::new(rows);
+        let mut views = self.views.lock().unwrap_or_else(|e| e.into_inner());
+        if views.len() >= MAX_CACHED_VIEWS {
+            views.remove(0);
+        }
+        views.push((key.clone(), rows.clone()));
+        Ok(rows)
+    }
+
+    fn page(
+        &self,
+        offset: usize,
+        count: usize,
+        sort: Option<TableSort>,
+        filter: Option<TableFilter>,
+    ) -> Result<TablePage, String> {
+        let count = count.min(MAX_PAGE_ROWS);
+        let mut file = BufReader::new(File::open(&self.path).map_err(|e| e.to_string())?);
+        let filter = filter.filter(|f| !f.query.trim().is_empty());
+        if sort.is_none() && filter.is_none() {
+            let end = (offset + count).min(self.offsets.len());
+            let rows = (offset.min(end)..end)
+                .map(|r| self.read_row(&mut file, r))
+                .collect::<Result<_, _>>()?;
+            return Ok(TablePage {
+                offset,
+                total: self.offsets.len(),
+                rows,
+            });
+        }
+        let view = self.view(&(sort, filter))?;
+        let rows = view
+            .iter()
+            .skip(offset)
+            .take(count)
+            .map(|&r| self.read_row(&mut file, r as usize))
+            .collect::<Result<_, _>>()?;
+        Ok(TablePage {
+            offset,
+            total: view.len(),
+            rows,
+        })
+    }
+}
+
+/// 结果表的关键标量：取最后一行（全局计算只有一行；扫描/瞬态为最后一个参数或时刻）
+/// 中可解析为数值的列，键为列名，无列名时为 `col<N>`
+pub(crate) fn last_row_scalars(path: &Path) -> Result<Vec<(String, f64)>, String> {
+    let index = build_index(path)?;
+    let Some(last) = index.offsets.len().checked_sub(1) else {
+        return Ok(Vec::new());
+    };
+    let file = File::open(path).map_err(|e| e.to_string())?;
+    let row = index.read_row(&mut BufReader::new(file), last)?;
+    Ok(row
+        .iter()
+        .enumerate()
+        .filter_map(|(i, v)| {
+            let v = v.parse::<f64>().ok()?;
+            let name = index
+                .columns
+                .get(i)
+                .filter(|c| !c.is_empty())
+                .cloned()
+                .unwrap_or_else(|| format!("col{}", i + 1));
+            Some((name, v))
+        })
+        .collect())
+}
+
+/// 表格概要（列名、行数、元数据），供产物后处理预先建立索引
+pub(crate) fn table_summary(path: &Path) -> Result<serde_json::Value, String> {
+    let index = build_index(path)?;
+    Ok(serde_json::json!({
+        "columns": index.columns,
+        "rows": index.offsets.len(),
+        "metadata": index.metadata,
+    }))
+}
+
+/// 打开 CSV/TXT 结果表并建立行索引，返回 handle 与列信息
+#[tauri::command]
+pub async fn table_open(
+    tables: tauri::State<'_, TableRegistry>,
+    path: String,
+) -> Result<TableInfo, String> {
+    guarded("table_open", async move {
+        let path = PathBuf::from(path.trim());
+        if !path.is_file() {
+            return Err("文件不存在".to_string());
+        }
+        let index = tauri::async_runtime::spawn_blocking(move || build_index(&path))
+            .await
+            .map_err(|e| e.to_string())??;
+        let info = TableInfo {
+            handle: 0,
+            path: index.path.to_string_lossy().into_owned(),
+            columns: index.columns.clone(),
+            rows: index.offsets.len(),
+            metadata: index.metadata.clone(),
+        };
+        Ok(TableInfo {
+            handle: tables.insert(index),
+            ..info
+        })
+    })
+    .await
+}
+
+/// 分页读取；带排序或过滤时首次会扫描全表，结果按条件缓存
+#[tauri::command]
+pub async fn table_rows(
+    tables: tauri::State<'_, TableRegistry>,
+    handle: u64,
+    offset: usize,
+    count: usize,
+    sort: Option<TableSort>,
+    filter: Option<TableFilter>,
+) -> Result<TablePage, String> {
+    guarded("table_rows", async move {
+        let table = tables.get(handle)?;
+        tauri::async_runtime::spawn_blocking(move || table.page(offset, count, sort, filter))
+            .await
+            .map_err(|e| e.to_string())?
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn table_close(
+    tables: tauri::State<'_, TableRegistry>,
+    handle: u64,
+) -> Result<(), String> {
+    guarded("table_close", async move {
+        tables.remove(handle);
+        Ok(())
+    })
+    .await
+}
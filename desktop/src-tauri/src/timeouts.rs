@@ -0,0 +1,223 @@
+use crate::bridge_error::BridgeError;
+use crate::panics::guarded;
+use crate::store::{load_json, save_json};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// 非流式请求的默认等待时间
+pub const DEFAULT_TIMEOUT_SECS: u64 = 120;
+const MAX_TIMEOUT_SECS: u64 = 24 * 3600;
+/// 请求中指定本次等待时间的字段，发送前移除
+const PAYLOAD_KEY: &str = "_timeout_secs";
+/// 流式任务的总时长上限与两条事件之间的最长静默
+const DEFAULT_STREAM_TOTAL_SECS: u64 = 24 * 3600;
+const DEFAULT_STREAM_IDLE_SECS: u64 = 30 * 60;
+/// 超过该时长没有任何输出（含心跳）时向前端发出 bridge-stalled
+const DEFAULT_STREAM_STALL_SECS: u64 = 60;
+const MAX_STREAM_TOTAL_SECS: u64 = 7 * 24 * 3600;
+const STREAM_TOTAL_KEY: &str = "_stream_total_secs";
+const STREAM_IDLE_KEY: &str = "_stream_idle_secs";
+const STREAM_STALL_KEY: &str = "_stream_stall_secs";
+/// 随流式请求发送给 bridge 的心跳间隔（秒）
+pub const HEARTBEAT_KEY: &str = "_heartbeat_secs";
+
+fn default_secs() -> u64 {
+    DEFAULT_TIMEOUT_SECS
+}
+
+fn default_stream_total_secs() -> u64 {
+    DEFAULT_STREAM_TOTAL_SECS
+}
+
+fn default_stream_idle_secs() -> u64 {
+    DEFAULT_STREAM_IDLE_SECS
+}
+
+fn default_stream_stall_secs() -> u64 {
+    DEFAULT_STREAM_STALL_SECS
+}
+
+/// 流式任务的两个时钟：总时长与静默时长分别计时，互不影响
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StreamTimeouts {
+    #[serde(default = "default_stream_total_secs")]
+    pub total_secs: u64,
+    #[serde(default = "default_stream_idle_secs")]
+    pub idle_secs: u64,
+    /// 判定为「看起来卡住」的静默时长；bridge 按其三分之一的间隔发送心跳
+    #[serde(default = "default_stream_stall_secs")]
+    pub stall_secs: u64,
+}
+
+impl Default for StreamTimeouts {
+    fn default() -> Self {
+        Self {
+            total_secs: DEFAULT_STREAM_TOTAL_SECS,
+            idle_secs: DEFAULT_STREAM_IDLE_SECS,
+            stall_secs: DEFAULT_STREAM_STALL_SECS,
+        }
+    }
+}
+
+impl StreamTimeouts {
+    fn clamped(self) -> Self {
+        Self {
+            total_secs: self.total_secs.clamp(1, MAX_STREAM_TOTAL_SECS),
+            idle_secs: clamp(self.idle_secs),
+            stall_secs: clamp(self.stall_secs),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct StreamBudget {
+    pub total: Duration,
+    pub idle: Duration,
+    pub stall: Duration,
+}
+
+impl StreamBudget {
+    /// 要求 bridge 发送心跳的间隔：静默判定时长的三分之一，至少 1 秒
+    pub fn heartbeat_secs(&self) -> u64 {
+        (self.stall.as_secs() / 3).max(1)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TimeoutConfig {
+    #[serde(default = "default_secs")]
+    pub default_secs: u64,
+    /// 按命令覆盖，如 `{"export": 600}`
+    #[serde(default)]
+    pub per_cmd: BTreeMap<String, u64>,
+    /// 流式任务（bridge_send_stream）不受上面的请求超时约束
+    #[serde(default)]
+    pub stream: StreamTimeouts,
+}
+
+impl Default for TimeoutConfig {
+    fn default() -> Self {
+        Self {
+            default_secs: DEFAULT_TIMEOUT_SECS,
+            per_cmd: BTreeMap::new(),
+            stream: StreamTimeouts::default(),
+        }
+    }
+}
+
+fn clamp(secs: u64) -> u64 {
+    secs.clamp(1, MAX_TIMEOUT_SECS)
+}
+
+/// bridge_send 等待响应的时间预算
+#[derive(Default)]
+pub struct RequestTimeouts {
+    path: Option<PathBuf>,
+    config: Mutex<TimeoutConfig>,
+}
+
+impl RequestTimeouts {
+    pub fn load(path: PathBuf) -> Self {
+        let config: TimeoutConfig = load_json(&path);
+        Self {
+            path: Some(path),
+            config: Mutex::new(config),
+        }
+    }
+
+    pub fn get(&self) -> TimeoutConfig {
+        self.config
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    pub(crate) fn set(&self, config: TimeoutConfig) -> Result<(), String> {
+        *self.config.lock().unwrap_or_else(|e| e.into_inner()) = config.clone();
+        match self.path {
+            Some(ref p) => save_json(p, &config),
+            None => Ok(()),
+        }
+    }
+
+    /// 本次请求的等待时间：调用参数 > 请求中的 `_timeout_secs` > 按命令配置 > 全局默认。
+    /// 同时从请求中移除 `_timeout_secs`，不发给 bridge
+    pub fn resolve(&self, cmd: &str, explicit: Option<u64>, payload: &mut Value) -> Duration {
+        let from_payload = payload
+            .as_object_mut()
+            .and_then(|obj| obj.remove(PAYLOAD_KEY))
+            .and_then(|v| v.as_u64());
+        let secs = explicit.or(from_payload).unwrap_or_else(|| {
+            let config = self.get();
+            config
+                .per_cmd
+                .get(cmd)
+                .copied()
+                .unwrap_or(config.default_secs)
+        });
+        Duration::from_secs(clamp(secs))
+    }
+
+    /// 流式任务的时间预算：请求中的 `_stream_total_secs` / `_stream_idle_secs` /
+    /// `_stream_stall_secs` 优先于配置，
+    /// 并从请求中移除
+    pub fn resolve_stream(&self, payload: &mut Value) -> StreamBudget {
+        let mut take = |key: &str| {
+            payload
+                .as_object_mut()
+                .and_then(|obj| obj.remove(key))
+                .and_then(|v| v.as_u64())
+        };
+        let (total, idle, stall) = (
+            take(STREAM_TOTAL_KEY),
+            take(STREAM_IDLE_KEY),
+            take(STREAM_STALL_KEY),
+        );
+        let config = self.get().stream;
+        let stream = StreamTimeouts {
+            total_secs: total.unwrap_or(config.total_secs),
+            idle_secs: idle.unwrap_or(config.idle_secs),
+            stall_secs: stall.unwrap_or(config.stall_secs),
+        }
+        .clamped();
+        StreamBudget {
+            total: Duration::from_secs(stream.total_secs),
+            idle: Duration::from_secs(stream.idle_secs),
+            stall: Duration::from_secs(stream.stall_secs),
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn bridge_timeouts_get(
+    timeouts: tauri::State<'_, RequestTimeouts>,
+) -> Result<TimeoutConfig, BridgeError> {
+    guarded("bridge_timeouts_get", async move { Ok(timeouts.get()) }).await
+}
+
+/// 设置 bridge_send 的默认等待秒数与按命令覆盖（1 秒 ~ 24 小时），以及流式任务的总时长、静默上限与卡住判定时长
+#[tauri::command]
+pub async fn bridge_timeouts_set(
+    timeouts: tauri::State<'_, RequestTimeouts>,
+    config: TimeoutConfig,
+) -> Result<TimeoutConfig, BridgeError> {
+    guarded("bridge_timeouts_set", async move {
+        let config = TimeoutConfig {
+            default_secs: clamp(config.default_secs),
+            per_cmd: config
+                .per_cmd
+                .into_iter()
+                .filter(|(cmd, _)| !cmd.trim().is_empty())
+                .map(|(cmd, secs)| (cmd, clamp(secs)))
+                .collect(),
+            stream: config.stream.clamped(),
+        };
+        timeouts.set(config.clone())?;
+        Ok(config)
+    })
+    .await
+}
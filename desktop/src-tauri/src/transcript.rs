@@ -0,0 +1,226 @@
+use crate::debug_console::Direction;
+use crate::dispatcher::is_final;
+use crate::logs::LOGS_DIR;
+use crate::panics::guarded;
+use crate::repro::redacted_line;
+use crate::sandbox::check_path;
+use crate::store::now_ms;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager};
+
+/// 录制文件放在 app data 的 logs/transcripts 下，随日志保留策略压缩与清理
+pub const TRANSCRIPTS_DIR: &str = "transcripts";
+const EXT: &str = "ndjson";
+/// 单个录制文件的大小上限，超出后停止写入并标记截断
+const MAX_BYTES: u64 = 64 * 1024 * 1024;
+
+#[derive(Clone, Debug, Serialize)]
+pub struct RecordingInfo {
+    pub id: String,
+    pub path: String,
+    pub started_at: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stopped_at: Option<u64>,
+    pub entries: u64,
+    pub bytes: u64,
+    pub truncated: bool,
+}
+
+struct Recording {
+    info: RecordingInfo,
+    file: std::fs::File,
+    /// 已发出、尚未收到最终响应的请求：`_id` → 发出时间
+    pending: HashMap<String, u64>,
+}
+
+impl Recording {
+    fn write(&mut self, entry: &Value) {
+        let mut line = entry.to_string();
+        line.push('\n');
+        if self.info.bytes + line.len() as u64 > MAX_BYTES {
+            self.info.truncated = true;
+            line = serde_json::json!({ "ts": now_ms(), "kind": "truncated" }).to_string() + "\n";
+        }
+        match self.file.write_all(line.as_bytes()) {
+            Ok(()) => {
+                self.info.entries += 1;
+                self.info.bytes += line.len() as u64;
+            }
+            Err(e) => {
+                eprintln!("Warning: 写入录制文件失败: {}", e);
+                self.info.truncated = true;
+            }
+        }
+    }
+}
+
+/// 当前录制；未录制时 `record` 直接返回
+static ACTIVE: Mutex<Option<Recording>> = Mutex::new(None);
+
+fn lock() -> std::sync::MutexGuard<'static, Option<Recording>> {
+    ACTIVE.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// 由 debug_console::record 调用：录制中时把一行请求/响应（脱敏后）追加到录制文件。
+/// 请求与响应按 `_id` 配对，最终响应记 `duration_ms`，流式事件记距请求发出的 `elapsed_ms`
+pub fn record(direction: Direction, line: &str) {
+    if direction == Direction::Err {
+        return;
+    }
+    let mut active = lock();
+    let Some(rec) = active.as_mut().filter(|r| !r.info.truncated) else {
+        return;
+    };
+    let ts = now_ms();
+    let data = redacted_line(line.trim_end_matches(['\r', '\n']));
+    let id = data.get("_id").and_then(|v| v.as_str()).map(String::from);
+    let mut entry = serde_json::json!({ "ts": ts, "id": id });
+    match direction {
+        Direction::Out => {
+            entry["kind"] = "request".into();
+            entry["cmd"] = data.get("cmd").cloned().unwrap_or(Value::Null);
+            if let Some(id) = id {
+                rec.pending.insert(id, ts);
+            }
+        }
+        _ => {
+            let last = is_final(&data);
+            entry["kind"] = if last { "response" } else { "event" }.into();
+            let sent = match id {
+                Some(ref id) if last => rec.pending.remove(id),
+                Some(ref id) => rec.pending.get(id).copied(),
+                None => None,
+            };
+            if let Some(sent) = sent {
+                let key = if last { "duration_ms" } else { "elapsed_ms" };
+                entry[key] = ts.saturating_sub(sent).into();
+            }
+        }
+    }
+    entry["data"] = data;
+    rec.write(&entry);
+}
+
+fn transcripts_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    app.path()
+        .app_data_dir()
+        .map(|d| d.join(LOGS_DIR).join(TRANSCRIPTS_DIR))
+        .map_err(|e| e.to_string())
+}
+
+/// 开始录制 bridge 的请求与响应；已在录制时返回当前录制
+#[tauri::command]
+pub async fn bridge_recording_start(app: AppHandle) -> Result<RecordingInfo, String> {
+    guarded("bridge_recording_start", async move {
+        let mut active = lock();
+        if let Some(ref rec) = *active {
+            return Ok(rec.info.clone());
+        }
+        let dir = transcripts_dir(&app)?;
+        std::fs::create_dir_all(&dir).map_err(|e| format!("创建 {} 失败: {}", dir.display(), e))?;
+        let started_at = now_ms();
+        let id = format!("transcript-{}", started_at);
+        let path = dir.join(format!("{}.{}", id, EXT));
+        let file = std::fs::File::create(&path)
+            .map_err(|e| format!("创建录制文件 {} 失败: {}", path.display(), e))?;
+        let mut rec = Recording {
+            info: RecordingInfo {
+                id,
+                path: path.to_string_lossy().into_owned(),
+                started_at,
+                stopped_at: None,
+                entries: 0,
+                bytes: 0,
+                truncated: false,
+            },
+            file,
+            pending: HashMap::new(),
+        };
+        rec.write(&serde_json::json!({
+            "ts": started_at,
+            "kind": "start",
+            "app_version": app.package_info().version.to_string(),
+            "os": std::env::consts::OS,
+        }));
+        let info = rec.info.clone();
+        *active = Some(rec);
+        Ok(info)
+    })
+    .await
+}
+
+/// 停止录制并返回录制文件的信息
+#[tauri::command]
+pub async fn bridge_recording_stop() -> Result<RecordingInfo, String> {
+    guarded("bridge_recording_stop", async move {
+        let mut rec = lock().take().ok_or("当前没有进行中的录制")?;
+        let stopped_at = now_ms();
+        rec.write(&serde_json::json!({
+            "ts": stopped_at,
+            "kind": "stop",
+            "unanswered": rec.pending.len(),
+        }));
+        let _ = rec.file.flush();
+        rec.info.stopped_at = Some(stopped_at);
+        Ok(rec.info)
+    })
+    .await
+}
+
+/// 按 ID 找录制文件（可能已被日志保留策略压缩）；未指定时取最近一次
+fn find_recording(dir: &Path, id: Option<&str>) -> Result<PathBuf, String> {
+    let mut files: Vec<PathBuf> = std::fs::read_dir(dir)
+        .map_err(|_| "还没有录制文件".to_string())?
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| {
+            let name = p.file_name().unwrap_or_default().to_string_lossy();
+            name.ends_with(&format!(".{}", EXT)) || name.ends_with(&format!(".{}.gz", EXT))
+        })
+        .collect();
+    files.sort();
+    match id.map(str::trim).filter(|s| !s.is_empty()) {
+        Some(id) => files
+            .into_iter()
+            .find(|p| {
+                let name = p.file_name().unwrap_or_default().to_string_lossy();
+                name.split('.').next() == Some(id)
+            })
+            .ok_or_else(|| format!("未找到录制: {}", id)),
+        None => files.pop().ok_or_else(|| "还没有录制文件".to_string()),
+    }
+}
+
+/// 把录制文件复制到 `path`，便于附在问题报告中；录制进行中也可导出已写入的部分
+#[tauri::command]
+pub async fn bridge_recording_export(
+    app: AppHandle,
+    id: Option<String>,
+    path: String,
+) -> Result<String, String> {
+    guarded("bridge_recording_export", async move {
+        let source = find_recording(&transcripts_dir(&app)?, id.as_deref())?;
+        let path = path.trim();
+        if path.is_empty() {
+            return Err("路径为空".to_string());
+        }
+        let mut out = PathBuf::from(path);
+        if out.extension().is_none() {
+            let name = source.file_name().unwrap_or_default().to_string_lossy();
+            let ext = name.split_once('.').map_or(EXT, |(_, e)| e);
+            out.set_extension(ext);
+        }
+        check_path(&out)?;
+        if let Some(ref mut rec) = *lock() {
+            let _ = rec.file.flush();
+        }
+        std::fs::copy(&source, &out).map_err(|e| format!("导出录制失败: {}", e))?;
+        Ok(out.to_string_lossy().into_owned())
+    })
+    .await
+}
@@ -0,0 +1,187 @@
+use crate::bridge::{bridge_abort, open_path};
+use crate::debug_console::debug_console_open;
+use crate::jobs::JobRegistry;
+use crate::recent::{RecentModel, RecentModels};
+use std::path::Path;
+use tauri::menu::{
+    CheckMenuItem, IsMenuItem, Menu, MenuEvent, MenuItem, PredefinedMenuItem, Submenu,
+};
+use tauri::tray::TrayIconBuilder;
+use tauri::{AppHandle, Manager, Wry};
+use tokio::sync::watch;
+
+const TRAY_ID: &str = "main";
+/// 托盘菜单里每个列表最多显示的条目
+const MENU_LIMIT: usize = 10;
+const OPEN_PREFIX: &str = "open:";
+
+fn file_name(path: &str) -> String {
+    Path::new(path)
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_else(|| path.to_string())
+}
+
+fn model_items(app: &AppHandle, models: &[&RecentModel]) -> tauri::Result<Vec<MenuItem<Wry>>> {
+    models
+        .iter()
+        .take(MENU_LIMIT)
+        .map(|m| {
+            MenuItem::with_id(
+                app,
+                format!("{}{}", OPEN_PREFIX, m.path),
+                file_name(&m.path),
+                true,
+                None::<&str>,
+            )
+        })
+        .collect()
+}
+
+fn submenu(app: &AppHandle, text: &str, items: &[MenuItem<Wry>]) -> tauri::Result<Submenu<Wry>> {
+    let refs: Vec<&dyn IsMenuItem<Wry>> = items.iter().map(|i| i as &dyn IsMenuItem<Wry>).collect();
+    Submenu::with_items(app, text, !refs.is_empty(), &refs)
+}
+
+/// 按当前任务登记表与最近模型列表构建托盘菜单
+fn build_menu(app: &AppHandle) -> tauri::Result<Menu<Wry>> {
+    let jobs = app.state::<JobRegistry>();
+    let recent = app.state::<RecentModels>().list();
+    let running = jobs.running();
+
+    let show = MenuItem::with_id(app, "show", "显示主窗口", true, None::<&str>)?;
+    let abort_text = match running {
+        Some(ref job) => format!("中止当前任务（{}）", job.cmd),
+        None => "中止当前任务".to_string(),
+    };
+    let abort = MenuItem::with_id(app, "abort", abort_text, running.is_some(), None::<&str>)?;
+    let pause = CheckMenuItem::with_id(
+        app,
+        "pause-queue",
+        "暂停队列",
+        true,
+        jobs.is_paused(),
+        None::<&str>,
+    )?;
+
+    let recent_items = model_items(app, &recent.iter().collect::<Vec<_>>())?;
+    let recent_menu = submenu(app, "最近模型", &recent_items)?;
+
+    // 每个项目一个子菜单，列出该项目最近的模型
+    let mut project_menus = Vec::new();
+    for project in jobs.projects().iter().take(MENU_LIMIT) {
+        let models: Vec<&RecentModel> = recent
+            .iter()
+            .filter(|m| m.project.as_deref() == Some(project.as_str()))
+            .collect();
+        let items = model_items(app, &models)?;
+        project_menus.push(submenu(app, &file_name(project), &items)?);
+    }
+    let project_refs: Vec<&dyn IsMenuItem<Wry>> = project_menus
+        .iter()
+        .map(|m| m as &dyn IsMenuItem<Wry>)
+        .collect();
+    let projects_menu = Submenu::with_items(app, "项目", !project_refs.is_empty(), &project_refs)?;
+
+    let console = MenuItem::with_id(app, "debug-console", "调试控制台", true, None::<&str>)?;
+    let quit = MenuItem::with_id(app, "quit", "退出", true, None::<&str>)?;
+    Menu::with_items(
+        app,
+        &[
+            &show,
+            &PredefinedMenuItem::separator(app)?,
+            &abort,
+            &pause,
+            &PredefinedMenuItem::separator(app)?,
+            &recent_menu,
+            &projects_menu,
+            &PredefinedMenuItem::separator(app)?,
+            &console,
+            &quit,
+        ],
+    )
+}
+
+fn refresh_menu(app: &AppHandle) {
+    let Some(tray) = app.tray_by_id(TRAY_ID) else {
+        return;
+    };
+    match build_menu(app) {
+        Ok(menu) => {
+            if let Err(e) = tray.set_menu(Some(menu)) {
+                eprintln!("Warning: 更新托盘菜单失败: {}", e);
+            }
+        }
+        Err(e) => eprintln!("Warning: 构建托盘菜单失败: {}", e),
+    }
+}
+
+fn handle_menu_event(app: &AppHandle, event: MenuEvent) {
+    let id = event.id().as_ref();
+    if let Some(path) = id.strip_prefix(OPEN_PREFIX) {
+        let path = path.to_string();
+        tauri::async_runtime::spawn(async move {
+            if let Err(e) = open_path(path).await {
+                eprintln!("Warning: 打开模型失败: {}", e);
+            }
+        });
+        return;
+    }
+    match id {
+        "show" => {
+            if let Some(w) = app.get_webview_window("main") {
+                let _ = w.unminimize();
+                let _ = w.show();
+                let _ = w.set_focus();
+            }
+        }
+        "abort" => {
+            let app = app.clone();
+            tauri::async_runtime::spawn(async move {
+                if let Err(e) = bridge_abort(app.clone()).await {
+                    eprintln!("Warning: 中止任务失败: {}", e);
+                }
+            });
+        }
+        "pause-queue" => {
+            let jobs = app.state::<JobRegistry>();
+            jobs.set_paused(!jobs.is_paused());
+        }
+        "debug-console" => {
+            let app = app.clone();
+            tauri::async_runtime::spawn(async move {
+                if let Err(e) = debug_console_open(app).await {
+                    eprintln!("Warning: 打开调试控制台失败: {}", e);
+                }
+            });
+        }
+        "quit" => app.exit(0),
+        _ => {}
+    }
+}
+
+/// 任一数据源变化即重建菜单
+fn refresh_on_change(app: AppHandle, mut rx: watch::Receiver<u64>) {
+    tauri::async_runtime::spawn(async move {
+        while rx.changed().await.is_ok() {
+            refresh_menu(&app);
+        }
+    });
+}
+
+/// 创建系统托盘；需在 JobRegistry 与 RecentModels 注册之后调用
+pub fn setup_tray(app: &tauri::App) -> tauri::Result<()> {
+    let handle = app.handle();
+    let mut builder = TrayIconBuilder::with_id(TRAY_ID)
+        .menu(&build_menu(handle)?)
+        .tooltip("多物理场建模智能体")
+        .on_menu_event(handle_menu_event);
+    if let Some(icon) = app.default_window_icon() {
+        builder = builder.icon(icon.clone());
+    }
+    builder.build(app)?;
+
+    refresh_on_change(handle.clone(), app.state::<JobRegistry>().subscribe());
+    refresh_on_change(handle.clone(), app.state::<RecentModels>().subscribe());
+    Ok(())
+}
@@ -0,0 +1,439 @@
+use crate::panics::guarded;
+use serde::Serialize;
+
+/// SI 基本量纲顺序：m, kg, s, A, K, mol, cd
+const BASE_SYMBOLS: [&str; 7] = ["m", "kg", "s", "A", "K", "mol", "cd"];
+
+type Dims = [i32; 7];
+
+#[derive(Clone, Copy, Debug)]
+struct Unit {
+    scale: f64,
+    /// 仅温度使用：si = value * scale + offset
+    offset: f64,
+    dims: Dims,
+}
+
+impl Unit {
+    const ONE: Unit = Unit {
+        scale: 1.0,
+        offset: 0.0,
+        dims: [0; 7],
+    };
+
+    fn mul(self, other: Unit) -> Unit {
+        let mut dims = self.dims;
+        for (d, o) in dims.iter_mut().zip(other.dims) {
+            *d += o;
+        }
+        Unit {
+            scale: self.scale * other.scale,
+            offset: 0.0,
+            dims,
+        }
+    }
+
+    fn powi(self, n: i32) -> Unit {
+        Unit {
+            scale: self.scale.powi(n),
+            offset: 0.0,
+            dims: self.dims.map(|d| d * n),
+        }
+    }
+}
+
+const fn u(scale: f64, dims: Dims) -> Unit {
+    Unit {
+        scale,
+        offset: 0.0,
+        dims,
+    }
+}
+
+/// 单位符号 → (定义, 是否可加 SI 前缀)
+fn lookup(symbol: &str) -> Option<(Unit, bool)> {
+    const L: Dims = [1, 0, 0, 0, 0, 0, 0];
+    const M: Dims = [0, 1, 0, 0, 0, 0, 0];
+    const T: Dims = [0, 0, 1, 0, 0, 0, 0];
+    const I: Dims = [0, 0, 0, 1, 0, 0, 0];
+    const TH: Dims = [0, 0, 0, 0, 1, 0, 0];
+    const N: Dims = [0, 0, 0, 0, 0, 1, 0];
+    const J: Dims = [0, 0, 0, 0, 0, 0, 1];
+    const NONE: Dims = [0; 7];
+    const FORCE: Dims = [1, 1, -2, 0, 0, 0, 0];
+    const PRESSURE: Dims = [-1, 1, -2, 0, 0, 0, 0];
+    const ENERGY: Dims = [2, 1, -2, 0, 0, 0, 0];
+    const POWER: Dims = [2, 1, -3, 0, 0, 0, 0];
+    const CHARGE: Dims = [0, 0, 1, 1, 0, 0, 0];
+    const VOLTAGE: Dims = [2, 1, -3, -1, 0, 0, 0];
+    let def = match symbol {
+        "m" => (u(1.0, L), true),
+        "g" => (u(1e-3, M), true),
+        "s" => (u(1.0, T), true),
+        "A" => (u(1.0, I), true),
+        "K" => (u(1.0, TH), true),
+        "mol" => (u(1.0, N), true),
+        "cd" => (u(1.0, J), true),
+        "Hz" => (u(1.0, [0, 0, -1, 0, 0, 0, 0]), true),
+        "N" => (u(1.0, FORCE), true),
+        "Pa" => (u(1.0, PRESSURE), true),
+        "J" => (u(1.0, ENERGY), true),
+        "W" => (u(1.0, POWER), true),
+        "C" => (u(1.0, CHARGE), true),
+        "V" => (u(1.0, VOLTAGE), true),
+        "F" => (u(1.0, [-2, -1, 4, 2, 0, 0, 0]), true),
+        "ohm" | "Ω" => (u(1.0, [2, 1, -3, -2, 0, 0, 0]), true),
+        "S" => (u(1.0, [-2, -1, 3, 2, 0, 0, 0]), true),
+        "Wb" => (u(1.0, [2, 1, -2, -1, 0, 0, 0]), true),
+        "T" => (u(1.0, [0, 1, -2, -1, 0, 0, 0]), true),
+        "H" => (u(1.0, [2, 1, -2, -2, 0, 0, 0]), true),
+        "L" | "l" => (u(1e-3, [3, 0, 0, 0, 0, 0, 0]), true),
+        "eV" => (u(1.602176634e-19, ENERGY), true),
+        "bar" => (u(1e5, PRESSURE), true),
+        "rad" | "sr" => (u(1.0, NONE), false),
+        "deg" | "°" => (u(std::f64::consts::PI / 180.0, NONE), false),
+        "%" => (u(0.01, NONE), false),
+        "min" => (u(60.0, T), false),
+        "h" => (u(3600.0, T), false),
+        "d" => (u(86400.0, T), false),
+        "in" => (u(0.0254, L), false),
+        "ft" => (u(0.3048, L), false),
+        "mi" => (u(1609.344, L), false),
+        "lb" | "lbm" => (u(0.45359237, M), false),
+        "lbf" => (u(4.4482216152605, FORCE), false),
+        "psi" => (u(6894.757293168, PRESSURE), false),
+        "atm" => (u(101325.0, PRESSURE), false),
+        "mmHg" => (u(133.322387415, PRESSURE), false),
+        "cal" => (u(4.184, ENERGY), true),
+        "degC" | "°C" => (
+            Unit {
+                scale: 1.0,
+                offset: 273.15,
+                dims: TH,
+            },
+            false,
+        ),
+        "degF" | "°F" => (
+            Unit {
+                scale: 5.0 / 9.0,
+                offset: 273.15 - 32.0 * 5.0 / 9.0,
+                dims: TH,
+            },
+            false,
+        ),
+        "degR" => (u(5.0 / 9.0, TH), false),
+        _ => return None,
+    };
+    Some(def)
+}
+
+fn prefix_scale(prefix: &str) -> Option<f64> {
+    Some(match prefix {
+        "Y" => 1e24,
+        "Z" => 1e21,
+        "E" => 1e18,
+        "P" => 1e15,
+        "T" => 1e12,
+        "G" => 1e9,
+        "M" => 1e6,
+        "k" => 1e3,
+        "h" => 1e2,
+        "da" => 1e1,
+        "d" => 1e-1,
+        "c" => 1e-2,
+        "m" => 1e-3,
+        "u" | "µ" | "μ" => 1e-6,
+        "n" => 1e-9,
+        "p" => 1e-12,
+        "f" => 1e-15,
+        "a" => 1e-18,
+        _ => return None,
+    })
+}
+
+/// 解析单个符号：先整体匹配，再尝试「前缀 + 可加前缀的单位」
+fn parse_symbol(symbol: &str) -> Result<Unit, String> {
+    if let Some((unit, _)) = lookup(symbol) {
+        return Ok(unit);
+    }
+    for (i, _) in symbol.char_indices().skip(1) {
+        let (prefix, rest) = symbol.split_at(i);
+        if let (Some(p), Some((unit, true))) = (prefix_scale(prefix), lookup(rest)) {
+            return Ok(Unit {
+                scale: unit.scale * p,
+                ..unit
+            });
+        }
+    }
+    Err(format!("未知单位: {}", symbol))
+}
+
+struct Parser<'a> {
+    chars: std::iter::Peekable<std::str::Chars<'a>>,
+}
+
+/// 单位表达式语法：term (('*'|'/'|'·'|' ') term)*，term = '(' expr ')' | symbol，可跟 ^n
+impl Parser<'_> {
+    fn skip_spaces(&mut self) -> bool {
+        let mut skipped = false;
+        while self.chars.peek().is_some_and(|c| c.is_whitespace()) {
+            self.chars.next();
+            skipped = true;
+        }
+        skipped
+    }
+
+    fn expr(&mut self) -> Result<Unit, String> {
+        self.skip_spaces();
+        let mut acc = self.term()?;
+        loop {
+            let spaced = self.skip_spaces();
+            match self.chars.peek() {
+                Some('*') | Some('·') => {
+                    self.chars.next();
+                    self.skip_spaces();
+                    acc = acc.mul(self.term()?);
+                }
+                Some('/') => {
+                    self.chars.next();
+                    self.skip_spaces();
+                    acc = acc.mul(self.term()?.powi(-1));
+                }
+                Some(')') | None => return Ok(acc),
+                Some(_) if spaced => acc = acc.mul(self.term()?),
+                Some(c) => return Err(format!("单位表达式中出现意外字符: {}", c)),
+            }
+        }
+    }
+
+    fn term(&mut self) -> Result<Unit, String> {
+        let base = if self.chars.peek() == Some(&'(') {
+            self.chars.next();
+            let inner = self.expr()?;
+            if self.chars.next() != Some(')') {
+                return Err("括号不匹配".to_string());
+            }
+            inner
+        } else {
+            let mut symbol = String::new();
+            while let Some(&c) = self.chars.peek() {
+                if c.is_whitespace() || "*/·^()".contains(c) || c.is_ascii_digit() || c == '-' {
+                    break;
+                }
+                symbol.push(c);
+                self.chars.next();
+            }
+            match symbol.as_str() {
+                "" if self.chars.peek() == Some(&'1') => {
+                    self.chars.next();
+                    Unit::ONE
+                }
+                "" => return Err("缺少单位".to_string()),
+                s => parse_symbol(s)?,
+            }
+        };
+        // 指数：m^2、m^-1，也接受 m2、s-1 这类简写
+        if self.chars.peek() == Some(&'^') {
+            self.chars.next();
+        }
+        let mut exp = String::new();
+        if self.chars.peek() == Some(&'-') {
+            exp.push('-');
+            self.chars.next();
+        }
+        while let Some(&c) = self.chars.peek() {
+            if !c.is_ascii_digit() {
+                break;
+            }
+            exp.push(c);
+            self.chars.next();
+        }
+        if exp.is_empty() {
+            return Ok(base);
+        }
+        let n: i32 = exp.parse().map_err(|_| format!("无效的指数: {}", exp))?;
+        if base.offset != 0.0 {
+            return Err("带偏移的温度单位不能取幂".to_string());
+        }
+        Ok(base.powi(n))
+    }
+}
+
+fn parse_unit(expr: &str) -> Result<Unit, String> {
+    let expr = expr.trim();
+    if expr.is_empty() || expr == "1" {
+        return Ok(Unit::ONE);
+    }
+    // degC、°F 等带偏移的单位只能单独使用
+    if let Some((unit, _)) = lookup(expr) {
+        return Ok(unit);
+    }
+    let mut p = Parser {
+        chars: expr.chars().peekable(),
+    };
+    let unit = p.expr()?;
+    if p.chars.next().is_some() {
+        return Err("括号不匹配".to_string());
+    }
+    Ok(unit)
+}
+
+/// 由量纲生成 SI 单位串，如 kg*m^2/s^3
+fn si_unit(dims: &Dims) -> String {
+    let part = |sym: &str, e: i32| {
+        if e == 1 {
+            sym.to_string()
+        } else {
+            format!("{}^{}", sym, e)
+        }
+    };
+    let num: Vec<String> = BASE_SYMBOLS
+        .iter()
+        .zip(dims)
+        .filter(|(_, e)| **e > 0)
+        .map(|(s, e)| part(s, *e))
+        .collect();
+    let den: Vec<String> = BASE_SYMBOLS
+        .iter()
+        .zip(dims)
+        .filter(|(_, e)| **e < 0)
+        .map(|(s, e)| part(s, -e))
+        .collect();
+    match (num.is_empty(), den.is_empty()) {
+        (true, true) => "1".to_string(),
+        (false, true) => num.join("*"),
+        (true, false) => format!("1/{}", den.join("/")),
+        (false, false) => format!("{}/{}", num.join("*"), den.join("/")),
+    }
+}
+
+pub fn convert(value: f64, from: &str, to: &str) -> Result<f64, String> {
+    let (f, t) = (parse_unit(from)?, parse_unit(to)?);
+    if f.dims != t.dims {
+        return Err(format!(
+            "量纲不一致: {} ({}) 与 {} ({})",
+            from,
+            si_unit(&f.dims),
+            to,
+            si_unit(&t.dims)
+        ));
+    }
+    let si = value * f.scale + f.offset;
+    Ok((si - t.offset) / t.scale)
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Quantity {
+    pub value: f64,
+    pub unit: String,
+    pub si_value: f64,
+    pub si_unit: String,
+    /// 各 SI 基本量纲的指数，顺序为 m, kg, s, A, K, mol, cd
+    pub dimensions: Dims,
+}
+
+/// 解析「数值 + 单位」，支持 `5 mm`、`5mm`、COMSOL 风格的 `5[mm]`
+pub fn parse(text: &str) -> Result<Quantity, String> {
+    let text = text.trim();
+    // 取能解析为数值的最长前缀，兼容 1e-3、.5 等写法
+    let (value, rest) = text
+        .char_indices()
+        .map(|(i, _)| i)
+        .chain([text.len()])
+        .rev()
+        .find_map(|i| {
+            let v: f64 = text[..i].parse().ok()?;
+            Some((v, &text[i..]))
+        })
+        .filter(|(v, _)| v.is_finite())
+        .ok_or_else(|| format!("无法解析数值: {}", text))?;
+    let unit = rest.trim();
+    let unit = unit
+        .strip_prefix('[')
+        .and_then(|u| u.strip_suffix(']'))
+        .unwrap_or(unit)
+        .trim();
+    let parsed = parse_unit(unit)?;
+    Ok(Quantity {
+        value,
+        unit: unit.to_string(),
+        si_value: value * parsed.scale + parsed.offset,
+        si_unit: si_unit(&parsed.dims),
+        dimensions: parsed.dims,
+    })
+}
+
+/// 单位换算，如 convert_value(5, "mm", "in")；量纲不一致时报错
+#[tauri::command]
+pub async fn convert_value(value: f64, from: String, to: String) -> Result<f64, String> {
+    guarded("convert_value", async move { convert(value, &from, &to) }).await
+}
+
+/// 解析用户输入的带单位数值，返回原值与 SI 值，供前端在提交前校验
+#[tauri::command]
+pub async fn parse_quantity(text: String) -> Result<Quantity, String> {
+    guarded("parse_quantity", async move { parse(&text) }).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() <= 1e-9 * b.abs().max(1.0)
+    }
+
+    #[test]
+    fn parse_accepts_spacing_and_bracket_forms() {
+        for text in ["5 mm", "5mm", "5[mm]", " 5 [mm] "] {
+            let q = parse(text).unwrap();
+            assert_eq!(q.value, 5.0);
+            assert_eq!(q.unit, "mm");
+            assert!(close(q.si_value, 5e-3));
+            assert_eq!(q.si_unit, "m");
+        }
+        let q = parse("1e-3 kg*m^2/s^3").unwrap();
+        assert!(close(q.si_value, 1e-3));
+        assert_eq!(q.si_unit, "m^2*kg/s^3");
+        assert_eq!(q.dimensions, [2, 1, -3, 0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn parse_handles_prefixes_exponents_and_dimensionless() {
+        assert!(close(parse("2 kPa").unwrap().si_value, 2000.0));
+        assert!(close(parse("3 cm^2").unwrap().si_value, 3e-4));
+        assert!(close(parse("3 cm2").unwrap().si_value, 3e-4));
+        assert_eq!(parse("1 s-1").unwrap().si_unit, "1/s");
+        assert_eq!(parse("1 W/(m*K)").unwrap().si_unit, "m*kg/s^3/K");
+        assert_eq!(parse("0.5").unwrap().si_unit, "1");
+        assert!(close(parse("20 degC").unwrap().si_value, 293.15));
+    }
+
+    #[test]
+    fn parse_rejects_bad_input() {
+        assert!(parse("mm").is_err());
+        assert!(parse("5 furlong").is_err());
+        assert!(parse("5 (mm").is_err());
+        assert!(parse("5 degC^2").is_err());
+    }
+
+    #[test]
+    fn convert_scales_and_offsets() {
+        assert!(close(convert(25.4, "mm", "in").unwrap(), 1.0));
+        assert!(close(convert(1.0, "atm", "kPa").unwrap(), 101.325));
+        assert!(close(convert(100.0, "degC", "degF").unwrap(), 212.0));
+        assert!(close(convert(0.0, "degC", "K").unwrap(), 273.15));
+        assert!(close(convert(1.0, "kW*h", "J").unwrap(), 3.6e6));
+        assert!(close(
+            convert(90.0, "deg", "rad").unwrap(),
+            std::f64::consts::FRAC_PI_2
+        ));
+    }
+
+    #[test]
+    fn convert_rejects_mismatched_dimensions() {
+        let err = convert(1.0, "m", "s").unwrap_err();
+        assert!(err.contains("量纲不一致"));
+        assert!(err.contains("(m)") && err.contains("(s)"));
+    }
+}
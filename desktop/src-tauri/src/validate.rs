@@ -0,0 +1,245 @@
+use crate::artifacts::{check_not_locked, ArtifactRegistry};
+use crate::attachments::{ingest_payload, preview_payload};
+use crate::backups::backup_before_write;
+use crate::bridge::{check_cmd_supported, BridgeState};
+use crate::filenames::sanitize_payload;
+use crate::jobs::redact_payload;
+use crate::mphserver::attach_endpoint;
+use crate::panics::guarded;
+use crate::policy::CommandPolicy;
+use crate::request_queue::Priority;
+use crate::sandbox::check_payload;
+use crate::session_options::apply_session_options;
+use crate::timeouts::RequestTimeouts;
+use serde::Serialize;
+use serde_json::Value;
+use tauri::{AppHandle, Manager};
+
+/// 校验未通过的一项：`check` 为检查类别，`field` 为出问题的字段（能确定时）
+#[derive(Clone, Debug, Serialize)]
+pub struct Violation {
+    pub check: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub field: Option<String>,
+    pub message: String,
+}
+
+impl Violation {
+    fn new(check: &'static str, message: String) -> Self {
+        Self {
+            check,
+            field: None,
+            message,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ValidationReport {
+    pub ok: bool,
+    pub cmd: String,
+    /// 通过时为实际会发送的请求（敏感字段已移除）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub normalized: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub violation: Option<Violation>,
+    /// 该请求的等待上限：非流式为 `timeout_secs`，流式为总时长与静默上限
+    pub budget: Value,
+}
+
+fn type_matches(expected: &str, value: &Value) -> bool {
+    match expected {
+        "string" => value.is_string(),
+        "number" => value.is_number(),
+        "integer" => value.is_i64() || value.is_u64(),
+        "boolean" => value.is_boolean(),
+        "array" => value.is_array(),
+        "object" => value.is_object(),
+        "null" => value.is_null(),
+        _ => true,
+    }
+}
+
+/// bridge 在能力声明中给出的参数 JSON Schema：只检查顶层的 required / type / enum，
+/// 以 `_` 开头的桌面端控制字段不参与检查
+fn check_schema(schema: &Value, payload: &Value) -> Result<(), Violation> {
+    let violation = |field: &str, message: String| Violation {
+        check: "schema",
+        field: Some(field.to_string()),
+        message,
+    };
+    for key in schema
+        .get("required")
+        .and_then(|v| v.as_array())
+        .into_iter()
+        .flatten()
+        .filter_map(|v| v.as_str())
+    {
+        if payload.get(key).is_none_or(|v| v.is_null()) {
+            return Err(violation(key, format!("缺少必填参数 `{}`", key)));
+        }
+    }
+    let Some(props) = schema.get("properties").and_then(|v| v.as_object()) else {
+        return Ok(());
+    };
+    let Some(fields) = payload.as_object() else {
+        return Ok(());
+    };
+    for (key, value) in fields.iter().filter(|(k, _)| !k.starts_with('_')) {
+        let Some(prop) = props.get(key) else {
+            if schema.get("additionalProperties") == Some(&Value::Bool(false)) {
+                return Err(violation(key, format!("未知参数 `{}`", key)));
+            }
+            continue;
+        };
+        let types: Vec<&str> = match prop.get("type") {
+            Some(Value::String(t)) => vec![t.as_str()],
+            Some(Value::Array(ts)) => ts.iter().filter_map(|t| t.as_str()).collect(),
+            _ => Vec::new(),
+        };
+        if !types.is_empty() && !types.iter().any(|t| type_matches(t, value)) {
+            return Err(violation(
+                key,
+                format!("参数 `{}` 应为 {}", key, types.join(" / ")),
+            ));
+        }
+        if let Some(allowed) = prop.get("enum").and_then(|v| v.as_array()) {
+            if !allowed.contains(value) {
+                return Err(violation(
+                    key,
+                    format!("参数 `{}` 的取值不在允许范围内", key),
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// 发送前的各项检查与改写，`bridge_send`、流式请求与 `validate_request` 共用，遇到第一项不通过即返回。
+/// `dry_run` 时只预览附件，不入库附件、不备份将被覆盖的模型
+pub(crate) async fn pre_send_checks(
+    app: &AppHandle,
+    state: &BridgeState,
+    cmd: &str,
+    payload: &mut Value,
+    request_id: Option<&str>,
+    dry_run: bool,
+) -> Result<(), Violation> {
+    app.state::<CommandPolicy>()
+        .check(cmd)
+        .map_err(|m| Violation::new("policy", m))?;
+    let capabilities = {
+        let guard = state.lock().await;
+        check_cmd_supported(&guard, cmd).map_err(|m| Violation::new("capability", m))?;
+        guard.capabilities.clone()
+    };
+    if let Some(schema) = capabilities
+        .as_ref()
+        .and_then(|c| c.specs.get(cmd))
+        .and_then(|s| s.params.as_ref())
+    {
+        check_schema(schema, payload)?;
+    }
+
+    sanitize_payload(payload);
+    check_payload(payload).map_err(|m| Violation::new("sandbox", m))?;
+    app.state::<ArtifactRegistry>()
+        .check_overwrite(payload)
+        .map_err(|m| Violation::new("overwrite", m))?;
+    check_not_locked(app, payload)
+        .await
+        .map_err(|m| Violation::new("file_lock", m))?;
+    attach_endpoint(app, payload);
+    apply_session_options(app, payload);
+
+    let attachment = |message: String| Violation {
+        check: "attachment",
+        field: Some("attachments".to_string()),
+        message,
+    };
+    if !dry_run {
+        ingest_payload(app, payload).await.map_err(attachment)?;
+        return backup_before_write(app, payload, request_id)
+            .await
+            .map_err(|m| Violation::new("backup", m));
+    }
+    let mut preview = std::mem::take(payload);
+    let handle = app.clone();
+    let preview = tauri::async_runtime::spawn_blocking(move || {
+        preview_payload(&handle, &mut preview).map(|_| preview)
+    })
+    .await
+    .map_err(|e| Violation::new("internal", e.to_string()))?
+    .map_err(attachment)?;
+    *payload = preview;
+    Ok(())
+}
+
+/// 计算等待上限后按 dry run 执行发送前检查
+async fn run_checks(
+    app: &AppHandle,
+    state: &BridgeState,
+    cmd: &str,
+    payload: &mut Value,
+    stream: bool,
+) -> Result<Value, Violation> {
+    let timeouts = app.state::<RequestTimeouts>();
+    let budget = if stream {
+        let b = timeouts.resolve_stream(payload);
+        serde_json::json!({
+            "total_secs": b.total.as_secs(),
+            "idle_secs": b.idle.as_secs(),
+            "stall_secs": b.stall.as_secs(),
+        })
+    } else {
+        serde_json::json!({ "timeout_secs": timeouts.resolve(cmd, None, payload).as_secs() })
+    };
+    Priority::take(payload, Priority::Normal);
+    pre_send_checks(app, state, cmd, payload, None, true).await?;
+    Ok(budget)
+}
+
+/// 只执行桌面端的发送前检查（参数 schema、沙箱路径、命令策略与能力、附件类型与大小、
+/// 覆盖与文件占用），不发送也不写入任何文件。通过时返回规范化后的请求，否则返回具体违规项，
+/// 便于前端在提交长任务前校验表单
+#[tauri::command]
+pub async fn validate_request(
+    app: AppHandle,
+    state: tauri::State<'_, BridgeState>,
+    cmd: String,
+    mut payload: Value,
+    stream: Option<bool>,
+) -> Result<ValidationReport, String> {
+    guarded("validate_request", async move {
+        let cmd = cmd.trim().to_string();
+        if !payload.is_object() {
+            return Ok(ValidationReport {
+                ok: false,
+                cmd,
+                normalized: None,
+                violation: Some(Violation::new("schema", "请求参数应为对象".to_string())),
+                budget: Value::Null,
+            });
+        }
+        let stream = stream.unwrap_or(false);
+        Ok(
+            match run_checks(&app, state.inner(), &cmd, &mut payload, stream).await {
+                Ok(budget) => ValidationReport {
+                    ok: true,
+                    cmd,
+                    normalized: Some(redact_payload(&payload)),
+                    violation: None,
+                    budget,
+                },
+                Err(violation) => ValidationReport {
+                    ok: false,
+                    cmd,
+                    normalized: None,
+                    violation: Some(violation),
+                    budget: Value::Null,
+                },
+            },
+        )
+    })
+    .await
+}
@@ -0,0 +1,274 @@
+use crate::artifacts::ArtifactRegistry;
+use crate::bridge::{find_project_root, open_path};
+use crate::panics::guarded;
+use crate::paths::output_dir;
+use crate::policy::CommandPolicy;
+use crate::settings::project_output_dir;
+use crate::store::{load_json, save_json};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// 未配置时默认交给 ParaView 打开的扩展名
+const PARAVIEW_EXTS: &[&str] = &["vtu", "vtk", "vtp", "vts", "vtr", "pvd", "pvtu"];
+
+#[derive(Clone, Debug, Serialize)]
+pub struct DetectedViewer {
+    pub name: String,
+    pub version: Option<String>,
+    pub path: String,
+}
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct ViewerConfig {
+    /// 扩展名（小写、不含点）→ 应用程序路径
+    #[serde(default)]
+    pub mapping: BTreeMap<String, String>,
+}
+
+/// 外部查看器映射，保存在 app data 目录
+#[derive(Default)]
+pub struct ViewerRegistry {
+    path: Option<PathBuf>,
+    config: Mutex<ViewerConfig>,
+}
+
+impl ViewerRegistry {
+    pub fn load(path: PathBuf) -> Self {
+        let config: ViewerConfig = load_json(&path);
+        Self {
+            path: Some(path),
+            config: Mutex::new(config),
+        }
+    }
+
+    pub fn config(&self) -> ViewerConfig {
+        self.config
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    pub fn set(&self, ext: &str, app: Option<String>) -> Result<(), String> {
+        let mut config = self.config.lock().unwrap_or_else(|e| e.into_inner());
+        match app {
+            Some(a) => config.mapping.insert(ext.to_string(), a),
+            None => config.mapping.remove(ext),
+        };
+        match self.path {
+            Some(ref p) => save_json(p, &*config),
+            None => Ok(()),
+        }
+    }
+}
+
+fn normalize_ext(ext: &str) -> String {
+    ext.trim().trim_start_matches('.').to_lowercase()
+}
+
+/// ParaView 安装目录名形如 ParaView 5.12.0 / ParaView-5.11.2
+fn paraview_version(dir_name: &str) -> Option<String> {
+    let rest = dir_name.strip_prefix("ParaView")?;
+    let v = rest.trim_start_matches([' ', '-']).trim_end_matches(".app");
+    let v: String = v
+        .chars()
+        .take_while(|c| c.is_ascii_digit() || *c == '.')
+        .collect();
+    (!v.is_empty()).then_some(v)
+}
+
+fn paraview_exe(install_dir: &Path) -> PathBuf {
+    #[cfg(target_os = "windows")]
+    return install_dir.join("bin").join("paraview.exe");
+    #[cfg(target_os = "macos")]
+    return install_dir.join("Contents").join("MacOS").join("paraview");
+    #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+    return install_dir.join("bin").join("paraview");
+}
+
+/// 扫描默认安装位置与 PATH 中的 ParaView
+pub fn detect_paraview() -> Vec<DetectedViewer> {
+    let mut bases: Vec<PathBuf> = Vec::new();
+    #[cfg(target_os = "windows")]
+    for var in ["ProgramFiles", "ProgramW6432"] {
+        if let Ok(p) = std::env::var(var) {
+            bases.push(PathBuf::from(p));
+        }
+    }
+    #[cfg(target_os = "macos")]
+    bases.push(PathBuf::from("/Applications"));
+    #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+    bases.extend([PathBuf::from("/opt"), PathBuf::from("/usr/local")]);
+
+    let mut out = Vec::new();
+    for base in bases {
+        let Ok(rd) = std::fs::read_dir(&base) else {
+            continue;
+        };
+        for entry in rd.flatten() {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            let Some(version) = paraview_version(&name) else {
+                continue;
+            };
+            let exe = paraview_exe(&entry.path());
+            if exe.is_file() {
+                out.push(DetectedViewer {
+                    name: "ParaView".to_string(),
+                    version: Some(version),
+                    path: exe.to_string_lossy().into_owned(),
+                });
+            }
+        }
+    }
+    if let Some(paths) = std::env::var_os("PATH") {
+        let exe_name = if cfg!(target_os = "windows") {
+            "paraview.exe"
+        } else {
+            "paraview"
+        };
+        for dir in std::env::split_paths(&paths) {
+            let exe = dir.join(exe_name);
+            if exe.is_file() {
+                out.push(DetectedViewer {
+                    name: "ParaView".to_string(),
+                    version: None,
+                    path: exe.to_string_lossy().into_owned(),
+                });
+            }
+        }
+    }
+    out.dedup_by(|a, b| a.path == b.path);
+    out
+}
+
+#[tauri::command]
+pub async fn viewers_get(
+    viewers: tauri::State<'_, ViewerRegistry>,
+) -> Result<serde_json::Value, String> {
+    guarded("viewers_get", async move {
+        let detected = tauri::async_runtime::spawn_blocking(detect_paraview)
+            .await
+            .map_err(|e| e.to_string())?;
+        Ok(serde_json::json!({
+            "mapping": viewers.config().mapping,
+            "detected": detected,
+        }))
+    })
+    .await
+}
+
+/// 设置某扩展名的外部查看器；`app` 为空时删除映射
+#[tauri::command]
+pub async fn viewers_set(
+    viewers: tauri::State<'_, ViewerRegistry>,
+    ext: String,
+    app: Option<String>,
+) -> Result<(), String> {
+    guarded("viewers_set", async move {
+        let ext = normalize_ext(&ext);
+        if ext.is_empty() {
+            return Err("扩展名为空".to_string());
+        }
+        let app = app.map(|a| a.trim().to_string()).filter(|a| !a.is_empty());
+        if let Some(ref a) = app {
+            if !Path::new(a).exists() {
+                return Err(format!("应用程序不存在: {}", a));
+            }
+        }
+        viewers.set(&ext, app)
+    })
+    .await
+}
+
+/// 只允许打开登记过的产物，或项目输出目录（未指定项目时为默认输出目录）下的文件，
+/// 避免借外部程序打开任意路径
+fn check_viewable(
+    artifacts: &ArtifactRegistry,
+    path: &str,
+    project: Option<&str>,
+) -> Result<(), String> {
+    if artifacts.is_tracked(path) {
+        return Ok(());
+    }
+    let file = std::fs::canonicalize(path).map_err(|e| format!("无法解析路径: {}", e))?;
+    let roots = project
+        .map(str::trim)
+        .filter(|p| !p.is_empty())
+        .map(|p| project_output_dir(Path::new(p)).unwrap_or_else(|| PathBuf::from(p)))
+        .into_iter()
+        .chain(output_dir(&find_project_root()));
+    for root in roots {
+        if std::fs::canonicalize(&root).is_ok_and(|r| file.starts_with(r)) {
+            return Ok(());
+        }
+    }
+    Err(format!("只能打开任务产物或项目输出目录下的文件: {}", path))
+}
+
+/// 用外部查看器打开结果文件。`viewer` 可为应用路径或 "paraview"；
+/// 为空时依次按扩展名映射、检测到的 ParaView 选择，都没有则交给系统默认程序
+#[tauri::command]
+pub async fn open_in_viewer(
+    viewers: tauri::State<'_, ViewerRegistry>,
+    artifacts: tauri::State<'_, ArtifactRegistry>,
+    policy: tauri::State<'_, CommandPolicy>,
+    path: String,
+    viewer: Option<String>,
+    project: Option<String>,
+) -> Result<(), String> {
+    guarded("open_in_viewer", async move {
+        policy.ensure_writable("用外部程序打开文件")?;
+        let path = path.trim().to_string();
+        if !Path::new(&path).is_file() {
+            return Err("文件不存在".to_string());
+        }
+        check_viewable(&artifacts, &path, project.as_deref())?;
+        let ext = normalize_ext(
+            &Path::new(&path)
+                .extension()
+                .map(|e| e.to_string_lossy().into_owned())
+                .unwrap_or_default(),
+        );
+        let wants_paraview = match viewer.as_deref().map(str::trim) {
+            Some(v) if v.eq_ignore_ascii_case("paraview") => true,
+            Some(v) if !v.is_empty() => return launch(v, &path),
+            _ => false,
+        };
+        if !wants_paraview {
+            if let Some(app) = viewers.config().mapping.get(&ext) {
+                return launch(app, &path);
+            }
+            if !PARAVIEW_EXTS.contains(&ext.as_str()) {
+                return open_path(path).await;
+            }
+        }
+        let paraview = tauri::async_runtime::spawn_blocking(detect_paraview)
+            .await
+            .map_err(|e| e.to_string())?
+            .into_iter()
+            .next();
+        match paraview {
+            Some(pv) => launch(&pv.path, &path),
+            None if wants_paraview => Err("未检测到 ParaView 安装".to_string()),
+            None => open_path(path).await,
+        }
+    })
+    .await
+}
+
+fn launch(app: &str, path: &str) -> Result<(), String> {
+    #[cfg(target_os = "macos")]
+    if app.ends_with(".app") {
+        std::process::Command::new("open")
+            .args(["-a", app, path])
+            .spawn()
+            .map_err(|e| e.to_string())?;
+        return Ok(());
+    }
+    std::process::Command::new(app)
+        .arg(path)
+        .spawn()
+        .map_err(|e| format!("启动 {} 失败: {}", app, e))?;
+    Ok(())
+}
@@ -0,0 +1,186 @@
+use crate::panics::guarded;
+use crate::store::{load_json, save_json};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager, WebviewWindow, WindowEvent};
+
+const MIN_ZOOM: f64 = 0.5;
+const MAX_ZOOM: f64 = 3.0;
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct ZoomConfig {
+    /// 按显示器 DPI 缩放（如 "1.50"）保存的页面缩放比例
+    #[serde(default)]
+    pub by_dpi: BTreeMap<String, f64>,
+    /// 当前 DPI 没有记录时使用；未设置时为 1.0
+    #[serde(default)]
+    pub default: Option<f64>,
+}
+
+/// DPI 缩放保留两位小数作为键，避免浮点误差产生多个条目
+fn dpi_key(scale_factor: f64) -> String {
+    format!("{:.2}", scale_factor)
+}
+
+fn check_zoom(zoom: f64) -> Result<f64, String> {
+    if !zoom.is_finite() || !(MIN_ZOOM..=MAX_ZOOM).contains(&zoom) {
+        return Err(format!(
+            "缩放比例须在 {} ~ {} 之间: {}",
+            MIN_ZOOM, MAX_ZOOM, zoom
+        ));
+    }
+    Ok(zoom)
+}
+
+/// 窗口所在显示器的 DPI 缩放；取不到显示器时退回窗口自身的缩放
+fn window_scale(window: &WebviewWindow) -> f64 {
+    window
+        .current_monitor()
+        .ok()
+        .flatten()
+        .map(|m| m.scale_factor())
+        .or_else(|| window.scale_factor().ok())
+        .unwrap_or(1.0)
+}
+
+/// 按显示器 DPI 记住的界面缩放。实验室投影仪、4K 显示器与笔记本屏幕需要不同的缩放，
+/// 窗口创建或移到另一块 DPI 不同的显示器时由 Rust 层自动应用
+#[derive(Default)]
+pub struct ZoomSettings {
+    path: Option<PathBuf>,
+    config: Mutex<ZoomConfig>,
+}
+
+impl ZoomSettings {
+    pub fn load(path: PathBuf) -> Self {
+        let config: ZoomConfig = load_json(&path);
+        Self {
+            path: Some(path),
+            config: Mutex::new(config),
+        }
+    }
+
+    fn config(&self) -> ZoomConfig {
+        self.config
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    fn update(&self, f: impl FnOnce(&mut ZoomConfig)) -> Result<(), String> {
+        let mut config = self.config.lock().unwrap_or_else(|e| e.into_inner());
+        let mut next = config.clone();
+        f(&mut next);
+        if let Some(ref p) = self.path {
+            save_json(p, &next)?;
+        }
+        *config = next;
+        Ok(())
+    }
+
+    fn zoom_for(&self, scale_factor: f64) -> f64 {
+        let config = self.config();
+        config
+            .by_dpi
+            .get(&dpi_key(scale_factor))
+            .copied()
+            .or(config.default)
+            .unwrap_or(1.0)
+    }
+}
+
+fn apply_scale(app: &AppHandle, window: &WebviewWindow, scale_factor: f64) {
+    let zoom = app.state::<ZoomSettings>().zoom_for(scale_factor);
+    if let Err(e) = window.set_zoom(zoom) {
+        eprintln!("Warning: 设置窗口 {} 缩放失败: {}", window.label(), e);
+    }
+}
+
+/// 新建窗口后调用：应用当前显示器 DPI 对应的缩放，并在窗口移到 DPI 不同的显示器时重新应用
+pub fn attach(app: &AppHandle, window: &WebviewWindow) {
+    if app.try_state::<ZoomSettings>().is_none() {
+        return;
+    }
+    apply_scale(app, window, window_scale(window));
+    let (app, target) = (app.clone(), window.clone());
+    window.on_window_event(move |event| {
+        if let WindowEvent::ScaleFactorChanged { scale_factor, .. } = event {
+            apply_scale(&app, &target, *scale_factor);
+        }
+    });
+}
+
+/// 重新应用所有窗口的缩放（设置变更后）
+fn apply_all(app: &AppHandle) {
+    for window in app.webview_windows().values() {
+        apply_scale(app, window, window_scale(window));
+    }
+}
+
+/// 当前窗口所在显示器的 DPI、生效的缩放与全部已保存的缩放
+#[tauri::command]
+pub async fn window_zoom_get(
+    window: WebviewWindow,
+    zoom: tauri::State<'_, ZoomSettings>,
+) -> Result<serde_json::Value, String> {
+    guarded("window_zoom_get", async move {
+        let scale = window_scale(&window);
+        let config = zoom.config();
+        Ok(serde_json::json!({
+            "scale_factor": scale,
+            "dpi": dpi_key(scale),
+            "zoom": zoom.zoom_for(scale),
+            "by_dpi": config.by_dpi,
+            "default": config.default,
+        }))
+    })
+    .await
+}
+
+/// 设置并保存缩放：默认只作用于当前窗口所在显示器的 DPI，`allDpi` 为 true 时作为默认值
+#[tauri::command]
+pub async fn window_zoom_set(
+    app: AppHandle,
+    window: WebviewWindow,
+    zoom: f64,
+    all_dpi: Option<bool>,
+) -> Result<(), String> {
+    guarded("window_zoom_set", async move {
+        let zoom = check_zoom(zoom)?;
+        let key = dpi_key(window_scale(&window));
+        app.state::<ZoomSettings>().update(|c| {
+            if all_dpi.unwrap_or(false) {
+                c.default = Some(zoom);
+            } else {
+                c.by_dpi.insert(key, zoom);
+            }
+        })?;
+        apply_all(&app);
+        Ok(())
+    })
+    .await
+}
+
+/// 清除当前显示器 DPI 的缩放记录；`allDpi` 为 true 时清除全部记录与默认值
+#[tauri::command]
+pub async fn window_zoom_reset(
+    app: AppHandle,
+    window: WebviewWindow,
+    all_dpi: Option<bool>,
+) -> Result<(), String> {
+    guarded("window_zoom_reset", async move {
+        let key = dpi_key(window_scale(&window));
+        app.state::<ZoomSettings>().update(|c| {
+            if all_dpi.unwrap_or(false) {
+                *c = ZoomConfig::default();
+            } else {
+                c.by_dpi.remove(&key);
+            }
+        })?;
+        apply_all(&app);
+        Ok(())
+    })
+    .await
+}
@@ -1,7 +1,7 @@
 import { useCallback, useEffect, useState } from "react";
 import { invoke } from "@tauri-apps/api/core";
 import { useAppState } from "./context/AppStateContext";
-import type { AppView } from "./lib/types";
+import type { AppView, BridgeError } from "./lib/types";
 import { Sidebar } from "./components/Sidebar";
 import { Session } from "./components/Session";
 import { CaseLibraryPage } from "./components/CaseLibraryPage";
@@ -18,6 +18,7 @@ import { SettingsDialog } from "./components/dialogs/SettingsDialog";
 import { ComsolOpsDialog } from "./components/dialogs/ComsolOpsDialog";
 import { ApiBrowserDialog } from "./components/dialogs/ApiBrowserDialog";
 import { PlanQuestionsDialog } from "./components/dialogs/PlanQuestionsDialog";
+import { PermissionPrompt } from "./components/dialogs/PermissionPrompt";
 
 interface BridgeInitStatus {
   ready: boolean;
@@ -48,10 +49,10 @@ export default function App() {
   const refreshBridgeStatus = useCallback(async (ensureReady = false) => {
     const command = ensureReady ? "bridge_ensure_ready" : "bridge_init_status";
     try {
-      const res = await invoke<{ ready: boolean; error: string | null; initializing?: boolean }>(command);
+      const res = await invoke<{ ready: boolean; error: BridgeError | null; initializing?: boolean }>(command);
       setBridgeStatus({
         ready: res.ready,
-        error: res.error ?? null,
+        error: res.error?.message ?? null,
         initializing: res.initializing ?? false,
       });
     } catch {
@@ -134,6 +135,7 @@ export default function App() {
           {dialogContent}
         </DialogOverlay>
       )}
+      <PermissionPrompt />
     </div>
   );
 }
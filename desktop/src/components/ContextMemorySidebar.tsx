@@ -1,6 +1,7 @@
 import { useCallback, useEffect, useMemo, useRef, useState } from "react";
 import { invoke } from "@tauri-apps/api/core";
 import { useAppState } from "../context/AppStateContext";
+import { errorMessage } from "../lib/api";
 
 const MEMORY_SIDEBAR_COLLAPSED_KEY = "mph-agent-memory-sidebar-collapsed";
 
@@ -106,7 +107,7 @@ export function ContextMemorySidebar({ embedded = false }: ContextMemorySidebarP
       setPreview((current) => ({
         ...current,
         loading: false,
-        error: String(error),
+        error: errorMessage(error),
         updatedAt: Date.now(),
       }));
     }
@@ -0,0 +1,174 @@
+import { useCallback, useEffect, useRef, useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import { listen } from "@tauri-apps/api/event";
+import type { TrafficLine } from "../lib/types";
+
+type DirectionFilter = "" | TrafficLine["direction"];
+
+/** 前端最多保留的行数，与后端历史上限一致 */
+const MAX_LINES = 2000;
+
+const ARROWS: Record<TrafficLine["direction"], string> = {
+  out: "→",
+  in: "←",
+  err: "!",
+};
+
+function formatTime(ts: number): string {
+  const d = new Date(ts);
+  const pad = (n: number, w = 2) => String(n).padStart(w, "0");
+  const time = `${pad(d.getHours())}:${pad(d.getMinutes())}:${pad(d.getSeconds())}`;
+  return `${time}.${pad(d.getMilliseconds(), 3)}`;
+}
+
+function matches(line: TrafficLine, direction: DirectionFilter, filter: string): boolean {
+  if (direction && line.direction !== direction) return false;
+  const f = filter.trim().toLowerCase();
+  return !f || line.line.toLowerCase().includes(f);
+}
+
+/** Bridge 调试控制台窗口（`index.html#/debug-console`）：实时显示 bridge 原始流量 */
+export function DebugConsole() {
+  const [lines, setLines] = useState<TrafficLine[]>([]);
+  const [paused, setPaused] = useState(false);
+  const [direction, setDirection] = useState<DirectionFilter>("");
+  const [filter, setFilter] = useState("");
+  const [follow, setFollow] = useState(true);
+  // 已显示的最大 seq；清空后只看之后的流量
+  const lastSeq = useRef(0);
+  const clearedAt = useRef(0);
+  const pausedRef = useRef(false);
+  const filterRef = useRef({ direction, filter });
+  const listRef = useRef<HTMLDivElement>(null);
+
+  const append = useCallback((incoming: TrafficLine[]) => {
+    const fresh = incoming.filter((l) => l.seq > lastSeq.current);
+    if (fresh.length === 0) return;
+    lastSeq.current = fresh[fresh.length - 1].seq;
+    setLines((prev) => prev.concat(fresh).slice(-MAX_LINES));
+  }, []);
+
+  /** 按当前过滤条件从后端历史重新加载 */
+  const reload = useCallback(() => {
+    const { direction: dir, filter: text } = filterRef.current;
+    invoke<TrafficLine[]>("debug_console_history", {
+      sinceSeq: clearedAt.current,
+      direction: dir || null,
+      filter: text.trim() || null,
+    })
+      .then((history) => {
+        lastSeq.current = clearedAt.current;
+        setLines([]);
+        append(history);
+      })
+      .catch(() => {});
+  }, [append]);
+
+  useEffect(() => {
+    filterRef.current = { direction, filter };
+    reload();
+  }, [direction, filter, reload]);
+
+  useEffect(() => {
+    const unlisten = listen<TrafficLine>("bridge-traffic", (event) => {
+      if (pausedRef.current) return;
+      const { direction: dir, filter: text } = filterRef.current;
+      if (!matches(event.payload, dir, text)) {
+        lastSeq.current = Math.max(lastSeq.current, event.payload.seq);
+        return;
+      }
+      append([event.payload]);
+    });
+    return () => {
+      unlisten.then((f) => f());
+    };
+  }, [append]);
+
+  useEffect(() => {
+    if (follow && listRef.current) {
+      listRef.current.scrollTop = listRef.current.scrollHeight;
+    }
+  }, [lines, follow]);
+
+  const togglePause = () => {
+    const next = !paused;
+    pausedRef.current = next;
+    setPaused(next);
+    invoke("debug_console_set_paused", { paused: next }).catch(() => {});
+    // 恢复时补齐暂停期间的流量
+    if (!next) {
+      const { direction: dir, filter: text } = filterRef.current;
+      invoke<TrafficLine[]>("debug_console_history", {
+        sinceSeq: lastSeq.current,
+        direction: dir || null,
+        filter: text.trim() || null,
+      })
+        .then(append)
+        .catch(() => {});
+    }
+  };
+
+  const clear = () => {
+    clearedAt.current = lastSeq.current;
+    setLines([]);
+  };
+
+  const close = () => {
+    invoke("debug_console_close").catch(() => {});
+  };
+
+  const onScroll = () => {
+    const el = listRef.current;
+    if (el) setFollow(el.scrollHeight - el.scrollTop - el.clientHeight < 24);
+  };
+
+  return (
+    <div className="debug-console">
+      <div className="debug-console-toolbar">
+        <select
+          value={direction}
+          onChange={(e) => setDirection(e.target.value as DirectionFilter)}
+          title="方向"
+        >
+          <option value="">全部</option>
+          <option value="out">发送</option>
+          <option value="in">接收</option>
+          <option value="err">stderr</option>
+        </select>
+        <input
+          type="search"
+          className="debug-console-filter"
+          placeholder="过滤关键字"
+          value={filter}
+          onChange={(e) => setFilter(e.target.value)}
+        />
+        <button type="button" onClick={togglePause}>
+          {paused ? "继续" : "暂停"}
+        </button>
+        <button type="button" onClick={clear}>
+          清空
+        </button>
+        <span className="debug-console-count">{lines.length} 行</span>
+        <button type="button" className="debug-console-close" onClick={close} title="关闭">
+          ×
+        </button>
+      </div>
+      <div className="debug-console-lines" ref={listRef} onScroll={onScroll}>
+        {lines.length === 0 ? (
+          <div className="debug-console-empty">暂无流量</div>
+        ) : (
+          lines.map((l) => (
+            <div key={l.seq} className={`debug-console-line ${l.direction}`}>
+              <span className="debug-console-time">{formatTime(l.ts)}</span>
+              <span className="debug-console-dir">{ARROWS[l.direction]}</span>
+              <span className="debug-console-text">
+                {l.line}
+                {l.truncated && <span className="debug-console-truncated">（已截断）</span>}
+              </span>
+            </div>
+          ))
+        )}
+      </div>
+    </div>
+  );
+}
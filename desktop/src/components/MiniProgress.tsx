@@ -0,0 +1,74 @@
+import { useEffect, useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import { listen } from "@tauri-apps/api/event";
+import type { JobProgress } from "../lib/types";
+
+function formatEta(ms: number): string {
+  const secs = Math.round(ms / 1000);
+  if (secs < 60) return `约 ${secs} 秒`;
+  const mins = Math.round(secs / 60);
+  if (mins < 60) return `约 ${mins} 分钟`;
+  return `约 ${Math.floor(mins / 60)} 小时 ${mins % 60} 分钟`;
+}
+
+/** 置顶迷你进度窗口（`index.html#/mini-progress`）：只显示最近更新的任务 */
+export function MiniProgress() {
+  const [progress, setProgress] = useState<JobProgress | null>(null);
+
+  useEffect(() => {
+    invoke<JobProgress | null>("job_progress_current")
+      .then((p) => setProgress((prev) => prev ?? p))
+      .catch(() => {});
+    const unlisten = listen<JobProgress>("job-progress", (event) => {
+      setProgress(event.payload);
+    });
+    return () => {
+      unlisten.then((f) => f());
+    };
+  }, []);
+
+  const close = () => {
+    invoke("progress_window_close").catch(() => {});
+  };
+
+  if (!progress) {
+    return (
+      <div className="mini-progress" data-tauri-drag-region>
+        <div className="mini-progress-stage">暂无进行中的任务</div>
+        <button type="button" className="mini-progress-close" onClick={close} title="关闭">
+          ×
+        </button>
+      </div>
+    );
+  }
+
+  const percent = Math.max(0, Math.min(100, progress.percent));
+  const status = progress.finished
+    ? progress.ok === false
+      ? "失败"
+      : "完成"
+    : progress.eta_ms != null
+      ? `剩余${formatEta(progress.eta_ms)}`
+      : `${percent.toFixed(0)}%`;
+
+  return (
+    <div
+      className={`mini-progress${progress.finished ? (progress.ok === false ? " failed" : " done") : ""}`}
+      data-tauri-drag-region
+    >
+      <div className="mini-progress-head" data-tauri-drag-region>
+        <span className="mini-progress-stage" title={progress.message ?? undefined}>
+          {progress.cmd} · {progress.stage}
+        </span>
+        <span className="mini-progress-status">{status}</span>
+        <button type="button" className="mini-progress-close" onClick={close} title="关闭">
+          ×
+        </button>
+      </div>
+      <div className="mini-progress-bar">
+        <div className="mini-progress-fill" style={{ width: `${percent}%` }} />
+      </div>
+      {progress.message && <div className="mini-progress-message">{progress.message}</div>}
+    </div>
+  );
+}
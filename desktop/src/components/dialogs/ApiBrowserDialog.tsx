@@ -1,6 +1,6 @@
 import { useEffect, useMemo, useState } from "react";
 import type { ApiWrapperItem } from "../../lib/api";
-import { listOfficialApis } from "../../lib/api";
+import { errorMessage, listOfficialApis } from "../../lib/api";
 import { useAppState } from "../../context/AppStateContext";
 
 interface ApiBrowserDialogProps {
@@ -27,7 +27,7 @@ export function ApiBrowserDialog({ onClose }: ApiBrowserDialogProps) {
         setItems(res.apis ?? []);
       })
       .catch((e) => {
-        setError(String(e));
+        setError(errorMessage(e));
         setItems([]);
       })
       .finally(() => setLoading(false));
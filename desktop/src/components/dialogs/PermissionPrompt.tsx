@@ -0,0 +1,86 @@
+import { useEffect, useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import { listen } from "@tauri-apps/api/event";
+import type { HostAction, PermissionRequest } from "../../lib/types";
+import { DialogOverlay } from "./DialogOverlay";
+
+function describe(action: HostAction): { title: string; target: string; cwd?: string | null } {
+  switch (action.action) {
+    case "open_url":
+      return { title: "打开网址", target: action.url };
+    case "read_file":
+      return { title: "读取工作区外的文件", target: action.path };
+    case "run_shell":
+      return { title: "运行命令", target: action.command, cwd: action.cwd };
+  }
+}
+
+/** agent 请求宿主执行操作时的授权对话框；多条询问依次处理，关闭对话框视为拒绝 */
+export function PermissionPrompt() {
+  const [queue, setQueue] = useState<PermissionRequest[]>([]);
+  const [remember, setRemember] = useState(false);
+
+  useEffect(() => {
+    const drop = (promptId: string) =>
+      setQueue((q) => q.filter((r) => r.prompt_id !== promptId));
+    const unlistens = [
+      listen<PermissionRequest>("permission-request", (event) => {
+        setQueue((q) => [...q, event.payload]);
+      }),
+      listen<{ prompt_id: string }>("permission-expired", (event) => {
+        drop(event.payload.prompt_id);
+      }),
+      listen<{ prompt_id: string }>("permission-resolved", (event) => {
+        drop(event.payload.prompt_id);
+      }),
+    ];
+    return () => {
+      unlistens.forEach((u) => u.then((f) => f()));
+    };
+  }, []);
+
+  const current = queue[0];
+  if (!current) return null;
+
+  const respond = (allow: boolean) => {
+    const promptId = current.prompt_id;
+    setQueue((q) => q.filter((r) => r.prompt_id !== promptId));
+    setRemember(false);
+    invoke("permission_respond", { promptId, allow, remember }).catch(() => {});
+  };
+
+  const { title, target, cwd } = describe(current.action);
+
+  return (
+    <DialogOverlay onClose={() => respond(false)}>
+      <div className="dialog-header">Agent 请求：{title}</div>
+      <div className="dialog-body confirm-dialog-body">
+        <p className="confirm-dialog-message">
+          <code>{target}</code>
+        </p>
+        {cwd && <p className="confirm-dialog-message">工作目录：{cwd}</p>}
+        {current.reason && <p className="confirm-dialog-message">用途：{current.reason}</p>}
+        <label className="permission-prompt-remember">
+          <input
+            type="checkbox"
+            checked={remember}
+            onChange={(e) => setRemember(e.target.checked)}
+          />
+          {current.project ? `在项目「${current.project}」中记住此决定` : "记住此决定"}
+        </label>
+        <p className="permission-prompt-hint">
+          {current.timeout_secs} 秒内未答复按拒绝处理
+          {queue.length > 1 ? `；还有 ${queue.length - 1} 条请求等待处理` : ""}
+        </p>
+      </div>
+      <div className="dialog-actions confirm-dialog-actions">
+        <button type="button" className="dialog-btn secondary" onClick={() => respond(false)}>
+          拒绝
+        </button>
+        <button type="button" className="dialog-btn danger" onClick={() => respond(true)}>
+          允许
+        </button>
+      </div>
+    </DialogOverlay>
+  );
+}
@@ -18,6 +18,7 @@ import {
   type ProviderCatalogEntry,
 } from "../../lib/apiConfig";
 import type { BridgeResponse, MyComsolModel } from "../../lib/types";
+import { errorMessage } from "../../lib/api";
 import {
   clearCaseLibraryRecords,
   loadCaseLibraryRecords,
@@ -215,7 +216,7 @@ export function SettingsDialog({
           setStatus(res.ok ? `已保存：${res.message}` : res.message);
         }
       } catch (error) {
-        setStatus(`同步失败：${String(error)}`);
+        setStatus(`同步失败：${errorMessage(error)}`);
       }
 
       window.setTimeout(() => setStatus(""), 6500);
@@ -314,7 +315,7 @@ export function SettingsDialog({
       });
       setOllamaTestResult({ ok: res.ok, msg: res.message });
     } catch (error) {
-      setOllamaTestResult({ ok: false, msg: String(error) });
+      setOllamaTestResult({ ok: false, msg: errorMessage(error) });
     }
   }, [apiConfig]);
 
@@ -332,7 +333,7 @@ export function SettingsDialog({
       setMemoryItems(res.ok ? parseMemoryItems(res.message) : []);
       setMemoryStatus(res.ok ? "已加载" : res.message);
     } catch (error) {
-      setMemoryStatus(`加载失败：${String(error)}`);
+      setMemoryStatus(`加载失败：${errorMessage(error)}`);
     }
     window.setTimeout(() => setMemoryStatus(""), 3000);
   }, [cid]);
@@ -350,7 +351,7 @@ export function SettingsDialog({
       });
       setMemoryStatus(res.ok ? "记忆已保存" : res.message);
     } catch (error) {
-      setMemoryStatus(`保存失败：${String(error)}`);
+      setMemoryStatus(`保存失败：${errorMessage(error)}`);
     }
     window.setTimeout(() => setMemoryStatus(""), 3000);
   }, [cid, serializedMemoryText]);
@@ -367,7 +368,7 @@ export function SettingsDialog({
       setMemoryItems([]);
       setMemoryStatus(res.ok ? "已清除" : res.message);
     } catch (error) {
-      setMemoryStatus(`清除失败：${String(error)}`);
+      setMemoryStatus(`清除失败：${errorMessage(error)}`);
     }
     window.setTimeout(() => setMemoryStatus(""), 3000);
   }, [cid]);
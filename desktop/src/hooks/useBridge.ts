@@ -10,6 +10,7 @@ import type {
   PromptExtensionName,
 } from "../lib/types";
 import { normalizeClarifyingQuestions } from "../lib/clarifying";
+import { errorMessage } from "../lib/api";
 
 function extractClarifyingQuestionsFromResponse(
   res: BridgeResponse
@@ -84,7 +85,7 @@ export function useBridge() {
         });
         return res;
       } catch (e) {
-        addMessage("assistant", "请求失败: " + String(e), { success: false });
+        addMessage("assistant", "请求失败: " + errorMessage(e), { success: false });
         return null;
       } finally {
         dispatch({ type: "SET_BUSY_CONVERSATION", conversationId: null });
@@ -149,7 +150,7 @@ export function useBridge() {
           dispatch({
             type: "FINALIZE_LAST",
             conversationId: cid,
-            text: "请求失败: " + String(e),
+            text: "请求失败: " + errorMessage(e),
             success: false,
           });
         }
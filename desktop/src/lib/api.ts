@@ -1,5 +1,13 @@
 import { invoke } from "@tauri-apps/api/core";
-import type { BridgeResponse } from "./types";
+import type { BridgeError, BridgeResponse } from "./types";
+
+/** invoke 抛出的错误转为可展示的文本；bridge 命令抛出 BridgeError，其他命令抛出字符串 */
+export function errorMessage(e: unknown): string {
+  if (e && typeof e === "object" && typeof (e as BridgeError).message === "string") {
+    return (e as BridgeError).message;
+  }
+  return String(e);
+}
 
 export interface ApiWrapperItem {
   wrapper_name: string;
@@ -1,4 +1,5 @@
 import { invoke } from "@tauri-apps/api/core";
+import { errorMessage } from "./api";
 
 export const CASE_LIBRARY_TARGET_VERSION = "COMSOL 6.3";
 
@@ -247,7 +248,7 @@ export async function fetchCaseLibrary(
   } catch (error) {
     return buildResult([], {
       ok: false,
-      message: String(error),
+      message: errorMessage(error),
       metadata: {},
     });
   }
@@ -1,4 +1,5 @@
 import { invoke } from "@tauri-apps/api/core";
+import { errorMessage } from "./api";
 import type { BridgeResponse, OpsCatalogItem } from "./types";
 
 interface OpsCatalogResponse extends BridgeResponse {
@@ -51,7 +52,7 @@ export async function fetchOpsCatalog(options?: {
   } catch (error) {
     return {
       ok: false,
-      message: String(error),
+      message: errorMessage(error),
       items: [],
       total: 0,
       limit,
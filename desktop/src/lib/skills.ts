@@ -1,4 +1,5 @@
 import { invoke } from "@tauri-apps/api/core";
+import { errorMessage } from "./api";
 
 export interface LocalSkillLibraryItem {
   id: string;
@@ -116,7 +117,7 @@ export async function fetchLocalSkillLibraries(): Promise<{
       total: typeof res.total === "number" ? res.total : items.length,
     };
   } catch (error) {
-    return { ok: false, message: String(error), items: [], total: 0 };
+    return { ok: false, message: errorMessage(error), items: [], total: 0 };
   }
 }
 
@@ -139,7 +140,7 @@ export async function createLocalSkillLibrary(input: {
       item: res.item ? normalizeLocalSkill(res.item) : null,
     };
   } catch (error) {
-    return { ok: false, message: String(error), item: null };
+    return { ok: false, message: errorMessage(error), item: null };
   }
 }
 
@@ -156,7 +157,7 @@ export async function importLocalSkillLibrary(sourcePath: string): Promise<{
       item: res.item ? normalizeLocalSkill(res.item) : null,
     };
   } catch (error) {
-    return { ok: false, message: String(error), item: null };
+    return { ok: false, message: errorMessage(error), item: null };
   }
 }
 
@@ -180,6 +181,6 @@ export async function fetchOnlineSkillLibrary(): Promise<{
       total: typeof res.total === "number" ? res.total : items.length,
     };
   } catch (error) {
-    return { ok: false, message: String(error), items: [], total: 0 };
+    return { ok: false, message: errorMessage(error), items: [], total: 0 };
   }
 }
@@ -58,6 +58,78 @@ export interface ConversationGroup {
   createdAt: number;
 }
 
+/** bridge 命令失败时 invoke 抛出的结构化错误 */
+export interface BridgeError {
+  kind:
+    | "not_initialized"
+    | "starting"
+    | "incompatible"
+    | "spawn_failed"
+    | "timeout"
+    | "protocol_error"
+    | "child_exited"
+    | "resource_limit"
+    | "aborted"
+    | "rejected"
+    | "panic";
+  message: string;
+  /** child_exited / resource_limit：子进程退出码，被信号终止时为 null */
+  code?: number | null;
+  /** incompatible：双方协议版本，bridge 未回报时为 null */
+  host_protocol?: number;
+  bridge_protocol?: number | null;
+  /** starting：建议的重试间隔（毫秒） */
+  retry_after_ms?: number;
+  /** panic：出错的命令 */
+  command?: string;
+}
+
+/** `job-progress` 事件与 job_progress_current 返回的任务进度 */
+export interface JobProgress {
+  request_id: string;
+  cmd: string;
+  stage: string;
+  percent: number;
+  message: string | null;
+  started_at: number;
+  updated_at: number;
+  finished: boolean;
+  ok: boolean | null;
+  /** 预计剩余毫秒数 */
+  eta_ms: number | null;
+  eta_basis: string | null;
+  eta_samples: number;
+}
+
+/** bridge 请求宿主执行的操作（`permission-request` 事件中的 action） */
+export type HostAction =
+  | { action: "open_url"; url: string }
+  | { action: "read_file"; path: string }
+  | { action: "run_shell"; command: string; cwd?: string | null };
+
+/** 宿主操作的授权询问（`permission-request` 事件），超时按拒绝处理 */
+export interface PermissionRequest {
+  prompt_id: string;
+  request_id: string;
+  project: string | null;
+  /** 记住决定时使用的规则 */
+  rule: string;
+  action: HostAction;
+  reason?: string | null;
+  timeout_secs: number;
+}
+
+/** 调试控制台中的一行 bridge 原始流量（`bridge-traffic` 事件） */
+export interface TrafficLine {
+  seq: number;
+  ts: number;
+  /** out: 写入 bridge stdin；in: 从 stdout 读到；err: stderr */
+  direction: "out" | "in" | "err";
+  line: string;
+  /** 超长行被截断 */
+  truncated?: boolean;
+}
+
 /** 后端 bridge_send 返回 */
 export interface BridgeResponse {
   ok: boolean;
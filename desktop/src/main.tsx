@@ -4,6 +4,8 @@ import { MathJaxContext } from "better-react-mathjax";
 import { AppStateProvider } from "./context/AppStateContext";
 import { ThemeProvider, initTheme } from "./context/ThemeContext";
 import App from "./App";
+import { MiniProgress } from "./components/MiniProgress";
+import { DebugConsole } from "./components/DebugConsole";
 
 import "@fontsource/fraunces/400.css";
 import "@fontsource/fraunces/600.css";
@@ -21,30 +23,48 @@ import "./styles/hermes.css";
 
 initTheme();
 
+/** 独立小窗口按 URL hash 选择视图（见 src-tauri 的 progress_window.rs、debug_console.rs），不加载主界面状态 */
+function standaloneView(hash: string) {
+  switch (hash) {
+    case "#/mini-progress":
+      return <MiniProgress />;
+    case "#/debug-console":
+      return <DebugConsole />;
+    default:
+      return null;
+  }
+}
+
+const standalone = standaloneView(window.location.hash);
+
 ReactDOM.createRoot(document.getElementById("root")!).render(
   <React.StrictMode>
-    <MathJaxContext
-      version={3}
-      config={{
-        loader: { load: ["[tex]/ams"] },
-        tex: {
-          inlineMath: [
-            ["$", "$"],
-            ["\\(", "\\)"],
-          ],
-          displayMath: [
-            ["$$", "$$"],
-            ["\\[", "\\]"],
-          ],
-          packages: { "[+]": ["ams"] },
-        },
-      }}
-    >
-      <AppStateProvider>
-        <ThemeProvider>
-          <App />
-        </ThemeProvider>
-      </AppStateProvider>
-    </MathJaxContext>
+    {standalone ? (
+      <ThemeProvider>{standalone}</ThemeProvider>
+    ) : (
+      <MathJaxContext
+        version={3}
+        config={{
+          loader: { load: ["[tex]/ams"] },
+          tex: {
+            inlineMath: [
+              ["$", "$"],
+              ["\\(", "\\)"],
+            ],
+            displayMath: [
+              ["$$", "$$"],
+              ["\\[", "\\]"],
+            ],
+            packages: { "[+]": ["ams"] },
+          },
+        }}
+      >
+        <AppStateProvider>
+          <ThemeProvider>
+            <App />
+          </ThemeProvider>
+        </AppStateProvider>
+      </MathJaxContext>
+    )}
   </React.StrictMode>
 );
@@ -0,0 +1,75 @@
+"""远程 bridge 服务单元测试：令牌校验与 TCP 转发（用回显脚本代替真实 bridge）。"""
+import asyncio
+import json
+import sys
+
+from agent.run.bridge_server import check_token, is_loopback, serve_tcp
+
+# 先发就绪信号，之后逐行回显 stdin
+ECHO_BRIDGE = [
+    sys.executable,
+    "-c",
+    "import sys\n"
+    "sys.stdout.write('{\"_ready\":true}\\n'); sys.stdout.flush()\n"
+    "for line in sys.stdin:\n"
+    "    sys.stdout.write(line); sys.stdout.flush()\n",
+]
+
+
+async def _exchange(token, lines):
+    server = await serve_tcp("127.0.0.1", 0, "secret", command=ECHO_BRIDGE)
+    port = server.sockets[0].getsockname()[1]
+    try:
+        reader, writer = await asyncio.open_connection("127.0.0.1", port)
+        writer.write((json.dumps({"token": token}) + "\n").encode())
+        await writer.drain()
+        out = [json.loads(await asyncio.wait_for(reader.readline(), 10))]
+        if out[0].get("ok"):
+            out.append(json.loads(await asyncio.wait_for(reader.readline(), 10)))
+            for line in lines:
+                writer.write((line + "\n").encode())
+                await writer.drain()
+                out.append(json.loads(await asyncio.wait_for(reader.readline(), 10)))
+        writer.close()
+        await writer.wait_closed()
+        # 让服务端处理完断开、回收回显进程
+        await asyncio.sleep(0.5)
+        return out
+    finally:
+        server.close()
+        await server.wait_closed()
+
+
+class TestCheckToken:
+    """令牌校验"""
+
+    def test_no_token_configured_allows_any(self):
+        assert check_token(None, None)
+        assert check_token("", "anything")
+
+    def test_token_must_match(self):
+        assert check_token("secret", "secret")
+        assert not check_token("secret", "Secret")
+        assert not check_token("secret", None)
+        assert not check_token("secret", 123)
+
+    def test_loopback(self):
+        assert is_loopback("127.0.0.1")
+        assert is_loopback("::1")
+        assert is_loopback("localhost")
+        assert not is_loopback("0.0.0.0")
+        assert not is_loopback("192.168.1.10")
+
+
+class TestServeTcp:
+    """TCP 转发"""
+
+    def test_relays_bridge_stream(self):
+        out = asyncio.run(_exchange("secret", ['{"cmd":"ping","_id":"1"}']))
+        assert out[0] == {"ok": True}
+        assert out[1] == {"_ready": True}
+        assert out[2] == {"cmd": "ping", "_id": "1"}
+
+    def test_rejects_bad_token(self):
+        out = asyncio.run(_exchange("wrong", []))
+        assert out[0]["ok"] is False
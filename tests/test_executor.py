@@ -55,3 +55,57 @@ class TestDoExecFromFile:
         ok, msg = do_exec_from_file(tmp_path / "nonexistent.json", verbose=False)
         assert ok is False
         assert msg
+
+
+@pytest.fixture
+def fake_server_runner(monkeypatch):
+    """COMSOLRunner 视为 JVM 已启动，ModelUtil.connect/disconnect 只记录调用。"""
+    from agent.executor import comsol_runner
+
+    calls = []
+
+    class _ModelUtil:
+        @staticmethod
+        def connect(host, port):
+            calls.append(("connect", host, port))
+
+        @staticmethod
+        def disconnect():
+            calls.append(("disconnect",))
+
+    fake = Mock()
+    fake.JClass.return_value = _ModelUtil
+    monkeypatch.setattr(comsol_runner, "_jpype", lambda: fake)
+    cls = comsol_runner.COMSOLRunner
+    for name, value in (("_jvm_started", True), ("_server", None), ("_connected", None), ("_standalone", False)):
+        monkeypatch.setattr(cls, name, value)
+    return cls, calls
+
+
+class TestComsolServer:
+    """COMSOLRunner 连接 COMSOL server（comsol_server 字段）"""
+
+    def test_connects_on_first_use(self, fake_server_runner):
+        cls, calls = fake_server_runner
+        cls.use_server("127.0.0.1", 2036)
+        assert calls == []  # 仅记录目标，首次使用 COMSOL 时才连接
+        cls._ensure_jvm_started()
+        cls._ensure_jvm_started()
+        assert calls == [("connect", "127.0.0.1", 2036)]
+
+    def test_switches_to_project_server(self, fake_server_runner):
+        """各项目保存的连接不同：切换项目后下次使用前断开旧 server 再连接新的"""
+        cls, calls = fake_server_runner
+        cls.use_server("127.0.0.1", 2036)
+        cls._ensure_jvm_started()
+        cls.use_server("10.0.0.2", "2037")
+        cls._ensure_jvm_started()
+        assert calls == [("connect", "127.0.0.1", 2036), ("disconnect",), ("connect", "10.0.0.2", 2037)]
+
+    def test_standalone_session_refuses_server(self, fake_server_runner, monkeypatch):
+        cls, calls = fake_server_runner
+        monkeypatch.setattr(cls, "_standalone", True)
+        cls.use_server("127.0.0.1", 2036)
+        with pytest.raises(RuntimeError, match="重启 bridge"):
+            cls._ensure_jvm_started()
+        assert calls == []
@@ -0,0 +1,41 @@
+"""文件名清理单元测试：非法字符、Windows 保留名、结尾的点与空格、超长名称。"""
+from agent.utils.filenames import sanitize_filename
+
+
+class TestSanitizeFilename:
+    """sanitize_filename 规则"""
+
+    def test_plain_name_unchanged(self):
+        assert sanitize_filename("heat_sink.mph") == "heat_sink.mph"
+        assert sanitize_filename("散热器 模型.mph") == "散热器 模型.mph"
+
+    def test_unsafe_chars_replaced(self):
+        assert sanitize_filename('a<b>:c"d|e?f*.mph') == "a_b__c_d_e_f_.mph"
+        assert sanitize_filename("dir/name\\x.mph") == "dir_name_x.mph"
+        assert sanitize_filename("tab\there.mph") == "tab_here.mph"
+
+    def test_trailing_dots_and_spaces(self):
+        assert sanitize_filename("  model. . ") == "model"
+        assert sanitize_filename("model.mph.") == "model.mph"
+
+    def test_reserved_names(self):
+        assert sanitize_filename("CON") == "_CON"
+        assert sanitize_filename("nul.mph") == "_nul.mph"
+        assert sanitize_filename("Com1.tar.gz") == "_Com1.tar.gz"
+        assert sanitize_filename("LPT¹.txt") == "_LPT¹.txt"
+        assert sanitize_filename("console.mph") == "console.mph"
+
+    def test_empty_uses_fallback(self):
+        assert sanitize_filename("") == "model"
+        assert sanitize_filename("...", fallback="untitled") == "untitled"
+        assert sanitize_filename(".mph") == ".mph"
+
+    def test_too_long_keeps_extension(self):
+        out = sanitize_filename("模" * 200 + ".mph")
+        assert out.endswith(".mph")
+        assert len(out.encode("utf-8")) <= 255
+        assert out[:-4] == "模" * ((255 - 4) // 3)
+
+    def test_custom_max_bytes(self):
+        out = sanitize_filename("a" * 300, max_bytes=200)
+        assert out == "a" * 200
@@ -1,6 +1,7 @@
 """Tests for JavaAPIController extensions added for staged workflow."""
 
 from pathlib import Path
+from types import SimpleNamespace
 
 import pytest
 
@@ -14,6 +15,10 @@ def controller(monkeypatch):
         def __init__(self, *args, **kwargs):
             pass
 
+        @classmethod
+        def _ensure_jvm_started(cls):
+            pass
+
     monkeypatch.setattr(jac, "COMSOLRunner", _DummyRunner)
     return JavaAPIController()
 
@@ -168,3 +173,128 @@ def test_extract_model_operation_case_builds_sectioned_analysis(controller, tmp_
     assert case["design_intent"]
     assert "原始模型路径" in case["copy_edit_prompt"]
     assert case["context_block"]
+
+
+class _FakeModelUtil:
+    """记录 load/remove 调用；save 按扩展名写出脚本文本。"""
+
+    loaded: list = []
+    removed: list = []
+
+    @classmethod
+    def load(cls, tag, path):
+        cls.loaded.append((tag, path))
+
+        class _Model:
+            def save(self, out):
+                Path(out).write_text(f"// {Path(out).name}\n", encoding="utf-8")
+
+        return _Model()
+
+    @classmethod
+    def remove(cls, tag):
+        cls.removed.append(tag)
+
+
+@pytest.fixture
+def fake_jpype(monkeypatch):
+    _FakeModelUtil.loaded, _FakeModelUtil.removed = [], []
+
+    class _Jpype:
+        @staticmethod
+        def JClass(name):
+            assert name == "com.comsol.model.util.ModelUtil"
+            return _FakeModelUtil
+
+    monkeypatch.setattr(jac, "_jpype", lambda: _Jpype)
+    return _FakeModelUtil
+
+
+def test_export_model_script_returns_code_and_removes_model(controller, fake_jpype, tmp_path):
+    model_path = tmp_path / "2d heat.mph"
+    model_path.write_text("dummy", encoding="utf-8")
+
+    res = controller.export_model_script(str(model_path), "java")
+    assert res["status"] == "success"
+    # 文件名作为 Java 类名：非法字符替换，数字开头加前缀
+    assert res["code"] == "// model_2d_heat.java\n"
+    assert fake_jpype.removed == [fake_jpype.loaded[0][0]]
+
+    res = controller.export_model_script(str(model_path), "matlab")
+    assert res["code"].startswith("// model_2d_heat.m")
+
+
+def test_export_model_script_rejects_bad_input(controller, fake_jpype, tmp_path):
+    res = controller.export_model_script(str(tmp_path / "missing.mph"), "java")
+    assert res["status"] == "error"
+    model_path = tmp_path / "demo.mph"
+    model_path.write_text("dummy", encoding="utf-8")
+    res = controller.export_model_script(str(model_path), "vba")
+    assert res["status"] == "error"
+    assert fake_jpype.loaded == []
+
+
+def test_list_library_materials_reads_install_libraries(controller, monkeypatch, tmp_path):
+    plugins = tmp_path / "plugins"
+    plugins.mkdir()
+    lib_dir = tmp_path / "data" / "mph" / "materials"
+    lib_dir.mkdir(parents=True)
+    (lib_dir / "Built-in.mph").write_text("dummy", encoding="utf-8")
+    controller.settings = SimpleNamespace(comsol_jar_path=str(plugins))
+
+    class _Group:
+        def properties(self):
+            return ["thermalconductivity"]
+
+        def getString(self, name):
+            return "400[W/(m*K)]"
+
+    class _Material:
+        def label(self):
+            return "Copper"
+
+        def propertyGroup(self, name):
+            assert name == "def"
+            return _Group()
+
+    class _MaterialList:
+        def tags(self):
+            return ["mat1"]
+
+        def get(self, tag):
+            return _Material()
+
+    class _Model:
+        def material(self):
+            return _MaterialList()
+
+    class _ModelUtil:
+        removed: list = []
+
+        @staticmethod
+        def load(tag, path):
+            return _Model()
+
+        @classmethod
+        def remove(cls, tag):
+            cls.removed.append(tag)
+
+    monkeypatch.setattr(jac, "_jpype", lambda: SimpleNamespace(JClass=lambda name: _ModelUtil))
+    res = controller.list_library_materials()
+    assert res["status"] == "success"
+    assert res["materials"] == [
+        {
+            "name": "Copper",
+            "library": "Built-in",
+            "source": str(lib_dir / "Built-in.mph"),
+            "properties": {"thermalconductivity": "400[W/(m*K)]"},
+        }
+    ]
+    assert len(_ModelUtil.removed) == 1
+
+
+def test_list_library_materials_without_install(controller):
+    controller.settings = SimpleNamespace(comsol_jar_path="")
+    res = controller.list_library_materials()
+    assert res["status"] == "error"
+    assert res["materials"] == []
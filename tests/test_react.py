@@ -159,6 +159,35 @@ class TestActionExecutor:
         assert result.get("status") == "error"
         assert "未知" in result.get("message", "")
 
+    def test_execute_host_action_asks_desktop(self):
+        """run_shell 经宿主执行；未授权时返回错误并标记 denied，无宿主时直接报错。"""
+        executor = ActionExecutor()
+        plan = ReActTaskPlan(task_id="t0", model_name="m0", user_input="u0")
+        step = ExecutionStep(step_id="s1", step_type="host", action="run_shell", status="pending")
+        thought = {"parameters": {"command": "ls", "cwd": "/tmp", "reason": "列目录"}}
+
+        handler = Mock(return_value={"ok": True, "result": {"stdout": "a\n", "status": 0}})
+        with patch("agent.utils.host._handler", handler):
+            result = executor.execute(plan, step, thought)
+        assert result.get("status") == "success"
+        assert result.get("result") == {"stdout": "a\n", "status": 0}
+        handler.assert_called_once_with("run_shell", command="ls", cwd="/tmp", reason="列目录")
+
+        handler = Mock(return_value={"ok": False, "denied": True, "message": "用户拒绝"})
+        with patch("agent.utils.host._handler", handler):
+            result = executor.execute(plan, step, thought)
+        assert result.get("status") == "error"
+        assert result.get("denied") is True
+
+        with patch("agent.utils.host._handler", None):
+            result = executor.execute(plan, step, thought)
+        assert result.get("status") == "error"
+        assert "桌面端" in result.get("message", "")
+
+        result = executor.execute(plan, step, {"parameters": {"cwd": "/tmp"}})
+        assert result.get("status") == "error"
+        assert "command" in result.get("message", "")
+
     def test_execute_import_geometry_delegates_to_clawcode(self):
         """import_geometry 通过 claw-code 子进程委托执行。"""
         executor = ActionExecutor()
@@ -391,6 +420,28 @@ class TestConfigSync:
         assert "CLAW_CODE_BASE_URL=https://example.test/v1" in env_text
         assert "CLAW_CODE_API_KEY=sk-test" in env_text
 
+    def test_config_save_writes_profile_env_file(self, tmp_path, monkeypatch):
+        from agent.utils import config as config_mod
+
+        profile_env = tmp_path / "profiles" / "work" / ".env"
+        monkeypatch.setenv(config_mod.ENV_FILE_ENV, str(profile_env))
+        monkeypatch.setattr(config_mod, "get_project_root", lambda: tmp_path)
+        monkeypatch.setattr(config_mod, "reload_settings", lambda: None)
+
+        ok, message = do_config_save({"LLM_BACKEND": "deepseek", "DEEPSEEK_MODEL": "deepseek-chat"})
+
+        assert ok is True, message
+        assert "LLM_BACKEND=deepseek" in profile_env.read_text(encoding="utf-8")
+        assert not (tmp_path / ".env").exists()
+
+    def test_data_dir_follows_profile(self, tmp_path, monkeypatch):
+        from agent.utils import config as config_mod
+
+        monkeypatch.delenv(config_mod.DATA_DIR_ENV, raising=False)
+        assert config_mod.get_data_dir() == config_mod.get_install_dir()
+        monkeypatch.setenv(config_mod.DATA_DIR_ENV, str(tmp_path))
+        assert config_mod.get_data_dir() == tmp_path
+
 
 class TestObserver:
     """测试观察器"""
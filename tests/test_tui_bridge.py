@@ -0,0 +1,368 @@
+"""TUI 桥接协议单元测试：事件序号与命令处理（输出写入内存缓冲区，不启动 JVM）。"""
+import io
+import json
+import os
+import queue
+import re
+import signal
+import struct
+import sys
+from pathlib import Path
+
+import pytest
+
+from agent.core.events import Event, EventType
+from agent.run import tui_bridge
+
+
+@pytest.fixture
+def out(monkeypatch):
+    """把协议输出改写到内存，按行分帧、JSON 编码。"""
+    buf = io.StringIO()
+    monkeypatch.setattr(tui_bridge, "_stdout", buf)
+    monkeypatch.setattr(tui_bridge, "_framing", "lines")
+    monkeypatch.setattr(tui_bridge, "_codec", "json")
+    monkeypatch.setattr(tui_bridge, "_current_request_id", None)
+    monkeypatch.setattr(tui_bridge, "_event_seqs", {})
+    monkeypatch.setattr(tui_bridge, "_request_closed", True)
+    monkeypatch.setattr(tui_bridge, "_interrupt_pending", False)
+    return buf
+
+
+@pytest.fixture
+def binary_out(monkeypatch):
+    """可按字节写入的协议输出，用于长度前缀分帧；握手会改写 sys.stdout，测试后恢复。"""
+    buf = io.TextIOWrapper(io.BytesIO(), encoding="utf-8")
+    monkeypatch.setattr(tui_bridge, "_stdout", buf)
+    monkeypatch.setattr(tui_bridge, "_framing", "lines")
+    monkeypatch.setattr(tui_bridge, "_codec", "json")
+    monkeypatch.setattr(tui_bridge, "_current_request_id", None)
+    monkeypatch.setattr(tui_bridge, "_event_seqs", {})
+    monkeypatch.setattr(sys, "stdout", sys.stdout)
+    return buf
+
+
+class _FakeStdin:
+    def __init__(self, fd: int) -> None:
+        self._fd = fd
+
+    def fileno(self) -> int:
+        return self._fd
+
+
+def _messages(buf: io.StringIO) -> list:
+    return [json.loads(line) for line in buf.getvalue().splitlines()]
+
+
+def _frame(payload: dict) -> bytes:
+    data = json.dumps(payload).encode("utf-8")
+    return struct.pack(">I", len(data)) + data
+
+
+def _pipe(data: bytes) -> int:
+    """写入数据并关闭写端，返回读端；读完即 EOF。"""
+    r, w = os.pipe()
+    os.write(w, data)
+    os.close(w)
+    return r
+
+
+def _split_output(buf: io.TextIOWrapper) -> tuple:
+    """握手回复按行写出，其后为长度前缀帧。"""
+    buf.flush()
+    data = buf.buffer.getvalue()
+    line, _, rest = data.partition(b"\n")
+    frames = []
+    while rest:
+        (length,) = struct.unpack(">I", rest[:4])
+        frames.append(json.loads(rest[4 : 4 + length]))
+        rest = rest[4 + length :]
+    return json.loads(line), frames
+
+
+class TestEventSeq:
+    """事件序号"""
+
+    def test_seq_increments_per_request(self, out, monkeypatch):
+        monkeypatch.setattr(tui_bridge, "_current_request_id", "r1")
+        for _ in range(3):
+            tui_bridge._emit_event(Event(type=EventType.CONTENT, data={}))
+        msgs = _messages(out)
+        assert [m["seq"] for m in msgs] == [1, 2, 3]
+        assert all(m["_id"] == "r1" for m in msgs)
+
+    def test_reply_resets_seq(self, out, monkeypatch):
+        monkeypatch.setattr(tui_bridge, "_current_request_id", "r1")
+        tui_bridge._emit_event(Event(type=EventType.CONTENT, data={}))
+        tui_bridge._reply(True, "done")
+        # 回复即结束请求；同一 `_id` 再次执行时序号从 1 开始
+        tui_bridge._open_request("r1")
+        tui_bridge._emit_event(Event(type=EventType.CONTENT, data={}))
+        msgs = _messages(out)
+        assert [m.get("seq") for m in msgs] == [1, None, 1]
+
+    def test_no_seq_without_request_id(self, out):
+        tui_bridge._emit_event(Event(type=EventType.CONTENT, data={}))
+        assert "seq" not in _messages(out)[0]
+
+
+class TestExportScript:
+    """export_script 命令"""
+
+    def test_missing_model_path(self, out):
+        tui_bridge._handle({"cmd": "export_script", "format": "java"})
+        reply = _messages(out)[0]
+        assert reply["ok"] is False
+        assert reply["code"] is None
+
+    def test_delegates_to_controller(self, out, monkeypatch):
+        calls = []
+
+        class _Controller:
+            def export_model_script(self, model_path, fmt="java"):
+                calls.append((model_path, fmt))
+                return {"status": "success", "message": "脚本已导出", "code": "class M {}"}
+
+        monkeypatch.setattr(tui_bridge, "JavaAPIController", _Controller)
+        tui_bridge._handle({"cmd": "export_script", "format": "Matlab", "model_path": "/m/a.mph"})
+        assert calls == [("/m/a.mph", "matlab")]
+        assert _messages(out)[0]["code"] == "class M {}"
+
+
+class TestMaterialsList:
+    """materials_list 命令"""
+
+    def test_returns_library_and_echoes_version(self, out, monkeypatch):
+        class _Controller:
+            def list_library_materials(self):
+                return {"status": "success", "message": "共 1 种材料", "materials": [{"name": "Copper"}]}
+
+        monkeypatch.setattr(tui_bridge, "JavaAPIController", _Controller)
+        tui_bridge._handle({"cmd": "materials_list", "comsol_version": "6.2"})
+        reply = _messages(out)[0]
+        assert reply["ok"] is True
+        assert reply["materials"] == [{"name": "Copper"}]
+        assert reply["comsol_version"] == "6.2"
+
+    def test_advertised(self):
+        assert "materials_list" in tui_bridge._COMMANDS
+
+
+class TestCommandCoverage:
+    """桌面端 Rust 代码直接发送的 cmd 必须在 _COMMANDS 中声明，否则握手后会被 check_cmd_supported 拒绝"""
+
+    _SRC = Path(__file__).parent.parent / "desktop" / "src-tauri" / "src"
+    _PATTERNS = (
+        # send_request(state, "cmd", ...) / send_request_with_timeout(...)；允许跨行
+        re.compile(r"send_request(?:_with_timeout)?\(\s*[^,;]+?,\s*\"([a-z_]+)\""),
+        # 基准测试经 roundtrip(state, "cmd", ...) 发送
+        re.compile(r"roundtrip\(\s*[^,;]+?,\s*\"([a-z_]+)\""),
+        # 控制消息 json!({ "cmd": "cancel", ... }) 与 line["cmd"] = "host_reply"
+        re.compile(r"\"cmd\":\s*\"([a-z_]+)\""),
+        re.compile(r"\[\"cmd\"\]\s*=\s*Value::String\(\"([a-z_]+)\""),
+    )
+
+    def _rust_cmds(self) -> dict:
+        found = {}
+        for path in sorted(self._SRC.glob("*.rs")):
+            text = path.read_text(encoding="utf-8")
+            for pattern in self._PATTERNS:
+                for cmd in pattern.findall(text):
+                    found.setdefault(cmd, path.name)
+        return found
+
+    def test_rust_cmds_are_advertised(self):
+        found = self._rust_cmds()
+        # 扫描本身要能命中已知调用点，避免正则失效后测试空转
+        assert {"selftest", "export_script", "materials_list", "ping", "echo", "cancel", "host_reply"} <= set(found)
+        missing = {cmd: src for cmd, src in found.items() if cmd not in tui_bridge._COMMANDS}
+        assert missing == {}
+
+
+class TestComsolServer:
+    """请求中的 comsol_server 字段"""
+
+    def test_request_selects_server(self, out, monkeypatch):
+        from agent.executor.comsol_runner import COMSOLRunner
+
+        monkeypatch.setattr(COMSOLRunner, "_server", None)
+        tui_bridge._handle({"cmd": "ping", "comsol_server": {"host": "127.0.0.1", "port": 2036}})
+        assert COMSOLRunner._server == ("127.0.0.1", 2036)
+        tui_bridge._handle({"cmd": "ping"})
+        assert COMSOLRunner._server == ("127.0.0.1", 2036)
+        assert [m["ok"] for m in _messages(out)] == [True, True]
+
+
+class TestHello:
+    """hello 握手与分帧切换"""
+
+    def _drive(self, monkeypatch, data: bytes):
+        fd = _pipe(data)
+        monkeypatch.setattr(sys, "stdin", _FakeStdin(fd))
+        requests, queries = queue.Queue(), queue.Queue()
+        monkeypatch.setattr(tui_bridge, "_requests", requests)
+        monkeypatch.setattr(tui_bridge, "_queries", queries)
+        try:
+            tui_bridge._read_stdin()
+        finally:
+            os.close(fd)
+        return requests
+
+    def test_switches_to_length_prefixed_after_reply(self, binary_out, monkeypatch):
+        hello = {
+            "cmd": "hello",
+            "protocol": 3,
+            "framing": ["length_prefixed", "lines"],
+            "codecs": ["json"],
+        }
+        data = (
+            json.dumps(hello).encode("utf-8")
+            + b"\n"
+            + _frame({"cmd": "cancel", "target": "r9", "_id": "c1"})
+            + _frame({"cmd": "export_script", "format": "java", "_id": "e1"})
+        )
+        requests = self._drive(monkeypatch, data)
+
+        assert tui_bridge._framing == "length_prefixed"
+        assert sys.stdout is sys.stderr
+        # 握手之后的普通请求交给主线程，按已切换的分帧回复
+        line = requests.get_nowait()
+        assert json.loads(line)["_id"] == "e1"
+        assert requests.get_nowait() is None
+        assert tui_bridge._serve(line) is True
+
+        reply, frames = _split_output(binary_out)
+        assert reply["ok"] is True
+        assert reply["framing"] == "length_prefixed"
+        assert reply["codec"] == "json"
+        assert reply["protocol"] == tui_bridge.PROTOCOL_VERSION
+        assert reply["capabilities"]["cmds"] == list(tui_bridge._COMMANDS)
+        assert reply["capabilities"]["concurrent"] == list(tui_bridge._CONCURRENT_COMMANDS)
+        assert frames[0] == {
+            "_id": "c1",
+            "ok": True,
+            "message": "目标请求未在执行",
+            "cancelling": False,
+        }
+        assert frames[1]["_id"] == "e1"
+        assert frames[1]["ok"] is False
+
+    def test_unknown_framing_falls_back_to_lines(self, out, monkeypatch):
+        monkeypatch.setattr(sys, "stdout", sys.stdout)
+        tui_bridge._handle_hello(
+            {"cmd": "hello", "framing": ["carrier_pigeon"], "codecs": ["msgpack"]}
+        )
+        reply = _messages(out)[0]
+        assert reply["framing"] == "lines"
+        # MessagePack 只在长度前缀分帧下可用
+        assert reply["codec"] == "json"
+        assert tui_bridge._framing == "lines"
+
+
+class TestCancel:
+    """cancel 控制消息"""
+
+    def test_interrupts_current_request(self, out, monkeypatch):
+        calls = []
+        monkeypatch.setattr(tui_bridge._thread, "interrupt_main", lambda: calls.append(True))
+        monkeypatch.setattr(tui_bridge, "_current_request_id", "r1")
+        tui_bridge._handle_cancel({"cmd": "cancel", "target": "r1", "_id": "c1"})
+        reply = _messages(out)[0]
+        assert calls == [True]
+        assert reply["cancelling"] is True
+        assert reply["_id"] == "c1"
+
+    def test_other_target_is_left_running(self, out, monkeypatch):
+        calls = []
+        monkeypatch.setattr(tui_bridge._thread, "interrupt_main", lambda: calls.append(True))
+        monkeypatch.setattr(tui_bridge, "_current_request_id", "r1")
+        tui_bridge._handle_cancel({"cmd": "cancel", "target": "r2"})
+        reply = _messages(out)[0]
+        assert calls == []
+        assert reply["cancelling"] is False
+        assert "_id" not in reply
+
+    def test_interrupted_request_replies_cancelled(self, out, monkeypatch):
+        def _interrupted(req):
+            raise KeyboardInterrupt
+
+        monkeypatch.setattr(tui_bridge, "_handle", _interrupted)
+        assert tui_bridge._serve(json.dumps({"cmd": "run", "_id": "r1"})) is True
+        reply = _messages(out)[0]
+        assert reply == {"ok": False, "message": "任务已取消", "cancelled": True, "_id": "r1"}
+        assert tui_bridge._current_request_id is None
+
+    def test_cancel_after_final_reply_does_not_interrupt(self, out, monkeypatch):
+        calls = []
+        monkeypatch.setattr(tui_bridge._thread, "interrupt_main", lambda: calls.append(True))
+
+        def _finish_then_cancel(req):
+            tui_bridge._reply(True, "done")
+            tui_bridge._handle_cancel({"cmd": "cancel", "target": "r1", "_id": "c1"})
+
+        monkeypatch.setattr(tui_bridge, "_handle", _finish_then_cancel)
+        tui_bridge._serve(json.dumps({"cmd": "run", "_id": "r1"}))
+        done, cancel = _messages(out)
+        assert calls == []
+        assert done == {"ok": True, "message": "done", "_id": "r1"}
+        assert cancel["cancelling"] is False
+
+    def test_interrupt_during_final_reply_is_dropped(self, out, monkeypatch):
+        write_raw = tui_bridge._write_raw
+
+        def _interrupted_write(payload):
+            # 取消中断恰在最终回复写出途中送达
+            tui_bridge._on_interrupt(signal.SIGINT, None)
+            write_raw(payload)
+
+        monkeypatch.setattr(tui_bridge, "_write_raw", _interrupted_write)
+        monkeypatch.setattr(tui_bridge, "_handle", lambda req: tui_bridge._reply(True, "done"))
+        assert tui_bridge._serve(json.dumps({"cmd": "run", "_id": "r1"})) is True
+        assert _messages(out) == [{"ok": True, "message": "done", "_id": "r1"}]
+        assert tui_bridge._current_request_id is None
+
+    def test_interrupt_during_event_write_is_deferred(self, out, monkeypatch):
+        write_raw = tui_bridge._write_raw
+
+        def _interrupted_write(payload):
+            if payload.get("_event"):
+                tui_bridge._on_interrupt(signal.SIGINT, None)
+            write_raw(payload)
+
+        def _emit_then_finish(req):
+            tui_bridge._emit_event(Event(type=EventType.CONTENT, data={"msg": "x"}))
+            tui_bridge._reply(True, "done")
+
+        monkeypatch.setattr(tui_bridge, "_write_raw", _interrupted_write)
+        monkeypatch.setattr(tui_bridge, "_handle", _emit_then_finish)
+        tui_bridge._serve(json.dumps({"cmd": "run", "_id": "r1"}))
+        event, reply = _messages(out)
+        # 事件完整写出后才抛出取消，任务以取消结束，不再写出原本的回复
+        assert event["_id"] == "r1" and event["data"] == {"msg": "x"}
+        assert reply == {"ok": False, "message": "任务已取消", "cancelled": True, "_id": "r1"}
+
+
+class TestFraming:
+    """分帧读写"""
+
+    def test_length_prefixed_write_puts_routing_keys_first(self, binary_out, monkeypatch):
+        monkeypatch.setattr(tui_bridge, "_framing", "length_prefixed")
+        tui_bridge._write_message({"ok": True, "message": "done", "_id": "r1"})
+        binary_out.flush()
+        data = binary_out.buffer.getvalue()
+        (length,) = struct.unpack(">I", data[:4])
+        assert len(data) == 4 + length
+        assert list(json.loads(data[4:])) == ["_id", "ok", "message"]
+
+    def test_read_message_per_framing(self, monkeypatch):
+        fd = _pipe(b'{"cmd": "a"}\n' + _frame({"cmd": "b"}) + b"\x00\x00\x00\x10{}")
+        try:
+            stream = tui_bridge._StdinReader(fd)
+            monkeypatch.setattr(tui_bridge, "_framing", "lines")
+            assert json.loads(tui_bridge._read_message(stream)) == {"cmd": "a"}
+            monkeypatch.setattr(tui_bridge, "_framing", "length_prefixed")
+            assert json.loads(tui_bridge._read_message(stream)) == {"cmd": "b"}
+            # 帧体不足声明长度时视为流已结束
+            assert tui_bridge._read_message(stream) is None
+        finally:
+            os.close(fd)